@@ -0,0 +1,90 @@
+//! Ingest and search benchmarks on synthetic data.
+//!
+//! Run with `cargo bench`; criterion reports changes against the previous
+//! run, so regressions show up as slowdowns.
+
+use claudecode_chat_history_lib::commands::run_search;
+use claudecode_chat_history_lib::db::Database;
+use claudecode_chat_history_lib::demo::{generate_conversations, write_fixture_files};
+use claudecode_chat_history_lib::ingest::{ingest_files, NoProgress};
+use claudecode_chat_history_lib::models::ConversationFilters;
+use claudecode_chat_history_lib::parser::parse_conversation_file;
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use std::sync::Arc;
+
+/// Conversations in the search benchmark's database.
+const SEARCH_DATASET_SIZE: usize = 10_000;
+
+fn scratch_database() -> Arc<Database> {
+    let db = Database::open_in_memory().unwrap();
+    db.init_schema().unwrap();
+    Arc::new(db)
+}
+
+fn bench_parse_and_ingest(c: &mut Criterion) {
+    let dir = tempfile::tempdir().unwrap();
+    let files = write_fixture_files(dir.path()).unwrap();
+    let bytes: u64 = files
+        .iter()
+        .map(|path| std::fs::metadata(path).unwrap().len())
+        .sum();
+
+    let mut group = c.benchmark_group("parse");
+    group.throughput(Throughput::Bytes(bytes));
+    group.bench_function("fixtures", |b| {
+        b.iter(|| {
+            for path in &files {
+                black_box(parse_conversation_file(path).unwrap());
+            }
+        })
+    });
+    group.finish();
+
+    let mut group = c.benchmark_group("ingest");
+    group.throughput(Throughput::Elements(files.len() as u64));
+    group.bench_function("fixtures", |b| {
+        b.iter_with_setup(scratch_database, |db| {
+            ingest_files(&db, &files, &NoProgress).unwrap();
+        })
+    });
+    group.finish();
+}
+
+fn bench_search(c: &mut Criterion) {
+    let db = scratch_database();
+    db.with_connection_mut(|conn| {
+        generate_conversations(conn, SEARCH_DATASET_SIZE, chrono::Utc::now())
+    })
+    .unwrap();
+    let filters = ConversationFilters::default();
+
+    let mut group = c.benchmark_group("search");
+    for query in ["regression", "cache", "test", "日本語"] {
+        group.bench_with_input(BenchmarkId::from_parameter(query), query, |b, query| {
+            b.iter(|| {
+                db.with_connection(|conn| run_search(conn, query, &filters))
+                    .unwrap()
+            })
+        });
+    }
+    group.finish();
+
+    // Scoped to one demo project, so only its partition of the index is read
+    let filters = ConversationFilters {
+        project: Some("rust-cli".to_string()),
+        ..Default::default()
+    };
+    let mut group = c.benchmark_group("search_project");
+    for query in ["regression", "test"] {
+        group.bench_with_input(BenchmarkId::from_parameter(query), query, |b, query| {
+            b.iter(|| {
+                db.with_connection(|conn| run_search(conn, query, &filters))
+                    .unwrap()
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_parse_and_ingest, bench_search);
+criterion_main!(benches);
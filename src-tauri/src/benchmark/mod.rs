@@ -0,0 +1,197 @@
+//! Performance measurements on the current dataset.
+//!
+//! `run_benchmark` measures parse throughput, ingest rate, and search
+//! latency against the user's own history, so slowdowns on real data are
+//! visible. Ingest writes to a scratch in-memory database; the real database
+//! is only read. The criterion benches in `benches/` cover the same paths on
+//! synthetic data for tracking regressions between commits.
+
+use crate::commands::run_search;
+use crate::db::sqlite::{Database, DbResult};
+use crate::ingest::{ingest_files, NoProgress};
+use crate::metrics::{latency_stats, millis};
+use crate::models::{BenchmarkOptions, BenchmarkReport, ConversationFilters};
+use crate::parser::parse_conversation_file;
+use rusqlite::Connection;
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+/// Default number of session files parsed and ingested.
+pub const DEFAULT_MAX_FILES: u32 = 200;
+
+/// Default number of times each search query is repeated.
+pub const DEFAULT_SEARCH_ITERATIONS: u32 = 5;
+
+/// Most distinct search queries derived from the dataset.
+const MAX_SEARCH_QUERIES: usize = 20;
+
+/// Queries used when the dataset yields none (e.g. an empty database).
+const FALLBACK_QUERIES: &[&str] = &["error", "test", "function"];
+
+/// Measures parse throughput, ingest rate, and search latency.
+pub fn run_benchmark(db: &Database, options: &BenchmarkOptions) -> DbResult<BenchmarkReport> {
+    let started = Instant::now();
+    let max_files = options.max_files.unwrap_or(DEFAULT_MAX_FILES) as usize;
+    let iterations = options.search_iterations.unwrap_or(DEFAULT_SEARCH_ITERATIONS);
+
+    let files = db.with_connection(|conn| session_files(conn, max_files))?;
+    let mut report = BenchmarkReport {
+        files: files.len(),
+        ..Default::default()
+    };
+
+    // Parse throughput
+    let mut parse_time = Duration::ZERO;
+    for path in &files {
+        report.bytes += std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+        let parse_started = Instant::now();
+        if let Err(e) = parse_conversation_file(path) {
+            warn!("Benchmark: failed to parse {:?}: {}", path, e);
+        }
+        parse_time += parse_started.elapsed();
+    }
+    report.parse_mb_per_sec = per_second(report.bytes as f64 / 1_000_000.0, parse_time);
+
+    // Ingest rate, into a scratch database
+    let scratch = Database::open_in_memory()?;
+    scratch.init_schema()?;
+    let ingest_started = Instant::now();
+    let stats = ingest_files(&scratch, &files, &NoProgress)?;
+    if stats.failed_files > 0 {
+        warn!("Benchmark: failed to ingest {} files", stats.failed_files);
+    }
+    report.conversations = stats.new_conversations;
+    report.ingest_conversations_per_sec =
+        per_second(report.conversations as f64, ingest_started.elapsed());
+
+    // Search latency
+    let (queries, latencies) = db.with_connection(|conn| {
+        let queries = search_queries(conn)?;
+        let filters = ConversationFilters::default();
+        let mut latencies = Vec::with_capacity(queries.len() * iterations as usize);
+        for _ in 0..iterations {
+            for query in &queries {
+                let search_started = Instant::now();
+                run_search(conn, query, &filters)?;
+                latencies.push(search_started.elapsed());
+            }
+        }
+        Ok((queries, latencies))
+    })?;
+    report.search_queries = queries.len();
+    report.search_latency = latency_stats(latencies);
+
+    report.duration_ms = millis(started.elapsed());
+    info!(
+        "Benchmark: parse {:.1} MB/s over {} files, ingest {:.0} conversations/s, search p50 {:.2}ms p95 {:.2}ms p99 {:.2}ms",
+        report.parse_mb_per_sec,
+        report.files,
+        report.ingest_conversations_per_sec,
+        report.search_latency.p50_ms,
+        report.search_latency.p95_ms,
+        report.search_latency.p99_ms,
+    );
+
+    Ok(report)
+}
+
+/// Returns up to `limit` existing session files, most recently active first.
+fn session_files(conn: &Connection, limit: usize) -> DbResult<Vec<PathBuf>> {
+    let mut stmt = conn.prepare(
+        "SELECT file_path FROM conversations GROUP BY file_path ORDER BY MAX(last_epoch) DESC",
+    )?;
+    let paths = stmt.query_map([], |row| row.get::<_, String>(0))?;
+
+    let mut files = Vec::new();
+    for path in paths {
+        let path = PathBuf::from(path?);
+        if path.is_file() {
+            files.push(path);
+            if files.len() == limit {
+                break;
+            }
+        }
+    }
+    Ok(files)
+}
+
+/// Derives search queries from the dataset: the longest word of each of
+/// the most recent previews.
+fn search_queries(conn: &Connection) -> DbResult<Vec<String>> {
+    let mut stmt = conn.prepare("SELECT preview FROM conversations ORDER BY last_epoch DESC LIMIT 100")?;
+    let previews = stmt.query_map([], |row| row.get::<_, String>(0))?;
+
+    let mut seen = HashSet::new();
+    let mut queries = Vec::new();
+    for preview in previews {
+        let preview = preview?;
+        let word = preview
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|w| w.chars().count() >= 4)
+            .max_by_key(|w| w.chars().count());
+        if let Some(word) = word {
+            let word = word.to_lowercase();
+            if seen.insert(word.clone()) {
+                queries.push(word);
+                if queries.len() == MAX_SEARCH_QUERIES {
+                    break;
+                }
+            }
+        }
+    }
+
+    if queries.is_empty() {
+        queries = FALLBACK_QUERIES.iter().map(|q| q.to_string()).collect();
+    }
+    Ok(queries)
+}
+
+fn per_second(amount: f64, elapsed: Duration) -> f64 {
+    let secs = elapsed.as_secs_f64();
+    if secs > 0.0 {
+        amount / secs
+    } else {
+        0.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::demo::write_fixture_files;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_run_benchmark_on_fixtures() {
+        let dir = tempdir().unwrap();
+        let db = Database::open_in_memory().unwrap();
+        db.init_schema().unwrap();
+        let paths = write_fixture_files(dir.path()).unwrap();
+        ingest_files(&db, &paths, &NoProgress).unwrap();
+
+        let options = BenchmarkOptions {
+            max_files: Some(4),
+            search_iterations: Some(2),
+        };
+        let report = run_benchmark(&db, &options).unwrap();
+        assert_eq!(report.files, 4);
+        assert_eq!(report.conversations, 4);
+        assert!(report.bytes > 0);
+        assert!(report.search_queries > 0);
+        assert_eq!(report.search_latency.samples, report.search_queries * 2);
+        assert!(report.search_latency.p50_ms <= report.search_latency.p99_ms);
+    }
+
+    #[test]
+    fn test_run_benchmark_on_empty_database() {
+        let db = Database::open_in_memory().unwrap();
+        db.init_schema().unwrap();
+
+        let report = run_benchmark(&db, &BenchmarkOptions::default()).unwrap();
+        assert_eq!(report.files, 0);
+        assert_eq!(report.parse_mb_per_sec, 0.0);
+        assert_eq!(report.search_queries, FALLBACK_QUERIES.len());
+    }
+}
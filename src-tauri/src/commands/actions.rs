@@ -0,0 +1,804 @@
+//! Actions of the command palette.
+//!
+//! The palette lists the backend's capabilities with `list_actions` and runs
+//! one with `run_action(action_id, args)`, so it offers every capability the
+//! same way without knowing each command. An action is a command: its ID is
+//! the command's name, its arguments are the command's, named as the
+//! frontend passes them, and its result is what the command returns.
+//! Commands that only feed a view aren't actions; the tests make sure every
+//! command is one or the other.
+
+use super::*;
+use crate::models::{ActionArg, ActionArgKind, ActionCategory, ActionInfo};
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value};
+use tauri::Manager;
+
+/// Declaration of an action.
+struct ActionSpec {
+    id: &'static str,
+    title: &'static str,
+    category: ActionCategory,
+    description: &'static str,
+    args: &'static [ArgSpec],
+    destructive: bool,
+}
+
+/// Declaration of an action's argument.
+struct ArgSpec {
+    name: &'static str,
+    kind: ActionArgKind,
+    required: bool,
+    description: &'static str,
+    options: &'static [&'static str],
+}
+
+const fn required(name: &'static str, kind: ActionArgKind, description: &'static str) -> ArgSpec {
+    ArgSpec {
+        name,
+        kind,
+        required: true,
+        description,
+        options: &[],
+    }
+}
+
+const fn optional(name: &'static str, kind: ActionArgKind, description: &'static str) -> ArgSpec {
+    ArgSpec {
+        name,
+        kind,
+        required: false,
+        description,
+        options: &[],
+    }
+}
+
+const CONVERSATION_ID: ArgSpec = required(
+    "conversationId",
+    ActionArgKind::Conversation,
+    "The conversation",
+);
+const ID: ArgSpec = required("id", ActionArgKind::Conversation, "The conversation");
+const WINDOW: ArgSpec = optional(
+    "windowLabel",
+    ActionArgKind::Window,
+    "Window running the action, for undo (default: main)",
+);
+const EXPORT_FORMAT: ArgSpec = ArgSpec {
+    name: "format",
+    kind: ActionArgKind::Choice,
+    required: false,
+    description: "Format to export to (default: markdown)",
+    options: &["markdown", "transcript", "html", "json"],
+};
+const FROM_MESSAGE: ArgSpec = optional(
+    "fromMessageId",
+    ActionArgKind::Message,
+    "First message to export (default: the first)",
+);
+const TO_MESSAGE: ArgSpec = optional(
+    "toMessageId",
+    ActionArgKind::Message,
+    "Last message to export (default: the last)",
+);
+const DEST_DIR: ArgSpec = required("destDir", ActionArgKind::Path, "Directory to write to");
+
+/// Every action, by category.
+const ACTIONS: &[ActionSpec] = &[
+    ActionSpec {
+        id: "toggle_bookmark",
+        title: "Toggle bookmark",
+        category: ActionCategory::Conversation,
+        description: "Bookmark the conversation, or remove its bookmark",
+        args: &[CONVERSATION_ID, WINDOW],
+        destructive: false,
+    },
+    ActionSpec {
+        id: "set_tags",
+        title: "Set tags",
+        category: ActionCategory::Conversation,
+        description: "Replace the conversation's tags",
+        args: &[
+            CONVERSATION_ID,
+            required("tags", ActionArgKind::Tags, "The new tags"),
+            WINDOW,
+        ],
+        destructive: false,
+    },
+    ActionSpec {
+        id: "suggest_tags",
+        title: "Suggest tags",
+        category: ActionCategory::Conversation,
+        description: "Propose tags from the conversation's languages, frameworks, and files",
+        args: &[ID],
+        destructive: false,
+    },
+    ActionSpec {
+        id: "set_protected",
+        title: "Protect conversation",
+        category: ActionCategory::Conversation,
+        description: "Keep the conversation from being archived or deleted, or stop keeping it",
+        args: &[
+            CONVERSATION_ID,
+            required("protected", ActionArgKind::Boolean, "Whether to protect it"),
+        ],
+        destructive: false,
+    },
+    ActionSpec {
+        id: "set_metadata",
+        title: "Set metadata field",
+        category: ActionCategory::Conversation,
+        description: "Set or remove a custom field of the conversation",
+        args: &[
+            ID,
+            required("key", ActionArgKind::Text, "Name of the field"),
+            optional("value", ActionArgKind::Text, "Its value; none removes it"),
+        ],
+        destructive: false,
+    },
+    ActionSpec {
+        id: "set_reminder",
+        title: "Set reminder",
+        category: ActionCategory::Conversation,
+        description: "Be reminded to revisit the conversation",
+        args: &[
+            ID,
+            required("datetime", ActionArgKind::DateTime, "When the reminder is due"),
+            optional("note", ActionArgKind::Text, "What to follow up on"),
+        ],
+        destructive: false,
+    },
+    ActionSpec {
+        id: "clear_reminder",
+        title: "Clear reminder",
+        category: ActionCategory::Conversation,
+        description: "Remove the conversation's reminder",
+        args: &[ID],
+        destructive: false,
+    },
+    ActionSpec {
+        id: "link_issue",
+        title: "Link issue",
+        category: ActionCategory::Conversation,
+        description: "Link the conversation to an issue or pull request",
+        args: &[
+            CONVERSATION_ID,
+            required("url", ActionArgKind::Text, "URL of the issue or pull request"),
+        ],
+        destructive: false,
+    },
+    ActionSpec {
+        id: "attach_child_conversation",
+        title: "Nest under session",
+        category: ActionCategory::Conversation,
+        description: "Nest a conversation, such as a sub-agent transcript, under another",
+        args: &[
+            required("parent", ActionArgKind::Conversation, "The invoking session"),
+            required("child", ActionArgKind::Conversation, "The conversation to nest"),
+        ],
+        destructive: false,
+    },
+    ActionSpec {
+        id: "save_prompt_from_message",
+        title: "Save message as prompt",
+        category: ActionCategory::Conversation,
+        description: "Add a message's text to the prompt library",
+        args: &[
+            CONVERSATION_ID,
+            required("messageId", ActionArgKind::Message, "The message"),
+            optional("title", ActionArgKind::Text, "Title of the prompt"),
+        ],
+        destructive: false,
+    },
+    ActionSpec {
+        id: "export_conversation",
+        title: "Export conversation",
+        category: ActionCategory::Export,
+        description: "Export the conversation, or a range of its messages, as text",
+        args: &[ID, EXPORT_FORMAT, FROM_MESSAGE, TO_MESSAGE],
+        destructive: false,
+    },
+    ActionSpec {
+        id: "export_conversation_pages",
+        title: "Export conversation as pages",
+        category: ActionCategory::Export,
+        description: "Export the conversation to files split into pages",
+        args: &[
+            ID,
+            EXPORT_FORMAT,
+            DEST_DIR,
+            optional("maxPageBytes", ActionArgKind::Integer, "Largest size of a page"),
+            FROM_MESSAGE,
+            TO_MESSAGE,
+            optional(
+                "previewImage",
+                ActionArgKind::Boolean,
+                "Whether to write a preview image",
+            ),
+        ],
+        destructive: false,
+    },
+    ActionSpec {
+        id: "export_with_template",
+        title: "Export with template",
+        category: ActionCategory::Export,
+        description: "Export the conversation with an export template",
+        args: &[
+            ID,
+            required("template", ActionArgKind::Text, "Name of the template"),
+            FROM_MESSAGE,
+            TO_MESSAGE,
+        ],
+        destructive: false,
+    },
+    ActionSpec {
+        id: "export_conversations",
+        title: "Export conversations",
+        category: ActionCategory::Export,
+        description: "Export the selected conversations to files",
+        args: &[
+            required(
+                "ids",
+                ActionArgKind::Conversations,
+                "The conversations to export",
+            ),
+            EXPORT_FORMAT,
+            DEST_DIR,
+        ],
+        destructive: false,
+    },
+    ActionSpec {
+        id: "export_static_site",
+        title: "Export static site",
+        category: ActionCategory::Export,
+        description: "Export the listed conversations as a browsable website",
+        args: &[
+            optional(
+                "filters",
+                ActionArgKind::Filters,
+                "Conversations to export (default: all)",
+            ),
+            DEST_DIR,
+        ],
+        destructive: false,
+    },
+    ActionSpec {
+        id: "extract_artifacts",
+        title: "Extract written files",
+        category: ActionCategory::Export,
+        description: "Rebuild the files the conversation wrote",
+        args: &[
+            ID,
+            DEST_DIR,
+            optional("zip", ActionArgKind::Boolean, "Whether to write a zip archive"),
+        ],
+        destructive: false,
+    },
+    ActionSpec {
+        id: "export_usage_csv",
+        title: "Export usage CSV",
+        category: ActionCategory::Export,
+        description: "Export a month's token usage and cost as CSV",
+        args: &[required("month", ActionArgKind::Text, "The month, as YYYY-MM")],
+        destructive: false,
+    },
+    ActionSpec {
+        id: "export_prompts",
+        title: "Export prompts",
+        category: ActionCategory::Export,
+        description: "Export the prompt library",
+        args: &[
+            optional(
+                "ids",
+                ActionArgKind::Json,
+                "IDs of the prompts to export (default: all)",
+            ),
+            ArgSpec {
+                name: "format",
+                kind: ActionArgKind::Choice,
+                required: false,
+                description: "Format to export to (default: markdown)",
+                options: &["markdown", "json"],
+            },
+        ],
+        destructive: false,
+    },
+    ActionSpec {
+        id: "share_to_slack",
+        title: "Share to Slack",
+        category: ActionCategory::Share,
+        description: "Post the conversation to a Slack channel",
+        args: &[
+            ID,
+            required("channel", ActionArgKind::Text, "The channel"),
+        ],
+        destructive: false,
+    },
+    ActionSpec {
+        id: "create_share_bundle",
+        title: "Create share link",
+        category: ActionCategory::Share,
+        description: "Share the conversation as a link that expires",
+        args: &[
+            ID,
+            optional(
+                "ttlHours",
+                ActionArgKind::Integer,
+                "Hours until the link expires",
+            ),
+        ],
+        destructive: false,
+    },
+    ActionSpec {
+        id: "revoke_share_bundle",
+        title: "Revoke share link",
+        category: ActionCategory::Share,
+        description: "Stop sharing a conversation link",
+        args: &[required("shareId", ActionArgKind::Text, "ID of the share")],
+        destructive: false,
+    },
+    ActionSpec {
+        id: "bulk_archive",
+        title: "Archive matching conversations",
+        category: ActionCategory::Library,
+        description: "Archive every unprotected conversation the filters match",
+        args: &[
+            required("filters", ActionArgKind::Filters, "Conversations to archive"),
+            optional(
+                "dryRun",
+                ActionArgKind::Boolean,
+                "Only count what would be archived",
+            ),
+        ],
+        destructive: true,
+    },
+    ActionSpec {
+        id: "bulk_delete",
+        title: "Delete matching conversations",
+        category: ActionCategory::Library,
+        description: "Delete every unprotected conversation the filters match; a first call without a token returns the count and the token to confirm with",
+        args: &[
+            required("filters", ActionArgKind::Filters, "Conversations to delete"),
+            optional(
+                "confirmToken",
+                ActionArgKind::Text,
+                "Token of the dry run, to delete",
+            ),
+        ],
+        destructive: true,
+    },
+    ActionSpec {
+        id: "undo_last_operation",
+        title: "Undo",
+        category: ActionCategory::Library,
+        description: "Undo the window's last bookmark or tag change",
+        args: &[WINDOW],
+        destructive: false,
+    },
+    ActionSpec {
+        id: "redo",
+        title: "Redo",
+        category: ActionCategory::Library,
+        description: "Redo the window's last undone change",
+        args: &[WINDOW],
+        destructive: false,
+    },
+    ActionSpec {
+        id: "rescan_conversations",
+        title: "Rescan sessions",
+        category: ActionCategory::Maintenance,
+        description: "Import new and changed session files",
+        args: &[],
+        destructive: false,
+    },
+    ActionSpec {
+        id: "reimport_conversations",
+        title: "Re-import sessions",
+        category: ActionCategory::Maintenance,
+        description: "Import session files again, even if unchanged",
+        args: &[optional(
+            "paths",
+            ActionArgKind::Json,
+            "Paths of the session files (default: all)",
+        )],
+        destructive: false,
+    },
+    ActionSpec {
+        id: "import_path",
+        title: "Import sessions",
+        category: ActionCategory::Maintenance,
+        description: "Import session files from a file, directory, or archive",
+        args: &[required(
+            "path",
+            ActionArgKind::Path,
+            "The file, directory, or archive",
+        )],
+        destructive: false,
+    },
+    ActionSpec {
+        id: "regenerate_previews",
+        title: "Regenerate previews",
+        category: ActionCategory::Maintenance,
+        description: "Rebuild every conversation's preview",
+        args: &[],
+        destructive: false,
+    },
+    ActionSpec {
+        id: "maintain_search_index",
+        title: "Reindex search",
+        category: ActionCategory::Maintenance,
+        description: "Optimize the search indexes and check their integrity",
+        args: &[],
+        destructive: false,
+    },
+    ActionSpec {
+        id: "run_benchmark",
+        title: "Run benchmark",
+        category: ActionCategory::Maintenance,
+        description: "Time parsing, ingest, and search on this machine",
+        args: &[optional(
+            "options",
+            ActionArgKind::Json,
+            "Benchmark options",
+        )],
+        destructive: false,
+    },
+];
+
+/// Lists the actions, by category.
+pub fn list() -> Vec<ActionInfo> {
+    ACTIONS
+        .iter()
+        .map(|spec| ActionInfo {
+            id: spec.id.to_string(),
+            title: spec.title.to_string(),
+            category: spec.category,
+            description: spec.description.to_string(),
+            args: spec
+                .args
+                .iter()
+                .map(|arg| ActionArg {
+                    name: arg.name.to_string(),
+                    kind: arg.kind,
+                    required: arg.required,
+                    description: arg.description.to_string(),
+                    options: arg.options.iter().map(|o| o.to_string()).collect(),
+                })
+                .collect(),
+            destructive: spec.destructive,
+        })
+        .collect()
+}
+
+/// An action's arguments, checked against its declaration.
+struct ActionArgs(Map<String, Value>);
+
+impl ActionArgs {
+    fn new(spec: &ActionSpec, args: Option<Value>) -> Result<Self, CommandError> {
+        let args = match args {
+            None | Some(Value::Null) => Map::new(),
+            Some(Value::Object(args)) => args,
+            Some(_) => return Err(validation::invalid("args", "must be an object")),
+        };
+        if let Some(name) = args
+            .keys()
+            .find(|name| !spec.args.iter().any(|arg| arg.name == name.as_str()))
+        {
+            return Err(validation::invalid(
+                name,
+                format!("is not an argument of {}", spec.id),
+            ));
+        }
+        let args = Self(args);
+        for arg in spec.args.iter().filter(|arg| arg.required) {
+            if args.optional::<Value>(arg.name)?.is_none() {
+                return Err(validation::invalid(arg.name, "is required"));
+            }
+        }
+        Ok(args)
+    }
+
+    fn optional<T: DeserializeOwned>(&self, name: &str) -> Result<Option<T>, CommandError> {
+        match self.0.get(name) {
+            None | Some(Value::Null) => Ok(None),
+            Some(value) => serde_json::from_value(value.clone())
+                .map(Some)
+                .map_err(|e| validation::invalid(name, e.to_string())),
+        }
+    }
+
+    fn required<T: DeserializeOwned>(&self, name: &str) -> Result<T, CommandError> {
+        self.optional(name)?
+            .ok_or_else(|| validation::invalid(name, "is required"))
+    }
+}
+
+/// Converts a command's result into the action's.
+fn output<T: serde::Serialize>(result: Result<T, CommandError>) -> Result<Value, CommandError> {
+    serde_json::to_value(result?)
+        .map_err(|e| CommandError::InvalidInput(format!("invalid result: {}", e)))
+}
+
+/// Runs an action by calling its command with `args`.
+pub async fn run(
+    app_handle: &AppHandle,
+    action_id: &str,
+    args: Option<Value>,
+) -> Result<Value, CommandError> {
+    let spec = ACTIONS
+        .iter()
+        .find(|spec| spec.id == action_id)
+        .ok_or_else(|| CommandError::NotFound(format!("Action not found: {}", action_id)))?;
+    let args = ActionArgs::new(spec, args)?;
+    let db = || app_handle.state::<Arc<Database>>();
+    let state = || app_handle.state::<Arc<AppState>>();
+
+    match spec.id {
+        "toggle_bookmark" => output(toggle_bookmark(
+            db(),
+            args.required("conversationId")?,
+            args.optional("windowLabel")?,
+        )),
+        "set_tags" => output(set_tags(
+            app_handle.clone(),
+            db(),
+            args.required("conversationId")?,
+            args.required("tags")?,
+            args.optional("windowLabel")?,
+        )),
+        "suggest_tags" => output(suggest_tags(db(), args.required("id")?)),
+        "set_protected" => output(set_protected(
+            db(),
+            args.required("conversationId")?,
+            args.required("protected")?,
+        )),
+        "set_metadata" => output(set_metadata(
+            db(),
+            args.required("id")?,
+            args.required("key")?,
+            args.optional("value")?,
+        )),
+        "set_reminder" => output(set_reminder(
+            db(),
+            args.required("id")?,
+            args.required("datetime")?,
+            args.optional("note")?,
+        )),
+        "clear_reminder" => output(clear_reminder(db(), args.required("id")?)),
+        "link_issue" => output(link_issue(
+            db(),
+            args.required("conversationId")?,
+            args.required("url")?,
+        )),
+        "attach_child_conversation" => output(attach_child_conversation(
+            db(),
+            args.required("parent")?,
+            args.required("child")?,
+        )),
+        "save_prompt_from_message" => output(save_prompt_from_message(
+            db(),
+            args.required("conversationId")?,
+            args.required("messageId")?,
+            args.optional("title")?,
+        )),
+        "export_conversation" => output(export_conversation(
+            db(),
+            args.required("id")?,
+            args.optional("format")?,
+            args.optional("fromMessageId")?,
+            args.optional("toMessageId")?,
+        )),
+        "export_conversation_pages" => output(export_conversation_pages(
+            db(),
+            args.required("id")?,
+            args.optional("format")?,
+            args.required("destDir")?,
+            args.optional("maxPageBytes")?,
+            args.optional("fromMessageId")?,
+            args.optional("toMessageId")?,
+            args.optional("previewImage")?,
+        )),
+        "export_with_template" => output(export_with_template(
+            db(),
+            args.required("id")?,
+            args.required("template")?,
+            args.optional("fromMessageId")?,
+            args.optional("toMessageId")?,
+        )),
+        "export_conversations" => output(export_conversations(
+            db(),
+            args.required("ids")?,
+            args.optional("format")?,
+            args.required("destDir")?,
+        )),
+        "export_static_site" => output(export_static_site(
+            db(),
+            args.optional("filters")?,
+            args.required("destDir")?,
+        )),
+        "extract_artifacts" => output(extract_artifacts(
+            db(),
+            args.required("id")?,
+            args.required("destDir")?,
+            args.optional("zip")?,
+        )),
+        "export_usage_csv" => output(export_usage_csv(db(), args.required("month")?)),
+        "export_prompts" => output(export_prompts(
+            db(),
+            args.optional("ids")?,
+            args.optional("format")?,
+        )),
+        "share_to_slack" => output(
+            share_to_slack(db(), args.required("id")?, args.required("channel")?).await,
+        ),
+        "create_share_bundle" => output(create_share_bundle(
+            db(),
+            args.required("id")?,
+            args.optional("ttlHours")?,
+        )),
+        "revoke_share_bundle" => output(revoke_share_bundle(db(), args.required("shareId")?)),
+        "bulk_archive" => output(bulk_archive(
+            db(),
+            args.required("filters")?,
+            args.optional("dryRun")?,
+        )),
+        "bulk_delete" => output(bulk_delete(
+            db(),
+            args.required("filters")?,
+            args.optional("confirmToken")?,
+        )),
+        "undo_last_operation" => output(undo_last_operation(
+            app_handle.clone(),
+            db(),
+            args.optional("windowLabel")?,
+        )),
+        "redo" => output(redo(
+            app_handle.clone(),
+            db(),
+            args.optional("windowLabel")?,
+        )),
+        "rescan_conversations" => output(rescan_conversations(state())),
+        "reimport_conversations" => {
+            output(reimport_conversations(state(), args.optional("paths")?))
+        }
+        "import_path" => output(import_path(state(), args.required("path")?)),
+        "regenerate_previews" => output(regenerate_previews(state())),
+        "maintain_search_index" => output(maintain_search_index(db())),
+        "run_benchmark" => output(run_benchmark(db(), args.optional("options")?)),
+        other => Err(CommandError::NotFound(format!(
+            "Action not found: {}",
+            other
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Commands that aren't actions: reads that feed a view, state the
+    /// frontend keeps in step, and what's edited in its own view.
+    const NOT_ACTIONS: &[&str] = &[
+        // Reads
+        "get_conversations",
+        "get_conversation_summaries_window",
+        "get_adjacent_conversation",
+        "get_conversation",
+        "load_full",
+        "has_changed_since",
+        "get_conversation_with_highlights",
+        "get_conversation_messages",
+        "get_message",
+        "get_block_copy_text",
+        "get_attachment",
+        "render_preview_image",
+        "get_projects",
+        "compare_projects",
+        "get_usage_anomalies",
+        "get_language_stats",
+        "get_model_stats",
+        "get_app_metrics",
+        "get_health",
+        "get_schema_observations",
+        "get_recent_logs",
+        "search_conversations",
+        "search_with_facets",
+        "search_questions",
+        "quick_open",
+        "get_all_tags",
+        "get_metadata",
+        "get_due_reminders",
+        "get_conversation_chain",
+        "get_child_conversations",
+        "get_filter_counts",
+        "get_top_terms",
+        "get_synonyms",
+        "get_audit_log",
+        "get_issue_links",
+        "get_conversations_for_issue",
+        "list_export_templates",
+        "list_share_bundles",
+        "list_webhooks",
+        "get_webhook_deliveries",
+        "list_prompts",
+        "list_workspaces",
+        "get_settings",
+        "get_read_only_mode",
+        "list_actions",
+        // State kept in step with the frontend
+        "begin_search",
+        "update_query",
+        "replay_conversation",
+        "stop_replay",
+        "get_selection",
+        "set_selection",
+        "set_read_position",
+        "load_workspace",
+        "run_action",
+        // Edited in their own views
+        "save_webhook",
+        "delete_webhook",
+        "save_prompt",
+        "delete_prompt",
+        "copy_prompt",
+        "save_workspace",
+        "delete_workspace",
+        "set_synonyms",
+        "update_settings",
+        // Demo mode and development
+        "greet",
+        "generate_demo_data",
+    ];
+
+    /// The commands registered with `generate_handler!`.
+    fn registered_commands() -> Vec<&'static str> {
+        let source = include_str!("../lib.rs");
+        let start = source.find("generate_handler![").unwrap() + "generate_handler![".len();
+        let end = start + source[start..].find(']').unwrap();
+        source[start..end]
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .collect()
+    }
+
+    #[test]
+    fn test_every_command_is_an_action_or_not() {
+        let commands = registered_commands();
+        let actions: Vec<&str> = ACTIONS.iter().map(|spec| spec.id).collect();
+        for command in &commands {
+            assert!(
+                actions.contains(command) != NOT_ACTIONS.contains(command),
+                "{} must be in exactly one of ACTIONS and NOT_ACTIONS",
+                command
+            );
+        }
+        for id in actions.iter().chain(NOT_ACTIONS) {
+            assert!(commands.contains(id), "{} is not a command", id);
+        }
+    }
+
+    #[test]
+    fn test_actions_are_well_formed() {
+        let mut ids = HashSet::new();
+        for spec in ACTIONS {
+            assert!(ids.insert(spec.id), "{} is declared twice", spec.id);
+            let mut names = HashSet::new();
+            for arg in spec.args {
+                assert!(
+                    names.insert(arg.name),
+                    "{}.{} is declared twice",
+                    spec.id,
+                    arg.name
+                );
+                assert_eq!(
+                    arg.kind == ActionArgKind::Choice,
+                    !arg.options.is_empty(),
+                    "{}.{} has options only if it's a choice",
+                    spec.id,
+                    arg.name
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,100 @@
+//! Read-only mode.
+//!
+//! For browsing someone else's mounted Claude home or a forensic copy, the
+//! app can be made to only read: launched with `--read-only`, or with the
+//! read-only setting on. Commands that delete, archive, import, or rebuild
+//! history (re-imports, previews, the search index) are then refused with
+//! `CommandError::ReadOnly`, and so are dropped files and exports into the
+//! session directory. Session files are only ever opened for reading,
+//! so nothing else writes there.
+
+use super::CommandError;
+use crate::db::{load_settings, Database};
+use crate::parser::get_claude_projects_dir;
+use std::fs;
+use std::path::Path;
+use tracing::warn;
+
+/// Whether the app is in read-only mode, by launch flag or setting.
+pub fn is_read_only(db: &Database) -> Result<bool, CommandError> {
+    Ok(db.is_read_only() || db.with_connection(load_settings)?.read_only)
+}
+
+/// Refuses `command` in read-only mode.
+pub fn ensure_writable(db: &Database, command: &str) -> Result<(), CommandError> {
+    if is_read_only(db)? {
+        warn!("{}: refused in read-only mode", command);
+        return Err(CommandError::ReadOnly(format!(
+            "{} is disabled in read-only mode",
+            command
+        )));
+    }
+    Ok(())
+}
+
+/// Refuses writing into `dest` in read-only mode if it's in the session
+/// directory.
+pub fn ensure_outside_sessions(db: &Database, dest: &Path) -> Result<(), CommandError> {
+    if !is_read_only(db)? {
+        return Ok(());
+    }
+    let Ok(sessions_dir) = get_claude_projects_dir() else {
+        return Ok(());
+    };
+    if is_inside(dest, &sessions_dir) {
+        warn!("Refused writing into {:?} in read-only mode", dest);
+        return Err(CommandError::ReadOnly(format!(
+            "{} is in the session directory, which is read-only",
+            dest.display()
+        )));
+    }
+    Ok(())
+}
+
+/// Whether `path` is `dir` or inside it, following symlinks where they
+/// exist.
+fn is_inside(path: &Path, dir: &Path) -> bool {
+    let path = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let dir = fs::canonicalize(dir).unwrap_or_else(|_| dir.to_path_buf());
+    path.starts_with(dir)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_read_only_by_flag_or_setting() {
+        let db = Database::open_in_memory().unwrap();
+        db.init_schema().unwrap();
+        assert!(!is_read_only(&db).unwrap());
+        assert!(ensure_writable(&db, "bulk_delete").is_ok());
+
+        db.set_read_only(true);
+        let error = ensure_writable(&db, "bulk_delete").unwrap_err();
+        assert_eq!(error.code(), "read_only");
+
+        db.set_read_only(false);
+        db.with_connection(|conn| {
+            crate::db::settings::set_setting(conn, crate::db::settings::READ_ONLY_KEY, "true")
+        })
+        .unwrap();
+        assert!(is_read_only(&db).unwrap());
+    }
+
+    #[test]
+    fn test_is_inside() {
+        let dir = tempdir().unwrap();
+        let sessions = dir.path().join("projects");
+        fs::create_dir_all(sessions.join("p")).unwrap();
+        assert!(is_inside(&sessions, &sessions));
+        assert!(is_inside(&sessions.join("p"), &sessions));
+        assert!(is_inside(
+            &sessions.join("p").join("..").join("p"),
+            &sessions
+        ));
+        assert!(!is_inside(dir.path(), &sessions));
+        assert!(!is_inside(&dir.path().join("projects-export"), &sessions));
+    }
+}
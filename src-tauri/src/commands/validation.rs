@@ -0,0 +1,443 @@
+//! Validation of command arguments.
+//!
+//! Commands check their arguments here before touching the database and
+//! report the first problem as `CommandError::Validation`, naming the
+//! argument as the frontend spells it.
+
+use super::{CommandError, PaginationParams};
+use crate::models::{ConversationFilters, PromptInput, WorkspaceInput};
+use crate::parser::{filter_boundary_millis, DateBoundary, FilterTimezone};
+use chrono::{DateTime, NaiveDate};
+
+/// Longest search query accepted, in characters.
+pub const MAX_QUERY_LENGTH: usize = 500;
+
+/// Longest tag accepted, in characters.
+pub const MAX_TAG_LENGTH: usize = 50;
+
+/// Most tags a conversation can have.
+pub const MAX_TAGS: usize = 50;
+
+/// Largest page of conversations `get_conversations` returns.
+pub const MAX_PAGE_LIMIT: i32 = 1000;
+
+/// Most conversations `get_conversation_summaries_window` returns at once.
+pub const MAX_WINDOW_SIZE: u32 = 1000;
+
+/// Longest prompt title accepted, in characters.
+pub const MAX_PROMPT_TITLE_LENGTH: usize = 200;
+
+/// Longest metadata key accepted, in characters.
+pub const MAX_METADATA_KEY_LENGTH: usize = 50;
+
+/// Longest metadata value accepted, in characters.
+pub const MAX_METADATA_VALUE_LENGTH: usize = 500;
+
+/// Longest reminder note accepted, in characters.
+pub const MAX_REMINDER_NOTE_LENGTH: usize = 500;
+
+/// Characters allowed in metadata keys besides letters and digits.
+const METADATA_KEY_PUNCTUATION: &[char] = &['-', '_', '.'];
+
+/// Longest workspace name accepted, in characters.
+pub const MAX_WORKSPACE_NAME_LENGTH: usize = 100;
+
+/// Characters allowed in tags besides letters and digits.
+const TAG_PUNCTUATION: &[char] = &[' ', '-', '_', '.', '/', ':', '#', '+'];
+
+/// Builds a validation error for `field`.
+pub fn invalid(field: &str, reason: impl Into<String>) -> CommandError {
+    CommandError::Validation {
+        field: field.to_string(),
+        reason: reason.into(),
+    }
+}
+
+/// Checks that a limit is between 1 and `max`.
+pub fn limit(field: &str, value: u32, max: u32) -> Result<(), CommandError> {
+    if value == 0 || value > max {
+        return Err(invalid(field, format!("must be between 1 and {}", max)));
+    }
+    Ok(())
+}
+
+/// Checks that a value is a date (`YYYY-MM-DD`) or an RFC 3339 timestamp.
+pub fn iso_date(field: &str, value: &str) -> Result<(), CommandError> {
+    let value = value.trim();
+    if NaiveDate::parse_from_str(value, "%Y-%m-%d").is_ok()
+        || DateTime::parse_from_rfc3339(value).is_ok()
+    {
+        return Ok(());
+    }
+    Err(invalid(
+        field,
+        format!("expected an ISO 8601 date or timestamp, got '{}'", value),
+    ))
+}
+
+/// Checks a search query's length; short queries are handled by the caller.
+pub fn query(value: &str) -> Result<(), CommandError> {
+    if value.chars().count() > MAX_QUERY_LENGTH {
+        return Err(invalid(
+            "query",
+            format!("must be at most {} characters", MAX_QUERY_LENGTH),
+        ));
+    }
+    Ok(())
+}
+
+/// Checks a normalized (trimmed, lowercase) tag.
+pub fn tag(value: &str) -> Result<(), CommandError> {
+    if value.is_empty() {
+        return Err(invalid("tags", "must not be empty"));
+    }
+    if value.chars().count() > MAX_TAG_LENGTH {
+        return Err(invalid(
+            "tags",
+            format!("'{}' is longer than {} characters", value, MAX_TAG_LENGTH),
+        ));
+    }
+    if let Some(c) = value
+        .chars()
+        .find(|c| !c.is_alphanumeric() && !TAG_PUNCTUATION.contains(c))
+    {
+        return Err(invalid(
+            "tags",
+            format!("'{}' contains unsupported character '{}'", value, c),
+        ));
+    }
+    Ok(())
+}
+
+/// Checks a normalized (trimmed, lowercase) metadata key.
+pub fn metadata_key(value: &str) -> Result<(), CommandError> {
+    if value.is_empty() {
+        return Err(invalid("key", "must not be empty"));
+    }
+    if value.chars().count() > MAX_METADATA_KEY_LENGTH {
+        return Err(invalid(
+            "key",
+            format!("must be at most {} characters", MAX_METADATA_KEY_LENGTH),
+        ));
+    }
+    if let Some(c) = value
+        .chars()
+        .find(|c| !c.is_alphanumeric() && !METADATA_KEY_PUNCTUATION.contains(c))
+    {
+        return Err(invalid(
+            "key",
+            format!("'{}' contains unsupported character '{}'", value, c),
+        ));
+    }
+    Ok(())
+}
+
+/// Checks a trimmed metadata value: not empty, at most
+/// `MAX_METADATA_VALUE_LENGTH` characters, and on one line.
+pub fn metadata_value(value: &str) -> Result<(), CommandError> {
+    if value.is_empty() {
+        return Err(invalid("value", "must not be empty"));
+    }
+    if value.chars().count() > MAX_METADATA_VALUE_LENGTH {
+        return Err(invalid(
+            "value",
+            format!("must be at most {} characters", MAX_METADATA_VALUE_LENGTH),
+        ));
+    }
+    if value.chars().any(char::is_control) {
+        return Err(invalid("value", "must not contain control characters"));
+    }
+    Ok(())
+}
+
+/// Checks a trimmed reminder note's length.
+pub fn reminder_note(value: &str) -> Result<(), CommandError> {
+    if value.chars().count() > MAX_REMINDER_NOTE_LENGTH {
+        return Err(invalid(
+            "note",
+            format!("must be at most {} characters", MAX_REMINDER_NOTE_LENGTH),
+        ));
+    }
+    Ok(())
+}
+
+/// Checks conversation filters: dates must parse and be in order, and
+/// tags and metadata fields must be valid.
+pub fn filters(filters: &ConversationFilters) -> Result<(), CommandError> {
+    if let Some(ref start) = filters.date_start {
+        iso_date("dateStart", start)?;
+    }
+    if let Some(ref end) = filters.date_end {
+        iso_date("dateEnd", end)?;
+    }
+    if let (Some(start), Some(end)) = (&filters.date_start, &filters.date_end) {
+        let start = filter_boundary_millis(start, FilterTimezone::Utc, DateBoundary::Start);
+        let end = filter_boundary_millis(end, FilterTimezone::Utc, DateBoundary::End);
+        if start > end {
+            return Err(invalid("dateEnd", "must not be before dateStart"));
+        }
+    }
+    for value in filters.tags.iter().flatten() {
+        tag(&value.trim().to_lowercase())?;
+    }
+    for (key, value) in filters.metadata.iter().flatten() {
+        metadata_key(&key.trim().to_lowercase())?;
+        metadata_value(value.trim())?;
+    }
+    Ok(())
+}
+
+/// Checks that a URL, e.g. of a webhook or an issue, is an absolute
+/// `http://` or `https://` URL.
+pub fn http_url(value: &str) -> Result<(), CommandError> {
+    let host = value
+        .strip_prefix("https://")
+        .or_else(|| value.strip_prefix("http://"));
+    match host {
+        Some(host)
+            if !host.is_empty()
+                && !host.starts_with('/')
+                && !value.chars().any(char::is_whitespace) =>
+        {
+            Ok(())
+        }
+        _ => Err(invalid("url", "must be an http:// or https:// URL")),
+    }
+}
+
+/// Checks a normalized (trimmed) prompt: a title of at most
+/// `MAX_PROMPT_TITLE_LENGTH` characters and a body, neither empty.
+pub fn prompt(value: &PromptInput) -> Result<(), CommandError> {
+    if value.title.is_empty() {
+        return Err(invalid("title", "must not be empty"));
+    }
+    if value.title.chars().count() > MAX_PROMPT_TITLE_LENGTH {
+        return Err(invalid(
+            "title",
+            format!("must be at most {} characters", MAX_PROMPT_TITLE_LENGTH),
+        ));
+    }
+    if value.body.is_empty() {
+        return Err(invalid("body", "must not be empty"));
+    }
+    Ok(())
+}
+
+/// Checks a workspace: a name of at most `MAX_WORKSPACE_NAME_LENGTH`
+/// characters that isn't empty, valid filters, and an active tab that's
+/// one of the open tabs.
+pub fn workspace(value: &WorkspaceInput) -> Result<(), CommandError> {
+    if value.name.is_empty() {
+        return Err(invalid("name", "must not be empty"));
+    }
+    if value.name.chars().count() > MAX_WORKSPACE_NAME_LENGTH {
+        return Err(invalid(
+            "name",
+            format!("must be at most {} characters", MAX_WORKSPACE_NAME_LENGTH),
+        ));
+    }
+    filters(&value.filters)?;
+    if let Some(ref active) = value.active_tab {
+        if !value.open_tabs.contains(active) {
+            return Err(invalid("activeTab", "must be one of the open tabs"));
+        }
+    }
+    Ok(())
+}
+
+/// Checks a list window: `end` not before `start` and at most
+/// `MAX_WINDOW_SIZE` conversations.
+pub fn window(start: u32, end: u32) -> Result<(), CommandError> {
+    if end < start {
+        return Err(invalid("end", "must not be before start"));
+    }
+    if end - start > MAX_WINDOW_SIZE {
+        return Err(invalid(
+            "end",
+            format!("must be at most {} after start", MAX_WINDOW_SIZE),
+        ));
+    }
+    Ok(())
+}
+
+/// Checks pagination: a limit between 1 and `MAX_PAGE_LIMIT` and a
+/// non-negative offset.
+pub fn pagination(pagination: &PaginationParams) -> Result<(), CommandError> {
+    if !(1..=MAX_PAGE_LIMIT).contains(&pagination.limit) {
+        return Err(invalid(
+            "limit",
+            format!("must be between 1 and {}", MAX_PAGE_LIMIT),
+        ));
+    }
+    if pagination.offset < 0 {
+        return Err(invalid("offset", "must not be negative"));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn field_of(result: Result<(), CommandError>) -> String {
+        match result {
+            Err(CommandError::Validation { field, .. }) => field,
+            other => panic!("expected a validation error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_iso_date() {
+        assert!(iso_date("dateStart", "2025-01-31").is_ok());
+        assert!(iso_date("dateStart", "2025-01-31T12:00:00+09:00").is_ok());
+        assert!(iso_date("dateStart", " 2025-01-31T12:00:00Z ").is_ok());
+        for bad in ["2025-02-30", "31/01/2025", "yesterday", "1735689600000", ""] {
+            assert_eq!(field_of(iso_date("dateStart", bad)), "dateStart", "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_tag() {
+        for good in ["rust", "needs review", "c++", "v1.2", "área/ui", "#42"] {
+            assert!(tag(good).is_ok(), "{}", good);
+        }
+        assert_eq!(field_of(tag("")), "tags");
+        assert_eq!(field_of(tag("a;drop")), "tags");
+        assert_eq!(field_of(tag("new\nline")), "tags");
+        assert_eq!(field_of(tag(&"x".repeat(MAX_TAG_LENGTH + 1))), "tags");
+    }
+
+    #[test]
+    fn test_metadata_fields() {
+        for good in ["customer", "sprint", "jira.epic", "team-name", "área_2"] {
+            assert!(metadata_key(good).is_ok(), "{}", good);
+        }
+        assert_eq!(field_of(metadata_key("")), "key");
+        assert_eq!(field_of(metadata_key("has space")), "key");
+        assert_eq!(field_of(metadata_key("a=b")), "key");
+        assert_eq!(field_of(metadata_key(&"k".repeat(MAX_METADATA_KEY_LENGTH + 1))), "key");
+
+        assert!(metadata_value("Acme Corp").is_ok());
+        assert_eq!(field_of(metadata_value("")), "value");
+        assert_eq!(field_of(metadata_value("two\nlines")), "value");
+        assert_eq!(
+            field_of(metadata_value(&"v".repeat(MAX_METADATA_VALUE_LENGTH + 1))),
+            "value"
+        );
+
+        let bad_key = ConversationFilters {
+            metadata: Some([("a b".to_string(), "x".to_string())].into()),
+            ..Default::default()
+        };
+        assert_eq!(field_of(filters(&bad_key)), "key");
+    }
+
+    #[test]
+    fn test_http_url() {
+        assert!(http_url("https://hooks.slack.com/services/T0/B0/x").is_ok());
+        assert!(http_url("http://localhost:8080/hook").is_ok());
+        for bad in [
+            "",
+            "hooks.example.com",
+            "ftp://example.com",
+            "https://",
+            "https:///x",
+            "https://a b",
+        ] {
+            assert_eq!(field_of(http_url(bad)), "url", "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_prompt() {
+        let prompt_input = |title: &str, body: &str| PromptInput {
+            id: None,
+            title: title.to_string(),
+            body: body.to_string(),
+            source_conversation_id: None,
+            source_message_id: None,
+        };
+        assert!(prompt(&prompt_input("Review", "Review [file]")).is_ok());
+        assert_eq!(field_of(prompt(&prompt_input("", "body"))), "title");
+        assert_eq!(
+            field_of(prompt(&prompt_input(&"x".repeat(MAX_PROMPT_TITLE_LENGTH + 1), "body"))),
+            "title"
+        );
+        assert_eq!(field_of(prompt(&prompt_input("Review", ""))), "body");
+    }
+
+    #[test]
+    fn test_workspace() {
+        let workspace_input = |name: &str| WorkspaceInput {
+            name: name.to_string(),
+            filters: ConversationFilters::default(),
+            sort: Default::default(),
+            pinned: vec!["c1".to_string()],
+            open_tabs: vec!["c2".to_string()],
+            active_tab: Some("c2".to_string()),
+        };
+        assert!(workspace(&workspace_input("Incident review")).is_ok());
+        assert_eq!(field_of(workspace(&workspace_input(""))), "name");
+        assert_eq!(
+            field_of(workspace(&workspace_input(&"x".repeat(MAX_WORKSPACE_NAME_LENGTH + 1)))),
+            "name"
+        );
+
+        let mut pinned_only = workspace_input("Rust learning");
+        pinned_only.active_tab = Some("c1".to_string());
+        assert_eq!(field_of(workspace(&pinned_only)), "activeTab");
+        let mut bad_filters = workspace_input("Rust learning");
+        bad_filters.filters.date_start = Some("last week".to_string());
+        assert_eq!(field_of(workspace(&bad_filters)), "dateStart");
+    }
+
+    #[test]
+    fn test_filters() {
+        let ok = ConversationFilters {
+            date_start: Some("2025-01-01".to_string()),
+            date_end: Some("2025-01-01".to_string()),
+            tags: Some(vec!["Rust".to_string()]),
+            ..Default::default()
+        };
+        assert!(filters(&ok).is_ok());
+
+        let reversed = ConversationFilters {
+            date_start: Some("2025-02-01".to_string()),
+            date_end: Some("2025-01-01T00:00:00Z".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(field_of(filters(&reversed)), "dateEnd");
+
+        let bad_date = ConversationFilters {
+            date_end: Some("soon".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(field_of(filters(&bad_date)), "dateEnd");
+    }
+
+    #[test]
+    fn test_limits_and_pagination() {
+        assert!(limit("limit", 1, 10).is_ok());
+        assert_eq!(field_of(limit("limit", 0, 10)), "limit");
+        assert_eq!(field_of(limit("count", 11, 10)), "count");
+
+        assert!(pagination(&PaginationParams::default()).is_ok());
+        let negative = PaginationParams {
+            limit: 10,
+            offset: -1,
+        };
+        assert_eq!(field_of(pagination(&negative)), "offset");
+        let huge = PaginationParams {
+            limit: MAX_PAGE_LIMIT + 1,
+            offset: 0,
+        };
+        assert_eq!(field_of(pagination(&huge)), "limit");
+
+        assert!(window(5, 5).is_ok());
+        assert_eq!(field_of(window(5, 4)), "end");
+        assert_eq!(field_of(window(0, MAX_WINDOW_SIZE + 1)), "end");
+
+        assert!(query(&"q".repeat(MAX_QUERY_LENGTH)).is_ok());
+        assert_eq!(field_of(query(&"q".repeat(MAX_QUERY_LENGTH + 1))), "query");
+    }
+}
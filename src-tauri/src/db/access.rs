@@ -0,0 +1,157 @@
+//! Access errors of session discovery.
+//!
+//! Project directories and session files a scan can't read, typically
+//! because a managed machine denies access to them, are recorded per path
+//! so the health report tells partial results apart from complete ones.
+//! Each path is retried with exponential backoff until a scan reads it,
+//! which clears its error.
+
+use crate::db::sqlite::DbResult;
+use crate::models::AccessError;
+use crate::parser::AccessFailure;
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+use std::collections::HashSet;
+use std::time::Duration;
+use tracing::info;
+
+/// Wait before the first retry of an unreadable path; doubled for each
+/// later one.
+const RETRY_BASE_DELAY: Duration = Duration::from_secs(30);
+
+/// Longest wait between retries.
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(60 * 60);
+
+/// Wait before retrying a path that has failed `attempts` times.
+pub fn retry_delay(attempts: u32) -> Duration {
+    let doublings = attempts.saturating_sub(1).min(16);
+    (RETRY_BASE_DELAY * 2u32.pow(doublings)).min(MAX_RETRY_DELAY)
+}
+
+/// Records the access failures of a full scan at `now` (epoch
+/// milliseconds). Paths failing again have their attempts counted and
+/// their retry pushed back; recorded paths the scan read are cleared.
+pub fn record_access_failures(
+    conn: &Connection,
+    failures: &[AccessFailure],
+    now: i64,
+) -> DbResult<()> {
+    let failing: HashSet<String> = failures
+        .iter()
+        .map(|f| f.path.to_string_lossy().into_owned())
+        .collect();
+    let recorded = {
+        let mut stmt = conn.prepare_cached("SELECT path FROM access_errors")?;
+        let paths = stmt.query_map([], |row| row.get::<_, String>(0))?;
+        paths.collect::<Result<Vec<_>, _>>()?
+    };
+    for path in recorded.iter().filter(|path| !failing.contains(*path)) {
+        info!("{} is readable again", path);
+        conn.execute("DELETE FROM access_errors WHERE path = ?1", [path])?;
+    }
+
+    let failed_at = DateTime::from_timestamp_millis(now)
+        .unwrap_or_default()
+        .to_rfc3339();
+    for failure in failures {
+        let path = failure.path.to_string_lossy();
+        let attempts: u32 = conn
+            .query_row(
+                "SELECT attempts FROM access_errors WHERE path = ?1",
+                [&path],
+                |row| row.get(0),
+            )
+            .optional()?
+            .unwrap_or(0)
+            + 1;
+        let next_retry_at = now + retry_delay(attempts).as_millis() as i64;
+        conn.execute(
+            "INSERT INTO access_errors
+                 (path, error, attempts, first_failed_at, last_failed_at, next_retry_at)
+             VALUES (?1, ?2, ?3, ?4, ?4, ?5)
+             ON CONFLICT(path) DO UPDATE SET
+                 error = excluded.error,
+                 attempts = excluded.attempts,
+                 last_failed_at = excluded.last_failed_at,
+                 next_retry_at = excluded.next_retry_at",
+            params![path, failure.error, attempts, failed_at, next_retry_at],
+        )?;
+    }
+    Ok(())
+}
+
+/// Lists the paths the last scan couldn't read, by path.
+pub fn list_access_errors(conn: &Connection) -> DbResult<Vec<AccessError>> {
+    let mut stmt = conn.prepare_cached(
+        "SELECT path, error, attempts, first_failed_at, last_failed_at, next_retry_at
+         FROM access_errors
+         ORDER BY path",
+    )?;
+    let rows = stmt.query_map([], |row| {
+        Ok(AccessError {
+            path: row.get(0)?,
+            error: row.get(1)?,
+            attempts: row.get(2)?,
+            first_failed_at: row.get(3)?,
+            last_failed_at: row.get(4)?,
+            next_retry_at: DateTime::<Utc>::from_timestamp_millis(row.get(5)?)
+                .unwrap_or_default()
+                .to_rfc3339(),
+        })
+    })?;
+    Ok(rows.collect::<Result<_, _>>()?)
+}
+
+/// When the next unreadable path is due to be retried (epoch
+/// milliseconds), or `None` if every path was read.
+pub fn next_access_retry(conn: &Connection) -> DbResult<Option<i64>> {
+    Ok(conn.query_row("SELECT MIN(next_retry_at) FROM access_errors", [], |row| {
+        row.get(0)
+    })?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::sqlite::init_db;
+    use std::path::PathBuf;
+
+    fn failure(path: &str) -> AccessFailure {
+        AccessFailure {
+            path: PathBuf::from(path),
+            error: "Permission denied (os error 13)".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_retry_delay_backs_off() {
+        assert_eq!(retry_delay(1), Duration::from_secs(30));
+        assert_eq!(retry_delay(3), Duration::from_secs(120));
+        assert_eq!(retry_delay(40), MAX_RETRY_DELAY);
+    }
+
+    #[test]
+    fn test_access_failures_are_retried_until_readable() {
+        let conn = Connection::open_in_memory().unwrap();
+        init_db(&conn).unwrap();
+        assert_eq!(next_access_retry(&conn).unwrap(), None);
+
+        let now = 1_700_000_000_000;
+        record_access_failures(&conn, &[failure("/p/a"), failure("/p/b")], now).unwrap();
+        record_access_failures(&conn, &[failure("/p/a")], now + 60_000).unwrap();
+
+        let errors = list_access_errors(&conn).unwrap();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].path, "/p/a");
+        assert_eq!(errors[0].attempts, 2);
+        assert!(errors[0].first_failed_at < errors[0].last_failed_at);
+        assert_eq!(
+            next_access_retry(&conn).unwrap(),
+            Some(now + 60_000 + retry_delay(2).as_millis() as i64)
+        );
+
+        record_access_failures(&conn, &[], now + 120_000).unwrap();
+        assert!(list_access_errors(&conn).unwrap().is_empty());
+        assert_eq!(next_access_retry(&conn).unwrap(), None);
+    }
+}
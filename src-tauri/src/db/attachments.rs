@@ -0,0 +1,146 @@
+//! Attachments store for images extracted from tool results.
+//!
+//! Tool results can carry base64 screenshots megabytes long. The parser moves
+//! them out of the content blocks, and they're kept here under the hash of
+//! their data, so the same image from several conversations (or read many
+//! times in one) is stored once and the viewer fetches it only when it
+//! displays it. Their data is stored compressed (see [`StoredText`]).
+//!
+//! `attachment_refs` records which conversations use each attachment. When
+//! the last of them is deleted, a trigger removes the attachment, so deleting
+//! conversations frees the space of their images. Attachments are extracted
+//! again from the session file whenever a conversation is loaded, so none is
+//! lost that could still be shown.
+
+use crate::db::sqlite::DbResult;
+use crate::db::storage::StoredText;
+use crate::models::Attachment;
+use rusqlite::{params, Connection, OptionalExtension};
+
+/// Stores a conversation's attachments, skipping any already stored, and
+/// references them from the conversation.
+pub fn store(
+    conn: &Connection,
+    conversation_id: &str,
+    attachments: &[Attachment],
+) -> DbResult<()> {
+    if attachments.is_empty() {
+        return Ok(());
+    }
+    let mut exists = conn.prepare_cached("SELECT 1 FROM attachments WHERE id = ?1")?;
+    let mut insert = conn.prepare_cached(
+        "INSERT INTO attachments (id, media_type, data, size, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+    )?;
+    let mut reference = conn.prepare_cached(
+        "INSERT OR IGNORE INTO attachment_refs (attachment_id, conversation_id) VALUES (?1, ?2)",
+    )?;
+    let now = chrono::Utc::now().to_rfc3339();
+    for attachment in attachments {
+        // Checked first, so stored data isn't compressed again
+        if !exists.exists([&attachment.id])? {
+            insert.execute(params![
+                attachment.id,
+                attachment.media_type,
+                StoredText(attachment.data.clone()),
+                attachment.size,
+                now
+            ])?;
+        }
+        reference.execute([&attachment.id, conversation_id])?;
+    }
+    Ok(())
+}
+
+/// Number of conversations referencing an attachment.
+pub fn ref_count(conn: &Connection, id: &str) -> DbResult<i64> {
+    let count = conn
+        .prepare_cached("SELECT COUNT(*) FROM attachment_refs WHERE attachment_id = ?1")?
+        .query_row([id], |row| row.get(0))?;
+    Ok(count)
+}
+
+/// Looks up an attachment by ID.
+pub fn get(conn: &Connection, id: &str) -> DbResult<Option<Attachment>> {
+    let attachment = conn
+        .prepare_cached("SELECT id, media_type, data, size FROM attachments WHERE id = ?1")?
+        .query_row([id], |row| {
+            Ok(Attachment {
+                id: row.get(0)?,
+                media_type: row.get(1)?,
+                data: row.get::<_, StoredText>(2)?.into_inner(),
+                size: row.get(3)?,
+            })
+        })
+        .optional()?;
+    Ok(attachment)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::sqlite::init_db;
+
+    fn attachment(data: &str) -> Attachment {
+        Attachment {
+            id: format!("id-{}", data),
+            media_type: "image/png".to_string(),
+            data: data.to_string(),
+            size: 3,
+        }
+    }
+
+    fn setup() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("PRAGMA foreign_keys = ON;").unwrap();
+        init_db(&conn).unwrap();
+        for id in ["c1", "c2"] {
+            conn.execute(
+                "INSERT INTO conversations (id, project_path, project_name, start_time, last_time, file_path, file_modified_at)
+                 VALUES (?1, '/p', 'p', '', '', '/f.jsonl', '')",
+                [id],
+            )
+            .unwrap();
+        }
+        conn
+    }
+
+    #[test]
+    fn test_store_and_get() {
+        let conn = setup();
+
+        store(
+            &conn,
+            "c1",
+            &[attachment("AAAA"), attachment("AAAA"), attachment("BBBB")],
+        )
+        .unwrap();
+        let count: i64 = conn
+            .query_row("SELECT COUNT(*) FROM attachments", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(count, 2);
+
+        assert_eq!(get(&conn, "id-BBBB").unwrap(), Some(attachment("BBBB")));
+        assert_eq!(get(&conn, "missing").unwrap(), None);
+    }
+
+    #[test]
+    fn test_attachments_go_with_their_last_conversation() {
+        let conn = setup();
+        store(&conn, "c1", &[attachment("AAAA"), attachment("BBBB")]).unwrap();
+        store(&conn, "c2", &[attachment("AAAA")]).unwrap();
+        store(&conn, "c2", &[attachment("AAAA")]).unwrap();
+        assert_eq!(ref_count(&conn, "id-AAAA").unwrap(), 2);
+        assert_eq!(ref_count(&conn, "id-BBBB").unwrap(), 1);
+
+        conn.execute("DELETE FROM conversations WHERE id = 'c1'", [])
+            .unwrap();
+        assert_eq!(ref_count(&conn, "id-AAAA").unwrap(), 1);
+        assert!(get(&conn, "id-AAAA").unwrap().is_some());
+        assert_eq!(get(&conn, "id-BBBB").unwrap(), None);
+
+        conn.execute("DELETE FROM conversations WHERE id = 'c2'", [])
+            .unwrap();
+        assert_eq!(get(&conn, "id-AAAA").unwrap(), None);
+    }
+}
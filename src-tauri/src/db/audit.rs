@@ -0,0 +1,195 @@
+//! Activity audit log of user-data changes.
+//!
+//! Tag, bookmark, archive, delete, protection, and export operations each
+//! append an entry to `audit_log` saying who did what and when. Entries
+//! don't reference `conversations`, so they outlive deleted conversations,
+//! which is when they're most useful.
+
+use crate::db::sqlite::DbResult;
+use crate::models::{AnnotationState, AuditAction, AuditEntry};
+use rusqlite::{params, Connection};
+
+/// Most entries kept; older ones are pruned as new ones are added.
+pub const MAX_AUDIT_ENTRIES: i64 = 10_000;
+
+/// Name of the OS user running the app.
+pub fn current_actor() -> String {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_else(|_| "unknown".to_string())
+}
+
+/// Appends an entry.
+pub fn record(
+    conn: &Connection,
+    action: AuditAction,
+    conversation_id: Option<&str>,
+    details: &str,
+) -> DbResult<()> {
+    conn.execute(
+        "INSERT INTO audit_log (created_at, actor, action, conversation_id, details)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![
+            chrono::Utc::now().to_rfc3339(),
+            current_actor(),
+            action.as_str(),
+            conversation_id,
+            details
+        ],
+    )?;
+    conn.execute(
+        "DELETE FROM audit_log WHERE id <= last_insert_rowid() - ?1",
+        [MAX_AUDIT_ENTRIES],
+    )?;
+    Ok(())
+}
+
+/// Records the tags added and removed, or the bookmark change, between two
+/// states of a conversation's annotation.
+pub fn record_annotation_change(
+    conn: &Connection,
+    conversation_id: &str,
+    before: &AnnotationState,
+    after: &AnnotationState,
+) -> DbResult<()> {
+    match (before, after) {
+        (AnnotationState::Tags(before), AnnotationState::Tags(after)) => {
+            for tag in after.iter().filter(|tag| !before.contains(tag)) {
+                record(conn, AuditAction::TagAdded, Some(conversation_id), tag)?;
+            }
+            for tag in before.iter().filter(|tag| !after.contains(tag)) {
+                record(conn, AuditAction::TagRemoved, Some(conversation_id), tag)?;
+            }
+        }
+        (AnnotationState::Bookmarked(before), AnnotationState::Bookmarked(after))
+            if before != after =>
+        {
+            let action = if *after {
+                AuditAction::Bookmarked
+            } else {
+                AuditAction::Unbookmarked
+            };
+            record(conn, action, Some(conversation_id), "")?;
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Lists entries, newest first, optionally only those about one conversation.
+pub fn list_entries(
+    conn: &Connection,
+    conversation_id: Option<&str>,
+    limit: u32,
+) -> DbResult<Vec<AuditEntry>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, created_at, actor, action, conversation_id, details FROM audit_log
+         WHERE ?1 IS NULL OR conversation_id = ?1
+         ORDER BY id DESC LIMIT ?2",
+    )?;
+    let rows = stmt.query_map(params![conversation_id, limit], |row| {
+        // Actions written by newer versions are skipped
+        let Some(action) = AuditAction::from_db(&row.get::<_, String>(3)?) else {
+            return Ok(None);
+        };
+        Ok(Some(AuditEntry {
+            id: row.get(0)?,
+            created_at: row.get(1)?,
+            actor: row.get(2)?,
+            action,
+            conversation_id: row.get(4)?,
+            details: row.get(5)?,
+        }))
+    })?;
+
+    let mut entries = Vec::new();
+    for row in rows {
+        entries.extend(row?);
+    }
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::sqlite::init_db;
+
+    fn setup() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        init_db(&conn).unwrap();
+        conn
+    }
+
+    fn actions(entries: &[AuditEntry]) -> Vec<(AuditAction, &str)> {
+        entries
+            .iter()
+            .map(|e| (e.action, e.details.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn test_record_and_list() {
+        let conn = setup();
+        record(&conn, AuditAction::Deleted, Some("c1"), "").unwrap();
+        record(&conn, AuditAction::Exported, Some("c2"), "markdown").unwrap();
+
+        let entries = list_entries(&conn, None, 10).unwrap();
+        assert_eq!(
+            actions(&entries),
+            vec![
+                (AuditAction::Exported, "markdown"),
+                (AuditAction::Deleted, "")
+            ]
+        );
+        assert_eq!(entries[0].actor, current_actor());
+        assert_eq!(entries[1].conversation_id.as_deref(), Some("c1"));
+
+        let only_c1 = list_entries(&conn, Some("c1"), 10).unwrap();
+        assert_eq!(actions(&only_c1), vec![(AuditAction::Deleted, "")]);
+        assert_eq!(list_entries(&conn, None, 1).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_record_annotation_change() {
+        let conn = setup();
+        let tags =
+            |values: &[&str]| AnnotationState::Tags(values.iter().map(|v| v.to_string()).collect());
+        record_annotation_change(&conn, "c1", &tags(&["a", "b"]), &tags(&["b", "c"])).unwrap();
+        record_annotation_change(
+            &conn,
+            "c1",
+            &AnnotationState::Bookmarked(false),
+            &AnnotationState::Bookmarked(true),
+        )
+        .unwrap();
+        record_annotation_change(
+            &conn,
+            "c1",
+            &AnnotationState::Bookmarked(true),
+            &AnnotationState::Bookmarked(true),
+        )
+        .unwrap();
+
+        let entries = list_entries(&conn, None, 10).unwrap();
+        assert_eq!(
+            actions(&entries),
+            vec![
+                (AuditAction::Bookmarked, ""),
+                (AuditAction::TagRemoved, "a"),
+                (AuditAction::TagAdded, "c"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_action_round_trip() {
+        for action in [
+            AuditAction::TagAdded,
+            AuditAction::Unprotected,
+            AuditAction::Exported,
+        ] {
+            assert_eq!(AuditAction::from_db(action.as_str()), Some(action));
+        }
+        assert_eq!(AuditAction::from_db("renamed"), None);
+    }
+}
@@ -0,0 +1,294 @@
+//! Sub-agent transcripts nested under the sessions that invoked them.
+//!
+//! A Task tool call runs a sub-agent whose transcript is written to a file
+//! of its own, so it's ingested as a conversation with no visible tie to
+//! the session that ran it. After each ingest, sub-agent transcripts (see
+//! [`ParsedConversation::is_subagent`]) without a parent are matched to
+//! one, by the first of:
+//!
+//! - a directory of their file named after the parent's session ID
+//!   (`<session>/subagents/agent-*.jsonl`);
+//! - the parent's session ID on their lines;
+//! - the session running in the same working directory (or project, if
+//!   it isn't recorded) when they started.
+//!
+//! Users attach children the heuristics miss by hand. Attached links are
+//! kept; detected ones are detected again whenever the child is ingested.
+//!
+//! [`ParsedConversation::is_subagent`]: crate::parser::jsonl::ParsedConversation::is_subagent
+
+use super::sqlite::DbResult;
+use crate::parser::jsonl::DEFAULT_SESSION_ID;
+use rusqlite::{params, Connection, OptionalExtension};
+use std::collections::HashSet;
+use std::path::Path;
+
+/// Source of a link found by the heuristics.
+const DETECTED: &str = "detected";
+
+/// Source of a link made by the user.
+const ATTACHED: &str = "attached";
+
+/// How many directories above a transcript are checked for a session ID.
+const SESSION_DIR_DEPTH: usize = 3;
+
+/// A sub-agent transcript without a parent.
+struct Orphan {
+    id: String,
+    file_path: String,
+    session_id: String,
+}
+
+/// Forgets a conversation's detected parent, to detect it again.
+pub fn clear_detected_parent(conn: &Connection, child_id: &str) -> DbResult<()> {
+    conn.prepare_cached("DELETE FROM conversation_children WHERE child_id = ?1 AND source = ?2")?
+        .execute([child_id, DETECTED])?;
+    Ok(())
+}
+
+/// A session that isn't a sub-agent's, other than `except_id`, by session ID.
+fn session_by_id(conn: &Connection, session_id: &str, except_id: &str) -> DbResult<Option<String>> {
+    Ok(conn
+        .prepare_cached(
+            "SELECT id FROM conversations
+             WHERE session_id = ?1 AND is_subagent = 0 AND id != ?2
+             ORDER BY id
+             LIMIT 1",
+        )?
+        .query_row([session_id, except_id], |row| row.get(0))
+        .optional()?)
+}
+
+/// Finds the session that invoked a sub-agent.
+fn find_parent(conn: &Connection, orphan: &Orphan) -> DbResult<Option<String>> {
+    let session_dirs = Path::new(&orphan.file_path)
+        .ancestors()
+        .skip(1)
+        .take(SESSION_DIR_DEPTH)
+        .filter_map(|dir| dir.file_name()?.to_str());
+    for dir in session_dirs {
+        if let Some(parent) = session_by_id(conn, dir, &orphan.id)? {
+            return Ok(Some(parent));
+        }
+    }
+
+    if !orphan.session_id.is_empty() && orphan.session_id != DEFAULT_SESSION_ID {
+        if let Some(parent) = session_by_id(conn, &orphan.session_id, &orphan.id)? {
+            return Ok(Some(parent));
+        }
+    }
+
+    Ok(conn
+        .prepare_cached(
+            "SELECT p.id
+             FROM conversations c
+             JOIN conversations p ON p.id != c.id AND p.is_subagent = 0
+             WHERE c.id = ?1
+               AND CASE WHEN c.cwd != '' THEN p.cwd = c.cwd
+                        ELSE p.project_path = c.project_path END
+               AND p.start_epoch <= c.start_epoch AND p.last_epoch >= c.start_epoch
+             ORDER BY p.start_epoch DESC, p.id
+             LIMIT 1",
+        )?
+        .query_row([&orphan.id], |row| row.get(0))
+        .optional()?)
+}
+
+/// Nests the sub-agent transcripts without a parent under the sessions
+/// that invoked them, where those can be found. Returns how many were
+/// nested.
+pub fn detect_parents(conn: &Connection) -> DbResult<usize> {
+    let orphans: Vec<Orphan> = {
+        let mut stmt = conn.prepare_cached(
+            "SELECT c.id, c.file_path, c.session_id FROM conversations c
+             WHERE c.is_subagent = 1
+               AND NOT EXISTS (SELECT 1 FROM conversation_children l WHERE l.child_id = c.id)",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok(Orphan {
+                id: row.get(0)?,
+                file_path: row.get(1)?,
+                session_id: row.get(2)?,
+            })
+        })?;
+        rows.collect::<Result<_, _>>()?
+    };
+
+    let mut nested = 0;
+    for orphan in &orphans {
+        if let Some(parent) = find_parent(conn, orphan)? {
+            conn.prepare_cached(
+                "INSERT INTO conversation_children (child_id, parent_id, source, created_at)
+                 VALUES (?1, ?2, ?3, ?4)",
+            )?
+            .execute(params![
+                orphan.id,
+                parent,
+                DETECTED,
+                chrono::Utc::now().to_rfc3339()
+            ])?;
+            nested += 1;
+        }
+    }
+    Ok(nested)
+}
+
+/// Nests a conversation under another by hand, replacing the parent it
+/// had.
+pub fn attach_child(conn: &Connection, parent_id: &str, child_id: &str) -> DbResult<()> {
+    conn.execute(
+        "INSERT INTO conversation_children (child_id, parent_id, source, created_at)
+         VALUES (?1, ?2, ?3, ?4)
+         ON CONFLICT(child_id) DO UPDATE SET
+             parent_id = excluded.parent_id, source = excluded.source,
+             created_at = excluded.created_at",
+        params![
+            child_id,
+            parent_id,
+            ATTACHED,
+            chrono::Utc::now().to_rfc3339()
+        ],
+    )?;
+    Ok(())
+}
+
+/// The conversation a conversation is nested under, if any.
+pub fn parent_id(conn: &Connection, child_id: &str) -> DbResult<Option<String>> {
+    Ok(conn
+        .prepare_cached("SELECT parent_id FROM conversation_children WHERE child_id = ?1")?
+        .query_row([child_id], |row| row.get(0))
+        .optional()?)
+}
+
+/// Whether `ancestor_id` is `id` or a conversation it's nested under,
+/// directly or not.
+pub fn is_ancestor(conn: &Connection, ancestor_id: &str, id: &str) -> DbResult<bool> {
+    let mut seen = HashSet::new();
+    let mut current = Some(id.to_string());
+    while let Some(id) = current {
+        if id == ancestor_id {
+            return Ok(true);
+        }
+        if !seen.insert(id.clone()) {
+            break;
+        }
+        current = parent_id(conn, &id)?;
+    }
+    Ok(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::sqlite::init_db;
+
+    fn insert_conversation(
+        conn: &Connection,
+        id: &str,
+        session_id: &str,
+        file_path: &str,
+        times: (&str, &str),
+        is_subagent: bool,
+        cwd: &str,
+    ) {
+        conn.execute(
+            "INSERT INTO conversations (id, project_path, project_name, start_time, last_time, file_path, file_modified_at, session_id, is_subagent, cwd)
+             VALUES (?1, '/p', 'p', ?2, ?3, ?4, '', ?5, ?6, ?7)",
+            params![id, times.0, times.1, file_path, session_id, is_subagent, cwd],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_detect_parents() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("PRAGMA foreign_keys = ON;").unwrap();
+        init_db(&conn).unwrap();
+        let morning = ("2025-01-15T09:00:00Z", "2025-01-15T11:00:00Z");
+        let task = ("2025-01-15T10:00:00Z", "2025-01-15T10:05:00Z");
+        insert_conversation(
+            &conn,
+            "main",
+            "s-main",
+            "/p/s-main.jsonl",
+            morning,
+            false,
+            "/work/api",
+        );
+        insert_conversation(
+            &conn,
+            "web",
+            "s-web",
+            "/p/s-web.jsonl",
+            morning,
+            false,
+            "/work/web",
+        );
+        // In the main session's directory
+        insert_conversation(
+            &conn,
+            "by-dir",
+            "s-1",
+            "/p/s-main/subagents/agent-1.jsonl",
+            task,
+            true,
+            "",
+        );
+        // With the web session's ID on its lines
+        insert_conversation(
+            &conn,
+            "by-session",
+            "s-web",
+            "/p/agent-2.jsonl",
+            task,
+            true,
+            "/work/api",
+        );
+        // Started in the web session's directory while it ran
+        insert_conversation(
+            &conn,
+            "by-cwd",
+            "s-3",
+            "/p/agent-3.jsonl",
+            task,
+            true,
+            "/work/web",
+        );
+        // Started before any session in its directory
+        let early = ("2025-01-15T08:00:00Z", "2025-01-15T08:05:00Z");
+        insert_conversation(
+            &conn,
+            "orphan",
+            "s-4",
+            "/p/agent-4.jsonl",
+            early,
+            true,
+            "/work/api",
+        );
+
+        assert_eq!(detect_parents(&conn).unwrap(), 3);
+        assert_eq!(parent_id(&conn, "by-dir").unwrap().as_deref(), Some("main"));
+        assert_eq!(
+            parent_id(&conn, "by-session").unwrap().as_deref(),
+            Some("web")
+        );
+        assert_eq!(parent_id(&conn, "by-cwd").unwrap().as_deref(), Some("web"));
+        assert_eq!(parent_id(&conn, "orphan").unwrap(), None);
+        assert_eq!(detect_parents(&conn).unwrap(), 0);
+
+        // Attached links survive detecting again
+        attach_child(&conn, "main", "by-cwd").unwrap();
+        attach_child(&conn, "main", "orphan").unwrap();
+        for id in ["by-dir", "by-cwd", "orphan"] {
+            clear_detected_parent(&conn, id).unwrap();
+        }
+        assert_eq!(detect_parents(&conn).unwrap(), 1);
+        assert_eq!(parent_id(&conn, "by-cwd").unwrap().as_deref(), Some("main"));
+        assert_eq!(parent_id(&conn, "orphan").unwrap().as_deref(), Some("main"));
+
+        assert!(is_ancestor(&conn, "main", "orphan").unwrap());
+        assert!(is_ancestor(&conn, "orphan", "orphan").unwrap());
+        assert!(!is_ancestor(&conn, "orphan", "main").unwrap());
+        assert!(!is_ancestor(&conn, "web", "orphan").unwrap());
+    }
+}
@@ -0,0 +1,158 @@
+//! Chains of resumed sessions.
+//!
+//! Resuming a session (`claude --resume`) continues it in a new session
+//! file, so one piece of work is spread over several conversations. At
+//! ingest every conversation records the UUIDs of its messages and of the
+//! earlier messages it continues from (see
+//! [`ParsedConversation::continues_from`]); a conversation continues the
+//! one holding such a message that ended before it. Resumed files may copy
+//! the earlier messages, so a UUID can be held by several conversations:
+//! the one it's the last message of is taken, or else the one that ended
+//! last before the continuation.
+//!
+//! [`ParsedConversation::continues_from`]: crate::parser::jsonl::ParsedConversation::continues_from
+
+use super::sqlite::DbResult;
+use rusqlite::{params, Connection, OptionalExtension};
+use std::collections::HashSet;
+
+/// Replaces what's recorded of a conversation's messages (their UUIDs in
+/// order) and what it continues from.
+pub fn replace_continuations(
+    conn: &Connection,
+    conversation_id: &str,
+    message_uuids: &[&str],
+    continues_from: &[String],
+) -> DbResult<()> {
+    conn.prepare_cached("DELETE FROM message_uuids WHERE conversation_id = ?1")?
+        .execute([conversation_id])?;
+    conn.prepare_cached("DELETE FROM continuation_refs WHERE conversation_id = ?1")?
+        .execute([conversation_id])?;
+    let mut insert = conn.prepare_cached(
+        "INSERT OR REPLACE INTO message_uuids (conversation_id, uuid, is_leaf) VALUES (?1, ?2, ?3)",
+    )?;
+    for (index, uuid) in message_uuids.iter().enumerate() {
+        insert.execute(params![
+            conversation_id,
+            uuid,
+            index + 1 == message_uuids.len()
+        ])?;
+    }
+    let mut insert = conn.prepare_cached(
+        "INSERT OR IGNORE INTO continuation_refs (conversation_id, uuid) VALUES (?1, ?2)",
+    )?;
+    for uuid in continues_from {
+        insert.execute([conversation_id, uuid])?;
+    }
+    Ok(())
+}
+
+/// The conversation `conversation_id` continues, if it was resumed from one.
+fn continued(conn: &Connection, conversation_id: &str) -> DbResult<Option<String>> {
+    Ok(conn
+        .prepare_cached(
+            "SELECT p.id
+             FROM continuation_refs r
+             JOIN message_uuids u ON u.uuid = r.uuid AND u.conversation_id != r.conversation_id
+             JOIN conversations p ON p.id = u.conversation_id
+             JOIN conversations c ON c.id = r.conversation_id
+             WHERE r.conversation_id = ?1 AND p.last_epoch < c.last_epoch
+             ORDER BY u.is_leaf DESC, p.last_epoch DESC, p.id
+             LIMIT 1",
+        )?
+        .query_row([conversation_id], |row| row.get(0))
+        .optional()?)
+}
+
+/// The first conversation resumed from `conversation_id`, if any.
+fn continuation(conn: &Connection, conversation_id: &str) -> DbResult<Option<String>> {
+    Ok(conn
+        .prepare_cached(
+            "SELECT s.id
+             FROM message_uuids u
+             JOIN continuation_refs r ON r.uuid = u.uuid AND r.conversation_id != u.conversation_id
+             JOIN conversations s ON s.id = r.conversation_id
+             JOIN conversations c ON c.id = u.conversation_id
+             WHERE u.conversation_id = ?1 AND s.last_epoch > c.last_epoch
+             ORDER BY u.is_leaf DESC, s.last_epoch, s.id
+             LIMIT 1",
+        )?
+        .query_row([conversation_id], |row| row.get(0))
+        .optional()?)
+}
+
+/// Lists the IDs of the chain of sessions a conversation belongs to, from
+/// the original session through each resume, oldest first. A session
+/// resumed more than once continues with its first resume. A conversation
+/// that wasn't resumed or resumed from is a chain of its own.
+pub fn conversation_chain(conn: &Connection, conversation_id: &str) -> DbResult<Vec<String>> {
+    let mut seen = HashSet::from([conversation_id.to_string()]);
+    let mut chain = vec![conversation_id.to_string()];
+    while let Some(earlier) = continued(conn, &chain[chain.len() - 1])? {
+        if !seen.insert(earlier.clone()) {
+            break;
+        }
+        chain.push(earlier);
+    }
+    chain.reverse();
+    while let Some(later) = continuation(conn, &chain[chain.len() - 1])? {
+        if !seen.insert(later.clone()) {
+            break;
+        }
+        chain.push(later);
+    }
+    Ok(chain)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::sqlite::init_db;
+
+    fn insert_conversation(
+        conn: &Connection,
+        id: &str,
+        last_time: &str,
+        uuids: &[&str],
+        continues_from: &[&str],
+    ) {
+        conn.execute(
+            "INSERT INTO conversations (id, project_path, project_name, start_time, last_time, file_path, file_modified_at)
+             VALUES (?1, '/p', 'p', '2025-01-15T09:00:00Z', ?2, '/a.jsonl', '')",
+            [id, last_time],
+        )
+        .unwrap();
+        let continues_from: Vec<String> = continues_from.iter().map(|s| s.to_string()).collect();
+        replace_continuations(conn, id, uuids, &continues_from).unwrap();
+    }
+
+    #[test]
+    fn test_chain_follows_resumes_both_ways() {
+        let conn = Connection::open_in_memory().unwrap();
+        init_db(&conn).unwrap();
+        insert_conversation(&conn, "a", "2025-01-15T10:00:00Z", &["a1", "a2"], &[]);
+        // Resumed from a with a copy of its messages
+        insert_conversation(
+            &conn,
+            "b",
+            "2025-01-16T10:00:00Z",
+            &["a1", "a2", "b1"],
+            &["a2"],
+        );
+        // Resumed from b, its summary still naming a's leaf
+        insert_conversation(&conn, "c", "2025-01-17T10:00:00Z", &["c1"], &["a2", "b1"]);
+        // Resumed from a again, later
+        insert_conversation(&conn, "d", "2025-01-18T10:00:00Z", &["d1"], &["a2"]);
+        insert_conversation(&conn, "e", "2025-01-19T10:00:00Z", &["e1"], &["unknown"]);
+
+        for id in ["a", "b", "c"] {
+            assert_eq!(conversation_chain(&conn, id).unwrap(), vec!["a", "b", "c"]);
+        }
+        assert_eq!(conversation_chain(&conn, "d").unwrap(), vec!["a", "d"]);
+        assert_eq!(conversation_chain(&conn, "e").unwrap(), vec!["e"]);
+
+        // Re-ingesting replaces the links
+        replace_continuations(&conn, "c", &["c1"], &[]).unwrap();
+        assert_eq!(conversation_chain(&conn, "c").unwrap(), vec!["c"]);
+    }
+}
@@ -0,0 +1,156 @@
+//! Custom metadata fields of conversations.
+//!
+//! Teams attach their own fields to conversations, such as `customer=acme`
+//! or `sprint=42`, without a column per field. Keys are stored lowercase;
+//! values as given. Conversation lists and searches filter by fields
+//! through `ConversationFilters::metadata`, and searches also through
+//! `metadata:key=value` terms (see [`crate::search::split_metadata_terms`]).
+
+use super::sqlite::DbResult;
+use crate::models::MetadataField;
+use rusqlite::{params, Connection, OptionalExtension};
+
+/// Lists a conversation's metadata fields, by key.
+pub fn get_metadata(conn: &Connection, conversation_id: &str) -> DbResult<Vec<MetadataField>> {
+    let mut stmt = conn.prepare_cached(
+        "SELECT key, value, updated_at FROM conversation_metadata
+         WHERE conversation_id = ?1
+         ORDER BY key",
+    )?;
+    let fields = stmt.query_map([conversation_id], |row| {
+        Ok(MetadataField {
+            key: row.get(0)?,
+            value: row.get(1)?,
+            updated_at: row.get(2)?,
+        })
+    })?;
+    Ok(fields.collect::<Result<_, _>>()?)
+}
+
+/// Sets a metadata field of a conversation, or removes it when `value` is
+/// `None`. `key` must already be lowercase. Returns the field's previous
+/// value, if it had one.
+pub fn set_metadata(
+    conn: &Connection,
+    conversation_id: &str,
+    key: &str,
+    value: Option<&str>,
+) -> DbResult<Option<String>> {
+    let previous: Option<String> = conn
+        .query_row(
+            "SELECT value FROM conversation_metadata WHERE conversation_id = ?1 AND key = ?2",
+            params![conversation_id, key],
+            |row| row.get(0),
+        )
+        .optional()?;
+    match value {
+        Some(value) => {
+            conn.execute(
+                "INSERT INTO conversation_metadata (conversation_id, key, value, updated_at)
+                 VALUES (?1, ?2, ?3, ?4)
+                 ON CONFLICT(conversation_id, key) DO UPDATE SET
+                     value = excluded.value, updated_at = excluded.updated_at",
+                params![conversation_id, key, value, chrono::Utc::now().to_rfc3339()],
+            )?;
+        }
+        None => {
+            conn.execute(
+                "DELETE FROM conversation_metadata WHERE conversation_id = ?1 AND key = ?2",
+                params![conversation_id, key],
+            )?;
+        }
+    }
+    Ok(previous)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::filters::push_conversation_filters;
+    use crate::db::query::QueryBuilder;
+    use crate::db::sqlite::init_db;
+    use crate::models::ConversationFilters;
+    use std::collections::BTreeMap;
+
+    fn setup() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("PRAGMA foreign_keys = ON;").unwrap();
+        init_db(&conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO conversations (id, project_path, project_name, start_time, last_time, file_path, file_modified_at)
+             VALUES ('c1', '/p', 'p', '2025-01-15T10:00:00Z', '2025-01-15T10:30:00Z', '/a.jsonl', ''),
+                    ('c2', '/p', 'p', '2025-01-16T10:00:00Z', '2025-01-16T10:30:00Z', '/b.jsonl', '');",
+        )
+        .unwrap();
+        conn
+    }
+
+    fn matching(conn: &Connection, fields: &[(&str, &str)]) -> Vec<String> {
+        let filters = ConversationFilters {
+            metadata: Some(
+                fields
+                    .iter()
+                    .map(|(key, value)| (key.to_string(), value.to_string()))
+                    .collect::<BTreeMap<_, _>>(),
+            ),
+            ..Default::default()
+        };
+        let mut query = QueryBuilder::new("SELECT c.id FROM conversations c WHERE 1 = 1");
+        push_conversation_filters(conn, &filters, &mut query).unwrap();
+        query.push(" ORDER BY c.id");
+        let mut stmt = query.prepare(conn).unwrap();
+        stmt.query_map(query.params().as_slice(), |row| row.get(0))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_set_replace_and_remove_fields() {
+        let conn = setup();
+        assert_eq!(
+            set_metadata(&conn, "c1", "sprint", Some("41")).unwrap(),
+            None
+        );
+        assert_eq!(
+            set_metadata(&conn, "c1", "sprint", Some("42")).unwrap(),
+            Some("41".to_string())
+        );
+        set_metadata(&conn, "c1", "customer", Some("Acme")).unwrap();
+
+        let fields = get_metadata(&conn, "c1").unwrap();
+        let pairs: Vec<(&str, &str)> = fields
+            .iter()
+            .map(|field| (field.key.as_str(), field.value.as_str()))
+            .collect();
+        assert_eq!(pairs, vec![("customer", "Acme"), ("sprint", "42")]);
+
+        assert_eq!(
+            set_metadata(&conn, "c1", "sprint", None).unwrap(),
+            Some("42".to_string())
+        );
+        assert_eq!(set_metadata(&conn, "c1", "sprint", None).unwrap(), None);
+        assert_eq!(get_metadata(&conn, "c1").unwrap().len(), 1);
+
+        // Fields go with their conversation
+        conn.execute("DELETE FROM conversations WHERE id = 'c1'", [])
+            .unwrap();
+        assert!(get_metadata(&conn, "c1").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_filter_by_fields() {
+        let conn = setup();
+        set_metadata(&conn, "c1", "customer", Some("Acme")).unwrap();
+        set_metadata(&conn, "c1", "sprint", Some("42")).unwrap();
+        set_metadata(&conn, "c2", "customer", Some("globex")).unwrap();
+
+        assert_eq!(matching(&conn, &[("Customer", "acme")]), vec!["c1"]);
+        assert_eq!(
+            matching(&conn, &[("customer", "acme"), ("sprint", "42")]),
+            vec!["c1"]
+        );
+        assert!(matching(&conn, &[("customer", "globex"), ("sprint", "42")]).is_empty());
+        assert!(matching(&conn, &[("customer", "acm")]).is_empty());
+    }
+}
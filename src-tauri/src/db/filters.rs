@@ -0,0 +1,131 @@
+//! Conversation filter conditions for SQL queries.
+//!
+//! Conversation lists, bulk operations, facets, and webhooks all select
+//! conversations by `ConversationFilters`; they share these conditions so a
+//! filter means the same everywhere.
+
+use super::query::QueryBuilder;
+use super::settings::load_settings;
+use super::sqlite::DbResult;
+use crate::models::ConversationFilters;
+use crate::parser::{filter_boundary_millis, DateBoundary, FilterTimezone};
+use rusqlite::Connection;
+use tracing::warn;
+
+/// Appends `ConversationFilters` conditions on `c` (the `conversations`
+/// table) to a query.
+pub fn push_conversation_filters(
+    conn: &Connection,
+    filters: &ConversationFilters,
+    query: &mut QueryBuilder,
+) -> DbResult<()> {
+    // Add project filter
+    if let Some(ref project) = filters.project {
+        query.push_bind(" AND c.project_name = ?", project.clone());
+    }
+
+    // Add date range filters (resolved against the configured timezone)
+    push_date_filters(conn, filters, query)?;
+
+    // Add bookmarked filter
+    if let Some(bookmarked) = filters.bookmarked {
+        query.push(if bookmarked {
+            " AND EXISTS (SELECT 1 FROM bookmarks bm WHERE bm.conversation_id = c.id)"
+        } else {
+            " AND NOT EXISTS (SELECT 1 FROM bookmarks bm WHERE bm.conversation_id = c.id)"
+        });
+    }
+
+    // Add tags filter (must have ALL specified tags)
+    if let Some(ref tags) = filters.tags {
+        for tag in tags {
+            query.push_bind(
+                " AND EXISTS (SELECT 1 FROM conversation_tags ct WHERE ct.conversation_id = c.id AND ct.tag = ?)",
+                tag.clone(),
+            );
+        }
+    }
+
+    // Add archived filter
+    if let Some(archived) = filters.archived {
+        query.push(if archived {
+            " AND c.archived_at IS NOT NULL"
+        } else {
+            " AND c.archived_at IS NULL"
+        });
+    }
+
+    // Add protected filter
+    if let Some(protected) = filters.protected {
+        query.push(if protected {
+            " AND c.protected_at IS NOT NULL"
+        } else {
+            " AND c.protected_at IS NULL"
+        });
+    }
+
+    // Add resolution filter
+    if let Some(resolution) = filters.resolution {
+        query.push_bind(" AND c.resolution = ?", resolution.as_str());
+    }
+
+    // Add metadata filter (must have ALL specified fields)
+    if let Some(ref metadata) = filters.metadata {
+        for (key, value) in metadata {
+            query.push_bind(
+                " AND EXISTS (SELECT 1 FROM conversation_metadata cm WHERE cm.conversation_id = c.id AND cm.key = ?",
+                key.trim().to_lowercase(),
+            );
+            query.push_bind(" AND cm.value = ? COLLATE NOCASE)", value.trim().to_string());
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the timezone setting used for day-based filters.
+pub fn filter_timezone(conn: &Connection) -> DbResult<FilterTimezone> {
+    let settings = load_settings(conn)?;
+    Ok(
+        FilterTimezone::parse(&settings.timezone).unwrap_or_else(|| {
+            warn!(
+                "Unknown timezone setting {:?}, using local time",
+                settings.timezone
+            );
+            FilterTimezone::Local
+        }),
+    )
+}
+
+/// Appends date range conditions on `c.last_epoch` to a filter query.
+///
+/// Bare `YYYY-MM-DD` dates are expanded to whole days in the configured
+/// timezone, so "today" also covers chats from late in the local evening.
+/// Unparseable values are ignored rather than compared as strings.
+fn push_date_filters(
+    conn: &Connection,
+    filters: &ConversationFilters,
+    query: &mut QueryBuilder,
+) -> DbResult<()> {
+    if filters.date_start.is_none() && filters.date_end.is_none() {
+        return Ok(());
+    }
+
+    let tz = filter_timezone(conn)?;
+    let bounds = [
+        (&filters.date_start, DateBoundary::Start, ">="),
+        (&filters.date_end, DateBoundary::End, "<="),
+    ];
+
+    for (value, boundary, op) in bounds {
+        let Some(value) = value else { continue };
+        match filter_boundary_millis(value, tz, boundary) {
+            Some(epoch_ms) => {
+                query.push_bind(&format!(" AND c.last_epoch {} ?", op), epoch_ms);
+            }
+            None => warn!("Ignoring unparseable date filter: {:?}", value),
+        }
+    }
+
+    Ok(())
+}
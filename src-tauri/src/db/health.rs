@@ -0,0 +1,59 @@
+//! Database figures of the health report.
+
+use crate::db::sqlite::DbResult;
+use crate::models::DatabaseHealth;
+use crate::search::get_index_count;
+use rusqlite::Connection;
+
+/// Measures the database: its size, unfinished ingests, and the number of
+/// conversations against the rows of their full-text index.
+pub fn database_health(conn: &Connection) -> DbResult<DatabaseHealth> {
+    let page_count: u64 = conn.query_row("PRAGMA page_count", [], |row| row.get(0))?;
+    let page_size: u64 = conn.query_row("PRAGMA page_size", [], |row| row.get(0))?;
+    let pending_files: usize = conn.query_row(
+        "SELECT COUNT(*) FROM file_metadata WHERE status != 'done'",
+        [],
+        |row| row.get(0),
+    )?;
+    let conversations: usize =
+        conn.query_row("SELECT COUNT(*) FROM conversations", [], |row| row.get(0))?;
+
+    Ok(DatabaseHealth {
+        size_bytes: page_count * page_size,
+        pending_files,
+        conversations,
+        indexed_conversations: get_index_count(conn)? as usize,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::metadata::{set_ingest_status, update_file_metadata, IngestStatus};
+    use crate::db::sqlite::init_db;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_database_health_counts_pending_files_and_index_rows() {
+        let conn = Connection::open_in_memory().unwrap();
+        init_db(&conn).unwrap();
+        conn.execute(
+            "INSERT INTO conversations (id, project_path, project_name, start_time, last_time, file_path, file_modified_at)
+             VALUES ('c1', '/p', 'p', '', '', '/a.jsonl', '')",
+            [],
+        )
+        .unwrap();
+        let files = [PathBuf::from("/a.jsonl"), PathBuf::from("/b.jsonl")];
+        for file in &files {
+            update_file_metadata(&conn, file, "2025-01-15T10:00:00Z").unwrap();
+        }
+        set_ingest_status(&conn, &files[1..], IngestStatus::Pending).unwrap();
+
+        let health = database_health(&conn).unwrap();
+        assert!(health.size_bytes > 0);
+        assert_eq!(health.pending_files, 1);
+        assert_eq!(health.conversations, 1);
+        // The conversation was never indexed
+        assert_eq!(health.indexed_conversations, 0);
+    }
+}
@@ -0,0 +1,621 @@
+//! Conversation identity maintenance.
+//!
+//! Bookmarks, tags, metadata fields, reminders, read positions, sub-agent
+//! links, and usage statistics reference conversations by ID, so whenever
+//! an ID changes they have to move with it. Conversation IDs are keyed on
+//! session, so a moved session file maps back onto its existing
+//! conversation; `reconcile_moved_files` updates the stored paths.
+
+use crate::db::metadata::remove_stale_metadata;
+use crate::db::migrations::column_exists;
+use crate::db::sqlite::DbResult;
+use crate::parser::jsonl::{agent_file_stem, DEFAULT_SESSION_ID};
+use crate::search::index::delete_index_entry;
+use rusqlite::{params, Connection, OptionalExtension};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use tracing::{debug, info};
+
+/// Outcome of a reconciliation pass.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReconcileStats {
+    /// Conversations re-linked to a session file at a new location.
+    pub relinked: usize,
+    /// Conversations whose session file could not be found anywhere.
+    pub orphaned: usize,
+}
+
+/// Re-links conversations whose session file was moved or renamed.
+///
+/// Claude Code names session files after their session ID, so a missing
+/// file is matched to a discovered file whose stem is the conversation's
+/// session ID. Also drops file metadata for paths that no longer exist so
+/// the new locations are picked up by the next scan.
+///
+/// # Arguments
+/// * `conn` - Database connection
+/// * `discovered_files` - All JSONL files currently on disk
+pub fn reconcile_moved_files(
+    conn: &Connection,
+    discovered_files: &[PathBuf],
+) -> DbResult<ReconcileStats> {
+    let existing: HashSet<String> = discovered_files
+        .iter()
+        .map(|p| p.to_string_lossy().to_string())
+        .collect();
+    let by_stem: HashMap<String, &PathBuf> = discovered_files
+        .iter()
+        .filter_map(|p| Some((p.file_stem()?.to_string_lossy().to_string(), p)))
+        .collect();
+
+    let rows: Vec<(String, String, String)> = {
+        let mut stmt = conn.prepare("SELECT id, file_path, session_id FROM conversations")?;
+        let mapped = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?;
+        mapped.collect::<Result<_, _>>()?
+    };
+
+    let mut stats = ReconcileStats::default();
+    for (id, file_path, session_id) in rows {
+        if existing.contains(&file_path) {
+            continue;
+        }
+
+        // Sub-agent transcripts aren't named after the session ID they carry
+        let new_path = if session_id.is_empty()
+            || session_id == DEFAULT_SESSION_ID
+            || agent_file_stem(Path::new(&file_path)).is_some()
+        {
+            None
+        } else {
+            by_stem.get(&session_id)
+        };
+
+        match new_path {
+            Some(new_path) => {
+                conn.execute(
+                    "UPDATE conversations SET file_path = ?1 WHERE id = ?2",
+                    params![new_path.to_string_lossy(), id],
+                )?;
+                debug!("Re-linked conversation {} from {} to {:?}", id, file_path, new_path);
+                stats.relinked += 1;
+            }
+            None => stats.orphaned += 1,
+        }
+    }
+
+    remove_stale_metadata(conn, discovered_files)?;
+
+    if stats.relinked > 0 || stats.orphaned > 0 {
+        info!(
+            "Reconciled moved files: {} re-linked, {} orphaned",
+            stats.relinked, stats.orphaned
+        );
+    }
+    Ok(stats)
+}
+
+/// Moves a conversation and its bookmarks, tags, and usage from `old_id` to `new_id`.
+///
+/// If `new_id` already exists, the annotations are merged into it and the
+/// old row (and its search index entry) is removed instead.
+///
+/// The deletion tombstone, share bundles, and saved prompts aren't tied to
+/// the row, so they follow even when there's no conversation to remap: a
+/// conversation the user deleted stays deleted under its new ID.
+///
+/// Must run inside a transaction: foreign keys are deferred until commit so
+/// parent and child rows can be renamed in any order.
+///
+/// # Returns
+/// * `true` - If a conversation with `old_id` existed and was remapped
+/// * `false` - If there was nothing to remap
+pub fn remap_conversation_id(conn: &Connection, old_id: &str, new_id: &str) -> DbResult<bool> {
+    if old_id == new_id {
+        return Ok(false);
+    }
+
+    conn.execute(
+        r#"
+        INSERT OR IGNORE INTO deleted_conversations (conversation_id, deleted_at)
+        SELECT ?2, deleted_at FROM deleted_conversations WHERE conversation_id = ?1
+        "#,
+        params![old_id, new_id],
+    )?;
+    conn.execute("DELETE FROM deleted_conversations WHERE conversation_id = ?1", [old_id])?;
+    conn.execute(
+        "UPDATE share_bundles SET conversation_id = ?2 WHERE conversation_id = ?1",
+        params![old_id, new_id],
+    )?;
+    conn.execute(
+        "UPDATE prompts SET source_conversation_id = ?2 WHERE source_conversation_id = ?1",
+        params![old_id, new_id],
+    )?;
+
+    let old_rowid: Option<i64> = conn
+        .query_row(
+            "SELECT rowid FROM conversations WHERE id = ?1",
+            [old_id],
+            |row| row.get(0),
+        )
+        .optional()?;
+    let Some(old_rowid) = old_rowid else {
+        return Ok(false);
+    };
+
+    let target_exists = conn
+        .query_row(
+            "SELECT 1 FROM conversations WHERE id = ?1",
+            [new_id],
+            |_| Ok(()),
+        )
+        .optional()?
+        .is_some();
+
+    conn.execute_batch("PRAGMA defer_foreign_keys = ON;")?;
+
+    // Sub-agent transcripts nested under the conversation follow it
+    conn.execute(
+        "UPDATE conversation_children SET parent_id = ?2 WHERE parent_id = ?1",
+        params![old_id, new_id],
+    )?;
+
+    if target_exists {
+        conn.execute(
+            r#"
+            INSERT OR IGNORE INTO bookmarks (conversation_id, created_at)
+            SELECT ?2, created_at FROM bookmarks WHERE conversation_id = ?1
+            "#,
+            params![old_id, new_id],
+        )?;
+        conn.execute(
+            r#"
+            INSERT OR IGNORE INTO conversation_tags (conversation_id, tag, created_at)
+            SELECT ?2, tag, created_at FROM conversation_tags WHERE conversation_id = ?1
+            "#,
+            params![old_id, new_id],
+        )?;
+        // Metadata fields the target already has keep its values
+        conn.execute(
+            r#"
+            INSERT OR IGNORE INTO conversation_metadata (conversation_id, key, value, updated_at)
+            SELECT ?2, key, value, updated_at FROM conversation_metadata WHERE conversation_id = ?1
+            "#,
+            params![old_id, new_id],
+        )?;
+        // A reminder the target already has wins
+        conn.execute(
+            r#"
+            INSERT OR IGNORE INTO reminders (conversation_id, due_at, note, created_at, notified_at)
+            SELECT ?2, due_at, note, created_at, notified_at FROM reminders WHERE conversation_id = ?1
+            "#,
+            params![old_id, new_id],
+        )?;
+        // So does where the target was read up to
+        conn.execute(
+            r#"
+            INSERT OR IGNORE INTO read_positions (conversation_id, message_id, updated_at)
+            SELECT ?2, message_id, updated_at FROM read_positions WHERE conversation_id = ?1
+            "#,
+            params![old_id, new_id],
+        )?;
+        // Issue links the target already has win
+        conn.execute(
+            r#"
+            INSERT OR IGNORE INTO conversation_issues (conversation_id, issue_key, url, source, created_at)
+            SELECT ?2, issue_key, url, source, created_at FROM conversation_issues WHERE conversation_id = ?1
+            "#,
+            params![old_id, new_id],
+        )?;
+        // Images it showed stay stored for the target
+        conn.execute(
+            r#"
+            INSERT OR IGNORE INTO attachment_refs (attachment_id, conversation_id)
+            SELECT attachment_id, ?2 FROM attachment_refs WHERE conversation_id = ?1
+            "#,
+            params![old_id, new_id],
+        )?;
+        // And the parent it's nested under
+        conn.execute(
+            r#"
+            INSERT OR IGNORE INTO conversation_children (child_id, parent_id, source, created_at)
+            SELECT ?2, parent_id, source, created_at FROM conversation_children WHERE child_id = ?1
+            "#,
+            params![old_id, new_id],
+        )?;
+        // Protection survives the merge (databases migrating from before
+        // v10 don't have the column yet)
+        if column_exists(conn, "conversations", "protected_at")? {
+            conn.execute(
+                r#"
+                UPDATE conversations
+                SET protected_at = COALESCE(protected_at, (SELECT protected_at FROM conversations WHERE id = ?1))
+                WHERE id = ?2
+                "#,
+                params![old_id, new_id],
+            )?;
+        }
+        delete_index_entry(conn, old_rowid)?;
+        conn.execute("DELETE FROM bookmarks WHERE conversation_id = ?1", [old_id])?;
+        conn.execute("DELETE FROM conversation_tags WHERE conversation_id = ?1", [old_id])?;
+        conn.execute("DELETE FROM conversation_metadata WHERE conversation_id = ?1", [old_id])?;
+        conn.execute("DELETE FROM reminders WHERE conversation_id = ?1", [old_id])?;
+        conn.execute("DELETE FROM read_positions WHERE conversation_id = ?1", [old_id])?;
+        conn.execute("DELETE FROM conversation_children WHERE child_id = ?1", [old_id])?;
+        conn.execute("DELETE FROM conversation_issues WHERE conversation_id = ?1", [old_id])?;
+        conn.execute("DELETE FROM attachment_refs WHERE conversation_id = ?1", [old_id])?;
+        // Usage is re-recorded for the target whenever its file is ingested
+        conn.execute("DELETE FROM usage_daily WHERE conversation_id = ?1", [old_id])?;
+        conn.execute("DELETE FROM tool_usage WHERE conversation_id = ?1", [old_id])?;
+        conn.execute("DELETE FROM language_usage WHERE conversation_id = ?1", [old_id])?;
+        conn.execute("DELETE FROM conversations WHERE id = ?1", [old_id])?;
+    } else {
+        conn.execute(
+            "UPDATE bookmarks SET conversation_id = ?2 WHERE conversation_id = ?1",
+            params![old_id, new_id],
+        )?;
+        conn.execute(
+            "UPDATE conversation_tags SET conversation_id = ?2 WHERE conversation_id = ?1",
+            params![old_id, new_id],
+        )?;
+        conn.execute(
+            "UPDATE conversation_metadata SET conversation_id = ?2 WHERE conversation_id = ?1",
+            params![old_id, new_id],
+        )?;
+        conn.execute(
+            "UPDATE reminders SET conversation_id = ?2 WHERE conversation_id = ?1",
+            params![old_id, new_id],
+        )?;
+        conn.execute(
+            "UPDATE read_positions SET conversation_id = ?2 WHERE conversation_id = ?1",
+            params![old_id, new_id],
+        )?;
+        conn.execute(
+            "UPDATE usage_daily SET conversation_id = ?2 WHERE conversation_id = ?1",
+            params![old_id, new_id],
+        )?;
+        conn.execute(
+            "UPDATE tool_usage SET conversation_id = ?2 WHERE conversation_id = ?1",
+            params![old_id, new_id],
+        )?;
+        conn.execute(
+            "UPDATE language_usage SET conversation_id = ?2 WHERE conversation_id = ?1",
+            params![old_id, new_id],
+        )?;
+        conn.execute(
+            "UPDATE user_questions SET conversation_id = ?2 WHERE conversation_id = ?1",
+            params![old_id, new_id],
+        )?;
+        conn.execute(
+            "UPDATE message_uuids SET conversation_id = ?2 WHERE conversation_id = ?1",
+            params![old_id, new_id],
+        )?;
+        conn.execute(
+            "UPDATE continuation_refs SET conversation_id = ?2 WHERE conversation_id = ?1",
+            params![old_id, new_id],
+        )?;
+        conn.execute(
+            "UPDATE conversation_issues SET conversation_id = ?2 WHERE conversation_id = ?1",
+            params![old_id, new_id],
+        )?;
+        conn.execute(
+            "UPDATE attachment_refs SET conversation_id = ?2 WHERE conversation_id = ?1",
+            params![old_id, new_id],
+        )?;
+        conn.execute(
+            "UPDATE conversation_children SET child_id = ?2 WHERE child_id = ?1",
+            params![old_id, new_id],
+        )?;
+        conn.execute(
+            "UPDATE conversations SET id = ?2 WHERE id = ?1",
+            params![old_id, new_id],
+        )?;
+    }
+    // A conversation merged with its parent isn't nested under itself
+    conn.execute(
+        "DELETE FROM conversation_children WHERE child_id = ?1 AND parent_id = ?1",
+        [new_id],
+    )?;
+
+    debug!("Remapped conversation {} -> {}", old_id, new_id);
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::sqlite::init_db;
+
+    fn setup() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("PRAGMA foreign_keys = ON;").unwrap();
+        init_db(&conn).unwrap();
+        conn
+    }
+
+    fn insert_conversation(conn: &Connection, id: &str) {
+        conn.execute(
+            r#"INSERT INTO conversations (id, project_path, project_name, start_time, last_time, file_path, file_modified_at)
+            VALUES (?1, '/p', 'p', '2025-01-01T00:00:00Z', '2025-01-01T00:00:00Z', '/f.jsonl', '')"#,
+            [id],
+        )
+        .unwrap();
+    }
+
+    fn annotate(conn: &Connection, id: &str, tag: &str) {
+        conn.execute(
+            "INSERT OR IGNORE INTO bookmarks (conversation_id, created_at) VALUES (?1, 'now')",
+            [id],
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO conversation_tags (conversation_id, tag, created_at) VALUES (?1, ?2, 'now')",
+            params![id, tag],
+        )
+        .unwrap();
+    }
+
+    fn tags_for(conn: &Connection, id: &str) -> Vec<String> {
+        let mut stmt = conn
+            .prepare("SELECT tag FROM conversation_tags WHERE conversation_id = ?1 ORDER BY tag")
+            .unwrap();
+        stmt.query_map([id], |row| row.get(0))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_remap_moves_annotations() {
+        let conn = setup();
+        insert_conversation(&conn, "old");
+        annotate(&conn, "old", "rust");
+        conn.execute(
+            "INSERT INTO reminders (conversation_id, due_at, created_at) VALUES ('old', 1, 'now')",
+            [],
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO read_positions (conversation_id, message_id, updated_at) VALUES ('old', 'm1', 'now')",
+            [],
+        )
+        .unwrap();
+        insert_conversation(&conn, "agent");
+        conn.execute(
+            "INSERT INTO conversation_children (child_id, parent_id, source, created_at) VALUES ('agent', 'old', 'attached', 'now')",
+            [],
+        )
+        .unwrap();
+
+        let tx = conn.unchecked_transaction().unwrap();
+        assert!(remap_conversation_id(&tx, "old", "new").unwrap());
+        tx.commit().unwrap();
+
+        let bookmarked: i64 = conn
+            .query_row("SELECT COUNT(*) FROM bookmarks WHERE conversation_id = 'new'", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(bookmarked, 1);
+        assert_eq!(tags_for(&conn, "new"), vec!["rust"]);
+        assert!(tags_for(&conn, "old").is_empty());
+        let reminded: String = conn
+            .query_row("SELECT conversation_id FROM reminders", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(reminded, "new");
+        let read: String = conn
+            .query_row("SELECT conversation_id FROM read_positions", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(read, "new");
+        let parent: String = conn
+            .query_row("SELECT parent_id FROM conversation_children", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(parent, "new");
+    }
+
+    #[test]
+    fn test_remap_merges_into_existing_target() {
+        let conn = setup();
+        insert_conversation(&conn, "old");
+        insert_conversation(&conn, "new");
+        annotate(&conn, "old", "rust");
+        annotate(&conn, "new", "async");
+        conn.execute_batch(
+            "INSERT INTO conversation_metadata (conversation_id, key, value, updated_at)
+             VALUES ('old', 'customer', 'acme', 'then'), ('old', 'sprint', '41', 'then'),
+                    ('new', 'sprint', '42', 'now');",
+        )
+        .unwrap();
+
+        let tx = conn.unchecked_transaction().unwrap();
+        assert!(remap_conversation_id(&tx, "old", "new").unwrap());
+        tx.commit().unwrap();
+
+        let count: i64 = conn
+            .query_row("SELECT COUNT(*) FROM conversations", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(count, 1);
+        assert_eq!(tags_for(&conn, "new"), vec!["async", "rust"]);
+        let metadata: Vec<(String, String)> = conn
+            .prepare("SELECT conversation_id, key || '=' || value FROM conversation_metadata ORDER BY key")
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(
+            metadata,
+            vec![
+                ("new".to_string(), "customer=acme".to_string()),
+                ("new".to_string(), "sprint=42".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_remap_merge_keeps_protection() {
+        let conn = setup();
+        insert_conversation(&conn, "old");
+        insert_conversation(&conn, "new");
+        conn.execute("UPDATE conversations SET protected_at = 'then' WHERE id = 'old'", [])
+            .unwrap();
+
+        let tx = conn.unchecked_transaction().unwrap();
+        assert!(remap_conversation_id(&tx, "old", "new").unwrap());
+        tx.commit().unwrap();
+
+        let protected_at: Option<String> = conn
+            .query_row("SELECT protected_at FROM conversations WHERE id = 'new'", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(protected_at.as_deref(), Some("then"));
+    }
+
+    #[test]
+    fn test_remap_moves_attachment_refs() {
+        let conn = setup();
+        insert_conversation(&conn, "old");
+        insert_conversation(&conn, "merged");
+        insert_conversation(&conn, "target");
+        conn.execute_batch(
+            "INSERT INTO attachments (id, media_type, data, size, created_at)
+             VALUES ('img-1', 'image/png', 'aGk=', 2, 'now'), ('img-2', 'image/png', 'aGk=', 2, 'now');
+             INSERT INTO attachment_refs (attachment_id, conversation_id)
+             VALUES ('img-1', 'old'), ('img-2', 'merged'), ('img-1', 'target');",
+        )
+        .unwrap();
+
+        // Renamed, then merged into a conversation sharing one of its images
+        let tx = conn.unchecked_transaction().unwrap();
+        assert!(remap_conversation_id(&tx, "old", "new").unwrap());
+        tx.commit().unwrap();
+        let tx = conn.unchecked_transaction().unwrap();
+        assert!(remap_conversation_id(&tx, "merged", "target").unwrap());
+        tx.commit().unwrap();
+
+        let refs: Vec<(String, String)> = conn
+            .prepare("SELECT attachment_id, conversation_id FROM attachment_refs ORDER BY 1, 2")
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(
+            refs,
+            vec![
+                ("img-1".to_string(), "new".to_string()),
+                ("img-1".to_string(), "target".to_string()),
+                ("img-2".to_string(), "target".to_string()),
+            ]
+        );
+        let attachments: i64 = conn
+            .query_row("SELECT COUNT(*) FROM attachments", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(attachments, 2);
+    }
+
+    #[test]
+    fn test_remap_moves_issue_links() {
+        let conn = setup();
+        insert_conversation(&conn, "old");
+        insert_conversation(&conn, "merged");
+        insert_conversation(&conn, "target");
+        conn.execute_batch(
+            "INSERT INTO conversation_issues (conversation_id, issue_key, url, source, created_at)
+             VALUES ('old', 'ENG-42', NULL, 'detected', 'then'),
+                    ('merged', 'ENG-7', 'https://linear.app/t/issue/ENG-7', 'linked', 'then'),
+                    ('merged', 'ENG-42', NULL, 'detected', 'then'),
+                    ('target', 'ENG-42', 'https://linear.app/t/issue/ENG-42', 'linked', 'now');",
+        )
+        .unwrap();
+
+        let tx = conn.unchecked_transaction().unwrap();
+        assert!(remap_conversation_id(&tx, "old", "new").unwrap());
+        tx.commit().unwrap();
+        let tx = conn.unchecked_transaction().unwrap();
+        assert!(remap_conversation_id(&tx, "merged", "target").unwrap());
+        tx.commit().unwrap();
+
+        let issues: Vec<(String, String, String)> = conn
+            .prepare("SELECT conversation_id, issue_key, source FROM conversation_issues ORDER BY 1, 2")
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(
+            issues,
+            vec![
+                ("new".to_string(), "ENG-42".to_string(), "detected".to_string()),
+                ("target".to_string(), "ENG-42".to_string(), "linked".to_string()),
+                ("target".to_string(), "ENG-7".to_string(), "linked".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_remap_moves_tombstone_shares_and_prompt_sources() {
+        let conn = setup();
+        insert_conversation(&conn, "old");
+        conn.execute_batch(
+            "INSERT INTO deleted_conversations (conversation_id, deleted_at) VALUES ('gone', 'then');
+             INSERT INTO share_bundles (id, conversation_id, path, created_at, expires_at)
+             VALUES ('share-1', 'old', '/shares/share-1', 'now', 0);
+             INSERT INTO prompts (title, body, source_conversation_id, source_message_id, created_at, updated_at)
+             VALUES ('Review', 'Review this', 'old', 'm1', 'now', 'now'),
+                    ('Plan', 'Plan this', 'gone', 'm2', 'now', 'now');",
+        )
+        .unwrap();
+
+        let tx = conn.unchecked_transaction().unwrap();
+        assert!(remap_conversation_id(&tx, "old", "new").unwrap());
+        // Deleted: no row to remap, but it must stay deleted
+        assert!(!remap_conversation_id(&tx, "gone", "gone-new").unwrap());
+        tx.commit().unwrap();
+
+        let deleted: String = conn
+            .query_row("SELECT conversation_id FROM deleted_conversations", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(deleted, "gone-new");
+        let shared: String = conn
+            .query_row("SELECT conversation_id FROM share_bundles", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(shared, "new");
+        let sources: Vec<String> = conn
+            .prepare("SELECT source_conversation_id FROM prompts ORDER BY id")
+            .unwrap()
+            .query_map([], |r| r.get(0))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(sources, vec!["new", "gone-new"]);
+    }
+
+    #[test]
+    fn test_reconcile_relinks_moved_session_file() {
+        let conn = setup();
+        conn.execute(
+            r#"INSERT INTO conversations (id, project_path, project_name, start_time, last_time, file_path, file_modified_at, session_id)
+            VALUES ('moved', '/p', 'p', '', '', '/old/abc-123.jsonl', '', 'abc-123'),
+                   ('gone', '/p', 'p', '', '', '/old/def-456.jsonl', '', 'def-456'),
+                   ('here', '/p', 'p', '', '', '/new/ghi-789.jsonl', '', 'ghi-789')"#,
+            [],
+        )
+        .unwrap();
+
+        let discovered = vec![
+            PathBuf::from("/new/abc-123.jsonl"),
+            PathBuf::from("/new/ghi-789.jsonl"),
+        ];
+        let stats = reconcile_moved_files(&conn, &discovered).unwrap();
+
+        assert_eq!(stats, ReconcileStats { relinked: 1, orphaned: 1 });
+        let path: String = conn
+            .query_row("SELECT file_path FROM conversations WHERE id = 'moved'", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(path, "/new/abc-123.jsonl");
+    }
+
+    #[test]
+    fn test_remap_missing_conversation_is_noop() {
+        let conn = setup();
+        assert!(!remap_conversation_id(&conn, "missing", "new").unwrap());
+        assert!(!remap_conversation_id(&conn, "same", "same").unwrap());
+    }
+}
@@ -0,0 +1,155 @@
+//! Links between conversations and Jira or Linear issues.
+//!
+//! A conversation is linked to an issue either by the user, with the
+//! issue's URL, or at ingest when the issue key is mentioned in its text
+//! (see [`crate::ingest::detect_issue_keys`]). Detected links are replaced
+//! on every ingest of the conversation; links made by the user are kept.
+
+use crate::db::sqlite::DbResult;
+use crate::models::{IssueLink, IssueSource};
+use rusqlite::{params, Connection, OptionalExtension};
+
+/// Links a conversation to an issue by URL, replacing a detected link to
+/// the same issue.
+///
+/// Returns `None` if the conversation doesn't exist.
+pub fn link_issue(
+    conn: &Connection,
+    conversation_id: &str,
+    key: &str,
+    url: &str,
+) -> DbResult<Option<IssueLink>> {
+    let exists = conn
+        .query_row(
+            "SELECT 1 FROM conversations WHERE id = ?1",
+            [conversation_id],
+            |_| Ok(()),
+        )
+        .optional()?
+        .is_some();
+    if !exists {
+        return Ok(None);
+    }
+    conn.execute(
+        "INSERT INTO conversation_issues (conversation_id, issue_key, url, source, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5)
+         ON CONFLICT(conversation_id, issue_key) DO UPDATE SET
+             url = excluded.url,
+             source = excluded.source",
+        params![
+            conversation_id,
+            key,
+            url,
+            IssueSource::Linked.as_str(),
+            chrono::Utc::now().to_rfc3339()
+        ],
+    )?;
+    let link = conn.query_row(
+        "SELECT issue_key, url, source, created_at FROM conversation_issues
+         WHERE conversation_id = ?1 AND issue_key = ?2",
+        [conversation_id, key],
+        link_from_row,
+    )?;
+    Ok(Some(link))
+}
+
+/// Replaces the issues detected in a conversation.
+pub fn replace_detected_issues(
+    conn: &Connection,
+    conversation_id: &str,
+    keys: &[String],
+) -> DbResult<()> {
+    conn.prepare_cached(
+        "DELETE FROM conversation_issues WHERE conversation_id = ?1 AND source = ?2",
+    )?
+    .execute([conversation_id, IssueSource::Detected.as_str()])?;
+    let now = chrono::Utc::now().to_rfc3339();
+    let mut insert = conn.prepare_cached(
+        "INSERT OR IGNORE INTO conversation_issues
+             (conversation_id, issue_key, url, source, created_at)
+         VALUES (?1, ?2, NULL, ?3, ?4)",
+    )?;
+    for key in keys {
+        insert.execute(params![
+            conversation_id,
+            key,
+            IssueSource::Detected.as_str(),
+            now
+        ])?;
+    }
+    Ok(())
+}
+
+/// Issues a conversation is linked to, by key.
+pub fn issues_for_conversation(
+    conn: &Connection,
+    conversation_id: &str,
+) -> DbResult<Vec<IssueLink>> {
+    let mut stmt = conn.prepare(
+        "SELECT issue_key, url, source, created_at FROM conversation_issues
+         WHERE conversation_id = ?1
+         ORDER BY issue_key",
+    )?;
+    let links = stmt.query_map([conversation_id], link_from_row)?;
+    Ok(links.collect::<Result<_, _>>()?)
+}
+
+fn link_from_row(row: &rusqlite::Row) -> rusqlite::Result<IssueLink> {
+    let source: String = row.get(2)?;
+    Ok(IssueLink {
+        key: row.get(0)?,
+        url: row.get(1)?,
+        source: IssueSource::from_db(&source).unwrap_or(IssueSource::Detected),
+        created_at: row.get(3)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::sqlite::init_db;
+
+    fn setup() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        init_db(&conn).unwrap();
+        conn.execute(
+            "INSERT INTO conversations
+                 (id, project_path, project_name, start_time, last_time, file_path, file_modified_at)
+             VALUES ('c1', '/p', 'p', '2025-01-15T10:00:00Z', '2025-01-15T10:00:00Z', '/p/c1.jsonl', '')",
+            [],
+        )
+        .unwrap();
+        conn
+    }
+
+    fn keys(links: &[IssueLink]) -> Vec<(&str, IssueSource)> {
+        links.iter().map(|l| (l.key.as_str(), l.source)).collect()
+    }
+
+    #[test]
+    fn test_detected_issues_are_replaced_but_links_kept() {
+        let conn = setup();
+        replace_detected_issues(&conn, "c1", &["ABC-1".to_string(), "ABC-2".to_string()]).unwrap();
+        let link = link_issue(&conn, "c1", "ABC-2", "https://linear.app/acme/issue/ABC-2")
+            .unwrap()
+            .unwrap();
+        assert_eq!(link.source, IssueSource::Linked);
+        assert_eq!(
+            link.url.as_deref(),
+            Some("https://linear.app/acme/issue/ABC-2")
+        );
+
+        replace_detected_issues(&conn, "c1", &["ABC-3".to_string(), "ABC-2".to_string()]).unwrap();
+        assert_eq!(
+            link_issue(&conn, "c2", "ABC-2", "https://x.test/ABC-2").unwrap(),
+            None
+        );
+        assert_eq!(
+            keys(&issues_for_conversation(&conn, "c1").unwrap()),
+            vec![
+                ("ABC-2", IssueSource::Linked),
+                ("ABC-3", IssueSource::Detected)
+            ]
+        );
+    }
+}
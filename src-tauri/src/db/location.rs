@@ -0,0 +1,177 @@
+//! Database file location.
+//!
+//! The database normally lives in the app data directory. It can be moved,
+//! in order of precedence:
+//! 1. with the `CLAUDE_HISTORY_DB` environment variable,
+//! 2. by placing a `portable` marker file next to the executable, which keeps
+//!    the database next to the executable (e.g. on an external drive),
+//! 3. with the `databasePath` setting.
+//!
+//! The setting can't live in the database it points to, so it is stored in
+//! a `database-location` file in the app data directory.
+
+use crate::db::sqlite::DbResult;
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::debug;
+
+/// Environment variable overriding the database path.
+pub const DB_PATH_ENV: &str = "CLAUDE_HISTORY_DB";
+
+/// File name of the database inside a data directory.
+pub const DB_FILE_NAME: &str = "conversations.db";
+
+/// Marker file next to the executable that enables portable mode.
+pub const PORTABLE_MARKER: &str = "portable";
+
+/// File in the app data directory holding the `databasePath` setting.
+const LOCATION_FILE_NAME: &str = "database-location";
+
+/// Resolves the database path from the environment, portable marker,
+/// location setting, and default location, in that order.
+pub fn resolve_db_path(app_data_dir: &Path) -> PathBuf {
+    let exe_dir = std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(Path::to_path_buf));
+    resolve_db_path_from(std::env::var_os(DB_PATH_ENV), exe_dir.as_deref(), app_data_dir)
+}
+
+fn resolve_db_path_from(
+    env_path: Option<OsString>,
+    exe_dir: Option<&Path>,
+    app_data_dir: &Path,
+) -> PathBuf {
+    if let Some(path) = env_path.filter(|p| !p.is_empty()) {
+        debug!("Using database path from {}", DB_PATH_ENV);
+        return database_file(PathBuf::from(path));
+    }
+
+    if let Some(exe_dir) = exe_dir.filter(|dir| dir.join(PORTABLE_MARKER).is_file()) {
+        debug!("Portable mode: using database next to the executable");
+        return exe_dir.join(DB_FILE_NAME);
+    }
+
+    if let Some(path) = read_location_setting(app_data_dir) {
+        debug!("Using database path from the location setting");
+        return database_file(path);
+    }
+
+    app_data_dir.join(DB_FILE_NAME)
+}
+
+/// Treats existing directories as the directory to keep the database in.
+fn database_file(path: PathBuf) -> PathBuf {
+    if path.is_dir() {
+        path.join(DB_FILE_NAME)
+    } else {
+        path
+    }
+}
+
+/// Reads the `databasePath` setting, if set.
+pub fn read_location_setting(app_data_dir: &Path) -> Option<PathBuf> {
+    let contents = fs::read_to_string(app_data_dir.join(LOCATION_FILE_NAME)).ok()?;
+    let path = contents.trim();
+    (!path.is_empty()).then(|| PathBuf::from(path))
+}
+
+/// Saves the `databasePath` setting, or clears it when `path` is `None`.
+///
+/// Takes effect the next time the database is opened.
+pub fn write_location_setting(app_data_dir: &Path, path: Option<&Path>) -> DbResult<()> {
+    let location_file = app_data_dir.join(LOCATION_FILE_NAME);
+    match path {
+        Some(path) => {
+            fs::create_dir_all(app_data_dir)?;
+            fs::write(location_file, path.to_string_lossy().as_bytes())?;
+        }
+        None => {
+            if location_file.exists() {
+                fs::remove_file(location_file)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_default_location() {
+        let app_data = tempdir().unwrap();
+        assert_eq!(
+            resolve_db_path_from(None, None, app_data.path()),
+            app_data.path().join(DB_FILE_NAME)
+        );
+    }
+
+    #[test]
+    fn test_env_takes_precedence() {
+        let app_data = tempdir().unwrap();
+        let exe_dir = tempdir().unwrap();
+        fs::write(exe_dir.path().join(PORTABLE_MARKER), "").unwrap();
+        write_location_setting(app_data.path(), Some(Path::new("/elsewhere/db.sqlite"))).unwrap();
+
+        let path = resolve_db_path_from(
+            Some(OsString::from("/custom/history.db")),
+            Some(exe_dir.path()),
+            app_data.path(),
+        );
+        assert_eq!(path, PathBuf::from("/custom/history.db"));
+
+        // An empty variable is ignored
+        let path = resolve_db_path_from(Some(OsString::new()), Some(exe_dir.path()), app_data.path());
+        assert_eq!(path, exe_dir.path().join(DB_FILE_NAME));
+    }
+
+    #[test]
+    fn test_env_directory_gets_default_file_name() {
+        let app_data = tempdir().unwrap();
+        let data_dir = tempdir().unwrap();
+
+        let path = resolve_db_path_from(
+            Some(data_dir.path().as_os_str().to_owned()),
+            None,
+            app_data.path(),
+        );
+        assert_eq!(path, data_dir.path().join(DB_FILE_NAME));
+    }
+
+    #[test]
+    fn test_portable_marker_requires_file() {
+        let app_data = tempdir().unwrap();
+        let exe_dir = tempdir().unwrap();
+
+        let path = resolve_db_path_from(None, Some(exe_dir.path()), app_data.path());
+        assert_eq!(path, app_data.path().join(DB_FILE_NAME));
+
+        fs::write(exe_dir.path().join(PORTABLE_MARKER), "").unwrap();
+        let path = resolve_db_path_from(None, Some(exe_dir.path()), app_data.path());
+        assert_eq!(path, exe_dir.path().join(DB_FILE_NAME));
+    }
+
+    #[test]
+    fn test_location_setting_round_trip() {
+        let app_data = tempdir().unwrap();
+        assert!(read_location_setting(app_data.path()).is_none());
+
+        write_location_setting(app_data.path(), Some(Path::new("/mnt/drive/history.db"))).unwrap();
+        assert_eq!(
+            read_location_setting(app_data.path()),
+            Some(PathBuf::from("/mnt/drive/history.db"))
+        );
+        assert_eq!(
+            resolve_db_path_from(None, None, app_data.path()),
+            PathBuf::from("/mnt/drive/history.db")
+        );
+
+        write_location_setting(app_data.path(), None).unwrap();
+        assert!(read_location_setting(app_data.path()).is_none());
+        // Clearing an unset location is fine
+        write_location_setting(app_data.path(), None).unwrap();
+    }
+}
@@ -2,8 +2,20 @@
 //!
 //! This module provides functions to track file modification times,
 //! enabling efficient incremental parsing that only processes changed files.
+//!
+//! Each file also carries an ingest status. A scan marks the files it will
+//! process `pending`, a batch marks its files `processing` before parsing,
+//! and the batch transaction marks them `done` together with their data. A
+//! file left `pending` or `processing` by a crash is picked up again on the
+//! next start, even if it hasn't changed since.
+//!
+//! Files are stored under their plain path spelling and looked up by
+//! [`path_key`], so the spellings Windows hands out for one file (see
+//! [`crate::parser::paths`]) don't make it look new on every launch.
 
 use crate::db::sqlite::DbResult;
+use crate::parser::paths::{long_path, normalize_path, path_key, CASE_INSENSITIVE_PATHS};
+use crate::parser::session_segments;
 use chrono::{DateTime, Utc};
 use rusqlite::Connection;
 use std::collections::HashMap;
@@ -11,15 +23,47 @@ use std::fs;
 use std::path::{Path, PathBuf};
 use tracing::{debug, info};
 
+/// Ingest status of a tracked file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IngestStatus {
+    /// Scheduled by a scan but not started.
+    Pending,
+    /// Being parsed and written.
+    Processing,
+    /// Ingested; `modified_at` is the ingested version.
+    Done,
+}
+
+impl IngestStatus {
+    /// Value stored in `file_metadata.status`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            IngestStatus::Pending => "pending",
+            IngestStatus::Processing => "processing",
+            IngestStatus::Done => "done",
+        }
+    }
+
+    fn from_db(value: &str) -> Self {
+        match value {
+            "pending" => IngestStatus::Pending,
+            "processing" => IngestStatus::Processing,
+            _ => IngestStatus::Done,
+        }
+    }
+}
+
 /// Metadata about a tracked file.
 #[derive(Debug, Clone)]
 pub struct FileMetadata {
     /// Path to the file.
     pub file_path: PathBuf,
-    /// Last known modification time (ISO 8601).
+    /// Last ingested modification time (ISO 8601); empty if never ingested.
     pub modified_at: String,
     /// When we last parsed this file (ISO 8601).
     pub parsed_at: String,
+    /// Ingest status.
+    pub status: IngestStatus,
 }
 
 /// Information about a file that needs processing.
@@ -35,10 +79,10 @@ pub struct ModifiedFile {
 
 /// Gets all tracked file metadata from the database.
 ///
-/// Returns a map of file path to metadata for quick lookup.
+/// Returns a map of [`path_key`] to metadata for quick lookup.
 pub fn get_all_file_metadata(conn: &Connection) -> DbResult<HashMap<String, FileMetadata>> {
     let mut stmt = conn.prepare(
-        "SELECT file_path, modified_at, parsed_at FROM file_metadata"
+        "SELECT file_path, modified_at, parsed_at, status FROM file_metadata"
     )?;
 
     let rows = stmt.query_map([], |row| {
@@ -46,14 +90,14 @@ pub fn get_all_file_metadata(conn: &Connection) -> DbResult<HashMap<String, File
             file_path: PathBuf::from(row.get::<_, String>(0)?),
             modified_at: row.get(1)?,
             parsed_at: row.get(2)?,
+            status: IngestStatus::from_db(&row.get::<_, String>(3)?),
         })
     })?;
 
     let mut metadata_map = HashMap::new();
     for row_result in rows {
         let metadata = row_result?;
-        let path_str = metadata.file_path.to_string_lossy().to_string();
-        metadata_map.insert(path_str, metadata);
+        metadata_map.insert(path_key(&metadata.file_path), metadata);
     }
 
     debug!("Loaded {} file metadata entries", metadata_map.len());
@@ -73,7 +117,7 @@ pub fn is_metadata_empty(conn: &Connection) -> DbResult<bool> {
 /// Gets files that have been modified since they were last parsed.
 ///
 /// Compares current filesystem modification times against stored metadata.
-/// Returns files that need to be re-parsed.
+/// Returns files that need to be re-parsed, under their plain spelling.
 ///
 /// # Arguments
 /// * `conn` - Database connection
@@ -84,7 +128,8 @@ pub fn is_metadata_empty(conn: &Connection) -> DbResult<bool> {
 ///
 /// # Behavior
 /// - If metadata table is empty, returns ALL discovered files as "new"
-/// - Otherwise, returns only files where modification time has changed
+/// - Otherwise, returns files where modification time has changed, plus
+///   files whose ingest was interrupted
 pub fn get_modified_files(
     conn: &Connection,
     discovered_files: &[PathBuf],
@@ -99,7 +144,7 @@ pub fn get_modified_files(
             .filter_map(|path| {
                 let modified_at = get_file_modified_time(path)?;
                 Some(ModifiedFile {
-                    file_path: path.clone(),
+                    file_path: normalize_path(path),
                     current_modified_at: modified_at,
                     is_new: true,
                 })
@@ -113,7 +158,8 @@ pub fn get_modified_files(
     let mut modified_files = Vec::new();
 
     for file_path in discovered_files {
-        let path_str = file_path.to_string_lossy().to_string();
+        let key = path_key(file_path);
+        let file_path = &normalize_path(file_path);
 
         // Get current modification time
         let current_modified_at = match get_file_modified_time(file_path) {
@@ -121,7 +167,20 @@ pub fn get_modified_files(
             None => continue, // Skip files we can't read
         };
 
-        match metadata_map.get(&path_str) {
+        match metadata_map.get(&key) {
+            Some(stored_metadata) if stored_metadata.status != IngestStatus::Done => {
+                // Scheduled or interrupted - resume regardless of modification time
+                debug!(
+                    "Resuming ingest of {:?} (status: {})",
+                    file_path,
+                    stored_metadata.status.as_str()
+                );
+                modified_files.push(ModifiedFile {
+                    file_path: file_path.clone(),
+                    current_modified_at,
+                    is_new: stored_metadata.modified_at.is_empty(),
+                });
+            }
             Some(stored_metadata) => {
                 // File exists in metadata - check if modified
                 if current_modified_at != stored_metadata.modified_at {
@@ -159,25 +218,28 @@ pub fn get_modified_files(
 
 /// Updates the metadata for a single file after successful parsing.
 ///
-/// Records the modification time and current timestamp as parsed time.
+/// Records the modification time and current timestamp as parsed time, and
+/// marks the file `done`. Call it in the transaction writing the file's data.
 pub fn update_file_metadata(
     conn: &Connection,
     file_path: &Path,
     modified_at: &str,
 ) -> DbResult<()> {
     let now = Utc::now().to_rfc3339();
-    let path_str = file_path.to_string_lossy().to_string();
+    let path_str = normalize_path(file_path).to_string_lossy().to_string();
+    merge_spellings(conn, &path_str)?;
 
-    conn.execute(
+    conn.prepare_cached(
         r#"
-        INSERT INTO file_metadata (file_path, modified_at, parsed_at)
-        VALUES (?1, ?2, ?3)
+        INSERT INTO file_metadata (file_path, modified_at, parsed_at, status)
+        VALUES (?1, ?2, ?3, 'done')
         ON CONFLICT(file_path) DO UPDATE SET
             modified_at = excluded.modified_at,
-            parsed_at = excluded.parsed_at
+            parsed_at = excluded.parsed_at,
+            status = 'done'
         "#,
-        [&path_str, modified_at, &now],
-    )?;
+    )?
+    .execute([&path_str, modified_at, &now])?;
 
     debug!("Updated metadata for {:?}", file_path);
     Ok(())
@@ -196,16 +258,18 @@ pub fn update_file_metadata_batch(
     {
         let mut stmt = tx.prepare(
             r#"
-            INSERT INTO file_metadata (file_path, modified_at, parsed_at)
-            VALUES (?1, ?2, ?3)
+            INSERT INTO file_metadata (file_path, modified_at, parsed_at, status)
+            VALUES (?1, ?2, ?3, 'done')
             ON CONFLICT(file_path) DO UPDATE SET
                 modified_at = excluded.modified_at,
-                parsed_at = excluded.parsed_at
+                parsed_at = excluded.parsed_at,
+                status = 'done'
             "#,
         )?;
 
         for (path, modified_at) in files {
-            let path_str = path.to_string_lossy().to_string();
+            let path_str = normalize_path(path).to_string_lossy().to_string();
+            merge_spellings(&tx, &path_str)?;
             stmt.execute([&path_str, modified_at, &now])?;
         }
     }
@@ -216,9 +280,39 @@ pub fn update_file_metadata_batch(
     Ok(())
 }
 
+/// Sets the ingest status of files, adding untracked files with an empty
+/// modification time so they still count as new.
+///
+/// The stored modification time is kept, so a file that changed while its
+/// ingest was interrupted is still recognized as updated.
+pub fn set_ingest_status(
+    conn: &Connection,
+    files: &[PathBuf],
+    status: IngestStatus,
+) -> DbResult<()> {
+    let mut stmt = conn.prepare(
+        r#"
+        INSERT INTO file_metadata (file_path, modified_at, parsed_at, status)
+        VALUES (?1, '', '', ?2)
+        ON CONFLICT(file_path) DO UPDATE SET status = excluded.status
+        "#,
+    )?;
+    for path in files {
+        let path_str = normalize_path(path).to_string_lossy().to_string();
+        merge_spellings(conn, &path_str)?;
+        stmt.execute([path_str.as_str(), status.as_str()])?;
+    }
+
+    debug!("Marked {} files {}", files.len(), status.as_str());
+    Ok(())
+}
+
 /// Removes metadata for files that no longer exist.
 ///
-/// Call this during cleanup to remove stale entries.
+/// Call this during cleanup to remove stale entries. A file missing from
+/// `existing_files` but still on disk, e.g. one imported from outside the
+/// projects directory, keeps its metadata so it isn't re-read until it
+/// changes.
 pub fn remove_stale_metadata(
     conn: &Connection,
     existing_files: &[PathBuf],
@@ -227,15 +321,16 @@ pub fn remove_stale_metadata(
     let metadata_map = get_all_file_metadata(conn)?;
 
     // Build set of existing file paths
-    let existing_set: std::collections::HashSet<String> = existing_files
-        .iter()
-        .map(|p| p.to_string_lossy().to_string())
-        .collect();
+    let existing_set: std::collections::HashSet<String> =
+        existing_files.iter().map(|p| path_key(p)).collect();
 
     // Find stale entries
-    let stale_paths: Vec<&String> = metadata_map
-        .keys()
-        .filter(|path| !existing_set.contains(*path))
+    let stale_paths: Vec<String> = metadata_map
+        .iter()
+        .filter(|(key, metadata)| {
+            !existing_set.contains(*key) && !long_path(&metadata.file_path).exists()
+        })
+        .map(|(_, metadata)| metadata.file_path.to_string_lossy().to_string())
         .collect();
 
     if stale_paths.is_empty() {
@@ -253,6 +348,37 @@ pub fn remove_stale_metadata(
     Ok(deleted)
 }
 
+/// Leaves `path` the only spelling of a file where file names ignore case:
+/// other spellings tracked before are merged into it, keeping the row of
+/// the exact spelling if there is one.
+fn merge_spellings(conn: &Connection, path: &str) -> DbResult<()> {
+    if !CASE_INSENSITIVE_PATHS {
+        return Ok(());
+    }
+    let tracked: bool = conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM file_metadata WHERE file_path = ?1)",
+        [path],
+        |row| row.get(0),
+    )?;
+    let merged = if tracked {
+        conn.execute(
+            "DELETE FROM file_metadata
+             WHERE file_path = ?1 COLLATE NOCASE AND file_path != ?1",
+            [path],
+        )?
+    } else {
+        conn.execute(
+            "UPDATE OR REPLACE file_metadata SET file_path = ?1
+             WHERE file_path = ?1 COLLATE NOCASE",
+            [path],
+        )?
+    };
+    if merged > 0 {
+        debug!("Merged {} other spellings of {}", merged, path);
+    }
+    Ok(())
+}
+
 /// Clears all file metadata (forces full rescan on next run).
 pub fn clear_all_metadata(conn: &Connection) -> DbResult<()> {
     conn.execute("DELETE FROM file_metadata", [])?;
@@ -260,10 +386,14 @@ pub fn clear_all_metadata(conn: &Connection) -> DbResult<()> {
     Ok(())
 }
 
-/// Gets the modification time of a file as an ISO 8601 string.
+/// Gets the modification time of a session file as an ISO 8601 string: the
+/// latest of its segments, so rotating or compressing a segment counts as a
+/// change.
 fn get_file_modified_time(path: &Path) -> Option<String> {
-    let metadata = fs::metadata(path).ok()?;
-    let modified = metadata.modified().ok()?;
+    let modified = session_segments(path)
+        .iter()
+        .filter_map(|segment| fs::metadata(long_path(segment)).and_then(|m| m.modified()).ok())
+        .max()?;
     let datetime: DateTime<Utc> = modified.into();
     Some(datetime.to_rfc3339())
 }
@@ -444,6 +574,12 @@ mod tests {
             [],
         ).unwrap();
 
+        // A file on disk that wasn't discovered, like an imported one
+        let temp_dir = tempdir().unwrap();
+        let imported = temp_dir.path().join("imported.jsonl");
+        File::create(&imported).unwrap().write_all(b"{}").unwrap();
+        update_file_metadata(&conn, &imported, "2025-01-01").unwrap();
+
         // Only /exists.jsonl still exists
         let existing = vec![PathBuf::from("/exists.jsonl")];
 
@@ -451,8 +587,9 @@ mod tests {
         assert_eq!(removed, 1);
 
         let metadata = get_all_file_metadata(&conn).unwrap();
-        assert_eq!(metadata.len(), 1);
+        assert_eq!(metadata.len(), 2);
         assert!(metadata.contains_key("/exists.jsonl"));
+        assert!(metadata.contains_key(&path_key(&imported)));
     }
 
     #[test]
@@ -490,4 +627,80 @@ mod tests {
         assert!(time_str.contains("T"));
         assert!(time_str.ends_with("Z") || time_str.contains("+"));
     }
+
+    #[test]
+    fn test_interrupted_files_are_resumed() {
+        let conn = setup_test_db();
+        let temp_dir = tempdir().unwrap();
+
+        let done = temp_dir.path().join("done.jsonl");
+        let interrupted = temp_dir.path().join("interrupted.jsonl");
+        let scheduled = temp_dir.path().join("scheduled.jsonl");
+        for path in [&done, &interrupted, &scheduled] {
+            File::create(path).unwrap().write_all(b"{}").unwrap();
+            update_file_metadata(&conn, path, &get_file_modified_time(path).unwrap()).unwrap();
+        }
+
+        // A scan scheduled two unchanged files, then crashed mid-batch
+        set_ingest_status(&conn, &[interrupted.clone(), scheduled.clone()], IngestStatus::Pending).unwrap();
+        set_ingest_status(&conn, std::slice::from_ref(&interrupted), IngestStatus::Processing).unwrap();
+
+        let files = vec![done.clone(), interrupted.clone(), scheduled.clone()];
+        let modified = get_modified_files(&conn, &files).unwrap();
+        let paths: Vec<_> = modified.iter().map(|f| &f.file_path).collect();
+        assert_eq!(paths.len(), 2);
+        assert!(paths.contains(&&interrupted));
+        assert!(paths.contains(&&scheduled));
+        assert!(modified.iter().all(|f| !f.is_new));
+
+        // Finishing the ingest marks the file done
+        update_file_metadata(&conn, &interrupted, &get_file_modified_time(&interrupted).unwrap()).unwrap();
+        let metadata = get_all_file_metadata(&conn).unwrap();
+        assert_eq!(
+            metadata[&path_key(&interrupted)].status,
+            IngestStatus::Done
+        );
+    }
+
+    #[test]
+    fn test_scheduled_new_files_stay_new() {
+        let conn = setup_test_db();
+        let temp_dir = tempdir().unwrap();
+
+        let old = temp_dir.path().join("old.jsonl");
+        let new = temp_dir.path().join("new.jsonl");
+        for path in [&old, &new] {
+            File::create(path).unwrap().write_all(b"{}").unwrap();
+        }
+        update_file_metadata(&conn, &old, &get_file_modified_time(&old).unwrap()).unwrap();
+        set_ingest_status(&conn, std::slice::from_ref(&new), IngestStatus::Pending).unwrap();
+
+        let modified = get_modified_files(&conn, &[old, new.clone()]).unwrap();
+        assert_eq!(modified.len(), 1);
+        assert_eq!(modified[0].file_path, new);
+        assert!(modified[0].is_new);
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn test_windows_spellings_share_metadata() {
+        let conn = setup_test_db();
+        let temp_dir = tempdir().unwrap();
+        let file = temp_dir.path().join("Session.jsonl");
+        File::create(&file).unwrap().write_all(b"{}").unwrap();
+        let plain = normalize_path(&file);
+        update_file_metadata(&conn, &plain, &get_file_modified_time(&plain).unwrap()).unwrap();
+
+        // The watcher's verbatim spelling and another case are the same file
+        let verbatim = PathBuf::from(format!(r"\\?\{}", plain.display()));
+        let lower = PathBuf::from(plain.to_string_lossy().to_lowercase());
+        assert!(get_modified_files(&conn, &[verbatim, lower.clone()]).unwrap().is_empty());
+
+        // Writing under another spelling doesn't track the file twice
+        set_ingest_status(&conn, std::slice::from_ref(&lower), IngestStatus::Pending).unwrap();
+        let metadata = get_all_file_metadata(&conn).unwrap();
+        assert_eq!(metadata.len(), 1);
+        assert_eq!(metadata[&path_key(&plain)].status, IngestStatus::Pending);
+        assert_eq!(remove_stale_metadata(&conn, &[plain]).unwrap(), 0);
+    }
 }
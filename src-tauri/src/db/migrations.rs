@@ -0,0 +1,1045 @@
+//! Schema migrations for existing databases.
+//!
+//! `init_db` creates the current schema for fresh databases with
+//! `CREATE ... IF NOT EXISTS`; migrations bring databases created by older
+//! versions up to date. The last applied version is tracked in SQLite's
+//! `PRAGMA user_version`, and each migration runs in its own transaction.
+
+use crate::db::identity::remap_conversation_id;
+use crate::db::settings::load_settings;
+use crate::db::sqlite::DbResult;
+use crate::db::storage::{StoredText, COMPRESSION_THRESHOLD};
+use crate::parser::jsonl::{
+    agent_file_stem, generate_conversation_id, legacy_conversation_id,
+    path_conversation_id, DEFAULT_SESSION_ID,
+};
+use crate::parser::{normalize_timestamp, parse_conversation_file};
+use crate::search::index::rebuild_fts_table;
+use rusqlite::Connection;
+use std::path::Path;
+use tracing::{debug, info, warn};
+
+/// A single migration step.
+type Migration = fn(&Connection) -> DbResult<()>;
+
+/// Ordered list of migrations: (version, migration).
+const MIGRATIONS: &[(i64, Migration)] = &[
+    (1, migrate_v1_timestamp_epochs),
+    (2, migrate_v2_stable_conversation_ids),
+    (3, migrate_v3_session_conversation_ids),
+    (4, migrate_v4_usage_backfill),
+    (5, migrate_v5_ingest_status),
+    (6, migrate_v6_medium_previews),
+    (7, migrate_v7_trigram_index),
+    (8, migrate_v8_fts_tokenizer),
+    (9, migrate_v9_archived_at),
+    (10, migrate_v10_protected_at),
+    (11, migrate_v11_covering_indexes),
+    (12, migrate_v12_keyset_index),
+    (13, migrate_v13_question_index),
+    (14, migrate_v14_resolution),
+    (15, migrate_v15_collapse_reminders),
+    (16, migrate_v16_project_partitions),
+    (17, migrate_v17_language_usage),
+    (18, migrate_v18_response_latency),
+    (19, migrate_v19_partial_storage),
+    (20, migrate_v20_compressed_attachments),
+    (21, migrate_v21_attachment_refs),
+    (22, migrate_v22_content_hash),
+    (23, migrate_v23_continuations),
+    (24, migrate_v24_subagents),
+];
+
+/// Current schema version (the highest migration version).
+pub const SCHEMA_VERSION: i64 = 24;
+
+/// Builds a generated-column definition holding Unix epoch milliseconds of `source`.
+///
+/// `julianday()` understands `Z` and `±HH:MM` suffixes and returns NULL for
+/// empty or unparseable strings, so the derived column is NULL in that case.
+pub fn epoch_column_definition(source: &str) -> String {
+    format!(
+        "INTEGER GENERATED ALWAYS AS (CAST(ROUND((julianday({}) - 2440587.5) * 86400000.0) AS INTEGER)) VIRTUAL",
+        source
+    )
+}
+
+/// Returns the schema version recorded in the database.
+pub fn get_schema_version(conn: &Connection) -> DbResult<i64> {
+    let version: i64 = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+    Ok(version)
+}
+
+/// Applies all pending migrations in order.
+///
+/// Safe to call on every startup; already-applied migrations are skipped.
+pub fn run_migrations(conn: &Connection) -> DbResult<()> {
+    let current = get_schema_version(conn)?;
+    if current >= SCHEMA_VERSION {
+        debug!("Database schema is up to date (version {})", current);
+        return Ok(());
+    }
+
+    for (version, migration) in MIGRATIONS {
+        if *version <= current {
+            continue;
+        }
+
+        let tx = conn.unchecked_transaction()?;
+        migration(&tx)?;
+        tx.pragma_update(None, "user_version", version)?;
+        tx.commit()?;
+
+        info!("Applied database migration v{}", version);
+    }
+
+    Ok(())
+}
+
+/// Checks whether a table has a column with the given name.
+pub fn column_exists(conn: &Connection, table: &str, column: &str) -> DbResult<bool> {
+    // table_xinfo also lists generated columns, which table_info hides
+    let mut stmt = conn.prepare(&format!("PRAGMA table_xinfo({})", table))?;
+    let names = stmt.query_map([], |row| row.get::<_, String>(1))?;
+    for name in names {
+        if name? == column {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// Adds a column to a table unless it already exists.
+pub fn add_column_if_missing(
+    conn: &Connection,
+    table: &str,
+    column: &str,
+    definition: &str,
+) -> DbResult<()> {
+    if !column_exists(conn, table, column)? {
+        conn.execute_batch(&format!(
+            "ALTER TABLE {} ADD COLUMN {} {}",
+            table, column, definition
+        ))?;
+        debug!("Added column {}.{}", table, column);
+    }
+    Ok(())
+}
+
+/// v1: canonical UTC timestamps plus derived epoch columns for sorting/filtering.
+///
+/// Older versions stored timestamps verbatim, so files with mixed formats
+/// (offsets, missing fractions, space separators) sorted incorrectly.
+fn migrate_v1_timestamp_epochs(conn: &Connection) -> DbResult<()> {
+    add_column_if_missing(
+        conn,
+        "conversations",
+        "start_epoch",
+        &epoch_column_definition("start_time"),
+    )?;
+    add_column_if_missing(
+        conn,
+        "conversations",
+        "last_epoch",
+        &epoch_column_definition("last_time"),
+    )?;
+
+    conn.execute_batch(
+        r#"
+        CREATE INDEX IF NOT EXISTS idx_conversations_last_epoch
+            ON conversations(last_epoch);
+        "#,
+    )?;
+
+    // Rewrite previously stored timestamps into canonical form
+    let rows: Vec<(String, String, String)> = {
+        let mut stmt = conn.prepare("SELECT id, start_time, last_time FROM conversations")?;
+        let mapped = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?;
+        mapped.collect::<Result<_, _>>()?
+    };
+
+    let mut updated = 0;
+    for (id, start_time, last_time) in rows {
+        let start = normalize_timestamp(&start_time).map(|t| t.utc).unwrap_or(start_time.clone());
+        let last = normalize_timestamp(&last_time).map(|t| t.utc).unwrap_or(last_time.clone());
+        if start != start_time || last != last_time {
+            conn.execute(
+                "UPDATE conversations SET start_time = ?1, last_time = ?2 WHERE id = ?3",
+                rusqlite::params![start, last, id],
+            )?;
+            updated += 1;
+        }
+    }
+
+    if updated > 0 {
+        info!("Normalized timestamps for {} conversations", updated);
+    }
+    Ok(())
+}
+
+/// v2: SHA-256 conversation IDs and a stored session ID.
+///
+/// Legacy IDs were derived from `DefaultHasher`, which can only be
+/// reproduced by re-reading each session file. Conversations whose files are
+/// gone keep their old ID; they are unreachable either way.
+fn migrate_v2_stable_conversation_ids(conn: &Connection) -> DbResult<()> {
+    add_column_if_missing(conn, "conversations", "session_id", "TEXT NOT NULL DEFAULT ''")?;
+
+    let remapped = remap_ids_from_files(conn, legacy_conversation_id)?;
+    if remapped > 0 {
+        info!("Migrated {} conversations to stable IDs", remapped);
+    }
+    Ok(())
+}
+
+/// v3: conversation IDs keyed on session instead of file path.
+///
+/// Path-based IDs changed whenever a session file was moved or renamed.
+/// Rows whose file is gone can still be remapped from the stored session ID.
+fn migrate_v3_session_conversation_ids(conn: &Connection) -> DbResult<()> {
+    let mut remapped = remap_ids_from_files(conn, path_conversation_id)?;
+
+    let rows: Vec<(String, String, String)> = {
+        let mut stmt = conn.prepare(
+            "SELECT id, file_path, session_id FROM conversations WHERE session_id NOT IN ('', ?1)",
+        )?;
+        let mapped = stmt.query_map([DEFAULT_SESSION_ID], |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+        })?;
+        mapped.collect::<Result<_, _>>()?
+    };
+
+    for (id, file_path, session_id) in rows {
+        if id == path_conversation_id(Path::new(&file_path), &session_id) {
+            let new_id = generate_conversation_id(&session_id, &[]);
+            if remap_conversation_id(conn, &id, &new_id)? {
+                remapped += 1;
+            }
+        }
+    }
+
+    if remapped > 0 {
+        info!("Migrated {} conversations to session-based IDs", remapped);
+    }
+    Ok(())
+}
+
+/// v4: usage statistics (`usage_daily`, `tool_usage`).
+///
+/// `init_db` creates the tables; usage is only recorded when a file is
+/// ingested, so forgetting the file modification times makes the next scan
+/// re-ingest every file and fill them in for existing conversations.
+fn migrate_v4_usage_backfill(conn: &Connection) -> DbResult<()> {
+    let cleared = conn.execute("DELETE FROM file_metadata", [])?;
+    if cleared > 0 {
+        info!("Scheduled {} files for re-ingest to record usage", cleared);
+    }
+    Ok(())
+}
+
+/// Re-parses every referenced session file and remaps rows from the ID
+/// `old_id` would have produced to the current one.
+///
+/// Also records each conversation's session ID. Returns the number of rows
+/// remapped.
+fn remap_ids_from_files(conn: &Connection, old_id: fn(&Path, &str) -> String) -> DbResult<usize> {
+    let file_paths: Vec<String> = {
+        let mut stmt = conn.prepare("SELECT DISTINCT file_path FROM conversations")?;
+        let mapped = stmt.query_map([], |row| row.get(0))?;
+        mapped.collect::<Result<_, _>>()?
+    };
+
+    let mut remapped = 0;
+    for file_path in file_paths {
+        let path = Path::new(&file_path);
+        let conversations = match parse_conversation_file(path) {
+            Ok(conversations) => conversations,
+            Err(e) => {
+                warn!("Skipping ID migration for {:?}: {}", path, e);
+                continue;
+            }
+        };
+
+        for conv in conversations {
+            if remap_conversation_id(conn, &old_id(path, &conv.session_id), &conv.id)? {
+                remapped += 1;
+            }
+            conn.execute(
+                "UPDATE conversations SET session_id = ?1 WHERE id = ?2",
+                rusqlite::params![conv.session_id, conv.id],
+            )?;
+        }
+    }
+
+    Ok(remapped)
+}
+
+/// v5: per-file ingest status, so an interrupted scan resumes on the next
+/// start. Previously tracked files were fully ingested.
+fn migrate_v5_ingest_status(conn: &Connection) -> DbResult<()> {
+    add_column_if_missing(conn, "file_metadata", "status", "TEXT NOT NULL DEFAULT 'done'")
+}
+
+/// v6: medium-length previews for tooltips. Existing conversations start
+/// with their short preview until previews are regenerated.
+fn migrate_v6_medium_previews(conn: &Connection) -> DbResult<()> {
+    add_column_if_missing(conn, "conversations", "preview_medium", "TEXT NOT NULL DEFAULT ''")?;
+    conn.execute(
+        "UPDATE conversations SET preview_medium = preview WHERE preview_medium = ''",
+        [],
+    )?;
+    Ok(())
+}
+
+/// v7: trigram index for typo-tolerant search. `init_db` creates the table;
+/// it's filled from the same text `rebuild_search_index` indexes.
+fn migrate_v7_trigram_index(conn: &Connection) -> DbResult<()> {
+    conn.execute("DELETE FROM conversations_trigram", [])?;
+    let indexed = conn.execute(
+        "INSERT INTO conversations_trigram(rowid, content, project_name)
+         SELECT rowid, preview, project_name FROM conversations",
+        [],
+    )?;
+    if indexed > 0 {
+        info!("Built trigram index for {} conversations", indexed);
+    }
+    Ok(())
+}
+
+/// v8: recreates `conversations_fts` with an explicit tokenizer, the one
+/// selected in settings. `update_settings` rebuilds it the same way when the
+/// tokenizer changes.
+fn migrate_v8_fts_tokenizer(conn: &Connection) -> DbResult<()> {
+    let tokenizer = load_settings(conn)?.search_tokenizer;
+    rebuild_fts_table(conn, tokenizer)?;
+    Ok(())
+}
+
+/// v9: archive timestamps for bulk archiving. `init_db` creates the
+/// `deleted_conversations` tombstone table.
+fn migrate_v9_archived_at(conn: &Connection) -> DbResult<()> {
+    add_column_if_missing(conn, "conversations", "archived_at", "TEXT")
+}
+
+/// v10: protection against archiving and deletion.
+fn migrate_v10_protected_at(conn: &Connection) -> DbResult<()> {
+    add_column_if_missing(conn, "conversations", "protected_at", "TEXT")
+}
+
+/// v11: indexes covering the list and filter queries, which otherwise scan
+/// the whole table once it holds tens of thousands of conversations.
+///
+/// Lists sort by `last_epoch` (derived from `last_time`), so the project
+/// index ends with it; `init_db` already indexes `last_time` and
+/// `bookmarks(conversation_id)`. The tag index replaces the one on `tag`
+/// alone, so tag counts and tag filters read only the index.
+fn migrate_v11_covering_indexes(conn: &Connection) -> DbResult<()> {
+    conn.execute_batch(
+        r#"
+        CREATE INDEX IF NOT EXISTS idx_conversations_project_last_epoch
+            ON conversations(project_name, last_epoch);
+        CREATE INDEX IF NOT EXISTS idx_conversations_last_time
+            ON conversations(last_time);
+        DROP INDEX IF EXISTS idx_conversation_tags_tag;
+        CREATE INDEX IF NOT EXISTS idx_conversation_tags_tag_conversation
+            ON conversation_tags(tag, conversation_id);
+        CREATE INDEX IF NOT EXISTS idx_bookmarks_conversation_id
+            ON bookmarks(conversation_id);
+        "#,
+    )?;
+    Ok(())
+}
+
+/// v12: index on the list sort key `(last_epoch, id)`, so list windows
+/// seek to a keyset cursor instead of stepping over rows with `OFFSET`.
+/// It replaces the index on `last_epoch` alone.
+fn migrate_v12_keyset_index(conn: &Connection) -> DbResult<()> {
+    conn.execute_batch(
+        r#"
+        DROP INDEX IF EXISTS idx_conversations_last_epoch;
+        CREATE INDEX IF NOT EXISTS idx_conversations_last_epoch_id
+            ON conversations(last_epoch, id);
+        "#,
+    )?;
+    Ok(())
+}
+
+/// v13: index of user messages for `search_questions`. `init_db` creates
+/// the tables; like usage in v4, they're filled when a file is ingested, so
+/// every file is scheduled for re-ingest.
+fn migrate_v13_question_index(conn: &Connection) -> DbResult<()> {
+    let cleared = conn.execute("DELETE FROM file_metadata", [])?;
+    if cleared > 0 {
+        info!("Scheduled {} files for re-ingest to index questions", cleared);
+    }
+    Ok(())
+}
+
+/// v14: how each conversation ended. It's detected when a file is
+/// ingested, so every file is scheduled for re-ingest.
+fn migrate_v14_resolution(conn: &Connection) -> DbResult<()> {
+    add_column_if_missing(conn, "conversations", "resolution", "TEXT NOT NULL DEFAULT 'unknown'")?;
+    let cleared = conn.execute("DELETE FROM file_metadata", [])?;
+    if cleared > 0 {
+        info!("Scheduled {} files for re-ingest to detect resolutions", cleared);
+    }
+    Ok(())
+}
+
+/// v15: previews and the question index are built from messages without
+/// repeated system reminders. Every file is scheduled for re-ingest so
+/// they're rebuilt.
+fn migrate_v15_collapse_reminders(conn: &Connection) -> DbResult<()> {
+    let cleared = conn.execute("DELETE FROM file_metadata", [])?;
+    if cleared > 0 {
+        info!("Scheduled {} files for re-ingest without repeated reminders", cleared);
+    }
+    Ok(())
+}
+
+/// v16: recreates `conversations_fts` with the `project_key` column that
+/// partitions it by project.
+fn migrate_v16_project_partitions(conn: &Connection) -> DbResult<()> {
+    let tokenizer = load_settings(conn)?.search_tokenizer;
+    rebuild_fts_table(conn, tokenizer)?;
+    Ok(())
+}
+
+/// v17: code blocks per language (`language_usage`). `init_db` creates the
+/// table; like usage in v4, it's filled when a file is ingested, so every
+/// file is scheduled for re-ingest.
+fn migrate_v17_language_usage(conn: &Connection) -> DbResult<()> {
+    let cleared = conn.execute("DELETE FROM file_metadata", [])?;
+    if cleared > 0 {
+        info!("Scheduled {} files for re-ingest to count code languages", cleared);
+    }
+    Ok(())
+}
+
+/// v18: answer latency per day and model in `usage_daily`. It's measured
+/// when a file is ingested, so every file is scheduled for re-ingest.
+fn migrate_v18_response_latency(conn: &Connection) -> DbResult<()> {
+    add_column_if_missing(conn, "usage_daily", "response_millis", "INTEGER NOT NULL DEFAULT 0")?;
+    add_column_if_missing(conn, "usage_daily", "timed_responses", "INTEGER NOT NULL DEFAULT 0")?;
+    let cleared = conn.execute("DELETE FROM file_metadata", [])?;
+    if cleared > 0 {
+        info!("Scheduled {} files for re-ingest to measure answer latency", cleared);
+    }
+    Ok(())
+}
+
+/// v19: partial storage of conversations from large session files. Whether
+/// a file is large is checked when it's ingested, so every file is
+/// scheduled for re-ingest.
+fn migrate_v19_partial_storage(conn: &Connection) -> DbResult<()> {
+    add_column_if_missing(conn, "conversations", "partial_storage", "INTEGER NOT NULL DEFAULT 0")?;
+    add_column_if_missing(conn, "conversations", "full_load_requested", "INTEGER NOT NULL DEFAULT 0")?;
+    let cleared = conn.execute("DELETE FROM file_metadata", [])?;
+    if cleared > 0 {
+        info!("Scheduled {} files for re-ingest to find large ones", cleared);
+    }
+    Ok(())
+}
+
+/// v20: compressed attachment data (see `db::storage`). Attachments stored
+/// before are rewritten compressed; the space is given back to the file
+/// system on the next `VACUUM`.
+fn migrate_v20_compressed_attachments(conn: &Connection) -> DbResult<()> {
+    let large: Vec<(String, StoredText)> = conn
+        .prepare(
+            "SELECT id, data FROM attachments WHERE typeof(data) = 'text' AND length(data) > ?1",
+        )?
+        .query_map([COMPRESSION_THRESHOLD as i64], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<Result<_, _>>()?;
+    let mut stmt = conn.prepare("UPDATE attachments SET data = ?1 WHERE id = ?2")?;
+    for (id, data) in &large {
+        stmt.execute(rusqlite::params![data, id])?;
+    }
+    if !large.is_empty() {
+        info!("Compressed {} attachments", large.len());
+    }
+    Ok(())
+}
+
+/// v21: conversations referencing each attachment (`attachment_refs`, which
+/// `init_db` creates). Attachments stored before aren't referenced, so they
+/// would never be removed; they're dropped instead, and extracted again from
+/// the session file when their conversation is next loaded.
+fn migrate_v21_attachment_refs(conn: &Connection) -> DbResult<()> {
+    let dropped = conn.execute(
+        "DELETE FROM attachments
+         WHERE id NOT IN (SELECT attachment_id FROM attachment_refs)",
+        [],
+    )?;
+    if dropped > 0 {
+        info!("Dropped {} unreferenced attachments", dropped);
+    }
+    Ok(())
+}
+
+/// v22: a hash of each conversation's messages, for change detection. It's
+/// computed when a file is ingested, so every file is scheduled for
+/// re-ingest.
+fn migrate_v22_content_hash(conn: &Connection) -> DbResult<()> {
+    add_column_if_missing(conn, "conversations", "content_hash", "TEXT NOT NULL DEFAULT ''")?;
+    let cleared = conn.execute("DELETE FROM file_metadata", [])?;
+    if cleared > 0 {
+        info!("Scheduled {} files for re-ingest to hash their conversations", cleared);
+    }
+    Ok(())
+}
+
+/// v23: links between resumed sessions. `init_db` creates the tables;
+/// they're filled when a file is ingested, so every file is scheduled for
+/// re-ingest.
+fn migrate_v23_continuations(conn: &Connection) -> DbResult<()> {
+    let cleared = conn.execute("DELETE FROM file_metadata", [])?;
+    if cleared > 0 {
+        info!("Scheduled {} files for re-ingest to link resumed sessions", cleared);
+    }
+    Ok(())
+}
+
+/// v24: which conversations are sub-agent transcripts, and the working
+/// directory they ran in, to nest them under the sessions that invoked
+/// them. Both are read when a file is ingested, so every file is scheduled
+/// for re-ingest.
+///
+/// Transcripts in `agent-*` files carry the session ID of their session,
+/// so they shared its conversation, written by whichever file was ingested
+/// last. Bookmarks, tags, and the like belong to the session, so a
+/// conversation last written from an agent file keeps its ID and is
+/// detached from the file: the scan relinks it to the session's file and
+/// the re-ingest rewrites it from there, while the transcript gets an ID of
+/// its own.
+fn migrate_v24_subagents(conn: &Connection) -> DbResult<()> {
+    add_column_if_missing(conn, "conversations", "is_subagent", "INTEGER NOT NULL DEFAULT 0")?;
+    add_column_if_missing(conn, "conversations", "cwd", "TEXT NOT NULL DEFAULT ''")?;
+
+    let rows: Vec<(String, String)> = {
+        let mut stmt = conn.prepare(
+            "SELECT id, file_path FROM conversations WHERE session_id NOT IN ('', ?1)",
+        )?;
+        let mapped = stmt.query_map([DEFAULT_SESSION_ID], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        mapped.collect::<Result<_, _>>()?
+    };
+    let mut detached = 0;
+    for (id, file_path) in rows {
+        if agent_file_stem(Path::new(&file_path)).is_some() {
+            detached += conn.execute(
+                "UPDATE conversations SET file_path = '', file_modified_at = '' WHERE id = ?1",
+                [&id],
+            )?;
+        }
+    }
+    if detached > 0 {
+        info!("Detached {} sessions from their sub-agent transcripts' files", detached);
+    }
+
+    let cleared = conn.execute("DELETE FROM file_metadata", [])?;
+    if cleared > 0 {
+        info!("Scheduled {} files for re-ingest to find sub-agent transcripts", cleared);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::identity::reconcile_moved_files;
+    use crate::db::sqlite::{init_db, Database};
+    use crate::ingest::{ingest_files, NoProgress};
+    use crate::parser::jsonl::agent_conversation_id;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_fresh_database_is_at_latest_version() {
+        let conn = Connection::open_in_memory().unwrap();
+        init_db(&conn).unwrap();
+        assert_eq!(get_schema_version(&conn).unwrap(), SCHEMA_VERSION);
+        assert!(column_exists(&conn, "conversations", "last_epoch").unwrap());
+    }
+
+    #[test]
+    fn test_migrate_v1_from_legacy_schema() {
+        let conn = Connection::open_in_memory().unwrap();
+        // Legacy conversations table without epoch columns
+        conn.execute_batch(
+            r#"
+            CREATE TABLE conversations (
+                id TEXT PRIMARY KEY NOT NULL,
+                project_path TEXT NOT NULL,
+                project_name TEXT NOT NULL,
+                start_time TEXT NOT NULL,
+                last_time TEXT NOT NULL,
+                preview TEXT NOT NULL DEFAULT '',
+                message_count INTEGER NOT NULL DEFAULT 0,
+                total_input_tokens INTEGER NOT NULL DEFAULT 0,
+                total_output_tokens INTEGER NOT NULL DEFAULT 0,
+                file_path TEXT NOT NULL,
+                file_modified_at TEXT NOT NULL
+            );
+            INSERT INTO conversations (id, project_path, project_name, start_time, last_time, file_path, file_modified_at)
+            VALUES ('c1', '/p', 'p', '2025-01-15T12:00:00+02:00', '2025-01-15 11:00:00', '/f.jsonl', '');
+            "#,
+        )
+        .unwrap();
+
+        init_db(&conn).unwrap();
+
+        let (start, last, last_epoch): (String, String, i64) = conn
+            .query_row(
+                "SELECT start_time, last_time, last_epoch FROM conversations WHERE id = 'c1'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .unwrap();
+        assert_eq!(start, "2025-01-15T10:00:00Z");
+        assert_eq!(last, "2025-01-15T11:00:00Z");
+        assert_eq!(last_epoch, 1_736_938_800_000);
+    }
+
+    #[test]
+    fn test_epoch_column_handles_fractions_and_empty() {
+        let conn = Connection::open_in_memory().unwrap();
+        init_db(&conn).unwrap();
+        conn.execute(
+            "INSERT INTO conversations (id, project_path, project_name, start_time, last_time, file_path, file_modified_at)
+             VALUES ('c1', '/p', 'p', '', '2025-01-15T10:30:00.123Z', '/f.jsonl', '')",
+            [],
+        )
+        .unwrap();
+
+        let (start_epoch, last_epoch): (Option<i64>, i64) = conn
+            .query_row(
+                "SELECT start_epoch, last_epoch FROM conversations WHERE id = 'c1'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(start_epoch, None);
+        assert_eq!(last_epoch, 1_736_937_000_123);
+    }
+
+    #[test]
+    fn test_migrate_v2_remaps_legacy_ids_with_annotations() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let file_path = temp_dir.path().join("session.jsonl");
+        std::fs::write(
+            &file_path,
+            r#"{"type":"user","message":{"role":"user","content":"Hello"},"timestamp":"2025-01-15T10:00:00Z","sessionId":"s1"}"#,
+        )
+        .unwrap();
+
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("PRAGMA foreign_keys = ON;").unwrap();
+        init_db(&conn).unwrap();
+
+        // Simulate a v1 database holding a legacy ID with a bookmark and tag
+        let legacy_id = legacy_conversation_id(&file_path, "s1");
+        conn.execute(
+            "INSERT INTO conversations (id, project_path, project_name, start_time, last_time, file_path, file_modified_at)
+             VALUES (?1, '/p', 'p', '2025-01-15T10:00:00Z', '2025-01-15T10:00:00Z', ?2, '')",
+            rusqlite::params![legacy_id, file_path.to_string_lossy()],
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO bookmarks (conversation_id, created_at) VALUES (?1, 'now')",
+            [&legacy_id],
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO conversation_tags (conversation_id, tag, created_at) VALUES (?1, 'rust', 'now')",
+            [&legacy_id],
+        )
+        .unwrap();
+        conn.pragma_update(None, "user_version", 1).unwrap();
+
+        run_migrations(&conn).unwrap();
+
+        let new_id = generate_conversation_id("s1", &[]);
+        let (session_id, bookmarks, tags): (String, i64, i64) = conn
+            .query_row(
+                "SELECT session_id,
+                        (SELECT COUNT(*) FROM bookmarks WHERE conversation_id = c.id),
+                        (SELECT COUNT(*) FROM conversation_tags WHERE conversation_id = c.id)
+                 FROM conversations c WHERE id = ?1",
+                [&new_id],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .unwrap();
+        assert_eq!(session_id, "s1");
+        assert_eq!(bookmarks, 1);
+        assert_eq!(tags, 1);
+        assert_eq!(get_schema_version(&conn).unwrap(), SCHEMA_VERSION);
+    }
+
+    #[test]
+    fn test_migrate_v2_keeps_rows_for_missing_files() {
+        let conn = Connection::open_in_memory().unwrap();
+        init_db(&conn).unwrap();
+        conn.execute(
+            "INSERT INTO conversations (id, project_path, project_name, start_time, last_time, file_path, file_modified_at)
+             VALUES ('abc123def456', '/p', 'p', '', '', '/does/not/exist.jsonl', '')",
+            [],
+        )
+        .unwrap();
+        conn.pragma_update(None, "user_version", 1).unwrap();
+
+        run_migrations(&conn).unwrap();
+
+        let count: i64 = conn
+            .query_row("SELECT COUNT(*) FROM conversations WHERE id = 'abc123def456'", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(count, 1);
+    }
+
+    #[test]
+    fn test_migrate_v3_remaps_path_ids_for_missing_files() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("PRAGMA foreign_keys = ON;").unwrap();
+        init_db(&conn).unwrap();
+
+        // A v2 row whose session file has since been moved away
+        let file_path = "/old/location/session.jsonl";
+        let path_id = path_conversation_id(Path::new(file_path), "s1");
+        conn.execute(
+            "INSERT INTO conversations (id, project_path, project_name, start_time, last_time, file_path, file_modified_at, session_id)
+             VALUES (?1, '/p', 'p', '', '', ?2, '', 's1')",
+            rusqlite::params![path_id, file_path],
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO conversation_tags (conversation_id, tag, created_at) VALUES (?1, 'keep', 'now')",
+            [&path_id],
+        )
+        .unwrap();
+        conn.pragma_update(None, "user_version", 2).unwrap();
+
+        run_migrations(&conn).unwrap();
+
+        let new_id = generate_conversation_id("s1", &[]);
+        let tag: String = conn
+            .query_row(
+                "SELECT tag FROM conversation_tags WHERE conversation_id = ?1",
+                [&new_id],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(tag, "keep");
+    }
+
+    #[test]
+    fn test_migrate_v4_schedules_reingest() {
+        let conn = Connection::open_in_memory().unwrap();
+        init_db(&conn).unwrap();
+        conn.execute(
+            "INSERT INTO file_metadata (file_path, modified_at, parsed_at) VALUES ('/f.jsonl', 'then', 'then')",
+            [],
+        )
+        .unwrap();
+        conn.pragma_update(None, "user_version", 3).unwrap();
+
+        run_migrations(&conn).unwrap();
+
+        let count: i64 = conn
+            .query_row("SELECT COUNT(*) FROM file_metadata", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(count, 0);
+    }
+
+    #[test]
+    fn test_migrate_v5_adds_ingest_status() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE file_metadata (file_path TEXT PRIMARY KEY NOT NULL, modified_at TEXT NOT NULL, parsed_at TEXT NOT NULL);
+             INSERT INTO file_metadata VALUES ('/f.jsonl', 'then', 'then');",
+        )
+        .unwrap();
+
+        migrate_v5_ingest_status(&conn).unwrap();
+
+        let status: String = conn
+            .query_row("SELECT status FROM file_metadata", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(status, "done");
+    }
+
+    #[test]
+    fn test_migrate_v6_backfills_medium_previews() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE conversations (id TEXT PRIMARY KEY NOT NULL, preview TEXT NOT NULL DEFAULT '');
+             INSERT INTO conversations VALUES ('c1', 'Fix the login test');",
+        )
+        .unwrap();
+
+        migrate_v6_medium_previews(&conn).unwrap();
+
+        let preview_medium: String = conn
+            .query_row("SELECT preview_medium FROM conversations", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(preview_medium, "Fix the login test");
+    }
+
+    #[test]
+    fn test_migrate_v9_v10_add_archive_and_protection_columns() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE conversations (id TEXT PRIMARY KEY NOT NULL);
+             INSERT INTO conversations VALUES ('c1');",
+        )
+        .unwrap();
+
+        migrate_v9_archived_at(&conn).unwrap();
+        migrate_v10_protected_at(&conn).unwrap();
+
+        let (archived_at, protected_at): (Option<String>, Option<String>) = conn
+            .query_row("SELECT archived_at, protected_at FROM conversations", [], |r| {
+                Ok((r.get(0)?, r.get(1)?))
+            })
+            .unwrap();
+        assert_eq!(archived_at, None);
+        assert_eq!(protected_at, None);
+    }
+
+    #[test]
+    fn test_migrate_v7_builds_trigram_index() {
+        let conn = Connection::open_in_memory().unwrap();
+        init_db(&conn).unwrap();
+        conn.execute(
+            "INSERT INTO conversations (id, project_path, project_name, start_time, last_time, preview, message_count, file_path, file_modified_at)
+             VALUES ('c1', '/p', 'acme-web', '2025-01-01T00:00:00Z', '2025-01-01T00:00:00Z', 'Fix the login test', 2, '/p/a.jsonl', '')",
+            [],
+        )
+        .unwrap();
+
+        migrate_v7_trigram_index(&conn).unwrap();
+
+        let found: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM conversations_trigram WHERE conversations_trigram MATCH 'ogi'",
+                [],
+                |r| r.get(0),
+            )
+            .unwrap();
+        assert_eq!(found, 1);
+    }
+
+    #[test]
+    fn test_migrate_v8_uses_configured_tokenizer() {
+        let conn = Connection::open_in_memory().unwrap();
+        init_db(&conn).unwrap();
+        crate::db::settings::set_setting(&conn, "search_tokenizer", "trigram").unwrap();
+
+        migrate_v8_fts_tokenizer(&conn).unwrap();
+
+        let sql: String = conn
+            .query_row(
+                "SELECT sql FROM sqlite_master WHERE name = 'conversations_fts'",
+                [],
+                |r| r.get(0),
+            )
+            .unwrap();
+        assert!(sql.contains("tokenize='trigram'"));
+    }
+
+    #[test]
+    fn test_migrate_v11_indexes_filter_queries() {
+        let conn = Connection::open_in_memory().unwrap();
+        init_db(&conn).unwrap();
+
+        let plan = |sql: &str| -> String {
+            let mut stmt = conn.prepare(&format!("EXPLAIN QUERY PLAN {}", sql)).unwrap();
+            let details = stmt
+                .query_map([], |row| row.get::<_, String>(3))
+                .unwrap()
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap();
+            details.join("\n")
+        };
+
+        let project = plan(
+            "SELECT id FROM conversations WHERE project_name = 'p' ORDER BY last_epoch DESC LIMIT 50",
+        );
+        assert!(project.contains("idx_conversations_project_last_epoch"), "{}", project);
+        assert!(!project.contains("TEMP B-TREE"), "{}", project);
+
+        let tags = plan("SELECT tag, COUNT(*) FROM conversation_tags GROUP BY tag ORDER BY tag ASC");
+        assert!(tags.contains("COVERING INDEX idx_conversation_tags_tag_conversation"), "{}", tags);
+
+        let tagged = plan("SELECT conversation_id FROM conversation_tags WHERE tag = 'rust'");
+        assert!(tagged.contains("COVERING INDEX"), "{}", tagged);
+    }
+
+    #[test]
+    fn test_migrate_v20_compresses_large_attachments() {
+        let conn = Connection::open_in_memory().unwrap();
+        init_db(&conn).unwrap();
+        let large = "iVBORw0KGgo".repeat(1_000);
+        for (id, data) in [("small", "iVBORw0KGgo="), ("large", large.as_str())] {
+            conn.execute(
+                "INSERT INTO attachments (id, media_type, data, size, created_at)
+                 VALUES (?1, 'image/png', ?2, 0, '')",
+                [id, data],
+            )
+            .unwrap();
+        }
+
+        migrate_v20_compressed_attachments(&conn).unwrap();
+
+        let types: Vec<String> = conn
+            .prepare("SELECT typeof(data) FROM attachments ORDER BY id")
+            .unwrap()
+            .query_map([], |r| r.get(0))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(types, vec!["blob", "text"]);
+        let attachment = crate::db::attachments::get(&conn, "large").unwrap().unwrap();
+        assert_eq!(attachment.data, large);
+    }
+
+    #[test]
+    fn test_migrate_v24_keeps_annotations_on_the_session() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("PRAGMA foreign_keys = ON;").unwrap();
+        init_db(&conn).unwrap();
+        let session_id = generate_conversation_id("s1", &[]);
+        for (id, file_path) in [
+            (session_id.as_str(), "/p/agent-1a2b.jsonl"),
+            ("other", "/p/s2.jsonl"),
+        ] {
+            conn.execute(
+                "INSERT INTO conversations (id, project_path, project_name, start_time, last_time, file_path, file_modified_at, session_id)
+                 VALUES (?1, '/p', 'p', '', '', ?2, 'then', 's1')",
+                [id, file_path],
+            )
+            .unwrap();
+        }
+        conn.execute(
+            "INSERT INTO bookmarks (conversation_id, created_at) VALUES (?1, 'now')",
+            [&session_id],
+        )
+        .unwrap();
+        // What the transcript's ingest derived stays until the re-ingest
+        conn.execute(
+            "INSERT INTO attachments (id, media_type, data, size, created_at) VALUES ('img-1', 'image/png', 'aGk=', 2, 'now')",
+            [],
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO attachment_refs (attachment_id, conversation_id) VALUES ('img-1', ?1)",
+            [&session_id],
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO conversation_issues (conversation_id, issue_key, source, created_at) VALUES (?1, 'ENG-42', 'detected', 'now')",
+            [&session_id],
+        )
+        .unwrap();
+
+        let tx = conn.unchecked_transaction().unwrap();
+        migrate_v24_subagents(&tx).unwrap();
+        tx.commit().unwrap();
+
+        let bookmarked: String = conn
+            .query_row("SELECT conversation_id FROM bookmarks", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(bookmarked, session_id);
+        let files: Vec<(String, String)> = conn
+            .prepare("SELECT id, file_path FROM conversations ORDER BY file_path")
+            .unwrap()
+            .query_map([], |r| Ok((r.get(0)?, r.get(1)?)))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(
+            files,
+            vec![
+                (session_id.clone(), String::new()),
+                ("other".to_string(), "/p/s2.jsonl".to_string()),
+            ]
+        );
+        let refs: i64 = conn
+            .query_row("SELECT COUNT(*) FROM attachment_refs", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(refs, 1);
+    }
+
+    #[test]
+    fn test_migrate_v24_reingest_splits_session_and_transcript() {
+        let dir = tempdir().unwrap();
+        let session_file = dir.path().join("s1.jsonl");
+        let agent_file = dir.path().join("agent-1a2b.jsonl");
+        fs::write(
+            &session_file,
+            r#"{"type":"user","message":{"content":"Review the parser"},"timestamp":"2025-01-15T10:00:00Z","uuid":"u-1","sessionId":"s1"}"#,
+        )
+        .unwrap();
+        fs::write(
+            &agent_file,
+            r#"{"type":"user","message":{"content":"Read parser.rs"},"timestamp":"2025-01-15T10:01:00Z","uuid":"g-1","sessionId":"s1","isSidechain":true}"#,
+        )
+        .unwrap();
+        let db = Database::open_in_memory().unwrap();
+        db.init_schema().unwrap();
+        // Before v24 the agent file, ingested last, owned the session's
+        // conversation, which the user bookmarked
+        let session_id = generate_conversation_id("s1", &[]);
+        db.with_connection(|conn| {
+            conn.execute(
+                "INSERT INTO conversations (id, project_path, project_name, start_time, last_time, file_path, file_modified_at, session_id)
+                 VALUES (?1, '/p', 'p', '', '', ?2, 'then', 's1')",
+                [&session_id, &agent_file.to_string_lossy().to_string()],
+            )?;
+            conn.execute(
+                "INSERT INTO bookmarks (conversation_id, created_at) VALUES (?1, 'now')",
+                [&session_id],
+            )?;
+            let tx = conn.unchecked_transaction()?;
+            migrate_v24_subagents(&tx)?;
+            tx.commit()?;
+            Ok(())
+        })
+        .unwrap();
+
+        // What the startup scan does
+        let files = vec![session_file.clone(), agent_file.clone()];
+        db.with_connection(|conn| reconcile_moved_files(conn, &files)).unwrap();
+        ingest_files(&db, &files, &NoProgress).unwrap();
+
+        let rows: Vec<(String, String, bool)> = db
+            .with_connection(|conn| {
+                let mut stmt = conn.prepare(
+                    "SELECT c.id, c.file_path, b.conversation_id IS NOT NULL
+                     FROM conversations c LEFT JOIN bookmarks b ON b.conversation_id = c.id
+                     ORDER BY c.file_path",
+                )?;
+                let rows = stmt.query_map([], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)))?;
+                Ok(rows.collect::<Result<_, _>>()?)
+            })
+            .unwrap();
+        assert_eq!(
+            rows,
+            vec![
+                (
+                    agent_conversation_id("s1", "agent-1a2b"),
+                    agent_file.to_string_lossy().to_string(),
+                    false
+                ),
+                (session_id, session_file.to_string_lossy().to_string(), true),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_run_migrations_idempotent() {
+        let conn = Connection::open_in_memory().unwrap();
+        init_db(&conn).unwrap();
+        run_migrations(&conn).unwrap();
+        run_migrations(&conn).unwrap();
+        assert_eq!(get_schema_version(&conn).unwrap(), SCHEMA_VERSION);
+    }
+}
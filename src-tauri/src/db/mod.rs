@@ -1,14 +1,41 @@
 //! Database operations and connection management.
 //!
 //! This module handles SQLite database initialization, connection pooling,
-//! schema creation, and CRUD operations for conversation data.
+//! schema creation, CRUD operations for conversation data, building
+//! statements from optional filters, the shared conversation filter
+//! conditions, keyset pagination of lists, the attachments store, issue
+//! links, the database figures of the health report, schema observations,
+//! compressed storage of large text, access errors of discovery, custom
+//! metadata fields of conversations, reading positions, the chains of
+//! resumed sessions, and sub-agent transcripts nested under their sessions.
 
+pub mod access;
+pub mod attachments;
+pub mod audit;
+pub mod children;
+pub mod continuations;
+pub mod custom_metadata;
+pub mod filters;
+pub mod health;
+pub mod identity;
+pub mod issues;
+pub mod location;
 pub mod metadata;
+pub mod migrations;
+pub mod observations;
+pub mod operations;
+pub mod query;
+pub mod read_position;
+pub mod retention;
+pub mod settings;
 pub mod sqlite;
+pub mod storage;
+pub mod window;
 
 pub use metadata::{
     clear_all_metadata, get_all_file_metadata, get_modified_files, is_metadata_empty,
-    remove_stale_metadata, update_file_metadata, update_file_metadata_batch, FileMetadata,
-    ModifiedFile,
+    remove_stale_metadata, set_ingest_status, update_file_metadata, update_file_metadata_batch,
+    FileMetadata, IngestStatus, ModifiedFile,
 };
+pub use settings::{load_settings, save_settings};
 pub use sqlite::{Database, DbError, DbResult, init_db};
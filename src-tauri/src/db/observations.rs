@@ -0,0 +1,90 @@
+//! Schema observations: structures in session files the parser doesn't
+//! know, counted across ingests (see [`crate::parser::schema`]).
+
+use crate::db::sqlite::DbResult;
+use crate::models::{SchemaObservation, SchemaObservationKind};
+use crate::parser::SchemaObservations;
+use chrono::Utc;
+use rusqlite::{params, Connection};
+use std::path::Path;
+
+/// Adds the observations of an ingested file to the recorded counts.
+pub fn record_observations(
+    conn: &Connection,
+    observations: &SchemaObservations,
+    file_path: &Path,
+) -> DbResult<()> {
+    if observations.is_empty() {
+        return Ok(());
+    }
+    let now = Utc::now().to_rfc3339();
+    let file = file_path.to_string_lossy();
+    let mut stmt = conn.prepare_cached(
+        "INSERT INTO schema_observations (kind, name, count, first_seen_at, last_seen_at, example_file)
+         VALUES (?1, ?2, ?3, ?4, ?4, ?5)
+         ON CONFLICT(kind, name) DO UPDATE SET
+             count = count + excluded.count,
+             last_seen_at = excluded.last_seen_at,
+             example_file = excluded.example_file",
+    )?;
+    for (kind, name, count) in observations.iter() {
+        stmt.execute(params![kind.as_str(), name, count as i64, now, file])?;
+    }
+    Ok(())
+}
+
+/// Lists the recorded observations, most frequent first.
+pub fn list_observations(conn: &Connection) -> DbResult<Vec<SchemaObservation>> {
+    let mut stmt = conn.prepare_cached(
+        "SELECT kind, name, count, first_seen_at, last_seen_at, example_file
+         FROM schema_observations
+         ORDER BY count DESC, kind, name",
+    )?;
+    let rows = stmt.query_map([], |row| {
+        Ok(SchemaObservation {
+            kind: SchemaObservationKind::from_db(&row.get::<_, String>(0)?),
+            name: row.get(1)?,
+            count: row.get::<_, i64>(2)? as u64,
+            first_seen_at: row.get(3)?,
+            last_seen_at: row.get(4)?,
+            example_file: row.get(5)?,
+        })
+    })?;
+    Ok(rows.collect::<Result<_, _>>()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::sqlite::init_db;
+    use crate::parser::ParsingProfile;
+    use serde_json::json;
+
+    #[test]
+    fn test_record_observations_accumulates_counts() {
+        let conn = Connection::open_in_memory().unwrap();
+        init_db(&conn).unwrap();
+        let profile = ParsingProfile::embedded();
+        let mut observations = SchemaObservations::default();
+        observations.observe_line(&json!({"type": "user", "hookInfos": []}), &profile);
+        observations.observe_line(&json!({"type": "pr-link", "hookInfos": []}), &profile);
+
+        record_observations(&conn, &observations, Path::new("/a.jsonl")).unwrap();
+        record_observations(&conn, &observations, Path::new("/b.jsonl")).unwrap();
+        record_observations(&conn, &SchemaObservations::default(), Path::new("/c.jsonl")).unwrap();
+
+        let recorded = list_observations(&conn).unwrap();
+        let summary: Vec<(SchemaObservationKind, &str, u64, &str)> = recorded
+            .iter()
+            .map(|o| (o.kind, o.name.as_str(), o.count, o.example_file.as_str()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (SchemaObservationKind::Field, "hookInfos", 4, "/b.jsonl"),
+                (SchemaObservationKind::LineType, "pr-link", 2, "/b.jsonl"),
+            ]
+        );
+        assert!(recorded[0].first_seen_at <= recorded[0].last_seen_at);
+    }
+}
@@ -0,0 +1,303 @@
+//! Undo and redo for tag and bookmark changes.
+//!
+//! Each change is recorded in `operation_log` with the annotation's state
+//! before and after, keyed by the window that made it, so every window has
+//! its own history. Undo reapplies the latest change's `before` state and
+//! redo its `after` state; a new change discards what could be redone.
+
+use crate::db::audit::record_annotation_change;
+use crate::db::sqlite::DbResult;
+use crate::models::{AnnotationState, AppliedOperation};
+use rusqlite::types::Type;
+use rusqlite::{params, Connection, OptionalExtension};
+
+/// Window used when the frontend doesn't say which one made a change.
+pub const DEFAULT_WINDOW_LABEL: &str = "main";
+
+/// Most operations kept per window; older ones can't be undone.
+pub const MAX_OPERATIONS_PER_WINDOW: i64 = 100;
+
+fn to_json(state: &AnnotationState) -> rusqlite::Result<String> {
+    serde_json::to_string(state).map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))
+}
+
+fn from_json(column: usize, json: &str) -> rusqlite::Result<AnnotationState> {
+    serde_json::from_str(json)
+        .map_err(|e| rusqlite::Error::FromSqlConversionFailure(column, Type::Text, Box::new(e)))
+}
+
+/// Reads the current tags of a conversation, sorted.
+pub fn current_tags(conn: &Connection, conversation_id: &str) -> DbResult<Vec<String>> {
+    let mut stmt =
+        conn.prepare("SELECT tag FROM conversation_tags WHERE conversation_id = ?1 ORDER BY tag")?;
+    let tags = stmt.query_map([conversation_id], |row| row.get(0))?;
+    Ok(tags.collect::<Result<_, _>>()?)
+}
+
+/// Checks whether a conversation is bookmarked.
+pub fn is_bookmarked(conn: &Connection, conversation_id: &str) -> DbResult<bool> {
+    let found = conn
+        .query_row(
+            "SELECT 1 FROM bookmarks WHERE conversation_id = ?1",
+            [conversation_id],
+            |_| Ok(()),
+        )
+        .optional()?;
+    Ok(found.is_some())
+}
+
+/// Reads the current state of the same annotation as `state`.
+fn current_state(
+    conn: &Connection,
+    conversation_id: &str,
+    state: &AnnotationState,
+) -> DbResult<AnnotationState> {
+    Ok(match state {
+        AnnotationState::Tags(_) => AnnotationState::Tags(current_tags(conn, conversation_id)?),
+        AnnotationState::Bookmarked(_) => {
+            AnnotationState::Bookmarked(is_bookmarked(conn, conversation_id)?)
+        }
+    })
+}
+
+/// Writes an annotation state.
+pub fn apply_state(conn: &Connection, conversation_id: &str, state: &AnnotationState) -> DbResult<()> {
+    let now = chrono::Utc::now().to_rfc3339();
+    match state {
+        AnnotationState::Tags(tags) => {
+            conn.execute(
+                "DELETE FROM conversation_tags WHERE conversation_id = ?1",
+                [conversation_id],
+            )?;
+            for tag in tags {
+                conn.execute(
+                    "INSERT INTO conversation_tags (conversation_id, tag, created_at) VALUES (?1, ?2, ?3)",
+                    params![conversation_id, tag, now],
+                )?;
+            }
+        }
+        AnnotationState::Bookmarked(true) => {
+            conn.execute(
+                "INSERT OR IGNORE INTO bookmarks (conversation_id, created_at) VALUES (?1, ?2)",
+                params![conversation_id, now],
+            )?;
+        }
+        AnnotationState::Bookmarked(false) => {
+            conn.execute(
+                "DELETE FROM bookmarks WHERE conversation_id = ?1",
+                [conversation_id],
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// Records a change made in `window`, discarding the window's redo history.
+///
+/// Changes that leave the state as it was aren't recorded.
+pub fn record_operation(
+    conn: &Connection,
+    window: &str,
+    conversation_id: &str,
+    before: &AnnotationState,
+    after: &AnnotationState,
+) -> DbResult<()> {
+    if before == after {
+        return Ok(());
+    }
+
+    conn.execute(
+        "DELETE FROM operation_log WHERE window_label = ?1 AND undone = 1",
+        [window],
+    )?;
+    conn.execute(
+        "INSERT INTO operation_log (window_label, conversation_id, before_state, after_state, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![
+            window,
+            conversation_id,
+            to_json(before)?,
+            to_json(after)?,
+            chrono::Utc::now().to_rfc3339()
+        ],
+    )?;
+    conn.execute(
+        "DELETE FROM operation_log WHERE window_label = ?1 AND id NOT IN
+         (SELECT id FROM operation_log WHERE window_label = ?1 ORDER BY id DESC LIMIT ?2)",
+        params![window, MAX_OPERATIONS_PER_WINDOW],
+    )?;
+    Ok(())
+}
+
+fn conversation_exists(conn: &Connection, conversation_id: &str) -> DbResult<bool> {
+    let found = conn
+        .query_row(
+            "SELECT 1 FROM conversations WHERE id = ?1",
+            [conversation_id],
+            |_| Ok(()),
+        )
+        .optional()?;
+    Ok(found.is_some())
+}
+
+/// Reapplies the next operation of `window` in one direction.
+///
+/// Operations on conversations deleted since are dropped and skipped.
+fn step(conn: &Connection, window: &str, undo: bool) -> DbResult<Option<AppliedOperation>> {
+    let sql = if undo {
+        "SELECT id, conversation_id, before_state FROM operation_log
+         WHERE window_label = ?1 AND undone = 0 ORDER BY id DESC LIMIT 1"
+    } else {
+        "SELECT id, conversation_id, after_state FROM operation_log
+         WHERE window_label = ?1 AND undone = 1 ORDER BY id ASC LIMIT 1"
+    };
+
+    loop {
+        let next: Option<(i64, String, String)> = conn
+            .query_row(sql, [window], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+            })
+            .optional()?;
+        let Some((id, conversation_id, json)) = next else {
+            return Ok(None);
+        };
+
+        if !conversation_exists(conn, &conversation_id)? {
+            conn.execute("DELETE FROM operation_log WHERE id = ?1", [id])?;
+            continue;
+        }
+
+        let state = from_json(2, &json)?;
+        let previous = current_state(conn, &conversation_id, &state)?;
+        apply_state(conn, &conversation_id, &state)?;
+        record_annotation_change(conn, &conversation_id, &previous, &state)?;
+        conn.execute(
+            "UPDATE operation_log SET undone = ?2 WHERE id = ?1",
+            params![id, undo],
+        )?;
+        return Ok(Some(AppliedOperation {
+            conversation_id,
+            state,
+        }));
+    }
+}
+
+/// Undoes the latest change made in `window`, if any.
+pub fn undo_last_operation(conn: &Connection, window: &str) -> DbResult<Option<AppliedOperation>> {
+    step(conn, window, true)
+}
+
+/// Redoes the latest change undone in `window`, if any.
+pub fn redo_operation(conn: &Connection, window: &str) -> DbResult<Option<AppliedOperation>> {
+    step(conn, window, false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::sqlite::init_db;
+
+    fn setup() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("PRAGMA foreign_keys = ON;").unwrap();
+        init_db(&conn).unwrap();
+        for id in ["c1", "c2"] {
+            conn.execute(
+                "INSERT INTO conversations (id, project_path, project_name, start_time, last_time, file_path, file_modified_at)
+                 VALUES (?1, '/p', 'p', '', '', '/p/s.jsonl', '')",
+                [id],
+            )
+            .unwrap();
+        }
+        conn
+    }
+
+    fn tags(values: &[&str]) -> AnnotationState {
+        AnnotationState::Tags(values.iter().map(|v| v.to_string()).collect())
+    }
+
+    /// Applies and records a change, like the commands do.
+    fn change(conn: &Connection, window: &str, id: &str, after: AnnotationState) {
+        let before = current_state(conn, id, &after).unwrap();
+        apply_state(conn, id, &after).unwrap();
+        record_operation(conn, window, id, &before, &after).unwrap();
+    }
+
+    #[test]
+    fn test_undo_and_redo() {
+        let conn = setup();
+        change(&conn, "main", "c1", tags(&["bug", "ui"]));
+        change(&conn, "main", "c1", tags(&[]));
+        change(&conn, "main", "c2", AnnotationState::Bookmarked(true));
+
+        let undone = undo_last_operation(&conn, "main").unwrap().unwrap();
+        assert_eq!(undone.conversation_id, "c2");
+        assert_eq!(undone.state, AnnotationState::Bookmarked(false));
+        assert!(!is_bookmarked(&conn, "c2").unwrap());
+
+        // "Remove all tags" is reversible
+        let undone = undo_last_operation(&conn, "main").unwrap().unwrap();
+        assert_eq!(undone.state, tags(&["bug", "ui"]));
+        assert_eq!(current_tags(&conn, "c1").unwrap(), vec!["bug", "ui"]);
+
+        let redone = redo_operation(&conn, "main").unwrap().unwrap();
+        assert_eq!(redone.state, tags(&[]));
+        assert!(current_tags(&conn, "c1").unwrap().is_empty());
+
+        let redone = redo_operation(&conn, "main").unwrap().unwrap();
+        assert_eq!(redone.state, AnnotationState::Bookmarked(true));
+        assert_eq!(redo_operation(&conn, "main").unwrap(), None);
+    }
+
+    #[test]
+    fn test_new_change_discards_redo() {
+        let conn = setup();
+        change(&conn, "main", "c1", tags(&["a"]));
+        undo_last_operation(&conn, "main").unwrap();
+        change(&conn, "main", "c1", tags(&["b"]));
+
+        assert_eq!(redo_operation(&conn, "main").unwrap(), None);
+        let undone = undo_last_operation(&conn, "main").unwrap().unwrap();
+        assert_eq!(undone.state, tags(&[]));
+        assert_eq!(undo_last_operation(&conn, "main").unwrap(), None);
+    }
+
+    #[test]
+    fn test_history_is_per_window() {
+        let conn = setup();
+        change(&conn, "main", "c1", tags(&["a"]));
+        change(&conn, "second", "c2", tags(&["b"]));
+
+        let undone = undo_last_operation(&conn, "main").unwrap().unwrap();
+        assert_eq!(undone.conversation_id, "c1");
+        assert_eq!(undo_last_operation(&conn, "main").unwrap(), None);
+        assert_eq!(current_tags(&conn, "c2").unwrap(), vec!["b"]);
+    }
+
+    #[test]
+    fn test_noop_changes_and_deleted_conversations_are_skipped() {
+        let conn = setup();
+        change(&conn, "main", "c1", tags(&["a"]));
+        change(&conn, "main", "c1", tags(&["a"]));
+        change(&conn, "main", "c2", tags(&["b"]));
+        conn.execute("DELETE FROM conversations WHERE id = 'c2'", [])
+            .unwrap();
+
+        let undone = undo_last_operation(&conn, "main").unwrap().unwrap();
+        assert_eq!(undone.conversation_id, "c1");
+        assert_eq!(undone.state, tags(&[]));
+        assert_eq!(undo_last_operation(&conn, "main").unwrap(), None);
+    }
+
+    #[test]
+    fn test_history_is_capped() {
+        let conn = setup();
+        for i in 0..MAX_OPERATIONS_PER_WINDOW + 5 {
+            change(&conn, "main", "c1", tags(&[&i.to_string()]));
+        }
+        let kept: i64 = conn
+            .query_row("SELECT COUNT(*) FROM operation_log", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(kept, MAX_OPERATIONS_PER_WINDOW);
+    }
+}
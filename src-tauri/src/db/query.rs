@@ -0,0 +1,122 @@
+//! Building SQL statements from optional conditions.
+//!
+//! Filters add SQL fragments with `?` placeholders and bind their values
+//! alongside, so the statement text depends only on which filters are set,
+//! never on their values. Statements are prepared through the connection's
+//! statement cache, so repeating a query with the same filters reuses the
+//! prepared statement.
+
+use rusqlite::{CachedStatement, Connection, ToSql};
+
+/// A SQL statement under construction and the values bound to it.
+pub struct QueryBuilder {
+    sql: String,
+    params: Vec<Box<dyn ToSql>>,
+}
+
+impl QueryBuilder {
+    /// Starts a statement with `sql`, which has no placeholders.
+    pub fn new(sql: &str) -> Self {
+        Self {
+            sql: sql.to_string(),
+            params: Vec::new(),
+        }
+    }
+
+    /// Appends SQL without placeholders.
+    pub fn push(&mut self, sql: &str) -> &mut Self {
+        self.sql.push_str(sql);
+        self
+    }
+
+    /// Appends SQL with one `?` placeholder and binds `value` to it.
+    pub fn push_bind(&mut self, sql: &str, value: impl ToSql + 'static) -> &mut Self {
+        self.sql.push_str(sql);
+        self.params.push(Box::new(value));
+        self
+    }
+
+    /// Binds a value to a numbered placeholder (`?1`) already in the SQL.
+    ///
+    /// Numbered placeholders must be bound before any `?` is pushed.
+    pub fn bind(&mut self, value: impl ToSql + 'static) -> &mut Self {
+        self.params.push(Box::new(value));
+        self
+    }
+
+    /// Replaces the value bound to the `index`th placeholder (from 0), to
+    /// run the statement again with another value.
+    pub fn rebind(&mut self, index: usize, value: impl ToSql + 'static) {
+        self.params[index] = Box::new(value);
+    }
+
+    /// The statement's SQL.
+    pub fn sql(&self) -> &str {
+        &self.sql
+    }
+
+    /// The bound values, in placeholder order.
+    pub fn params(&self) -> Vec<&dyn ToSql> {
+        self.params.iter().map(|p| p.as_ref()).collect()
+    }
+
+    /// Prepares the statement through the connection's statement cache.
+    pub fn prepare<'conn>(
+        &self,
+        conn: &'conn Connection,
+    ) -> rusqlite::Result<CachedStatement<'conn>> {
+        conn.prepare_cached(&self.sql)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_values_are_bound_not_inlined() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE t (name TEXT, n INTEGER);
+             INSERT INTO t VALUES ('a', 1), ('b', 2), ('it''s', 3);",
+        )
+        .unwrap();
+
+        let count = |name: &str, min: i64| {
+            let mut query = QueryBuilder::new("SELECT COUNT(*) FROM t WHERE 1=1");
+            query.push_bind(" AND name = ?", name.to_string());
+            query.push_bind(" AND n >= ?", min);
+            let mut stmt = query.prepare(&conn).unwrap();
+            let count: i64 = stmt
+                .query_row(query.params().as_slice(), |row| row.get(0))
+                .unwrap();
+            (query.sql().to_string(), count)
+        };
+
+        let (sql, found) = count("it's", 3);
+        assert_eq!(found, 1);
+        assert_eq!(
+            sql,
+            "SELECT COUNT(*) FROM t WHERE 1=1 AND name = ? AND n >= ?"
+        );
+        // Other values give the same statement text, so it's prepared once
+        assert_eq!(count("b", 3), (sql, 0));
+    }
+
+    #[test]
+    fn test_numbered_placeholders_and_rebind() {
+        let conn = Connection::open_in_memory().unwrap();
+        let mut query = QueryBuilder::new("SELECT ?1 + 0");
+        query.bind(1i64).push(" + 0");
+        let mut values = Vec::new();
+        for value in [1i64, 2, 3] {
+            query.rebind(0, value);
+            let mut stmt = query.prepare(&conn).unwrap();
+            values.push(
+                stmt.query_row(query.params().as_slice(), |row| row.get::<_, i64>(0))
+                    .unwrap(),
+            );
+        }
+        assert_eq!(values, vec![1, 2, 3]);
+    }
+}
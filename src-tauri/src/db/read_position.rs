@@ -0,0 +1,82 @@
+//! Reading positions of conversations.
+//!
+//! Long sessions are read over several sittings, so the frontend records
+//! the last message read in each conversation and `get_conversation`
+//! returns it to resume there. A position references its message by ID
+//! only: messages live in the session files, so one the file no longer
+//! has is dropped when the conversation is loaded.
+
+use super::sqlite::DbResult;
+use crate::models::ReadPosition;
+use rusqlite::{params, Connection, OptionalExtension};
+
+/// Reads where a conversation was last read up to.
+pub fn get_read_position(
+    conn: &Connection,
+    conversation_id: &str,
+) -> DbResult<Option<ReadPosition>> {
+    Ok(conn
+        .prepare_cached(
+            "SELECT message_id, updated_at FROM read_positions WHERE conversation_id = ?1",
+        )?
+        .query_row([conversation_id], |row| {
+            Ok(ReadPosition {
+                message_id: row.get(0)?,
+                updated_at: row.get(1)?,
+            })
+        })
+        .optional()?)
+}
+
+/// Records that a conversation was read up to `message_id`, replacing the
+/// previous position. Returns `None` if the conversation doesn't exist.
+pub fn set_read_position(
+    conn: &Connection,
+    conversation_id: &str,
+    message_id: &str,
+) -> DbResult<Option<ReadPosition>> {
+    let updated_at = chrono::Utc::now().to_rfc3339();
+    let inserted = conn.execute(
+        "INSERT INTO read_positions (conversation_id, message_id, updated_at)
+         SELECT id, ?2, ?3 FROM conversations WHERE id = ?1
+         ON CONFLICT(conversation_id) DO UPDATE SET
+             message_id = excluded.message_id, updated_at = excluded.updated_at",
+        params![conversation_id, message_id, updated_at],
+    )?;
+    Ok((inserted > 0).then(|| ReadPosition {
+        message_id: message_id.to_string(),
+        updated_at,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::sqlite::init_db;
+
+    #[test]
+    fn test_set_and_replace_read_position() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("PRAGMA foreign_keys = ON;").unwrap();
+        init_db(&conn).unwrap();
+        conn.execute(
+            "INSERT INTO conversations (id, project_path, project_name, start_time, last_time, file_path, file_modified_at)
+             VALUES ('c1', '/p', 'p', '2025-01-15T10:00:00Z', '2025-01-15T10:30:00Z', '/a.jsonl', '')",
+            [],
+        )
+        .unwrap();
+
+        assert_eq!(get_read_position(&conn, "c1").unwrap(), None);
+        assert_eq!(set_read_position(&conn, "missing", "m1").unwrap(), None);
+
+        set_read_position(&conn, "c1", "m1").unwrap().unwrap();
+        let replaced = set_read_position(&conn, "c1", "m7").unwrap().unwrap();
+        assert_eq!(replaced.message_id, "m7");
+        assert_eq!(get_read_position(&conn, "c1").unwrap(), Some(replaced));
+
+        // Positions go with their conversation
+        conn.execute("DELETE FROM conversations WHERE id = 'c1'", [])
+            .unwrap();
+        assert_eq!(get_read_position(&conn, "c1").unwrap(), None);
+    }
+}
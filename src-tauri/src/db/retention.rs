@@ -0,0 +1,243 @@
+//! Bulk archive and delete for retention workflows.
+//!
+//! Archiving sets `conversations.archived_at`, which hides a conversation
+//! from lists but keeps it searchable and exportable. Deleting removes the
+//! conversation and its bookmarks, tags, usage, index entries, and the
+//! attachments no other conversation uses, and records a tombstone in
+//! `deleted_conversations` so the next scan doesn't import it again from its
+//! (untouched) JSONL file.
+//!
+//! Protected conversations (`conversations.protected_at`) are exempt: both
+//! operations skip them, whatever the caller matched.
+
+use crate::db::audit;
+use crate::db::sqlite::DbResult;
+use crate::models::AuditAction;
+use crate::search::index::delete_index_entry;
+use rusqlite::{Connection, OptionalExtension};
+use sha2::{Digest, Sha256};
+
+/// Archives conversations that aren't archived or protected.
+///
+/// Returns how many were newly archived.
+pub fn archive_conversations(conn: &Connection, ids: &[String]) -> DbResult<usize> {
+    let now = chrono::Utc::now().to_rfc3339();
+    let mut stmt = conn.prepare(
+        "UPDATE conversations SET archived_at = ?1
+         WHERE id = ?2 AND archived_at IS NULL AND protected_at IS NULL",
+    )?;
+    let mut archived = 0;
+    for id in ids {
+        if stmt.execute([&now, id])? > 0 {
+            audit::record(conn, AuditAction::Archived, Some(id), "")?;
+            archived += 1;
+        }
+    }
+    Ok(archived)
+}
+
+/// Deletes conversations and tombstones them against re-import.
+///
+/// Returns how many were deleted; unknown and protected IDs are ignored.
+pub fn delete_conversations(conn: &Connection, ids: &[String]) -> DbResult<usize> {
+    let now = chrono::Utc::now().to_rfc3339();
+    let mut deleted = 0;
+    for id in ids {
+        let rowid: Option<i64> = conn
+            .query_row(
+                "SELECT rowid FROM conversations WHERE id = ?1 AND protected_at IS NULL",
+                [id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        let Some(rowid) = rowid else {
+            continue;
+        };
+
+        delete_index_entry(conn, rowid)?;
+        // Bookmarks, tags, usage, and attachment references cascade
+        conn.execute("DELETE FROM conversations WHERE id = ?1", [id])?;
+        conn.execute(
+            "INSERT OR REPLACE INTO deleted_conversations (conversation_id, deleted_at) VALUES (?1, ?2)",
+            [id, &now],
+        )?;
+        audit::record(conn, AuditAction::Deleted, Some(id), "")?;
+        deleted += 1;
+    }
+    Ok(deleted)
+}
+
+/// Protects a conversation from archiving and deletion, or removes the
+/// protection.
+///
+/// Returns `false` if the conversation doesn't exist.
+pub fn set_protected(conn: &Connection, id: &str, protected: bool) -> DbResult<bool> {
+    let updated = if protected {
+        conn.execute(
+            "UPDATE conversations SET protected_at = COALESCE(protected_at, ?2) WHERE id = ?1",
+            [id, &chrono::Utc::now().to_rfc3339()],
+        )?
+    } else {
+        conn.execute(
+            "UPDATE conversations SET protected_at = NULL WHERE id = ?1",
+            [id],
+        )?
+    };
+    if updated > 0 {
+        let action = if protected {
+            AuditAction::Protected
+        } else {
+            AuditAction::Unprotected
+        };
+        audit::record(conn, action, Some(id), "")?;
+    }
+    Ok(updated > 0)
+}
+
+/// Checks whether a conversation was deleted by the user.
+pub fn is_deleted(conn: &Connection, id: &str) -> DbResult<bool> {
+    let found = conn
+        .prepare_cached("SELECT 1 FROM deleted_conversations WHERE conversation_id = ?1")?
+        .query_row([id], |_| Ok(()))
+        .optional()?;
+    Ok(found.is_some())
+}
+
+/// Token confirming a bulk delete of exactly `ids` matched by `scope`.
+///
+/// A dry run returns it and the real run must pass it back, so the delete
+/// fails if the filters or the matching conversations changed in between.
+pub fn confirm_token(scope: &str, ids: &[String]) -> String {
+    let mut sorted: Vec<&String> = ids.iter().collect();
+    sorted.sort();
+
+    let mut hasher = Sha256::new();
+    hasher.update(scope.as_bytes());
+    for id in sorted {
+        hasher.update([0]);
+        hasher.update(id.as_bytes());
+    }
+    hasher
+        .finalize()
+        .iter()
+        .take(8)
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::sqlite::init_db;
+
+    fn setup() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("PRAGMA foreign_keys = ON;").unwrap();
+        init_db(&conn).unwrap();
+        for id in ["c1", "c2"] {
+            conn.execute(
+                "INSERT INTO conversations (id, project_path, project_name, start_time, last_time, file_path, file_modified_at)
+                 VALUES (?1, '/p', 'p', '2025-01-01T00:00:00Z', '2025-01-01T00:00:00Z', '/p/s.jsonl', '')",
+                [id],
+            )
+            .unwrap();
+            conn.execute(
+                "INSERT INTO conversations_fts(rowid, content, project_name)
+                 SELECT rowid, 'text', project_name FROM conversations WHERE id = ?1",
+                [id],
+            )
+            .unwrap();
+        }
+        conn.execute(
+            "INSERT INTO conversation_tags (conversation_id, tag, created_at) VALUES ('c1', 'old', '')",
+            [],
+        )
+        .unwrap();
+        conn
+    }
+
+    fn ids(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    fn count(conn: &Connection, sql: &str) -> i64 {
+        conn.query_row(sql, [], |row| row.get(0)).unwrap()
+    }
+
+    #[test]
+    fn test_archive_conversations() {
+        let conn = setup();
+        assert_eq!(
+            archive_conversations(&conn, &ids(&["c1", "missing"])).unwrap(),
+            1
+        );
+        // Already archived
+        assert_eq!(
+            archive_conversations(&conn, &ids(&["c1", "c2"])).unwrap(),
+            1
+        );
+        assert_eq!(
+            count(
+                &conn,
+                "SELECT COUNT(*) FROM conversations WHERE archived_at IS NOT NULL"
+            ),
+            2
+        );
+    }
+
+    #[test]
+    fn test_delete_conversations() {
+        let conn = setup();
+        assert_eq!(
+            delete_conversations(&conn, &ids(&["c1", "missing"])).unwrap(),
+            1
+        );
+
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM conversations"), 1);
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM conversation_tags"), 0);
+        assert_eq!(
+            count(
+                &conn,
+                "SELECT COUNT(*) FROM conversations_fts WHERE conversations_fts MATCH 'text'"
+            ),
+            1
+        );
+        assert!(is_deleted(&conn, "c1").unwrap());
+        assert!(!is_deleted(&conn, "c2").unwrap());
+        assert!(!is_deleted(&conn, "missing").unwrap());
+
+        let entries = audit::list_entries(&conn, None, 10).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].action, AuditAction::Deleted);
+        assert_eq!(entries[0].conversation_id.as_deref(), Some("c1"));
+    }
+
+    #[test]
+    fn test_protected_conversations_are_skipped() {
+        let conn = setup();
+        assert!(set_protected(&conn, "c1", true).unwrap());
+        assert!(!set_protected(&conn, "missing", true).unwrap());
+
+        assert_eq!(
+            archive_conversations(&conn, &ids(&["c1", "c2"])).unwrap(),
+            1
+        );
+        assert_eq!(delete_conversations(&conn, &ids(&["c1", "c2"])).unwrap(), 1);
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM conversations"), 1);
+        assert!(!is_deleted(&conn, "c1").unwrap());
+
+        assert!(set_protected(&conn, "c1", false).unwrap());
+        assert_eq!(delete_conversations(&conn, &ids(&["c1"])).unwrap(), 1);
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM conversations"), 0);
+    }
+
+    #[test]
+    fn test_confirm_token() {
+        let token = confirm_token("scope", &ids(&["a", "b"]));
+        assert_eq!(token.len(), 16);
+        assert_eq!(token, confirm_token("scope", &ids(&["b", "a"])));
+        assert_ne!(token, confirm_token("other", &ids(&["a", "b"])));
+        assert_ne!(token, confirm_token("scope", &ids(&["a"])));
+        assert_ne!(token, confirm_token("scope", &ids(&["ab"])));
+    }
+}
@@ -0,0 +1,246 @@
+//! Persistent application settings.
+//!
+//! Settings are stored as key/value rows in the `settings` table so new
+//! settings can be added without schema changes. Missing keys fall back to
+//! the defaults in [`AppSettings`].
+
+use crate::db::sqlite::DbResult;
+use crate::models::{AnsiMode, AppSettings, PreviewSource, SearchTokenizer, SessionConflictPolicy};
+use rusqlite::{params, Connection, OptionalExtension};
+
+/// Settings key for the date filter timezone.
+pub const TIMEZONE_KEY: &str = "timezone";
+
+/// Settings key for usage anomaly notifications (`"true"` or `"false"`).
+pub const ANOMALY_NOTIFICATIONS_KEY: &str = "anomaly_notifications";
+
+/// Settings key for what conversation previews are built from.
+pub const PREVIEW_SOURCE_KEY: &str = "preview_source";
+
+/// Settings key for the length of list previews.
+pub const PREVIEW_LENGTH_KEY: &str = "preview_length";
+
+/// Settings key for the typo-tolerant search fallback (`"true"` or `"false"`).
+pub const TYPO_TOLERANT_SEARCH_KEY: &str = "typo_tolerant_search";
+
+/// Settings key for the full-text index tokenizer.
+pub const SEARCH_TOKENIZER_KEY: &str = "search_tokenizer";
+
+/// Settings key for the search ranking recency half-life, in days.
+pub const RECENCY_HALF_LIFE_KEY: &str = "recency_half_life_days";
+
+/// Settings key for how ANSI escape sequences in tool results are handled.
+pub const ANSI_MODE_KEY: &str = "ansi_mode";
+
+/// Settings key for the Slack bot token; unset or empty when there's none.
+pub const SLACK_TOKEN_KEY: &str = "slack_token";
+
+/// Settings key for what happens when a session is found in two files.
+pub const SESSION_CONFLICT_POLICY_KEY: &str = "session_conflict_policy";
+
+/// Settings key for the locale dates in generated documents are written in.
+pub const LOCALE_KEY: &str = "locale";
+
+/// Settings key for the ignore patterns of discovery, as a JSON array.
+pub const IGNORE_PATTERNS_KEY: &str = "ignore_patterns";
+
+/// Settings key for the session file size limit in megabytes; empty when
+/// there's none.
+pub const MAX_SESSION_FILE_MB_KEY: &str = "max_session_file_mb";
+
+/// Settings key for the size in megabytes above which session files are
+/// stored partially; empty when they never are.
+pub const PARTIAL_STORAGE_MB_KEY: &str = "partial_storage_mb";
+
+/// Settings key for read-only mode (`"true"` or `"false"`).
+pub const READ_ONLY_KEY: &str = "read_only";
+
+/// Settings key for writing Spotlight stubs (`"true"` or `"false"`).
+pub const SPOTLIGHT_STUBS_KEY: &str = "spotlight_stubs";
+
+/// Reads a single setting value.
+pub fn get_setting(conn: &Connection, key: &str) -> DbResult<Option<String>> {
+    let value = conn
+        .query_row("SELECT value FROM settings WHERE key = ?1", [key], |row| row.get(0))
+        .optional()?;
+    Ok(value)
+}
+
+/// Inserts or replaces a single setting value.
+pub fn set_setting(conn: &Connection, key: &str, value: &str) -> DbResult<()> {
+    let now = chrono::Utc::now().to_rfc3339();
+    conn.execute(
+        r#"
+        INSERT INTO settings (key, value, updated_at) VALUES (?1, ?2, ?3)
+        ON CONFLICT(key) DO UPDATE SET value = excluded.value, updated_at = excluded.updated_at
+        "#,
+        params![key, value, now],
+    )?;
+    Ok(())
+}
+
+/// Loads all settings, using defaults for any that were never saved.
+pub fn load_settings(conn: &Connection) -> DbResult<AppSettings> {
+    let mut settings = AppSettings::default();
+    if let Some(timezone) = get_setting(conn, TIMEZONE_KEY)? {
+        settings.timezone = timezone;
+    }
+    if let Some(enabled) = get_setting(conn, ANOMALY_NOTIFICATIONS_KEY)? {
+        settings.anomaly_notifications = enabled == "true";
+    }
+    if let Some(source) = get_setting(conn, PREVIEW_SOURCE_KEY)? {
+        settings.preview_source = PreviewSource::from_db(&source);
+    }
+    if let Some(length) = get_setting(conn, PREVIEW_LENGTH_KEY)?.and_then(|v| v.parse().ok()) {
+        settings.preview_length = length;
+    }
+    if let Some(enabled) = get_setting(conn, TYPO_TOLERANT_SEARCH_KEY)? {
+        settings.typo_tolerant_search = enabled == "true";
+    }
+    if let Some(tokenizer) = get_setting(conn, SEARCH_TOKENIZER_KEY)? {
+        settings.search_tokenizer = SearchTokenizer::from_db(&tokenizer);
+    }
+    if let Some(days) = get_setting(conn, RECENCY_HALF_LIFE_KEY)?.and_then(|v| v.parse().ok()) {
+        settings.recency_half_life_days = days;
+    }
+    if let Some(mode) = get_setting(conn, ANSI_MODE_KEY)? {
+        settings.ansi_mode = AnsiMode::from_db(&mode);
+    }
+    settings.slack_token = get_setting(conn, SLACK_TOKEN_KEY)?.filter(|token| !token.is_empty());
+    if let Some(policy) = get_setting(conn, SESSION_CONFLICT_POLICY_KEY)? {
+        settings.session_conflict_policy = SessionConflictPolicy::from_db(&policy);
+    }
+    if let Some(locale) = get_setting(conn, LOCALE_KEY)? {
+        settings.locale = locale;
+    }
+    if let Some(patterns) =
+        get_setting(conn, IGNORE_PATTERNS_KEY)?.and_then(|v| serde_json::from_str(&v).ok())
+    {
+        settings.ignore_patterns = patterns;
+    }
+    settings.max_session_file_mb =
+        get_setting(conn, MAX_SESSION_FILE_MB_KEY)?.and_then(|v| v.parse().ok());
+    if let Some(mb) = get_setting(conn, PARTIAL_STORAGE_MB_KEY)? {
+        settings.partial_storage_mb = mb.parse().ok();
+    }
+    if let Some(enabled) = get_setting(conn, READ_ONLY_KEY)? {
+        settings.read_only = enabled == "true";
+    }
+    if let Some(enabled) = get_setting(conn, SPOTLIGHT_STUBS_KEY)? {
+        settings.spotlight_stubs = enabled == "true";
+    }
+    Ok(settings)
+}
+
+/// Persists all settings.
+pub fn save_settings(conn: &Connection, settings: &AppSettings) -> DbResult<()> {
+    set_setting(conn, TIMEZONE_KEY, &settings.timezone)?;
+    set_setting(
+        conn,
+        ANOMALY_NOTIFICATIONS_KEY,
+        if settings.anomaly_notifications { "true" } else { "false" },
+    )?;
+    set_setting(conn, PREVIEW_SOURCE_KEY, settings.preview_source.as_str())?;
+    set_setting(conn, PREVIEW_LENGTH_KEY, &settings.preview_length.to_string())?;
+    set_setting(
+        conn,
+        TYPO_TOLERANT_SEARCH_KEY,
+        if settings.typo_tolerant_search { "true" } else { "false" },
+    )?;
+    set_setting(conn, SEARCH_TOKENIZER_KEY, settings.search_tokenizer.as_str())?;
+    set_setting(
+        conn,
+        RECENCY_HALF_LIFE_KEY,
+        &settings.recency_half_life_days.to_string(),
+    )?;
+    set_setting(conn, ANSI_MODE_KEY, settings.ansi_mode.as_str())?;
+    set_setting(
+        conn,
+        SLACK_TOKEN_KEY,
+        settings.slack_token.as_deref().unwrap_or_default(),
+    )?;
+    set_setting(
+        conn,
+        SESSION_CONFLICT_POLICY_KEY,
+        settings.session_conflict_policy.as_str(),
+    )?;
+    set_setting(conn, LOCALE_KEY, &settings.locale)?;
+    let ignore_patterns = serde_json::to_string(&settings.ignore_patterns)
+        .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+    set_setting(conn, IGNORE_PATTERNS_KEY, &ignore_patterns)?;
+    set_setting(
+        conn,
+        MAX_SESSION_FILE_MB_KEY,
+        &settings
+            .max_session_file_mb
+            .map(|mb| mb.to_string())
+            .unwrap_or_default(),
+    )?;
+    set_setting(
+        conn,
+        PARTIAL_STORAGE_MB_KEY,
+        &settings
+            .partial_storage_mb
+            .map(|mb| mb.to_string())
+            .unwrap_or_default(),
+    )?;
+    set_setting(
+        conn,
+        READ_ONLY_KEY,
+        if settings.read_only { "true" } else { "false" },
+    )?;
+    set_setting(
+        conn,
+        SPOTLIGHT_STUBS_KEY,
+        if settings.spotlight_stubs { "true" } else { "false" },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::sqlite::init_db;
+
+    fn setup() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        init_db(&conn).unwrap();
+        conn
+    }
+
+    #[test]
+    fn test_defaults_when_unset() {
+        let conn = setup();
+        assert_eq!(load_settings(&conn).unwrap(), AppSettings::default());
+        assert_eq!(get_setting(&conn, TIMEZONE_KEY).unwrap(), None);
+    }
+
+    #[test]
+    fn test_save_and_load_round_trip() {
+        let conn = setup();
+        let settings = AppSettings {
+            timezone: "Europe/Berlin".to_string(),
+            anomaly_notifications: true,
+            database_path: None,
+            preview_source: PreviewSource::SubstantivePair,
+            preview_length: 120,
+            typo_tolerant_search: false,
+            search_tokenizer: SearchTokenizer::Trigram,
+            recency_half_life_days: 30,
+            ansi_mode: AnsiMode::Styled,
+            slack_token: Some("xoxb-1-2-abc".to_string()),
+            session_conflict_policy: SessionConflictPolicy::KeepBoth,
+            locale: "de-DE".to_string(),
+            ignore_patterns: vec!["*/archive/*".to_string()],
+            max_session_file_mb: Some(50),
+            partial_storage_mb: None,
+            read_only: true,
+            spotlight_stubs: true,
+        };
+        save_settings(&conn, &settings).unwrap();
+        assert_eq!(load_settings(&conn).unwrap(), settings);
+
+        // Saving again replaces the stored value
+        set_setting(&conn, TIMEZONE_KEY, "UTC").unwrap();
+        assert_eq!(load_settings(&conn).unwrap().timezone, "UTC");
+    }
+}
@@ -3,8 +3,11 @@
 //! This module provides database connectivity for storing conversation
 //! metadata and full-text search indexes.
 
+use crate::db::location::resolve_db_path;
+use crate::db::migrations::run_migrations;
 use rusqlite::{Connection, OpenFlags};
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Mutex;
 use thiserror::Error;
 use tracing::{debug, info, warn};
@@ -25,9 +28,36 @@ pub enum DbError {
     Io(#[from] std::io::Error),
 }
 
+impl DbError {
+    /// Stable code identifying the kind of error across the IPC boundary.
+    pub fn code(&self) -> &'static str {
+        match self {
+            DbError::AppDataNotFound => "app_data_not_found",
+            DbError::Sqlite(rusqlite::Error::QueryReturnedNoRows) => "not_found",
+            DbError::Sqlite(rusqlite::Error::SqliteFailure(e, _))
+                if matches!(
+                    e.code,
+                    rusqlite::ErrorCode::DatabaseBusy | rusqlite::ErrorCode::DatabaseLocked
+                ) =>
+            {
+                "database_locked"
+            }
+            DbError::Sqlite(_) => "database",
+            DbError::Locked(_) => "database_locked",
+            DbError::Io(_) => "io",
+        }
+    }
+}
+
 /// Result type for database operations.
 pub type DbResult<T> = Result<T, DbError>;
 
+/// Prepared statements kept in the connection's statement cache.
+///
+/// Enough for every statement of the commands, search, and ingest, plus the
+/// common combinations of search and list filters.
+pub const STATEMENT_CACHE_CAPACITY: usize = 128;
+
 /// Database connection manager.
 ///
 /// Provides a single connection with proper lifecycle management.
@@ -35,6 +65,8 @@ pub type DbResult<T> = Result<T, DbError>;
 pub struct Database {
     conn: Mutex<Connection>,
     path: PathBuf,
+    /// Launched with `--read-only` (see `commands::read_only`).
+    read_only: AtomicBool,
 }
 
 impl Database {
@@ -66,21 +98,42 @@ impl Database {
         // Enable foreign keys
         conn.execute_batch("PRAGMA foreign_keys=ON;")?;
 
+        conn.set_prepared_statement_cache_capacity(STATEMENT_CACHE_CAPACITY);
+
         info!("Database opened successfully at: {:?}", path);
 
         Ok(Self {
             conn: Mutex::new(conn),
             path,
+            read_only: AtomicBool::new(false),
+        })
+    }
+
+    /// Opens a private in-memory database.
+    ///
+    /// The database is discarded when dropped; used for demo mode and tests.
+    pub fn open_in_memory() -> DbResult<Self> {
+        let conn = Connection::open_in_memory()?;
+        conn.execute_batch("PRAGMA foreign_keys=ON;")?;
+        conn.set_prepared_statement_cache_capacity(STATEMENT_CACHE_CAPACITY);
+
+        debug!("Opened in-memory database");
+
+        Ok(Self {
+            conn: Mutex::new(conn),
+            path: PathBuf::from(":memory:"),
+            read_only: AtomicBool::new(false),
         })
     }
 
-    /// Opens or creates the database in the default app data directory.
+    /// Opens or creates the database at its configured location.
     ///
-    /// The database file is created at `{app_data}/conversations.db`.
+    /// The database file defaults to `{app_data}/conversations.db`; it can be
+    /// moved with the `CLAUDE_HISTORY_DB` environment variable, portable mode,
+    /// or the database location setting (see [`crate::db::location`]).
     pub fn open_default() -> DbResult<Self> {
         let app_data_dir = get_app_data_dir()?;
-        let db_path = app_data_dir.join("conversations.db");
-        Self::open(db_path)
+        Self::open(resolve_db_path(&app_data_dir))
     }
 
     /// Returns the database file path.
@@ -88,6 +141,17 @@ impl Database {
         &self.path
     }
 
+    /// Puts the app in read-only mode for this session, whatever the
+    /// read-only setting says.
+    pub fn set_read_only(&self, read_only: bool) {
+        self.read_only.store(read_only, Ordering::Relaxed);
+    }
+
+    /// Whether the app was put in read-only mode for this session.
+    pub fn is_read_only(&self) -> bool {
+        self.read_only.load(Ordering::Relaxed)
+    }
+
     /// Executes a function with the database connection.
     ///
     /// This provides thread-safe access to the connection.
@@ -132,7 +196,7 @@ impl Database {
 /// On macOS: `~/Library/Application Support/com.claudecode.history-viewer`
 /// On Windows: `%APPDATA%\com.claudecode.history-viewer`
 /// On Linux: `~/.local/share/com.claudecode.history-viewer`
-fn get_app_data_dir() -> DbResult<PathBuf> {
+pub fn get_app_data_dir() -> DbResult<PathBuf> {
     let base_dir = dirs::data_dir().ok_or(DbError::AppDataNotFound)?;
     Ok(base_dir.join("com.claudecode.history-viewer"))
 }
@@ -154,11 +218,35 @@ pub fn init_db(conn: &Connection) -> DbResult<()> {
             start_time TEXT NOT NULL,
             last_time TEXT NOT NULL,
             preview TEXT NOT NULL DEFAULT '',
+            -- Longer, multi-line preview for tooltips
+            preview_medium TEXT NOT NULL DEFAULT '',
             message_count INTEGER NOT NULL DEFAULT 0,
             total_input_tokens INTEGER NOT NULL DEFAULT 0,
             total_output_tokens INTEGER NOT NULL DEFAULT 0,
             file_path TEXT NOT NULL,
-            file_modified_at TEXT NOT NULL
+            file_modified_at TEXT NOT NULL,
+            session_id TEXT NOT NULL DEFAULT '',
+            -- When the user archived the conversation, if they did
+            archived_at TEXT,
+            -- When the user protected the conversation from archiving and
+            -- deletion, if they did
+            protected_at TEXT,
+            -- How the conversation ended: 'resolved', 'unresolved', or 'unknown'
+            resolution TEXT NOT NULL DEFAULT 'unknown',
+            -- 1 if the session file was large when ingested, so tool results
+            -- are shortened when the conversation is loaded
+            partial_storage INTEGER NOT NULL DEFAULT 0,
+            -- 1 once the user asked to load the conversation in full anyway
+            full_load_requested INTEGER NOT NULL DEFAULT 0,
+            -- SHA-256 of the messages when ingested (see parser::content_hash)
+            content_hash TEXT NOT NULL DEFAULT '',
+            -- 1 if it's the transcript of a sub-agent (see db::children)
+            is_subagent INTEGER NOT NULL DEFAULT 0,
+            -- Working directory of the session, '' if it isn't recorded
+            cwd TEXT NOT NULL DEFAULT '',
+            -- Derived Unix epoch milliseconds for sorting and date filtering
+            start_epoch INTEGER GENERATED ALWAYS AS (CAST(ROUND((julianday(start_time) - 2440587.5) * 86400000.0) AS INTEGER)) VIRTUAL,
+            last_epoch INTEGER GENERATED ALWAYS AS (CAST(ROUND((julianday(last_time) - 2440587.5) * 86400000.0) AS INTEGER)) VIRTUAL
         );
 
         -- Indexes for common queries
@@ -179,19 +267,38 @@ pub fn init_db(conn: &Connection) -> DbResult<()> {
         CREATE TABLE IF NOT EXISTS file_metadata (
             file_path TEXT PRIMARY KEY NOT NULL,
             modified_at TEXT NOT NULL,
-            parsed_at TEXT NOT NULL
+            parsed_at TEXT NOT NULL,
+            -- Ingest checkpoint: pending, processing, or done
+            status TEXT NOT NULL DEFAULT 'done'
         );
         "#,
     )?;
 
     // Create FTS5 virtual table for full-text search
     // Uses content="" for external content mode - we manage content ourselves
-    // This indexes conversation content and project names for fast searching
+    // This indexes conversation content and project names for fast searching;
+    // project_key partitions it by project (see search::partition)
     conn.execute_batch(
         r#"
         CREATE VIRTUAL TABLE IF NOT EXISTS conversations_fts USING fts5(
             content,
             project_name,
+            project_key,
+            content='',
+            contentless_delete=1
+        );
+        "#,
+    )?;
+
+    // Secondary trigram index over the same text, kept in sync with
+    // conversations_fts. Used as a typo-tolerant fallback when a search
+    // finds few results.
+    conn.execute_batch(
+        r#"
+        CREATE VIRTUAL TABLE IF NOT EXISTS conversations_trigram USING fts5(
+            content,
+            project_name,
+            tokenize='trigram',
             content='',
             contentless_delete=1
         );
@@ -224,14 +331,374 @@ pub fn init_db(conn: &Connection) -> DbResult<()> {
             FOREIGN KEY (conversation_id) REFERENCES conversations(id) ON DELETE CASCADE
         );
 
-        -- Index for efficient tag lookups
+        -- Index for efficient tag lookups (migration v11 indexes tags by
+        -- name)
         CREATE INDEX IF NOT EXISTS idx_conversation_tags_conversation_id
             ON conversation_tags(conversation_id);
-        CREATE INDEX IF NOT EXISTS idx_conversation_tags_tag
-            ON conversation_tags(tag);
         "#,
     )?;
 
+    // Create deleted_conversations table: tombstones for conversations the
+    // user deleted, so rescans don't import them again
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS deleted_conversations (
+            conversation_id TEXT PRIMARY KEY NOT NULL,
+            deleted_at TEXT NOT NULL
+        );
+        "#,
+    )?;
+
+    // Create operation_log table: tag and bookmark changes per window, for
+    // undo and redo
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS operation_log (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            window_label TEXT NOT NULL,
+            conversation_id TEXT NOT NULL,
+            -- AnnotationState JSON before and after the change
+            before_state TEXT NOT NULL,
+            after_state TEXT NOT NULL,
+            undone INTEGER NOT NULL DEFAULT 0,
+            created_at TEXT NOT NULL
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_operation_log_window
+            ON operation_log(window_label, id);
+        "#,
+    )?;
+
+    // Create audit_log table: who changed which user data when. No foreign
+    // key, so entries outlive deleted conversations
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS audit_log (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            created_at TEXT NOT NULL,
+            actor TEXT NOT NULL,
+            action TEXT NOT NULL,
+            conversation_id TEXT,
+            details TEXT NOT NULL DEFAULT ''
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_audit_log_conversation_id
+            ON audit_log(conversation_id);
+        "#,
+    )?;
+
+    // Create search_synonyms table for user-defined query expansions
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS search_synonyms (
+            term TEXT NOT NULL,
+            synonym TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            PRIMARY KEY (term, synonym)
+        );
+        "#,
+    )?;
+
+    // Create settings table for user preferences (key/value)
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS settings (
+            key TEXT PRIMARY KEY NOT NULL,
+            value TEXT NOT NULL,
+            updated_at TEXT NOT NULL
+        );
+        "#,
+    )?;
+
+    // Create usage tables: token usage per UTC day and model, tool calls per
+    // UTC day and tool, and code blocks per UTC day and language, recorded
+    // when a conversation is ingested
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS usage_daily (
+            conversation_id TEXT NOT NULL,
+            day TEXT NOT NULL,
+            model TEXT NOT NULL,
+            messages INTEGER NOT NULL DEFAULT 0,
+            input_tokens INTEGER NOT NULL DEFAULT 0,
+            output_tokens INTEGER NOT NULL DEFAULT 0,
+            cache_creation_tokens INTEGER NOT NULL DEFAULT 0,
+            cache_read_tokens INTEGER NOT NULL DEFAULT 0,
+            response_millis INTEGER NOT NULL DEFAULT 0,
+            timed_responses INTEGER NOT NULL DEFAULT 0,
+            PRIMARY KEY (conversation_id, day, model),
+            FOREIGN KEY (conversation_id) REFERENCES conversations(id) ON DELETE CASCADE
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_usage_daily_day
+            ON usage_daily(day);
+
+        CREATE TABLE IF NOT EXISTS tool_usage (
+            conversation_id TEXT NOT NULL,
+            day TEXT NOT NULL,
+            tool_name TEXT NOT NULL,
+            calls INTEGER NOT NULL DEFAULT 0,
+            errors INTEGER NOT NULL DEFAULT 0,
+            PRIMARY KEY (conversation_id, day, tool_name),
+            FOREIGN KEY (conversation_id) REFERENCES conversations(id) ON DELETE CASCADE
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_tool_usage_day
+            ON tool_usage(day);
+
+        CREATE TABLE IF NOT EXISTS language_usage (
+            conversation_id TEXT NOT NULL,
+            day TEXT NOT NULL,
+            language TEXT NOT NULL,
+            blocks INTEGER NOT NULL DEFAULT 0,
+            lines INTEGER NOT NULL DEFAULT 0,
+            PRIMARY KEY (conversation_id, day, language),
+            FOREIGN KEY (conversation_id) REFERENCES conversations(id) ON DELETE CASCADE
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_language_usage_day
+            ON language_usage(day);
+        "#,
+    )?;
+
+    // Create attachments table for images extracted from tool results, keyed
+    // by the hash of their data. Shared between conversations, so no foreign
+    // key; attachment_refs counts the conversations using each, and the
+    // trigger removes an attachment once none does
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS attachments (
+            id TEXT PRIMARY KEY NOT NULL,
+            media_type TEXT NOT NULL,
+            data TEXT NOT NULL,
+            size INTEGER NOT NULL,
+            created_at TEXT NOT NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS attachment_refs (
+            attachment_id TEXT NOT NULL,
+            conversation_id TEXT NOT NULL REFERENCES conversations(id) ON DELETE CASCADE,
+            PRIMARY KEY (attachment_id, conversation_id)
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_attachment_refs_conversation_id
+            ON attachment_refs(conversation_id);
+
+        CREATE TRIGGER IF NOT EXISTS attachment_refs_ad AFTER DELETE ON attachment_refs
+        WHEN NOT EXISTS (SELECT 1 FROM attachment_refs WHERE attachment_id = old.attachment_id)
+        BEGIN
+            DELETE FROM attachments WHERE id = old.attachment_id;
+        END;
+        "#,
+    )?;
+
+    // Create webhooks table and the log of their deliveries. Deliveries
+    // aren't tied to conversations, so the log outlives deleted ones
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS webhooks (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            url TEXT NOT NULL,
+            -- ConversationFilters as JSON
+            filters TEXT NOT NULL DEFAULT '{}',
+            -- Comma-separated WebhookEvent values
+            events TEXT NOT NULL,
+            enabled INTEGER NOT NULL DEFAULT 1,
+            created_at TEXT NOT NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS webhook_deliveries (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            webhook_id INTEGER NOT NULL REFERENCES webhooks(id) ON DELETE CASCADE,
+            conversation_id TEXT NOT NULL,
+            event TEXT NOT NULL,
+            payload TEXT NOT NULL,
+            status TEXT NOT NULL DEFAULT 'pending',
+            attempts INTEGER NOT NULL DEFAULT 0,
+            -- Unix epoch milliseconds of the next attempt, while pending
+            next_attempt_at INTEGER,
+            response_status INTEGER,
+            last_error TEXT,
+            created_at TEXT NOT NULL,
+            delivered_at TEXT
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_webhook_deliveries_due
+            ON webhook_deliveries(status, next_attempt_at);
+        CREATE INDEX IF NOT EXISTS idx_webhook_deliveries_webhook_id
+            ON webhook_deliveries(webhook_id);
+
+        CREATE TABLE IF NOT EXISTS conversation_issues (
+            conversation_id TEXT NOT NULL REFERENCES conversations(id) ON DELETE CASCADE,
+            issue_key TEXT NOT NULL,
+            url TEXT,
+            -- 'linked' by the user or 'detected' in the conversation text
+            source TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            PRIMARY KEY (conversation_id, issue_key)
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_conversation_issues_key
+            ON conversation_issues(issue_key);
+
+        CREATE TABLE IF NOT EXISTS prompts (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            title TEXT NOT NULL,
+            body TEXT NOT NULL,
+            -- Message the prompt was saved from; not foreign keys, so
+            -- prompts outlive deleted conversations
+            source_conversation_id TEXT,
+            source_message_id TEXT,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL
+        );
+
+        -- Text of each user message, for searching questions apart from
+        -- the answers. questions_fts indexes it; the triggers keep it in sync.
+        CREATE TABLE IF NOT EXISTS user_questions (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            conversation_id TEXT NOT NULL REFERENCES conversations(id) ON DELETE CASCADE,
+            message_id TEXT NOT NULL,
+            position INTEGER NOT NULL,
+            text TEXT NOT NULL
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_user_questions_conversation_id
+            ON user_questions(conversation_id);
+
+        CREATE VIRTUAL TABLE IF NOT EXISTS questions_fts USING fts5(
+            text,
+            content='user_questions',
+            content_rowid='id'
+        );
+
+        CREATE TRIGGER IF NOT EXISTS user_questions_ai AFTER INSERT ON user_questions BEGIN
+            INSERT INTO questions_fts(rowid, text) VALUES (new.id, new.text);
+        END;
+        CREATE TRIGGER IF NOT EXISTS user_questions_ad AFTER DELETE ON user_questions BEGIN
+            INSERT INTO questions_fts(questions_fts, rowid, text) VALUES ('delete', old.id, old.text);
+        END;
+
+        -- Fields, block types, and line types seen in session files that
+        -- the parsing profile doesn't know (see parser::schema)
+        CREATE TABLE IF NOT EXISTS schema_observations (
+            kind TEXT NOT NULL,
+            name TEXT NOT NULL,
+            count INTEGER NOT NULL,
+            first_seen_at TEXT NOT NULL,
+            last_seen_at TEXT NOT NULL,
+            example_file TEXT NOT NULL,
+            PRIMARY KEY (kind, name)
+        );
+
+        -- Paths the last scan couldn't read, retried with backoff (see
+        -- db::access); next_retry_at is in epoch milliseconds
+        CREATE TABLE IF NOT EXISTS access_errors (
+            path TEXT PRIMARY KEY,
+            error TEXT NOT NULL,
+            attempts INTEGER NOT NULL,
+            first_failed_at TEXT NOT NULL,
+            last_failed_at TEXT NOT NULL,
+            next_retry_at INTEGER NOT NULL
+        );
+
+        -- Encrypted share bundles written to app data, until revoked or
+        -- expired (see share); expires_at is in epoch milliseconds
+        CREATE TABLE IF NOT EXISTS share_bundles (
+            id TEXT PRIMARY KEY,
+            conversation_id TEXT NOT NULL,
+            path TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            expires_at INTEGER NOT NULL
+        );
+
+        -- Named workspaces (see workspaces); filters, pinned, and open_tabs
+        -- are JSON
+        CREATE TABLE IF NOT EXISTS workspaces (
+            name TEXT PRIMARY KEY COLLATE NOCASE,
+            filters TEXT NOT NULL,
+            sort TEXT NOT NULL,
+            pinned TEXT NOT NULL,
+            open_tabs TEXT NOT NULL,
+            active_tab TEXT,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL,
+            last_opened_at TEXT
+        );
+
+        -- Custom metadata fields of conversations, such as customer=acme
+        -- (see db::custom_metadata); keys are lowercase
+        CREATE TABLE IF NOT EXISTS conversation_metadata (
+            conversation_id TEXT NOT NULL,
+            key TEXT NOT NULL,
+            value TEXT NOT NULL,
+            updated_at TEXT NOT NULL,
+            PRIMARY KEY (conversation_id, key),
+            FOREIGN KEY (conversation_id) REFERENCES conversations(id) ON DELETE CASCADE
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_conversation_metadata_key_value
+            ON conversation_metadata(key, value COLLATE NOCASE);
+
+        -- Follow-up reminders, one per conversation (see reminders); due_at
+        -- is in epoch milliseconds
+        CREATE TABLE IF NOT EXISTS reminders (
+            conversation_id TEXT PRIMARY KEY NOT NULL,
+            due_at INTEGER NOT NULL,
+            note TEXT,
+            created_at TEXT NOT NULL,
+            notified_at TEXT,
+            FOREIGN KEY (conversation_id) REFERENCES conversations(id) ON DELETE CASCADE
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_reminders_due_at ON reminders(due_at);
+
+        -- Where reading left off in each conversation (see db::read_position)
+        CREATE TABLE IF NOT EXISTS read_positions (
+            conversation_id TEXT PRIMARY KEY NOT NULL,
+            message_id TEXT NOT NULL,
+            updated_at TEXT NOT NULL,
+            FOREIGN KEY (conversation_id) REFERENCES conversations(id) ON DELETE CASCADE
+        );
+
+        -- UUIDs of each conversation's messages, and of the earlier messages
+        -- it continues from, linking resumed sessions into chains (see
+        -- db::continuations); both are replaced when a file is ingested
+        CREATE TABLE IF NOT EXISTS message_uuids (
+            conversation_id TEXT NOT NULL REFERENCES conversations(id) ON DELETE CASCADE,
+            uuid TEXT NOT NULL,
+            -- 1 for the conversation's last message
+            is_leaf INTEGER NOT NULL DEFAULT 0,
+            PRIMARY KEY (conversation_id, uuid)
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_message_uuids_uuid ON message_uuids(uuid);
+
+        CREATE TABLE IF NOT EXISTS continuation_refs (
+            conversation_id TEXT NOT NULL REFERENCES conversations(id) ON DELETE CASCADE,
+            uuid TEXT NOT NULL,
+            PRIMARY KEY (conversation_id, uuid)
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_continuation_refs_uuid ON continuation_refs(uuid);
+
+        -- Sub-agent transcripts nested under the session that invoked them
+        -- (see db::children): 'detected' at ingest or 'attached' by the user
+        CREATE TABLE IF NOT EXISTS conversation_children (
+            child_id TEXT PRIMARY KEY NOT NULL REFERENCES conversations(id) ON DELETE CASCADE,
+            parent_id TEXT NOT NULL REFERENCES conversations(id) ON DELETE CASCADE,
+            source TEXT NOT NULL,
+            created_at TEXT NOT NULL
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_conversation_children_parent_id
+            ON conversation_children(parent_id);
+        "#,
+    )?;
+
+    // Bring databases created by older versions up to date
+    run_migrations(conn)?;
+
     info!("Database schema initialized successfully");
     Ok(())
 }
@@ -280,6 +747,12 @@ mod tests {
             let exists: bool = stmt.exists([]).unwrap();
             assert!(exists, "conversations_fts FTS5 table should exist");
 
+            let mut stmt = conn
+                .prepare("SELECT name FROM sqlite_master WHERE type='table' AND name='conversations_trigram'")
+                .unwrap();
+            let exists: bool = stmt.exists([]).unwrap();
+            assert!(exists, "conversations_trigram FTS5 table should exist");
+
             Ok(())
         })
         .unwrap();
@@ -0,0 +1,125 @@
+//! Compressed storage of large text columns.
+//!
+//! Messages aren't stored: they're read from the session files on demand.
+//! The large content the database does hold is tool result output moved out
+//! of the messages, such as the screenshots in `attachments`, and
+//! [`StoredText`] keeps it zstd-compressed.
+//!
+//! Text up to `COMPRESSION_THRESHOLD` bytes, or text that doesn't get
+//! smaller, is stored as is, as `TEXT`. Larger text is stored as a `BLOB` of
+//! zstd frames. SQLite columns take either, so reading tells the two apart
+//! by the value's type, and rows written before compression stay readable.
+
+use rusqlite::types::{FromSql, FromSqlError, FromSqlResult, ToSql, ToSqlOutput, ValueRef};
+use std::ops::Deref;
+
+/// Text longer than this (in bytes) is compressed.
+pub const COMPRESSION_THRESHOLD: usize = 4 * 1024;
+
+/// zstd level of compressed text; 0 selects zstd's default.
+const COMPRESSION_LEVEL: i32 = 0;
+
+/// Text stored compressed when it's large. Writes and reads like a
+/// `String` column.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct StoredText(pub String);
+
+impl StoredText {
+    /// Returns the text.
+    pub fn into_inner(self) -> String {
+        self.0
+    }
+}
+
+impl Deref for StoredText {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<String> for StoredText {
+    fn from(text: String) -> Self {
+        Self(text)
+    }
+}
+
+/// Compresses `text` if it's large and gets smaller.
+pub fn compress(text: &str) -> std::io::Result<Option<Vec<u8>>> {
+    if text.len() <= COMPRESSION_THRESHOLD {
+        return Ok(None);
+    }
+    let compressed = zstd::bulk::compress(text.as_bytes(), COMPRESSION_LEVEL)?;
+    Ok((compressed.len() < text.len()).then_some(compressed))
+}
+
+/// Decompresses text written by [`compress`].
+pub fn decompress(data: &[u8]) -> std::io::Result<String> {
+    let bytes = zstd::decode_all(data)?;
+    String::from_utf8(bytes).map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+}
+
+impl ToSql for StoredText {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        match compress(&self.0).map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))? {
+            Some(compressed) => Ok(ToSqlOutput::from(compressed)),
+            None => Ok(ToSqlOutput::from(self.0.as_str())),
+        }
+    }
+}
+
+impl FromSql for StoredText {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        match value {
+            ValueRef::Blob(data) => decompress(data)
+                .map(StoredText)
+                .map_err(|e| FromSqlError::Other(Box::new(e))),
+            value => String::column_result(value).map(StoredText),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusqlite::Connection;
+
+    #[test]
+    fn test_stored_text_round_trip() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute("CREATE TABLE t (id INTEGER, data TEXT NOT NULL)", [])
+            .unwrap();
+
+        let small = StoredText("short output".to_string());
+        let large = StoredText("cargo build\n".repeat(2_000));
+        for (id, text) in [(1, &small), (2, &large)] {
+            conn.execute(
+                "INSERT INTO t (id, data) VALUES (?1, ?2)",
+                rusqlite::params![id, text],
+            )
+            .unwrap();
+        }
+        conn.execute("INSERT INTO t (id, data) VALUES (3, ?1)", [&*large.0])
+            .unwrap();
+
+        let stored: Vec<(String, i64)> = conn
+            .prepare("SELECT typeof(data), length(CAST(data AS BLOB)) FROM t ORDER BY id")
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(stored[0].0, "text");
+        assert_eq!(stored[1].0, "blob");
+        assert!(stored[1].1 * 10 < large.len() as i64);
+
+        // Compressed and uncompressed rows read back the same
+        for (id, expected) in [(1, &small), (2, &large), (3, &large)] {
+            let text: StoredText = conn
+                .query_row("SELECT data FROM t WHERE id = ?1", [id], |row| row.get(0))
+                .unwrap();
+            assert_eq!(&text, expected);
+        }
+    }
+}
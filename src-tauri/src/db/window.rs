@@ -0,0 +1,158 @@
+//! Keyset checkpoints for windows of conversation lists.
+//!
+//! Virtual lists read conversations by index range. Reading a range with
+//! `OFFSET` makes SQLite step over every row before it, which gets slow deep
+//! into a long list. Instead, as a list is read, the sort key of the row
+//! before every `CHECKPOINT_INTERVAL`th index is remembered; a window then
+//! seeks past the nearest checkpoint through the index and steps over at
+//! most the rows after it. Positions shift when the database changes, so
+//! checkpoints (and list totals) are dropped whenever it does.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+
+/// Rows between checkpoints.
+pub const CHECKPOINT_INTERVAL: u32 = 200;
+
+/// Most lists (filter and sort combinations) kept; when another list would
+/// exceed it, all are dropped.
+pub const MAX_LISTS: usize = 16;
+
+/// Sort key of a conversation in a list: `last_epoch`, then `id`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Keyset {
+    /// NULL for unparseable last activity times.
+    pub last_epoch: Option<i64>,
+    pub id: String,
+}
+
+#[derive(Default)]
+struct List {
+    /// Number of conversations in the list.
+    total: Option<u32>,
+    /// Key of the row before each checkpointed index.
+    checkpoints: BTreeMap<u32, Keyset>,
+}
+
+#[derive(Default)]
+struct Lists {
+    /// Database change count the lists were read at.
+    version: u64,
+    lists: HashMap<String, List>,
+}
+
+/// Checkpoints and totals of conversation lists, by list.
+///
+/// `version` arguments are the database's change count
+/// (`Connection::total_changes`); a new version drops everything.
+pub struct ListCheckpoints {
+    interval: u32,
+    lists: Mutex<Lists>,
+}
+
+impl Default for ListCheckpoints {
+    fn default() -> Self {
+        Self::new(CHECKPOINT_INTERVAL)
+    }
+}
+
+impl ListCheckpoints {
+    /// Creates checkpoints every `interval` rows.
+    pub fn new(interval: u32) -> Self {
+        Self {
+            interval: interval.max(1),
+            lists: Mutex::new(Lists::default()),
+        }
+    }
+
+    /// Returns the nearest checkpoint at or before `index`, with the key
+    /// of the row before it.
+    pub fn nearest(&self, list: &str, version: u64, index: u32) -> Option<(u32, Keyset)> {
+        self.with_list(list, version, |list| {
+            list.checkpoints
+                .range(..=index)
+                .next_back()
+                .map(|(index, key)| (*index, key.clone()))
+        })
+    }
+
+    /// Records the key of the row at `index`, if the next index is a
+    /// checkpoint.
+    pub fn record(&self, list: &str, version: u64, index: u32, key: &Keyset) {
+        let next = index + 1;
+        if next.is_multiple_of(self.interval) {
+            self.with_list(list, version, |list| {
+                list.checkpoints.insert(next, key.clone());
+            });
+        }
+    }
+
+    /// Returns the list's total, if known.
+    pub fn total(&self, list: &str, version: u64) -> Option<u32> {
+        self.with_list(list, version, |list| list.total)
+    }
+
+    /// Remembers the list's total.
+    pub fn set_total(&self, list: &str, version: u64, total: u32) {
+        self.with_list(list, version, |list| list.total = Some(total));
+    }
+
+    fn with_list<T>(&self, list: &str, version: u64, f: impl FnOnce(&mut List) -> T) -> T {
+        let mut lists = self.lists.lock().unwrap_or_else(|e| e.into_inner());
+        if lists.version != version {
+            lists.lists.clear();
+            lists.version = version;
+        }
+        if !lists.lists.contains_key(list) && lists.lists.len() >= MAX_LISTS {
+            lists.lists.clear();
+        }
+        f(lists.lists.entry(list.to_string()).or_default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(id: &str) -> Keyset {
+        Keyset {
+            last_epoch: Some(1),
+            id: id.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_checkpoints_every_interval() {
+        let checkpoints = ListCheckpoints::new(10);
+        for index in 0..25 {
+            checkpoints.record("list", 1, index, &key(&format!("c{}", index)));
+        }
+
+        assert_eq!(checkpoints.nearest("list", 1, 9), None);
+        assert_eq!(checkpoints.nearest("list", 1, 10), Some((10, key("c9"))));
+        assert_eq!(checkpoints.nearest("list", 1, 24), Some((20, key("c19"))));
+        assert_eq!(checkpoints.nearest("other", 1, 24), None);
+    }
+
+    #[test]
+    fn test_database_changes_drop_checkpoints_and_totals() {
+        let checkpoints = ListCheckpoints::new(1);
+        checkpoints.record("list", 1, 0, &key("c0"));
+        checkpoints.set_total("list", 1, 5);
+        assert_eq!(checkpoints.total("list", 1), Some(5));
+
+        assert_eq!(checkpoints.total("list", 2), None);
+        assert_eq!(checkpoints.nearest("list", 2, 3), None);
+    }
+
+    #[test]
+    fn test_lists_are_bounded() {
+        let checkpoints = ListCheckpoints::new(1);
+        for list in 0..MAX_LISTS {
+            checkpoints.set_total(&list.to_string(), 1, 1);
+        }
+        assert_eq!(checkpoints.total("0", 1), Some(1));
+        checkpoints.set_total("new", 1, 1);
+        assert_eq!(checkpoints.total("0", 1), None);
+    }
+}
@@ -0,0 +1,224 @@
+//! Deterministic fixture conversations.
+//!
+//! The fixtures are written as Claude Code session files and ingested like
+//! real history, so every command (including loading full conversations
+//! from disk) behaves as it does in normal use. Content, timestamps, and
+//! session IDs are fixed, so conversation IDs are stable across runs.
+
+use serde_json::{json, Value};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+const SONNET: &str = "claude-sonnet-4-5-20250929";
+const OPUS: &str = "claude-opus-4-1-20250805";
+
+/// A fixture session file: project directory, session ID, and JSONL lines.
+struct FixtureSession {
+    project: &'static str,
+    session_id: &'static str,
+    lines: Vec<Value>,
+}
+
+/// Builds the lines of one session, numbering messages and spacing their
+/// timestamps a minute apart from `start`.
+struct SessionBuilder {
+    session_id: &'static str,
+    start: chrono::DateTime<chrono::Utc>,
+    lines: Vec<Value>,
+}
+
+impl SessionBuilder {
+    fn new(session_id: &'static str, start: &str) -> Self {
+        Self {
+            session_id,
+            start: chrono::DateTime::parse_from_rfc3339(start)
+                .expect("fixture start time is valid")
+                .with_timezone(&chrono::Utc),
+            lines: Vec::new(),
+        }
+    }
+
+    fn push(&mut self, message_type: &str, message: Value) -> &mut Self {
+        let n = self.lines.len();
+        let timestamp = self.start + chrono::Duration::minutes(n as i64);
+        self.lines.push(json!({
+            "type": message_type,
+            "message": message,
+            "timestamp": timestamp.to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+            "uuid": format!("{}-{:03}", self.session_id, n),
+            "sessionId": self.session_id,
+        }));
+        self
+    }
+
+    fn user(&mut self, text: &str) -> &mut Self {
+        self.push("user", json!({ "role": "user", "content": text }))
+    }
+
+    fn assistant(&mut self, model: &str, content: Value, usage: (i64, i64)) -> &mut Self {
+        let n = self.lines.len();
+        self.push(
+            "assistant",
+            json!({
+                "id": format!("msg_{}_{:03}", self.session_id, n),
+                "role": "assistant",
+                "model": model,
+                "content": content,
+                "usage": {
+                    "input_tokens": usage.0,
+                    "output_tokens": usage.1,
+                    "cache_read_input_tokens": usage.0 * 4,
+                },
+            }),
+        )
+    }
+
+    fn tool_use(&mut self, model: &str, id: &str, name: &str, input: Value) -> &mut Self {
+        self.assistant(
+            model,
+            json!([{ "type": "tool_use", "id": id, "name": name, "input": input }]),
+            (40, 25),
+        )
+    }
+
+    fn tool_result(&mut self, id: &str, output: &str, is_error: bool) -> &mut Self {
+        self.push(
+            "user",
+            json!({
+                "role": "user",
+                "content": [{
+                    "type": "tool_result",
+                    "tool_use_id": id,
+                    "content": output,
+                    "is_error": is_error,
+                }],
+            }),
+        )
+    }
+
+    fn build(&mut self, project: &'static str) -> FixtureSession {
+        FixtureSession {
+            project,
+            session_id: self.session_id,
+            lines: std::mem::take(&mut self.lines),
+        }
+    }
+}
+
+fn text(text: &str) -> Value {
+    json!([{ "type": "text", "text": text }])
+}
+
+fn fixture_sessions() -> Vec<FixtureSession> {
+    vec![
+        SessionBuilder::new("demo-dark-mode", "2025-01-06T09:00:00Z")
+            .user("Add a dark mode toggle to the settings page")
+            .assistant(SONNET, text("I'll look at the settings page first."), (1200, 80))
+            .tool_use(SONNET, "toolu_demo_01", "Read", json!({ "file_path": "src/routes/settings/+page.svelte" }))
+            .tool_result("toolu_demo_01", "<script>\n  let theme = 'light';\n</script>", false)
+            .assistant(
+                SONNET,
+                text("Here's the toggle:\n\n```svelte\n<button onclick={() => (theme = theme === 'light' ? 'dark' : 'light')}>\n  Toggle theme\n</button>\n```"),
+                (1500, 220),
+            )
+            .user("Perfect, thanks!")
+            .build("-Users-demo-acme-web"),
+        SessionBuilder::new("demo-flaky-login", "2025-01-07T14:30:00Z")
+            .user("The login test fails about once in ten runs. Can you find out why?")
+            .tool_use(SONNET, "toolu_demo_02", "Bash", json!({ "command": "npm test -- login" }))
+            .tool_result("toolu_demo_02", "FAIL tests/login.test.ts\n  Timeout waiting for redirect", true)
+            .tool_use(SONNET, "toolu_demo_03", "Bash", json!({ "command": "npm test -- login" }))
+            .tool_result("toolu_demo_03", "FAIL tests/login.test.ts\n  Timeout waiting for redirect", true)
+            .assistant(
+                SONNET,
+                text("The test doesn't wait for the session cookie before asserting the redirect. Awaiting `page.waitForURL('/dashboard')` fixes it."),
+                (2400, 140),
+            )
+            .build("-Users-demo-acme-web"),
+        SessionBuilder::new("demo-serde-config", "2025-01-08T10:15:00Z")
+            .user("How do I load a TOML config file with serde?")
+            .assistant(
+                SONNET,
+                text("Derive `Deserialize` and use the `toml` crate:\n\n```rust\n#[derive(Deserialize)]\nstruct Config {\n    name: String,\n    port: u16,\n}\n\nlet config: Config = toml::from_str(&std::fs::read_to_string(\"app.toml\")?)?;\n```"),
+                (900, 310),
+            )
+            .user("And default values for missing fields?")
+            .assistant(SONNET, text("Add `#[serde(default)]` to the struct or a field, and implement `Default`."), (1300, 60))
+            .build("-Users-demo-rust-cli"),
+        SessionBuilder::new("demo-unicode", "2025-01-09T08:00:00Z")
+            .user("Why does \"日本語のテキスト\" print as mojibake on Windows? 🤔")
+            .assistant(
+                SONNET,
+                text("The console is using a legacy code page. Switch it to UTF-8 with `chcp 65001`, or write through `WriteConsoleW`. Ünïcödé and emoji 🎉 will then render correctly."),
+                (700, 90),
+            )
+            .build("-Users-demo-rust-cli"),
+        SessionBuilder::new("demo-slow-query", "2025-01-10T16:45:00Z")
+            .user("This report query takes 40 seconds. Can you speed it up?")
+            .assistant(
+                OPUS,
+                text("It scans `events` once per customer. An index and a single grouped scan should help:\n\n```sql\nCREATE INDEX idx_events_customer_time ON events (customer_id, created_at);\n\nSELECT customer_id, COUNT(*) AS events\nFROM events\nWHERE created_at >= date('now', '-30 days')\nGROUP BY customer_id;\n```"),
+                (3100, 420),
+            )
+            .user("Down to 300ms 🚀")
+            .build("-Users-demo-data-pipeline"),
+        SessionBuilder::new("demo-quick-question", "2025-01-10T18:00:00Z")
+            .user("What's the difference between UNION and UNION ALL?")
+            .assistant(SONNET, text("`UNION` removes duplicate rows; `UNION ALL` keeps them and is faster."), (400, 30))
+            .build("-Users-demo-data-pipeline"),
+    ]
+}
+
+/// Writes the fixture session files under `dir` as
+/// `{dir}/{project}/{session}.jsonl`, mirroring `~/.claude/projects/`.
+///
+/// Returns the paths of the written files.
+pub fn write_fixture_files(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut paths = Vec::new();
+    for session in fixture_sessions() {
+        let project_dir = dir.join(session.project);
+        fs::create_dir_all(&project_dir)?;
+
+        let path = project_dir.join(format!("{}.jsonl", session.session_id));
+        let mut contents = String::new();
+        for line in &session.lines {
+            contents.push_str(&line.to_string());
+            contents.push('\n');
+        }
+        fs::write(&path, contents)?;
+        paths.push(path);
+    }
+    Ok(paths)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_conversation_file;
+
+    #[test]
+    fn test_fixture_files_parse() {
+        let dir = tempfile::tempdir().unwrap();
+        let paths = write_fixture_files(dir.path()).unwrap();
+        assert_eq!(paths.len(), 6);
+
+        for path in &paths {
+            let conversations = parse_conversation_file(path).unwrap();
+            assert_eq!(conversations.len(), 1, "{:?}", path);
+            assert!(!conversations[0].messages.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_fixture_files_are_deterministic() {
+        let first = tempfile::tempdir().unwrap();
+        let second = tempfile::tempdir().unwrap();
+        let first_paths = write_fixture_files(first.path()).unwrap();
+        let second_paths = write_fixture_files(second.path()).unwrap();
+
+        for (a, b) in first_paths.iter().zip(&second_paths) {
+            assert_eq!(fs::read_to_string(a).unwrap(), fs::read_to_string(b).unwrap());
+        }
+    }
+}
@@ -0,0 +1,364 @@
+//! Synthetic conversation generator for performance testing.
+//!
+//! Generated conversations are written straight to the database (summary
+//! row, search index, usage statistics, occasional bookmarks and tags)
+//! without session files, so large datasets are cheap to create. Their
+//! message details can't be opened; they exist to exercise the list,
+//! search, and statistics views at scale.
+
+use crate::db::sqlite::DbResult;
+use crate::search::index::index_conversation_content;
+use crate::usage::{
+    normalize_language, record_usage, ConversationUsage, DailyUsage, LanguageUsage, ToolUsage,
+};
+use chrono::{DateTime, Duration, SecondsFormat, Utc};
+use rusqlite::{params, Connection};
+
+/// Prefix of generated conversation IDs.
+pub const GENERATED_ID_PREFIX: &str = "demo-gen-";
+
+/// Marker standing in for the session file of generated conversations.
+const GENERATED_FILE_ROOT: &str = "<generated>";
+
+/// Generated history spans this many days before `now`.
+const HISTORY_DAYS: u64 = 365;
+
+const PROJECTS: &[&str] = &[
+    "acme-web",
+    "rust-cli",
+    "data-pipeline",
+    "mobile-app",
+    "infra",
+    "ml-experiments",
+    "docs-site",
+    "payments-service",
+    "game-engine",
+    "dotfiles",
+];
+
+const MODELS: &[&str] = &[
+    "claude-sonnet-4-5-20250929",
+    "claude-opus-4-1-20250805",
+    "claude-haiku-4-5-20251001",
+];
+
+const TOOLS: &[&str] = &["Read", "Edit", "Bash", "Grep", "Glob", "Write", "WebFetch", "Task"];
+
+const LANGUAGES: &[&str] = &[
+    "Rust", "TypeScript", "Python", "Go", "SQL", "Svelte", "Bash", "Kotlin",
+];
+
+/// Opening prompts; `{lang}` is replaced with a programming language.
+const PROMPTS: &[&str] = &[
+    "Fix the failing {lang} test in the checkout flow",
+    "Refactor the {lang} config loader to support environment overrides",
+    "Why is this {lang} function so slow on large inputs?",
+    "Add pagination to the {lang} API client",
+    "Write a migration that backfills the new column",
+    "Explain the borrow checker error in this {lang} snippet",
+    "Set up CI caching for the {lang} build",
+    "Review this pull request for race conditions",
+    "Convert the callback-based {lang} code to async/await",
+    "日本語の入力でテキストが文字化けする問題を修正して",
+    "为这个 {lang} 模块添加单元测试",
+    "¿Por qué falla la compilación de {lang} en Windows?",
+    "Warum schlägt der {lang}-Build mit einer Linker-Fehlermeldung fehl?",
+    "Corrige l'encodage des caractères accentués dans l'export CSV",
+    "Добавь обработку ошибок в {lang} клиент",
+    "Ship it 🚀 — bump the version and update the changelog 📝",
+];
+
+/// Follow-up sentences mixed into the indexed content.
+const DETAILS: &[&str] = &[
+    "The stack trace points at the connection pool.",
+    "It only happens when the cache is cold.",
+    "We should keep backwards compatibility with the old format.",
+    "Benchmarks show a 3x regression since the last release.",
+    "The error message says `permission denied` on the second run.",
+    "Let's add a regression test before changing anything.",
+    "The CI logs are attached below.",
+    "Use the existing helper instead of duplicating the logic.",
+];
+
+const TAGS: &[&str] = &["bug", "refactor", "performance", "docs", "follow-up"];
+
+/// Small deterministic PRNG (xorshift64*), so generated data is reproducible
+/// without an extra dependency.
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        // Spread nearby seeds apart; xorshift must not start at zero
+        Self(seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1)
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    /// Returns a value in `0..n`.
+    fn below(&mut self, n: u64) -> u64 {
+        self.next() % n
+    }
+
+    fn pick<'a>(&mut self, items: &[&'a str]) -> &'a str {
+        items[self.below(items.len() as u64) as usize]
+    }
+
+    /// Returns true with probability `percent` / 100.
+    fn chance(&mut self, percent: u64) -> bool {
+        self.below(100) < percent
+    }
+}
+
+/// Generates `count` synthetic conversations active within the year
+/// before `now`.
+///
+/// Generation is deterministic: conversation `n` is always the same, and
+/// re-running with the same count replaces the previous data instead of
+/// adding to it. Returns the number of conversations written.
+pub fn generate_conversations(
+    conn: &mut Connection,
+    count: usize,
+    now: DateTime<Utc>,
+) -> DbResult<usize> {
+    let tx = conn.transaction()?;
+    for n in 0..count {
+        insert_conversation(&tx, n, now)?;
+    }
+    tx.commit()?;
+    Ok(count)
+}
+
+fn insert_conversation(conn: &Connection, n: usize, now: DateTime<Utc>) -> DbResult<()> {
+    let mut rng = Rng::new(n as u64);
+
+    let id = format!("{}{:06}", GENERATED_ID_PREFIX, n);
+    let project = rng.pick(PROJECTS);
+    let project_path = format!("/Users/demo/{}", project);
+    let language = rng.pick(LANGUAGES);
+    let preview = rng.pick(PROMPTS).replace("{lang}", language);
+
+    // Most conversations are short; a few run to hundreds of messages
+    let message_count = match rng.below(10) {
+        0..=5 => 2 + rng.below(20),
+        6..=8 => 20 + rng.below(100),
+        _ => 120 + rng.below(400),
+    } as i64;
+
+    let start = now
+        - Duration::seconds(rng.below(HISTORY_DAYS * 86_400) as i64)
+        - Duration::minutes(message_count * 2);
+    let last = start + Duration::seconds(message_count * (30 + rng.below(150) as i64));
+    let start_time = start.to_rfc3339_opts(SecondsFormat::Millis, true);
+    let last_time = last.to_rfc3339_opts(SecondsFormat::Millis, true);
+
+    let assistant_messages = message_count / 2;
+    let input_tokens = assistant_messages * (200 + rng.below(3_000) as i64);
+    let output_tokens = assistant_messages * (50 + rng.below(800) as i64);
+
+    conn.execute(
+        r#"
+        INSERT INTO conversations (
+            id, project_path, project_name, start_time, last_time,
+            preview, preview_medium, message_count, total_input_tokens,
+            total_output_tokens, file_path, file_modified_at, session_id
+        )
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?6, ?7, ?8, ?9, ?10, ?11, ?12)
+        ON CONFLICT(id) DO UPDATE SET
+            project_path = excluded.project_path,
+            project_name = excluded.project_name,
+            start_time = excluded.start_time,
+            last_time = excluded.last_time,
+            preview = excluded.preview,
+            preview_medium = excluded.preview_medium,
+            message_count = excluded.message_count,
+            total_input_tokens = excluded.total_input_tokens,
+            total_output_tokens = excluded.total_output_tokens,
+            file_path = excluded.file_path,
+            file_modified_at = excluded.file_modified_at,
+            session_id = excluded.session_id
+        "#,
+        params![
+            id,
+            project_path,
+            project,
+            start_time,
+            last_time,
+            preview,
+            message_count,
+            input_tokens,
+            output_tokens,
+            format!("{}/{}/{}.jsonl", GENERATED_FILE_ROOT, project, id),
+            last_time,
+            id,
+        ],
+    )?;
+
+    let mut content = preview.clone();
+    for _ in 0..1 + rng.below(4) {
+        content.push(' ');
+        content.push_str(rng.pick(DETAILS));
+    }
+    index_conversation_content(conn, &id, &content, project)?;
+
+    let day = start.format("%Y-%m-%d").to_string();
+    let tools = (0..rng.below(4))
+        .map(|_| {
+            let calls = 1 + rng.below(assistant_messages.max(1) as u64) as i64;
+            ToolUsage {
+                day: day.clone(),
+                tool_name: rng.pick(TOOLS).to_string(),
+                calls,
+                errors: if rng.chance(15) { rng.below(calls as u64 + 1) as i64 } else { 0 },
+            }
+        })
+        .fold(Vec::<ToolUsage>::new(), |mut tools, tool| {
+            // Tool names are drawn with replacement; merge repeats
+            match tools.iter_mut().find(|t| t.tool_name == tool.tool_name) {
+                Some(existing) => {
+                    existing.calls += tool.calls;
+                    existing.errors += tool.errors;
+                }
+                None => tools.push(tool),
+            }
+            tools
+        });
+    // About every other answer has a code block in the conversation's language
+    let code_blocks = (assistant_messages / 2).max(1);
+    let languages = normalize_language(language)
+        .map(|language| LanguageUsage {
+            day: day.clone(),
+            language,
+            blocks: code_blocks,
+            lines: code_blocks * 12,
+        })
+        .into_iter()
+        .collect();
+    let usage = ConversationUsage {
+        daily: vec![DailyUsage {
+            day,
+            model: rng.pick(MODELS).to_string(),
+            messages: assistant_messages,
+            input_tokens,
+            output_tokens,
+            cache_creation_tokens: input_tokens / 10,
+            cache_read_tokens: input_tokens * 3,
+            // Answers stream at about 50 tokens a second
+            response_millis: output_tokens * 20,
+            timed_responses: assistant_messages,
+        }],
+        tools,
+        languages,
+    };
+    record_usage(conn, &id, &usage)?;
+
+    if rng.chance(5) {
+        conn.execute(
+            "INSERT OR IGNORE INTO bookmarks (conversation_id, created_at) VALUES (?1, ?2)",
+            params![id, last_time],
+        )?;
+    }
+    if rng.chance(10) {
+        conn.execute(
+            "INSERT OR IGNORE INTO conversation_tags (conversation_id, tag, created_at) VALUES (?1, ?2, ?3)",
+            params![id, rng.pick(TAGS), last_time],
+        )?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::sqlite::init_db;
+    use chrono::TimeZone;
+
+    fn setup() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        init_db(&conn).unwrap();
+        conn
+    }
+
+    fn now() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2025, 6, 1, 12, 0, 0).unwrap()
+    }
+
+    fn count(conn: &Connection, table: &str) -> i64 {
+        conn.query_row(&format!("SELECT COUNT(*) FROM {}", table), [], |row| row.get(0))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_generate_conversations() {
+        let mut conn = setup();
+        assert_eq!(generate_conversations(&mut conn, 200, now()).unwrap(), 200);
+
+        assert_eq!(count(&conn, "conversations"), 200);
+        assert_eq!(count(&conn, "conversations_fts"), 200);
+        assert!(count(&conn, "usage_daily") > 0);
+        assert!(count(&conn, "tool_usage") > 0);
+        assert!(count(&conn, "language_usage") > 0);
+
+        let projects: i64 = conn
+            .query_row("SELECT COUNT(DISTINCT project_name) FROM conversations", [], |row| row.get(0))
+            .unwrap();
+        assert!(projects > 5);
+
+        let (first, last): (String, String) = conn
+            .query_row("SELECT MIN(start_time), MAX(last_time) FROM conversations", [], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })
+            .unwrap();
+        assert!(first.as_str() >= "2024-05-01");
+        assert!(last.as_str() <= "2025-06-02");
+    }
+
+    #[test]
+    fn test_generation_is_deterministic_and_idempotent() {
+        let mut conn = setup();
+        generate_conversations(&mut conn, 50, now()).unwrap();
+        let snapshot = |conn: &Connection| -> Vec<(String, String, i64)> {
+            let mut stmt = conn
+                .prepare("SELECT id, preview, message_count FROM conversations ORDER BY id")
+                .unwrap();
+            stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+                .unwrap()
+                .collect::<Result<_, _>>()
+                .unwrap()
+        };
+        let before = snapshot(&conn);
+
+        generate_conversations(&mut conn, 50, now()).unwrap();
+        assert_eq!(snapshot(&conn), before);
+        assert_eq!(count(&conn, "conversations_fts"), 50);
+    }
+
+    #[test]
+    fn test_generated_content_is_searchable() {
+        let mut conn = setup();
+        generate_conversations(&mut conn, 300, now()).unwrap();
+
+        let unicode: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM conversations WHERE preview GLOB '*[^ -~]*'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert!(unicode > 0);
+
+        let matches: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM conversations_fts WHERE conversations_fts MATCH 'regression'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert!(matches > 0);
+    }
+}
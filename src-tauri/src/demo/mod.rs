@@ -0,0 +1,10 @@
+//! Demo and fixture data.
+//!
+//! Synthetic conversations for UI tests, screenshots, and demos, so they
+//! don't depend on a developer's real chat history.
+
+pub mod fixtures;
+pub mod generator;
+
+pub use fixtures::write_fixture_files;
+pub use generator::{generate_conversations, GENERATED_ID_PREFIX};
@@ -0,0 +1,258 @@
+//! Typed events emitted to the frontend.
+//!
+//! Each event says what changed (which conversations, which tags, how far
+//! indexing got, whether the watcher runs), so components can update just
+//! the affected parts instead of reloading everything.
+
+use crate::db::operations::DEFAULT_WINDOW_LABEL;
+use crate::export::spotlight::parse_conversation_link;
+use crate::models::{
+    ConversationsChanged, DropImportResult, IngestStats, OpenConversation, Reminder, TagsChanged,
+    WatcherState, WatcherStatus,
+};
+use crate::state::AppState;
+use serde::Serialize;
+use std::sync::Arc;
+use tauri::{AppHandle, Emitter, Manager};
+use tracing::{error, info, warn};
+
+/// Event name for conversations new since the last scan.
+pub const CONVERSATION_ADDED_EVENT: &str = "conversation-added";
+
+/// Event name for existing conversations whose session file changed.
+pub const CONVERSATION_UPDATED_EVENT: &str = "conversation-updated";
+
+/// Event name for a conversation's tags changing.
+pub const TAGS_CHANGED_EVENT: &str = "tags-changed";
+
+/// Event name for indexing progress during scans.
+pub const INDEX_PROGRESS_EVENT: &str = "index-progress";
+
+/// Event name for the file watcher starting, stopping, or failing.
+pub const WATCHER_STATUS_EVENT: &str = "watcher-status";
+
+/// Event name for the outcome of importing files dropped on the window.
+pub const IMPORT_RESULT_EVENT: &str = "import-result";
+
+/// Event name for a link asking to open a conversation.
+pub const OPEN_CONVERSATION_EVENT: &str = "open-conversation";
+
+/// Event name for follow-up reminders coming due.
+pub const REMINDER_DUE_EVENT: &str = "reminder-due";
+
+/// Emits an event, logging rather than returning failures.
+pub fn emit<S: Serialize + Clone>(app_handle: &AppHandle, event: &str, payload: S) {
+    if let Err(e) = app_handle.emit(event, payload) {
+        error!("Error emitting {} event: {}", event, e);
+    }
+}
+
+/// Emits `conversation-added` and `conversation-updated` for an ingest run;
+/// an event without conversations isn't emitted.
+pub fn emit_conversations_changed(app_handle: &AppHandle, stats: &IngestStats, from_watcher: bool) {
+    for (event, ids) in [
+        (CONVERSATION_ADDED_EVENT, &stats.added_ids),
+        (CONVERSATION_UPDATED_EVENT, &stats.updated_ids),
+    ] {
+        if ids.is_empty() {
+            continue;
+        }
+        emit(
+            app_handle,
+            event,
+            ConversationsChanged {
+                conversation_ids: ids.clone(),
+                from_watcher,
+            },
+        );
+        info!("Emitted {} event: {} conversations", event, ids.len());
+    }
+}
+
+/// Emits `tags-changed` with a conversation's new tags.
+pub fn emit_tags_changed(app_handle: &AppHandle, conversation_id: &str, tags: &[String]) {
+    emit(
+        app_handle,
+        TAGS_CHANGED_EVENT,
+        TagsChanged {
+            conversation_id: conversation_id.to_string(),
+            tags: tags.to_vec(),
+        },
+    );
+}
+
+/// Emits `watcher-status`.
+pub fn emit_watcher_status(app_handle: &AppHandle, state: WatcherState, message: Option<String>) {
+    emit(
+        app_handle,
+        WATCHER_STATUS_EVENT,
+        WatcherStatus { state, message },
+    );
+}
+
+/// Emits `import-result` for files dropped on the window.
+pub fn emit_import_result(app_handle: &AppHandle, result: &DropImportResult) {
+    emit(app_handle, IMPORT_RESULT_EVENT, result);
+    info!(
+        "Emitted {} event: {} conversations added",
+        IMPORT_RESULT_EVENT, result.stats.new_conversations
+    );
+}
+
+/// Emits `reminder-due` with the reminders that just came due.
+pub fn emit_reminders_due(app_handle: &AppHandle, reminders: &[Reminder]) {
+    emit(app_handle, REMINDER_DUE_EVENT, reminders);
+    info!(
+        "Emitted {} event: {} reminders",
+        REMINDER_DUE_EVENT,
+        reminders.len()
+    );
+}
+
+/// Opens the conversation of a `claude-history://` link, such as those of
+/// Spotlight stubs: it becomes the main window's selection, for a frontend
+/// that isn't listening yet, and `open-conversation` is emitted. Returns
+/// false for other links.
+pub fn open_deep_link(app_handle: &AppHandle, url: &str) -> bool {
+    let Some(id) = parse_conversation_link(url) else {
+        warn!("Ignoring link {}", url);
+        return false;
+    };
+    let state = app_handle.state::<Arc<AppState>>();
+    state.window(DEFAULT_WINDOW_LABEL).set_selection(Some(id.to_string()));
+    emit(
+        app_handle,
+        OPEN_CONVERSATION_EVENT,
+        OpenConversation {
+            conversation_id: id.to_string(),
+        },
+    );
+    info!("Emitted {} event: {}", OPEN_CONVERSATION_EVENT, id);
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+    use tauri::Listener;
+
+    /// Collects the payloads of `event` emitted on `app_handle`.
+    fn capture(app_handle: &AppHandle, event: &str) -> Arc<Mutex<Vec<String>>> {
+        let payloads = Arc::new(Mutex::new(Vec::new()));
+        let sink = payloads.clone();
+        app_handle.listen(event, move |event| {
+            sink.lock().unwrap().push(event.payload().to_string());
+        });
+        payloads
+    }
+
+    fn mock_handle() -> AppHandle {
+        let app = tauri::test::mock_builder()
+            .build(tauri::test::mock_context(tauri::test::noop_assets()))
+            .expect("failed to build mock app");
+        app.handle().clone()
+    }
+
+    #[test]
+    fn test_conversations_changed_are_split_by_kind() {
+        let app_handle = mock_handle();
+        let added = capture(&app_handle, CONVERSATION_ADDED_EVENT);
+        let updated = capture(&app_handle, CONVERSATION_UPDATED_EVENT);
+
+        let stats = IngestStats {
+            added_ids: vec!["c1".to_string(), "c2".to_string()],
+            ..IngestStats::default()
+        };
+        emit_conversations_changed(&app_handle, &stats, true);
+
+        let added = added.lock().unwrap();
+        assert_eq!(added.len(), 1);
+        let payload: ConversationsChanged = serde_json::from_str(&added[0]).unwrap();
+        assert_eq!(payload.conversation_ids, vec!["c1", "c2"]);
+        assert!(payload.from_watcher);
+        assert!(added[0].contains("\"conversationIds\""));
+        assert!(updated.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_import_result_payload() {
+        let app_handle = mock_handle();
+        let results = capture(&app_handle, IMPORT_RESULT_EVENT);
+
+        let result = DropImportResult {
+            copied_files: 2,
+            rejected_paths: vec!["/tmp/notes.txt".to_string()],
+            stats: IngestStats {
+                processed_files: 2,
+                new_conversations: 3,
+                ..IngestStats::default()
+            },
+        };
+        emit_import_result(&app_handle, &result);
+
+        let results = results.lock().unwrap();
+        assert_eq!(results.len(), 1);
+        assert!(results[0].contains(r#""copiedFiles":2"#));
+        assert!(results[0].contains(r#""newConversations":3"#));
+        assert!(results[0].contains(r#""rejectedPaths":["/tmp/notes.txt"]"#));
+    }
+
+    #[test]
+    fn test_reminders_due_payload() {
+        let app_handle = mock_handle();
+        let payloads = capture(&app_handle, REMINDER_DUE_EVENT);
+
+        let reminder = Reminder {
+            conversation_id: "c1".to_string(),
+            project_name: "api".to_string(),
+            preview: "Why does the login test flake?".to_string(),
+            due_at: "2025-01-17T09:00:00+00:00".to_string(),
+            note: Some("Check the CI run".to_string()),
+            created_at: "2025-01-15T10:00:00+00:00".to_string(),
+            notified_at: Some("2025-01-17T09:00:10+00:00".to_string()),
+        };
+        emit_reminders_due(&app_handle, std::slice::from_ref(&reminder));
+
+        let payloads = payloads.lock().unwrap();
+        assert_eq!(payloads.len(), 1);
+        let due: Vec<Reminder> = serde_json::from_str(&payloads[0]).unwrap();
+        assert_eq!(due, vec![reminder]);
+        assert!(payloads[0].contains(r#""dueAt":"2025-01-17T09:00:00+00:00""#));
+    }
+
+    #[test]
+    fn test_watcher_status_payload() {
+        let app_handle = mock_handle();
+        let statuses = capture(&app_handle, WATCHER_STATUS_EVENT);
+
+        emit_watcher_status(
+            &app_handle,
+            WatcherState::Failed,
+            Some("denied".to_string()),
+        );
+        assert_eq!(
+            *statuses.lock().unwrap(),
+            vec![r#"{"state":"failed","message":"denied"}"#.to_string()]
+        );
+    }
+
+    #[test]
+    fn test_deep_links_open_their_conversation() {
+        let state = Arc::new(AppState::new_in_memory_with_fixtures().unwrap());
+        let app = tauri::test::mock_builder()
+            .manage(state.clone())
+            .build(tauri::test::mock_context(tauri::test::noop_assets()))
+            .expect("failed to build mock app");
+        let app_handle = app.handle().clone();
+        let opened = capture(&app_handle, OPEN_CONVERSATION_EVENT);
+
+        assert!(!open_deep_link(&app_handle, "claude-history://settings"));
+        assert!(open_deep_link(&app_handle, "claude-history://conversation/c1"));
+        assert_eq!(
+            *opened.lock().unwrap(),
+            vec![r#"{"conversationId":"c1"}"#.to_string()]
+        );
+        assert_eq!(state.window("main").selection().as_deref(), Some("c1"));
+    }
+}
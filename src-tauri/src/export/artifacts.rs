@@ -0,0 +1,270 @@
+//! Files a conversation wrote, rebuilt from its tool calls.
+//!
+//! Write calls record a file's full contents, and Edit and MultiEdit calls
+//! the text they replaced, so replaying them in order gives each file as the
+//! conversation left it, even if it has changed on disk since. Calls whose
+//! results were errors are skipped, as they changed nothing. A file that was
+//! only edited can't be rebuilt, since its contents before the first edit
+//! aren't recorded.
+
+use super::copy::relative_path;
+use crate::parser::jsonl::{RawContent, RawMessage};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashSet};
+use std::io::{Cursor, Write};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+/// A file written during a conversation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Artifact {
+    /// Path relative to the project directory, or to the filesystem root for
+    /// files outside it, using `/` separators.
+    pub path: String,
+    /// Final contents, or `None` if they can't be rebuilt: the file was
+    /// edited without being written in full first, or an edit didn't match.
+    pub content: Option<String>,
+}
+
+/// Rebuilds the files written by a conversation's Write, Edit, and
+/// MultiEdit calls, sorted by path.
+///
+/// # Arguments
+/// * `messages` - The conversation's messages, in order
+/// * `project_path` - Directory artifact paths are made relative to
+pub fn extract_artifacts(messages: &[RawMessage], project_path: &str) -> Vec<Artifact> {
+    let failed = failed_tool_calls(messages);
+    let mut files: BTreeMap<String, Option<String>> = BTreeMap::new();
+
+    for message in messages {
+        let RawContent::Blocks(blocks) = &message.message.content else {
+            continue;
+        };
+        for block in blocks.iter().filter(|block| block.block_type == "tool_use") {
+            if block
+                .tool_use_id
+                .as_ref()
+                .is_some_and(|id| failed.contains(id))
+            {
+                continue;
+            }
+            let (Some(name), Some(input)) = (block.name.as_deref(), block.input.as_ref()) else {
+                continue;
+            };
+            let Some(path) = input.get("file_path").and_then(Value::as_str) else {
+                continue;
+            };
+            let edits = match name {
+                "Write" => {
+                    let content = input.get("content").and_then(Value::as_str);
+                    files.insert(
+                        artifact_path(path, project_path),
+                        content.map(str::to_string),
+                    );
+                    continue;
+                }
+                "Edit" => vec![input],
+                "MultiEdit" => match input.get("edits").and_then(Value::as_array) {
+                    Some(edits) => edits.iter().collect(),
+                    None => continue,
+                },
+                _ => continue,
+            };
+            let file = files.entry(artifact_path(path, project_path)).or_default();
+            for edit in edits {
+                *file = apply_edit(file.take(), edit);
+            }
+        }
+    }
+
+    files
+        .into_iter()
+        .map(|(path, content)| Artifact { path, content })
+        .collect()
+}
+
+/// IDs of tool calls whose results were errors.
+fn failed_tool_calls(messages: &[RawMessage]) -> HashSet<String> {
+    let mut failed = HashSet::new();
+    for message in messages {
+        if let RawContent::Blocks(blocks) = &message.message.content {
+            for block in blocks {
+                if block.block_type == "tool_result" && block.is_error == Some(true) {
+                    failed.extend(block.tool_use_id.clone());
+                }
+            }
+        }
+    }
+    failed
+}
+
+/// Applies one `old_string`/`new_string` edit to a file's contents.
+///
+/// An empty `old_string` creates the file. Returns `None` if the contents
+/// are unknown or the edit doesn't match them.
+fn apply_edit(content: Option<String>, edit: &Value) -> Option<String> {
+    let old = edit.get("old_string")?.as_str()?;
+    let new = edit.get("new_string")?.as_str()?;
+    if old.is_empty() {
+        return match content {
+            Some(content) if !content.is_empty() => None,
+            _ => Some(new.to_string()),
+        };
+    }
+    let content = content?;
+    if !content.contains(old) {
+        return None;
+    }
+    let replace_all = edit.get("replace_all").and_then(Value::as_bool) == Some(true);
+    Some(if replace_all {
+        content.replace(old, new)
+    } else {
+        content.replacen(old, new, 1)
+    })
+}
+
+/// Relative path of an artifact, without components that could point
+/// outside the directory it's extracted into.
+fn artifact_path(path: &str, project_path: &str) -> String {
+    relative_path(path, project_path)
+        .split(['/', '\\'])
+        .filter(|part| !part.is_empty() && *part != "." && *part != "..")
+        .map(|part| part.replace(':', "_"))
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Packs rebuilt artifacts into a ZIP archive, skipping those without
+/// contents.
+pub fn zip_artifacts(artifacts: &[Artifact]) -> zip::result::ZipResult<Vec<u8>> {
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    for artifact in artifacts {
+        if let Some(content) = &artifact.content {
+            zip.start_file(artifact.path.as_str(), options)?;
+            zip.write_all(content.as_bytes())?;
+        }
+    }
+    Ok(zip.finish()?.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::jsonl::{RawContentBlock, RawMessageType};
+    use serde_json::json;
+    use std::io::Read;
+
+    fn message(message_type: RawMessageType, blocks: Vec<RawContentBlock>) -> RawMessage {
+        RawMessage::new(message_type, RawContent::Blocks(blocks))
+    }
+
+    fn call(id: &str, name: &str, input: Value) -> RawMessage {
+        message(
+            RawMessageType::Assistant,
+            vec![RawContentBlock {
+                block_type: "tool_use".to_string(),
+                text: None,
+                name: Some(name.to_string()),
+                input: Some(input),
+                tool_use_id: Some(id.to_string()),
+                content: None,
+                is_error: None,
+            }],
+        )
+    }
+
+    fn result(id: &str, is_error: bool) -> RawMessage {
+        message(
+            RawMessageType::User,
+            vec![RawContentBlock {
+                block_type: "tool_result".to_string(),
+                text: None,
+                name: None,
+                input: None,
+                tool_use_id: Some(id.to_string()),
+                content: Some(json!("done")),
+                is_error: Some(is_error),
+            }],
+        )
+    }
+
+    fn artifact(path: &str, content: Option<&str>) -> Artifact {
+        Artifact {
+            path: path.to_string(),
+            content: content.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_extract_artifacts_replays_writes_and_edits() {
+        let messages = vec![
+            call(
+                "t1",
+                "Write",
+                json!({ "file_path": "/work/app/src/main.rs", "content": "fn main() {\n    old();\n}\n" }),
+            ),
+            result("t1", false),
+            call(
+                "t2",
+                "Edit",
+                json!({ "file_path": "/work/app/src/main.rs", "old_string": "old", "new_string": "new" }),
+            ),
+            result("t2", false),
+            // Failed, so not applied
+            call(
+                "t3",
+                "Edit",
+                json!({ "file_path": "/work/app/src/main.rs", "old_string": "new", "new_string": "bad" }),
+            ),
+            result("t3", true),
+            call(
+                "t4",
+                "MultiEdit",
+                json!({ "file_path": "/work/app/README.md", "edits": [
+                    { "old_string": "", "new_string": "# App\nTODO\nTODO\n" },
+                    { "old_string": "TODO", "new_string": "Done", "replace_all": true }
+                ] }),
+            ),
+            call(
+                "t5",
+                "Edit",
+                json!({ "file_path": "/work/app/lib.rs", "old_string": "a", "new_string": "b" }),
+            ),
+            call(
+                "t6",
+                "Write",
+                json!({ "file_path": "/tmp/../notes.txt", "content": "outside" }),
+            ),
+        ];
+
+        assert_eq!(
+            extract_artifacts(&messages, "/work/app"),
+            vec![
+                artifact("README.md", Some("# App\nDone\nDone\n")),
+                artifact("lib.rs", None),
+                artifact("src/main.rs", Some("fn main() {\n    new();\n}\n")),
+                artifact("tmp/notes.txt", Some("outside")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_zip_artifacts_skips_unknown_contents() {
+        let bytes = zip_artifacts(&[
+            artifact("src/main.rs", Some("fn main() {}\n")),
+            artifact("lib.rs", None),
+        ])
+        .unwrap();
+
+        let mut archive = zip::ZipArchive::new(Cursor::new(bytes)).unwrap();
+        assert_eq!(archive.len(), 1);
+        let mut content = String::new();
+        archive
+            .by_name("src/main.rs")
+            .unwrap()
+            .read_to_string(&mut content)
+            .unwrap();
+        assert_eq!(content, "fn main() {}\n");
+    }
+}
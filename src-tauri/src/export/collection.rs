@@ -0,0 +1,193 @@
+//! Exports of several conversations at once.
+//!
+//! Each conversation is written to its own file, and an index file links to
+//! them all, so a multi-select export can be browsed from one place.
+
+use super::{file_extension, html::escape_html};
+use crate::locale::DateFormatter;
+use crate::models::export::{ExportIndex, ExportIndexEntry, EXPORT_SCHEMA_VERSION};
+use crate::models::{Conversation, ExportFormat};
+use std::collections::HashSet;
+
+/// File name stem of the index file.
+pub const INDEX_FILE_STEM: &str = "index";
+
+/// A conversation listed in the index.
+#[derive(Debug, Clone)]
+pub struct IndexEntry {
+    /// Name of the conversation's file, relative to the index.
+    pub file_name: String,
+    pub project_name: String,
+    pub start_time: String,
+    pub message_count: usize,
+    pub tags: Vec<String>,
+}
+
+impl IndexEntry {
+    pub fn new(conversation: &Conversation, file_name: String) -> Self {
+        Self {
+            file_name,
+            project_name: conversation.project_name.clone(),
+            start_time: conversation.start_time.clone(),
+            message_count: conversation.messages.len(),
+            tags: conversation.tags.clone().unwrap_or_default(),
+        }
+    }
+}
+
+/// Claims a file name for `stem`, adding `-2`, `-3`, ... if it is taken.
+/// The index's name is always taken.
+pub fn unique_file_name(used: &mut HashSet<String>, stem: &str, format: ExportFormat) -> String {
+    let extension = file_extension(format);
+    let mut candidate = stem.to_string();
+    let mut n = 1;
+    while candidate == INDEX_FILE_STEM || used.contains(&candidate) {
+        n += 1;
+        candidate = format!("{}-{}", stem, n);
+    }
+    used.insert(candidate.clone());
+    format!("{}.{}", candidate, extension)
+}
+
+/// File name of the index for a format.
+pub fn index_file_name(format: ExportFormat) -> String {
+    format!("{}.{}", INDEX_FILE_STEM, file_extension(format))
+}
+
+/// Renders the index linking to each exported conversation, in order, with
+/// dates formatted by `dates`.
+pub fn render_index(entries: &[IndexEntry], format: ExportFormat, dates: &DateFormatter) -> String {
+    match format {
+        ExportFormat::Markdown | ExportFormat::Transcript => markdown_index(entries, dates),
+        ExportFormat::Html => html_index(entries, dates),
+        ExportFormat::Json => json_index(entries),
+    }
+}
+
+fn markdown_index(entries: &[IndexEntry], dates: &DateFormatter) -> String {
+    let mut lines = vec![
+        "# Exported Conversations".to_string(),
+        String::new(),
+        format!("**Conversations:** {}", entries.len()),
+        String::new(),
+    ];
+    for entry in entries {
+        let mut line = format!(
+            "- [{}](<{}>) - {}, {} messages",
+            entry.project_name.replace(['[', ']'], ""),
+            entry.file_name,
+            dates.datetime(&entry.start_time),
+            entry.message_count
+        );
+        if !entry.tags.is_empty() {
+            line.push_str(&format!(" ({})", entry.tags.join(", ")));
+        }
+        lines.push(line);
+    }
+    lines.push(String::new());
+    lines.join("\n")
+}
+
+fn html_index(entries: &[IndexEntry], dates: &DateFormatter) -> String {
+    let mut html = String::from(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <title>Exported Conversations</title>\n</head>\n<body>\n\
+         <h1>Exported Conversations</h1>\n",
+    );
+    html.push_str(&format!("<p>Conversations: {}</p>\n<ul>\n", entries.len()));
+    for entry in entries {
+        html.push_str(&format!(
+            "<li><a href=\"{}\">{}</a> - {}, {} messages",
+            escape_html(&entry.file_name),
+            escape_html(&entry.project_name),
+            escape_html(&dates.datetime(&entry.start_time)),
+            entry.message_count
+        ));
+        if !entry.tags.is_empty() {
+            html.push_str(&format!(" ({})", escape_html(&entry.tags.join(", "))));
+        }
+        html.push_str("</li>\n");
+    }
+    html.push_str("</ul>\n</body>\n</html>\n");
+    html
+}
+
+fn json_index(entries: &[IndexEntry]) -> String {
+    let index = ExportIndex {
+        schema_version: EXPORT_SCHEMA_VERSION,
+        conversations: entries
+            .iter()
+            .map(|entry| ExportIndexEntry {
+                file: entry.file_name.clone(),
+                project_name: entry.project_name.clone(),
+                start_time: entry.start_time.clone(),
+                message_count: entry.message_count,
+                tags: entry.tags.clone(),
+            })
+            .collect(),
+    };
+    let mut json = serde_json::to_string_pretty(&index).unwrap_or_default();
+    json.push('\n');
+    json
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(project_name: &str, file_name: &str) -> IndexEntry {
+        IndexEntry {
+            file_name: file_name.to_string(),
+            project_name: project_name.to_string(),
+            start_time: "2025-01-15T10:00:00Z".to_string(),
+            message_count: 4,
+            tags: vec!["rust".to_string()],
+        }
+    }
+
+    #[test]
+    fn test_unique_file_names() {
+        let mut used = HashSet::new();
+        let names: Vec<String> = [
+            "app_2025-01-15",
+            "app_2025-01-15",
+            "index",
+            "app_2025-01-15",
+        ]
+        .iter()
+        .map(|stem| unique_file_name(&mut used, stem, ExportFormat::Markdown))
+        .collect();
+        assert_eq!(
+            names,
+            vec![
+                "app_2025-01-15.md",
+                "app_2025-01-15-2.md",
+                "index-2.md",
+                "app_2025-01-15-3.md"
+            ]
+        );
+        assert_eq!(index_file_name(ExportFormat::Html), "index.html");
+    }
+
+    #[test]
+    fn test_render_index() {
+        let entries = [entry("my app", "my-app.md"), entry("<b>", "b.html")];
+        let dates = DateFormatter::default();
+        let markdown = render_index(&entries, ExportFormat::Markdown, &dates);
+        assert!(markdown.contains("**Conversations:** 2"));
+        assert!(markdown.contains(
+            "- [my app](<my-app.md>) - Wednesday, January 15, 2025 10:00 UTC, 4 messages (rust)"
+        ));
+        let german = DateFormatter::new("de-DE").unwrap();
+        assert!(render_index(&entries, ExportFormat::Markdown, &german)
+            .contains("- [my app](<my-app.md>) - Mittwoch 15.01.2025 10:00 UTC, 4 messages"));
+
+        let html = render_index(&entries, ExportFormat::Html, &dates);
+        assert!(html.contains("<a href=\"b.html\">&lt;b&gt;</a>"));
+
+        let json: ExportIndex =
+            serde_json::from_str(&render_index(&entries, ExportFormat::Json, &dates)).unwrap();
+        assert_eq!(json.schema_version, EXPORT_SCHEMA_VERSION);
+        assert_eq!(json.conversations[1].file, "b.html");
+    }
+}
@@ -0,0 +1,223 @@
+//! Clipboard text for single content blocks.
+//!
+//! Copying a block should give text that can be pasted straight into an
+//! editor or terminal: code without its fence, a tool call's input as JSON,
+//! or a file edit as a patch, rather than the block as it is displayed.
+
+use super::markdown::block_to_markdown;
+use crate::models::{ContentBlock, ContentBlockType, CopyMode};
+use serde_json::{json, Value};
+
+/// Lines of context kept around each change in a patch.
+const PATCH_CONTEXT_LINES: usize = 3;
+
+/// Returns the text to copy for a block, or `None` if the mode doesn't
+/// apply to it (such as `Patch` for a block that isn't a file edit).
+///
+/// # Arguments
+/// * `block` - The block to copy
+/// * `mode` - What to copy
+/// * `project_path` - Directory patch paths are made relative to
+pub fn block_copy_text(block: &ContentBlock, mode: CopyMode, project_path: &str) -> Option<String> {
+    match mode {
+        CopyMode::Text => match block.block_type {
+            ContentBlockType::Image => None,
+            ContentBlockType::Code | ContentBlockType::FileView => {
+                Some(block.content.trim_end_matches('\n').to_string())
+            }
+            _ => Some(block.content.clone()),
+        },
+        CopyMode::Json => {
+            tool_input(block).map(|input| serde_json::to_string_pretty(&input).unwrap_or_default())
+        }
+        CopyMode::Patch => {
+            let input = tool_input(block)?;
+            edit_patch(block.tool_name.as_deref()?, &input, project_path)
+        }
+        CopyMode::Markdown => Some(block_to_markdown(block)),
+    }
+}
+
+/// Input of the tool call a block shows, if it shows one.
+fn tool_input(block: &ContentBlock) -> Option<Value> {
+    match block.block_type {
+        ContentBlockType::ToolUse => serde_json::from_str(&block.content).ok(),
+        ContentBlockType::Terminal => {
+            let terminal = block.terminal.as_ref()?;
+            let mut input = json!({ "command": terminal.command });
+            if let Some(description) = &terminal.description {
+                input["description"] = json!(description);
+            }
+            Some(input)
+        }
+        _ => None,
+    }
+}
+
+/// Unified diff of an Edit, MultiEdit, or Write tool call.
+///
+/// Where in the file an edit applied isn't recorded, so hunks count lines
+/// from the start of the replaced text; tools that apply patches locate
+/// hunks by their context. Write calls are shown as creating the file,
+/// since its previous contents aren't known.
+fn edit_patch(tool_name: &str, input: &Value, project_path: &str) -> Option<String> {
+    let path = input.get("file_path")?.as_str()?;
+    let path = relative_path(path, project_path);
+    let string = |value: &Value, key: &str| value.get(key)?.as_str().map(str::to_string);
+
+    let (old_path, hunks) = match tool_name {
+        "Edit" => {
+            let old = string(input, "old_string")?;
+            let new = string(input, "new_string")?;
+            (format!("a/{}", path), vec![hunk(&old, &new)])
+        }
+        "MultiEdit" => {
+            let hunks = input
+                .get("edits")?
+                .as_array()?
+                .iter()
+                .map(|edit| {
+                    Some(hunk(
+                        &string(edit, "old_string")?,
+                        &string(edit, "new_string")?,
+                    ))
+                })
+                .collect::<Option<Vec<_>>>()?;
+            (format!("a/{}", path), hunks)
+        }
+        "Write" => {
+            let content = string(input, "content")?;
+            ("/dev/null".to_string(), vec![hunk("", &content)])
+        }
+        _ => return None,
+    };
+
+    let mut patch = format!("--- {}\n+++ b/{}\n", old_path, path);
+    for hunk in hunks {
+        patch.push_str(&hunk);
+    }
+    Some(patch)
+}
+
+/// Path relative to the project directory, or without its leading `/` if
+/// it is outside the project.
+pub(super) fn relative_path<'a>(path: &'a str, project_path: &str) -> &'a str {
+    let relative = match path.strip_prefix(project_path) {
+        Some(rest) if !project_path.is_empty() && rest.starts_with(['/', '\\']) => rest,
+        _ => path,
+    };
+    relative.trim_start_matches(['/', '\\'])
+}
+
+/// One hunk replacing `old` with `new`, keeping up to `PATCH_CONTEXT_LINES`
+/// of the lines they share at each end as context.
+fn hunk(old: &str, new: &str) -> String {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+
+    let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let start = prefix.saturating_sub(PATCH_CONTEXT_LINES);
+    let end_context = suffix.min(PATCH_CONTEXT_LINES);
+    let removed = &old[prefix..old.len() - suffix];
+    let added = &new[prefix..new.len() - suffix];
+    let trailing = &old[old.len() - suffix..old.len() - suffix + end_context];
+
+    let leading = &old[start..prefix];
+    let old_len = leading.len() + removed.len() + trailing.len();
+    let new_len = leading.len() + added.len() + trailing.len();
+    let mut hunk = format!(
+        "@@ -{} +{} @@\n",
+        hunk_range(start, old_len),
+        hunk_range(start, new_len)
+    );
+    let lines = leading
+        .iter()
+        .map(|line| (' ', line))
+        .chain(removed.iter().map(|line| ('-', line)))
+        .chain(added.iter().map(|line| ('+', line)))
+        .chain(trailing.iter().map(|line| (' ', line)));
+    for (marker, line) in lines {
+        hunk.push(marker);
+        hunk.push_str(line);
+        hunk.push('\n');
+    }
+    hunk
+}
+
+/// `start,len` range of a hunk header, with one-based line numbers; an
+/// empty range names the line before it.
+fn hunk_range(start: usize, len: usize) -> String {
+    if len == 0 {
+        format!("{},0", start)
+    } else {
+        format!("{},{}", start + 1, len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::TerminalTranscript;
+
+    fn block(block_type: ContentBlockType, content: &str, tool_name: Option<&str>) -> ContentBlock {
+        ContentBlock {
+            block_type,
+            content: content.to_string(),
+            language: None,
+            tool_name: tool_name.map(str::to_string),
+            spans: None,
+            terminal: None,
+            file_view: None,
+        }
+    }
+
+    #[test]
+    fn test_text_and_json_modes() {
+        let code = block(ContentBlockType::Code, "fn main() {}\n", None);
+        assert_eq!(
+            block_copy_text(&code, CopyMode::Text, "").as_deref(),
+            Some("fn main() {}")
+        );
+        assert_eq!(block_copy_text(&code, CopyMode::Json, ""), None);
+
+        let mut terminal = block(ContentBlockType::Terminal, "$ ls\nsrc", Some("Bash"));
+        terminal.terminal = Some(TerminalTranscript {
+            command: "ls".to_string(),
+            ..Default::default()
+        });
+        assert_eq!(
+            block_copy_text(&terminal, CopyMode::Json, "").as_deref(),
+            Some("{\n  \"command\": \"ls\"\n}")
+        );
+    }
+
+    #[test]
+    fn test_edit_patch() {
+        let input = json!({
+            "file_path": "/work/app/src/lib.rs",
+            "old_string": "a\nb\nc\nd\ne\nf\ng\nh",
+            "new_string": "a\nb\nc\nd\nE\nf\ng\nh",
+        });
+        let edit = block(ContentBlockType::ToolUse, &input.to_string(), Some("Edit"));
+        assert_eq!(
+            block_copy_text(&edit, CopyMode::Patch, "/work/app").unwrap(),
+            "--- a/src/lib.rs\n+++ b/src/lib.rs\n@@ -2,7 +2,7 @@\n b\n c\n d\n-e\n+E\n f\n g\n h\n"
+        );
+
+        let input = json!({ "file_path": "/tmp/new.txt", "content": "one\ntwo\n" });
+        let write = block(ContentBlockType::ToolUse, &input.to_string(), Some("Write"));
+        assert_eq!(
+            block_copy_text(&write, CopyMode::Patch, "/work/app").unwrap(),
+            "--- /dev/null\n+++ b/tmp/new.txt\n@@ -0,0 +1,2 @@\n+one\n+two\n"
+        );
+
+        let read = block(ContentBlockType::ToolUse, &input.to_string(), Some("Read"));
+        assert_eq!(block_copy_text(&read, CopyMode::Patch, ""), None);
+    }
+}
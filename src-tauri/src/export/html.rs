@@ -0,0 +1,254 @@
+//! HTML export.
+//!
+//! Produces a self-contained, print-friendly HTML document with inline
+//! styles and no external assets.
+
+use super::{file_view_title, PageInfo};
+use crate::locale::DateFormatter;
+use crate::models::{ContentBlock, ContentBlockType, Conversation, Message, MessageRole};
+
+/// Inline stylesheet, kept small and printer-friendly.
+const STYLE: &str = "body{font-family:system-ui,sans-serif;max-width:48rem;margin:2rem auto;padding:0 1rem;line-height:1.5;color:#1a1a1a}\
+pre{background:#f5f5f5;padding:.75rem;overflow-x:auto;white-space:pre-wrap;word-break:break-word}\
+.message{border-top:1px solid #ddd;padding:1rem 0;break-inside:avoid-page}\
+.meta{color:#666;font-size:.875rem}\
+.tool{border-left:3px solid #bbb;padding-left:.75rem;margin:.5rem 0}\
+nav{margin:1rem 0}nav a{margin-right:1rem}\
+@media print{nav{display:none}}";
+
+/// Renders a conversation (or one page of it) as a standalone HTML document.
+///
+/// # Arguments
+/// * `conversation` - Conversation metadata for the header
+/// * `messages` - Messages to render (all of them, or one page)
+/// * `page` - Page position and sibling links for multi-file exports
+/// * `dates` - Formats the dates
+pub fn render_html(
+    conversation: &Conversation,
+    messages: &[Message],
+    page: Option<&PageInfo>,
+    dates: &DateFormatter,
+) -> String {
+    let title = escape_html(&conversation.project_name);
+    let mut html = String::new();
+
+    html.push_str("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n");
+    html.push_str(&format!("<title>{}</title>\n", title));
+    html.push_str(&format!("<style>{}</style>\n</head>\n<body>\n", STYLE));
+    html.push_str(&format!("<h1>{}</h1>\n", title));
+
+    html.push_str("<p class=\"meta\">");
+    html.push_str(&format!(
+        "Started: {}<br>Last Updated: {}<br>Messages: {}",
+        escape_html(&dates.datetime(&conversation.start_time)),
+        escape_html(&dates.datetime(&conversation.last_time)),
+        conversation.messages.len()
+    ));
+    if let Some(tags) = conversation.tags.as_ref().filter(|t| !t.is_empty()) {
+        html.push_str(&format!("<br>Tags: {}", escape_html(&tags.join(", "))));
+    }
+    html.push_str("</p>\n");
+
+    if let Some(page) = page {
+        html.push_str(&page_nav(page));
+    }
+
+    if messages.is_empty() {
+        html.push_str("<p><em>This conversation contains no messages.</em></p>\n");
+    }
+    for message in messages {
+        html.push_str(&message_to_html(message, dates));
+    }
+
+    if let Some(page) = page {
+        html.push_str(&page_nav(page));
+    }
+
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+/// Renders the page navigation bar.
+fn page_nav(page: &PageInfo) -> String {
+    let mut nav = format!("<nav><strong>Part {} of {}</strong> ", page.number, page.total);
+    if page.number > 1 {
+        nav.push_str(&format!("<a href=\"{}\">First</a>", escape_html(&page.first)));
+    }
+    if let Some(previous) = &page.previous {
+        nav.push_str(&format!("<a href=\"{}\">Previous</a>", escape_html(previous)));
+    }
+    if let Some(next) = &page.next {
+        nav.push_str(&format!("<a href=\"{}\">Next</a>", escape_html(next)));
+    }
+    nav.push_str("</nav>\n");
+    nav
+}
+
+/// Converts a message to an HTML section.
+pub(super) fn message_to_html(message: &Message, dates: &DateFormatter) -> String {
+    let role_label = match message.role {
+        MessageRole::User => "User",
+        MessageRole::Assistant => "Assistant",
+        MessageRole::System => "System",
+    };
+
+    let mut html = format!(
+        "<section class=\"message\" id=\"msg-{}\">\n<h3>{}</h3>\n<p class=\"meta\">{}</p>\n",
+        message.ordinal,
+        role_label,
+        escape_html(&dates.datetime(&message.timestamp))
+    );
+    for block in &message.content {
+        html.push_str(&block_to_html(block));
+    }
+    html.push_str("</section>\n");
+    html
+}
+
+/// ` (exit N)` for terminal blocks whose command failed.
+fn exit_status(block: &ContentBlock) -> String {
+    match block.terminal.as_ref().and_then(|terminal| terminal.exit_code) {
+        Some(code) if code != 0 => format!(" (exit {})", code),
+        _ => String::new(),
+    }
+}
+
+/// Converts a content block to HTML.
+fn block_to_html(block: &ContentBlock) -> String {
+    match block.block_type {
+        ContentBlockType::Text => escape_html(&block.content)
+            .split("\n\n")
+            .map(|paragraph| format!("<p>{}</p>\n", paragraph.replace('\n', "<br>")))
+            .collect(),
+        ContentBlockType::Code => format!(
+            "<pre><code{}>{}</code></pre>\n",
+            block
+                .language
+                .as_deref()
+                .filter(|lang| !lang.is_empty())
+                .map(|lang| format!(" class=\"language-{}\"", escape_html(lang)))
+                .unwrap_or_default(),
+            escape_html(&block.content)
+        ),
+        ContentBlockType::ToolUse => format!(
+            "<div class=\"tool\"><strong>Tool Use: {}</strong><pre>{}</pre></div>\n",
+            escape_html(block.tool_name.as_deref().unwrap_or("Unknown")),
+            escape_html(&block.content)
+        ),
+        ContentBlockType::ToolResult => format!(
+            "<div class=\"tool\"><strong>Tool Result{}</strong><pre>{}</pre></div>\n",
+            block
+                .tool_name
+                .as_deref()
+                .map(|name| format!(": {}", escape_html(name)))
+                .unwrap_or_default(),
+            escape_html(&block.content)
+        ),
+        ContentBlockType::Terminal => format!(
+            "<div class=\"tool\"><strong>Terminal{}</strong><pre>{}</pre></div>\n",
+            exit_status(block),
+            escape_html(&block.content)
+        ),
+        ContentBlockType::FileView => format!(
+            "<div class=\"tool\"><strong>{}</strong><pre><code{}>{}</code></pre></div>\n",
+            escape_html(&file_view_title(block)),
+            block
+                .language
+                .as_deref()
+                .map(|lang| format!(" class=\"language-{}\"", escape_html(lang)))
+                .unwrap_or_default(),
+            escape_html(&block.content)
+        ),
+        ContentBlockType::Image => format!(
+            "<div class=\"tool\"><em>[Image attachment {}]</em></div>\n",
+            escape_html(&block.content)
+        ),
+    }
+}
+
+/// Escapes text for use in HTML content and attribute values.
+pub fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::TokenCount;
+
+    fn sample_conversation() -> Conversation {
+        Conversation {
+            id: "conv".to_string(),
+            project_path: "/p".to_string(),
+            project_name: "<script>".to_string(),
+            start_time: "2025-01-15T10:30:00Z".to_string(),
+            last_time: "2025-01-15T10:31:00Z".to_string(),
+            messages: vec![Message {
+                id: "msg_0".to_string(),
+                ordinal: 0,
+                role: MessageRole::User,
+                content: vec![ContentBlock {
+                    block_type: ContentBlockType::Code,
+                    content: "if a < b && c > d {}".to_string(),
+                    language: Some("rust".to_string()),
+                    tool_name: None,
+                    spans: None,
+                    terminal: None,
+                    file_view: None,
+                }],
+                timestamp: "2025-01-15T10:30:00Z".to_string(),
+                token_count: None,
+                collapsed_reminders: 0,
+                is_meta: false,
+            }],
+            total_tokens: TokenCount::default(),
+            bookmarked: None,
+            tags: None,
+            completeness: Default::default(),
+            content_hash: None,
+            read_position: None,
+        }
+    }
+
+    #[test]
+    fn test_render_html_escapes_content() {
+        let conversation = sample_conversation();
+        let html = render_html(&conversation, &conversation.messages, None, &DateFormatter::default());
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<title>&lt;script&gt;</title>"));
+        assert!(html.contains("<code class=\"language-rust\">if a &lt; b &amp;&amp; c &gt; d {}</code>"));
+        assert!(html.contains("id=\"msg-0\""));
+        assert!(!html.contains("<nav>"));
+    }
+
+    #[test]
+    fn test_render_html_page_nav() {
+        let conversation = sample_conversation();
+        let page = PageInfo {
+            number: 1,
+            total: 2,
+            first: "a_part-001.html".to_string(),
+            previous: None,
+            next: Some("a_part-002.html".to_string()),
+        };
+        let html = render_html(
+            &conversation,
+            &conversation.messages,
+            Some(&page),
+            &DateFormatter::default(),
+        );
+        assert_eq!(html.matches("<a href=\"a_part-002.html\">Next</a>").count(), 2);
+        assert!(!html.contains(">First<"));
+    }
+}
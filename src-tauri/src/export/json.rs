@@ -0,0 +1,66 @@
+//! JSON export, in the versioned schema of `models::export`.
+
+use crate::models::export::{ExportConversation, ExportDocument, ExportMessage};
+use crate::models::{Conversation, Message};
+
+/// Renders a conversation (or one page of it) as a JSON export document.
+///
+/// # Arguments
+/// * `conversation` - Conversation metadata
+/// * `messages` - Messages to include (all of them, or one page)
+pub fn render_json(conversation: &Conversation, messages: &[Message]) -> String {
+    let mut exported = ExportConversation::from(conversation);
+    exported.messages = messages.iter().map(ExportMessage::from).collect();
+    let mut json =
+        serde_json::to_string_pretty(&ExportDocument::new(vec![exported])).unwrap_or_default();
+    json.push('\n');
+    json
+}
+
+/// Converts a message to JSON, used to size pages.
+pub(super) fn message_to_json(message: &Message) -> String {
+    serde_json::to_string_pretty(&ExportMessage::from(message)).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::export::EXPORT_SCHEMA_VERSION;
+    use crate::models::{MessageRole, TokenCount};
+
+    #[test]
+    fn test_render_json_page() {
+        let message = |ordinal| Message {
+            id: format!("m{}", ordinal),
+            ordinal,
+            role: MessageRole::User,
+            content: vec![],
+            timestamp: String::new(),
+            token_count: None,
+            collapsed_reminders: 0,
+            is_meta: false,
+        };
+        let conversation = Conversation {
+            id: "c1".to_string(),
+            project_path: "/p".to_string(),
+            project_name: "p".to_string(),
+            start_time: String::new(),
+            last_time: String::new(),
+            messages: vec![message(0), message(1)],
+            total_tokens: TokenCount::default(),
+            bookmarked: Some(true),
+            tags: None,
+            completeness: Default::default(),
+            content_hash: None,
+            read_position: None,
+        };
+
+        let json = render_json(&conversation, &conversation.messages[1..]);
+        let document: ExportDocument = serde_json::from_str(&json).unwrap();
+        assert_eq!(document.schema_version, EXPORT_SCHEMA_VERSION);
+        let exported = &document.conversations[0];
+        assert!(exported.bookmarked);
+        assert_eq!(exported.messages.len(), 1);
+        assert_eq!(exported.messages[0].ordinal, 1);
+    }
+}
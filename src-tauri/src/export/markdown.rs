@@ -0,0 +1,259 @@
+//! Markdown export.
+//!
+//! Mirrors the layout of the frontend's Markdown export: a metadata header
+//! followed by one section per message, separated by horizontal rules.
+
+use super::{file_view_title, PageInfo};
+use crate::locale::DateFormatter;
+use crate::models::{ContentBlock, ContentBlockType, Conversation, Message, MessageRole};
+
+/// Renders a conversation (or one page of it) as Markdown.
+///
+/// # Arguments
+/// * `conversation` - Conversation metadata for the header
+/// * `messages` - Messages to render (all of them, or one page)
+/// * `clean` - Messages are a clean transcript; omit per-message timestamps
+/// * `page` - Page position and sibling links for multi-file exports
+/// * `dates` - Formats the dates
+pub fn render_markdown(
+    conversation: &Conversation,
+    messages: &[Message],
+    clean: bool,
+    page: Option<&PageInfo>,
+    dates: &DateFormatter,
+) -> String {
+    let mut lines: Vec<String> = Vec::new();
+
+    // Header
+    lines.push(format!("# {}", conversation.project_name));
+    lines.push(String::new());
+    if let Some(page) = page {
+        lines.push(page_links(page));
+        lines.push(String::new());
+    }
+    lines.push(format!("**Started:** {}", dates.datetime(&conversation.start_time)));
+    lines.push(format!("**Last Updated:** {}", dates.datetime(&conversation.last_time)));
+    lines.push(format!("**Messages:** {}", conversation.messages.len()));
+
+    if let Some(tags) = conversation.tags.as_ref().filter(|t| !t.is_empty()) {
+        lines.push(format!("**Tags:** {}", tags.join(", ")));
+    }
+    if clean {
+        lines.push("*Transcript only: tool calls and results are omitted.*".to_string());
+    }
+
+    lines.push(String::new());
+    lines.push("---".to_string());
+    lines.push(String::new());
+
+    if messages.is_empty() {
+        lines.push("*This conversation contains no messages.*".to_string());
+        lines.push(String::new());
+    } else {
+        for message in messages {
+            lines.push(message_to_markdown(message, clean, dates));
+            lines.push(String::new());
+            lines.push("---".to_string());
+            lines.push(String::new());
+        }
+    }
+
+    if let Some(page) = page {
+        lines.push(page_links(page));
+        lines.push(String::new());
+    }
+
+    lines.join("\n")
+}
+
+/// Renders the "Part N of M" line with links to the first, previous, and next pages.
+fn page_links(page: &PageInfo) -> String {
+    let mut parts = vec![format!("**Part {} of {}**", page.number, page.total)];
+    if page.number > 1 {
+        parts.push(format!("[First]({})", page.first));
+    }
+    if let Some(previous) = &page.previous {
+        parts.push(format!("[Previous]({})", previous));
+    }
+    if let Some(next) = &page.next {
+        parts.push(format!("[Next]({})", next));
+    }
+    parts.join(" · ")
+}
+
+/// Converts a message to a Markdown section.
+pub(super) fn message_to_markdown(message: &Message, clean: bool, dates: &DateFormatter) -> String {
+    let role_label = match message.role {
+        MessageRole::User => "User",
+        MessageRole::Assistant => "Assistant",
+        MessageRole::System => "System",
+    };
+
+    let content = message
+        .content
+        .iter()
+        .map(block_to_markdown)
+        .collect::<Vec<_>>()
+        .join("\n\n");
+
+    if clean {
+        format!("### {}\n\n{}", role_label, content)
+    } else {
+        format!(
+            "### {}\n\n*{}*\n\n{}",
+            role_label,
+            dates.datetime(&message.timestamp),
+            content
+        )
+    }
+}
+
+/// Converts a content block to Markdown.
+pub(super) fn block_to_markdown(block: &ContentBlock) -> String {
+    match block.block_type {
+        ContentBlockType::Text => block.content.clone(),
+        ContentBlockType::Code => format!(
+            "```{}\n{}\n```",
+            block.language.as_deref().unwrap_or_default(),
+            block.content
+        ),
+        ContentBlockType::ToolUse => format!(
+            "> **Tool Use: {}**\n>\n> ```\n> {}\n> ```",
+            block.tool_name.as_deref().unwrap_or("Unknown"),
+            block.content.split('\n').collect::<Vec<_>>().join("\n> ")
+        ),
+        ContentBlockType::ToolResult => format!(
+            "> **Tool Result{}**\n>\n> ```\n> {}\n> ```",
+            block
+                .tool_name
+                .as_deref()
+                .map(|name| format!(": {}", name))
+                .unwrap_or_default(),
+            block.content.split('\n').collect::<Vec<_>>().join("\n> ")
+        ),
+        ContentBlockType::Terminal => {
+            let exit_code = block.terminal.as_ref().and_then(|terminal| terminal.exit_code);
+            match exit_code {
+                Some(code) if code != 0 => {
+                    format!("```console\n{}\n```\n*Exit code {}*", block.content, code)
+                }
+                _ => format!("```console\n{}\n```", block.content),
+            }
+        }
+        ContentBlockType::FileView => format!(
+            "`{}`\n```{}\n{}\n```",
+            file_view_title(block),
+            block.language.as_deref().unwrap_or_default(),
+            block.content
+        ),
+        ContentBlockType::Image => format!("> *[Image attachment {}]*", block.content),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::TokenCount;
+    use crate::transform::clean_transcript;
+
+    fn block(block_type: ContentBlockType, content: &str, tool_name: Option<&str>) -> ContentBlock {
+        ContentBlock {
+            block_type,
+            content: content.to_string(),
+            language: None,
+            tool_name: tool_name.map(String::from),
+            spans: None,
+            terminal: None,
+            file_view: None,
+        }
+    }
+
+    fn sample_conversation() -> Conversation {
+        let message = |ordinal: u32, role, content| Message {
+            id: format!("msg_{}", ordinal),
+            ordinal,
+            role,
+            content,
+            timestamp: "2025-01-15T10:30:00Z".to_string(),
+            token_count: None,
+            collapsed_reminders: 0,
+            is_meta: false,
+        };
+
+        Conversation {
+            id: "conv".to_string(),
+            project_path: "/p".to_string(),
+            project_name: "my-project".to_string(),
+            start_time: "2025-01-15T10:30:00Z".to_string(),
+            last_time: "2025-01-15T10:31:00Z".to_string(),
+            messages: vec![
+                message(0, MessageRole::User, vec![block(ContentBlockType::Text, "Read the file", None)]),
+                message(
+                    1,
+                    MessageRole::Assistant,
+                    vec![block(ContentBlockType::ToolUse, "{\"path\":\"a.rs\"}", Some("Read"))],
+                ),
+                message(
+                    2,
+                    MessageRole::User,
+                    vec![block(ContentBlockType::ToolResult, "fn a() {}", Some("toolu_1"))],
+                ),
+                message(3, MessageRole::Assistant, vec![block(ContentBlockType::Text, "It defines a()", None)]),
+            ],
+            total_tokens: TokenCount::default(),
+            bookmarked: None,
+            tags: Some(vec!["rust".to_string()]),
+            completeness: Default::default(),
+            content_hash: None,
+            read_position: None,
+        }
+    }
+
+    #[test]
+    fn test_render_markdown_full() {
+        let conversation = sample_conversation();
+        let dates = DateFormatter::default();
+        let markdown = render_markdown(&conversation, &conversation.messages, false, None, &dates);
+        assert!(markdown.starts_with("# my-project\n"));
+        assert!(markdown.contains("**Started:** Wednesday, January 15, 2025 10:30 UTC"));
+        assert!(markdown.contains("**Tags:** rust"));
+        assert!(markdown.contains("> **Tool Use: Read**"));
+        assert!(markdown.contains("> **Tool Result: toolu_1**"));
+        assert_eq!(markdown.matches("### ").count(), 4);
+    }
+
+    #[test]
+    fn test_render_markdown_clean_transcript() {
+        let conversation = sample_conversation();
+        let messages = clean_transcript(conversation.messages.clone());
+        let markdown =
+            render_markdown(&conversation, &messages, true, None, &DateFormatter::default());
+        assert!(!markdown.contains("Tool Use"));
+        assert!(!markdown.contains("Tool Result"));
+        assert!(markdown.contains("*Transcript only"));
+        assert_eq!(markdown.matches("### User").count(), 1);
+        assert_eq!(markdown.matches("### Assistant").count(), 1);
+        assert!(markdown.contains("### Assistant\n\nIt defines a()"));
+    }
+
+    #[test]
+    fn test_render_markdown_page_links() {
+        let conversation = sample_conversation();
+        let page = PageInfo {
+            number: 2,
+            total: 3,
+            first: "chat_part-001.md".to_string(),
+            previous: Some("chat_part-001.md".to_string()),
+            next: Some("chat_part-003.md".to_string()),
+        };
+        let markdown = render_markdown(
+            &conversation,
+            &conversation.messages[..1],
+            false,
+            Some(&page),
+            &DateFormatter::default(),
+        );
+        let links = "**Part 2 of 3** · [First](chat_part-001.md) · [Previous](chat_part-001.md) · [Next](chat_part-003.md)";
+        assert_eq!(markdown.matches(links).count(), 2);
+    }
+}
@@ -0,0 +1,190 @@
+//! Conversation export rendering.
+//!
+//! Renders full conversations into shareable documents, short summaries
+//! and preview cards of them, and Spotlight stubs, and rebuilds the files
+//! they wrote.
+//! Rendering is pure; callers decide where the output is written.
+
+pub mod artifacts;
+pub mod collection;
+pub mod copy;
+pub mod html;
+pub mod json;
+pub mod markdown;
+pub mod pages;
+pub mod preview;
+pub mod site;
+pub mod spotlight;
+pub mod summary;
+pub mod template;
+
+use crate::locale::DateFormatter;
+use crate::models::{ContentBlock, Conversation, ExportFormat, Message};
+use crate::parser::normalize_timestamp;
+use crate::transform::clean_transcript;
+
+pub use artifacts::{extract_artifacts, zip_artifacts, Artifact};
+pub use collection::{index_file_name, render_index, unique_file_name, IndexEntry};
+pub use copy::block_copy_text;
+pub use pages::{paginate_conversation, ExportPage, DEFAULT_MAX_PAGE_BYTES};
+pub use preview::render_preview_image;
+pub use site::{SiteBuilder, SiteFile};
+pub use summary::render_summary;
+pub use template::{find_template, list_templates, render_template, templates_dir};
+
+/// Position of a page within a multi-file export, with links to its siblings.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PageInfo {
+    /// One-based page number.
+    pub number: usize,
+    /// Total number of pages.
+    pub total: usize,
+    /// File name of the first page.
+    pub first: String,
+    /// File name of the previous page, if any.
+    pub previous: Option<String>,
+    /// File name of the next page, if any.
+    pub next: Option<String>,
+}
+
+/// Renders a conversation in the requested export format, with dates
+/// formatted by `dates`.
+pub fn render_conversation(
+    conversation: &Conversation,
+    format: ExportFormat,
+    dates: &DateFormatter,
+) -> String {
+    let messages = prepare_messages(conversation, format);
+    render_page(conversation, &messages, format, None, dates)
+}
+
+/// Returns the file extension for an export format.
+pub fn file_extension(format: ExportFormat) -> &'static str {
+    match format {
+        ExportFormat::Markdown | ExportFormat::Transcript => "md",
+        ExportFormat::Html => "html",
+        ExportFormat::Json => "json",
+    }
+}
+
+/// Builds a file name stem (without extension) such as `my-project_2025-01-15`.
+///
+/// Matches the frontend's export naming; the project part is limited to 200
+/// characters to stay within filesystem limits.
+pub fn export_file_stem(conversation: &Conversation) -> String {
+    file_stem(&conversation.project_name, &conversation.last_time)
+}
+
+/// Builds the file name stem of a conversation by its project name and last
+/// message time, as `export_file_stem`.
+pub fn file_stem(project_name: &str, last_time: &str) -> String {
+    let mut project = String::new();
+    for c in project_name.chars() {
+        let mapped = match c {
+            '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*' => '-',
+            c if c.is_whitespace() => '-',
+            c => c.to_ascii_lowercase(),
+        };
+        // Collapse runs of dashes
+        if mapped == '-' && project.ends_with('-') {
+            continue;
+        }
+        project.push(mapped);
+    }
+    let project: String = project.trim_matches('-').chars().take(200).collect();
+    let project = if project.is_empty() {
+        "conversation".to_string()
+    } else {
+        project
+    };
+
+    let date = normalize_timestamp(last_time)
+        .map(|ts| ts.utc[..10].to_string())
+        .unwrap_or_else(|| "unknown-date".to_string());
+
+    format!("{}_{}", project, date)
+}
+
+/// Returns the messages to render for a format (clean formats drop tool activity).
+fn prepare_messages(conversation: &Conversation, format: ExportFormat) -> Vec<Message> {
+    if is_clean(format) {
+        clean_transcript(conversation.messages.clone())
+    } else {
+        conversation.messages.clone()
+    }
+}
+
+fn is_clean(format: ExportFormat) -> bool {
+    matches!(format, ExportFormat::Transcript)
+}
+
+/// Renders one page: the conversation header, the given messages, and page links.
+fn render_page(
+    conversation: &Conversation,
+    messages: &[Message],
+    format: ExportFormat,
+    page: Option<&PageInfo>,
+    dates: &DateFormatter,
+) -> String {
+    match format {
+        ExportFormat::Markdown | ExportFormat::Transcript => {
+            markdown::render_markdown(conversation, messages, is_clean(format), page, dates)
+        }
+        ExportFormat::Html => html::render_html(conversation, messages, page, dates),
+        ExportFormat::Json => json::render_json(conversation, messages),
+    }
+}
+
+/// Renders a single message, used to size pages.
+fn render_message(message: &Message, format: ExportFormat, dates: &DateFormatter) -> String {
+    match format {
+        ExportFormat::Markdown | ExportFormat::Transcript => {
+            markdown::message_to_markdown(message, is_clean(format), dates)
+        }
+        ExportFormat::Html => html::message_to_html(message, dates),
+        ExportFormat::Json => json::message_to_json(message),
+    }
+}
+
+/// `path:start-end` for file view blocks.
+fn file_view_title(block: &ContentBlock) -> String {
+    match &block.file_view {
+        Some(view) => {
+            let end = view.start_line + block.content.lines().count().saturating_sub(1) as u32;
+            format!("{}:{}-{}", view.path, view.start_line, end)
+        }
+        None => "File".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::TokenCount;
+
+    fn conversation(project_name: &str) -> Conversation {
+        Conversation {
+            id: "conv".to_string(),
+            project_path: "/p".to_string(),
+            project_name: project_name.to_string(),
+            start_time: "2025-01-15T10:30:00Z".to_string(),
+            last_time: "2025-01-16T08:00:00.123Z".to_string(),
+            messages: Vec::new(),
+            total_tokens: TokenCount::default(),
+            bookmarked: None,
+            tags: None,
+            completeness: Default::default(),
+            content_hash: None,
+            read_position: None,
+        }
+    }
+
+    #[test]
+    fn test_export_file_stem() {
+        assert_eq!(
+            export_file_stem(&conversation("My Project: v2")),
+            "my-project-v2_2025-01-16"
+        );
+        assert_eq!(export_file_stem(&conversation("///")), "conversation_2025-01-16");
+    }
+}
@@ -0,0 +1,201 @@
+//! Multi-file export pagination.
+//!
+//! Very long conversations produce exports that downstream tools (editors,
+//! converters, printers) struggle with. Pagination splits them at message
+//! boundaries into numbered files that link to each other.
+
+use super::{file_extension, prepare_messages, render_message, render_page, PageInfo};
+use crate::locale::DateFormatter;
+use crate::models::{Conversation, ExportFormat, Message};
+
+/// Default target size of a single page (1 MB).
+pub const DEFAULT_MAX_PAGE_BYTES: usize = 1_000_000;
+
+/// One file of a paginated export.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExportPage {
+    /// File name, e.g. `my-project_2025-01-15_part-002.md`.
+    pub file_name: String,
+    /// Rendered document.
+    pub content: String,
+}
+
+/// Splits a conversation export into pages of roughly `max_page_bytes`.
+///
+/// Pages break between messages, so a single oversized message still gets
+/// a page of its own. Conversations that fit on one page produce a single
+/// file without part numbers or navigation.
+///
+/// # Arguments
+/// * `conversation` - Conversation to export
+/// * `format` - Export format
+/// * `file_stem` - File name without extension (see `export_file_stem`)
+/// * `max_page_bytes` - Target maximum size of the messages on each page
+/// * `dates` - Formats the dates
+pub fn paginate_conversation(
+    conversation: &Conversation,
+    format: ExportFormat,
+    file_stem: &str,
+    max_page_bytes: usize,
+    dates: &DateFormatter,
+) -> Vec<ExportPage> {
+    let extension = file_extension(format);
+    let messages = prepare_messages(conversation, format);
+    let groups = group_messages(&messages, format, max_page_bytes, dates);
+
+    if groups.len() <= 1 {
+        return vec![ExportPage {
+            file_name: format!("{}.{}", file_stem, extension),
+            content: render_page(conversation, &messages, format, None, dates),
+        }];
+    }
+
+    let total = groups.len();
+    let names: Vec<String> = (1..=total)
+        .map(|number| format!("{}_part-{:03}.{}", file_stem, number, extension))
+        .collect();
+
+    groups
+        .iter()
+        .enumerate()
+        .map(|(idx, group)| {
+            let page = PageInfo {
+                number: idx + 1,
+                total,
+                first: names[0].clone(),
+                previous: idx.checked_sub(1).map(|prev| names[prev].clone()),
+                next: names.get(idx + 1).cloned(),
+            };
+            ExportPage {
+                file_name: names[idx].clone(),
+                content: render_page(conversation, group, format, Some(&page), dates),
+            }
+        })
+        .collect()
+}
+
+/// Greedily groups consecutive messages so each group stays under the size limit.
+fn group_messages<'a>(
+    messages: &'a [Message],
+    format: ExportFormat,
+    max_page_bytes: usize,
+    dates: &DateFormatter,
+) -> Vec<&'a [Message]> {
+    let mut groups = Vec::new();
+    let mut start = 0;
+    let mut size = 0;
+
+    for (idx, message) in messages.iter().enumerate() {
+        let message_size = render_message(message, format, dates).len();
+        if idx > start && size + message_size > max_page_bytes {
+            groups.push(&messages[start..idx]);
+            start = idx;
+            size = 0;
+        }
+        size += message_size;
+    }
+    if start < messages.len() {
+        groups.push(&messages[start..]);
+    }
+
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ContentBlock, ContentBlockType, MessageRole, TokenCount};
+
+    fn conversation_with(count: u32, text_len: usize) -> Conversation {
+        Conversation {
+            id: "conv".to_string(),
+            project_path: "/p".to_string(),
+            project_name: "p".to_string(),
+            start_time: "2025-01-15T10:30:00Z".to_string(),
+            last_time: "2025-01-15T10:31:00Z".to_string(),
+            messages: (0..count)
+                .map(|ordinal| Message {
+                    id: format!("msg_{}", ordinal),
+                    ordinal,
+                    role: if ordinal % 2 == 0 {
+                        MessageRole::User
+                    } else {
+                        MessageRole::Assistant
+                    },
+                    content: vec![ContentBlock {
+                        block_type: ContentBlockType::Text,
+                        content: "x".repeat(text_len),
+                        language: None,
+                        tool_name: None,
+                        spans: None,
+                        terminal: None,
+                        file_view: None,
+                    }],
+                    timestamp: "2025-01-15T10:30:00Z".to_string(),
+                    token_count: None,
+                    collapsed_reminders: 0,
+                    is_meta: false,
+                })
+                .collect(),
+            total_tokens: TokenCount::default(),
+            bookmarked: None,
+            tags: None,
+            completeness: Default::default(),
+            content_hash: None,
+            read_position: None,
+        }
+    }
+
+    #[test]
+    fn test_small_conversation_is_single_file() {
+        let pages = paginate_conversation(
+            &conversation_with(3, 10),
+            ExportFormat::Markdown,
+            "p",
+            10_000,
+            &DateFormatter::default(),
+        );
+        assert_eq!(pages.len(), 1);
+        assert_eq!(pages[0].file_name, "p.md");
+        assert!(!pages[0].content.contains("Part 1"));
+    }
+
+    #[test]
+    fn test_large_conversation_is_split_with_cross_links() {
+        let pages = paginate_conversation(
+            &conversation_with(10, 1_000),
+            ExportFormat::Markdown,
+            "p",
+            2_500,
+            &DateFormatter::default(),
+        );
+
+        // Each message renders to a bit over 1 KB, so two fit per page
+        assert_eq!(pages.len(), 5);
+        assert_eq!(pages[0].file_name, "p_part-001.md");
+        assert_eq!(pages[4].file_name, "p_part-005.md");
+        assert!(pages[0].content.contains("[Next](p_part-002.md)"));
+        assert!(!pages[0].content.contains("[Previous]"));
+        assert!(pages[2].content.contains("[Previous](p_part-002.md)"));
+        assert!(pages[2].content.contains("[First](p_part-001.md)"));
+        assert!(!pages[4].content.contains("[Next]"));
+
+        // Every message appears exactly once across all pages
+        let sections: usize = pages.iter().map(|p| p.content.matches("### ").count()).sum();
+        assert_eq!(sections, 10);
+    }
+
+    #[test]
+    fn test_oversized_message_gets_its_own_page() {
+        let pages = paginate_conversation(
+            &conversation_with(2, 5_000),
+            ExportFormat::Html,
+            "p",
+            100,
+            &DateFormatter::default(),
+        );
+        assert_eq!(pages.len(), 2);
+        assert_eq!(pages[1].file_name, "p_part-002.html");
+        assert!(pages[1].content.contains("<a href=\"p_part-001.html\">Previous</a>"));
+    }
+}
@@ -0,0 +1,460 @@
+//! Preview cards of conversations.
+//!
+//! A preview card is a PNG picture of a conversation's first exchange,
+//! sized for link previews, that goes alongside shared links and exported
+//! files so file managers show what an export holds. It's drawn with a
+//! built-in 5x7 pixel font, so rendering needs no font files: typographic
+//! punctuation is spelled in ASCII, and other characters outside ASCII are
+//! drawn as `?`.
+
+use super::summary::message_text;
+use crate::locale::DateFormatter;
+use crate::models::{Conversation, MessageRole};
+use crate::transform::clean_transcript;
+use std::io;
+
+/// Width of a preview card, the usual size of link previews.
+pub const PREVIEW_WIDTH: u32 = 1200;
+
+/// Height of a preview card.
+pub const PREVIEW_HEIGHT: u32 = 630;
+
+/// Space between the text and the card's edges.
+const PADDING: u32 = 60;
+
+/// Scale of the project name, in pixels per font pixel.
+const TITLE_SCALE: u32 = 4;
+
+/// Scale of the exchange's text.
+const BODY_SCALE: u32 = 3;
+
+/// Scale of labels, the date, and the footer.
+const LABEL_SCALE: u32 = 2;
+
+/// Most lines of the question; the answer gets the rest of the card.
+const QUESTION_LINES: usize = 4;
+
+const BACKGROUND: Rgb = [0x1f, 0x23, 0x2b];
+const TEXT: Rgb = [0xe6, 0xe8, 0xeb];
+const MUTED: Rgb = [0x9a, 0xa0, 0xa6];
+const USER_ACCENT: Rgb = [0x6c, 0xa0, 0xdc];
+const ASSISTANT_ACCENT: Rgb = [0xd9, 0x77, 0x57];
+
+type Rgb = [u8; 3];
+
+/// Width and height of a glyph, in font pixels.
+const GLYPH_WIDTH: u32 = 5;
+const GLYPH_HEIGHT: u32 = 7;
+
+/// Font pixels from one glyph to the next, and from one line to the next.
+const ADVANCE: u32 = GLYPH_WIDTH + 1;
+const LINE_HEIGHT: u32 = GLYPH_HEIGHT + 3;
+
+/// Glyphs of printable ASCII (` ` to `~`), one byte per column from left
+/// to right, with the top row in the lowest bit.
+const GLYPHS: [[u8; 5]; 95] = [
+    [0x00, 0x00, 0x00, 0x00, 0x00], // ' '
+    [0x00, 0x00, 0x5f, 0x00, 0x00], // !
+    [0x00, 0x07, 0x00, 0x07, 0x00], // "
+    [0x14, 0x7f, 0x14, 0x7f, 0x14], // #
+    [0x24, 0x2a, 0x7f, 0x2a, 0x12], // $
+    [0x23, 0x13, 0x08, 0x64, 0x62], // %
+    [0x36, 0x49, 0x55, 0x22, 0x50], // &
+    [0x00, 0x05, 0x03, 0x00, 0x00], // '
+    [0x00, 0x1c, 0x22, 0x41, 0x00], // (
+    [0x00, 0x41, 0x22, 0x1c, 0x00], // )
+    [0x14, 0x08, 0x3e, 0x08, 0x14], // *
+    [0x08, 0x08, 0x3e, 0x08, 0x08], // +
+    [0x00, 0x50, 0x30, 0x00, 0x00], // ,
+    [0x08, 0x08, 0x08, 0x08, 0x08], // -
+    [0x00, 0x60, 0x60, 0x00, 0x00], // .
+    [0x20, 0x10, 0x08, 0x04, 0x02], // /
+    [0x3e, 0x51, 0x49, 0x45, 0x3e], // 0
+    [0x00, 0x42, 0x7f, 0x40, 0x00], // 1
+    [0x42, 0x61, 0x51, 0x49, 0x46], // 2
+    [0x21, 0x41, 0x45, 0x4b, 0x31], // 3
+    [0x18, 0x14, 0x12, 0x7f, 0x10], // 4
+    [0x27, 0x45, 0x45, 0x45, 0x39], // 5
+    [0x3c, 0x4a, 0x49, 0x49, 0x30], // 6
+    [0x01, 0x71, 0x09, 0x05, 0x03], // 7
+    [0x36, 0x49, 0x49, 0x49, 0x36], // 8
+    [0x06, 0x49, 0x49, 0x29, 0x1e], // 9
+    [0x00, 0x36, 0x36, 0x00, 0x00], // :
+    [0x00, 0x56, 0x36, 0x00, 0x00], // ;
+    [0x08, 0x14, 0x22, 0x41, 0x00], // <
+    [0x14, 0x14, 0x14, 0x14, 0x14], // =
+    [0x00, 0x41, 0x22, 0x14, 0x08], // >
+    [0x02, 0x01, 0x51, 0x09, 0x06], // ?
+    [0x32, 0x49, 0x79, 0x41, 0x3e], // @
+    [0x7e, 0x11, 0x11, 0x11, 0x7e], // A
+    [0x7f, 0x49, 0x49, 0x49, 0x36], // B
+    [0x3e, 0x41, 0x41, 0x41, 0x22], // C
+    [0x7f, 0x41, 0x41, 0x22, 0x1c], // D
+    [0x7f, 0x49, 0x49, 0x49, 0x41], // E
+    [0x7f, 0x09, 0x09, 0x01, 0x01], // F
+    [0x3e, 0x41, 0x41, 0x51, 0x32], // G
+    [0x7f, 0x08, 0x08, 0x08, 0x7f], // H
+    [0x00, 0x41, 0x7f, 0x41, 0x00], // I
+    [0x20, 0x40, 0x41, 0x3f, 0x01], // J
+    [0x7f, 0x08, 0x14, 0x22, 0x41], // K
+    [0x7f, 0x40, 0x40, 0x40, 0x40], // L
+    [0x7f, 0x02, 0x04, 0x02, 0x7f], // M
+    [0x7f, 0x04, 0x08, 0x10, 0x7f], // N
+    [0x3e, 0x41, 0x41, 0x41, 0x3e], // O
+    [0x7f, 0x09, 0x09, 0x09, 0x06], // P
+    [0x3e, 0x41, 0x51, 0x21, 0x5e], // Q
+    [0x7f, 0x09, 0x19, 0x29, 0x46], // R
+    [0x46, 0x49, 0x49, 0x49, 0x31], // S
+    [0x01, 0x01, 0x7f, 0x01, 0x01], // T
+    [0x3f, 0x40, 0x40, 0x40, 0x3f], // U
+    [0x1f, 0x20, 0x40, 0x20, 0x1f], // V
+    [0x7f, 0x20, 0x18, 0x20, 0x7f], // W
+    [0x63, 0x14, 0x08, 0x14, 0x63], // X
+    [0x03, 0x04, 0x78, 0x04, 0x03], // Y
+    [0x61, 0x51, 0x49, 0x45, 0x43], // Z
+    [0x00, 0x7f, 0x41, 0x41, 0x00], // [
+    [0x02, 0x04, 0x08, 0x10, 0x20], // \
+    [0x00, 0x41, 0x41, 0x7f, 0x00], // ]
+    [0x04, 0x02, 0x01, 0x02, 0x04], // ^
+    [0x40, 0x40, 0x40, 0x40, 0x40], // _
+    [0x00, 0x01, 0x02, 0x04, 0x00], // `
+    [0x20, 0x54, 0x54, 0x54, 0x78], // a
+    [0x7f, 0x48, 0x44, 0x44, 0x38], // b
+    [0x38, 0x44, 0x44, 0x44, 0x20], // c
+    [0x38, 0x44, 0x44, 0x48, 0x7f], // d
+    [0x38, 0x54, 0x54, 0x54, 0x18], // e
+    [0x08, 0x7e, 0x09, 0x01, 0x02], // f
+    [0x0c, 0x52, 0x52, 0x52, 0x3e], // g
+    [0x7f, 0x08, 0x04, 0x04, 0x78], // h
+    [0x00, 0x44, 0x7d, 0x40, 0x00], // i
+    [0x20, 0x40, 0x44, 0x3d, 0x00], // j
+    [0x00, 0x7f, 0x10, 0x28, 0x44], // k
+    [0x00, 0x41, 0x7f, 0x40, 0x00], // l
+    [0x7c, 0x04, 0x18, 0x04, 0x78], // m
+    [0x7c, 0x08, 0x04, 0x04, 0x78], // n
+    [0x38, 0x44, 0x44, 0x44, 0x38], // o
+    [0x7c, 0x14, 0x14, 0x14, 0x08], // p
+    [0x08, 0x14, 0x14, 0x18, 0x7c], // q
+    [0x7c, 0x08, 0x04, 0x04, 0x08], // r
+    [0x48, 0x54, 0x54, 0x54, 0x20], // s
+    [0x04, 0x3f, 0x44, 0x40, 0x20], // t
+    [0x3c, 0x40, 0x40, 0x20, 0x7c], // u
+    [0x1c, 0x20, 0x40, 0x20, 0x1c], // v
+    [0x3c, 0x40, 0x30, 0x40, 0x3c], // w
+    [0x44, 0x28, 0x10, 0x28, 0x44], // x
+    [0x0c, 0x50, 0x50, 0x50, 0x3c], // y
+    [0x44, 0x64, 0x54, 0x4c, 0x44], // z
+    [0x00, 0x08, 0x36, 0x41, 0x00], // {
+    [0x00, 0x00, 0x7f, 0x00, 0x00], // |
+    [0x00, 0x41, 0x36, 0x08, 0x00], // }
+    [0x02, 0x01, 0x02, 0x04, 0x02], // ~
+];
+
+/// Renders the preview card of a conversation as a PNG: its project and
+/// date, its first question, and the answer to it, with the date formatted
+/// by `dates`.
+///
+/// # Errors
+/// Only if the PNG encoder fails, which it doesn't for a valid canvas.
+pub fn render_preview_image(
+    conversation: &Conversation,
+    dates: &DateFormatter,
+) -> io::Result<Vec<u8>> {
+    let messages = clean_transcript(conversation.messages.clone());
+    let question_at = messages
+        .iter()
+        .position(|message| message.role == MessageRole::User && !message_text(message).is_empty());
+    let question = question_at.map(|at| message_text(&messages[at]));
+    let answer = question_at.and_then(|at| {
+        messages[at + 1..]
+            .iter()
+            .filter(|message| message.role == MessageRole::Assistant)
+            .map(message_text)
+            .find(|text| !text.is_empty())
+    });
+
+    let mut canvas = Canvas::new(PREVIEW_WIDTH, PREVIEW_HEIGHT, BACKGROUND);
+    canvas.fill_rect(0, 0, PREVIEW_WIDTH, 8, ASSISTANT_ACCENT);
+    let text_width = PREVIEW_WIDTH - 2 * PADDING;
+
+    let mut y = PADDING;
+    let title = wrap_text(
+        &conversation.project_name,
+        columns(text_width, TITLE_SCALE),
+        1,
+    );
+    canvas.draw_lines(PADDING, y, &title, TITLE_SCALE, TEXT);
+    y += LINE_HEIGHT * TITLE_SCALE;
+    canvas.draw_text(
+        PADDING,
+        y,
+        &dates.datetime(&conversation.start_time),
+        LABEL_SCALE,
+        MUTED,
+    );
+    y += LINE_HEIGHT * LABEL_SCALE * 2;
+
+    let footer_y = PREVIEW_HEIGHT - PADDING - GLYPH_HEIGHT * LABEL_SCALE;
+    let body_columns = columns(text_width, BODY_SCALE);
+    if let Some(question) = question {
+        canvas.draw_text(PADDING, y, "You", LABEL_SCALE, USER_ACCENT);
+        y += LINE_HEIGHT * LABEL_SCALE;
+        let lines = wrap_text(&question, body_columns, QUESTION_LINES);
+        y = canvas.draw_lines(PADDING, y, &lines, BODY_SCALE, TEXT) + LINE_HEIGHT * LABEL_SCALE;
+    }
+    if let Some(answer) = answer {
+        let room = footer_y.saturating_sub(y + LINE_HEIGHT * (LABEL_SCALE + BODY_SCALE));
+        let max_lines = (room / (LINE_HEIGHT * BODY_SCALE)) as usize;
+        if max_lines > 0 {
+            canvas.draw_text(PADDING, y, "Claude", LABEL_SCALE, ASSISTANT_ACCENT);
+            y += LINE_HEIGHT * LABEL_SCALE;
+            let lines = wrap_text(&answer, body_columns, max_lines);
+            canvas.draw_lines(PADDING, y, &lines, BODY_SCALE, TEXT);
+        }
+    }
+
+    let footer = format!(
+        "Claude Code History Viewer - {} messages",
+        conversation.messages.len()
+    );
+    canvas.draw_text(PADDING, footer_y, &footer, LABEL_SCALE, MUTED);
+    canvas.encode_png()
+}
+
+/// Characters fitting in `width` pixels at `scale`.
+fn columns(width: u32, scale: u32) -> usize {
+    ((width + scale) / (ADVANCE * scale)) as usize
+}
+
+/// Wraps text at word boundaries into at most `max_lines` lines of
+/// `columns` characters, ending with an ellipsis if it doesn't fit. Blank
+/// lines and code fences are dropped, and words longer than a line are
+/// broken.
+fn wrap_text(text: &str, columns: usize, max_lines: usize) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    let mut truncated = false;
+    'paragraphs: for paragraph in text.lines().map(str::trim) {
+        if paragraph.is_empty() || paragraph.starts_with("```") {
+            continue;
+        }
+        let mut line = String::new();
+        for word in to_ascii(paragraph).split_whitespace() {
+            let mut word = word;
+            loop {
+                let fits = if line.is_empty() {
+                    word.len() <= columns
+                } else {
+                    line.len() + 1 + word.len() <= columns
+                };
+                if fits {
+                    if !line.is_empty() {
+                        line.push(' ');
+                    }
+                    line.push_str(word);
+                    break;
+                }
+                if line.is_empty() {
+                    // Longer than a line on its own: break the word
+                    line.push_str(&word[..columns]);
+                    word = &word[columns..];
+                }
+                if lines.len() == max_lines {
+                    truncated = true;
+                    break 'paragraphs;
+                }
+                lines.push(std::mem::take(&mut line));
+            }
+        }
+        if lines.len() == max_lines {
+            truncated = true;
+            break;
+        }
+        lines.push(line);
+    }
+
+    if truncated {
+        if let Some(last) = lines.last_mut() {
+            last.truncate(columns.saturating_sub(3));
+            last.truncate(last.trim_end().len());
+            last.push_str("...");
+        }
+    }
+    lines
+}
+
+/// Spells text in printable ASCII, which the font covers.
+fn to_ascii(text: &str) -> String {
+    let mut ascii = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            ' '..='~' => ascii.push(c),
+            '\u{2018}' | '\u{2019}' => ascii.push('\''),
+            '\u{201c}' | '\u{201d}' => ascii.push('"'),
+            '\u{2013}' | '\u{2014}' => ascii.push('-'),
+            '\u{2026}' => ascii.push_str("..."),
+            '\u{2022}' | '\u{00b7}' => ascii.push('*'),
+            c if c.is_whitespace() => ascii.push(' '),
+            _ => ascii.push('?'),
+        }
+    }
+    ascii
+}
+
+/// An RGB picture being drawn.
+struct Canvas {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+impl Canvas {
+    fn new(width: u32, height: u32, background: Rgb) -> Self {
+        Self {
+            width,
+            height,
+            pixels: background.repeat((width * height) as usize),
+        }
+    }
+
+    /// Fills a rectangle, clipped to the canvas.
+    fn fill_rect(&mut self, x: u32, y: u32, width: u32, height: u32, color: Rgb) {
+        for row in y..(y + height).min(self.height) {
+            for column in x..(x + width).min(self.width) {
+                let at = ((row * self.width + column) * 3) as usize;
+                self.pixels[at..at + 3].copy_from_slice(&color);
+            }
+        }
+    }
+
+    /// Draws one line of text with its top left corner at `x`, `y`.
+    fn draw_text(&mut self, x: u32, y: u32, text: &str, scale: u32, color: Rgb) {
+        let mut left = x;
+        for c in to_ascii(text).bytes() {
+            let glyph = &GLYPHS[(c - b' ') as usize];
+            for (column, bits) in glyph.iter().enumerate() {
+                for row in 0..GLYPH_HEIGHT {
+                    if bits & (1 << row) != 0 {
+                        let px = left + column as u32 * scale;
+                        self.fill_rect(px, y + row * scale, scale, scale, color);
+                    }
+                }
+            }
+            left += ADVANCE * scale;
+        }
+    }
+
+    /// Draws lines of text from `y` down. Returns the `y` below the last.
+    fn draw_lines(&mut self, x: u32, y: u32, lines: &[String], scale: u32, color: Rgb) -> u32 {
+        let mut y = y;
+        for line in lines {
+            self.draw_text(x, y, line, scale, color);
+            y += LINE_HEIGHT * scale;
+        }
+        y
+    }
+
+    fn encode_png(&self) -> io::Result<Vec<u8>> {
+        let mut png = Vec::new();
+        let mut encoder = png::Encoder::new(&mut png, self.width, self.height);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.set_compression(png::Compression::Best);
+        let mut writer = encoder.write_header().map_err(io::Error::other)?;
+        writer
+            .write_image_data(&self.pixels)
+            .map_err(io::Error::other)?;
+        writer.finish().map_err(io::Error::other)?;
+        Ok(png)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ContentBlock, ContentBlockType, Message, TokenCount};
+
+    fn message(ordinal: u32, role: MessageRole, text: &str) -> Message {
+        Message {
+            id: format!("m{}", ordinal),
+            ordinal,
+            role,
+            content: vec![ContentBlock {
+                block_type: ContentBlockType::Text,
+                content: text.to_string(),
+                language: None,
+                tool_name: None,
+                spans: None,
+                terminal: None,
+                file_view: None,
+            }],
+            timestamp: "2025-01-15T10:00:00Z".to_string(),
+            token_count: None,
+            collapsed_reminders: 0,
+            is_meta: false,
+        }
+    }
+
+    #[test]
+    fn test_wrap_text() {
+        let text = "Fix the flaky login test\n\n```rust\nfn main() {}";
+        assert_eq!(
+            wrap_text(text, 12, 4),
+            vec!["Fix the", "flaky login", "test", "fn main() {}"]
+        );
+        assert_eq!(wrap_text(text, 12, 2), vec!["Fix the", "flaky log..."]);
+        assert_eq!(wrap_text("abcdefghij", 4, 3), vec!["abcd", "efgh", "ij"]);
+        assert_eq!(
+            wrap_text("\u{201c}caf\u{e9}\u{201d} \u{2014} ok", 20, 1),
+            vec!["\"caf?\" - ok"]
+        );
+    }
+
+    #[test]
+    fn test_render_preview_image() {
+        let conversation = Conversation {
+            id: "c1".to_string(),
+            project_path: "/work/my-project".to_string(),
+            project_name: "my-project".to_string(),
+            start_time: "2025-01-15T10:00:00Z".to_string(),
+            last_time: "2025-01-15T10:30:00Z".to_string(),
+            messages: vec![
+                message(0, MessageRole::User, "Why does the login test fail on CI?"),
+                message(
+                    1,
+                    MessageRole::Assistant,
+                    "The cookie expires before the retry.",
+                ),
+            ],
+            total_tokens: TokenCount::default(),
+            bookmarked: None,
+            tags: None,
+            completeness: Default::default(),
+            content_hash: None,
+            read_position: None,
+        };
+        let png = render_preview_image(&conversation, &DateFormatter::default()).unwrap();
+
+        let decoder = png::Decoder::new(png.as_slice());
+        let mut reader = decoder.read_info().unwrap();
+        let mut pixels = vec![0; reader.output_buffer_size()];
+        let frame = reader.next_frame(&mut pixels).unwrap();
+        assert_eq!((frame.width, frame.height), (PREVIEW_WIDTH, PREVIEW_HEIGHT));
+
+        // The title, question, and answer are drawn in their places; rows
+        // are checked two font pixels down, where lower case letters start
+        let has_text = |y: u32, scale: u32| {
+            let row_bytes = (PREVIEW_WIDTH * 3) as usize;
+            let start = (y + 2 * scale) as usize * row_bytes;
+            pixels[start..start + row_bytes]
+                .chunks(3)
+                .any(|pixel| pixel == TEXT)
+        };
+        let labels_at = PADDING + LINE_HEIGHT * (TITLE_SCALE + 2 * LABEL_SCALE);
+        let question_at = labels_at + LINE_HEIGHT * LABEL_SCALE;
+        let answer_at = question_at + LINE_HEIGHT * (BODY_SCALE + 2 * LABEL_SCALE);
+        assert!(has_text(PADDING, TITLE_SCALE));
+        assert!(has_text(question_at, BODY_SCALE));
+        assert!(has_text(answer_at, BODY_SCALE));
+        assert!(!has_text(answer_at + LINE_HEIGHT * BODY_SCALE, BODY_SCALE));
+    }
+}
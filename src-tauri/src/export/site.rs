@@ -0,0 +1,391 @@
+//! Static site export.
+//!
+//! Builds a browsable HTML archive that needs no server: an index page
+//! grouping conversations by project and date, one page per conversation,
+//! and a prebuilt inverted index that the index page searches in the
+//! browser. The search index is written both as JSON, for other tools, and
+//! as a script, since browsers don't let pages opened from disk fetch files.
+
+use super::collection::unique_file_name;
+use super::html::escape_html;
+use super::{export_file_stem, render_conversation};
+use crate::locale::DateFormatter;
+use crate::models::{ContentBlockType, Conversation, ExportFormat, MessageRole};
+use crate::parser::normalize_timestamp;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// Directory, relative to the site root, holding the conversation pages.
+pub const CONVERSATIONS_DIR: &str = "conversations";
+
+/// Version of the search index layout, for tools reading the JSON.
+pub const SEARCH_INDEX_VERSION: u32 = 1;
+
+/// Longest preview shown in the index and search results, in characters.
+const PREVIEW_LENGTH: usize = 160;
+
+/// Shortest indexed term, in characters.
+const MIN_TERM_LENGTH: usize = 2;
+
+/// Words too common to be worth indexing.
+const STOP_WORDS: &[&str] = &[
+    "an", "and", "are", "as", "at", "be", "but", "by", "for", "if", "in", "into", "is", "it", "no",
+    "not", "of", "on", "or", "so", "that", "the", "then", "there", "these", "this", "to", "was",
+    "we", "will", "with", "you",
+];
+
+/// A file of the site, with its path relative to the site root.
+#[derive(Debug, Clone)]
+pub struct SiteFile {
+    pub path: String,
+    pub content: String,
+}
+
+/// A conversation in the site, as listed in the index and search results.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SiteDocument {
+    title: String,
+    project: String,
+    date: String,
+    url: String,
+    preview: String,
+}
+
+/// Inverted index: documents, and for each term the documents containing
+/// it with the term's frequency in each, as `[document, frequency]` pairs.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SearchIndex<'a> {
+    version: u32,
+    documents: &'a [SiteDocument],
+    index: BTreeMap<&'a str, &'a [(usize, u32)]>,
+}
+
+/// Builds a static site one conversation at a time, so conversation pages
+/// can be written as they're rendered.
+#[derive(Debug, Default)]
+pub struct SiteBuilder {
+    used_names: HashSet<String>,
+    documents: Vec<SiteDocument>,
+    /// Last activity of each document, to order the index.
+    sort_keys: Vec<String>,
+    postings: HashMap<String, Vec<(usize, u32)>>,
+    dates: DateFormatter,
+}
+
+impl SiteBuilder {
+    /// A builder for a site whose pages format dates with `dates`.
+    pub fn new(dates: DateFormatter) -> Self {
+        Self {
+            dates,
+            ..Self::default()
+        }
+    }
+
+    /// Renders a conversation's page and adds it to the index.
+    pub fn add(&mut self, conversation: &Conversation) -> SiteFile {
+        let file_name = unique_file_name(
+            &mut self.used_names,
+            &export_file_stem(conversation),
+            ExportFormat::Html,
+        );
+        let path = format!("{}/{}", CONVERSATIONS_DIR, file_name);
+
+        let document = self.documents.len();
+        let mut frequencies: HashMap<String, u32> = HashMap::new();
+        let mut preview = String::new();
+        for message in &conversation.messages {
+            for block in &message.content {
+                if block.block_type != ContentBlockType::Text {
+                    continue;
+                }
+                if preview.is_empty() && message.role == MessageRole::User {
+                    preview = truncate(&block.content, PREVIEW_LENGTH);
+                }
+                for term in terms(&block.content) {
+                    *frequencies.entry(term).or_default() += 1;
+                }
+            }
+        }
+        for term in terms(&conversation.project_name) {
+            *frequencies.entry(term).or_default() += 1;
+        }
+        for (term, frequency) in frequencies {
+            self.postings
+                .entry(term)
+                .or_default()
+                .push((document, frequency));
+        }
+
+        self.documents.push(SiteDocument {
+            title: if preview.is_empty() {
+                conversation.project_name.clone()
+            } else {
+                truncate(&preview, 80)
+            },
+            project: conversation.project_name.clone(),
+            date: normalize_timestamp(&conversation.start_time)
+                .map(|ts| ts.utc[..10].to_string())
+                .unwrap_or_default(),
+            url: path.clone(),
+            preview,
+        });
+        self.sort_keys.push(
+            normalize_timestamp(&conversation.last_time)
+                .map(|ts| ts.utc)
+                .unwrap_or_default(),
+        );
+
+        // Conversation pages link back to the index
+        let content = render_conversation(conversation, ExportFormat::Html, &self.dates).replacen(
+            "<body>\n",
+            "<body>\n<nav><a href=\"../index.html\">All conversations</a></nav>\n",
+            1,
+        );
+        SiteFile { path, content }
+    }
+
+    /// Number of conversations added.
+    pub fn len(&self) -> usize {
+        self.documents.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.documents.is_empty()
+    }
+
+    /// Renders the index page and the search index.
+    pub fn finish(self) -> Vec<SiteFile> {
+        let search_index = SearchIndex {
+            version: SEARCH_INDEX_VERSION,
+            documents: &self.documents,
+            index: self
+                .postings
+                .iter()
+                .map(|(term, postings)| (term.as_str(), postings.as_slice()))
+                .collect(),
+        };
+        let json = serde_json::to_string(&search_index).unwrap_or_default();
+
+        vec![
+            SiteFile {
+                path: "index.html".to_string(),
+                content: self.render_index(),
+            },
+            SiteFile {
+                path: "search-index.js".to_string(),
+                content: format!("window.SEARCH_INDEX = {};\n", json),
+            },
+            SiteFile {
+                path: "search-index.json".to_string(),
+                content: json,
+            },
+        ]
+    }
+
+    /// Index page: conversations by project, newest first within each.
+    fn render_index(&self) -> String {
+        let mut projects: BTreeMap<&str, Vec<usize>> = BTreeMap::new();
+        for (document, entry) in self.documents.iter().enumerate() {
+            projects.entry(&entry.project).or_default().push(document);
+        }
+
+        let mut html = String::from(
+            "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+             <title>Conversation Archive</title>\n",
+        );
+        html.push_str(&format!(
+            "<style>{}</style>\n</head>\n<body>\n",
+            INDEX_STYLE
+        ));
+        html.push_str("<h1>Conversation Archive</h1>\n");
+        html.push_str(&format!(
+            "<p class=\"meta\">{} conversations in {} projects</p>\n",
+            self.documents.len(),
+            projects.len()
+        ));
+        html.push_str(
+            "<input id=\"search\" type=\"search\" placeholder=\"Search conversations\" \
+             autocomplete=\"off\">\n<ol id=\"results\" hidden></ol>\n<div id=\"browse\">\n",
+        );
+
+        for (project, mut documents) in projects {
+            documents.sort_by(|a, b| self.sort_keys[*b].cmp(&self.sort_keys[*a]));
+            html.push_str(&format!(
+                "<section>\n<h2>{}</h2>\n<ul>\n",
+                escape_html(project)
+            ));
+            for document in documents {
+                let entry = &self.documents[document];
+                html.push_str(&format!(
+                    "<li><a href=\"{}\">{}</a> <span class=\"meta\">{}</span></li>\n",
+                    escape_html(&entry.url),
+                    escape_html(&entry.title),
+                    escape_html(&self.dates.datetime(&self.sort_keys[document]))
+                ));
+            }
+            html.push_str("</ul>\n</section>\n");
+        }
+
+        html.push_str("</div>\n<script src=\"search-index.js\"></script>\n");
+        html.push_str(&format!("<script>{}</script>\n", SEARCH_SCRIPT));
+        html.push_str("</body>\n</html>\n");
+        html
+    }
+}
+
+/// Index page stylesheet.
+const INDEX_STYLE: &str = "body{font-family:system-ui,sans-serif;max-width:48rem;margin:2rem auto;padding:0 1rem;line-height:1.5;color:#1a1a1a}\
+.meta{color:#666;font-size:.875rem}\
+#search{width:100%;padding:.5rem;font-size:1rem;margin:1rem 0;box-sizing:border-box}\
+#results li{margin-bottom:.75rem}";
+
+/// Searches `window.SEARCH_INDEX` as the query changes. Every query term
+/// must prefix-match an indexed term; documents are ranked by the summed
+/// frequencies of the matched terms.
+const SEARCH_SCRIPT: &str = r#"
+(function () {
+  var data = window.SEARCH_INDEX;
+  var input = document.getElementById("search");
+  var results = document.getElementById("results");
+  var browse = document.getElementById("browse");
+  if (!data) { input.disabled = true; return; }
+  var terms = Object.keys(data.index);
+  function tokenize(text) {
+    return text.toLowerCase().split(/[^\p{L}\p{N}_]+/u).filter(function (t) { return t.length > 1; });
+  }
+  function search(query) {
+    var scores = null;
+    tokenize(query).forEach(function (queryTerm) {
+      var termScores = {};
+      terms.forEach(function (term) {
+        if (term.indexOf(queryTerm) !== 0) return;
+        data.index[term].forEach(function (posting) {
+          termScores[posting[0]] = (termScores[posting[0]] || 0) + posting[1];
+        });
+      });
+      if (scores === null) { scores = termScores; return; }
+      Object.keys(scores).forEach(function (doc) {
+        if (termScores[doc] === undefined) delete scores[doc];
+        else scores[doc] += termScores[doc];
+      });
+    });
+    if (scores === null) return null;
+    return Object.keys(scores).sort(function (a, b) { return scores[b] - scores[a]; });
+  }
+  input.addEventListener("input", function () {
+    var matches = search(input.value);
+    browse.hidden = matches !== null;
+    results.hidden = matches === null;
+    results.replaceChildren();
+    (matches || []).slice(0, 100).forEach(function (doc) {
+      var entry = data.documents[doc];
+      var item = document.createElement("li");
+      var link = document.createElement("a");
+      link.href = entry.url;
+      link.textContent = entry.title;
+      var meta = document.createElement("div");
+      meta.className = "meta";
+      meta.textContent = entry.project + " · " + entry.date;
+      item.append(link, meta);
+      results.append(item);
+    });
+  });
+})();
+"#;
+
+/// Lowercased words of the text worth indexing.
+fn terms(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .filter(|word| word.chars().count() >= MIN_TERM_LENGTH)
+        .map(str::to_lowercase)
+        .filter(|word| !STOP_WORDS.contains(&word.as_str()))
+}
+
+/// First line of the text, cut to `max` characters with an ellipsis.
+fn truncate(text: &str, max: usize) -> String {
+    let line = text.trim().lines().next().unwrap_or_default();
+    if line.chars().count() <= max {
+        return line.to_string();
+    }
+    let mut cut: String = line.chars().take(max.saturating_sub(1)).collect();
+    cut.push('…');
+    cut
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ContentBlock, Message, TokenCount};
+
+    fn conversation(project_name: &str, last_time: &str, question: &str) -> Conversation {
+        Conversation {
+            id: format!("{}-{}", project_name, last_time),
+            project_path: format!("/work/{}", project_name),
+            project_name: project_name.to_string(),
+            start_time: last_time.to_string(),
+            last_time: last_time.to_string(),
+            messages: vec![Message {
+                id: "m0".to_string(),
+                ordinal: 0,
+                role: MessageRole::User,
+                content: vec![ContentBlock {
+                    block_type: ContentBlockType::Text,
+                    content: question.to_string(),
+                    language: None,
+                    tool_name: None,
+                    spans: None,
+                    terminal: None,
+                    file_view: None,
+                }],
+                timestamp: last_time.to_string(),
+                token_count: None,
+                collapsed_reminders: 0,
+                is_meta: false,
+            }],
+            total_tokens: TokenCount::default(),
+            bookmarked: None,
+            tags: None,
+            completeness: Default::default(),
+            content_hash: None,
+            read_position: None,
+        }
+    }
+
+    #[test]
+    fn test_site_pages_and_index() {
+        let mut site = SiteBuilder::new(DateFormatter::default());
+        let older = site.add(&conversation(
+            "app",
+            "2025-01-01T10:00:00Z",
+            "Fix the parser",
+        ));
+        let newer = site.add(&conversation("app", "2025-01-02T10:00:00Z", "Parser tests"));
+        site.add(&conversation(
+            "docs",
+            "2025-01-03T10:00:00Z",
+            "Write <docs>",
+        ));
+        assert_eq!(site.len(), 3);
+
+        assert_eq!(older.path, "conversations/app_2025-01-01.html");
+        assert!(older.content.contains("<a href=\"../index.html\">"));
+
+        let files = site.finish();
+        let index = &files[0].content;
+        assert_eq!(files[0].path, "index.html");
+        // Newest first within a project
+        let newer_at = index.find(&newer.path).unwrap();
+        assert!(newer_at < index.find(&older.path).unwrap());
+        assert!(index.contains("<h2>docs</h2>"));
+        assert!(index.contains("Write &lt;docs&gt;"));
+
+        let json: serde_json::Value = serde_json::from_str(&files[2].content).unwrap();
+        assert_eq!(json["version"], SEARCH_INDEX_VERSION);
+        assert_eq!(json["documents"][1]["title"], "Parser tests");
+        assert_eq!(json["index"]["parser"], serde_json::json!([[0, 1], [1, 1]]));
+        assert!(json["index"].get("the").is_none());
+        assert!(files[1].content.starts_with("window.SEARCH_INDEX = {"));
+    }
+}
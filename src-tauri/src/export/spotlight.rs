@@ -0,0 +1,144 @@
+//! Spotlight stubs of conversations.
+//!
+//! On macOS, system-wide search finds conversations through a small
+//! Markdown file per conversation in a folder Spotlight indexes (see
+//! [`crate::ingest::spotlight`]). A stub holds what a search should match
+//! (the title, project, tags, and preview) and a `claude-history://` link
+//! that opens the conversation in the app.
+
+use super::file_stem;
+use crate::locale::DateFormatter;
+
+/// URL scheme of links opening the app.
+pub const DEEP_LINK_SCHEME: &str = "claude-history";
+
+/// Host of links opening a conversation.
+const CONVERSATION_HOST: &str = "conversation";
+
+/// Characters kept of the title, taken from the preview.
+const TITLE_CHARS: usize = 100;
+
+/// What a stub says about a conversation.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StubConversation {
+    pub id: String,
+    pub project_name: String,
+    pub project_path: String,
+    pub start_time: String,
+    pub last_time: String,
+    pub preview: String,
+    pub preview_medium: String,
+    pub message_count: i64,
+    pub tags: Vec<String>,
+}
+
+/// Link opening a conversation in the app.
+pub fn conversation_link(id: &str) -> String {
+    format!("{}://{}/{}", DEEP_LINK_SCHEME, CONVERSATION_HOST, id)
+}
+
+/// Returns the conversation ID of a link made by [`conversation_link`].
+pub fn parse_conversation_link(url: &str) -> Option<&str> {
+    let id = url
+        .strip_prefix(DEEP_LINK_SCHEME)?
+        .strip_prefix("://")?
+        .strip_prefix(CONVERSATION_HOST)?
+        .strip_prefix('/')?
+        .trim_end_matches('/');
+    (!id.is_empty() && !id.contains('/')).then_some(id)
+}
+
+/// File name of a stub, which Spotlight shows as the result, like
+/// `my-project_2025-01-15.md`.
+pub fn stub_file_name(conversation: &StubConversation) -> String {
+    format!(
+        "{}.md",
+        file_stem(&conversation.project_name, &conversation.last_time)
+    )
+}
+
+/// Renders the stub of a conversation, with dates formatted by `dates`.
+pub fn render_stub(conversation: &StubConversation, dates: &DateFormatter) -> String {
+    let first_line = conversation.preview.lines().next().unwrap_or("").trim();
+    let title: String = match first_line {
+        "" => conversation.project_name.clone(),
+        line => line.chars().take(TITLE_CHARS).collect(),
+    };
+
+    let mut lines = vec![
+        format!("# {}", title),
+        String::new(),
+        format!(
+            "- Project: {} ({})",
+            conversation.project_name, conversation.project_path
+        ),
+        format!("- Started: {}", dates.datetime(&conversation.start_time)),
+        format!("- Last message: {}", dates.datetime(&conversation.last_time)),
+        format!("- Messages: {}", conversation.message_count),
+    ];
+    if !conversation.tags.is_empty() {
+        lines.push(format!("- Tags: {}", conversation.tags.join(", ")));
+    }
+
+    let preview = if conversation.preview_medium.is_empty() {
+        &conversation.preview
+    } else {
+        &conversation.preview_medium
+    };
+    if !preview.trim().is_empty() {
+        lines.push(String::new());
+        lines.push(preview.trim().to_string());
+    }
+    lines.push(String::new());
+    lines.push(format!(
+        "[Open in Claude Code History Viewer]({})",
+        conversation_link(&conversation.id)
+    ));
+    lines.join("\n") + "\n"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_stub() {
+        let conversation = StubConversation {
+            id: "c1".to_string(),
+            project_name: "my-project".to_string(),
+            project_path: "/Users/me/my-project".to_string(),
+            start_time: "2025-01-15T10:00:00Z".to_string(),
+            last_time: "2025-01-15T10:30:00Z".to_string(),
+            preview: "Fix the flaky login test".to_string(),
+            preview_medium: "Fix the flaky login test\nIt fails on CI".to_string(),
+            message_count: 12,
+            tags: vec!["ci".to_string(), "tests".to_string()],
+        };
+        let stub = render_stub(&conversation, &DateFormatter::default());
+        assert!(stub.starts_with("# Fix the flaky login test\n"));
+        assert!(stub.contains("- Project: my-project (/Users/me/my-project)\n"));
+        assert!(stub.contains("- Tags: ci, tests\n"));
+        assert!(stub.contains("It fails on CI"));
+        assert!(stub.ends_with("(claude-history://conversation/c1)\n"));
+        assert_eq!(stub_file_name(&conversation), "my-project_2025-01-15.md");
+
+        let untitled = StubConversation {
+            project_name: "p".to_string(),
+            ..Default::default()
+        };
+        let stub = render_stub(&untitled, &DateFormatter::default());
+        assert!(stub.starts_with("# p\n"));
+        assert!(!stub.contains("Tags"));
+    }
+
+    #[test]
+    fn test_conversation_links_round_trip() {
+        let link = conversation_link("abc-123");
+        assert_eq!(link, "claude-history://conversation/abc-123");
+        assert_eq!(parse_conversation_link(&link), Some("abc-123"));
+        assert_eq!(parse_conversation_link(&(link + "/")), Some("abc-123"));
+        assert_eq!(parse_conversation_link("claude-history://conversation/"), None);
+        assert_eq!(parse_conversation_link("claude-history://settings/x"), None);
+        assert_eq!(parse_conversation_link("https://conversation/abc"), None);
+    }
+}
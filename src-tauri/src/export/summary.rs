@@ -0,0 +1,164 @@
+//! Short Markdown summaries of conversations.
+//!
+//! A summary tells what a conversation was about at a glance, for places
+//! with little room such as a chat message: the project and date, the
+//! opening question, and the assistant's last answer, both trimmed. The
+//! full transcript is meant to go alongside it as a file.
+
+use crate::locale::DateFormatter;
+use crate::models::{ContentBlockType, Conversation, Message, MessageRole};
+use crate::transform::clean_transcript;
+
+/// Characters kept of the opening question.
+const QUESTION_CHARS: usize = 500;
+
+/// Characters kept of the last answer.
+const ANSWER_CHARS: usize = 1000;
+
+/// Renders the summary of a conversation, with its date formatted by
+/// `dates`.
+pub fn render_summary(conversation: &Conversation, dates: &DateFormatter) -> String {
+    let messages = clean_transcript(conversation.messages.clone());
+    let mut lines = vec![format!(
+        "**{}** · {} · {} messages",
+        conversation.project_name.replace('*', ""),
+        dates.datetime(&conversation.start_time),
+        conversation.messages.len()
+    )];
+
+    let question = messages
+        .iter()
+        .find(|message| message.role == MessageRole::User)
+        .map(message_text);
+    let answer = messages
+        .iter()
+        .rev()
+        .find(|message| message.role == MessageRole::Assistant)
+        .map(message_text);
+    let sections = [
+        ("Question", question, QUESTION_CHARS),
+        ("Last answer", answer, ANSWER_CHARS),
+    ];
+    for (title, text, max_chars) in sections {
+        let Some(text) = text.filter(|text| !text.is_empty()) else {
+            continue;
+        };
+        lines.push(String::new());
+        lines.push(format!("**{}**", title));
+        for line in trim_chars(&text, max_chars).lines() {
+            lines.push(format!("> {}", line).trim_end().to_string());
+        }
+    }
+
+    if let Some(tags) = conversation.tags.as_ref().filter(|tags| !tags.is_empty()) {
+        let tags: Vec<String> = tags.iter().map(|tag| format!("`{}`", tag)).collect();
+        lines.push(String::new());
+        lines.push(format!("Tags: {}", tags.join(", ")));
+    }
+    lines.join("\n")
+}
+
+/// Text blocks of a message, as paragraphs.
+pub(crate) fn message_text(message: &Message) -> String {
+    message
+        .content
+        .iter()
+        .filter(|block| block.block_type == ContentBlockType::Text)
+        .map(|block| block.content.trim())
+        .filter(|text| !text.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// Cuts text to `max` characters at a word boundary, with an ellipsis.
+/// A code fence left open by the cut is closed.
+fn trim_chars(text: &str, max: usize) -> String {
+    if text.chars().count() <= max {
+        return text.to_string();
+    }
+    let cut: String = text.chars().take(max.saturating_sub(1)).collect();
+    let cut = match cut.rfind(char::is_whitespace) {
+        Some(end) if end > 0 => &cut[..end],
+        _ => &cut,
+    };
+    let mut trimmed = format!("{}…", cut.trim_end());
+    let fences = trimmed
+        .lines()
+        .filter(|line| line.trim_start().starts_with("```"))
+        .count();
+    if fences % 2 == 1 {
+        trimmed.push_str("\n```");
+    }
+    trimmed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ContentBlock, TokenCount};
+
+    fn message(ordinal: u32, role: MessageRole, text: &str) -> Message {
+        Message {
+            id: format!("m{}", ordinal),
+            ordinal,
+            role,
+            content: vec![ContentBlock {
+                block_type: ContentBlockType::Text,
+                content: text.to_string(),
+                language: None,
+                tool_name: None,
+                spans: None,
+                terminal: None,
+                file_view: None,
+            }],
+            timestamp: "2025-01-15T10:00:00Z".to_string(),
+            token_count: None,
+            collapsed_reminders: 0,
+            is_meta: false,
+        }
+    }
+
+    #[test]
+    fn test_render_summary() {
+        let conversation = Conversation {
+            id: "c1".to_string(),
+            project_path: "/work/api".to_string(),
+            project_name: "api".to_string(),
+            start_time: "2025-01-15T10:00:00Z".to_string(),
+            last_time: "2025-01-15T10:30:00Z".to_string(),
+            messages: vec![
+                message(
+                    0,
+                    MessageRole::User,
+                    "Why is checkout failing?\nIt started at 09:40.",
+                ),
+                message(1, MessageRole::Assistant, "Looking at the logs."),
+                message(2, MessageRole::Assistant, &"word ".repeat(400)),
+            ],
+            total_tokens: TokenCount::default(),
+            bookmarked: None,
+            tags: Some(vec!["incident".to_string()]),
+            completeness: Default::default(),
+            content_hash: None,
+            read_position: None,
+        };
+
+        let summary = render_summary(&conversation, &DateFormatter::default());
+        assert!(summary.starts_with(
+            "**api** · Wednesday, January 15, 2025 10:00 UTC · 3 messages\n\n\
+             **Question**\n> Why is checkout failing?\n> It started at 09:40.\n\n\
+             **Last answer**\n> Looking at the logs.\n>\n> word word"
+        ));
+        assert!(summary.ends_with("word…\n\nTags: `incident`"));
+        assert!(summary.chars().count() < 1200);
+    }
+
+    #[test]
+    fn test_trim_chars_closes_code_fence() {
+        assert_eq!(trim_chars("short", 10), "short");
+        assert_eq!(
+            trim_chars("Run this:\n```sh\ncargo test --workspace\n```", 30),
+            "Run this:\n```sh\ncargo test…\n```"
+        );
+    }
+}
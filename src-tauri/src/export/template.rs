@@ -0,0 +1,261 @@
+//! Exports rendered with user-editable Handlebars templates.
+//!
+//! Templates live in the `export-templates` directory under the app data
+//! directory, one file each, named `<name>.<extension>.hbs`: `wiki.md.hbs`
+//! is the template `wiki`, and its exports are `.md` files. The directory
+//! is created with an example template the first time templates are
+//! listed.
+//!
+//! Templates see a conversation in the JSON export schema
+//! (`models::export::ExportConversation`), so they keep working as the
+//! internal models change, plus `date` and `datetime` helpers that format
+//! a timestamp in the locale setting. Output is only HTML-escaped for
+//! `html` templates.
+
+use crate::db::sqlite::{get_app_data_dir, DbResult};
+use crate::locale::DateFormatter;
+use crate::models::export::ExportConversation;
+use crate::models::{Conversation, ExportTemplate};
+use handlebars::{
+    no_escape, Context, Handlebars, Helper, HelperResult, Output, RenderContext, RenderError,
+};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Name of the directory templates are read from.
+pub const TEMPLATES_DIR_NAME: &str = "export-templates";
+
+/// Suffix of template files.
+const TEMPLATE_SUFFIX: &str = ".hbs";
+
+/// Extension of exports whose template doesn't name one.
+const DEFAULT_EXTENSION: &str = "md";
+
+/// File name and contents of the example template.
+const EXAMPLE_TEMPLATE: (&str, &str) = ("wiki.md.hbs", include_str!("default-template.md.hbs"));
+
+/// Returns the directory templates are read from.
+pub fn templates_dir() -> DbResult<PathBuf> {
+    Ok(get_app_data_dir()?.join(TEMPLATES_DIR_NAME))
+}
+
+/// Reads a template file's name and export extension from its file name.
+fn template_at(path: &Path) -> Option<ExportTemplate> {
+    let stem = path.file_name()?.to_str()?.strip_suffix(TEMPLATE_SUFFIX)?;
+    let (name, extension) = match stem.rsplit_once('.') {
+        Some((name, extension)) if !name.is_empty() => (name, extension),
+        _ => (stem, DEFAULT_EXTENSION),
+    };
+    (!name.is_empty()).then(|| ExportTemplate {
+        name: name.to_string(),
+        extension: extension.to_string(),
+        path: path.to_string_lossy().to_string(),
+    })
+}
+
+/// Lists the templates in `dir` by name, creating it with the example
+/// template if it doesn't exist.
+pub fn list_templates(dir: &Path) -> io::Result<Vec<ExportTemplate>> {
+    if !dir.exists() {
+        fs::create_dir_all(dir)?;
+        let (file_name, source) = EXAMPLE_TEMPLATE;
+        fs::write(dir.join(file_name), source)?;
+    }
+
+    let mut templates = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_file() {
+            templates.extend(template_at(&path));
+        }
+    }
+    templates.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(templates)
+}
+
+/// Finds the template named `name` in `dir`.
+pub fn find_template(dir: &Path, name: &str) -> io::Result<Option<ExportTemplate>> {
+    Ok(list_templates(dir)?
+        .into_iter()
+        .find(|template| template.name == name))
+}
+
+/// Renders a conversation with a template's source.
+///
+/// # Arguments
+/// * `source` - Handlebars source of the template
+/// * `extension` - Extension of the exports, `html` to escape the output
+/// * `conversation` - Conversation to render, with the messages to include
+/// * `dates` - Formats dates for the `date` and `datetime` helpers
+pub fn render_template(
+    source: &str,
+    extension: &str,
+    conversation: &Conversation,
+    dates: &DateFormatter,
+) -> Result<String, RenderError> {
+    let mut registry = Handlebars::new();
+    if !extension.eq_ignore_ascii_case("html") {
+        registry.register_escape_fn(no_escape);
+    }
+    let dates = *dates;
+    registry.register_helper(
+        "date",
+        Box::new(
+            move |h: &Helper,
+                  _: &Handlebars,
+                  _: &Context,
+                  _: &mut RenderContext,
+                  out: &mut dyn Output|
+                  -> HelperResult {
+                out.write(&dates.date(timestamp_param(h)))?;
+                Ok(())
+            },
+        ),
+    );
+    registry.register_helper(
+        "datetime",
+        Box::new(
+            move |h: &Helper,
+                  _: &Handlebars,
+                  _: &Context,
+                  _: &mut RenderContext,
+                  out: &mut dyn Output|
+                  -> HelperResult {
+                out.write(&dates.datetime(timestamp_param(h)))?;
+                Ok(())
+            },
+        ),
+    );
+    registry.render_template(source, &ExportConversation::from(conversation))
+}
+
+/// The timestamp a date helper was given, or an empty string.
+fn timestamp_param<'a>(h: &'a Helper) -> &'a str {
+    h.param(0)
+        .and_then(|param| param.value().as_str())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ContentBlock, ContentBlockType, Message, MessageRole, TokenCount};
+    use tempfile::tempdir;
+
+    fn block(block_type: ContentBlockType, content: &str, language: Option<&str>) -> ContentBlock {
+        ContentBlock {
+            block_type,
+            content: content.to_string(),
+            language: language.map(str::to_string),
+            tool_name: None,
+            spans: None,
+            terminal: None,
+            file_view: None,
+        }
+    }
+
+    fn conversation() -> Conversation {
+        Conversation {
+            id: "c1".to_string(),
+            project_path: "/p".to_string(),
+            project_name: "Parser <rewrite>".to_string(),
+            start_time: "2025-01-15T10:00:00Z".to_string(),
+            last_time: "2025-01-15T10:05:00Z".to_string(),
+            messages: vec![
+                Message {
+                    id: "u-1".to_string(),
+                    ordinal: 0,
+                    role: MessageRole::User,
+                    content: vec![block(ContentBlockType::Text, "Why does it fail?", None)],
+                    timestamp: "2025-01-15T10:00:00Z".to_string(),
+                    token_count: None,
+                    collapsed_reminders: 0,
+                    is_meta: false,
+                },
+                Message {
+                    id: "a-1".to_string(),
+                    ordinal: 1,
+                    role: MessageRole::Assistant,
+                    content: vec![block(ContentBlockType::Code, "fn main() {}", Some("rust"))],
+                    timestamp: "2025-01-15T10:05:00Z".to_string(),
+                    token_count: None,
+                    collapsed_reminders: 0,
+                    is_meta: false,
+                },
+            ],
+            total_tokens: TokenCount::default(),
+            bookmarked: None,
+            tags: Some(vec!["rust".to_string(), "parser".to_string()]),
+            completeness: Default::default(),
+            content_hash: None,
+            read_position: None,
+        }
+    }
+
+    #[test]
+    fn test_list_templates_seeds_example() {
+        let dir = tempdir().unwrap();
+        let templates_dir = dir.path().join(TEMPLATES_DIR_NAME);
+
+        let templates = list_templates(&templates_dir).unwrap();
+        assert_eq!(templates.len(), 1);
+        assert_eq!(templates[0].name, "wiki");
+        assert_eq!(templates[0].extension, "md");
+
+        // A user's templates are listed by name; other files are ignored,
+        // and the example isn't written again once deleted
+        fs::remove_file(templates_dir.join("wiki.md.hbs")).unwrap();
+        fs::write(templates_dir.join("page.html.hbs"), "").unwrap();
+        fs::write(templates_dir.join("notes.hbs"), "").unwrap();
+        fs::write(templates_dir.join("README.txt"), "").unwrap();
+        let found: Vec<(String, String)> = list_templates(&templates_dir)
+            .unwrap()
+            .into_iter()
+            .map(|template| (template.name, template.extension))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                ("notes".to_string(), "md".to_string()),
+                ("page".to_string(), "html".to_string()),
+            ]
+        );
+        assert!(find_template(&templates_dir, "page").unwrap().is_some());
+        assert!(find_template(&templates_dir, "wiki").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_render_example_template() {
+        let (_, source) = EXAMPLE_TEMPLATE;
+        let rendered =
+            render_template(source, "md", &conversation(), &DateFormatter::default()).unwrap();
+        assert!(rendered.starts_with("# Parser <rewrite>\n"));
+        assert!(rendered.contains("**Started:** Wednesday, January 15, 2025 10:00 UTC\n"));
+        assert!(rendered.contains("**Messages:** 2\n"));
+        assert!(rendered.contains("**Tags:** rust, parser\n"));
+        assert!(rendered
+            .contains("## user (Wednesday, January 15, 2025 10:00 UTC)\n\nWhy does it fail?\n"));
+        assert!(rendered.contains("```rust\nfn main() {}\n```\n"));
+    }
+
+    #[test]
+    fn test_render_template_escapes_html_only() {
+        let dates = DateFormatter::default();
+        let source = "<h1>{{projectName}}</h1>";
+        assert_eq!(
+            render_template(source, "html", &conversation(), &dates).unwrap(),
+            "<h1>Parser &lt;rewrite&gt;</h1>"
+        );
+        assert_eq!(
+            render_template(source, "md", &conversation(), &dates).unwrap(),
+            "<h1>Parser <rewrite></h1>"
+        );
+        let german = DateFormatter::new("de-DE").unwrap();
+        assert_eq!(
+            render_template("{{date startTime}}", "md", &conversation(), &german).unwrap(),
+            "15.01.2025"
+        );
+        assert!(render_template("{{#each messages}}", "md", &conversation(), &dates).is_err());
+    }
+}
@@ -0,0 +1,201 @@
+//! Sessions found in more than one file.
+//!
+//! Conversation IDs are derived from the session ID, so a session copied to
+//! a second place (an import of a backup, another root) maps to the same
+//! conversation as the original, and whichever file was ingested last would
+//! silently take it over. A conversation whose row points at another file
+//! that still exists is a conflict, resolved by the session conflict
+//! policy. A file that no longer exists was moved, not copied, and is
+//! simply replaced.
+
+use crate::db::DbResult;
+use crate::models::SessionConflictPolicy;
+use crate::parser::{conflict_conversation_id, epoch_millis, ParsedConversation};
+use rusqlite::{Connection, OptionalExtension};
+use std::path::Path;
+use tracing::warn;
+
+/// Where a parsed conversation is written.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Placement {
+    /// Under this ID: the conversation's own, or its suffixed ID when it's
+    /// kept alongside another copy.
+    Write(String),
+    /// Not at all: another file has a more recent copy of the session.
+    Skip,
+}
+
+/// Returns the suffixed ID of a conversation if an earlier ingest kept it
+/// alongside another copy of its session.
+fn kept_copy_id(conn: &Connection, conv: &ParsedConversation) -> DbResult<Option<String>> {
+    let suffixed = conflict_conversation_id(&conv.id, &conv.file_path);
+    let exists = conn
+        .prepare_cached("SELECT 1 FROM conversations WHERE id = ?1")?
+        .query_row([&suffixed], |_| Ok(()))
+        .optional()?;
+    Ok(exists.map(|_| suffixed))
+}
+
+/// Returns the ID a conversation parsed from its file is stored under.
+pub fn stored_conversation_id(conn: &Connection, conv: &ParsedConversation) -> DbResult<String> {
+    Ok(kept_copy_id(conn, conv)?.unwrap_or_else(|| conv.id.clone()))
+}
+
+/// Decides where a conversation parsed from its file is written, and
+/// whether it conflicts with a copy of the session in another file.
+pub fn place_conversation(
+    conn: &Connection,
+    conv: &ParsedConversation,
+    policy: SessionConflictPolicy,
+) -> DbResult<(Placement, bool)> {
+    if let Some(id) = kept_copy_id(conn, conv)? {
+        return Ok((Placement::Write(id), false));
+    }
+
+    let existing: Option<(String, String)> = conn
+        .prepare_cached("SELECT file_path, last_time FROM conversations WHERE id = ?1")?
+        .query_row([&conv.id], |row| Ok((row.get(0)?, row.get(1)?)))
+        .optional()?;
+    let Some((other_file, other_last_time)) = existing else {
+        return Ok((Placement::Write(conv.id.clone()), false));
+    };
+    if Path::new(&other_file) == conv.file_path || !Path::new(&other_file).is_file() {
+        return Ok((Placement::Write(conv.id.clone()), false));
+    }
+
+    warn!(
+        "Session {} is in both {} and {:?}, resolving with {}",
+        conv.session_id,
+        other_file,
+        conv.file_path,
+        policy.as_str()
+    );
+    let placement = match policy {
+        SessionConflictPolicy::PreferNewest => {
+            if epoch_millis(&conv.last_time) >= epoch_millis(&other_last_time) {
+                Placement::Write(conv.id.clone())
+            } else {
+                Placement::Skip
+            }
+        }
+        SessionConflictPolicy::KeepBoth => {
+            Placement::Write(conflict_conversation_id(&conv.id, &conv.file_path))
+        }
+    };
+    Ok((placement, true))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::settings::save_settings;
+    use crate::db::sqlite::Database;
+    use crate::ingest::{ingest_files, NoProgress};
+    use crate::models::AppSettings;
+    use std::fs;
+    use std::path::PathBuf;
+    use tempfile::tempdir;
+
+    /// Writes a copy of session `s1` whose last message is at `last_time`.
+    fn write_session(dir: &Path, project: &str, last_time: &str) -> PathBuf {
+        let path = dir.join(project).join("s1.jsonl");
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(
+            &path,
+            format!(
+                concat!(
+                    r#"{{"type":"user","message":{{"content":"Hello"}},"timestamp":"2025-01-15T10:00:00Z","sessionId":"s1"}}"#,
+                    "\n",
+                    r#"{{"type":"assistant","message":{{"content":"Hi"}},"timestamp":"{}","sessionId":"s1"}}"#,
+                    "\n"
+                ),
+                last_time
+            ),
+        )
+        .unwrap();
+        path
+    }
+
+    fn setup_db(policy: SessionConflictPolicy) -> Database {
+        let db = Database::open_in_memory().unwrap();
+        db.init_schema().unwrap();
+        db.with_connection(|conn| {
+            save_settings(
+                conn,
+                &AppSettings {
+                    session_conflict_policy: policy,
+                    ..Default::default()
+                },
+            )
+        })
+        .unwrap();
+        db
+    }
+
+    fn rows(db: &Database) -> Vec<(String, String)> {
+        db.with_connection(|conn| {
+            let mut stmt =
+                conn.prepare("SELECT id, project_name FROM conversations ORDER BY id")?;
+            let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+            Ok(rows.collect::<Result<_, _>>()?)
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_prefer_newest_keeps_latest_copy() {
+        let dir = tempdir().unwrap();
+        let db = setup_db(SessionConflictPolicy::PreferNewest);
+        let original = write_session(dir.path(), "original", "2025-01-15T12:00:00Z");
+        let stale = write_session(dir.path(), "stale-backup", "2025-01-15T10:05:00Z");
+        let newer = write_session(dir.path(), "newer-backup", "2025-01-15T13:00:00Z");
+
+        ingest_files(&db, &[original], &NoProgress).unwrap();
+        let stats = ingest_files(&db, &[stale], &NoProgress).unwrap();
+        assert_eq!(stats.conflicts, 1);
+        assert_eq!(stats.new_conversations, 0);
+        let rows = rows(&db);
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].1, "original");
+
+        let stats = ingest_files(&db, &[newer], &NoProgress).unwrap();
+        assert_eq!((stats.conflicts, stats.new_conversations), (1, 0));
+        assert_eq!(stats.updated_conversations, 1);
+        assert_eq!(
+            self::rows(&db),
+            vec![(rows[0].0.clone(), "newer-backup".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_keep_both_suffixes_the_second_copy() {
+        let dir = tempdir().unwrap();
+        let db = setup_db(SessionConflictPolicy::KeepBoth);
+        let original = write_session(dir.path(), "original", "2025-01-15T12:00:00Z");
+        let copy = write_session(dir.path(), "backup", "2025-01-15T10:05:00Z");
+
+        ingest_files(&db, std::slice::from_ref(&original), &NoProgress).unwrap();
+        let stats = ingest_files(&db, std::slice::from_ref(&copy), &NoProgress).unwrap();
+        assert_eq!((stats.conflicts, stats.new_conversations), (1, 1));
+        let rows = rows(&db);
+        assert_eq!(rows.len(), 2);
+        let (id, suffixed) = (&rows[0].0, &rows[1].0);
+        assert_eq!(rows[0].1, "original");
+        assert_eq!(rows[1].1, "backup");
+        assert_eq!(*suffixed, conflict_conversation_id(id, &copy));
+
+        // Re-ingesting either file rewrites its own row, unchanged
+        db.with_connection(|conn| {
+            crate::db::metadata::set_ingest_status(
+                conn,
+                &[original.clone(), copy.clone()],
+                crate::db::metadata::IngestStatus::Pending,
+            )
+        })
+        .unwrap();
+        let stats = ingest_files(&db, &[original, copy], &NoProgress).unwrap();
+        assert_eq!(stats.processed_files, 2);
+        assert_eq!((stats.conflicts, stats.updated_conversations), (0, 0));
+        assert_eq!(self::rows(&db).len(), 2);
+    }
+}
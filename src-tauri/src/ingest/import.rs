@@ -0,0 +1,173 @@
+//! Import of session files dropped on the window.
+//!
+//! Dropped files may live anywhere, including places that are cleaned up
+//! later (Downloads, a mounted backup), so they're copied into the managed
+//! `imported` directory under the app data directory before being ingested
+//! from there. Each file keeps its name under a folder named after its
+//! source folder, which keeps the project name a session file in
+//! `~/.claude/projects/<project>/` would get, and dropping the same session
+//! again replaces the earlier copy.
+
+use super::{ingest_files, NoProgress};
+use crate::db::sqlite::{get_app_data_dir, Database, DbResult};
+use crate::models::DropImportResult;
+use crate::parser::{
+    discover_jsonl_files_in, is_session_file, primary_session_file, session_segments,
+    IgnoreRules,
+};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+/// Name of the managed directory dropped files are copied into.
+pub const IMPORTED_DIR_NAME: &str = "imported";
+
+/// Folder for files dropped without a parent folder name (e.g. a root).
+const FALLBACK_PROJECT_DIR: &str = "dropped";
+
+/// Returns the managed directory dropped files are copied into.
+pub fn imported_dir() -> DbResult<PathBuf> {
+    Ok(get_app_data_dir()?.join(IMPORTED_DIR_NAME))
+}
+
+/// Copies a session file, with its rotated segments, into `imported_dir`.
+/// Returns the copy of the primary file.
+fn copy_session(primary: &Path, imported_dir: &Path) -> io::Result<PathBuf> {
+    let project = primary
+        .parent()
+        .and_then(|dir| dir.file_name())
+        .map(|name| name.to_os_string())
+        .unwrap_or_else(|| FALLBACK_PROJECT_DIR.into());
+    let target_dir = imported_dir.join(project);
+    fs::create_dir_all(&target_dir)?;
+
+    let mut copied = target_dir.clone();
+    for segment in session_segments(primary) {
+        let Some(name) = segment.file_name() else {
+            continue;
+        };
+        let target = target_dir.join(name);
+        // Dropping an imported file again: copying onto itself would
+        // truncate it
+        let same_file = match (fs::canonicalize(&segment), fs::canonicalize(&target)) {
+            (Ok(source), Ok(existing)) => source == existing,
+            _ => false,
+        };
+        if !same_file {
+            fs::copy(&segment, &target)?;
+        }
+        copied = target;
+    }
+    Ok(copied)
+}
+
+/// Copies dropped session files (or the session files in dropped folders)
+/// into `imported_dir` and ingests them.
+///
+/// Paths that aren't session files, or that fail to copy, are logged and
+/// listed in the result rather than failing the import.
+pub fn import_dropped_files(
+    db: &Database,
+    paths: &[PathBuf],
+    imported_dir: &Path,
+) -> DbResult<DropImportResult> {
+    let mut result = DropImportResult::default();
+    let mut copies = Vec::new();
+
+    for path in paths {
+        let sessions = if path.is_dir() {
+            discover_jsonl_files_in(path, &IgnoreRules::default())
+        } else if path.is_file() && is_session_file(path) {
+            vec![primary_session_file(path)]
+        } else {
+            warn!(
+                "Import: ignoring dropped path {:?}, not a session file",
+                path
+            );
+            result
+                .rejected_paths
+                .push(path.to_string_lossy().to_string());
+            continue;
+        };
+
+        for session in sessions {
+            match copy_session(&session, imported_dir) {
+                Ok(copy) => copies.push(copy),
+                Err(e) => {
+                    warn!("Import: failed to copy {:?}: {}", session, e);
+                    result
+                        .rejected_paths
+                        .push(session.to_string_lossy().to_string());
+                }
+            }
+        }
+    }
+
+    result.copied_files = copies.len();
+    result.stats = ingest_files(db, &copies, &NoProgress)?;
+    info!(
+        "Import: {} dropped files copied, {} new and {} updated conversations",
+        result.copied_files, result.stats.new_conversations, result.stats.updated_conversations
+    );
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::demo::write_fixture_files;
+    use tempfile::tempdir;
+
+    fn setup_db() -> Database {
+        let db = Database::open_in_memory().unwrap();
+        db.init_schema().unwrap();
+        db
+    }
+
+    #[test]
+    fn test_import_dropped_files_copies_and_ingests() {
+        let source = tempdir().unwrap();
+        let imported = tempdir().unwrap();
+        let db = setup_db();
+        let fixtures = write_fixture_files(source.path()).unwrap();
+        let notes = source.path().join("notes.txt");
+        fs::write(&notes, "not a session").unwrap();
+
+        let dropped = vec![fixtures[0].clone(), notes.clone()];
+        let result = import_dropped_files(&db, &dropped, imported.path()).unwrap();
+        assert_eq!(result.copied_files, 1);
+        assert_eq!(result.stats.new_conversations, 1);
+        assert_eq!(
+            result.rejected_paths,
+            vec![notes.to_string_lossy().to_string()]
+        );
+
+        // The copy keeps its project folder and is what the conversation
+        // points at
+        let project = fixtures[0].parent().unwrap().file_name().unwrap();
+        let copy = imported
+            .path()
+            .join(project)
+            .join(fixtures[0].file_name().unwrap());
+        assert!(copy.is_file());
+        let file_path: String = db
+            .with_connection(|conn| {
+                Ok(conn.query_row("SELECT file_path FROM conversations", [], |row| row.get(0))?)
+            })
+            .unwrap();
+        assert_eq!(Path::new(&file_path), copy);
+
+        // Dropping the copy itself leaves it intact
+        let result =
+            import_dropped_files(&db, std::slice::from_ref(&copy), imported.path()).unwrap();
+        assert_eq!(result.copied_files, 1);
+        assert_eq!(fs::read(&copy).unwrap(), fs::read(&fixtures[0]).unwrap());
+
+        // A dropped folder imports every session in it
+        let result =
+            import_dropped_files(&db, &[source.path().to_path_buf()], imported.path()).unwrap();
+        assert_eq!(result.copied_files, fixtures.len());
+        assert_eq!(result.stats.new_conversations, fixtures.len() - 1);
+    }
+}
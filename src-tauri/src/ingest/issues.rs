@@ -0,0 +1,146 @@
+//! Issue keys mentioned in conversations.
+//!
+//! Jira and Linear refer to issues by keys such as `ABC-123`: a project
+//! prefix of capital letters and digits, a hyphen, and a number. Keys in
+//! user and assistant text are recorded at ingest so conversations can be
+//! looked up by the ticket they worked on. Tool calls and results are
+//! skipped, as logs and file contents mention too many unrelated keys.
+
+use crate::parser::jsonl::{RawContent, RawMessage, RawMessageType};
+use regex::Regex;
+use std::collections::BTreeSet;
+use std::sync::LazyLock;
+
+/// An issue key: a prefix of 2-10 capitals and digits, and a number.
+static ISSUE_KEY_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\b([A-Z][A-Z0-9]{1,9})-([1-9][0-9]{0,6})\b").expect("Invalid regex pattern")
+});
+
+/// Prefixes of standards, encodings, and models that look like issue keys.
+const NOT_ISSUE_PREFIXES: &[&str] = &[
+    "AES", "CP", "CVE", "CWE", "ECMA", "GPT", "HTTP", "IEC", "IEEE", "IPV4", "IPV6", "ISO", "MD5",
+    "PEP", "RFC", "SHA", "SSL", "TLS", "UTF", "WCAG",
+];
+
+/// Issue keys in text, in order of first mention, without duplicates.
+pub fn find_issue_keys(text: &str) -> Vec<String> {
+    let mut keys: Vec<String> = Vec::new();
+    for captures in ISSUE_KEY_REGEX.captures_iter(text) {
+        let whole = captures.get(0).expect("match has a whole group");
+        // Part of a longer identifier, like CVE-2024-1234 or a UUID
+        let rest = &text[whole.end()..];
+        let before = text[..whole.start()].chars().next_back();
+        let continues =
+            rest.starts_with(['-', '.']) && rest[1..].starts_with(char::is_alphanumeric);
+        if continues || before == Some('-') {
+            continue;
+        }
+        if NOT_ISSUE_PREFIXES.contains(&&captures[1]) {
+            continue;
+        }
+        let key = whole.as_str().to_string();
+        if !keys.contains(&key) {
+            keys.push(key);
+        }
+    }
+    keys
+}
+
+/// Issue keys mentioned in the user and assistant text of a conversation,
+/// sorted.
+pub fn detect_issue_keys(messages: &[RawMessage]) -> Vec<String> {
+    let mut keys = BTreeSet::new();
+    for message in messages {
+        if !matches!(
+            message.message_type,
+            RawMessageType::User | RawMessageType::Assistant
+        ) {
+            continue;
+        }
+        match &message.message.content {
+            RawContent::Text(text) => keys.extend(find_issue_keys(text)),
+            RawContent::Blocks(blocks) => {
+                for block in blocks.iter().filter(|block| block.block_type == "text") {
+                    if let Some(text) = &block.text {
+                        keys.extend(find_issue_keys(text));
+                    }
+                }
+            }
+        }
+    }
+    keys.into_iter().collect()
+}
+
+/// Key of the issue an issue tracker URL points to, such as `ABC-123` in
+/// `https://acme.atlassian.net/browse/ABC-123` or
+/// `https://linear.app/acme/issue/ABC-123/fix-login`.
+pub fn issue_key_from_url(url: &str) -> Option<String> {
+    find_issue_keys(url).into_iter().next()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::jsonl::RawContentBlock;
+
+    fn block(block_type: &str, text: &str) -> RawContentBlock {
+        RawContentBlock {
+            block_type: block_type.to_string(),
+            text: Some(text.to_string()),
+            name: None,
+            input: None,
+            tool_use_id: None,
+            content: None,
+            is_error: None,
+        }
+    }
+
+    #[test]
+    fn test_find_issue_keys() {
+        assert_eq!(
+            find_issue_keys("Fixes ABC-123 and PAY2-7 (see ABC-123), not abc-1 or ABC-0."),
+            vec!["ABC-123", "PAY2-7"]
+        );
+        assert!(find_issue_keys("UTF-8, SHA-256, ISO-8601, GPT-4, CVE-2024-1234").is_empty());
+        assert!(find_issue_keys("id 6F9619FF-8B86-D011 and X-1 and ABC-123x").is_empty());
+        assert_eq!(find_issue_keys("ticket ENG-42."), vec!["ENG-42"]);
+    }
+
+    #[test]
+    fn test_detect_issue_keys_skips_tool_output() {
+        let messages = vec![
+            RawMessage::new(
+                RawMessageType::User,
+                RawContent::Text("Can you fix PAY-12?".to_string()),
+            ),
+            RawMessage::new(
+                RawMessageType::Assistant,
+                RawContent::Blocks(vec![
+                    block("text", "PAY-12 is caused by ENG-7."),
+                    block("tool_use", "OPS-1"),
+                ]),
+            ),
+            RawMessage::new(
+                RawMessageType::System,
+                RawContent::Text("OPS-2".to_string()),
+            ),
+        ];
+        assert_eq!(detect_issue_keys(&messages), vec!["ENG-7", "PAY-12"]);
+    }
+
+    #[test]
+    fn test_issue_key_from_url() {
+        assert_eq!(
+            issue_key_from_url("https://acme.atlassian.net/browse/OPS-981").as_deref(),
+            Some("OPS-981")
+        );
+        assert_eq!(
+            issue_key_from_url("https://linear.app/acme/issue/ENG-42/fix-login-loop").as_deref(),
+            Some("ENG-42")
+        );
+        assert_eq!(
+            issue_key_from_url("https://github.com/acme/app/issues/42"),
+            None
+        );
+    }
+}
@@ -0,0 +1,823 @@
+//! Ingest of session files into the database.
+//!
+//! Every path that brings conversations into the database (the startup
+//! scan, the file watcher, the rescan, reimport, and import commands, and
+//! files dropped on the window) goes
+//! through [`ingest_files`], so previews, usage statistics, search
+//! indexing, Spotlight stubs, and checkpoints can't drift apart.
+//!
+//! Files are ingested in transactional batches with per-file checkpoints
+//! in `file_metadata` (see [`crate::db::metadata`]): all files are first
+//! marked `pending`, each batch is marked `processing`, parsed, and written
+//! in one transaction that also marks its files `done`. A crash loses at
+//! most the current batch, which the next scan resumes.
+
+mod conflicts;
+mod import;
+mod issues;
+mod preview;
+mod resolution;
+pub mod spotlight;
+
+pub use conflicts::{place_conversation, stored_conversation_id, Placement};
+pub use import::{import_dropped_files, imported_dir, IMPORTED_DIR_NAME};
+pub use issues::{detect_issue_keys, find_issue_keys, issue_key_from_url};
+pub use preview::{
+    generate_previews, PreviewOptions, Previews, MAX_PREVIEW_LENGTH, MIN_PREVIEW_LENGTH,
+};
+pub use resolution::{classify_text, detect_resolution};
+pub use spotlight::{apply_spotlight_setting, spotlight_enabled};
+
+use crate::db::access::record_access_failures;
+use crate::db::children::{clear_detected_parent, detect_parents};
+use crate::db::continuations::replace_continuations;
+use crate::db::identity::reconcile_moved_files;
+use crate::db::issues::replace_detected_issues;
+use crate::db::metadata::{
+    get_modified_files, set_ingest_status, update_file_metadata, IngestStatus, ModifiedFile,
+};
+use crate::db::observations::record_observations;
+use crate::db::retention::is_deleted;
+use crate::db::settings::load_settings;
+use crate::db::sqlite::{Database, DbError, DbResult};
+use crate::metrics;
+use crate::models::{IngestProgress, IngestStats, SessionConflictPolicy};
+use crate::parser::ignore::BYTES_PER_MB;
+use crate::parser::jsonl::{
+    content_hash, discover_session_files, parse_conversation_file,
+    parse_conversation_file_observed, Discovery, ParsedConversation,
+};
+use crate::parser::{IgnoreRules, ParserError};
+use crate::search::index::index_conversation_content;
+use crate::search::{index_questions, merge_search_indexes, INGEST_MERGE_PAGES};
+use crate::usage::{record_usage, summarize_usage};
+use crate::webhooks::enqueue_deliveries;
+use rusqlite::{Connection, OptionalExtension};
+use std::fs;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+use tracing::{debug, error, info, warn};
+
+/// Number of files whose data is committed in one transaction.
+const INGEST_BATCH_SIZE: usize = 50;
+
+/// Ingest errors that stop a whole scan; per-file failures are counted in
+/// [`IngestStats`] instead.
+#[derive(Error, Debug)]
+pub enum IngestError {
+    #[error("Database error: {0}")]
+    Database(#[from] DbError),
+
+    #[error("Parser error: {0}")]
+    Parser(#[from] ParserError),
+}
+
+/// Receives progress after each committed batch.
+pub trait ProgressSink {
+    fn report(&self, progress: &IngestProgress);
+}
+
+/// Discards progress.
+pub struct NoProgress;
+
+impl ProgressSink for NoProgress {
+    fn report(&self, _progress: &IngestProgress) {}
+}
+
+impl<F: Fn(&IngestProgress)> ProgressSink for F {
+    fn report(&self, progress: &IngestProgress) {
+        self(progress)
+    }
+}
+
+/// Scans `~/.claude/projects/` and ingests new and changed session files,
+/// leaving out those the ignore settings match.
+///
+/// Also re-links conversations whose session files were moved while the
+/// app was closed, and records the paths that couldn't be read (see
+/// [`crate::db::access`]).
+pub fn scan_all(db: &Database, progress: &dyn ProgressSink) -> Result<IngestStats, IngestError> {
+    let rules = db.with_connection(|conn| Ok(IgnoreRules::from_settings(&load_settings(conn)?)))?;
+    let Discovery {
+        files: all_files,
+        access_failures,
+    } = discover_session_files(&rules)?;
+    info!(
+        "Scan: found {} JSONL files, {} paths unreadable",
+        all_files.len(),
+        access_failures.len()
+    );
+
+    let now = chrono::Utc::now().timestamp_millis();
+    if let Err(e) = db.with_connection(|conn| record_access_failures(conn, &access_failures, now)) {
+        error!("Scan: failed to record access errors: {}", e);
+    }
+
+    if let Err(e) = db.with_connection(|conn| reconcile_moved_files(conn, &all_files)) {
+        error!("Scan: failed to reconcile moved files: {}", e);
+    }
+    Ok(ingest_files(db, &all_files, progress)?)
+}
+
+/// Ingests those of `paths` that are new, changed, or whose previous ingest
+/// was interrupted.
+///
+/// Files that fail to parse are logged, counted, and retried by the next
+/// scan. Only errors determining which files to ingest are returned.
+pub fn ingest_files(
+    db: &Database,
+    paths: &[PathBuf],
+    progress: &dyn ProgressSink,
+) -> DbResult<IngestStats> {
+    let files = db.with_connection(|conn| get_modified_files(conn, paths))?;
+    if files.is_empty() {
+        debug!("Ingest: all {} files up to date", paths.len());
+        return Ok(IngestStats::default());
+    }
+    info!("Ingest: {} files need processing", files.len());
+    let settings = db.with_connection(load_settings)?;
+    let preview_options = PreviewOptions::from_settings(&settings);
+    let partial_storage_bytes = settings.partial_storage_mb.map(|mb| u64::from(mb) * BYTES_PER_MB);
+
+    let file_paths: Vec<PathBuf> = files.iter().map(|f| f.file_path.clone()).collect();
+    db.with_connection_mut(|conn| {
+        let tx = conn.transaction()?;
+        set_ingest_status(&tx, &file_paths, IngestStatus::Pending)?;
+        tx.commit()?;
+        Ok(())
+    })?;
+
+    let mut stats = IngestStats::default();
+    for batch in files.chunks(INGEST_BATCH_SIZE) {
+        if let Err(e) = process_file_batch(
+            db,
+            batch,
+            preview_options,
+            settings.session_conflict_policy,
+            partial_storage_bytes,
+            &mut stats,
+        ) {
+            metrics::registry().add("ingest.file_errors", batch.len() as u64);
+            error!("Error processing batch of {} files: {}", batch.len(), e);
+            stats.failed_files += batch.len();
+        }
+        progress.report(&IngestProgress {
+            total_files: files.len(),
+            stats: stats.clone(),
+        });
+    }
+
+    info!(
+        "Ingest: {} files processed ({} failed), {} new and {} updated conversations, {} session conflicts",
+        stats.processed_files,
+        stats.failed_files,
+        stats.new_conversations,
+        stats.updated_conversations,
+        stats.conflicts
+    );
+
+    // Sub-agent transcripts may have come in before or after their sessions
+    if stats.processed_files > 0 {
+        match db.with_connection(detect_parents) {
+            Ok(0) => {}
+            Ok(nested) => info!("Ingest: nested {} sub-agent transcripts", nested),
+            Err(e) => warn!("Ingest: failed to nest sub-agent transcripts: {}", e),
+        }
+    }
+
+    // A webhook problem shouldn't fail the ingest that triggered it
+    match db.with_connection(|conn| enqueue_deliveries(conn, &stats)) {
+        Ok(0) => {}
+        Ok(queued) => info!("Ingest: queued {} webhook deliveries", queued),
+        Err(e) => error!("Ingest: failed to queue webhook deliveries: {}", e),
+    }
+
+    // Stubs only help system search, so failing to write them isn't fatal
+    if spotlight_enabled(&settings) && stats.processed_files > 0 {
+        let ids: Vec<String> = stats.added_ids.iter().chain(&stats.updated_ids).cloned().collect();
+        let written = spotlight::spotlight_dir().and_then(|dir| {
+            db.with_connection(|conn| spotlight::write_spotlight_stubs(conn, &dir, Some(&ids)))
+        });
+        if let Err(e) = written {
+            warn!("Ingest: failed to write Spotlight stubs: {}", e);
+        }
+    }
+
+    // Fold the index segments this ingest wrote; a full optimize is left to
+    // the maintain_search_index command
+    if stats.processed_files > 0 {
+        if let Err(e) = db.with_connection(|conn| merge_search_indexes(conn, INGEST_MERGE_PAGES)) {
+            warn!("Ingest: failed to merge search index segments: {}", e);
+        }
+    }
+    Ok(stats)
+}
+
+/// Rebuilds the previews (and their search index entries) of all
+/// conversations from their session files, e.g. after the preview settings
+/// changed.
+///
+/// Conversations whose session file is missing or unreadable keep their
+/// preview. Returns the number of previews that changed.
+pub fn regenerate_previews(db: &Database) -> DbResult<usize> {
+    let (preview_options, files) = db.with_connection(|conn| {
+        let preview_options = PreviewOptions::from_settings(&load_settings(conn)?);
+        let mut stmt = conn.prepare_cached("SELECT DISTINCT file_path FROM conversations")?;
+        let files = stmt
+            .query_map([], |row| row.get::<_, String>(0))?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok((preview_options, files))
+    })?;
+
+    // Parse outside the database lock
+    let mut previews = Vec::new();
+    for file in &files {
+        let path = Path::new(file);
+        if !path.is_file() {
+            continue;
+        }
+        match parse_conversation_file(path) {
+            Ok(conversations) => previews.extend(conversations.into_iter().map(|conv| {
+                let previews = generate_previews(&conv.messages, preview_options);
+                (conv, previews)
+            })),
+            Err(e) => warn!("Error parsing {:?} for previews: {}", path, e),
+        }
+    }
+
+    let changed = db.with_connection_mut(|conn| {
+        let tx = conn.transaction()?;
+        let mut changed = 0;
+        for (conv, previews) in &previews {
+            // A conflicting copy of the session in another file keeps its
+            // own preview
+            let id = stored_conversation_id(&tx, conv)?;
+            let updated = tx
+                .prepare_cached(
+                    "UPDATE conversations SET preview = ?2, preview_medium = ?3
+                     WHERE id = ?1 AND file_path = ?4
+                       AND (preview IS NOT ?2 OR preview_medium IS NOT ?3)",
+                )?
+                .execute(rusqlite::params![
+                    id,
+                    previews.short,
+                    previews.medium,
+                    conv.file_path.to_string_lossy()
+                ])?;
+            if updated > 0 {
+                index_conversation_content(&tx, &id, &previews.short, &conv.project_name)?;
+                changed += 1;
+            }
+        }
+        tx.commit()?;
+        Ok(changed)
+    })?;
+
+    info!(
+        "Regenerated previews: {} of {} conversations changed",
+        changed,
+        previews.len()
+    );
+    Ok(changed)
+}
+
+/// Parses a batch of files and writes them in a single transaction.
+/// Conversations of files larger than `partial_storage_bytes` are flagged
+/// for partial storage.
+///
+/// Adds to `stats` only once the batch is committed.
+fn process_file_batch(
+    db: &Database,
+    batch: &[ModifiedFile],
+    preview_options: PreviewOptions,
+    conflict_policy: SessionConflictPolicy,
+    partial_storage_bytes: Option<u64>,
+    stats: &mut IngestStats,
+) -> DbResult<()> {
+    let _timer = metrics::start_timer("ingest.batch");
+    let paths: Vec<PathBuf> = batch.iter().map(|f| f.file_path.clone()).collect();
+    db.with_connection(|conn| set_ingest_status(conn, &paths, IngestStatus::Processing))?;
+
+    // Parse outside the database lock
+    let mut parsed = Vec::with_capacity(batch.len());
+    let mut failed = Vec::new();
+    for modified_file in batch {
+        match parse_conversation_file_observed(&modified_file.file_path) {
+            Ok((conversations, observations)) => {
+                parsed.push((modified_file, conversations, observations))
+            }
+            Err(e) => {
+                metrics::increment("ingest.file_errors");
+                error!("Error processing file {:?}: {}", modified_file.file_path, e);
+                failed.push(modified_file.file_path.clone());
+            }
+        }
+    }
+
+    let written = db.with_connection_mut(|conn| {
+        let tx = conn.transaction()?;
+        let mut written = Vec::with_capacity(parsed.len());
+        for (modified_file, conversations, observations) in &parsed {
+            record_observations(&tx, observations, &modified_file.file_path)?;
+            let partial_storage = partial_storage_bytes.is_some_and(|max| {
+                fs::metadata(&modified_file.file_path).is_ok_and(|m| m.len() > max)
+            });
+            if partial_storage {
+                info!("Ingest: storing {:?} partially, it's large", modified_file.file_path);
+            }
+            written.push(write_file_conversations(
+                &tx,
+                conversations,
+                &modified_file.file_path,
+                &modified_file.current_modified_at,
+                preview_options,
+                conflict_policy,
+                partial_storage,
+            )?);
+        }
+        // Retry failed files on the next scan
+        set_ingest_status(&tx, &failed, IngestStatus::Pending)?;
+        tx.commit()?;
+        Ok(written)
+    })?;
+
+    let mut batch_conversations = 0;
+    for file in written {
+        batch_conversations += file.added.len() + file.updated.len() + file.unchanged;
+        stats.conflicts += file.conflicts;
+        stats.new_conversations += file.added.len();
+        stats.added_ids.extend(file.added);
+        stats.updated_conversations += file.updated.len();
+        stats.updated_ids.extend(file.updated);
+    }
+    stats.processed_files += parsed.len();
+    stats.failed_files += failed.len();
+    metrics::registry().add("ingest.conversations", batch_conversations as u64);
+    debug!(
+        "Processed batch of {} files ({} conversations)",
+        batch.len(),
+        batch_conversations
+    );
+    Ok(())
+}
+
+/// Conversations written for one file.
+struct WrittenFile {
+    /// IDs of conversations that weren't stored before.
+    added: Vec<String>,
+    /// IDs of stored conversations whose messages changed.
+    updated: Vec<String>,
+    /// Stored conversations rewritten with the same messages.
+    unchanged: usize,
+    /// Sessions also found in another file.
+    conflicts: usize,
+}
+
+/// Writes a file's parsed conversations (summary, resolution, usage, search
+/// indexes) and marks the file ingested, within the caller's transaction.
+///
+/// A session also found in another file is written as `conflict_policy`
+/// says (see [`place_conversation`]).
+///
+/// Whether a conversation was added or updated is decided from the stored
+/// row, not from the file being new: migrations clear file metadata to
+/// force a re-ingest, which mustn't report every conversation as new. A
+/// stored conversation whose content hash didn't change isn't reported.
+fn write_file_conversations(
+    tx: &Connection,
+    conversations: &[ParsedConversation],
+    file_path: &Path,
+    modified_at: &str,
+    preview_options: PreviewOptions,
+    conflict_policy: SessionConflictPolicy,
+    partial_storage: bool,
+) -> DbResult<WrittenFile> {
+    let mut written = WrittenFile {
+        added: Vec::new(),
+        updated: Vec::new(),
+        unchanged: 0,
+        conflicts: 0,
+    };
+    for conv in conversations {
+        let (placement, conflict) = place_conversation(tx, conv, conflict_policy)?;
+        if conflict {
+            written.conflicts += 1;
+        }
+        let id = match placement {
+            Placement::Write(id) => id,
+            Placement::Skip => {
+                debug!("Skipping older copy of conversation {}", conv.id);
+                continue;
+            }
+        };
+        if is_deleted(tx, &id)? {
+            debug!("Skipping deleted conversation {}", id);
+            continue;
+        }
+        let previews = generate_previews(&conv.messages, preview_options);
+        let hash = content_hash(&conv.messages);
+        let stored_hash: Option<String> = tx
+            .prepare_cached("SELECT content_hash FROM conversations WHERE id = ?1")?
+            .query_row([&id], |row| row.get(0))
+            .optional()?;
+
+        // Insert or update conversation
+        tx.prepare_cached(
+            r#"
+            INSERT INTO conversations (
+                id, project_path, project_name, start_time, last_time,
+                preview, preview_medium, message_count, total_input_tokens,
+                total_output_tokens, file_path, file_modified_at, session_id, resolution,
+                partial_storage, content_hash, is_subagent, cwd
+            )
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18)
+            ON CONFLICT(id) DO UPDATE SET
+                project_path = excluded.project_path,
+                project_name = excluded.project_name,
+                start_time = excluded.start_time,
+                last_time = excluded.last_time,
+                preview = excluded.preview,
+                preview_medium = excluded.preview_medium,
+                message_count = excluded.message_count,
+                total_input_tokens = excluded.total_input_tokens,
+                total_output_tokens = excluded.total_output_tokens,
+                file_path = excluded.file_path,
+                file_modified_at = excluded.file_modified_at,
+                session_id = excluded.session_id,
+                resolution = excluded.resolution,
+                partial_storage = excluded.partial_storage,
+                content_hash = excluded.content_hash,
+                is_subagent = excluded.is_subagent,
+                cwd = excluded.cwd
+            "#,
+        )?
+        .execute(rusqlite::params![
+            id,
+            conv.project_path,
+            conv.project_name,
+            conv.start_time,
+            conv.last_time,
+            previews.short,
+            previews.medium,
+            conv.messages.iter().filter(|message| !message.is_meta).count(),
+            conv.total_input_tokens,
+            conv.total_output_tokens,
+            conv.file_path.to_string_lossy(),
+            modified_at,
+            conv.session_id,
+            detect_resolution(&conv.messages).as_str(),
+            partial_storage,
+            hash,
+            conv.is_subagent,
+            conv.cwd.as_deref().unwrap_or_default(),
+        ])?;
+
+        record_usage(tx, &id, &summarize_usage(conv))?;
+        replace_detected_issues(tx, &id, &detect_issue_keys(&conv.messages))?;
+        index_questions(tx, &id, &conv.messages)?;
+        let message_uuids: Vec<&str> =
+            conv.messages.iter().filter_map(|m| m.uuid.as_deref()).collect();
+        replace_continuations(tx, &id, &message_uuids, &conv.continues_from)?;
+        clear_detected_parent(tx, &id)?;
+
+        // Update search index (best-effort: log warning if fails but continue)
+        if let Err(e) = index_conversation_content(tx, &id, &previews.short, &conv.project_name) {
+            warn!("Error indexing conversation {}: {}", id, e);
+        }
+        match stored_hash {
+            None => written.added.push(id),
+            Some(stored) if stored != hash => written.updated.push(id),
+            Some(_) => written.unchanged += 1,
+        }
+    }
+
+    // Update file metadata
+    update_file_metadata(tx, file_path, modified_at)?;
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::metadata::get_all_file_metadata;
+    use crate::demo::write_fixture_files;
+    use std::cell::RefCell;
+    use tempfile::tempdir;
+
+    fn setup_db() -> Database {
+        let db = Database::open_in_memory().unwrap();
+        db.init_schema().unwrap();
+        db
+    }
+
+    /// Appends a copy of a session file's last message under a new UUID.
+    fn append_message(path: &Path) {
+        let mut contents = fs::read_to_string(path).unwrap();
+        let last_line = contents.lines().last().unwrap();
+        let mut message: serde_json::Value = serde_json::from_str(last_line).unwrap();
+        message["uuid"] = serde_json::json!(format!("appended-{}", contents.len()));
+        contents.push_str(&format!("{}\n", message));
+        fs::write(path, contents).unwrap();
+    }
+
+    #[test]
+    fn test_ingest_files() {
+        let dir = tempdir().unwrap();
+        let db = setup_db();
+        let paths = write_fixture_files(dir.path()).unwrap();
+
+        let stats = ingest_files(&db, &paths, &NoProgress).unwrap();
+        assert_eq!(stats.processed_files, 6);
+        assert_eq!(stats.failed_files, 0);
+        assert_eq!(stats.new_conversations, 6);
+        assert_eq!(stats.updated_conversations, 0);
+        assert_eq!(stats.added_ids.len(), 6);
+        assert!(stats.updated_ids.is_empty());
+
+        let metadata = db.with_connection(get_all_file_metadata).unwrap();
+        assert!(metadata
+            .values()
+            .all(|entry| entry.status == IngestStatus::Done));
+
+        // Unchanged files are skipped
+        let stats = ingest_files(&db, &paths, &NoProgress).unwrap();
+        assert_eq!(stats, IngestStats::default());
+    }
+
+    #[test]
+    fn test_failed_files_stay_pending() {
+        let dir = tempdir().unwrap();
+        let db = setup_db();
+
+        let mut paths = write_fixture_files(dir.path()).unwrap();
+        let missing = dir.path().join("missing.jsonl");
+        paths.push(missing.clone());
+        db.with_connection(|conn| set_ingest_status(conn, &paths, IngestStatus::Pending))
+            .unwrap();
+
+        // The file disappeared after it was scheduled
+        let batch: Vec<ModifiedFile> = paths
+            .iter()
+            .map(|path| ModifiedFile {
+                file_path: path.clone(),
+                current_modified_at: "2025-01-10T18:00:00Z".to_string(),
+                is_new: true,
+            })
+            .collect();
+        let mut stats = IngestStats::default();
+        process_file_batch(
+            &db,
+            &batch,
+            PreviewOptions::default(),
+            SessionConflictPolicy::default(),
+            None,
+            &mut stats,
+        )
+        .unwrap();
+        assert_eq!((stats.processed_files, stats.failed_files), (6, 1));
+
+        let metadata = db.with_connection(get_all_file_metadata).unwrap();
+        assert_eq!(metadata.len(), 7);
+        for (path, entry) in &metadata {
+            if *path == crate::parser::path_key(&missing) {
+                assert_eq!(entry.status, IngestStatus::Pending);
+                assert!(entry.modified_at.is_empty());
+            } else {
+                assert_eq!(entry.status, IngestStatus::Done);
+            }
+        }
+    }
+
+    #[test]
+    fn test_large_files_are_stored_partially() {
+        let dir = tempdir().unwrap();
+        let db = setup_db();
+        let paths = write_fixture_files(dir.path()).unwrap();
+        let batch: Vec<ModifiedFile> = paths
+            .iter()
+            .map(|path| ModifiedFile {
+                file_path: path.clone(),
+                current_modified_at: "2025-01-10T18:00:00Z".to_string(),
+                is_new: true,
+            })
+            .collect();
+        let large = fs::metadata(&paths[0]).unwrap().len() - 1;
+        let mut stats = IngestStats::default();
+        process_file_batch(
+            &db,
+            &batch[..1],
+            PreviewOptions::default(),
+            SessionConflictPolicy::default(),
+            Some(large),
+            &mut stats,
+        )
+        .unwrap();
+        process_file_batch(
+            &db,
+            &batch[1..],
+            PreviewOptions::default(),
+            SessionConflictPolicy::default(),
+            Some(u64::MAX),
+            &mut stats,
+        )
+        .unwrap();
+
+        let flagged: Vec<String> = db
+            .with_connection(|conn| {
+                let mut stmt =
+                    conn.prepare("SELECT file_path FROM conversations WHERE partial_storage = 1")?;
+                let rows = stmt.query_map([], |row| row.get(0))?;
+                Ok(rows.collect::<Result<_, _>>()?)
+            })
+            .unwrap();
+        assert!(!flagged.is_empty());
+        assert!(flagged.iter().all(|path| *path == paths[0].to_string_lossy()));
+    }
+
+    #[test]
+    fn test_ingest_files_resumes_interrupted_batch() {
+        let dir = tempdir().unwrap();
+        let db = setup_db();
+        let paths = write_fixture_files(dir.path()).unwrap();
+        ingest_files(&db, &paths, &NoProgress).unwrap();
+
+        // Simulate a crash after the first two files were scheduled
+        db.with_connection(|conn| set_ingest_status(conn, &paths[..2], IngestStatus::Processing))
+            .unwrap();
+
+        let stats = ingest_files(&db, &paths, &NoProgress).unwrap();
+        assert_eq!(stats.processed_files, 2);
+        // Rewritten with the same messages: nothing to report
+        assert_eq!(stats.updated_conversations, 0);
+        assert!(stats.added_ids.is_empty() && stats.updated_ids.is_empty());
+    }
+
+    #[test]
+    fn test_reingest_reports_only_changed_conversations() {
+        let dir = tempdir().unwrap();
+        let db = setup_db();
+        let paths = write_fixture_files(dir.path()).unwrap();
+        ingest_files(&db, &paths, &NoProgress).unwrap();
+
+        // A migration forcing a re-ingest doesn't make anything "added"
+        db.with_connection(|conn| {
+            conn.execute("DELETE FROM file_metadata", [])?;
+            Ok(())
+        })
+        .unwrap();
+        append_message(&paths[0]);
+
+        let stats = ingest_files(&db, &paths, &NoProgress).unwrap();
+        assert_eq!(stats.processed_files, 6);
+        assert!(stats.added_ids.is_empty());
+        assert_eq!(stats.updated_ids.len(), 1);
+        assert_eq!(stats.new_conversations, 0);
+        assert_eq!(stats.updated_conversations, 1);
+    }
+
+    #[test]
+    fn test_ingest_queues_webhook_deliveries() {
+        let dir = tempdir().unwrap();
+        let db = setup_db();
+        let paths = write_fixture_files(dir.path()).unwrap();
+        db.with_connection(|conn| {
+            crate::webhooks::save_webhook(
+                conn,
+                &crate::models::WebhookInput {
+                    id: None,
+                    url: "https://hooks.example.com/in".to_string(),
+                    filters: Default::default(),
+                    events: vec![crate::models::WebhookEvent::Updated],
+                    enabled: true,
+                },
+            )
+        })
+        .unwrap();
+
+        let deliveries = || {
+            db.with_connection(|conn| crate::webhooks::list_deliveries(conn, None, 100))
+                .unwrap()
+                .len()
+        };
+        ingest_files(&db, &paths, &NoProgress).unwrap();
+        assert_eq!(deliveries(), 0);
+
+        db.with_connection(|conn| set_ingest_status(conn, &paths[..2], IngestStatus::Pending))
+            .unwrap();
+        ingest_files(&db, &paths, &NoProgress).unwrap();
+        assert_eq!(deliveries(), 0, "unchanged conversations aren't delivered");
+
+        append_message(&paths[0]);
+        append_message(&paths[1]);
+        ingest_files(&db, &paths, &NoProgress).unwrap();
+        assert_eq!(deliveries(), 2);
+    }
+
+    #[test]
+    fn test_ingest_records_resolution() {
+        let dir = tempdir().unwrap();
+        let db = setup_db();
+        let path = dir.path().join("session-r.jsonl");
+        std::fs::write(
+            &path,
+            concat!(
+                r#"{"type":"user","message":{"role":"user","content":"The build fails"},"timestamp":"2025-01-15T10:00:00Z","uuid":"u-1","sessionId":"session-r"}"#,
+                "\n",
+                r#"{"type":"assistant","message":{"role":"assistant","content":"Pin the version"},"timestamp":"2025-01-15T10:00:05Z","uuid":"a-1","sessionId":"session-r"}"#,
+                "\n",
+                r#"{"type":"user","message":{"role":"user","content":"That fixed it, thanks"},"timestamp":"2025-01-15T10:01:00Z","uuid":"u-2","sessionId":"session-r"}"#,
+                "\n",
+            ),
+        )
+        .unwrap();
+
+        ingest_files(&db, &[path], &NoProgress).unwrap();
+        let resolution: String = db
+            .with_connection(|conn| {
+                Ok(conn.query_row("SELECT resolution FROM conversations", [], |row| row.get(0))?)
+            })
+            .unwrap();
+        assert_eq!(resolution, "resolved");
+    }
+
+    #[test]
+    fn test_deleted_conversations_are_not_reimported() {
+        let dir = tempdir().unwrap();
+        let db = setup_db();
+        let paths = write_fixture_files(dir.path()).unwrap();
+        ingest_files(&db, &paths, &NoProgress).unwrap();
+
+        let count = || {
+            db.with_connection(|conn| {
+                Ok(conn.query_row("SELECT COUNT(*) FROM conversations", [], |row| {
+                    row.get::<_, i64>(0)
+                })?)
+            })
+            .unwrap()
+        };
+        db.with_connection(|conn| {
+            let id: String =
+                conn.query_row("SELECT id FROM conversations LIMIT 1", [], |row| row.get(0))?;
+            crate::db::retention::delete_conversations(conn, &[id])
+        })
+        .unwrap();
+        assert_eq!(count(), 5);
+
+        // Force every file to be parsed again
+        db.with_connection(|conn| set_ingest_status(conn, &paths, IngestStatus::Pending))
+            .unwrap();
+        ingest_files(&db, &paths, &NoProgress).unwrap();
+        assert_eq!(count(), 5);
+    }
+
+    #[test]
+    fn test_ingest_files_reports_progress() {
+        let dir = tempdir().unwrap();
+        let db = setup_db();
+        let paths = write_fixture_files(dir.path()).unwrap();
+
+        let reports = RefCell::new(Vec::new());
+        let sink = |progress: &IngestProgress| reports.borrow_mut().push(progress.clone());
+        ingest_files(&db, &paths, &sink).unwrap();
+
+        let reports = reports.into_inner();
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].total_files, 6);
+        assert_eq!(reports[0].stats.processed_files, 6);
+    }
+
+    #[test]
+    fn test_regenerate_previews() {
+        let dir = tempdir().unwrap();
+        let db = setup_db();
+        let paths = write_fixture_files(dir.path()).unwrap();
+        ingest_files(&db, &paths, &NoProgress).unwrap();
+        let previews = |db: &Database| -> Vec<String> {
+            db.with_connection(|conn| {
+                let mut stmt = conn.prepare("SELECT preview FROM conversations ORDER BY id")?;
+                let previews = stmt
+                    .query_map([], |row| row.get(0))?
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok(previews)
+            })
+            .unwrap()
+        };
+        let before = previews(&db);
+
+        // Unchanged settings leave previews alone
+        assert_eq!(regenerate_previews(&db).unwrap(), 0);
+
+        db.with_connection(|conn| {
+            crate::db::settings::set_setting(
+                conn,
+                crate::db::settings::PREVIEW_SOURCE_KEY,
+                crate::models::PreviewSource::SubstantivePair.as_str(),
+            )
+        })
+        .unwrap();
+        assert!(regenerate_previews(&db).unwrap() > 0);
+        let after = previews(&db);
+        assert!(after.iter().any(|preview| preview.contains(" → ")));
+        assert_ne!(before, after);
+    }
+}
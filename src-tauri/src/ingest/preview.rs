@@ -0,0 +1,417 @@
+//! Conversation preview generation.
+//!
+//! Two previews are stored per conversation: a single-line one for the
+//! conversation list, whose length is configurable, and a longer one that
+//! keeps line breaks, for tooltips. By default they're built from the first
+//! user message; with [`PreviewSource::SubstantivePair`] short follow-ups
+//! such as "continue" are skipped and the assistant's answer is added.
+//!
+//! Truncation counts characters, never bytes, so multibyte text is cut on
+//! character boundaries.
+
+use crate::models::{AppSettings, PreviewSource};
+use crate::parser::jsonl::{RawContent, RawMessage, RawMessageType};
+
+/// Shortest configurable list preview, in characters.
+pub const MIN_PREVIEW_LENGTH: u32 = 40;
+
+/// Longest configurable list preview, in characters.
+pub const MAX_PREVIEW_LENGTH: u32 = 500;
+
+/// Length of tooltip previews, in characters.
+const MEDIUM_PREVIEW_CHARS: usize = 1000;
+
+/// User messages shorter than this (in characters) aren't substantive.
+const MIN_SUBSTANTIVE_CHARS: usize = 8;
+
+/// Follow-up prompts that carry no topic of their own.
+const FILLER_PROMPTS: &[&str] = &[
+    "continue",
+    "please continue",
+    "go on",
+    "keep going",
+    "go ahead",
+    "proceed",
+    "resume",
+    "yes",
+    "yes please",
+    "ok",
+    "okay",
+    "sure",
+    "thanks",
+    "thank you",
+];
+
+/// How previews are built, from [`AppSettings`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PreviewOptions {
+    pub source: PreviewSource,
+    /// Length of the list preview, in characters.
+    pub max_chars: usize,
+}
+
+impl PreviewOptions {
+    pub fn from_settings(settings: &AppSettings) -> Self {
+        Self {
+            source: settings.preview_source,
+            max_chars: settings.preview_length as usize,
+        }
+    }
+}
+
+impl Default for PreviewOptions {
+    fn default() -> Self {
+        Self::from_settings(&AppSettings::default())
+    }
+}
+
+/// The previews stored for a conversation.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Previews {
+    /// Single line, for the conversation list.
+    pub short: String,
+    /// Multi-line, for tooltips.
+    pub medium: String,
+}
+
+/// Generates the list and tooltip previews from conversation messages.
+pub fn generate_previews(messages: &[RawMessage], options: PreviewOptions) -> Previews {
+    let Some((question, answer)) = preview_source_text(messages, options.source) else {
+        return Previews::default();
+    };
+    Previews {
+        short: compose(question, answer, options.max_chars, Layout::SingleLine),
+        medium: compose(question, answer, MEDIUM_PREVIEW_CHARS, Layout::MultiLine),
+    }
+}
+
+/// Picks the text previews are built from: a message and, for pairs, the
+/// answer to it.
+fn preview_source_text(
+    messages: &[RawMessage],
+    source: PreviewSource,
+) -> Option<(&str, Option<&str>)> {
+    if source == PreviewSource::SubstantivePair {
+        if let Some(pair) = substantive_pair(messages) {
+            return Some(pair);
+        }
+    }
+
+    // First user message, falling back to the first message of any type
+    messages
+        .iter()
+        .filter(|msg| matches!(msg.message_type, RawMessageType::User))
+        .chain(messages.first())
+        .find_map(message_text)
+        .map(|text| (text, None))
+}
+
+/// Finds the first substantive user message and the assistant's first
+/// text reply to it.
+fn substantive_pair(messages: &[RawMessage]) -> Option<(&str, Option<&str>)> {
+    let (index, question) = messages.iter().enumerate().find_map(|(i, msg)| {
+        if !matches!(msg.message_type, RawMessageType::User) {
+            return None;
+        }
+        message_text(msg)
+            .filter(|text| is_substantive(text))
+            .map(|text| (i, text))
+    })?;
+
+    let answer = messages[index + 1..]
+        .iter()
+        .take_while(|msg| {
+            // Stop at the next prompt; tool results are user messages without text
+            !matches!(msg.message_type, RawMessageType::User) || message_text(msg).is_none()
+        })
+        .filter(|msg| matches!(msg.message_type, RawMessageType::Assistant))
+        .find_map(message_text);
+
+    Some((question, answer))
+}
+
+/// Returns the text of a message: plain text content or its first text
+/// block. Meta messages have none, so previews skip them.
+fn message_text(msg: &RawMessage) -> Option<&str> {
+    if msg.is_meta {
+        return None;
+    }
+    let text = match &msg.message.content {
+        RawContent::Text(text) => Some(text.as_str()),
+        RawContent::Blocks(blocks) => blocks
+            .iter()
+            .filter(|block| block.block_type == "text")
+            .find_map(|block| block.text.as_deref()),
+    };
+    text.filter(|text| !text.trim().is_empty())
+}
+
+/// Whether a user message states a topic, as opposed to a short follow-up,
+/// a slash command, or injected markup such as `<command-name>`.
+fn is_substantive(text: &str) -> bool {
+    let text = text.trim();
+    if text.starts_with('/') || text.starts_with('<') {
+        return false;
+    }
+    let normalized = text
+        .trim_end_matches(|c: char| c.is_ascii_punctuation())
+        .to_lowercase();
+    normalized.chars().count() >= MIN_SUBSTANTIVE_CHARS
+        && !FILLER_PROMPTS.contains(&normalized.as_str())
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Layout {
+    SingleLine,
+    MultiLine,
+}
+
+impl Layout {
+    /// Separates the user message from the assistant's answer.
+    fn separator(self) -> &'static str {
+        match self {
+            Layout::SingleLine => " → ",
+            Layout::MultiLine => "\n\n→ ",
+        }
+    }
+
+    /// Truncates to `max_chars` characters and lays out the lines.
+    fn format(self, text: &str, max_chars: usize) -> String {
+        let truncated: String = text.chars().take(max_chars).collect();
+        match self {
+            Layout::SingleLine => truncated.split_whitespace().collect::<Vec<_>>().join(" "),
+            Layout::MultiLine => {
+                // Keep paragraphs but drop trailing spaces and runs of blank lines
+                let mut lines: Vec<&str> = Vec::new();
+                for line in truncated.lines().map(str::trim_end) {
+                    if !line.is_empty() || lines.last().is_some_and(|last| !last.is_empty()) {
+                        lines.push(line);
+                    }
+                }
+                lines.join("\n").trim().to_string()
+            }
+        }
+    }
+}
+
+/// Joins a message and an optional answer within `max_chars` characters.
+fn compose(question: &str, answer: Option<&str>, max_chars: usize, layout: Layout) -> String {
+    let question = layout.format(question, max_chars);
+    let Some(answer) = answer else {
+        return question;
+    };
+
+    let separator = layout.separator();
+    let remaining = max_chars.saturating_sub(question.chars().count() + separator.chars().count());
+    if remaining == 0 {
+        return question;
+    }
+    format!(
+        "{}{}{}",
+        question,
+        separator,
+        layout.format(answer, remaining)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::jsonl::RawContentBlock;
+
+    const DEFAULT_LENGTH: usize = 200;
+
+    fn user(text: &str) -> RawMessage {
+        RawMessage::new(RawMessageType::User, RawContent::Text(text.to_string()))
+    }
+
+    fn assistant(text: &str) -> RawMessage {
+        RawMessage::new(
+            RawMessageType::Assistant,
+            RawContent::Text(text.to_string()),
+        )
+    }
+
+    /// The list preview with the default length.
+    fn list_preview(messages: &[RawMessage], source: PreviewSource) -> String {
+        let options = PreviewOptions {
+            source,
+            ..Default::default()
+        };
+        generate_previews(messages, options).short
+    }
+
+    fn tool_result() -> RawMessage {
+        RawMessage::new(
+            RawMessageType::User,
+            RawContent::Blocks(vec![RawContentBlock {
+                block_type: "tool_result".to_string(),
+                text: None,
+                name: None,
+                input: None,
+                tool_use_id: Some("toolu_1".to_string()),
+                content: None,
+                is_error: None,
+            }]),
+        )
+    }
+
+    #[test]
+    fn test_generate_preview_first_user_message() {
+        let messages = vec![user("Hello, how can I help you today?")];
+        let preview = list_preview(&messages, PreviewSource::FirstUserMessage);
+        assert_eq!(preview, "Hello, how can I help you today?");
+    }
+
+    #[test]
+    fn test_generate_preview_truncates_long_text() {
+        let messages = vec![user(&"a".repeat(300))];
+        let preview = list_preview(&messages, PreviewSource::FirstUserMessage);
+        assert_eq!(preview.len(), 200);
+    }
+
+    #[test]
+    fn test_generate_preview_removes_newlines() {
+        let messages = vec![user("Line 1\nLine 2\nLine 3")];
+        let preview = list_preview(&messages, PreviewSource::FirstUserMessage);
+        assert!(!preview.contains('\n'));
+        assert_eq!(preview, "Line 1 Line 2 Line 3");
+    }
+
+    #[test]
+    fn test_generate_preview_skips_meta_messages() {
+        let mut caveat = user("Caveat: The messages below were generated by the user");
+        caveat.is_meta = true;
+        let messages = vec![caveat, user("Explain the retry logic")];
+        let preview = list_preview(&messages, PreviewSource::FirstUserMessage);
+        assert_eq!(preview, "Explain the retry logic");
+    }
+
+    #[test]
+    fn test_generate_preview_empty_messages() {
+        assert!(list_preview(&[], PreviewSource::FirstUserMessage).is_empty());
+        assert!(list_preview(&[], PreviewSource::SubstantivePair).is_empty());
+    }
+
+    #[test]
+    fn test_substantive_pair_skips_filler() {
+        let messages = vec![
+            user("continue"),
+            assistant("Continuing."),
+            user("Why does the build fail on Windows?"),
+            assistant("The linker can't find\nthe OpenSSL libraries."),
+        ];
+
+        assert_eq!(
+            list_preview(&messages, PreviewSource::FirstUserMessage),
+            "continue"
+        );
+        assert_eq!(
+            list_preview(&messages, PreviewSource::SubstantivePair),
+            "Why does the build fail on Windows? → The linker can't find the OpenSSL libraries."
+        );
+    }
+
+    #[test]
+    fn test_substantive_pair_answer_after_tool_calls() {
+        let messages = vec![
+            user("Fix the failing checkout test"),
+            RawMessage::new(RawMessageType::Assistant, RawContent::Blocks(Vec::new())),
+            tool_result(),
+            assistant("Fixed the off-by-one in the cart total."),
+        ];
+        assert_eq!(
+            list_preview(&messages, PreviewSource::SubstantivePair),
+            "Fix the failing checkout test → Fixed the off-by-one in the cart total."
+        );
+    }
+
+    #[test]
+    fn test_substantive_pair_without_answer_or_topic() {
+        // No reply before the next prompt: the question alone
+        let messages = vec![
+            user("Refactor the config loader"),
+            user("Also add tests"),
+            assistant("Done."),
+        ];
+        assert_eq!(
+            list_preview(&messages, PreviewSource::SubstantivePair),
+            "Refactor the config loader"
+        );
+
+        // Nothing substantive: same as the default
+        let messages = vec![user("/clear"), user("ok!"), assistant("Hi")];
+        assert_eq!(
+            list_preview(&messages, PreviewSource::SubstantivePair),
+            "/clear"
+        );
+    }
+
+    #[test]
+    fn test_substantive_pair_fits_preview_length() {
+        let messages = vec![user(&"q".repeat(150)), assistant(&"a".repeat(150))];
+        let preview = list_preview(&messages, PreviewSource::SubstantivePair);
+        assert_eq!(preview.chars().count(), DEFAULT_LENGTH);
+        assert!(preview.starts_with(&"q".repeat(150)));
+
+        let messages = vec![user(&"q".repeat(300)), assistant("answer")];
+        let preview = list_preview(&messages, PreviewSource::SubstantivePair);
+        assert_eq!(preview, "q".repeat(DEFAULT_LENGTH));
+    }
+
+    #[test]
+    fn test_preview_length_is_configurable() {
+        let messages = vec![user(&"word ".repeat(100))];
+        let previews = generate_previews(
+            &messages,
+            PreviewOptions {
+                source: PreviewSource::FirstUserMessage,
+                max_chars: 40,
+            },
+        );
+        assert!(previews.short.chars().count() <= 40);
+        assert!(previews.medium.chars().count() > 40);
+    }
+
+    #[test]
+    fn test_medium_preview_keeps_paragraphs() {
+        let messages = vec![
+            user("Why does the build fail?\n\n\n\nIt worked yesterday.   \n"),
+            assistant("Two causes:\n- the lockfile\n- the cache"),
+        ];
+        let previews = generate_previews(
+            &messages,
+            PreviewOptions {
+                source: PreviewSource::SubstantivePair,
+                max_chars: DEFAULT_LENGTH,
+            },
+        );
+        assert_eq!(
+            previews.medium,
+            "Why does the build fail?\n\nIt worked yesterday.\n\n→ Two causes:\n- the lockfile\n- the cache"
+        );
+        assert_eq!(
+            previews.short,
+            "Why does the build fail? It worked yesterday. → Two causes: - the lockfile - the cache"
+        );
+    }
+
+    #[test]
+    fn test_truncation_respects_char_boundaries() {
+        let text = "日本語のテキスト🚀".repeat(50);
+        let messages = vec![user(&text)];
+        let previews = generate_previews(
+            &messages,
+            PreviewOptions {
+                source: PreviewSource::FirstUserMessage,
+                max_chars: 45,
+            },
+        );
+        assert_eq!(previews.short.chars().count(), 45);
+        assert!(text.starts_with(&previews.short));
+        assert_eq!(
+            previews.medium.chars().count(),
+            MEDIUM_PREVIEW_CHARS.min(450)
+        );
+    }
+}
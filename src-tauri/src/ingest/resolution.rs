@@ -0,0 +1,126 @@
+//! Whether a conversation solved the user's problem.
+//!
+//! Judged by simple phrase rules on the user's last few messages: thanks or
+//! "it works" mark a conversation resolved, "that's wrong" or "still
+//! failing" unresolved. The latest message with either kind of phrase
+//! decides; a message with both counts as unresolved, as in "thanks, but
+//! it still fails".
+
+use crate::models::Resolution;
+use crate::parser::jsonl::RawMessage;
+use crate::search::extract_questions;
+use regex::Regex;
+use std::sync::LazyLock;
+
+/// Number of user messages, from the end, searched for signals.
+const SIGNAL_WINDOW: usize = 3;
+
+/// Phrases saying the problem persists or the answer was wrong.
+static UNRESOLVED_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?i)\b(still (fail|broken|not|does ?n[o']?t|get+ing|the same|err)|(that|this|it)('?s| is) (wrong|incorrect|not right)|(does ?n[o']?t|did ?n[o']?t|is ?n[o']?t|not) work|same (error|issue|problem)|no luck|not fixed)",
+    )
+    .expect("Invalid regex pattern")
+});
+
+/// Phrases saying the problem was solved.
+static RESOLVED_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?i)\b(thanks|thank you|thx|(it|that|this) (works|worked|fixed it|did it)|works (now|great|perfectly)|perfect|lgtm|looks good|problem solved|all good)\b",
+    )
+    .expect("Invalid regex pattern")
+});
+
+/// Classifies a single message's text, if it has a signal.
+pub fn classify_text(text: &str) -> Option<Resolution> {
+    if UNRESOLVED_REGEX.is_match(text) {
+        Some(Resolution::Unresolved)
+    } else if RESOLVED_REGEX.is_match(text) {
+        Some(Resolution::Resolved)
+    } else {
+        None
+    }
+}
+
+/// Judges how a conversation ended from its last user messages.
+pub fn detect_resolution(messages: &[RawMessage]) -> Resolution {
+    extract_questions(messages)
+        .iter()
+        .rev()
+        .take(SIGNAL_WINDOW)
+        .find_map(|question| classify_text(&question.text))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::jsonl::{RawContent, RawMessageType};
+
+    fn message(message_type: RawMessageType, text: &str) -> RawMessage {
+        RawMessage::new(message_type, RawContent::Text(text.to_string()))
+    }
+
+    fn user(text: &str) -> RawMessage {
+        message(RawMessageType::User, text)
+    }
+
+    #[test]
+    fn test_classify_text() {
+        for text in [
+            "Thanks!",
+            "ok it works now",
+            "That fixed it, LGTM",
+            "perfect",
+        ] {
+            assert_eq!(classify_text(text), Some(Resolution::Resolved), "{}", text);
+        }
+        for text in [
+            "That's wrong",
+            "still failing with the same error",
+            "It doesn't work",
+            "Thanks, but the build is still broken",
+            "not working",
+        ] {
+            assert_eq!(
+                classify_text(text),
+                Some(Resolution::Unresolved),
+                "{}",
+                text
+            );
+        }
+        for text in [
+            "Add a test for the parser",
+            "Is this imperfect?",
+            "Thanksgiving plans",
+        ] {
+            assert_eq!(classify_text(text), None, "{}", text);
+        }
+    }
+
+    #[test]
+    fn test_detect_resolution_uses_latest_signal() {
+        let messages = vec![
+            user("The login test fails"),
+            message(
+                RawMessageType::Assistant,
+                "Thanks for the report, it works now",
+            ),
+            user("Still failing"),
+            user("ok, that fixed it"),
+            user("Now commit it"),
+        ];
+        assert_eq!(detect_resolution(&messages), Resolution::Resolved);
+
+        let messages = vec![
+            user("it works, thanks"),
+            user("one more: it's wrong on Windows"),
+        ];
+        assert_eq!(detect_resolution(&messages), Resolution::Unresolved);
+
+        // Signals before the last few user messages are stale
+        let mut messages = vec![user("thanks")];
+        messages.extend((0..SIGNAL_WINDOW).map(|_| user("next step")));
+        assert_eq!(detect_resolution(&messages), Resolution::Unknown);
+    }
+}
@@ -0,0 +1,204 @@
+//! Spotlight stubs on disk.
+//!
+//! With the `spotlight_stubs` setting on macOS, each ingest writes the
+//! stubs (see [`crate::export::spotlight`]) of the conversations it added
+//! or updated into the Spotlight folder in app data, one folder per
+//! conversation ID, and removes those of conversations that are gone.
+//! Each stub's modification time is its conversation's last message, so
+//! Spotlight sorts and filters them by when the conversation happened.
+
+use crate::db::settings::load_settings;
+use crate::db::sqlite::{get_app_data_dir, DbResult};
+use crate::export::spotlight::{render_stub, stub_file_name, StubConversation};
+use crate::locale::DateFormatter;
+use crate::models::AppSettings;
+use crate::parser::epoch_millis;
+use rusqlite::{Connection, OptionalExtension, Row};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use tracing::{debug, info};
+
+/// Name of the folder in app data the stubs are written to.
+pub const SPOTLIGHT_DIR_NAME: &str = "Spotlight";
+
+/// Returns the folder the stubs are written to.
+pub fn spotlight_dir() -> DbResult<PathBuf> {
+    Ok(get_app_data_dir()?.join(SPOTLIGHT_DIR_NAME))
+}
+
+/// Whether stubs are written: the setting is on and the app runs on macOS.
+pub fn spotlight_enabled(settings: &AppSettings) -> bool {
+    settings.spotlight_stubs && cfg!(target_os = "macos")
+}
+
+/// Writes the stubs of all conversations if they're enabled, or removes
+/// them if not, after the setting changed.
+pub fn apply_spotlight_setting(conn: &Connection, settings: &AppSettings) -> DbResult<()> {
+    let dir = spotlight_dir()?;
+    if spotlight_enabled(settings) {
+        let written = write_spotlight_stubs(conn, &dir, None)?;
+        info!("Wrote {} Spotlight stubs to {:?}", written, dir);
+        Ok(())
+    } else {
+        remove_spotlight_stubs(&dir)
+    }
+}
+
+/// Writes the stubs of the conversations `ids` (all conversations if
+/// `None`) into `dir`, replacing their earlier stubs, and removes the stubs
+/// of conversations no longer in the database. Returns the number written.
+pub fn write_spotlight_stubs(
+    conn: &Connection,
+    dir: &Path,
+    ids: Option<&[String]>,
+) -> DbResult<usize> {
+    fs::create_dir_all(dir)?;
+    let dates = DateFormatter::from_setting(&load_settings(conn)?.locale);
+
+    let conversations = load_stub_conversations(conn, ids)?;
+    for conversation in &conversations {
+        let conversation_dir = dir.join(&conversation.id);
+        if conversation_dir.exists() {
+            fs::remove_dir_all(&conversation_dir)?;
+        }
+        fs::create_dir_all(&conversation_dir)?;
+        let path = conversation_dir.join(stub_file_name(conversation));
+        fs::write(&path, render_stub(conversation, &dates))?;
+        if let Some(millis) = epoch_millis(&conversation.last_time) {
+            let modified = SystemTime::UNIX_EPOCH + Duration::from_millis(millis.max(0) as u64);
+            fs::File::options().write(true).open(&path)?.set_modified(modified)?;
+        }
+    }
+
+    let removed = remove_orphaned_stubs(conn, dir)?;
+    debug!(
+        "Wrote {} Spotlight stubs, removed {}",
+        conversations.len(),
+        removed
+    );
+    Ok(conversations.len())
+}
+
+/// Removes the stubs of conversations no longer in the database. Returns
+/// the number removed.
+fn remove_orphaned_stubs(conn: &Connection, dir: &Path) -> DbResult<usize> {
+    let ids: HashSet<String> = {
+        let mut stmt = conn.prepare_cached("SELECT id FROM conversations")?;
+        let ids = stmt.query_map([], |row| row.get(0))?;
+        ids.collect::<Result<_, _>>()?
+    };
+    let mut removed = 0;
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if entry.path().is_dir() && !ids.contains(&name) {
+            fs::remove_dir_all(entry.path())?;
+            removed += 1;
+        }
+    }
+    Ok(removed)
+}
+
+/// Removes all stubs, e.g. once the setting is turned off.
+pub fn remove_spotlight_stubs(dir: &Path) -> DbResult<()> {
+    if dir.exists() {
+        fs::remove_dir_all(dir)?;
+        info!("Removed the Spotlight stubs in {:?}", dir);
+    }
+    Ok(())
+}
+
+/// Stub fields of conversations, with their tags sorted and joined by the
+/// unit separator.
+const STUB_COLUMNS: &str = "SELECT c.id, c.project_name, c.project_path, c.start_time,
+        c.last_time, c.preview, c.preview_medium, c.message_count,
+        (SELECT group_concat(tag, char(31)) FROM
+            (SELECT tag FROM conversation_tags t WHERE t.conversation_id = c.id ORDER BY tag))
+     FROM conversations c";
+
+fn load_stub_conversations(
+    conn: &Connection,
+    ids: Option<&[String]>,
+) -> DbResult<Vec<StubConversation>> {
+    let Some(ids) = ids else {
+        let mut stmt = conn.prepare(STUB_COLUMNS)?;
+        let rows = stmt.query_map([], stub_conversation)?;
+        return Ok(rows.collect::<Result<_, _>>()?);
+    };
+    let mut stmt = conn.prepare(&format!("{} WHERE c.id = ?1", STUB_COLUMNS))?;
+    let mut conversations = Vec::with_capacity(ids.len());
+    for id in ids {
+        conversations.extend(stmt.query_row([id], stub_conversation).optional()?);
+    }
+    Ok(conversations)
+}
+
+fn stub_conversation(row: &Row) -> rusqlite::Result<StubConversation> {
+    let tags: Option<String> = row.get(8)?;
+    Ok(StubConversation {
+        id: row.get(0)?,
+        project_name: row.get(1)?,
+        project_path: row.get(2)?,
+        start_time: row.get(3)?,
+        last_time: row.get(4)?,
+        preview: row.get(5)?,
+        preview_medium: row.get(6)?,
+        message_count: row.get(7)?,
+        tags: tags
+            .map(|tags| tags.split('\u{1f}').map(str::to_string).collect())
+            .unwrap_or_default(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::sqlite::init_db;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_stubs_follow_their_conversations() {
+        let conn = Connection::open_in_memory().unwrap();
+        init_db(&conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO conversations (id, project_path, project_name, start_time, last_time, file_path, file_modified_at, preview)
+             VALUES ('c1', '/p', 'p', '2025-01-15T10:00:00Z', '2025-01-15T10:30:00Z', '/a.jsonl', '', 'Fix the build'),
+                    ('c2', '/p', 'p', '2025-01-16T10:00:00Z', '2025-01-16T10:30:00Z', '/b.jsonl', '', 'Add a test');
+             INSERT INTO conversation_tags (conversation_id, tag, created_at)
+             VALUES ('c1', 'ci', ''), ('c1', 'build', '');",
+        )
+        .unwrap();
+        let dir = tempdir().unwrap();
+
+        assert_eq!(write_spotlight_stubs(&conn, dir.path(), None).unwrap(), 2);
+        let stub = dir.path().join("c1").join("p_2025-01-15.md");
+        let text = fs::read_to_string(&stub).unwrap();
+        assert!(text.starts_with("# Fix the build\n"));
+        assert!(text.contains("- Tags: build, ci\n"));
+        let modified = fs::metadata(&stub).unwrap().modified().unwrap();
+        assert_eq!(
+            modified.duration_since(SystemTime::UNIX_EPOCH).unwrap().as_millis() as i64,
+            epoch_millis("2025-01-15T10:30:00Z").unwrap()
+        );
+
+        // A newer stub replaces the old one, and deleted conversations lose theirs
+        conn.execute_batch(
+            "UPDATE conversations SET last_time = '2025-01-17T09:00:00Z' WHERE id = 'c1';
+             DELETE FROM conversations WHERE id = 'c2';",
+        )
+        .unwrap();
+        let ids = ["c1".to_string()];
+        assert_eq!(write_spotlight_stubs(&conn, dir.path(), Some(&ids)).unwrap(), 1);
+        let files: Vec<String> = fs::read_dir(dir.path().join("c1"))
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(files, vec!["p_2025-01-17.md"]);
+        assert!(!dir.path().join("c2").exists());
+
+        remove_spotlight_stubs(dir.path()).unwrap();
+        assert!(!dir.path().exists());
+    }
+}
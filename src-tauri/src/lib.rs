@@ -1,22 +1,46 @@
 // Backend modules
+pub mod benchmark;
 pub mod commands;
 pub mod db;
+pub mod demo;
+pub mod events;
+pub mod export;
+pub mod ingest;
+pub mod locale;
+pub mod logging;
+pub mod metrics;
 pub mod models;
 pub mod parser;
+pub mod prompts;
+pub mod reminders;
+pub mod replay;
 pub mod search;
+pub mod share;
+pub mod slack;
 pub mod state;
+pub mod tags;
+pub mod transform;
+pub mod usage;
 pub mod watcher;
+pub mod webhooks;
+pub mod workspaces;
 
-use crate::db::metadata::get_modified_files;
-use crate::parser::jsonl::discover_jsonl_files;
+use crate::commands::read_only::ensure_writable;
+use crate::db::sqlite::get_app_data_dir;
+use crate::events::{emit_import_result, emit_watcher_status};
+use crate::ingest::{import_dropped_files, imported_dir, scan_all};
+use crate::models::WatcherState;
+use crate::parser::{install_profile, load_profile, PROFILE_FILE_NAME};
 use crate::state::AppState;
-use crate::watcher::{process_files_and_emit, start_watcher};
+use crate::watcher::{
+    emit_conversations_updated, retry_unreadable_paths, start_watcher, EventProgress,
+};
 use std::sync::Arc;
-use tauri::Manager;
+use tauri::{AppHandle, DragDropEvent, Manager, RunEvent, WindowEvent};
 use tracing::{error, info};
 
 // Re-export command handlers
-pub use commands::{get_all_tags, get_conversation, get_conversations, get_projects, search_conversations, set_tags, toggle_bookmark};
+pub use commands::{attach_child_conversation, begin_search, bulk_archive, bulk_delete, clear_reminder, compare_projects, copy_prompt, create_share_bundle, delete_prompt, delete_webhook, delete_workspace, export_conversation, export_conversation_pages, export_conversations, export_prompts, export_static_site, export_usage_csv, export_with_template, extract_artifacts, generate_demo_data, get_adjacent_conversation, get_all_tags, get_app_metrics, get_attachment, get_audit_log, get_block_copy_text, get_child_conversations, get_conversation, get_conversation_chain, get_conversation_messages, get_conversation_summaries_window, get_conversation_with_highlights, get_conversations, get_conversations_for_issue, get_due_reminders, get_filter_counts, get_health, get_issue_links, get_language_stats, get_message, get_metadata, get_model_stats, get_projects, get_read_only_mode, get_recent_logs, get_schema_observations, get_selection, get_settings, get_synonyms, get_top_terms, get_usage_anomalies, get_webhook_deliveries, has_changed_since, import_path, link_issue, list_actions, list_export_templates, list_prompts, list_share_bundles, list_webhooks, list_workspaces, load_full, load_workspace, maintain_search_index, quick_open, redo, regenerate_previews, render_preview_image, reimport_conversations, replay_conversation, rescan_conversations, revoke_share_bundle, run_action, run_benchmark, save_prompt, save_prompt_from_message, save_webhook, save_workspace, search_conversations, search_questions, search_with_facets, set_metadata, set_protected, set_read_position, set_reminder, set_selection, set_synonyms, share_to_slack, set_tags, stop_replay, suggest_tags, toggle_bookmark, undo_last_operation, update_query, update_settings};
 
 // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
 #[tauri::command]
@@ -26,17 +50,40 @@ fn greet(name: &str) -> String {
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    // Initialize tracing subscriber for logging
-    tracing_subscriber::fmt()
-        .with_env_filter(
-            tracing_subscriber::EnvFilter::from_default_env()
-                .add_directive(tracing::Level::INFO.into()),
-        )
-        .init();
+    // Log to stderr and a rotating file in app data; the guard flushes the
+    // file on exit
+    let _log_guard = logging::init_logging();
+
+    // Hidden flag: serve fixture conversations from an in-memory database
+    // instead of the user's history (for UI tests and screenshots)
+    let demo_mode = std::env::args().skip(1).any(|arg| arg == "--demo");
+
+    // Only read, e.g. to browse someone else's mounted Claude home: commands
+    // that delete or archive history and exports into it are refused
+    let read_only = std::env::args().skip(1).any(|arg| arg == "--read-only");
+
+    // Map session lines with the parsing profile: the built-in one, or a
+    // newer one shipped as a data file in app data
+    match get_app_data_dir() {
+        Ok(dir) => {
+            install_profile(load_profile(&dir.join(PROFILE_FILE_NAME)));
+        }
+        Err(e) => error!("Using the built-in parsing profile: {}", e),
+    }
 
     // Initialize application state (database + cache)
-    let app_state = AppState::new().expect("Failed to initialize application state");
+    let app_state = if demo_mode {
+        info!("Demo mode: using fixture conversations");
+        AppState::new_in_memory_with_fixtures()
+    } else {
+        AppState::new()
+    }
+    .expect("Failed to initialize application state");
     info!("Application state initialized");
+    if read_only {
+        info!("Read-only mode");
+        app_state.db().set_read_only(true);
+    }
 
     // Load initial cache from database
     if let Err(e) = app_state.refresh_conversations_cache() {
@@ -46,6 +93,7 @@ pub fn run() {
     // Wrap in Arc for shared state
     let app_state = Arc::new(app_state);
     let app_state_for_watcher = app_state.clone();
+    let app_state_for_windows = app_state.clone();
 
     // Also provide database directly for compatibility with existing commands
     let db = app_state.db();
@@ -57,7 +105,41 @@ pub fn run() {
         .plugin(tauri_plugin_dialog::init())
         .manage(db)
         .manage(app_state)
-        .invoke_handler(tauri::generate_handler![greet, get_conversations, get_conversation, get_projects, search_conversations, toggle_bookmark, set_tags, get_all_tags])
+        .invoke_handler(tauri::generate_handler![greet, get_conversations, get_conversation, get_projects, search_conversations, toggle_bookmark, set_tags, get_all_tags, get_settings, update_settings, get_message, get_conversation_messages, get_conversation_with_highlights, export_conversation, export_conversation_pages, compare_projects, get_usage_anomalies, export_usage_csv, generate_demo_data, run_benchmark, get_app_metrics, get_recent_logs, rescan_conversations, reimport_conversations, regenerate_previews, quick_open, get_synonyms, set_synonyms, search_with_facets, bulk_archive, bulk_delete, set_protected, undo_last_operation, redo, get_audit_log, begin_search, update_query, get_conversation_summaries_window, get_filter_counts, get_attachment, get_block_copy_text, get_adjacent_conversation, export_conversations, export_static_site, list_webhooks, save_webhook, delete_webhook, get_webhook_deliveries, share_to_slack, link_issue, get_issue_links, get_conversations_for_issue, extract_artifacts, list_prompts, save_prompt, save_prompt_from_message, delete_prompt, copy_prompt, export_prompts, search_questions, maintain_search_index, import_path, get_health, get_schema_observations, list_export_templates, export_with_template, get_top_terms, get_language_stats, get_model_stats, replay_conversation, stop_replay, get_selection, set_selection, load_full, has_changed_since, get_read_only_mode, render_preview_image, create_share_bundle, list_share_bundles, revoke_share_bundle, list_workspaces, save_workspace, load_workspace, delete_workspace, get_metadata, set_metadata, set_reminder, clear_reminder, get_due_reminders, set_read_position, get_conversation_chain, attach_child_conversation, get_child_conversations, suggest_tags, list_actions, run_action])
+        .on_window_event(move |window, event| {
+            // A closed window's searches, list checkpoints, and replays go
+            // with it
+            if let WindowEvent::Destroyed = event {
+                app_state_for_windows.close_window(window.label());
+                return;
+            }
+
+            // Session files dropped on the window are copied into the
+            // imported directory and ingested, off the event loop
+            let WindowEvent::DragDrop(DragDropEvent::Drop { paths, .. }) = event else {
+                return;
+            };
+            if demo_mode {
+                return;
+            }
+            let app_handle = window.app_handle().clone();
+            let app_state = app_state_for_windows.clone();
+            let paths = paths.clone();
+            std::thread::spawn(move || {
+                if ensure_writable(&app_state.db(), "Importing dropped files").is_err() {
+                    return;
+                }
+                let result = imported_dir()
+                    .and_then(|dir| import_dropped_files(&app_state.db(), &paths, &dir));
+                match result {
+                    Ok(result) => {
+                        emit_conversations_updated(&app_handle, &app_state, &result.stats, false);
+                        emit_import_result(&app_handle, &result);
+                    }
+                    Err(e) => error!("Import of dropped files failed: {}", e),
+                }
+            });
+        })
         .setup(move |app| {
             // Open devtools in debug mode
             #[cfg(debug_assertions)]
@@ -67,6 +149,20 @@ pub fn run() {
                 }
             }
 
+            // Demo data is static, so don't watch or scan the real history
+            if demo_mode {
+                return Ok(());
+            }
+
+            // Send webhook deliveries queued by ingests in the background
+            webhooks::start_dispatcher(app_state_for_watcher.db());
+
+            // Delete share bundles as they expire
+            share::start_cleaner(app_state_for_watcher.db());
+
+            // Emit follow-up reminders as they come due
+            reminders::start_scheduler(app.handle().clone(), app_state_for_watcher.db());
+
             // Start file watcher after app is ready
             let app_handle = app.handle().clone();
             match start_watcher(app_handle.clone(), app_state_for_watcher.clone()) {
@@ -80,30 +176,45 @@ pub fn run() {
                     let scan_app_handle = app_handle;
                     let scan_app_state = app_state_for_watcher;
                     std::thread::spawn(move || {
-                        match discover_jsonl_files() {
-                            Ok(all_files) if !all_files.is_empty() => {
-                                info!("Initial scan: found {} JSONL files", all_files.len());
-                                let db = scan_app_state.db();
-                                match db.with_connection(|conn| get_modified_files(conn, &all_files)) {
-                                    Ok(modified) if !modified.is_empty() => {
-                                        info!("Initial scan: {} files need processing", modified.len());
-                                        process_files_and_emit(&modified, &scan_app_handle, &scan_app_state);
-                                    }
-                                    Ok(_) => info!("Initial scan: all files already up to date"),
-                                    Err(e) => error!("Initial scan: failed to check modified files: {}", e),
-                                }
+                        let progress = EventProgress(&scan_app_handle);
+                        let result = scan_all(&scan_app_state.db(), &progress);
+                        if result.is_ok() {
+                            scan_app_state.record_scan();
+                        }
+                        match result {
+                            Ok(stats) if stats.processed_files + stats.failed_files > 0 => {
+                                emit_conversations_updated(&scan_app_handle, &scan_app_state, &stats, false);
                             }
-                            Ok(_) => info!("Initial scan: no JSONL files found in ~/.claude/projects/"),
-                            Err(e) => error!("Initial scan: failed to discover JSONL files: {}", e),
+                            Ok(_) => info!("Initial scan: all files already up to date"),
+                            Err(e) => error!("Initial scan failed: {}", e),
                         }
+
+                        // Paths the scan couldn't read are retried with backoff
+                        retry_unreadable_paths(&scan_app_handle, &scan_app_state);
                     });
                 }
                 Err(e) => {
                     error!("Failed to start file watcher: {}. App will still work but won't detect new conversations.", e);
+                    app_state_for_watcher.set_watcher_state(WatcherState::Failed);
+                    emit_watcher_status(&app_handle, WatcherState::Failed, Some(e.to_string()));
                 }
             }
             Ok(())
         })
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(handle_run_event);
+}
+
+/// Handles events of the running app: on macOS, the `claude-history://`
+/// links of Spotlight stubs arrive as `Opened` and open their conversation.
+fn handle_run_event(app_handle: &AppHandle, event: RunEvent) {
+    #[cfg(target_os = "macos")]
+    if let RunEvent::Opened { urls } = event {
+        for url in urls {
+            events::open_deep_link(app_handle, url.as_str());
+        }
+    }
+    #[cfg(not(target_os = "macos"))]
+    let _ = (app_handle, event);
 }
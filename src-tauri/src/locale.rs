@@ -0,0 +1,112 @@
+//! Locale-aware formatting of dates in generated documents.
+//!
+//! Exports, summaries, and templated exports are read by people who may
+//! not read ISO 8601, so their dates are written in the words and order of
+//! the `locale` setting. Times stay in UTC, labelled as such, as the reader
+//! may be anywhere. Machine-readable output (JSON exports, CSV) keeps
+//! ISO 8601.
+
+use crate::parser::normalize_timestamp;
+use chrono::{DateTime, Locale};
+
+/// Locale used when none is set, or the one set is unknown.
+pub const DEFAULT_LOCALE: &str = "en-US";
+
+/// Parses a locale tag such as `de-DE`, `de_DE`, or `sr-RS@latin`.
+pub fn parse_locale(tag: &str) -> Option<Locale> {
+    let tag = tag.trim().replace('-', "_");
+    let tag = match tag.split_once('_') {
+        Some((language, region)) if region.len() == 2 => {
+            format!("{}_{}", language.to_lowercase(), region.to_uppercase())
+        }
+        _ => tag,
+    };
+    Locale::try_from(tag.as_str()).ok()
+}
+
+/// Formats timestamps in one locale.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DateFormatter {
+    locale: Locale,
+}
+
+impl Default for DateFormatter {
+    fn default() -> Self {
+        Self {
+            locale: Locale::en_US,
+        }
+    }
+}
+
+impl DateFormatter {
+    /// A formatter for a locale tag, or `None` if the locale is unknown.
+    pub fn new(tag: &str) -> Option<Self> {
+        parse_locale(tag).map(|locale| Self { locale })
+    }
+
+    /// A formatter for the `locale` setting, falling back to the default
+    /// locale if it's unknown.
+    pub fn from_setting(tag: &str) -> Self {
+        Self::new(tag).unwrap_or_default()
+    }
+
+    /// Date and time, e.g. `Wednesday, January 15, 2025 10:30 UTC` or
+    /// `Mittwoch 15.01.2025 10:30 UTC`. Unparseable timestamps are returned
+    /// as they are.
+    pub fn datetime(&self, timestamp: &str) -> String {
+        let pattern = match self.locale {
+            Locale::en_US => "%A, %B %-d, %Y %H:%M UTC",
+            _ => "%A %x %H:%M UTC",
+        };
+        self.format(timestamp, pattern)
+    }
+
+    /// Date only, e.g. `January 15, 2025` or `15.01.2025`.
+    pub fn date(&self, timestamp: &str) -> String {
+        let pattern = match self.locale {
+            Locale::en_US => "%B %-d, %Y",
+            _ => "%x",
+        };
+        self.format(timestamp, pattern)
+    }
+
+    fn format(&self, timestamp: &str, pattern: &str) -> String {
+        normalize_timestamp(timestamp)
+            .and_then(|ts| DateTime::parse_from_rfc3339(&ts.utc).ok())
+            .map(|dt| dt.format_localized(pattern, self.locale).to_string())
+            .unwrap_or_else(|| timestamp.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TIMESTAMP: &str = "2025-01-15T10:30:00Z";
+
+    #[test]
+    fn test_parse_locale() {
+        assert_eq!(parse_locale("en-US"), Some(Locale::en_US));
+        assert_eq!(parse_locale(" de_de "), Some(Locale::de_DE));
+        assert_eq!(parse_locale("sr-RS@latin"), Some(Locale::sr_RS_latin));
+        assert_eq!(parse_locale("xx-YY"), None);
+        assert_eq!(parse_locale(""), None);
+    }
+
+    #[test]
+    fn test_format_in_locale() {
+        let english = DateFormatter::default();
+        assert_eq!(
+            english.datetime(TIMESTAMP),
+            "Wednesday, January 15, 2025 10:30 UTC"
+        );
+        assert_eq!(english.date(TIMESTAMP), "January 15, 2025");
+
+        let german = DateFormatter::new("de-DE").unwrap();
+        assert_eq!(german.datetime(TIMESTAMP), "Mittwoch 15.01.2025 10:30 UTC");
+        assert_eq!(german.date(TIMESTAMP), "15.01.2025");
+
+        assert_eq!(DateFormatter::from_setting("unknown"), english);
+        assert_eq!(german.datetime("not a date"), "not a date");
+    }
+}
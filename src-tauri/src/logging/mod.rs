@@ -0,0 +1,261 @@
+//! Logging setup and the log viewer backend.
+//!
+//! Logs go to stderr as before and, as JSON lines, to a daily rotating file
+//! in `{app_data}/logs/`, keeping the last week. Users can then attach
+//! diagnostics to bug reports without running the app from a terminal;
+//! `get_recent_logs` reads the files back.
+
+use crate::db::sqlite::get_app_data_dir;
+use crate::models::LogEntry;
+use serde::Deserialize;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use tracing::Level;
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{fmt, EnvFilter};
+
+/// Directory inside the app data directory holding log files.
+pub const LOG_DIR_NAME: &str = "logs";
+
+/// Log files are named `{prefix}.{YYYY-MM-DD}.{suffix}`.
+const LOG_FILE_PREFIX: &str = "claude-history";
+const LOG_FILE_SUFFIX: &str = "log";
+
+/// Daily log files kept before the oldest is deleted.
+const MAX_LOG_FILES: usize = 7;
+
+/// Returns the log directory for an app data directory.
+pub fn log_dir(app_data_dir: &Path) -> PathBuf {
+    app_data_dir.join(LOG_DIR_NAME)
+}
+
+/// Installs the global tracing subscriber: stderr plus the rotating log file.
+///
+/// Falls back to stderr only when the log directory can't be created. The
+/// returned guard flushes the file on drop, so keep it alive until exit.
+pub fn init_logging() -> Option<WorkerGuard> {
+    let filter = EnvFilter::from_default_env().add_directive(Level::INFO.into());
+
+    let file_writer = get_app_data_dir()
+        .ok()
+        .map(|dir| log_dir(&dir))
+        .and_then(|dir| {
+            RollingFileAppender::builder()
+                .rotation(Rotation::DAILY)
+                .filename_prefix(LOG_FILE_PREFIX)
+                .filename_suffix(LOG_FILE_SUFFIX)
+                .max_log_files(MAX_LOG_FILES)
+                .build(&dir)
+                .map_err(|e| eprintln!("Failed to open log file in {:?}: {}", dir, e))
+                .ok()
+        })
+        .map(tracing_appender::non_blocking);
+
+    let (file_layer, guard) = match file_writer {
+        Some((writer, guard)) => (
+            Some(
+                fmt::layer()
+                    .json()
+                    .with_current_span(false)
+                    .with_span_list(false)
+                    .with_writer(writer),
+            ),
+            Some(guard),
+        ),
+        None => (None, None),
+    };
+
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(fmt::layer())
+        .with(file_layer)
+        .init();
+
+    guard
+}
+
+/// A line of the JSON log file.
+#[derive(Deserialize)]
+struct RawLogLine {
+    timestamp: String,
+    level: String,
+    #[serde(default)]
+    target: String,
+    #[serde(default)]
+    fields: RawLogFields,
+}
+
+#[derive(Deserialize, Default)]
+struct RawLogFields {
+    #[serde(default)]
+    message: String,
+}
+
+/// Reads the most recent log entries at `min_level` or more severe.
+///
+/// Returns up to `limit` entries, oldest first. Lines that aren't valid
+/// JSON log entries are skipped.
+pub fn read_recent_logs(dir: &Path, min_level: Level, limit: usize) -> io::Result<Vec<LogEntry>> {
+    let mut entries = Vec::new();
+    if limit == 0 {
+        return Ok(entries);
+    }
+
+    // Newest file first; the date in the name sorts chronologically
+    for path in log_files(dir)?.iter().rev() {
+        let contents = fs::read_to_string(path)?;
+        for line in contents.lines().rev() {
+            let Some(entry) = parse_log_line(line) else {
+                continue;
+            };
+            if entry
+                .level
+                .parse::<Level>()
+                .is_ok_and(|level| level <= min_level)
+            {
+                entries.push(entry);
+                if entries.len() == limit {
+                    entries.reverse();
+                    return Ok(entries);
+                }
+            }
+        }
+    }
+
+    entries.reverse();
+    Ok(entries)
+}
+
+/// Returns the log files in `dir`, sorted oldest first.
+fn log_files(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let read_dir = match fs::read_dir(dir) {
+        Ok(read_dir) => read_dir,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+
+    let prefix = format!("{}.", LOG_FILE_PREFIX);
+    let suffix = format!(".{}", LOG_FILE_SUFFIX);
+    let mut files: Vec<PathBuf> = read_dir
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with(&prefix) && name.ends_with(&suffix))
+        })
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+fn parse_log_line(line: &str) -> Option<LogEntry> {
+    let raw: RawLogLine = serde_json::from_str(line).ok()?;
+    Some(LogEntry {
+        timestamp: raw.timestamp,
+        level: raw.level,
+        target: raw.target,
+        message: raw.fields.message,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn line(timestamp: &str, level: &str, message: &str) -> String {
+        serde_json::json!({
+            "timestamp": timestamp,
+            "level": level,
+            "fields": { "message": message },
+            "target": "claudecode_chat_history_lib::watcher::fs",
+        })
+        .to_string()
+    }
+
+    fn write_log(dir: &Path, day: &str, lines: &[String]) {
+        fs::write(
+            dir.join(format!("{}.{}.{}", LOG_FILE_PREFIX, day, LOG_FILE_SUFFIX)),
+            lines.join("\n") + "\n",
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_reads_recent_entries_across_files() {
+        let dir = tempdir().unwrap();
+        write_log(
+            dir.path(),
+            "2025-01-05",
+            &[
+                line("2025-01-05T10:00:00Z", "INFO", "first"),
+                line("2025-01-05T11:00:00Z", "ERROR", "second"),
+            ],
+        );
+        write_log(
+            dir.path(),
+            "2025-01-06",
+            &[
+                line("2025-01-06T09:00:00Z", "WARN", "third"),
+                line("2025-01-06T10:00:00Z", "INFO", "fourth"),
+            ],
+        );
+
+        let entries = read_recent_logs(dir.path(), Level::INFO, 3).unwrap();
+        let messages: Vec<_> = entries.iter().map(|e| e.message.as_str()).collect();
+        assert_eq!(messages, ["second", "third", "fourth"]);
+        assert_eq!(entries[0].level, "ERROR");
+        assert_eq!(entries[0].target, "claudecode_chat_history_lib::watcher::fs");
+    }
+
+    #[test]
+    fn test_filters_by_level() {
+        let dir = tempdir().unwrap();
+        write_log(
+            dir.path(),
+            "2025-01-06",
+            &[
+                line("2025-01-06T09:00:00Z", "DEBUG", "noise"),
+                line("2025-01-06T09:01:00Z", "ERROR", "broken"),
+                line("2025-01-06T09:02:00Z", "WARN", "odd"),
+                line("2025-01-06T09:03:00Z", "INFO", "fine"),
+            ],
+        );
+
+        let entries = read_recent_logs(dir.path(), Level::WARN, 10).unwrap();
+        let messages: Vec<_> = entries.iter().map(|e| e.message.as_str()).collect();
+        assert_eq!(messages, ["broken", "odd"]);
+
+        let entries = read_recent_logs(dir.path(), Level::TRACE, 10).unwrap();
+        assert_eq!(entries.len(), 4);
+    }
+
+    #[test]
+    fn test_skips_malformed_lines_and_other_files() {
+        let dir = tempdir().unwrap();
+        write_log(
+            dir.path(),
+            "2025-01-06",
+            &[
+                "not json".to_string(),
+                line("2025-01-06T09:00:00Z", "INFO", "kept"),
+            ],
+        );
+        fs::write(dir.path().join("other.txt"), line("2025-01-07T00:00:00Z", "INFO", "ignored")).unwrap();
+
+        let entries = read_recent_logs(dir.path(), Level::INFO, 10).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].message, "kept");
+    }
+
+    #[test]
+    fn test_missing_directory_has_no_entries() {
+        let dir = tempdir().unwrap();
+        let entries = read_recent_logs(&dir.path().join("logs"), Level::INFO, 10).unwrap();
+        assert!(entries.is_empty());
+    }
+}
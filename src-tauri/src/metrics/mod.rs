@@ -0,0 +1,234 @@
+//! Local performance metrics.
+//!
+//! A process-wide registry of counters and timers (command latencies,
+//! watcher events, ingest, cache hits) for diagnosing slowness on a user's
+//! machine. Metrics are kept in memory for the current session only and are
+//! never sent anywhere; `get_app_metrics` returns a snapshot.
+
+use crate::models::{AppMetrics, LatencyStats, TimerMetrics};
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::{Mutex, MutexGuard, OnceLock};
+use std::time::{Duration, Instant};
+
+/// Recent samples kept per timer for percentiles.
+const RECENT_SAMPLES: usize = 256;
+
+#[derive(Default)]
+struct TimerState {
+    count: u64,
+    total: Duration,
+    max: Duration,
+    recent: VecDeque<Duration>,
+}
+
+/// Counters and timers keyed by dotted names (e.g. `command.get_conversations`).
+pub struct MetricsRegistry {
+    started: Instant,
+    counters: Mutex<BTreeMap<&'static str, u64>>,
+    timers: Mutex<BTreeMap<&'static str, TimerState>>,
+}
+
+impl Default for MetricsRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MetricsRegistry {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            counters: Mutex::new(BTreeMap::new()),
+            timers: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Adds `n` to a counter.
+    pub fn add(&self, name: &'static str, n: u64) {
+        *lock(&self.counters).entry(name).or_default() += n;
+    }
+
+    /// Returns the current value of a counter, 0 if it was never added to.
+    pub fn counter(&self, name: &str) -> u64 {
+        lock(&self.counters).get(name).copied().unwrap_or(0)
+    }
+
+    /// Records one duration for a timer.
+    pub fn record(&self, name: &'static str, duration: Duration) {
+        let mut timers = lock(&self.timers);
+        let timer = timers.entry(name).or_default();
+        timer.count += 1;
+        timer.total += duration;
+        timer.max = timer.max.max(duration);
+        if timer.recent.len() == RECENT_SAMPLES {
+            timer.recent.pop_front();
+        }
+        timer.recent.push_back(duration);
+    }
+
+    /// Returns the current values of all metrics.
+    pub fn snapshot(&self) -> AppMetrics {
+        let counters = lock(&self.counters)
+            .iter()
+            .map(|(name, value)| (name.to_string(), *value))
+            .collect();
+        let timers = lock(&self.timers)
+            .iter()
+            .map(|(name, timer)| {
+                let recent = latency_stats(timer.recent.iter().copied().collect());
+                TimerMetrics {
+                    name: name.to_string(),
+                    count: timer.count,
+                    total_ms: millis(timer.total),
+                    mean_ms: millis(timer.total) / timer.count as f64,
+                    max_ms: millis(timer.max),
+                    p50_ms: recent.p50_ms,
+                    p95_ms: recent.p95_ms,
+                }
+            })
+            .collect();
+
+        AppMetrics {
+            uptime_secs: self.started.elapsed().as_secs(),
+            counters,
+            timers,
+        }
+    }
+}
+
+/// Metrics stay usable after a panic while the lock was held.
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Returns the process-wide registry.
+pub fn registry() -> &'static MetricsRegistry {
+    static REGISTRY: OnceLock<MetricsRegistry> = OnceLock::new();
+    REGISTRY.get_or_init(MetricsRegistry::new)
+}
+
+/// Increments a counter in the process-wide registry.
+pub fn increment(name: &'static str) {
+    registry().add(name, 1);
+}
+
+/// Records the time until it is dropped.
+#[must_use = "the timer records when dropped"]
+pub struct Timer {
+    name: &'static str,
+    started: Instant,
+}
+
+impl Drop for Timer {
+    fn drop(&mut self) {
+        registry().record(self.name, self.started.elapsed());
+    }
+}
+
+/// Starts a timer in the process-wide registry, recorded when dropped.
+pub fn start_timer(name: &'static str) -> Timer {
+    Timer {
+        name,
+        started: Instant::now(),
+    }
+}
+
+/// Computes nearest-rank percentiles.
+pub fn latency_stats(mut latencies: Vec<Duration>) -> LatencyStats {
+    if latencies.is_empty() {
+        return LatencyStats::default();
+    }
+    latencies.sort();
+
+    let percentile = |p: f64| {
+        let rank = (p * latencies.len() as f64).ceil() as usize;
+        millis(latencies[rank.clamp(1, latencies.len()) - 1])
+    };
+    LatencyStats {
+        samples: latencies.len(),
+        p50_ms: percentile(0.50),
+        p95_ms: percentile(0.95),
+        p99_ms: percentile(0.99),
+        max_ms: millis(latencies[latencies.len() - 1]),
+    }
+}
+
+/// Converts a duration to fractional milliseconds.
+pub fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_counters() {
+        let registry = MetricsRegistry::new();
+        registry.add("watcher.events", 1);
+        registry.add("watcher.events", 2);
+        registry.add("cache.hits", 1);
+
+        let snapshot = registry.snapshot();
+        assert_eq!(snapshot.counters.get("watcher.events"), Some(&3));
+        assert_eq!(snapshot.counters.get("cache.hits"), Some(&1));
+        assert!(snapshot.timers.is_empty());
+        assert_eq!(registry.counter("watcher.events"), 3);
+        assert_eq!(registry.counter("ingest.file_errors"), 0);
+    }
+
+    #[test]
+    fn test_timers() {
+        let registry = MetricsRegistry::new();
+        for ms in [10, 20, 30, 40] {
+            registry.record("command.get_conversations", Duration::from_millis(ms));
+        }
+
+        let snapshot = registry.snapshot();
+        let timer = &snapshot.timers[0];
+        assert_eq!(timer.name, "command.get_conversations");
+        assert_eq!(timer.count, 4);
+        assert_eq!(timer.total_ms, 100.0);
+        assert_eq!(timer.mean_ms, 25.0);
+        assert_eq!(timer.max_ms, 40.0);
+        assert_eq!(timer.p50_ms, 20.0);
+        assert_eq!(timer.p95_ms, 40.0);
+    }
+
+    #[test]
+    fn test_timer_keeps_recent_samples() {
+        let registry = MetricsRegistry::new();
+        registry.record("slow", Duration::from_secs(10));
+        for _ in 0..RECENT_SAMPLES {
+            registry.record("slow", Duration::from_millis(1));
+        }
+
+        let timer = &registry.snapshot().timers[0];
+        assert_eq!(timer.count, RECENT_SAMPLES as u64 + 1);
+        // The all-time max is kept; percentiles only cover recent samples
+        assert_eq!(timer.max_ms, 10_000.0);
+        assert_eq!(timer.p95_ms, 1.0);
+    }
+
+    #[test]
+    fn test_start_timer_records_on_drop() {
+        drop(start_timer("test.start_timer"));
+
+        let snapshot = registry().snapshot();
+        assert!(snapshot.timers.iter().any(|t| t.name == "test.start_timer"));
+    }
+
+    #[test]
+    fn test_latency_stats() {
+        let latencies = (1..=100).map(Duration::from_millis).collect();
+        let stats = latency_stats(latencies);
+        assert_eq!(stats.samples, 100);
+        assert_eq!(stats.p50_ms, 50.0);
+        assert_eq!(stats.p95_ms, 95.0);
+        assert_eq!(stats.p99_ms, 99.0);
+        assert_eq!(stats.max_ms, 100.0);
+
+        assert_eq!(latency_stats(Vec::new()), LatencyStats::default());
+    }
+}
@@ -0,0 +1,322 @@
+//! Versioned JSON schema for conversation exports.
+//!
+//! These structs are the contract with tools that read JSON exports. They
+//! are kept separate from the internal models, which change with the app:
+//! converters map internal models onto them, and any change to the
+//! serialized shape bumps `EXPORT_SCHEMA_VERSION`.
+//!
+//! Version history:
+//! * 1 - Conversations with messages and content blocks; index of a
+//!   multi-conversation export.
+
+use super::{ContentBlock, ContentBlockType, Conversation, Message, MessageRole, TokenCount};
+use serde::{Deserialize, Serialize};
+
+/// Version of the export schema written in `schemaVersion`.
+pub const EXPORT_SCHEMA_VERSION: u32 = 1;
+
+/// Top-level JSON export document.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportDocument {
+    /// `EXPORT_SCHEMA_VERSION` of the writer.
+    pub schema_version: u32,
+    pub conversations: Vec<ExportConversation>,
+}
+
+impl ExportDocument {
+    /// A document with the current schema version.
+    pub fn new(conversations: Vec<ExportConversation>) -> Self {
+        Self {
+            schema_version: EXPORT_SCHEMA_VERSION,
+            conversations,
+        }
+    }
+}
+
+/// Index of a multi-conversation export, listing the exported files.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportIndex {
+    pub schema_version: u32,
+    pub conversations: Vec<ExportIndexEntry>,
+}
+
+/// A conversation listed in an export index.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportIndexEntry {
+    /// File holding the conversation, relative to the index.
+    pub file: String,
+    pub project_name: String,
+    pub start_time: String,
+    pub message_count: usize,
+    pub tags: Vec<String>,
+}
+
+/// An exported conversation.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportConversation {
+    pub id: String,
+    /// Project directory the conversation ran in.
+    pub project_path: String,
+    pub project_name: String,
+    /// Timestamps as recorded (ISO 8601).
+    pub start_time: String,
+    pub last_time: String,
+    pub bookmarked: bool,
+    pub tags: Vec<String>,
+    pub total_tokens: ExportTokens,
+    pub messages: Vec<ExportMessage>,
+}
+
+/// Token usage.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportTokens {
+    pub input: i64,
+    pub output: i64,
+}
+
+/// An exported message.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportMessage {
+    pub id: String,
+    /// Zero-based position in the conversation.
+    pub ordinal: u32,
+    /// `user`, `assistant`, or `system`.
+    pub role: String,
+    pub timestamp: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tokens: Option<ExportTokens>,
+    pub blocks: Vec<ExportBlock>,
+}
+
+/// An exported content block.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportBlock {
+    /// `text`, `code`, `tool_use`, `tool_result`, `image`, `terminal`, or
+    /// `file_view`.
+    #[serde(rename = "type")]
+    pub block_type: String,
+    /// Text of the block; for images, the attachment ID.
+    pub text: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    /// Tool name for tool calls; tool call ID for results.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool: Option<String>,
+    /// Command and exit code, for terminal blocks.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub command: Option<ExportCommand>,
+    /// File and first line, for file view blocks.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file: Option<ExportFile>,
+}
+
+/// A shell command run by a tool call.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportCommand {
+    pub command: String,
+    pub stdout: String,
+    pub stderr: String,
+    /// Missing if the command didn't finish.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i32>,
+}
+
+/// Where the lines of a file view come from.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportFile {
+    pub path: String,
+    /// Number of the first line, from 1.
+    pub start_line: u32,
+}
+
+impl From<&Conversation> for ExportConversation {
+    fn from(conversation: &Conversation) -> Self {
+        Self {
+            id: conversation.id.clone(),
+            project_path: conversation.project_path.clone(),
+            project_name: conversation.project_name.clone(),
+            start_time: conversation.start_time.clone(),
+            last_time: conversation.last_time.clone(),
+            bookmarked: conversation.bookmarked.unwrap_or(false),
+            tags: conversation.tags.clone().unwrap_or_default(),
+            total_tokens: (&conversation.total_tokens).into(),
+            messages: conversation.messages.iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl From<&TokenCount> for ExportTokens {
+    fn from(tokens: &TokenCount) -> Self {
+        Self {
+            input: tokens.input,
+            output: tokens.output,
+        }
+    }
+}
+
+impl From<&Message> for ExportMessage {
+    fn from(message: &Message) -> Self {
+        Self {
+            id: message.id.clone(),
+            ordinal: message.ordinal,
+            role: match message.role {
+                MessageRole::User => "user",
+                MessageRole::Assistant => "assistant",
+                MessageRole::System => "system",
+            }
+            .to_string(),
+            timestamp: message.timestamp.clone(),
+            tokens: message.token_count.as_ref().map(Into::into),
+            blocks: message.content.iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl From<&ContentBlock> for ExportBlock {
+    fn from(block: &ContentBlock) -> Self {
+        Self {
+            block_type: match block.block_type {
+                ContentBlockType::Text => "text",
+                ContentBlockType::Code => "code",
+                ContentBlockType::ToolUse => "tool_use",
+                ContentBlockType::ToolResult => "tool_result",
+                ContentBlockType::Image => "image",
+                ContentBlockType::Terminal => "terminal",
+                ContentBlockType::FileView => "file_view",
+            }
+            .to_string(),
+            text: block.content.clone(),
+            language: block.language.clone(),
+            tool: block.tool_name.clone(),
+            command: block.terminal.as_ref().map(|terminal| ExportCommand {
+                command: terminal.command.clone(),
+                stdout: terminal.stdout.clone(),
+                stderr: terminal.stderr.clone(),
+                exit_code: terminal.exit_code,
+            }),
+            file: block.file_view.as_ref().map(|view| ExportFile {
+                path: view.path.clone(),
+                start_line: view.start_line,
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{FileView, TerminalTranscript};
+
+    #[test]
+    fn test_export_schema_shape() {
+        let conversation = Conversation {
+            id: "c1".to_string(),
+            project_path: "/work/app".to_string(),
+            project_name: "app".to_string(),
+            start_time: "2025-01-15T10:00:00Z".to_string(),
+            last_time: "2025-01-15T10:05:00Z".to_string(),
+            messages: vec![Message {
+                id: "m1".to_string(),
+                ordinal: 0,
+                role: MessageRole::Assistant,
+                content: vec![
+                    ContentBlock {
+                        block_type: ContentBlockType::Terminal,
+                        content: "$ false".to_string(),
+                        language: None,
+                        tool_name: Some("Bash".to_string()),
+                        spans: None,
+                        terminal: Some(TerminalTranscript {
+                            command: "false".to_string(),
+                            exit_code: Some(1),
+                            ..Default::default()
+                        }),
+                        file_view: None,
+                    },
+                    ContentBlock {
+                        block_type: ContentBlockType::FileView,
+                        content: "fn main() {}".to_string(),
+                        language: Some("rust".to_string()),
+                        tool_name: Some("toolu_2".to_string()),
+                        spans: None,
+                        terminal: None,
+                        file_view: Some(FileView {
+                            path: "/work/app/src/main.rs".to_string(),
+                            start_line: 1,
+                        }),
+                    },
+                ],
+                timestamp: "2025-01-15T10:00:00Z".to_string(),
+                token_count: None,
+                collapsed_reminders: 0,
+                is_meta: false,
+            }],
+            total_tokens: TokenCount {
+                input: 10,
+                output: 20,
+            },
+            bookmarked: None,
+            tags: None,
+            completeness: Default::default(),
+            content_hash: None,
+            read_position: None,
+        };
+
+        let document = ExportDocument::new(vec![(&conversation).into()]);
+        let json = serde_json::to_value(&document).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "schemaVersion": 1,
+                "conversations": [{
+                    "id": "c1",
+                    "projectPath": "/work/app",
+                    "projectName": "app",
+                    "startTime": "2025-01-15T10:00:00Z",
+                    "lastTime": "2025-01-15T10:05:00Z",
+                    "bookmarked": false,
+                    "tags": [],
+                    "totalTokens": { "input": 10, "output": 20 },
+                    "messages": [{
+                        "id": "m1",
+                        "ordinal": 0,
+                        "role": "assistant",
+                        "timestamp": "2025-01-15T10:00:00Z",
+                        "blocks": [
+                            {
+                                "type": "terminal",
+                                "text": "$ false",
+                                "tool": "Bash",
+                                "command": {
+                                    "command": "false",
+                                    "stdout": "",
+                                    "stderr": "",
+                                    "exitCode": 1
+                                }
+                            },
+                            {
+                                "type": "file_view",
+                                "text": "fn main() {}",
+                                "language": "rust",
+                                "tool": "toolu_2",
+                                "file": { "path": "/work/app/src/main.rs", "startLine": 1 }
+                            }
+                        ]
+                    }]
+                }]
+            })
+        );
+
+        let parsed: ExportDocument = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed, document);
+    }
+}
@@ -2,7 +2,10 @@
 //!
 //! This module contains all data structures used throughout the application,
 //! including `Conversation`, `Message`, `ContentBlock`, and filter types.
-//! All structs derive serde traits for serialization.
+//! All structs derive serde traits for serialization. The versioned JSON
+//! export schema is in `export`.
+
+pub mod export;
 
 use serde::{Deserialize, Serialize};
 
@@ -22,6 +25,13 @@ pub enum ContentBlockType {
     Code,
     ToolUse,
     ToolResult,
+    /// An image from a tool result; the content is its attachment ID.
+    Image,
+    /// A shell command and its output, from a Bash tool call and its result.
+    Terminal,
+    /// Lines of a file returned by the Read tool; the content is the lines,
+    /// without their numbers.
+    FileView,
 }
 
 /// A content block within a message.
@@ -38,6 +48,82 @@ pub struct ContentBlock {
     /// Tool name for tool_use/tool_result blocks.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tool_name: Option<String>,
+    /// Styled runs of the content (of the output, for terminal blocks), for
+    /// tool output that had ANSI colors when parsed with `AnsiMode::Styled`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub spans: Option<Vec<StyledSpan>>,
+    /// The command and its output, for terminal blocks, and for the results
+    /// of Bash calls that recorded their output streams.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub terminal: Option<TerminalTranscript>,
+    /// Which file and lines, for file view blocks.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file_view: Option<FileView>,
+}
+
+/// Where the lines of a file view block come from.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct FileView {
+    pub path: String,
+    /// Number of the first line, from 1.
+    pub start_line: u32,
+}
+
+/// A shell command run through the Bash tool, with its output.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct TerminalTranscript {
+    /// ID of the tool call.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool_use_id: Option<String>,
+    pub command: String,
+    /// What the command does, as given with the tool call.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    pub stdout: String,
+    pub stderr: String,
+    /// `None` until the result is known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i32>,
+    #[serde(default)]
+    pub interrupted: bool,
+}
+
+/// A run of text sharing one terminal style.
+///
+/// Colors are the standard color names (`red`, `bright_blue`, ...) or
+/// `#rrggbb`; no color means the default.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct StyledSpan {
+    pub text: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fg: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bg: Option<String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub bold: bool,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub dim: bool,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub italic: bool,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub underline: bool,
+}
+
+/// An image extracted from a tool result, stored by the hash of its data.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Attachment {
+    /// SHA-256 of the base64 data, in hex.
+    pub id: String,
+    /// MIME type, such as `image/png`.
+    pub media_type: String,
+    /// Base64-encoded bytes.
+    pub data: String,
+    /// Decoded size in bytes.
+    pub size: i64,
 }
 
 /// Message role discriminator.
@@ -53,7 +139,10 @@ pub enum MessageRole {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Message {
+    /// Message UUID from the JSONL file, or `msg_{ordinal}` if it has none.
     pub id: String,
+    /// Zero-based position in chronological order, stable as messages are appended.
+    pub ordinal: u32,
     pub role: MessageRole,
     pub content: Vec<ContentBlock>,
     /// ISO 8601 timestamp.
@@ -61,6 +150,33 @@ pub struct Message {
     /// Token count for this message (optional).
     #[serde(skip_serializing_if = "Option::is_none")]
     pub token_count: Option<TokenCount>,
+    /// Repeated system reminders removed from the content; the first of
+    /// each is kept where it appeared.
+    #[serde(default)]
+    pub collapsed_reminders: u32,
+    /// Not part of the dialogue: command output, hook output, or progress.
+    /// Hidden from filtered views unless asked for.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub is_meta: bool,
+}
+
+/// An event of a conversation replay, sent over the replay's channel.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "camelCase")]
+pub enum ReplayEvent {
+    /// The replay began; its messages follow.
+    #[serde(rename_all = "camelCase")]
+    Started {
+        /// ID to pass to `stop_replay`.
+        replay_id: String,
+        message_count: usize,
+        /// Time the replay takes at its speed, without stops.
+        duration_millis: u64,
+    },
+    /// A message's turn came.
+    Message { message: Message },
+    /// Every message was sent, or the replay was stopped.
+    Finished { stopped: bool },
 }
 
 /// A complete conversation with all messages.
@@ -68,7 +184,7 @@ pub struct Message {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Conversation {
-    /// Unique ID derived from file path + session.
+    /// Stable ID derived from the session ID.
     pub id: String,
     /// Original project directory path.
     pub project_path: String,
@@ -88,6 +204,73 @@ pub struct Conversation {
     /// User-defined tags (MVP extension point).
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tags: Option<Vec<String>>,
+    /// How much of the session file made it into `messages`.
+    #[serde(default)]
+    pub completeness: Completeness,
+    /// Hash of the messages, for `has_changed_since`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_hash: Option<String>,
+    /// Where reading left off, to resume there (see `set_read_position`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub read_position: Option<ReadPosition>,
+}
+
+/// The message a conversation was last read up to.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReadPosition {
+    /// ID of the last-read message.
+    pub message_id: String,
+    /// When it was recorded (RFC 3339).
+    pub updated_at: String,
+}
+
+/// Lines of a conversation's session file that were parsed or skipped; a
+/// transcript with skipped lines is partial.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Completeness {
+    /// Lines parsed into messages.
+    pub parsed: usize,
+    /// Lines that couldn't be read or parsed (malformed JSON, unknown line
+    /// types).
+    pub skipped: usize,
+    /// The session file is large, so tool results are shortened and their
+    /// images left out until `load_full` is called.
+    #[serde(default)]
+    pub partial_storage: bool,
+}
+
+/// A matched range of text, in UTF-16 code units.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MatchSpan {
+    pub start: usize,
+    pub end: usize,
+}
+
+/// A search match within a conversation's content blocks.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConversationMatch {
+    /// Ordinal of the message containing the match.
+    pub message_ordinal: u32,
+    /// Index of the content block within the message.
+    pub block_index: usize,
+    /// Start of the match in the block's content (UTF-16 code units).
+    pub start: usize,
+    /// End of the match, exclusive.
+    pub end: usize,
+}
+
+/// A conversation opened from search results, with the query's matches.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConversationWithHighlights {
+    #[serde(flatten)]
+    pub conversation: Conversation,
+    /// All matches in reading order, for next/previous navigation.
+    pub matches: Vec<ConversationMatch>,
 }
 
 /// Lightweight conversation summary for list view.
@@ -101,13 +284,50 @@ pub struct ConversationSummary {
     pub start_time: String,
     /// Last message timestamp (ISO 8601).
     pub last_time: String,
-    /// First user message, truncated to 100 characters.
+    /// Single-line preview for the list (see [`AppSettings::preview_length`]).
     pub preview: String,
+    /// Longer, multi-line preview for tooltips.
+    #[serde(default)]
+    pub preview_medium: String,
     /// Total number of messages.
     pub message_count: i32,
     /// Whether this conversation is bookmarked.
     #[serde(default)]
     pub bookmarked: bool,
+    /// Whether the user's last messages say the problem was solved.
+    #[serde(default)]
+    pub resolution: Resolution,
+}
+
+/// How a conversation ended, judged from the user's last messages.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Resolution {
+    /// The user thanked or said it works.
+    Resolved,
+    /// The user said it's wrong or still failing.
+    Unresolved,
+    /// Neither was said.
+    #[default]
+    Unknown,
+}
+
+impl Resolution {
+    /// Returns the value stored in the database.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Resolution::Resolved => "resolved",
+            Resolution::Unresolved => "unresolved",
+            Resolution::Unknown => "unknown",
+        }
+    }
+
+    /// Parses a stored value.
+    pub fn from_db(value: &str) -> Option<Self> {
+        [Resolution::Resolved, Resolution::Unresolved, Resolution::Unknown]
+            .into_iter()
+            .find(|resolution| resolution.as_str() == value)
+    }
 }
 
 /// Filter options for querying conversations.
@@ -117,10 +337,11 @@ pub struct ConversationFilters {
     /// Filter by project name.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub project: Option<String>,
-    /// Start of date range (inclusive, ISO 8601).
+    /// Start of date range (inclusive). A bare `YYYY-MM-DD` date is a day in
+    /// the configured timezone; a full ISO 8601 timestamp is an exact instant.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub date_start: Option<String>,
-    /// End of date range (inclusive, ISO 8601).
+    /// End of date range (inclusive, same formats as `date_start`).
     #[serde(skip_serializing_if = "Option::is_none")]
     pub date_end: Option<String>,
     /// Filter by bookmark status.
@@ -129,6 +350,171 @@ pub struct ConversationFilters {
     /// Filter by tags (conversation must have ALL specified tags).
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tags: Option<Vec<String>>,
+    /// Filter by archive status. Conversation lists hide archived
+    /// conversations unless this is set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub archived: Option<bool>,
+    /// Filter by protection against archiving and deletion.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub protected: Option<bool>,
+    /// Filter by how the conversation ended.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resolution: Option<Resolution>,
+    /// Filter by custom metadata fields (conversation must have ALL
+    /// specified keys with these values, compared case-insensitively).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<std::collections::BTreeMap<String, String>>,
+}
+
+/// A custom metadata field of a conversation, such as `customer=acme`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MetadataField {
+    /// Lowercase key, such as `customer`.
+    pub key: String,
+    pub value: String,
+    /// When the value was last set (RFC 3339).
+    pub updated_at: String,
+}
+
+/// A conversation shared to Slack.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SlackShare {
+    /// ID of the channel posted to.
+    pub channel_id: String,
+    /// Timestamp identifying the summary message in the channel.
+    pub message_ts: String,
+    /// ID of the uploaded transcript file.
+    pub file_id: String,
+}
+
+/// An encrypted share bundle of a conversation: one HTML file that opens
+/// with its passphrase until it expires.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShareBundle {
+    pub id: String,
+    pub conversation_id: String,
+    /// The bundle's HTML file, in the Shares folder of app data.
+    pub path: String,
+    /// `file://` link to the bundle.
+    pub url: String,
+    pub created_at: String,
+    /// When the page stops opening and the file is deleted.
+    pub expires_at: String,
+    /// Passphrase opening the bundle; only returned when it's created,
+    /// as it isn't stored.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub passphrase: Option<String>,
+}
+
+/// How a conversation came to be linked to an issue.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IssueSource {
+    /// Linked by the user with an issue URL.
+    Linked,
+    /// The issue key was mentioned in the conversation.
+    Detected,
+}
+
+impl IssueSource {
+    /// Returns the value stored in the database.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            IssueSource::Linked => "linked",
+            IssueSource::Detected => "detected",
+        }
+    }
+
+    /// Parses a stored value.
+    pub fn from_db(value: &str) -> Option<Self> {
+        [IssueSource::Linked, IssueSource::Detected]
+            .into_iter()
+            .find(|source| source.as_str() == value)
+    }
+}
+
+/// A Jira or Linear issue a conversation is about.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IssueLink {
+    /// Issue key, e.g. `ABC-123`.
+    pub key: String,
+    /// Link to the issue, if it was linked by URL.
+    pub url: Option<String>,
+    pub source: IssueSource,
+    /// When the link was recorded (RFC 3339).
+    pub created_at: String,
+}
+
+/// Order of conversation lists.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConversationSort {
+    /// Most recently active first.
+    #[default]
+    Newest,
+    /// Least recently active first.
+    Oldest,
+}
+
+/// Result of a static site export.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StaticSiteExport {
+    /// Path of the site's index page.
+    pub index_path: String,
+    /// Number of conversation pages written.
+    pub conversation_count: u32,
+}
+
+/// Result of extracting the files a conversation wrote.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArtifactExport {
+    /// Directory the files were written into, or path of the ZIP archive.
+    pub path: String,
+    /// Relative paths of the files extracted.
+    pub files: Vec<String>,
+    /// Relative paths of files that were edited but couldn't be rebuilt.
+    pub skipped: Vec<String>,
+}
+
+/// A user-editable export template.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportTemplate {
+    /// Name the template is chosen by: its file name without extensions.
+    pub name: String,
+    /// Extension of the files it renders, e.g. `md`.
+    pub extension: String,
+    /// Path of the template file, for editing.
+    pub path: String,
+}
+
+/// Direction to move through a conversation list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ListDirection {
+    /// Toward the end of the list.
+    Next,
+    /// Toward the start of the list.
+    Previous,
+}
+
+/// A range of a conversation list, for virtual scrolling.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConversationWindow {
+    /// Index of the first conversation in the list.
+    pub start: u32,
+    /// The conversations from `start`, fewer than asked for at the end of
+    /// the list.
+    pub conversations: Vec<ConversationSummary>,
+    /// Number of conversations in the whole list.
+    pub total: u32,
 }
 
 /// A search result with matching conversation info.
@@ -143,6 +529,583 @@ pub struct SearchResult {
     pub match_count: i32,
     /// Search relevance rank (lower is better).
     pub rank: f64,
+    /// Found by the typo-tolerant trigram fallback rather than an exact
+    /// term match.
+    #[serde(default)]
+    pub approximate: bool,
+    /// What the rank was computed from, for debugging. `None` for
+    /// approximate results.
+    #[serde(default)]
+    pub components: Option<RankComponents>,
+    /// The best matching user messages in the conversation, best first.
+    #[serde(default)]
+    pub hits: Vec<MessageHit>,
+}
+
+/// A matching message nested under its conversation's search result.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MessageHit {
+    /// ID of the message, as in `Message::id`.
+    pub message_id: String,
+    /// Position of the message in its conversation.
+    pub ordinal: u32,
+    /// The message text around the match, with `<mark>` highlights.
+    pub snippet: String,
+}
+
+/// Components of a full-text search rank.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RankComponents {
+    /// FTS5 BM25 score (negative, lower is better).
+    pub bm25: f64,
+    /// Days since the conversation's last activity.
+    pub age_days: f64,
+    /// Recency factor: 1 for now, halving every half-life, 0 if disabled.
+    pub recency: f64,
+    /// BM25 boosted by recency, which results are ordered by (lower is better).
+    pub score: f64,
+}
+
+/// A user message matching a `search_questions` query.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QuestionMatch {
+    /// ID of the conversation the message is in.
+    pub conversation_id: String,
+    /// ID of the message, as in `Message::id`.
+    pub message_id: String,
+    /// Position of the message in its conversation.
+    pub ordinal: u32,
+    /// Project the conversation belongs to.
+    pub project_name: String,
+    /// Last activity in the conversation (ISO 8601).
+    pub last_time: String,
+    /// The message text around the match, with `<mark>` highlights.
+    pub snippet: String,
+    /// FTS5 BM25 score (lower is better).
+    pub rank: f64,
+}
+
+/// Number of search results with a facet value.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FacetCount {
+    pub value: String,
+    pub count: i64,
+}
+
+/// Search results broken down for filter chips.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchFacets {
+    /// Results per project, most first.
+    pub projects: Vec<FacetCount>,
+    /// Results per tag, most first.
+    pub tags: Vec<FacetCount>,
+    /// Results per `YYYY-MM` month of last activity, in the filter
+    /// timezone, newest first.
+    pub months: Vec<FacetCount>,
+}
+
+/// A term characteristic of a set of conversations, weighted for a word
+/// cloud.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TopTerm {
+    /// The term as indexed; a stem under the Porter tokenizer.
+    pub term: String,
+    /// tf-idf style weight; higher is more characteristic of the set.
+    pub weight: f64,
+    /// Occurrences in the set's conversations.
+    pub occurrences: i64,
+    /// Conversations of the set containing the term.
+    pub conversations: i64,
+}
+
+/// Conversations per filter value under the active filters, for the
+/// counts next to filter controls.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FilterCounts {
+    /// Conversations matching the filters.
+    pub total: i64,
+    /// Matching conversations per project, most first.
+    pub projects: Vec<FacetCount>,
+    /// Matching conversations per tag, most first.
+    pub tags: Vec<FacetCount>,
+    /// Matching conversations that are bookmarked.
+    pub bookmarked: i64,
+    /// Matching conversations that aren't bookmarked.
+    pub not_bookmarked: i64,
+}
+
+/// Search results with their facet counts.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchResponse {
+    pub results: Vec<SearchResult>,
+    pub facets: SearchFacets,
+}
+
+/// Kind of user-data change in the audit log.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum AuditAction {
+    TagAdded,
+    TagRemoved,
+    Bookmarked,
+    Unbookmarked,
+    Archived,
+    Deleted,
+    Protected,
+    Unprotected,
+    Exported,
+    MetadataSet,
+    MetadataRemoved,
+}
+
+impl AuditAction {
+    /// Returns the value stored in the audit log.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AuditAction::TagAdded => "tag_added",
+            AuditAction::TagRemoved => "tag_removed",
+            AuditAction::Bookmarked => "bookmarked",
+            AuditAction::Unbookmarked => "unbookmarked",
+            AuditAction::Archived => "archived",
+            AuditAction::Deleted => "deleted",
+            AuditAction::Protected => "protected",
+            AuditAction::Unprotected => "unprotected",
+            AuditAction::Exported => "exported",
+            AuditAction::MetadataSet => "metadata_set",
+            AuditAction::MetadataRemoved => "metadata_removed",
+        }
+    }
+
+    /// Parses a stored value.
+    pub fn from_db(value: &str) -> Option<Self> {
+        [
+            AuditAction::TagAdded,
+            AuditAction::TagRemoved,
+            AuditAction::Bookmarked,
+            AuditAction::Unbookmarked,
+            AuditAction::Archived,
+            AuditAction::Deleted,
+            AuditAction::Protected,
+            AuditAction::Unprotected,
+            AuditAction::Exported,
+            AuditAction::MetadataSet,
+            AuditAction::MetadataRemoved,
+        ]
+        .into_iter()
+        .find(|action| action.as_str() == value)
+    }
+}
+
+/// A recorded user-data change.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditEntry {
+    pub id: i64,
+    /// When the change was made (RFC 3339).
+    pub created_at: String,
+    /// OS user who made it.
+    pub actor: String,
+    pub action: AuditAction,
+    /// Affected conversation, if the change was about one.
+    pub conversation_id: Option<String>,
+    /// What changed, e.g. the tag or the export format.
+    pub details: String,
+}
+
+/// Ingest event a webhook can be notified of.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum WebhookEvent {
+    /// A conversation was ingested for the first time.
+    Added,
+    /// An ingested conversation's session file changed.
+    Updated,
+}
+
+impl WebhookEvent {
+    /// Returns the value stored in the database and sent in payloads.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            WebhookEvent::Added => "conversation.added",
+            WebhookEvent::Updated => "conversation.updated",
+        }
+    }
+
+    /// Parses a stored value.
+    pub fn from_db(value: &str) -> Option<Self> {
+        [WebhookEvent::Added, WebhookEvent::Updated]
+            .into_iter()
+            .find(|event| event.as_str() == value)
+    }
+}
+
+/// A URL that is sent a JSON payload when conversations matching its
+/// filters are ingested.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Webhook {
+    pub id: i64,
+    pub url: String,
+    /// Which conversations to send; empty filters match all.
+    pub filters: ConversationFilters,
+    pub events: Vec<WebhookEvent>,
+    /// Disabled webhooks keep their settings and log but aren't sent.
+    pub enabled: bool,
+    /// When the webhook was created (RFC 3339).
+    pub created_at: String,
+}
+
+/// A webhook to create, or the new settings of an existing one.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WebhookInput {
+    /// Webhook to update; a new one is created without it.
+    #[serde(default)]
+    pub id: Option<i64>,
+    pub url: String,
+    #[serde(default)]
+    pub filters: ConversationFilters,
+    pub events: Vec<WebhookEvent>,
+    #[serde(default = "default_webhook_enabled")]
+    pub enabled: bool,
+}
+
+/// State of a webhook delivery.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DeliveryStatus {
+    /// Waiting for its first attempt or a retry.
+    Pending,
+    /// Accepted with a 2xx response.
+    Delivered,
+    /// Given up on after the last retry.
+    Failed,
+}
+
+impl DeliveryStatus {
+    /// Returns the value stored in the database.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            DeliveryStatus::Pending => "pending",
+            DeliveryStatus::Delivered => "delivered",
+            DeliveryStatus::Failed => "failed",
+        }
+    }
+
+    /// Parses a stored value.
+    pub fn from_db(value: &str) -> Option<Self> {
+        [
+            DeliveryStatus::Pending,
+            DeliveryStatus::Delivered,
+            DeliveryStatus::Failed,
+        ]
+        .into_iter()
+        .find(|status| status.as_str() == value)
+    }
+}
+
+/// A payload sent, or to be sent, to a webhook.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WebhookDelivery {
+    pub id: i64,
+    pub webhook_id: i64,
+    pub conversation_id: String,
+    pub event: WebhookEvent,
+    pub status: DeliveryStatus,
+    /// Attempts made so far.
+    pub attempts: u32,
+    /// HTTP status of the last response, if there was one.
+    pub response_status: Option<u16>,
+    /// Why the last attempt failed.
+    pub last_error: Option<String>,
+    /// When the delivery was queued (RFC 3339).
+    pub created_at: String,
+    /// When it was accepted, if it was.
+    pub delivered_at: Option<String>,
+}
+
+/// A reusable prompt, typically saved from a message in history.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Prompt {
+    pub id: i64,
+    pub title: String,
+    pub body: String,
+    /// Names of the placeholders in the body, such as `file` for `[file]`
+    /// or `{{file}}`, in order of first use.
+    pub variables: Vec<String>,
+    /// Conversation the prompt was saved from, if any.
+    pub source_conversation_id: Option<String>,
+    /// Message the prompt was saved from, if any.
+    pub source_message_id: Option<String>,
+    /// When the prompt was saved (RFC 3339).
+    pub created_at: String,
+    /// When it was last changed (RFC 3339).
+    pub updated_at: String,
+}
+
+/// A prompt to create or update.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PromptInput {
+    /// Prompt to update; a new one is created without it.
+    #[serde(default)]
+    pub id: Option<i64>,
+    pub title: String,
+    pub body: String,
+    #[serde(default)]
+    pub source_conversation_id: Option<String>,
+    #[serde(default)]
+    pub source_message_id: Option<String>,
+}
+
+/// Format of an exported prompt library.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PromptExportFormat {
+    /// One section per prompt, with the body in a fenced block.
+    #[default]
+    Markdown,
+    /// An array of prompts.
+    Json,
+}
+
+/// A named context to switch to: the conversation list's filters and
+/// order, pinned conversations, and the tabs that were open.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Workspace {
+    /// Unique name, compared case-insensitively.
+    pub name: String,
+    pub filters: ConversationFilters,
+    pub sort: ConversationSort,
+    /// IDs of the pinned conversations, in the order they're shown.
+    pub pinned: Vec<String>,
+    /// IDs of the conversations open in tabs, in tab order.
+    pub open_tabs: Vec<String>,
+    /// Conversation of the selected tab, if any.
+    pub active_tab: Option<String>,
+    /// When the workspace was first saved (RFC 3339).
+    pub created_at: String,
+    /// When it was last saved (RFC 3339).
+    pub updated_at: String,
+    /// When it was last loaded (RFC 3339), if ever.
+    pub last_opened_at: Option<String>,
+}
+
+/// A workspace to save, replacing any with the same name.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkspaceInput {
+    pub name: String,
+    #[serde(default)]
+    pub filters: ConversationFilters,
+    #[serde(default)]
+    pub sort: ConversationSort,
+    #[serde(default)]
+    pub pinned: Vec<String>,
+    #[serde(default)]
+    pub open_tabs: Vec<String>,
+    #[serde(default)]
+    pub active_tab: Option<String>,
+}
+
+/// A follow-up reminder on a conversation; each conversation has at most
+/// one.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Reminder {
+    pub conversation_id: String,
+    /// Project of the conversation, to say what's due.
+    pub project_name: String,
+    /// Preview of the conversation's first question.
+    pub preview: String,
+    /// When the reminder is due (RFC 3339).
+    pub due_at: String,
+    /// What to follow up on, if the user said.
+    pub note: Option<String>,
+    /// When the reminder was set (RFC 3339).
+    pub created_at: String,
+    /// When `reminder-due` was emitted for it (RFC 3339), if it has been.
+    pub notified_at: Option<String>,
+}
+
+/// What a tag suggestion is based on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TagSuggestionSource {
+    /// Languages of the code blocks Claude wrote.
+    Language,
+    /// Frameworks and tools named in the dialogue or implied by its files.
+    Framework,
+    /// Extensions of the files the conversation read or changed.
+    FileExtension,
+    /// Tags of conversations of the same project or languages.
+    SimilarConversations,
+}
+
+/// A tag proposed for a conversation, accepted with `set_tags`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TagSuggestion {
+    pub tag: String,
+    /// How sure the suggestion is, from 0 to 1.
+    pub confidence: f64,
+    /// What it's based on, strongest first.
+    pub sources: Vec<TagSuggestionSource>,
+}
+
+/// Group of a command palette action.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ActionCategory {
+    /// Acts on one conversation: bookmark, tag, remind, ...
+    Conversation,
+    /// Writes conversations, prompts, or usage to files or text.
+    Export,
+    /// Shares a conversation outside the app.
+    Share,
+    /// Acts on many conversations at once, or undoes and redoes.
+    Library,
+    /// Rescans, imports, and rebuilds indexes.
+    Maintenance,
+}
+
+/// What an action's argument holds, so the palette knows how to ask for it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ActionArgKind {
+    /// A conversation ID; the open conversation, if there is one.
+    Conversation,
+    /// Conversation IDs; the selected conversations.
+    Conversations,
+    /// A message ID of the conversation.
+    Message,
+    /// The label of the window running the action.
+    Window,
+    Text,
+    /// A list of tags.
+    Tags,
+    /// A file or directory path.
+    Path,
+    Boolean,
+    Integer,
+    /// A date and time (RFC 3339, or `YYYY-MM-DD`).
+    DateTime,
+    /// Conversation filters; the list's current ones.
+    Filters,
+    /// One of `options`.
+    Choice,
+    /// A JSON value of the command's argument type.
+    Json,
+}
+
+/// An argument of a command palette action.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActionArg {
+    /// Name as the frontend passes it to the command.
+    pub name: String,
+    pub kind: ActionArgKind,
+    pub required: bool,
+    pub description: String,
+    /// Values a `choice` argument takes.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub options: Vec<String>,
+}
+
+/// A backend capability the command palette can run with `run_action`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActionInfo {
+    /// Action ID: the name of the command it runs.
+    pub id: String,
+    pub title: String,
+    pub category: ActionCategory,
+    pub description: String,
+    pub args: Vec<ActionArg>,
+    /// Whether it archives or deletes conversations, so the palette can
+    /// confirm first.
+    pub destructive: bool,
+}
+
+/// Outcome of a bulk archive or delete.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BulkOperationResult {
+    /// Conversations affected, or that would be in a dry run.
+    pub affected: usize,
+    /// Matching conversations left alone because they're protected.
+    #[serde(default)]
+    pub protected: usize,
+    /// Whether nothing was changed.
+    pub dry_run: bool,
+    /// For a bulk delete dry run, the token the real run must pass.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub confirm_token: Option<String>,
+}
+
+/// An undoable annotation of a conversation.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "value", rename_all = "camelCase")]
+pub enum AnnotationState {
+    /// The conversation's tags, sorted.
+    Tags(Vec<String>),
+    /// Whether the conversation is bookmarked.
+    Bookmarked(bool),
+}
+
+/// A user-data change reapplied by undo or redo.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AppliedOperation {
+    pub conversation_id: String,
+    /// The annotation as it is now.
+    pub state: AnnotationState,
+}
+
+/// A search term and the terms queries for it are expanded to.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SynonymEntry {
+    pub term: String,
+    pub synonyms: Vec<String>,
+}
+
+/// What a quick-open result refers to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum QuickOpenKind {
+    Conversation,
+    Project,
+    Tag,
+}
+
+/// A fuzzy-matched item for the quick-switcher.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QuickOpenResult {
+    pub kind: QuickOpenKind,
+    /// Conversation ID, project name, or tag.
+    pub value: String,
+    /// Text that was matched: the conversation preview, project name, or tag.
+    pub label: String,
+    /// Secondary text: the project of a conversation, or the number of
+    /// conversations in a project or with a tag.
+    pub detail: String,
+    /// Match score (higher is better).
+    pub score: i64,
+    /// Matched positions in `label` (UTF-16 code units), empty for typo matches.
+    pub positions: Vec<usize>,
 }
 
 /// Project information for the project filter.
@@ -159,6 +1122,777 @@ pub struct ProjectInfo {
     pub last_activity: String,
 }
 
+/// Server-side filtering options for a conversation's messages.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct MessageFilterOptions {
+    /// Only include messages with these roles (all roles when unset or empty).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub roles: Option<Vec<MessageRole>>,
+    /// Keep only text and code blocks (drops tool calls and tool results).
+    #[serde(default)]
+    pub text_only: bool,
+    /// Drop tool result blocks.
+    #[serde(default)]
+    pub hide_tool_results: bool,
+    /// Clean mode: drop tool activity and merge consecutive assistant turns.
+    #[serde(default)]
+    pub clean: bool,
+    /// Include meta messages (command output, hook output, progress).
+    #[serde(default)]
+    pub show_meta: bool,
+}
+
+/// Output format for conversation exports.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum ExportFormat {
+    /// Full Markdown including tool calls and results.
+    #[default]
+    Markdown,
+    /// Clean-mode Markdown: the dialogue only, for sharing with non-developers.
+    Transcript,
+    /// Standalone, print-friendly HTML.
+    Html,
+    /// JSON in the versioned export schema, for other tools.
+    Json,
+}
+
+impl ExportFormat {
+    /// Returns the serialized name.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ExportFormat::Markdown => "markdown",
+            ExportFormat::Transcript => "transcript",
+            ExportFormat::Html => "html",
+            ExportFormat::Json => "json",
+        }
+    }
+}
+
+/// Call and error counts for one tool.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolStats {
+    pub tool_name: String,
+    pub calls: i64,
+    pub errors: i64,
+}
+
+/// Aggregate usage metrics for one project.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectMetrics {
+    pub project_name: String,
+    pub conversations: i64,
+    pub messages: i64,
+    pub input_tokens: i64,
+    pub output_tokens: i64,
+    pub cache_creation_tokens: i64,
+    pub cache_read_tokens: i64,
+    /// Estimated cost in USD at list prices.
+    pub estimated_cost_usd: f64,
+    /// Tokens from models without known pricing (not included in the cost).
+    pub unpriced_tokens: i64,
+    pub tool_calls: i64,
+    pub tool_errors: i64,
+    /// Fraction of tool calls that returned an error (0 when there were none).
+    pub error_rate: f64,
+    /// Calls per tool, most used first.
+    pub tool_mix: Vec<ToolStats>,
+}
+
+/// Side-by-side usage metrics for two projects.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectComparison {
+    pub project_a: ProjectMetrics,
+    pub project_b: ProjectMetrics,
+}
+
+/// What made a usage anomaly stand out.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum AnomalyKind {
+    /// Far more tokens than usual.
+    TokenSpike,
+    /// Far more failed tool calls than usual.
+    ErrorBurst,
+}
+
+/// A day or conversation whose usage is unusual compared to its peers.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageAnomaly {
+    pub kind: AnomalyKind,
+    /// UTC day (`YYYY-MM-DD`); a conversation's last active day.
+    pub day: String,
+    /// Set for conversation anomalies; unset when the whole day is unusual.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub conversation_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub project_name: Option<String>,
+    /// Observed tokens or tool errors.
+    pub value: i64,
+    /// Median over the lookback window.
+    pub baseline: f64,
+    /// Robust z-score; higher is more unusual.
+    pub score: f64,
+}
+
+/// Options for usage anomaly detection.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct AnomalyOptions {
+    /// Number of trailing days to analyze (default: 30).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lookback_days: Option<u32>,
+    /// Robust z-score above which a value is flagged (default: 3.5).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub threshold: Option<f64>,
+}
+
+/// Length of the periods statistics are grouped by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum StatsPeriod {
+    /// UTC days (`YYYY-MM-DD`).
+    Day,
+    /// Weeks starting on Monday, named by that Monday (`YYYY-MM-DD`).
+    Week,
+    /// Calendar months (`YYYY-MM`).
+    Month,
+}
+
+/// Options for `get_language_stats` and `get_model_stats`.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageStatsOptions {
+    /// Only this project's conversations.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub project: Option<String>,
+    /// First UTC day counted (`YYYY-MM-DD`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_date: Option<String>,
+    /// Last UTC day counted (`YYYY-MM-DD`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end_date: Option<String>,
+    /// Group the statistics by period (default: one total for the range).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub period: Option<StatsPeriod>,
+}
+
+/// Code blocks Claude wrote in one language.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct LanguageStats {
+    /// The period counted, when grouped by period.
+    pub period: Option<String>,
+    /// Normalized language name, e.g. `typescript` for `ts` fences.
+    pub language: String,
+    /// Fenced code blocks in assistant messages.
+    pub blocks: i64,
+    /// Lines of those blocks.
+    pub lines: i64,
+    /// Conversations they're in.
+    pub conversations: i64,
+}
+
+/// How one model answered over a period.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelStats {
+    /// The period counted, when grouped by period.
+    pub period: Option<String>,
+    /// Model ID, or empty when the session files don't record one.
+    pub model: String,
+    /// Answers (API responses) from the model.
+    pub answers: i64,
+    /// Fraction of the period's answers from the model, 0 to 1.
+    pub share: f64,
+    pub input_tokens: i64,
+    pub output_tokens: i64,
+    /// Output tokens per answer.
+    pub average_output_tokens: f64,
+    /// Mean time from the prompt or tool result to the answer, if any
+    /// answer could be timed.
+    pub average_latency_millis: Option<f64>,
+    /// Conversations the model answered in.
+    pub conversations: i64,
+}
+
+/// Options for `run_benchmark`.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct BenchmarkOptions {
+    /// Most session files parsed and ingested (default: 200).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_files: Option<u32>,
+    /// Times each search query is repeated (default: 5).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub search_iterations: Option<u32>,
+}
+
+/// Latency percentiles in milliseconds.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct LatencyStats {
+    pub samples: usize,
+    pub p50_ms: f64,
+    pub p95_ms: f64,
+    pub p99_ms: f64,
+    pub max_ms: f64,
+}
+
+/// Outcome of an ingest run.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct IngestStats {
+    /// Session files parsed and written.
+    pub processed_files: usize,
+    /// Session files that failed and will be retried by the next scan.
+    pub failed_files: usize,
+    pub new_conversations: usize,
+    pub updated_conversations: usize,
+    /// Sessions also found in another file, resolved by the session
+    /// conflict policy.
+    #[serde(default)]
+    pub conflicts: usize,
+    /// IDs of the new conversations, for `conversation-added` events.
+    #[serde(skip)]
+    pub added_ids: Vec<String>,
+    /// IDs of the conversations whose messages changed, for
+    /// `conversation-updated` events.
+    #[serde(skip)]
+    pub updated_ids: Vec<String>,
+}
+
+/// Progress of an ingest run, reported after each batch.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IngestProgress {
+    /// Session files needing ingest in this run.
+    pub total_files: usize,
+    #[serde(flatten)]
+    pub stats: IngestStats,
+}
+
+/// Outcome of importing files dropped on the window: the payload of the
+/// `import-result` event.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DropImportResult {
+    /// Session files copied into the imported directory.
+    pub copied_files: usize,
+    /// Dropped paths that weren't imported: not session files, or failed to copy.
+    pub rejected_paths: Vec<String>,
+    #[serde(flatten)]
+    pub stats: IngestStats,
+}
+
+/// Payload of the `conversation-added` and `conversation-updated` events.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ConversationsChanged {
+    pub conversation_ids: Vec<String>,
+    /// Whether the file watcher found the change (vs the initial scan).
+    pub from_watcher: bool,
+}
+
+/// Payload of the `open-conversation` event.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct OpenConversation {
+    pub conversation_id: String,
+}
+
+/// Payload of the `tags-changed` event.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TagsChanged {
+    pub conversation_id: String,
+    /// The conversation's tags after the change, sorted.
+    pub tags: Vec<String>,
+}
+
+/// State of the file watcher.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum WatcherState {
+    Running,
+    Stopped,
+    Failed,
+}
+
+/// Payload of the `watcher-status` event.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct WatcherStatus {
+    pub state: WatcherState,
+    /// Why the watcher failed, if it did.
+    pub message: Option<String>,
+}
+
+/// Database figures of the health report.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DatabaseHealth {
+    /// Size of the database file, excluding the write-ahead log.
+    pub size_bytes: u64,
+    /// Session files scheduled or being ingested, including ones whose
+    /// ingest failed and will be retried.
+    pub pending_files: usize,
+    pub conversations: usize,
+    /// Rows of the full-text index; differs from `conversations` when the
+    /// index is out of step with the conversations it indexes.
+    pub indexed_conversations: usize,
+}
+
+/// Summary of the app's state for the diagnostics panel.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct HealthReport {
+    /// State of the file watcher; `None` if it was never started (demo mode).
+    pub watcher: Option<WatcherState>,
+    /// When the last full scan of the session files finished (RFC 3339).
+    pub last_scan_at: Option<String>,
+    /// Session files that failed to parse since launch.
+    pub parse_errors: u64,
+    pub database: DatabaseHealth,
+    /// Whether the full-text index and the conversations disagree, which
+    /// `maintain_search_index` repairs.
+    pub index_mismatch: bool,
+    /// Directories and session files the last scan couldn't read, whose
+    /// conversations are missing.
+    pub access_errors: Vec<AccessError>,
+}
+
+/// A directory or session file the last scan couldn't read.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct AccessError {
+    pub path: String,
+    pub error: String,
+    /// Scans that failed to read it in a row.
+    pub attempts: u32,
+    /// When it was first found unreadable (RFC 3339).
+    pub first_failed_at: String,
+    /// When it was last found unreadable (RFC 3339).
+    pub last_failed_at: String,
+    /// When it will be retried (RFC 3339).
+    pub next_retry_at: String,
+}
+
+/// What kind of unknown structure a schema observation is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SchemaObservationKind {
+    /// A top-level field of lines.
+    Field,
+    /// A content block type.
+    BlockType,
+    /// A line `type`; such lines are skipped.
+    LineType,
+}
+
+impl SchemaObservationKind {
+    /// Returns the value stored in `schema_observations.kind`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SchemaObservationKind::Field => "field",
+            SchemaObservationKind::BlockType => "block_type",
+            SchemaObservationKind::LineType => "line_type",
+        }
+    }
+
+    /// Parses a stored value, falling back to `Field` for unknown ones.
+    pub fn from_db(value: &str) -> Self {
+        match value {
+            "block_type" => SchemaObservationKind::BlockType,
+            "line_type" => SchemaObservationKind::LineType,
+            _ => SchemaObservationKind::Field,
+        }
+    }
+}
+
+/// A structure seen in session files that the parsing profile doesn't
+/// know, and so is dropped or shown as plain text.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct SchemaObservation {
+    pub kind: SchemaObservationKind,
+    /// Name of the field, block type, or line type.
+    pub name: String,
+    /// Occurrences across ingests; a file ingested again counts again.
+    pub count: u64,
+    /// When it was first seen (RFC 3339).
+    pub first_seen_at: String,
+    /// When it was last seen (RFC 3339).
+    pub last_seen_at: String,
+    /// A session file it was last seen in.
+    pub example_file: String,
+}
+
+/// Results of a benchmark run on the current dataset.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct BenchmarkReport {
+    /// Session files parsed.
+    pub files: usize,
+    /// Total size of the parsed files.
+    pub bytes: u64,
+    /// Parse throughput in MB/s.
+    pub parse_mb_per_sec: f64,
+    /// Conversations parsed and written to a scratch database.
+    pub conversations: usize,
+    /// Ingest rate (parse, write, and index) in conversations/s.
+    pub ingest_conversations_per_sec: f64,
+    /// Distinct search queries run.
+    pub search_queries: usize,
+    pub search_latency: LatencyStats,
+    /// Wall-clock duration of the whole run.
+    pub duration_ms: f64,
+}
+
+/// Results of checking and optimizing the full-text indexes.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchMaintenanceReport {
+    pub indexes: Vec<FtsIndexReport>,
+    /// Wall-clock duration of the whole run.
+    pub duration_ms: f64,
+}
+
+/// Maintenance of one FTS5 table.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FtsIndexReport {
+    pub table: String,
+    /// What the integrity check found, if the index was damaged.
+    pub problem: Option<String>,
+    /// Whether the index was rebuilt from the stored text.
+    pub rebuilt: bool,
+    pub duration_ms: f64,
+}
+
+/// Aggregated durations of one timer.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimerMetrics {
+    /// Dotted metric name (e.g. `command.get_conversations`).
+    pub name: String,
+    pub count: u64,
+    pub total_ms: f64,
+    pub mean_ms: f64,
+    /// Slowest recorded duration this session.
+    pub max_ms: f64,
+    /// Median over the most recent samples.
+    pub p50_ms: f64,
+    /// 95th percentile over the most recent samples.
+    pub p95_ms: f64,
+}
+
+/// Snapshot of the local metrics registry.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AppMetrics {
+    /// Seconds since the registry was created (roughly, since launch).
+    pub uptime_secs: u64,
+    pub counters: std::collections::BTreeMap<String, u64>,
+    pub timers: Vec<TimerMetrics>,
+}
+
+/// An entry of the application log file.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct LogEntry {
+    /// When the entry was logged (RFC 3339).
+    pub timestamp: String,
+    /// `ERROR`, `WARN`, `INFO`, `DEBUG`, or `TRACE`.
+    pub level: String,
+    /// Module that logged the entry.
+    pub target: String,
+    pub message: String,
+}
+
+/// User-configurable application settings.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AppSettings {
+    /// Timezone for day-based date filters: `"local"`, `"UTC"`, or an IANA name.
+    #[serde(default = "default_timezone")]
+    pub timezone: String,
+    /// Emit a `usage-anomaly` event when today's usage looks unusual.
+    #[serde(default)]
+    pub anomaly_notifications: bool,
+    /// Custom database file or directory (absolute). Applies after a restart,
+    /// unless overridden by `CLAUDE_HISTORY_DB` or portable mode.
+    #[serde(default)]
+    pub database_path: Option<String>,
+    /// What conversation previews are built from. Applies to newly ingested
+    /// conversations; `regenerate_previews` updates existing ones.
+    #[serde(default)]
+    pub preview_source: PreviewSource,
+    /// Length of list previews in characters. Like `preview_source`, applies
+    /// to newly ingested conversations.
+    #[serde(default = "default_preview_length")]
+    pub preview_length: u32,
+    /// When a search finds few results, also look for near matches of
+    /// misspelled words.
+    #[serde(default = "default_typo_tolerant_search")]
+    pub typo_tolerant_search: bool,
+    /// How the full-text index splits text into terms. Changing it rebuilds
+    /// the index.
+    #[serde(default)]
+    pub search_tokenizer: SearchTokenizer,
+    /// Days after which a conversation's recency boost in search ranking
+    /// halves; 0 ranks by text relevance only.
+    #[serde(default = "default_recency_half_life_days")]
+    pub recency_half_life_days: u32,
+    /// What happens to ANSI escape sequences in tool results.
+    #[serde(default)]
+    pub ansi_mode: AnsiMode,
+    /// Slack bot token (`xoxb-...`) used by `share_to_slack`. Needs the
+    /// `chat:write` and `files:write` scopes.
+    #[serde(default)]
+    pub slack_token: Option<String>,
+    /// What happens when the same session is found in two files.
+    #[serde(default)]
+    pub session_conflict_policy: SessionConflictPolicy,
+    /// Locale dates are written in by exports and summaries, e.g. `de-DE`.
+    #[serde(default = "default_locale")]
+    pub locale: String,
+    /// Globs of session files scans and the watcher leave out, matched
+    /// against the full path (`*` also matches `/`), e.g. `*/archive/*`.
+    /// Conversations already indexed stay.
+    #[serde(default)]
+    pub ignore_patterns: Vec<String>,
+    /// Session files larger than this many megabytes are left out like
+    /// ignored ones; `None` for no limit.
+    #[serde(default)]
+    pub max_session_file_mb: Option<u32>,
+    /// Conversations of session files larger than this many megabytes are
+    /// stored partially: they open with shortened tool results until
+    /// `load_full` is called. `None` stores every file in full. Applies to
+    /// files ingested after the change.
+    #[serde(default = "default_partial_storage_mb")]
+    pub partial_storage_mb: Option<u32>,
+    /// Only read: commands that delete, archive, or rebuild history are
+    /// refused and nothing is written into the session directory. Also
+    /// turned on for a session by launching with `--read-only`.
+    #[serde(default)]
+    pub read_only: bool,
+    /// Write a Markdown stub per conversation for Spotlight to index, so
+    /// system-wide search finds conversations (macOS only).
+    #[serde(default)]
+    pub spotlight_stubs: bool,
+}
+
+impl Default for AppSettings {
+    fn default() -> Self {
+        Self {
+            timezone: default_timezone(),
+            anomaly_notifications: false,
+            database_path: None,
+            preview_source: PreviewSource::default(),
+            preview_length: default_preview_length(),
+            typo_tolerant_search: default_typo_tolerant_search(),
+            search_tokenizer: SearchTokenizer::default(),
+            recency_half_life_days: default_recency_half_life_days(),
+            ansi_mode: AnsiMode::default(),
+            slack_token: None,
+            session_conflict_policy: SessionConflictPolicy::default(),
+            locale: default_locale(),
+            ignore_patterns: Vec::new(),
+            max_session_file_mb: None,
+            partial_storage_mb: default_partial_storage_mb(),
+            read_only: false,
+            spotlight_stubs: false,
+        }
+    }
+}
+
+/// What `get_block_copy_text` copies from a content block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CopyMode {
+    /// The block's text as is, with code and file views unfenced.
+    #[default]
+    Text,
+    /// The input of a tool call (including Bash commands), as JSON.
+    Json,
+    /// An Edit, MultiEdit, or Write tool call, as a unified diff.
+    Patch,
+    /// The block as it appears in Markdown exports.
+    Markdown,
+}
+
+/// What happens to ANSI escape sequences (terminal colors) in tool results.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum AnsiMode {
+    /// Removed, leaving plain text.
+    #[default]
+    Strip,
+    /// Removed, with the colors and styles they set kept as the block's
+    /// styled spans.
+    Styled,
+}
+
+impl AnsiMode {
+    /// Returns the value stored in the settings table.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AnsiMode::Strip => "strip",
+            AnsiMode::Styled => "styled",
+        }
+    }
+
+    /// Parses a stored value, falling back to the default for unknown ones.
+    pub fn from_db(value: &str) -> Self {
+        match value {
+            "styled" => AnsiMode::Styled,
+            _ => AnsiMode::Strip,
+        }
+    }
+}
+
+/// What happens when a session found in one file is also found in another,
+/// e.g. a backup imported next to the original.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SessionConflictPolicy {
+    /// The copy with the most recent activity is the conversation; the
+    /// other file is ignored.
+    #[default]
+    PreferNewest,
+    /// Both are kept, the later one under its ID with a suffix derived from
+    /// its file.
+    KeepBoth,
+}
+
+impl SessionConflictPolicy {
+    /// Returns the value stored in the settings table.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SessionConflictPolicy::PreferNewest => "prefer_newest",
+            SessionConflictPolicy::KeepBoth => "keep_both",
+        }
+    }
+
+    /// Parses a stored value, falling back to the default for unknown ones.
+    pub fn from_db(value: &str) -> Self {
+        match value {
+            "keep_both" => SessionConflictPolicy::KeepBoth,
+            _ => SessionConflictPolicy::PreferNewest,
+        }
+    }
+}
+
+/// What conversation previews are built from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PreviewSource {
+    /// The first user message.
+    #[default]
+    FirstUserMessage,
+    /// The first substantive user message (skipping e.g. "continue") and
+    /// the assistant's answer to it.
+    SubstantivePair,
+}
+
+impl PreviewSource {
+    /// Returns the value stored in the settings table.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PreviewSource::FirstUserMessage => "first_user_message",
+            PreviewSource::SubstantivePair => "substantive_pair",
+        }
+    }
+
+    /// Parses a stored value, falling back to the default for unknown ones.
+    pub fn from_db(value: &str) -> Self {
+        match value {
+            "substantive_pair" => PreviewSource::SubstantivePair,
+            _ => PreviewSource::FirstUserMessage,
+        }
+    }
+}
+
+/// Tokenizer of the full-text search index.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SearchTokenizer {
+    /// Words separated by spaces and punctuation; single words match by
+    /// prefix. Text without spaces, like Chinese or Japanese, becomes one
+    /// long term and is only found from its start.
+    #[default]
+    Unicode61,
+    /// Overlapping three-character sequences, so any substring of three or
+    /// more characters matches, in any script. Larger index.
+    Trigram,
+    /// Like `Unicode61`, but words are reduced to their English stem, so
+    /// "tests" and "testing" also find "test".
+    Porter,
+}
+
+impl SearchTokenizer {
+    /// Returns the value stored in the settings table.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SearchTokenizer::Unicode61 => "unicode61",
+            SearchTokenizer::Trigram => "trigram",
+            SearchTokenizer::Porter => "porter",
+        }
+    }
+
+    /// Returns the FTS5 `tokenize` option.
+    pub fn fts5_option(self) -> &'static str {
+        match self {
+            SearchTokenizer::Unicode61 => "unicode61",
+            SearchTokenizer::Trigram => "trigram",
+            SearchTokenizer::Porter => "porter unicode61",
+        }
+    }
+
+    /// Parses a stored value, falling back to the default for unknown ones.
+    pub fn from_db(value: &str) -> Self {
+        match value {
+            "trigram" => SearchTokenizer::Trigram,
+            "porter" => SearchTokenizer::Porter,
+            _ => SearchTokenizer::Unicode61,
+        }
+    }
+}
+
+fn default_timezone() -> String {
+    "local".to_string()
+}
+
+fn default_locale() -> String {
+    crate::locale::DEFAULT_LOCALE.to_string()
+}
+
+fn default_partial_storage_mb() -> Option<u32> {
+    Some(50)
+}
+
+fn default_preview_length() -> u32 {
+    200
+}
+
+fn default_typo_tolerant_search() -> bool {
+    true
+}
+
+fn default_recency_half_life_days() -> u32 {
+    90
+}
+
+fn default_webhook_enabled() -> bool {
+    true
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -170,6 +1904,9 @@ mod tests {
             content: "fn main() {}".to_string(),
             language: Some("rust".to_string()),
             tool_name: None,
+            spans: None,
+            terminal: None,
+            file_view: None,
         };
 
         let json = serde_json::to_string(&block).unwrap();
@@ -200,14 +1937,18 @@ mod tests {
             start_time: "2025-01-01T00:00:00Z".to_string(),
             last_time: "2025-01-01T01:00:00Z".to_string(),
             preview: "How do I...".to_string(),
+            preview_medium: "How do I...\n\nwith details".to_string(),
             message_count: 10,
             bookmarked: true,
+            resolution: Resolution::Resolved,
         };
 
         let json = serde_json::to_string(&summary).unwrap();
         assert!(json.contains("\"projectName\":\"my-project\""));
         assert!(json.contains("\"messageCount\":10"));
         assert!(json.contains("\"bookmarked\":true"));
+        assert!(json.contains("\"resolution\":\"resolved\""));
+        assert!(json.contains("\"previewMedium\":\"How do I...\\n\\nwith details\""));
 
         let deserialized: ConversationSummary = serde_json::from_str(&json).unwrap();
         assert_eq!(deserialized.id, "abc123");
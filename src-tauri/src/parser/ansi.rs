@@ -0,0 +1,270 @@
+//! ANSI escape sequences in terminal output.
+//!
+//! Bash tool results keep the escape sequences of colored output, which
+//! render as garbage outside a terminal. This module either strips them or
+//! turns SGR (color and style) sequences into styled spans of the text.
+//! Other sequences, such as cursor movement and window titles, are dropped.
+
+use crate::models::StyledSpan;
+
+const ESC: char = '\x1b';
+
+/// Names of the 16 standard colors, by SGR color index.
+const COLOR_NAMES: [&str; 16] = [
+    "black",
+    "red",
+    "green",
+    "yellow",
+    "blue",
+    "magenta",
+    "cyan",
+    "white",
+    "bright_black",
+    "bright_red",
+    "bright_green",
+    "bright_yellow",
+    "bright_blue",
+    "bright_magenta",
+    "bright_cyan",
+    "bright_white",
+];
+
+/// Piece of text between escape sequences, or an SGR sequence's parameters.
+enum Token<'a> {
+    Text(&'a str),
+    Sgr(&'a str),
+}
+
+/// Whether the text contains escape sequences.
+pub fn has_escapes(text: &str) -> bool {
+    text.contains(ESC)
+}
+
+/// Removes all escape sequences.
+pub fn strip(text: &str) -> String {
+    if !has_escapes(text) {
+        return text.to_string();
+    }
+    tokens(text)
+        .into_iter()
+        .filter_map(|token| match token {
+            Token::Text(text) => Some(text),
+            Token::Sgr(_) => None,
+        })
+        .collect()
+}
+
+/// Removes all escape sequences, returning the plain text and the runs of
+/// it that share a style, in order. Joined, the spans' texts are the plain text.
+pub fn styled(text: &str) -> (String, Vec<StyledSpan>) {
+    let mut plain = String::new();
+    let mut spans: Vec<StyledSpan> = Vec::new();
+    let mut style = Style::default();
+
+    for token in tokens(text) {
+        match token {
+            Token::Sgr(params) => style.apply(params),
+            Token::Text(text) => {
+                plain.push_str(text);
+                match spans.last_mut() {
+                    Some(last) if style.matches(last) => last.text.push_str(text),
+                    _ => spans.push(style.span(text)),
+                }
+            }
+        }
+    }
+    (plain, spans)
+}
+
+/// Splits text at escape sequences, keeping SGR parameters.
+fn tokens(text: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    let mut rest = text;
+
+    while let Some(start) = rest.find(ESC) {
+        if start > 0 {
+            tokens.push(Token::Text(&rest[..start]));
+        }
+        let sequence = &rest[start + 1..];
+        let mut chars = sequence.char_indices();
+        let consumed = match chars.next() {
+            // CSI: parameters, intermediates, then a final byte
+            Some((_, '[')) => {
+                match chars.find(|(_, c)| ('\x40'..='\x7e').contains(c)) {
+                    Some((i, 'm')) => {
+                        tokens.push(Token::Sgr(&sequence[1..i]));
+                        i + 1
+                    }
+                    Some((i, _)) => i + 1,
+                    None => sequence.len(),
+                }
+            }
+            // OSC: ends at BEL or ESC \
+            Some((_, ']')) => match sequence.find(['\x07', ESC]) {
+                Some(i) if sequence[i..].starts_with('\x07') => i + 1,
+                Some(i) => (i + 2).min(sequence.len()),
+                None => sequence.len(),
+            },
+            // Other escapes are one character, after any intermediates
+            Some((_, c)) if (' '..='/').contains(&c) => {
+                chars.next().map_or(sequence.len(), |(i, c)| i + c.len_utf8())
+            }
+            Some((_, c)) => c.len_utf8(),
+            None => 0,
+        };
+        rest = &sequence[consumed..];
+    }
+    if !rest.is_empty() {
+        tokens.push(Token::Text(rest));
+    }
+    tokens
+}
+
+/// Current SGR style.
+#[derive(Default)]
+struct Style {
+    fg: Option<String>,
+    bg: Option<String>,
+    bold: bool,
+    dim: bool,
+    italic: bool,
+    underline: bool,
+}
+
+impl Style {
+    /// Applies SGR parameters, like `1;31`.
+    fn apply(&mut self, params: &str) {
+        let codes: Vec<u16> = params
+            .split([';', ':'])
+            .map(|code| code.parse().unwrap_or(0))
+            .collect();
+        let mut i = 0;
+        while i < codes.len() {
+            match codes[i] {
+                0 => *self = Style::default(),
+                1 => self.bold = true,
+                2 => self.dim = true,
+                3 => self.italic = true,
+                4 => self.underline = true,
+                22 => {
+                    self.bold = false;
+                    self.dim = false;
+                }
+                23 => self.italic = false,
+                24 => self.underline = false,
+                code @ 30..=37 => self.fg = Some(COLOR_NAMES[(code - 30) as usize].to_string()),
+                code @ 90..=97 => {
+                    self.fg = Some(COLOR_NAMES[(code - 90 + 8) as usize].to_string())
+                }
+                39 => self.fg = None,
+                code @ 40..=47 => self.bg = Some(COLOR_NAMES[(code - 40) as usize].to_string()),
+                code @ 100..=107 => {
+                    self.bg = Some(COLOR_NAMES[(code - 100 + 8) as usize].to_string())
+                }
+                49 => self.bg = None,
+                code @ (38 | 48) => {
+                    let (color, used) = extended_color(&codes[i + 1..]);
+                    if code == 38 {
+                        self.fg = color;
+                    } else {
+                        self.bg = color;
+                    }
+                    i += used;
+                }
+                _ => {}
+            }
+            i += 1;
+        }
+    }
+
+    fn matches(&self, span: &StyledSpan) -> bool {
+        self.fg == span.fg
+            && self.bg == span.bg
+            && self.bold == span.bold
+            && self.dim == span.dim
+            && self.italic == span.italic
+            && self.underline == span.underline
+    }
+
+    fn span(&self, text: &str) -> StyledSpan {
+        StyledSpan {
+            text: text.to_string(),
+            fg: self.fg.clone(),
+            bg: self.bg.clone(),
+            bold: self.bold,
+            dim: self.dim,
+            italic: self.italic,
+            underline: self.underline,
+        }
+    }
+}
+
+/// Reads the color after a 38 or 48 code: `5;n` from the 256-color palette
+/// or `2;r;g;b`. Returns it and the number of codes read.
+fn extended_color(codes: &[u16]) -> (Option<String>, usize) {
+    match codes {
+        [5, n, ..] => (Some(palette_color(*n)), 2),
+        [2, r, g, b, ..] => (Some(hex(*r, *g, *b)), 4),
+        [] => (None, 0),
+        _ => (None, codes.len()),
+    }
+}
+
+/// Names the 16 standard colors; gives the rest of the 256-color palette
+/// as `#rrggbb`.
+fn palette_color(n: u16) -> String {
+    match n {
+        0..=15 => COLOR_NAMES[n as usize].to_string(),
+        16..=231 => {
+            let level = |v: u16| if v == 0 { 0 } else { 55 + v * 40 };
+            let n = n - 16;
+            hex(level(n / 36), level(n / 6 % 6), level(n % 6))
+        }
+        _ => {
+            let gray = 8 + (n.min(255) - 232) * 10;
+            hex(gray, gray, gray)
+        }
+    }
+}
+
+fn hex(r: u16, g: u16, b: u16) -> String {
+    format!("#{:02x}{:02x}{:02x}", r.min(255), g.min(255), b.min(255))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strip_removes_all_sequences() {
+        let text = "\x1b[1;32mPASS\x1b[0m src/lib.rs\x1b[2K\r\n\x1b]0;title\x07done\x1b(B";
+        assert_eq!(strip(text), "PASS src/lib.rs\r\ndone");
+        assert_eq!(strip("plain"), "plain");
+        // Sequences cut off at the end are dropped
+        assert_eq!(strip("ok\x1b[31"), "ok");
+    }
+
+    #[test]
+    fn test_styled_spans() {
+        let (plain, spans) = styled("\x1b[1;31merror\x1b[0m: \x1b[38;5;208mwarn\x1b[39m\x1b[4m!");
+        assert_eq!(plain, "error: warn!");
+        assert_eq!(spans.len(), 4);
+        assert_eq!(spans[0].text, "error");
+        assert_eq!(spans[0].fg.as_deref(), Some("red"));
+        assert!(spans[0].bold);
+        assert_eq!(spans[1].text, ": ");
+        assert_eq!(spans[1].fg, None);
+        assert!(!spans[1].bold);
+        assert_eq!(spans[2].fg.as_deref(), Some("#ff8700"));
+        assert!(spans[3].underline);
+        assert_eq!(spans[3].fg, None);
+    }
+
+    #[test]
+    fn test_styled_merges_runs_with_the_same_style() {
+        let (_, spans) = styled("\x1b[32ma\x1b[32mb\x1b[48;2;1;2;3mc");
+        assert_eq!(spans.len(), 2);
+        assert_eq!(spans[0].text, "ab");
+        assert_eq!(spans[1].bg.as_deref(), Some("#010203"));
+    }
+}
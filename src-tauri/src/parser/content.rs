@@ -2,13 +2,37 @@
 //!
 //! This module handles parsing raw message content into structured ContentBlocks.
 //! It extracts code blocks from markdown fences, handles tool_use/tool_result blocks,
-//! and preserves the order of all content.
-
-use crate::models::{ContentBlock, ContentBlockType};
-use crate::parser::jsonl::{RawContent, RawContentBlock};
+//! and preserves the order of all content. Base64 images in tool results are
+//! moved out into attachments, leaving an image block that references them,
+//! ANSI escape sequences in tool results are stripped or turned into styled
+//! spans, and numbered file contents returned by the Read tool become file
+//! view blocks. Conversations stored partially keep only the start of each
+//! tool result.
+
+use crate::models::{
+    AnsiMode, Attachment, ContentBlock, ContentBlockType, FileView, TerminalTranscript,
+};
+use crate::parser::ansi;
+use crate::parser::jsonl::{RawContent, RawContentBlock, RawMessage};
 use regex::Regex;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::Path;
 use std::sync::LazyLock;
 
+/// Name of the tool that runs shell commands.
+pub const BASH_TOOL_NAME: &str = "Bash";
+
+/// Name of the tool that reads files.
+pub const READ_TOOL_NAME: &str = "Read";
+
+/// Regex for a line of a Read result: right-aligned line number, then `→`
+/// (or a tab, in older versions), then the line.
+static NUMBERED_LINE_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^\s*(\d+)(?:→|\t)(.*)$").expect("Invalid regex pattern")
+});
+
 /// Regex for matching markdown code fences.
 /// Matches: ```language\ncode\n``` or ```\ncode\n```
 static CODE_FENCE_REGEX: LazyLock<Regex> = LazyLock::new(|| {
@@ -35,9 +59,84 @@ static CODE_FENCE_REGEX: LazyLock<Regex> = LazyLock::new(|| {
 /// assert_eq!(blocks.len(), 2); // text block + code block
 /// ```
 pub fn parse_content_blocks(raw_content: &RawContent) -> Vec<ContentBlock> {
+    parse_content(raw_content, &mut ContentContext::default())
+}
+
+/// Characters of each tool result kept for conversations stored partially.
+pub const PARTIAL_TOOL_RESULT_CHARS: usize = 2_000;
+
+/// Options for parsing content blocks.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ContentOptions {
+    /// What happens to ANSI escape sequences in tool results.
+    pub ansi: AnsiMode,
+    /// Characters tool results are cut to, leaving out their images; `None`
+    /// keeps them whole.
+    pub tool_result_limit: Option<usize>,
+}
+
+/// Cuts a tool result to its first `limit` characters, noting how many were
+/// left out.
+pub fn shorten_tool_result(text: String, limit: usize) -> String {
+    match text.char_indices().nth(limit) {
+        Some((end, _)) => {
+            let omitted = text[end..].chars().count();
+            format!(
+                "{}\n… [{} more characters; load the full conversation to see them]",
+                &text[..end],
+                omitted
+            )
+        }
+        None => text,
+    }
+}
+
+/// What parsing the messages of a conversation shares between them.
+#[derive(Default)]
+pub struct ContentContext<'a> {
+    pub options: ContentOptions,
+    /// Tool calls by ID, so their results can be read by tool.
+    calls: HashMap<&'a str, &'a RawContentBlock>,
+    /// Images extracted from tool results so far.
+    pub attachments: Vec<Attachment>,
+}
+
+impl<'a> ContentContext<'a> {
+    /// Creates a context for parsing `messages` with `options`.
+    pub fn new(options: ContentOptions, messages: &'a [RawMessage]) -> Self {
+        let mut calls = HashMap::new();
+        for message in messages {
+            if let RawContent::Blocks(blocks) = &message.message.content {
+                for block in blocks.iter().filter(|b| b.block_type == "tool_use") {
+                    if let Some(id) = block.tool_use_id.as_deref() {
+                        calls.insert(id, block);
+                    }
+                }
+            }
+        }
+        Self {
+            options,
+            calls,
+            attachments: Vec::new(),
+        }
+    }
+
+    /// The tool call a result answers.
+    fn call(&self, tool_use_id: Option<&str>) -> Option<&'a RawContentBlock> {
+        self.calls.get(tool_use_id?).copied()
+    }
+}
+
+/// Parses raw content like `parse_content_blocks`, in the context of its
+/// conversation.
+///
+/// Images in tool results are added to the context's attachments, each
+/// becoming an `Image` block after its tool result, whose content is the
+/// attachment ID and whose tool name is the tool result's.
+pub fn parse_content(raw_content: &RawContent, context: &mut ContentContext) -> Vec<ContentBlock> {
     match raw_content {
         RawContent::Text(text) => parse_text_content(text),
-        RawContent::Blocks(blocks) => parse_block_array(blocks),
+        RawContent::Blocks(blocks) => parse_block_array(blocks, context),
     }
 }
 
@@ -72,6 +171,9 @@ fn parse_text_content(text: &str) -> Vec<ContentBlock> {
                     content: preceding_text.to_string(),
                     language: None,
                     tool_name: None,
+                    spans: None,
+                    terminal: None,
+                    file_view: None,
                 });
             }
         }
@@ -92,6 +194,9 @@ fn parse_text_content(text: &str) -> Vec<ContentBlock> {
             content: code.trim_end().to_string(),
             language: Some(lang),
             tool_name: None,
+            spans: None,
+            terminal: None,
+            file_view: None,
         });
 
         last_end = end;
@@ -106,6 +211,9 @@ fn parse_text_content(text: &str) -> Vec<ContentBlock> {
                 content: remaining_text.to_string(),
                 language: None,
                 tool_name: None,
+                spans: None,
+                terminal: None,
+                file_view: None,
             });
         }
     }
@@ -117,6 +225,9 @@ fn parse_text_content(text: &str) -> Vec<ContentBlock> {
             content: text.trim().to_string(),
             language: None,
             tool_name: None,
+            spans: None,
+            terminal: None,
+            file_view: None,
         });
     }
 
@@ -127,9 +238,16 @@ fn parse_text_content(text: &str) -> Vec<ContentBlock> {
 ///
 /// Handles these block types:
 /// - "text": Extracts text content, also scans for embedded code fences
-/// - "tool_use": Extracts tool name and serializes input as content
-/// - "tool_result": Extracts tool_use_id and result content
-fn parse_block_array(raw_blocks: &[RawContentBlock]) -> Vec<ContentBlock> {
+/// - "tool_use": Extracts tool name and serializes input as content; Bash
+///   calls become terminal blocks, whose output is filled in from their
+///   result by `transform::combine_terminal_blocks`
+/// - "tool_result": Extracts tool_use_id and result content, moves base64
+///   images into the context's attachments, and handles ANSI escapes per its
+///   options; results of Read calls with numbered lines become file views
+fn parse_block_array(
+    raw_blocks: &[RawContentBlock],
+    context: &mut ContentContext,
+) -> Vec<ContentBlock> {
     let mut blocks = Vec::new();
 
     for raw in raw_blocks {
@@ -142,6 +260,11 @@ fn parse_block_array(raw_blocks: &[RawContentBlock]) -> Vec<ContentBlock> {
                 }
             }
             "tool_use" => {
+                if let Some(block) = terminal_block(raw) {
+                    blocks.push(block);
+                    continue;
+                }
+
                 let tool_name = raw.name.clone();
                 let content = raw
                     .input
@@ -154,25 +277,77 @@ fn parse_block_array(raw_blocks: &[RawContentBlock]) -> Vec<ContentBlock> {
                     content,
                     language: None,
                     tool_name,
+                    spans: None,
+                    terminal: None,
+                    file_view: None,
                 });
             }
             "tool_result" => {
+                let images_start = context.attachments.len();
+
                 // tool_result content can be a string or a more complex structure
                 let content = match &raw.content {
-                    Some(serde_json::Value::String(s)) => s.clone(),
+                    Some(Value::String(s)) => s.clone(),
+                    Some(Value::Array(items)) => tool_result_items(items, &mut context.attachments),
                     Some(v) => serde_json::to_string_pretty(v).unwrap_or_default(),
                     None => String::new(),
                 };
+                let content = match context.options.tool_result_limit {
+                    Some(limit) => {
+                        context.attachments.truncate(images_start);
+                        shorten_tool_result(content, limit)
+                    }
+                    None => content,
+                };
+
+                // Terminal output keeps its escape sequences
+                let (content, spans) = match context.options.ansi {
+                    _ if !ansi::has_escapes(&content) => (content, None),
+                    AnsiMode::Strip => (ansi::strip(&content), None),
+                    AnsiMode::Styled => {
+                        let (plain, spans) = ansi::styled(&content);
+                        (plain, Some(spans))
+                    }
+                };
 
                 // Use tool_use_id as a pseudo tool name for reference
                 let tool_name = raw.tool_use_id.clone();
 
-                blocks.push(ContentBlock {
-                    block_type: ContentBlockType::ToolResult,
-                    content,
-                    language: None,
-                    tool_name,
-                });
+                let call = context.call(raw.tool_use_id.as_deref());
+                let (file_view, content) =
+                    match call.and_then(|call| read_file_view(call, &content)) {
+                        Some((block, rest)) => (Some(block), rest.to_string()),
+                        None => (None, content),
+                    };
+                let is_file_view = file_view.is_some();
+                if let Some(mut block) = file_view {
+                    block.tool_name = tool_name.clone();
+                    blocks.push(block);
+                }
+
+                // Whatever follows a file's lines stays a tool result
+                if !is_file_view || !content.is_empty() {
+                    blocks.push(ContentBlock {
+                        block_type: ContentBlockType::ToolResult,
+                        content,
+                        language: None,
+                        tool_name: tool_name.clone(),
+                        spans: if is_file_view { None } else { spans },
+                        terminal: None,
+                        file_view: None,
+                    });
+                }
+                for attachment in &context.attachments[images_start..] {
+                    blocks.push(ContentBlock {
+                        block_type: ContentBlockType::Image,
+                        content: attachment.id.clone(),
+                        language: None,
+                        tool_name: tool_name.clone(),
+                        spans: None,
+                        terminal: None,
+                        file_view: None,
+                    });
+                }
             }
             _ => {
                 // Unknown block types are treated as text
@@ -182,6 +357,9 @@ fn parse_block_array(raw_blocks: &[RawContentBlock]) -> Vec<ContentBlock> {
                         content: text.clone(),
                         language: None,
                         tool_name: None,
+                        spans: None,
+                        terminal: None,
+                        file_view: None,
                     });
                 }
             }
@@ -191,6 +369,179 @@ fn parse_block_array(raw_blocks: &[RawContentBlock]) -> Vec<ContentBlock> {
     blocks
 }
 
+/// Makes a terminal block from a Bash tool call, without output yet.
+///
+/// The content is the command line, `$ command`.
+fn terminal_block(raw: &RawContentBlock) -> Option<ContentBlock> {
+    if raw.name.as_deref() != Some(BASH_TOOL_NAME) {
+        return None;
+    }
+    let input = raw.input.as_ref()?;
+    let command = input.get("command")?.as_str()?;
+    let description = input
+        .get("description")
+        .and_then(Value::as_str)
+        .map(String::from);
+
+    Some(ContentBlock {
+        block_type: ContentBlockType::Terminal,
+        content: format!("$ {}", command),
+        language: None,
+        tool_name: raw.name.clone(),
+        spans: None,
+        terminal: Some(TerminalTranscript {
+            tool_use_id: raw.tool_use_id.clone(),
+            command: command.to_string(),
+            description,
+            ..Default::default()
+        }),
+        file_view: None,
+    })
+}
+
+/// Makes a file view block from the result of a Read call, whose content is
+/// the file's lines prefixed with their numbers. Returns it with whatever
+/// follows the lines, trimmed.
+fn read_file_view<'c>(call: &RawContentBlock, content: &'c str) -> Option<(ContentBlock, &'c str)> {
+    if call.name.as_deref() != Some(READ_TOOL_NAME) {
+        return None;
+    }
+    let path = call.input.as_ref()?.get("file_path")?.as_str()?;
+
+    let mut start_line = None;
+    let mut lines = Vec::new();
+    let mut consumed = 0;
+    for line in content.split_inclusive('\n') {
+        let line_text = line.trim_end_matches(['\r', '\n']);
+        let Some(captures) = NUMBERED_LINE_REGEX.captures(line_text) else {
+            break;
+        };
+        let number: u32 = captures[1].parse().ok()?;
+        let start = *start_line.get_or_insert(number);
+        // Numbers must run on from the first line
+        if number != start + lines.len() as u32 {
+            break;
+        }
+        lines.push(captures.get(2).map_or("", |m| m.as_str()));
+        consumed += line.len();
+    }
+    let start_line = start_line?;
+
+    let block = ContentBlock {
+        block_type: ContentBlockType::FileView,
+        content: lines.join("\n"),
+        language: Some(language_for_path(path).unwrap_or("text").to_string()),
+        tool_name: None,
+        spans: None,
+        terminal: None,
+        file_view: Some(FileView {
+            path: path.to_string(),
+            start_line,
+        }),
+    };
+    Some((block, content[consumed..].trim()))
+}
+
+/// Infers a code block language from a file's extension or name.
+fn language_for_path(path: &str) -> Option<&'static str> {
+    let path = Path::new(path);
+    let file_name = path.file_name()?.to_str()?;
+    if file_name.eq_ignore_ascii_case("dockerfile") {
+        return Some("dockerfile");
+    }
+    let language = match path.extension()?.to_str()?.to_ascii_lowercase().as_str() {
+        "rs" => "rust",
+        "ts" | "mts" | "cts" => "typescript",
+        "tsx" => "tsx",
+        "js" | "mjs" | "cjs" => "javascript",
+        "jsx" => "jsx",
+        "py" => "python",
+        "go" => "go",
+        "rb" => "ruby",
+        "java" => "java",
+        "kt" | "kts" => "kotlin",
+        "swift" => "swift",
+        "c" | "h" => "c",
+        "cpp" | "cc" | "cxx" | "hpp" | "hh" => "cpp",
+        "cs" => "csharp",
+        "php" => "php",
+        "html" | "htm" => "html",
+        "css" => "css",
+        "scss" => "scss",
+        "json" | "jsonl" => "json",
+        "toml" => "toml",
+        "yaml" | "yml" => "yaml",
+        "md" | "markdown" => "markdown",
+        "sh" | "bash" | "zsh" => "bash",
+        "sql" => "sql",
+        "svelte" => "svelte",
+        "vue" => "vue",
+        "xml" => "xml",
+        "graphql" | "gql" => "graphql",
+        _ => return None,
+    };
+    Some(language)
+}
+
+/// Renders the items of an array tool result, moving base64 images into
+/// `attachments`.
+///
+/// Arrays without images render as JSON, as before. With images removed,
+/// the rest renders as its text if it's only text items, else as JSON.
+fn tool_result_items(items: &[Value], attachments: &mut Vec<Attachment>) -> String {
+    let mut rest = Vec::new();
+    let mut found_image = false;
+    for item in items {
+        match base64_image(item) {
+            Some(attachment) => {
+                found_image = true;
+                attachments.push(attachment);
+            }
+            None => rest.push(item),
+        }
+    }
+
+    if found_image {
+        let texts: Option<Vec<&str>> = rest
+            .iter()
+            .map(|item| match (item.get("type"), item.get("text")) {
+                (Some(Value::String(kind)), Some(Value::String(text))) if kind == "text" => {
+                    Some(text.as_str())
+                }
+                _ => None,
+            })
+            .collect();
+        if let Some(texts) = texts {
+            return texts.join("\n");
+        }
+    }
+    serde_json::to_string_pretty(&rest).unwrap_or_default()
+}
+
+/// Reads an `{"type": "image", "source": {"type": "base64", ...}}` item into
+/// an attachment, identified by the hash of its data.
+fn base64_image(item: &Value) -> Option<Attachment> {
+    if item.get("type")?.as_str()? != "image" {
+        return None;
+    }
+    let source = item.get("source")?;
+    if source.get("type")?.as_str()? != "base64" {
+        return None;
+    }
+    let media_type = source.get("media_type")?.as_str()?;
+    let data = source.get("data")?.as_str()?;
+
+    let digest = Sha256::digest(data.as_bytes());
+    let id = digest.iter().map(|byte| format!("{:02x}", byte)).collect();
+    let padding = data.bytes().rev().take_while(|&b| b == b'=').count();
+    Some(Attachment {
+        id,
+        media_type: media_type.to_string(),
+        data: data.to_string(),
+        size: (data.len() / 4 * 3).saturating_sub(padding) as i64,
+    })
+}
+
 /// Extracts the first user message preview from content blocks.
 ///
 /// Returns the first 100 characters of the first text block,
@@ -199,11 +550,12 @@ pub fn extract_preview(blocks: &[ContentBlock]) -> String {
     for block in blocks {
         if block.block_type == ContentBlockType::Text && !block.content.is_empty() {
             let content = &block.content;
-            if content.len() <= 100 {
+            // Byte offset of the 101st character; slicing at it can't split one
+            let Some((end, _)) = content.char_indices().nth(100) else {
                 return content.clone();
-            }
+            };
             // Truncate at word boundary if possible
-            let truncated = &content[..100];
+            let truncated = &content[..end];
             if let Some(last_space) = truncated.rfind(' ') {
                 return format!("{}...", &truncated[..last_space]);
             }
@@ -299,9 +651,10 @@ mod tests {
             input: None,
             tool_use_id: None,
             content: None,
+            is_error: None,
         }];
 
-        let blocks = parse_block_array(&raw_blocks);
+        let blocks = parse_block_array(&raw_blocks, &mut ContentContext::default());
         assert_eq!(blocks.len(), 1);
         assert_eq!(blocks[0].block_type, ContentBlockType::Text);
         assert_eq!(blocks[0].content, "Hello world");
@@ -316,9 +669,10 @@ mod tests {
             input: Some(json!({"path": "/test.txt"})),
             tool_use_id: Some("toolu_123".to_string()),
             content: None,
+            is_error: None,
         }];
 
-        let blocks = parse_block_array(&raw_blocks);
+        let blocks = parse_block_array(&raw_blocks, &mut ContentContext::default());
         assert_eq!(blocks.len(), 1);
         assert_eq!(blocks[0].block_type, ContentBlockType::ToolUse);
         assert_eq!(blocks[0].tool_name, Some("read_file".to_string()));
@@ -334,9 +688,10 @@ mod tests {
             input: None,
             tool_use_id: Some("toolu_123".to_string()),
             content: Some(json!("File contents here")),
+            is_error: None,
         }];
 
-        let blocks = parse_block_array(&raw_blocks);
+        let blocks = parse_block_array(&raw_blocks, &mut ContentContext::default());
         assert_eq!(blocks.len(), 1);
         assert_eq!(blocks[0].block_type, ContentBlockType::ToolResult);
         assert_eq!(blocks[0].tool_name, Some("toolu_123".to_string()));
@@ -353,6 +708,7 @@ mod tests {
                 input: None,
                 tool_use_id: None,
                 content: None,
+                is_error: None,
             },
             RawContentBlock {
                 block_type: "tool_use".to_string(),
@@ -361,10 +717,11 @@ mod tests {
                 input: Some(json!({"path": "/test.txt"})),
                 tool_use_id: Some("toolu_456".to_string()),
                 content: None,
+                is_error: None,
             },
         ];
 
-        let blocks = parse_block_array(&raw_blocks);
+        let blocks = parse_block_array(&raw_blocks, &mut ContentContext::default());
         assert_eq!(blocks.len(), 2);
         assert_eq!(blocks[0].block_type, ContentBlockType::Text);
         assert_eq!(blocks[1].block_type, ContentBlockType::ToolUse);
@@ -379,9 +736,10 @@ mod tests {
             input: None,
             tool_use_id: None,
             content: None,
+            is_error: None,
         }];
 
-        let blocks = parse_block_array(&raw_blocks);
+        let blocks = parse_block_array(&raw_blocks, &mut ContentContext::default());
         assert_eq!(blocks.len(), 2);
         assert_eq!(blocks[0].block_type, ContentBlockType::Text);
         assert_eq!(blocks[1].block_type, ContentBlockType::Code);
@@ -408,6 +766,7 @@ mod tests {
             input: None,
             tool_use_id: None,
             content: None,
+            is_error: None,
         }]);
 
         let blocks = parse_content_blocks(&raw);
@@ -424,6 +783,9 @@ mod tests {
             content: "Short preview".to_string(),
             language: None,
             tool_name: None,
+            spans: None,
+            terminal: None,
+            file_view: None,
         }];
 
         let preview = extract_preview(&blocks);
@@ -438,6 +800,9 @@ mod tests {
             content: long_text,
             language: None,
             tool_name: None,
+            spans: None,
+            terminal: None,
+            file_view: None,
         }];
 
         let preview = extract_preview(&blocks);
@@ -453,12 +818,18 @@ mod tests {
                 content: "fn main() {}".to_string(),
                 language: Some("rust".to_string()),
                 tool_name: None,
+                spans: None,
+                terminal: None,
+                file_view: None,
             },
             ContentBlock {
                 block_type: ContentBlockType::Text,
                 content: "This is the text".to_string(),
                 language: None,
                 tool_name: None,
+                spans: None,
+                terminal: None,
+                file_view: None,
             },
         ];
 
@@ -572,7 +943,7 @@ mod tests {
         let blocks = parse_text_content(text);
 
         // Should treat escaped backticks as content
-        assert!(blocks.len() >= 1);
+        assert!(!blocks.is_empty());
     }
 
     #[test]
@@ -605,9 +976,10 @@ mod tests {
             input: Some(json!({})),
             tool_use_id: Some("toolu_empty".to_string()),
             content: None,
+            is_error: None,
         }];
 
-        let blocks = parse_block_array(&raw_blocks);
+        let blocks = parse_block_array(&raw_blocks, &mut ContentContext::default());
         assert_eq!(blocks.len(), 1);
         assert_eq!(blocks[0].block_type, ContentBlockType::ToolUse);
         assert_eq!(blocks[0].content, "{}");
@@ -622,9 +994,10 @@ mod tests {
             input: None,
             tool_use_id: Some("toolu_noinput".to_string()),
             content: None,
+            is_error: None,
         }];
 
-        let blocks = parse_block_array(&raw_blocks);
+        let blocks = parse_block_array(&raw_blocks, &mut ContentContext::default());
         assert_eq!(blocks.len(), 1);
         assert_eq!(blocks[0].block_type, ContentBlockType::ToolUse);
         assert!(blocks[0].content.is_empty());
@@ -639,9 +1012,10 @@ mod tests {
             input: None,
             tool_use_id: Some("toolu_result".to_string()),
             content: None,
+            is_error: None,
         }];
 
-        let blocks = parse_block_array(&raw_blocks);
+        let blocks = parse_block_array(&raw_blocks, &mut ContentContext::default());
         assert_eq!(blocks.len(), 1);
         assert_eq!(blocks[0].block_type, ContentBlockType::ToolResult);
         assert!(blocks[0].content.is_empty());
@@ -656,15 +1030,201 @@ mod tests {
             input: None,
             tool_use_id: Some("toolu_arr".to_string()),
             content: Some(json!(["item1", "item2", "item3"])),
+            is_error: None,
         }];
 
-        let blocks = parse_block_array(&raw_blocks);
+        let blocks = parse_block_array(&raw_blocks, &mut ContentContext::default());
         assert_eq!(blocks.len(), 1);
         assert_eq!(blocks[0].block_type, ContentBlockType::ToolResult);
         assert!(blocks[0].content.contains("item1"));
         assert!(blocks[0].content.contains("item2"));
     }
 
+    #[test]
+    fn test_tool_result_images_become_attachments() {
+        let raw_blocks = vec![RawContentBlock {
+            block_type: "tool_result".to_string(),
+            text: None,
+            name: None,
+            input: None,
+            tool_use_id: Some("toolu_shot".to_string()),
+            content: Some(json!([
+                {"type": "text", "text": "Took a screenshot"},
+                {"type": "image", "source": {"type": "base64", "media_type": "image/png", "data": "iVBORw0KGgo="}}
+            ])),
+            is_error: None,
+        }];
+
+        let mut context = ContentContext::default();
+        let blocks = parse_block_array(&raw_blocks, &mut context);
+        let attachments = context.attachments;
+
+        assert_eq!(attachments.len(), 1);
+        assert_eq!(attachments[0].media_type, "image/png");
+        assert_eq!(attachments[0].data, "iVBORw0KGgo=");
+        assert_eq!(attachments[0].size, 8);
+        assert_eq!(attachments[0].id.len(), 64);
+
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(blocks[0].block_type, ContentBlockType::ToolResult);
+        assert_eq!(blocks[0].content, "Took a screenshot");
+        assert_eq!(blocks[1].block_type, ContentBlockType::Image);
+        assert_eq!(blocks[1].content, attachments[0].id);
+        assert_eq!(blocks[1].tool_name, Some("toolu_shot".to_string()));
+    }
+
+    #[test]
+    fn test_tool_result_limit_shortens_and_drops_images() {
+        let raw_blocks = vec![RawContentBlock {
+            block_type: "tool_result".to_string(),
+            text: None,
+            name: None,
+            input: None,
+            tool_use_id: Some("toolu_shot".to_string()),
+            content: Some(json!([
+                {"type": "text", "text": "Took a screenshot"},
+                {"type": "image", "source": {"type": "base64", "media_type": "image/png", "data": "iVBORw0KGgo="}}
+            ])),
+            is_error: None,
+        }];
+
+        let mut context = ContentContext {
+            options: ContentOptions {
+                tool_result_limit: Some(4),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let blocks = parse_block_array(&raw_blocks, &mut context);
+        assert!(context.attachments.is_empty());
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(
+            blocks[0].content,
+            "Took\n… [13 more characters; load the full conversation to see them]"
+        );
+
+        assert_eq!(shorten_tool_result("short".to_string(), 5), "short");
+    }
+
+    #[test]
+    fn test_tool_result_ansi_modes() {
+        let raw_blocks = vec![RawContentBlock {
+            block_type: "tool_result".to_string(),
+            text: None,
+            name: None,
+            input: None,
+            tool_use_id: Some("toolu_ls".to_string()),
+            content: Some(json!("\u{1b}[1;34msrc\u{1b}[0m  Cargo.toml")),
+            is_error: None,
+        }];
+
+        let stripped = parse_block_array(&raw_blocks, &mut ContentContext::default());
+        assert_eq!(stripped[0].content, "src  Cargo.toml");
+        assert_eq!(stripped[0].spans, None);
+
+        let mut context = ContentContext {
+            options: ContentOptions {
+                ansi: AnsiMode::Styled,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let styled = parse_block_array(&raw_blocks, &mut context);
+        assert_eq!(styled[0].content, "src  Cargo.toml");
+        let spans = styled[0].spans.as_ref().unwrap();
+        assert_eq!(spans.len(), 2);
+        assert_eq!(spans[0].text, "src");
+        assert_eq!(spans[0].fg.as_deref(), Some("blue"));
+        assert!(spans[0].bold);
+        assert_eq!(spans[1].text, "  Cargo.toml");
+    }
+
+    #[test]
+    fn test_bash_tool_use_becomes_terminal_block() {
+        let raw_blocks = vec![RawContentBlock {
+            block_type: "tool_use".to_string(),
+            text: None,
+            name: Some("Bash".to_string()),
+            input: Some(json!({"command": "cargo test", "description": "Run tests"})),
+            tool_use_id: Some("toolu_bash".to_string()),
+            content: None,
+            is_error: None,
+        }];
+
+        let blocks = parse_block_array(&raw_blocks, &mut ContentContext::default());
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(blocks[0].block_type, ContentBlockType::Terminal);
+        assert_eq!(blocks[0].content, "$ cargo test");
+        let terminal = blocks[0].terminal.as_ref().unwrap();
+        assert_eq!(terminal.tool_use_id.as_deref(), Some("toolu_bash"));
+        assert_eq!(terminal.command, "cargo test");
+        assert_eq!(terminal.description.as_deref(), Some("Run tests"));
+        assert_eq!(terminal.exit_code, None);
+    }
+
+    #[test]
+    fn test_read_result_becomes_file_view() {
+        let call = RawContentBlock {
+            block_type: "tool_use".to_string(),
+            text: None,
+            name: Some("Read".to_string()),
+            input: Some(json!({"file_path": "/repo/src/main.rs", "offset": 10})),
+            tool_use_id: Some("toolu_read".to_string()),
+            content: None,
+            is_error: None,
+        };
+        let result = RawContentBlock {
+            block_type: "tool_result".to_string(),
+            text: None,
+            name: None,
+            input: None,
+            tool_use_id: Some("toolu_read".to_string()),
+            content: Some(json!(
+                "    10→fn main() {\n    11→    run();\n    12→}\n\n<system-reminder>\nNote\n</system-reminder>"
+            )),
+            is_error: None,
+        };
+        let messages: Vec<RawMessage> = [vec![call], vec![result.clone()]]
+            .into_iter()
+            .map(|blocks| {
+                let line = r#"{"type":"user","message":{"content":"x"}}"#;
+                let mut message = crate::parser::parse_jsonl_line(line).unwrap();
+                message.message.content = RawContent::Blocks(blocks);
+                message
+            })
+            .collect();
+
+        let mut context = ContentContext::new(ContentOptions::default(), &messages);
+        let blocks = parse_content(&messages[1].message.content, &mut context);
+
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(blocks[0].block_type, ContentBlockType::FileView);
+        assert_eq!(blocks[0].content, "fn main() {\n    run();\n}");
+        assert_eq!(blocks[0].language.as_deref(), Some("rust"));
+        assert_eq!(blocks[0].tool_name.as_deref(), Some("toolu_read"));
+        assert_eq!(
+            blocks[0].file_view,
+            Some(FileView {
+                path: "/repo/src/main.rs".to_string(),
+                start_line: 10,
+            })
+        );
+        assert_eq!(blocks[1].block_type, ContentBlockType::ToolResult);
+        assert!(blocks[1].content.starts_with("<system-reminder>"));
+
+        // Without its call, the result stays a plain tool result
+        let blocks = parse_block_array(&[result], &mut ContentContext::default());
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(blocks[0].block_type, ContentBlockType::ToolResult);
+    }
+
+    #[test]
+    fn test_language_for_path() {
+        assert_eq!(language_for_path("/a/lib.TS"), Some("typescript"));
+        assert_eq!(language_for_path("Dockerfile"), Some("dockerfile"));
+        assert_eq!(language_for_path("/a/notes"), None);
+    }
+
     #[test]
     fn test_unknown_block_type_with_text() {
         let raw_blocks = vec![RawContentBlock {
@@ -674,9 +1234,10 @@ mod tests {
             input: None,
             tool_use_id: None,
             content: None,
+            is_error: None,
         }];
 
-        let blocks = parse_block_array(&raw_blocks);
+        let blocks = parse_block_array(&raw_blocks, &mut ContentContext::default());
         assert_eq!(blocks.len(), 1);
         assert_eq!(blocks[0].block_type, ContentBlockType::Text);
         assert_eq!(blocks[0].content, "Fallback text");
@@ -691,9 +1252,10 @@ mod tests {
             input: None,
             tool_use_id: None,
             content: None,
+            is_error: None,
         }];
 
-        let blocks = parse_block_array(&raw_blocks);
+        let blocks = parse_block_array(&raw_blocks, &mut ContentContext::default());
         assert!(blocks.is_empty(), "Unknown type without text should produce no block");
     }
 
@@ -706,9 +1268,10 @@ mod tests {
             input: None,
             tool_use_id: None,
             content: None,
+            is_error: None,
         }];
 
-        let blocks = parse_block_array(&raw_blocks);
+        let blocks = parse_block_array(&raw_blocks, &mut ContentContext::default());
         assert!(blocks.is_empty(), "Empty text block should produce no output");
     }
 
@@ -721,6 +1284,9 @@ mod tests {
             content: text.to_string(),
             language: None,
             tool_name: None,
+            spans: None,
+            terminal: None,
+            file_view: None,
         }];
 
         let preview = extract_preview(&blocks);
@@ -742,6 +1308,9 @@ mod tests {
             content: text,
             language: None,
             tool_name: None,
+            spans: None,
+            terminal: None,
+            file_view: None,
         }];
 
         let preview = extract_preview(&blocks);
@@ -757,12 +1326,34 @@ mod tests {
             content: text.clone(),
             language: None,
             tool_name: None,
+            spans: None,
+            terminal: None,
+            file_view: None,
         }];
 
         let preview = extract_preview(&blocks);
         assert_eq!(preview, text); // No truncation needed
     }
 
+    #[test]
+    fn test_extract_preview_multibyte_text() {
+        // 3-byte characters: byte 100 falls inside one
+        let text = "日本語".repeat(50);
+        let blocks = vec![ContentBlock {
+            block_type: ContentBlockType::Text,
+            content: text.clone(),
+            language: None,
+            tool_name: None,
+            spans: None,
+            terminal: None,
+            file_view: None,
+        }];
+
+        let preview = extract_preview(&blocks);
+        assert_eq!(preview.chars().count(), 103); // 100 + "..."
+        assert!(text.starts_with(preview.trim_end_matches("...")));
+    }
+
     #[test]
     fn test_extract_preview_only_code_blocks() {
         let blocks = vec![
@@ -771,12 +1362,18 @@ mod tests {
                 content: "fn main() {}".to_string(),
                 language: Some("rust".to_string()),
                 tool_name: None,
+                spans: None,
+                terminal: None,
+                file_view: None,
             },
             ContentBlock {
                 block_type: ContentBlockType::ToolUse,
                 content: r#"{"path": "/test"}"#.to_string(),
                 language: None,
                 tool_name: Some("read_file".to_string()),
+                spans: None,
+                terminal: None,
+                file_view: None,
             },
         ];
 
@@ -839,9 +1436,10 @@ mod tests {
             })),
             tool_use_id: Some("toolu_complex".to_string()),
             content: None,
+            is_error: None,
         }];
 
-        let blocks = parse_block_array(&raw_blocks);
+        let blocks = parse_block_array(&raw_blocks, &mut ContentContext::default());
         assert_eq!(blocks.len(), 1);
         assert_eq!(blocks[0].block_type, ContentBlockType::ToolUse);
         // JSON should be pretty-printed
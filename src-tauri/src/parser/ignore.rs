@@ -0,0 +1,116 @@
+//! Session files left out of discovery.
+//!
+//! The ignore patterns and size limit in settings keep archives, scratch
+//! projects, and gigantic test transcripts out of scans and the watcher, so
+//! they don't bloat the index. Patterns are globs matched against a file's
+//! full path with `/` separators, where `*` also matches `/`: `*/archive/*`
+//! leaves out every `archive` folder, `*/-Users-me-scratch/*` one project.
+
+use crate::models::AppSettings;
+use crate::parser::paths::long_path;
+use glob::Pattern;
+use std::fs;
+use std::path::Path;
+use tracing::{debug, warn};
+
+/// Most ignore patterns in settings.
+pub const MAX_IGNORE_PATTERNS: usize = 100;
+
+/// Bytes in a megabyte of the session file size settings.
+pub const BYTES_PER_MB: u64 = 1024 * 1024;
+
+/// Which session files discovery leaves out.
+#[derive(Debug, Clone, Default)]
+pub struct IgnoreRules {
+    patterns: Vec<Pattern>,
+    max_file_bytes: Option<u64>,
+}
+
+impl IgnoreRules {
+    /// Builds the rules of the ignore settings. Invalid patterns (which
+    /// `update_settings` rejects) are logged and skipped.
+    pub fn from_settings(settings: &AppSettings) -> Self {
+        let patterns = settings
+            .ignore_patterns
+            .iter()
+            .filter_map(|pattern| match Pattern::new(pattern) {
+                Ok(pattern) => Some(pattern),
+                Err(e) => {
+                    warn!("Skipping invalid ignore pattern '{}': {}", pattern, e);
+                    None
+                }
+            })
+            .collect();
+        Self {
+            patterns,
+            max_file_bytes: settings
+                .max_session_file_mb
+                .map(|mb| u64::from(mb) * BYTES_PER_MB),
+        }
+    }
+
+    /// Whether nothing is ignored.
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_empty() && self.max_file_bytes.is_none()
+    }
+
+    /// Whether a session file is left out: its path matches a pattern, or
+    /// it's larger than the size limit.
+    pub fn is_ignored(&self, path: &Path) -> bool {
+        let normalized = path.to_string_lossy().replace('\\', "/");
+        if let Some(pattern) = self.patterns.iter().find(|p| p.matches(&normalized)) {
+            debug!("Ignoring {:?}: matches '{}'", path, pattern);
+            return true;
+        }
+        if let Some(max) = self.max_file_bytes {
+            if let Ok(metadata) = fs::metadata(long_path(path)) {
+                if metadata.len() > max {
+                    debug!("Ignoring {:?}: {} bytes", path, metadata.len());
+                    return true;
+                }
+            }
+        }
+        false
+    }
+}
+
+/// Checks an ignore pattern, returning why it's invalid.
+pub fn validate_ignore_pattern(pattern: &str) -> Result<(), String> {
+    Pattern::new(pattern).map(|_| ()).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_ignore_rules_match_patterns_and_size() {
+        let dir = tempdir().unwrap();
+        let small = dir.path().join("small.jsonl");
+        let large = dir.path().join("large.jsonl");
+        fs::write(&small, b"{}").unwrap();
+        fs::write(&large, vec![b' '; 2 * BYTES_PER_MB as usize]).unwrap();
+
+        let settings = AppSettings {
+            ignore_patterns: vec![
+                "*/archive/*".to_string(),
+                "*/-Users-me-scratch/*".to_string(),
+                "[".to_string(),
+            ],
+            max_session_file_mb: Some(1),
+            ..Default::default()
+        };
+        let rules = IgnoreRules::from_settings(&settings);
+        assert!(rules.is_ignored(Path::new("/home/me/.claude/projects/p/archive/s.jsonl")));
+        assert!(rules.is_ignored(Path::new("/p/-Users-me-scratch/s.jsonl")));
+        assert!(!rules.is_ignored(Path::new("/p/-Users-me-scratchpad/s.jsonl")));
+        assert!(rules.is_ignored(&large));
+        assert!(!rules.is_ignored(&small));
+
+        assert!(IgnoreRules::default().is_empty());
+        assert!(!IgnoreRules::default().is_ignored(&large));
+        assert!(validate_ignore_pattern("*/archive/*").is_ok());
+        assert!(validate_ignore_pattern("[").is_err());
+    }
+}
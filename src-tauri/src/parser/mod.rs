@@ -2,14 +2,41 @@
 //!
 //! This module handles discovery and parsing of Claude Code JSONL conversation files
 //! from `~/.claude/projects/`. Includes line parsing, conversation aggregation,
-//! and content block extraction.
+//! content block extraction, handling of ANSI escapes in tool output,
+//! collapsing of repeated system reminders, reading of gzipped and rotated
+//! session files, the parsing profile mapping lines to messages,
+//! detection of structures the profile doesn't know, the ignore rules
+//! leaving session files out of discovery, and Windows path spellings.
 
+pub mod ansi;
 pub mod content;
+pub mod ignore;
 pub mod jsonl;
+pub mod paths;
+pub mod profile;
+pub mod reminders;
+pub mod schema;
+pub mod segments;
+pub mod timestamp;
 
-pub use content::{extract_preview, parse_content_blocks};
+pub use content::{
+    extract_preview, parse_content, parse_content_blocks, shorten_tool_result, ContentContext,
+    ContentOptions, PARTIAL_TOOL_RESULT_CHARS,
+};
+pub use ignore::{validate_ignore_pattern, IgnoreRules, MAX_IGNORE_PATTERNS};
 pub use jsonl::{
-    discover_jsonl_files, get_claude_projects_dir, parse_conversation_file, parse_jsonl_line,
-    ParsedConversation, ParserError, ParserResult, RawContent, RawContentBlock, RawInnerMessage,
-    RawMessage, RawMessageType, RawTokenCount,
+    conflict_conversation_id, content_hash, discover_jsonl_files, discover_jsonl_files_in, discover_session_files, discover_session_files_in, get_claude_projects_dir, get_session_roots, parse_conversation_file, parse_conversation_file_observed, parse_jsonl_line,
+    AccessFailure, Discovery, ParsedConversation, ParserError, ParserResult, RawCommandOutput, RawContent, RawContentBlock, RawInnerMessage,
+    RawMessage, RawMessageType, RawTokenCount, RawUsage,
+};
+pub use paths::{long_path, normalize_path, path_key};
+pub use profile::{
+    active_profile, install_profile, load_profile, ParsingProfile, PROFILE_FILE_NAME,
+};
+pub use reminders::collapse_reminders;
+pub use schema::SchemaObservations;
+pub use segments::{is_session_file, primary_session_file, session_segments};
+pub use timestamp::{
+    epoch_millis, filter_boundary_millis, normalize_timestamp, DateBoundary, FilterTimezone,
+    NormalizedTimestamp,
 };
@@ -0,0 +1,148 @@
+//! Windows path spellings.
+//!
+//! On Windows one session file reaches the app under several spellings:
+//! with or without the `\\?\` verbatim prefix (which the watcher and
+//! `fs::canonicalize` add), with either slash, and with the drive letter
+//! or folder names in another case. Files are tracked under the plain
+//! spelling of [`normalize_path`] and looked up by [`path_key`], which also
+//! ignores case where file names do. The file system is accessed through
+//! [`long_path`], which adds the prefix back to paths longer than
+//! `MAX_PATH`, so deeply nested project directories can be read.
+//!
+//! Elsewhere paths are used as they are. The string handling itself works
+//! on every platform, so it's tested everywhere.
+
+use std::path::{Path, PathBuf};
+
+/// Prefix of verbatim paths, which skip `MAX_PATH` and path parsing.
+const VERBATIM_PREFIX: &str = r"\\?\";
+
+/// Prefix of verbatim UNC paths (`\\?\UNC\server\share`).
+const VERBATIM_UNC_PREFIX: &str = r"\\?\UNC\";
+
+/// Longest path Windows APIs accept without the verbatim prefix,
+/// including the terminating NUL.
+pub const MAX_PATH: usize = 260;
+
+/// Whether file names are compared ignoring case.
+pub const CASE_INSENSITIVE_PATHS: bool = cfg!(windows);
+
+/// Returns the plain spelling of a path files are tracked under.
+pub fn normalize_path(path: &Path) -> PathBuf {
+    match path.to_str() {
+        Some(path) if cfg!(windows) => PathBuf::from(normalize_windows_path(path)),
+        _ => path.to_path_buf(),
+    }
+}
+
+/// Returns the key comparing paths: the plain spelling, in lower case where
+/// file names are case-insensitive.
+pub fn path_key(path: &Path) -> String {
+    let path = normalize_path(path).to_string_lossy().into_owned();
+    if CASE_INSENSITIVE_PATHS {
+        path.to_lowercase()
+    } else {
+        path
+    }
+}
+
+/// Returns the path to access the file system with: on Windows, paths too
+/// long for `MAX_PATH` get the verbatim prefix.
+pub fn long_path(path: &Path) -> PathBuf {
+    match path.to_str() {
+        Some(path) if cfg!(windows) => PathBuf::from(long_windows_path(path)),
+        _ => path.to_path_buf(),
+    }
+}
+
+/// Drops the verbatim prefix, uses backslashes only, and upper-cases the
+/// drive letter of a Windows path.
+pub fn normalize_windows_path(path: &str) -> String {
+    let path = if let Some(unc) = path.strip_prefix(VERBATIM_UNC_PREFIX) {
+        format!(r"\\{}", unc)
+    } else if let Some(local) = path.strip_prefix(VERBATIM_PREFIX) {
+        local.to_string()
+    } else {
+        path.replace('/', r"\")
+    };
+
+    let mut chars = path.chars();
+    match (chars.next(), chars.next()) {
+        (Some(drive), Some(':')) if drive.is_ascii_lowercase() => {
+            format!("{}{}", drive.to_ascii_uppercase(), &path[1..])
+        }
+        _ => path,
+    }
+}
+
+/// Adds the verbatim prefix to an absolute Windows path too long for
+/// `MAX_PATH`. Short and relative paths are only normalized.
+pub fn long_windows_path(path: &str) -> String {
+    let path = normalize_windows_path(path);
+    if path.len() < MAX_PATH {
+        return path;
+    }
+    if let Some(unc) = path.strip_prefix(r"\\") {
+        return format!("{}{}", VERBATIM_UNC_PREFIX, unc);
+    }
+    let bytes = path.as_bytes();
+    let is_absolute = bytes.len() > 2 && bytes[1] == b':' && bytes[2] == b'\\';
+    if is_absolute {
+        format!("{}{}", VERBATIM_PREFIX, path)
+    } else {
+        path
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_windows_path_spellings_normalize_alike() {
+        let plain = r"C:\Users\me\.claude\projects\p\s.jsonl";
+        for spelling in [
+            plain,
+            r"\\?\C:\Users\me\.claude\projects\p\s.jsonl",
+            r"c:\Users\me\.claude\projects\p\s.jsonl",
+            "C:/Users/me/.claude/projects/p/s.jsonl",
+        ] {
+            assert_eq!(normalize_windows_path(spelling), plain);
+        }
+        assert_eq!(
+            normalize_windows_path(r"\\?\UNC\server\share\s.jsonl"),
+            r"\\server\share\s.jsonl"
+        );
+        assert_eq!(normalize_windows_path("relative/s.jsonl"), r"relative\s.jsonl");
+    }
+
+    #[test]
+    fn test_long_windows_paths_get_the_verbatim_prefix() {
+        let short = r"C:\Users\me\s.jsonl";
+        assert_eq!(long_windows_path(short), short);
+
+        let nested = format!(r"C:\Users\me\.claude\projects\{}\s.jsonl", "x".repeat(300));
+        assert_eq!(long_windows_path(&nested), format!(r"\\?\{}", nested));
+        // Already prefixed paths keep a single prefix
+        assert_eq!(
+            long_windows_path(&format!(r"\\?\{}", nested)),
+            format!(r"\\?\{}", nested)
+        );
+
+        let unc = format!(r"\\server\share\{}\s.jsonl", "x".repeat(300));
+        assert_eq!(long_windows_path(&unc), format!(r"\\?\UNC\{}", &unc[2..]));
+        let relative = format!(r"projects\{}", "x".repeat(300));
+        assert_eq!(long_windows_path(&relative), relative);
+    }
+
+    #[test]
+    fn test_paths_are_unchanged_off_windows() {
+        if cfg!(windows) {
+            return;
+        }
+        let path = Path::new("/home/Me/.claude/projects/p/S.jsonl");
+        assert_eq!(normalize_path(path), path);
+        assert_eq!(long_path(path), path);
+        assert_eq!(path_key(path), "/home/Me/.claude/projects/p/S.jsonl");
+    }
+}
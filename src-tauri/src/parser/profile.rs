@@ -0,0 +1,277 @@
+//! Parsing profile: how JSONL lines map to messages.
+//!
+//! The Claude Code session format changes between releases: new line types
+//! appear and fields get renamed. The line types the parser understands,
+//! the fields it reads, the fields and block types it expects to see, and
+//! the markup that flags command output are data, in a versioned profile. The default is embedded in the binary; a
+//! `parsing-profile.json` in the app data directory with at least the same
+//! version replaces it at startup, so a new line type can be supported by
+//! shipping a profile rather than a release.
+
+use crate::parser::jsonl::RawMessageType;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::sync::OnceLock;
+use tracing::{info, warn};
+
+/// File name of the user's profile in the app data directory.
+pub const PROFILE_FILE_NAME: &str = "parsing-profile.json";
+
+/// The profile shipped with the app.
+const EMBEDDED_PROFILE: &str = include_str!("profile.json");
+
+/// How lines of one `type` are read.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LineType {
+    /// Message type the lines become.
+    #[serde(rename = "as")]
+    pub message_type: RawMessageType,
+    /// Whether the lines are meta messages rather than part of the dialogue.
+    #[serde(default)]
+    pub meta: bool,
+    /// Paths of the text standing in for the lines' content, the first
+    /// present one winning; empty to use the message content.
+    #[serde(default)]
+    pub text: Vec<String>,
+    /// Whether the lines aren't messages at all (session summaries, file
+    /// history snapshots): skipped without counting as unreadable.
+    #[serde(default)]
+    pub ignore: bool,
+}
+
+/// Paths of the fields read from a line. Each field lists alternatives,
+/// the first present one winning, so a renamed field can be read under
+/// both names.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LineFields {
+    #[serde(rename = "type")]
+    pub line_type: Vec<String>,
+    pub message: Vec<String>,
+    pub timestamp: Vec<String>,
+    pub uuid: Vec<String>,
+    pub session_id: Vec<String>,
+    pub is_meta: Vec<String>,
+    pub token_count: Vec<String>,
+    pub tool_use_result: Vec<String>,
+    pub parent_uuid: Vec<String>,
+    pub logical_parent_uuid: Vec<String>,
+    pub leaf_uuid: Vec<String>,
+    pub is_sidechain: Vec<String>,
+    pub agent_id: Vec<String>,
+    pub cwd: Vec<String>,
+}
+
+/// Paths of the fields read from a line's chat message.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MessageFields {
+    pub content: Vec<String>,
+    pub role: Vec<String>,
+    pub model: Vec<String>,
+    pub usage: Vec<String>,
+    pub id: Vec<String>,
+}
+
+/// Mapping of JSONL lines to messages.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ParsingProfile {
+    /// Raised with every change; a user profile older than the embedded one
+    /// is ignored.
+    pub version: u32,
+    /// Line types the parser understands, by their `type` value; other
+    /// lines are parse errors.
+    pub line_types: BTreeMap<String, LineType>,
+    pub fields: LineFields,
+    pub message_fields: MessageFields,
+    /// Top-level fields known to appear on lines besides those in
+    /// `fields`; others are recorded as schema observations.
+    pub known_fields: Vec<String>,
+    /// Content block types known to appear in messages.
+    pub block_types: Vec<String>,
+    /// Prefixes of user messages that record a slash or `!` command and
+    /// its output rather than something the user wrote.
+    pub command_markup_prefixes: Vec<String>,
+}
+
+impl ParsingProfile {
+    /// Returns the profile shipped with the app.
+    pub fn embedded() -> Self {
+        serde_json::from_str(EMBEDDED_PROFILE).expect("Invalid embedded parsing profile")
+    }
+
+    /// Returns true if a top-level field of lines is known: read by the
+    /// parser or listed in `known_fields`.
+    pub fn knows_field(&self, name: &str) -> bool {
+        let fields = &self.fields;
+        let read = [
+            &fields.line_type,
+            &fields.message,
+            &fields.timestamp,
+            &fields.uuid,
+            &fields.session_id,
+            &fields.is_meta,
+            &fields.token_count,
+            &fields.tool_use_result,
+            &fields.parent_uuid,
+            &fields.logical_parent_uuid,
+            &fields.leaf_uuid,
+            &fields.is_sidechain,
+            &fields.agent_id,
+            &fields.cwd,
+        ];
+        let text = self.line_types.values().map(|line_type| &line_type.text);
+        read.into_iter()
+            .chain(text)
+            .flatten()
+            .any(|path| path.split('.').next() == Some(name))
+            || self.known_fields.iter().any(|field| field == name)
+    }
+
+    /// Returns true if a line is of a type flagged `ignore`.
+    pub fn ignores_line(&self, value: &Value) -> bool {
+        lookup_str(value, &self.fields.line_type)
+            .and_then(|line_type| self.line_types.get(line_type))
+            .is_some_and(|line_type| line_type.ignore)
+    }
+}
+
+/// Returns the value at the first of `paths` present in `value`. Paths
+/// are dot-separated keys (`data.hookName`).
+pub fn lookup<'a>(value: &'a Value, paths: &[String]) -> Option<&'a Value> {
+    paths
+        .iter()
+        .find_map(|path| path.split('.').try_fold(value, |value, key| value.get(key)))
+}
+
+/// Returns the string at the first of `paths` holding one in `value`.
+pub fn lookup_str<'a>(value: &'a Value, paths: &[String]) -> Option<&'a str> {
+    paths
+        .iter()
+        .find_map(|path| lookup(value, std::slice::from_ref(path))?.as_str())
+}
+
+/// Returns the profile in `path` if it's valid and at least as recent as
+/// the embedded one, and the embedded profile otherwise.
+pub fn load_profile(path: &Path) -> ParsingProfile {
+    let embedded = ParsingProfile::embedded();
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return embedded,
+        Err(e) => {
+            warn!("Failed to read parsing profile {:?}: {}", path, e);
+            return embedded;
+        }
+    };
+    match serde_json::from_str::<ParsingProfile>(&contents) {
+        Ok(profile) if profile.version >= embedded.version => {
+            info!(
+                "Using parsing profile version {} from {:?}",
+                profile.version, path
+            );
+            profile
+        }
+        Ok(profile) => {
+            warn!(
+                "Ignoring parsing profile {:?}: version {} is older than the built-in version {}",
+                path, profile.version, embedded.version
+            );
+            embedded
+        }
+        Err(e) => {
+            warn!("Ignoring invalid parsing profile {:?}: {}", path, e);
+            embedded
+        }
+    }
+}
+
+static ACTIVE_PROFILE: OnceLock<ParsingProfile> = OnceLock::new();
+
+/// Makes `profile` the one used for parsing. Only the first profile
+/// installed, before anything is parsed, takes effect; returns whether it
+/// did.
+pub fn install_profile(profile: ParsingProfile) -> bool {
+    ACTIVE_PROFILE.set(profile).is_ok()
+}
+
+/// Returns the profile used for parsing: the installed one, or the
+/// embedded one if none was installed.
+pub fn active_profile() -> &'static ParsingProfile {
+    ACTIVE_PROFILE.get_or_init(ParsingProfile::embedded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_embedded_profile_maps_known_line_types() {
+        let profile = ParsingProfile::embedded();
+        assert_eq!(
+            profile.line_types.keys().collect::<Vec<_>>(),
+            vec!["assistant", "file-history-snapshot", "progress", "summary", "system", "user"]
+        );
+        assert!(profile.ignores_line(&json!({"type": "summary", "leafUuid": "u1"})));
+        assert!(!profile.ignores_line(&json!({"type": "user"})));
+        assert_eq!(
+            profile.line_types["progress"].message_type,
+            RawMessageType::System
+        );
+        assert!(profile.line_types["progress"].meta);
+        assert!(profile.knows_field("sessionId"));
+        assert!(profile.knows_field("data"));
+        assert!(profile.knows_field("parentUuid"));
+        assert!(profile.knows_field("logicalParentUuid"));
+        assert!(!profile.knows_field("hookInfos"));
+    }
+
+    #[test]
+    fn test_lookup_takes_first_present_path() {
+        let value = json!({"data": {"command": "npm test", "hookName": null}, "type": "x"});
+        let paths = |paths: &[&str]| paths.iter().map(|p| p.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            lookup(&value, &paths(&["data.hookName", "data.command"])),
+            Some(&Value::Null)
+        );
+        assert_eq!(
+            lookup_str(&value, &paths(&["data.hookName", "data.command"])),
+            Some("npm test")
+        );
+        assert_eq!(lookup(&value, &paths(&["type"])), Some(&json!("x")));
+        assert_eq!(lookup(&value, &paths(&["data.missing", "other"])), None);
+    }
+
+    #[test]
+    fn test_load_profile_prefers_newer_user_profile() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join(PROFILE_FILE_NAME);
+        assert_eq!(load_profile(&path), ParsingProfile::embedded());
+
+        let mut newer = ParsingProfile::embedded();
+        newer.version += 1;
+        newer.line_types.insert(
+            "summary".to_string(),
+            LineType {
+                message_type: RawMessageType::System,
+                meta: true,
+                text: vec!["summary".to_string()],
+                ignore: false,
+            },
+        );
+        fs::write(&path, serde_json::to_string(&newer).unwrap()).unwrap();
+        assert_eq!(load_profile(&path), newer);
+
+        let mut older = ParsingProfile::embedded();
+        older.version -= 1;
+        fs::write(&path, serde_json::to_string(&older).unwrap()).unwrap();
+        assert_eq!(load_profile(&path), ParsingProfile::embedded());
+
+        fs::write(&path, "{\"version\": 99}").unwrap();
+        assert_eq!(load_profile(&path), ParsingProfile::embedded());
+    }
+}
@@ -0,0 +1,139 @@
+//! Collapsing of repeated system reminders.
+//!
+//! The CLI injects `<system-reminder>` blocks into user messages and tool
+//! results, often the same reminder on every turn. Parsing keeps the first
+//! occurrence of each distinct reminder in a conversation and removes the
+//! repeats, counting them on the message they were removed from, so
+//! previews, search, and exports aren't filled with the same boilerplate.
+
+use crate::parser::jsonl::{RawContent, RawMessage};
+use regex::{Captures, Regex};
+use serde_json::Value;
+use std::collections::HashSet;
+use std::sync::LazyLock;
+
+/// A system reminder and the blank lines after it.
+static REMINDER_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?s)<system-reminder>(.*?)</system-reminder>[ \t]*\n*")
+        .expect("Invalid regex pattern")
+});
+
+/// Removes repeated system reminders from a conversation's messages, in
+/// order, and records how many were removed from each message in
+/// `collapsed_reminders`. Returns the total removed.
+pub fn collapse_reminders(messages: &mut [RawMessage]) -> usize {
+    let mut seen: HashSet<String> = HashSet::new();
+    let mut total = 0;
+    for message in messages {
+        let mut collapsed = 0;
+        for_each_text_mut(&mut message.message.content, |text| {
+            if let Some(collapsed_text) = collapse_text(text, &mut seen, &mut collapsed) {
+                *text = collapsed_text;
+            }
+        });
+        message.collapsed_reminders += collapsed;
+        total += collapsed as usize;
+    }
+    total
+}
+
+/// Returns `text` without the reminders already in `seen`, adding the new
+/// ones, or `None` if nothing was removed.
+fn collapse_text(text: &str, seen: &mut HashSet<String>, collapsed: &mut u32) -> Option<String> {
+    if !text.contains("<system-reminder>") {
+        return None;
+    }
+    let before = *collapsed;
+    let result = REMINDER_REGEX.replace_all(text, |captures: &Captures| {
+        if seen.insert(captures[1].trim().to_string()) {
+            captures[0].to_string()
+        } else {
+            *collapsed += 1;
+            String::new()
+        }
+    });
+    (*collapsed > before).then(|| result.trim_end().to_string())
+}
+
+/// Calls `f` with each piece of text in a message: plain text, text blocks,
+/// and the text of tool results.
+fn for_each_text_mut(content: &mut RawContent, mut f: impl FnMut(&mut String)) {
+    let blocks = match content {
+        RawContent::Text(text) => return f(text),
+        RawContent::Blocks(blocks) => blocks,
+    };
+    for block in blocks {
+        if let Some(text) = &mut block.text {
+            f(text);
+        }
+        match &mut block.content {
+            Some(Value::String(text)) => f(text),
+            Some(Value::Array(items)) => {
+                for item in items {
+                    if let Some(Value::String(text)) = item.get_mut("text") {
+                        f(text);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_jsonl_line;
+    use serde_json::json;
+
+    fn user(content: Value) -> RawMessage {
+        let line = json!({ "type": "user", "message": { "role": "user", "content": content } });
+        parse_jsonl_line(&line.to_string()).unwrap()
+    }
+
+    fn text(message: &RawMessage) -> &str {
+        match &message.message.content {
+            RawContent::Text(text) => text,
+            RawContent::Blocks(blocks) => blocks[0].text.as_deref().unwrap_or_default(),
+        }
+    }
+
+    #[test]
+    fn test_collapse_reminders_keeps_first_of_each() {
+        let reminder = "<system-reminder>\nThe todo list is empty.\n</system-reminder>";
+        let other = "<system-reminder>Files changed.</system-reminder>";
+        let mut messages = vec![
+            user(json!(format!("Fix the test\n\n{}", reminder))),
+            user(json!(format!("{}\n\nNow the docs\n{}", reminder, other))),
+            user(json!([{ "type": "text", "text": format!("Done? {}", other) }])),
+        ];
+
+        assert_eq!(collapse_reminders(&mut messages), 2);
+        assert_eq!(text(&messages[0]), format!("Fix the test\n\n{}", reminder));
+        assert_eq!(text(&messages[1]), format!("Now the docs\n{}", other));
+        assert_eq!(text(&messages[2]), "Done?");
+        let counts: Vec<u32> = messages.iter().map(|m| m.collapsed_reminders).collect();
+        assert_eq!(counts, vec![0, 1, 1]);
+    }
+
+    #[test]
+    fn test_collapse_reminders_in_tool_results() {
+        let reminder = "<system-reminder>Be careful.</system-reminder>";
+        let result = |content: Value| {
+            user(json!([{ "type": "tool_result", "tool_use_id": "t", "content": content }]))
+        };
+        let mut messages = vec![
+            result(json!(format!("ok\n{}", reminder))),
+            result(json!([{ "type": "text", "text": format!("ok\n{}", reminder) }])),
+        ];
+
+        assert_eq!(collapse_reminders(&mut messages), 1);
+        let RawContent::Blocks(blocks) = &messages[1].message.content else {
+            panic!("expected blocks");
+        };
+        assert_eq!(
+            blocks[0].content,
+            Some(json!([{ "type": "text", "text": "ok" }]))
+        );
+    }
+}
@@ -0,0 +1,117 @@
+//! Schema drift: structures in session files the parser doesn't know.
+//!
+//! Claude Code adds fields, block types, and line types without notice, and
+//! the parser drops what it doesn't understand. While a file is parsed,
+//! its lines are checked against the parsing profile and what's unknown is
+//! counted, so ingest can record it (see `db::observations`).
+
+use crate::models::SchemaObservationKind;
+use crate::parser::profile::{lookup, ParsingProfile};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// Unknown structures seen in a file, with their number of occurrences.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SchemaObservations {
+    counts: BTreeMap<(SchemaObservationKind, String), u64>,
+}
+
+impl SchemaObservations {
+    /// Counts the unknown top-level fields, line type, and content block
+    /// types of a line.
+    pub fn observe_line(&mut self, value: &Value, profile: &ParsingProfile) {
+        let Some(line) = value.as_object() else {
+            return;
+        };
+        for key in line.keys() {
+            if !profile.knows_field(key) {
+                self.add(SchemaObservationKind::Field, key);
+            }
+        }
+        if let Some(line_type) = lookup(value, &profile.fields.line_type).and_then(Value::as_str) {
+            if !profile.line_types.contains_key(line_type) {
+                self.add(SchemaObservationKind::LineType, line_type);
+            }
+        }
+
+        let blocks = lookup(value, &profile.fields.message)
+            .and_then(|message| lookup(message, &profile.message_fields.content))
+            .and_then(Value::as_array);
+        for block in blocks.into_iter().flatten() {
+            if let Some(block_type) = block.get("type").and_then(Value::as_str) {
+                if !profile.block_types.iter().any(|known| known == block_type) {
+                    self.add(SchemaObservationKind::BlockType, block_type);
+                }
+            }
+        }
+    }
+
+    fn add(&mut self, kind: SchemaObservationKind, name: &str) {
+        *self.counts.entry((kind, name.to_string())).or_default() += 1;
+    }
+
+    /// Adds the observations of another file.
+    pub fn merge(&mut self, other: SchemaObservations) {
+        for (key, count) in other.counts {
+            *self.counts.entry(key).or_default() += count;
+        }
+    }
+
+    /// Returns true if nothing unknown was seen.
+    pub fn is_empty(&self) -> bool {
+        self.counts.is_empty()
+    }
+
+    /// Iterates over the unknown structures and their counts.
+    pub fn iter(&self) -> impl Iterator<Item = (SchemaObservationKind, &str, u64)> {
+        self.counts
+            .iter()
+            .map(|((kind, name), count)| (*kind, name.as_str(), *count))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_observe_line_counts_unknown_structures() {
+        let profile = ParsingProfile::embedded();
+        let mut observations = SchemaObservations::default();
+        let lines = [
+            json!({"type": "user", "message": {"content": "Hi"}, "sessionId": "s1", "cwd": "/p"}),
+            json!({
+                "type": "assistant",
+                "message": {"content": [
+                    {"type": "text", "text": "Hello"},
+                    {"type": "server_tool_use", "name": "web_search"}
+                ]},
+                "hookInfos": [],
+                "sessionId": "s1"
+            }),
+            json!({"type": "queue-operation", "hookInfos": []}),
+        ];
+        for line in &lines {
+            observations.observe_line(line, &profile);
+        }
+
+        assert_eq!(
+            observations.iter().collect::<Vec<_>>(),
+            vec![
+                (SchemaObservationKind::Field, "hookInfos", 2),
+                (SchemaObservationKind::BlockType, "server_tool_use", 1),
+                (SchemaObservationKind::LineType, "queue-operation", 1),
+            ]
+        );
+
+        let mut merged = SchemaObservations::default();
+        assert!(merged.is_empty());
+        merged.merge(observations.clone());
+        merged.merge(observations);
+        assert_eq!(
+            merged.iter().next(),
+            Some((SchemaObservationKind::Field, "hookInfos", 4))
+        );
+    }
+}
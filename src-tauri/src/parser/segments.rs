@@ -0,0 +1,190 @@
+//! Compressed and rotated session files.
+//!
+//! Old sessions are sometimes gzipped (`session.jsonl.gz`), and log rotation
+//! moves the earlier lines of a session to numbered files (`session.jsonl.1`,
+//! `session.jsonl.2.gz`, ...). These are all segments of one session:
+//! discovery lists each session once, by its primary file, and parsing reads
+//! every segment of it, oldest first.
+
+use crate::parser::paths::long_path;
+use flate2::read::MultiGzDecoder;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::{Path, PathBuf};
+
+/// Extension of session files.
+const SESSION_SUFFIX: &str = ".jsonl";
+
+/// Suffix of gzipped segments.
+const GZIP_SUFFIX: &str = ".gz";
+
+/// Splits a segment's file name into the session's file name (ending in
+/// `.jsonl`) and its rotation number, 0 for the current segment.
+fn split_segment_name(name: &str) -> Option<(&str, u32)> {
+    let name = name.strip_suffix(GZIP_SUFFIX).unwrap_or(name);
+    if name.ends_with(SESSION_SUFFIX) {
+        return Some((name, 0));
+    }
+    let (session, number) = name.rsplit_once('.')?;
+    if !session.ends_with(SESSION_SUFFIX) || !number.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let rotation = number.parse().ok().filter(|&rotation| rotation > 0)?;
+    Some((session, rotation))
+}
+
+/// Returns true if the path names a segment of a session file.
+pub fn is_session_file(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .and_then(split_segment_name)
+        .is_some()
+}
+
+/// Returns true if the segment is gzipped.
+pub fn is_compressed(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "gz")
+}
+
+/// Returns the segment with a rotation number, plain or gzipped, if it
+/// exists. The plain file wins while both exist (mid-compression).
+fn existing_segment(dir: &Path, session: &str, rotation: u32) -> Option<PathBuf> {
+    let name = match rotation {
+        0 => session.to_string(),
+        rotation => format!("{}.{}", session, rotation),
+    };
+    let plain = dir.join(&name);
+    if long_path(&plain).is_file() {
+        return Some(plain);
+    }
+    let gzipped = dir.join(name + GZIP_SUFFIX);
+    long_path(&gzipped).is_file().then_some(gzipped)
+}
+
+/// Lists the segments of the session a file belongs to, oldest (highest
+/// rotation number) first and the current one last.
+///
+/// Rotation numbers are expected to be consecutive from 1. A path that
+/// isn't a session file, or whose segments can't be found, is returned
+/// alone.
+pub fn session_segments(path: &Path) -> Vec<PathBuf> {
+    let name = path.file_name().and_then(|name| name.to_str());
+    let (Some(dir), Some((session, _))) = (path.parent(), name.and_then(split_segment_name)) else {
+        return vec![path.to_path_buf()];
+    };
+
+    let mut segments: Vec<PathBuf> = (1..)
+        .map_while(|rotation| existing_segment(dir, session, rotation))
+        .collect();
+    segments.reverse();
+    segments.extend(existing_segment(dir, session, 0));
+    if segments.is_empty() {
+        segments.push(path.to_path_buf());
+    }
+    segments
+}
+
+/// Returns the file a session is known by: its current segment, or the
+/// most recent rotated one if the current segment is gone.
+pub fn primary_session_file(path: &Path) -> PathBuf {
+    session_segments(path)
+        .pop()
+        .unwrap_or_else(|| path.to_path_buf())
+}
+
+/// Opens a segment for reading lines, decompressing gzipped ones.
+pub fn open_segment(path: &Path) -> io::Result<Box<dyn BufRead>> {
+    let file = File::open(long_path(path))?;
+    if is_compressed(path) {
+        Ok(Box::new(BufReader::new(MultiGzDecoder::new(file))))
+    } else {
+        Ok(Box::new(BufReader::new(file)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use std::fs;
+    use std::io::Write;
+    use tempfile::tempdir;
+
+    fn names(paths: &[PathBuf]) -> Vec<String> {
+        paths
+            .iter()
+            .map(|path| path.file_name().unwrap().to_string_lossy().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_is_session_file() {
+        for name in ["s.jsonl", "s.jsonl.gz", "s.jsonl.1", "s.jsonl.12.gz"] {
+            assert!(is_session_file(Path::new(name)), "{}", name);
+        }
+        for name in [
+            "s.json",
+            "s.jsonl.0",
+            "s.jsonl.bak",
+            "s.jsonl.1.zip",
+            "s.gz",
+        ] {
+            assert!(!is_session_file(Path::new(name)), "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_session_segments_orders_rotations_oldest_first() {
+        let dir = tempdir().unwrap();
+        for name in [
+            "s.jsonl",
+            "s.jsonl.1",
+            "s.jsonl.2.gz",
+            "s.jsonl.4",
+            "other.jsonl.1",
+        ] {
+            fs::write(dir.path().join(name), "").unwrap();
+        }
+
+        let expected = vec!["s.jsonl.2.gz", "s.jsonl.1", "s.jsonl"];
+        assert_eq!(
+            names(&session_segments(&dir.path().join("s.jsonl"))),
+            expected
+        );
+        // Any segment finds the whole session
+        assert_eq!(
+            names(&session_segments(&dir.path().join("s.jsonl.1"))),
+            expected
+        );
+        assert_eq!(
+            primary_session_file(&dir.path().join("s.jsonl.2.gz")),
+            dir.path().join("s.jsonl")
+        );
+
+        // Without a current segment, the latest rotation is primary
+        assert_eq!(
+            primary_session_file(&dir.path().join("other.jsonl.1")),
+            dir.path().join("other.jsonl.1")
+        );
+        let missing = dir.path().join("missing.jsonl");
+        assert_eq!(session_segments(&missing), vec![missing]);
+    }
+
+    #[test]
+    fn test_open_segment_decompresses_gzip() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("s.jsonl.gz");
+        let file = fs::File::create(&path).unwrap();
+        let mut encoder = GzEncoder::new(file, Compression::default());
+        encoder.write_all(b"{\"a\":1}\n{\"b\":2}\n").unwrap();
+        encoder.finish().unwrap();
+
+        let lines: Vec<String> = open_segment(&path)
+            .unwrap()
+            .lines()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(lines, vec!["{\"a\":1}", "{\"b\":2}"]);
+    }
+}
@@ -0,0 +1,356 @@
+//! Timestamp normalization.
+//!
+//! Claude Code JSONL files are not consistent about timestamp formats: most
+//! lines carry RFC 3339 UTC strings with millisecond fractions, but older or
+//! hand-edited files may use offsets, a space separator, no timezone at all,
+//! or numeric epoch values. Comparing those as raw strings sorts them wrongly,
+//! so every timestamp is normalized to a canonical UTC string at parse time.
+
+use chrono::{DateTime, Local, LocalResult, NaiveDate, NaiveDateTime, SecondsFormat, TimeZone, Utc};
+use chrono_tz::Tz;
+use serde_json::Value;
+
+/// Naive (timezone-less) formats accepted as UTC.
+const NAIVE_FORMATS: &[&str] = &[
+    "%Y-%m-%dT%H:%M:%S%.f",
+    "%Y-%m-%d %H:%M:%S%.f",
+    "%Y-%m-%dT%H:%M",
+    "%Y-%m-%d %H:%M",
+];
+
+/// Offset-bearing formats not covered by strict RFC 3339 parsing.
+const OFFSET_FORMATS: &[&str] = &[
+    "%Y-%m-%d %H:%M:%S%.f%:z",
+    "%Y-%m-%dT%H:%M:%S%.f%z",
+    "%Y-%m-%d %H:%M:%S%.f%z",
+];
+
+/// Epoch values above this are treated as milliseconds rather than seconds.
+const EPOCH_MILLIS_THRESHOLD: i64 = 100_000_000_000;
+
+/// A timestamp converted to canonical UTC form.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NormalizedTimestamp {
+    /// Canonical RFC 3339 UTC string (`Z` suffix, fraction only when non-zero).
+    pub utc: String,
+    /// Milliseconds since the Unix epoch.
+    pub epoch_ms: i64,
+}
+
+impl NormalizedTimestamp {
+    fn from_datetime(dt: DateTime<Utc>) -> Self {
+        Self {
+            utc: dt.to_rfc3339_opts(SecondsFormat::AutoSi, true),
+            epoch_ms: dt.timestamp_millis(),
+        }
+    }
+}
+
+/// Parses a timestamp string in any supported format.
+///
+/// Accepts RFC 3339 with `Z` or numeric offsets and any fraction length,
+/// a space instead of `T`, offsets without a colon, naive date-times
+/// (assumed UTC), bare dates, and numeric epoch seconds/milliseconds.
+///
+/// # Returns
+/// * `Some(NormalizedTimestamp)` - Canonical UTC representation
+/// * `None` - If the input is empty or not a recognizable timestamp
+pub fn normalize_timestamp(raw: &str) -> Option<NormalizedTimestamp> {
+    parse_to_utc(raw).map(NormalizedTimestamp::from_datetime)
+}
+
+/// Normalizes a JSON timestamp value, which may be a string or an epoch number.
+pub fn normalize_timestamp_value(value: &Value) -> Option<NormalizedTimestamp> {
+    match value {
+        Value::String(s) => normalize_timestamp(s),
+        Value::Number(n) => n
+            .as_i64()
+            .or_else(|| n.as_f64().map(|f| f as i64))
+            .and_then(epoch_to_utc)
+            .map(NormalizedTimestamp::from_datetime),
+        _ => None,
+    }
+}
+
+/// Returns milliseconds since the Unix epoch for a timestamp string.
+///
+/// Convenience wrapper used for ordering; returns `None` for unparseable input.
+pub fn epoch_millis(raw: &str) -> Option<i64> {
+    parse_to_utc(raw).map(|dt| dt.timestamp_millis())
+}
+
+/// Timezone used to interpret day-based filter boundaries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterTimezone {
+    /// The system timezone of the machine running the app.
+    Local,
+    /// Coordinated Universal Time.
+    Utc,
+    /// An IANA timezone such as `Europe/Berlin`.
+    Named(Tz),
+}
+
+impl FilterTimezone {
+    /// Parses a timezone setting: `"local"`, `"UTC"`, or an IANA name.
+    ///
+    /// Returns `None` for unknown timezone names.
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim() {
+            "" | "local" => Some(Self::Local),
+            "UTC" | "utc" | "Z" => Some(Self::Utc),
+            other => other.parse::<Tz>().ok().map(Self::Named),
+        }
+    }
+
+    /// Returns the `YYYY-MM` month an instant falls in, in this timezone.
+    pub fn month_of_millis(self, millis: i64) -> Option<String> {
+        let utc = Utc.timestamp_millis_opt(millis).single()?;
+        Some(match self {
+            Self::Local => utc.with_timezone(&Local).format("%Y-%m").to_string(),
+            Self::Utc => utc.format("%Y-%m").to_string(),
+            Self::Named(tz) => utc.with_timezone(&tz).format("%Y-%m").to_string(),
+        })
+    }
+
+    /// Returns epoch milliseconds of the first instant of `date` in this timezone.
+    fn start_of_day_millis(self, date: NaiveDate) -> Option<i64> {
+        // Midnight can fall into a DST gap in a few zones; use the first valid hour
+        (0..3).find_map(|hour| {
+            let naive = date.and_hms_opt(hour, 0, 0)?;
+            match self {
+                Self::Local => earliest_millis(Local.from_local_datetime(&naive)),
+                Self::Utc => Some(Utc.from_utc_datetime(&naive).timestamp_millis()),
+                Self::Named(tz) => earliest_millis(tz.from_local_datetime(&naive)),
+            }
+        })
+    }
+}
+
+/// Which end of a date range a filter value describes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DateBoundary {
+    Start,
+    End,
+}
+
+/// Converts a date filter value into an inclusive epoch-millisecond boundary.
+///
+/// Bare dates (`YYYY-MM-DD`) are calendar days in `tz`: a start boundary is
+/// the first millisecond of that day and an end boundary the last one. Full
+/// timestamps are absolute instants and ignore `tz`.
+///
+/// # Returns
+/// * `Some(i64)` - Boundary in milliseconds since the Unix epoch
+/// * `None` - If the value is not a recognizable date or timestamp
+pub fn filter_boundary_millis(raw: &str, tz: FilterTimezone, boundary: DateBoundary) -> Option<i64> {
+    let s = raw.trim();
+    if let Ok(date) = NaiveDate::parse_from_str(s, "%Y-%m-%d") {
+        return match boundary {
+            DateBoundary::Start => tz.start_of_day_millis(date),
+            DateBoundary::End => date
+                .succ_opt()
+                .and_then(|next| tz.start_of_day_millis(next))
+                .map(|ms| ms - 1),
+        };
+    }
+    epoch_millis(s)
+}
+
+fn earliest_millis<T: TimeZone>(result: LocalResult<DateTime<T>>) -> Option<i64> {
+    result.earliest().map(|dt| dt.timestamp_millis())
+}
+
+fn parse_to_utc(raw: &str) -> Option<DateTime<Utc>> {
+    let s = raw.trim();
+    if s.is_empty() {
+        return None;
+    }
+
+    if let Ok(dt) = DateTime::parse_from_rfc3339(s) {
+        return Some(dt.with_timezone(&Utc));
+    }
+
+    for format in OFFSET_FORMATS {
+        if let Ok(dt) = DateTime::parse_from_str(s, format) {
+            return Some(dt.with_timezone(&Utc));
+        }
+    }
+
+    // A trailing `Z` with a non-RFC 3339 body (e.g. space separator)
+    let naive_input = s.strip_suffix('Z').unwrap_or(s);
+    for format in NAIVE_FORMATS {
+        if let Ok(naive) = NaiveDateTime::parse_from_str(naive_input, format) {
+            return Some(Utc.from_utc_datetime(&naive));
+        }
+    }
+
+    if let Ok(date) = NaiveDate::parse_from_str(naive_input, "%Y-%m-%d") {
+        return date.and_hms_opt(0, 0, 0).map(|naive| Utc.from_utc_datetime(&naive));
+    }
+
+    if s.bytes().all(|b| b.is_ascii_digit()) {
+        return s.parse::<i64>().ok().and_then(epoch_to_utc);
+    }
+
+    None
+}
+
+fn epoch_to_utc(value: i64) -> Option<DateTime<Utc>> {
+    if value.abs() >= EPOCH_MILLIS_THRESHOLD {
+        Utc.timestamp_millis_opt(value).single()
+    } else {
+        Utc.timestamp_opt(value, 0).single()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_utc_z_is_preserved() {
+        let ts = normalize_timestamp("2025-01-15T10:30:00Z").unwrap();
+        assert_eq!(ts.utc, "2025-01-15T10:30:00Z");
+        assert_eq!(ts.epoch_ms, 1_736_937_000_000);
+    }
+
+    #[test]
+    fn test_fraction_is_preserved() {
+        let ts = normalize_timestamp("2025-01-15T10:30:00.123Z").unwrap();
+        assert_eq!(ts.utc, "2025-01-15T10:30:00.123Z");
+        assert_eq!(ts.epoch_ms, 1_736_937_000_123);
+    }
+
+    #[test]
+    fn test_offset_converted_to_utc() {
+        let ts = normalize_timestamp("2025-01-15T12:30:00+02:00").unwrap();
+        assert_eq!(ts.utc, "2025-01-15T10:30:00Z");
+    }
+
+    #[test]
+    fn test_offset_without_colon() {
+        let ts = normalize_timestamp("2025-01-15T05:30:00-0500").unwrap();
+        assert_eq!(ts.utc, "2025-01-15T10:30:00Z");
+    }
+
+    #[test]
+    fn test_space_separator_and_naive() {
+        assert_eq!(
+            normalize_timestamp("2025-01-15 10:30:00").unwrap().utc,
+            "2025-01-15T10:30:00Z"
+        );
+        assert_eq!(
+            normalize_timestamp("2025-01-15T10:30:00.5").unwrap().utc,
+            "2025-01-15T10:30:00.500Z"
+        );
+    }
+
+    #[test]
+    fn test_nanosecond_fraction() {
+        let ts = normalize_timestamp("2025-01-15T10:30:00.123456789Z").unwrap();
+        assert_eq!(ts.utc, "2025-01-15T10:30:00.123456789Z");
+        assert_eq!(ts.epoch_ms, 1_736_937_000_123);
+    }
+
+    #[test]
+    fn test_bare_date() {
+        assert_eq!(
+            normalize_timestamp("2025-01-15").unwrap().utc,
+            "2025-01-15T00:00:00Z"
+        );
+    }
+
+    #[test]
+    fn test_epoch_values() {
+        assert_eq!(
+            normalize_timestamp("1736937000").unwrap().utc,
+            "2025-01-15T10:30:00Z"
+        );
+        assert_eq!(
+            normalize_timestamp_value(&json!(1_736_937_000_000i64)).unwrap().utc,
+            "2025-01-15T10:30:00Z"
+        );
+    }
+
+    #[test]
+    fn test_invalid_timestamps() {
+        assert!(normalize_timestamp("").is_none());
+        assert!(normalize_timestamp("not a date").is_none());
+        assert!(normalize_timestamp_value(&json!(true)).is_none());
+    }
+
+    #[test]
+    fn test_mixed_formats_order_by_epoch() {
+        let a = epoch_millis("2025-01-15T10:30:00+02:00").unwrap(); // 08:30 UTC
+        let b = epoch_millis("2025-01-15T09:00:00Z").unwrap();
+        assert!(a < b, "offset timestamp should sort before later UTC time");
+        assert!("2025-01-15T10:30:00+02:00" > "2025-01-15T09:00:00Z");
+    }
+
+    #[test]
+    fn test_filter_timezone_parse() {
+        assert_eq!(FilterTimezone::parse("local"), Some(FilterTimezone::Local));
+        assert_eq!(FilterTimezone::parse("UTC"), Some(FilterTimezone::Utc));
+        assert_eq!(
+            FilterTimezone::parse("America/New_York"),
+            Some(FilterTimezone::Named(chrono_tz::America::New_York))
+        );
+        assert_eq!(FilterTimezone::parse("Mars/Olympus_Mons"), None);
+    }
+
+    #[test]
+    fn test_date_boundaries_in_named_timezone() {
+        let tz = FilterTimezone::parse("America/New_York").unwrap();
+        // 2025-01-15 in New York is 05:00Z on the 15th to 04:59:59.999Z on the 16th
+        let start = filter_boundary_millis("2025-01-15", tz, DateBoundary::Start).unwrap();
+        let end = filter_boundary_millis("2025-01-15", tz, DateBoundary::End).unwrap();
+        assert_eq!(start, epoch_millis("2025-01-15T05:00:00Z").unwrap());
+        assert_eq!(end, epoch_millis("2025-01-16T04:59:59.999Z").unwrap());
+
+        // A late-night chat (23:30 local) is still "today"
+        let late = epoch_millis("2025-01-16T04:30:00Z").unwrap();
+        assert!(start <= late && late <= end);
+    }
+
+    #[test]
+    fn test_date_boundaries_in_utc() {
+        let tz = FilterTimezone::Utc;
+        assert_eq!(
+            filter_boundary_millis("2025-01-15", tz, DateBoundary::Start),
+            epoch_millis("2025-01-15T00:00:00Z")
+        );
+        assert_eq!(
+            filter_boundary_millis("2025-01-15", tz, DateBoundary::End),
+            epoch_millis("2025-01-15T23:59:59.999Z")
+        );
+    }
+
+    #[test]
+    fn test_date_boundary_across_dst_change() {
+        // Europe/Berlin switches to summer time on 2025-03-30, a 23-hour day
+        let tz = FilterTimezone::parse("Europe/Berlin").unwrap();
+        let start = filter_boundary_millis("2025-03-30", tz, DateBoundary::Start).unwrap();
+        let end = filter_boundary_millis("2025-03-30", tz, DateBoundary::End).unwrap();
+        assert_eq!(end - start + 1, 23 * 60 * 60 * 1000);
+    }
+
+    #[test]
+    fn test_month_of_millis() {
+        // New Year's Eve in New York is already January in UTC
+        let millis = epoch_millis("2025-01-01T03:00:00Z").unwrap();
+        assert_eq!(FilterTimezone::Utc.month_of_millis(millis).as_deref(), Some("2025-01"));
+        let tz = FilterTimezone::parse("America/New_York").unwrap();
+        assert_eq!(tz.month_of_millis(millis).as_deref(), Some("2024-12"));
+    }
+
+    #[test]
+    fn test_full_timestamp_boundary_ignores_timezone() {
+        let tz = FilterTimezone::parse("Asia/Tokyo").unwrap();
+        assert_eq!(
+            filter_boundary_millis("2025-01-15T10:30:00Z", tz, DateBoundary::End),
+            Some(1_736_937_000_000)
+        );
+        assert_eq!(filter_boundary_millis("yesterday", tz, DateBoundary::Start), None);
+    }
+}
@@ -0,0 +1,268 @@
+//! Library of reusable prompts.
+//!
+//! Any user message can be saved as a prompt, to mine the best ones from
+//! history. Placeholders in a prompt's body, written `[name]` or
+//! `{{name}}`, are its variables: they're detected when the prompt is read
+//! and can be filled in when it's copied. Prompts don't reference
+//! `conversations`, so deleting a conversation keeps the prompts saved
+//! from it.
+
+use crate::db::sqlite::DbResult;
+use crate::models::{Prompt, PromptExportFormat, PromptInput};
+use regex::{Captures, Regex};
+use rusqlite::{params, Connection, OptionalExtension};
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+/// A placeholder: `{{name}}`, or `[name]` that isn't a Markdown link.
+/// Names start with a letter and may contain letters, digits, spaces,
+/// `_`, and `-`; `[name]` needs at least two characters, so task list
+/// boxes like `[x]` aren't placeholders.
+static PLACEHOLDER_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\{\{\s*([A-Za-z][\w\- ]{0,39}?)\s*\}\}|\[([A-Za-z][\w\- ]{1,39})\]")
+        .expect("Invalid regex pattern")
+});
+
+/// Name of the placeholder a match is, or `None` if it's a Markdown link
+/// text (followed by `(url)` or `[ref]`).
+fn placeholder_name<'t>(text: &str, captures: &Captures<'t>) -> Option<&'t str> {
+    if let Some(name) = captures.get(1) {
+        return Some(name.as_str());
+    }
+    let whole = captures.get(0)?;
+    if text[whole.end()..].starts_with(['(', '[']) {
+        return None;
+    }
+    captures.get(2).map(|name| name.as_str().trim())
+}
+
+/// Names of the placeholders in a prompt body, in order of first use,
+/// without duplicates.
+pub fn detect_variables(body: &str) -> Vec<String> {
+    let mut variables: Vec<String> = Vec::new();
+    for captures in PLACEHOLDER_REGEX.captures_iter(body) {
+        if let Some(name) = placeholder_name(body, &captures) {
+            if !variables.iter().any(|variable| variable == name) {
+                variables.push(name.to_string());
+            }
+        }
+    }
+    variables
+}
+
+/// Replaces the placeholders that have values; others are left as written.
+pub fn fill_variables(body: &str, values: &HashMap<String, String>) -> String {
+    PLACEHOLDER_REGEX
+        .replace_all(body, |captures: &Captures| {
+            placeholder_name(body, captures)
+                .and_then(|name| values.get(name))
+                .cloned()
+                .unwrap_or_else(|| captures[0].to_string())
+        })
+        .into_owned()
+}
+
+const PROMPT_COLUMNS: &str =
+    "id, title, body, source_conversation_id, source_message_id, created_at, updated_at";
+
+fn prompt_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<Prompt> {
+    let body: String = row.get(2)?;
+    Ok(Prompt {
+        id: row.get(0)?,
+        title: row.get(1)?,
+        variables: detect_variables(&body),
+        body,
+        source_conversation_id: row.get(3)?,
+        source_message_id: row.get(4)?,
+        created_at: row.get(5)?,
+        updated_at: row.get(6)?,
+    })
+}
+
+/// Lists prompts, most recently changed first, optionally only those whose
+/// title or body contains `query` (case-insensitively).
+pub fn list_prompts(conn: &Connection, query: Option<&str>) -> DbResult<Vec<Prompt>> {
+    let mut stmt = conn.prepare_cached(&format!(
+        "SELECT {} FROM prompts
+         WHERE ?1 IS NULL OR instr(lower(title || char(10) || body), lower(?1)) > 0
+         ORDER BY updated_at DESC, id DESC",
+        PROMPT_COLUMNS
+    ))?;
+    let prompts = stmt.query_map([query], prompt_from_row)?;
+    Ok(prompts.collect::<Result<_, _>>()?)
+}
+
+/// Reads a prompt.
+pub fn get_prompt(conn: &Connection, id: i64) -> DbResult<Option<Prompt>> {
+    Ok(conn
+        .query_row(
+            &format!("SELECT {} FROM prompts WHERE id = ?1", PROMPT_COLUMNS),
+            [id],
+            prompt_from_row,
+        )
+        .optional()?)
+}
+
+/// Creates a prompt, or updates the one `input.id` names. Returns `None`
+/// if that prompt doesn't exist.
+///
+/// The source of an existing prompt is kept when the input has none.
+pub fn save_prompt(conn: &Connection, input: &PromptInput) -> DbResult<Option<Prompt>> {
+    let now = chrono::Utc::now().to_rfc3339();
+    let id = match input.id {
+        Some(id) => {
+            let updated = conn.execute(
+                "UPDATE prompts
+                 SET title = ?2, body = ?3,
+                     source_conversation_id = COALESCE(?4, source_conversation_id),
+                     source_message_id = COALESCE(?5, source_message_id),
+                     updated_at = ?6
+                 WHERE id = ?1",
+                params![
+                    id,
+                    input.title,
+                    input.body,
+                    input.source_conversation_id,
+                    input.source_message_id,
+                    now
+                ],
+            )?;
+            if updated == 0 {
+                return Ok(None);
+            }
+            id
+        }
+        None => {
+            conn.execute(
+                "INSERT INTO prompts
+                     (title, body, source_conversation_id, source_message_id, created_at, updated_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?5)",
+                params![
+                    input.title,
+                    input.body,
+                    input.source_conversation_id,
+                    input.source_message_id,
+                    now
+                ],
+            )?;
+            conn.last_insert_rowid()
+        }
+    };
+    get_prompt(conn, id)
+}
+
+/// Deletes a prompt. Returns whether it existed.
+pub fn delete_prompt(conn: &Connection, id: i64) -> DbResult<bool> {
+    Ok(conn.execute("DELETE FROM prompts WHERE id = ?1", [id])? > 0)
+}
+
+/// Renders prompts as a Markdown document or a JSON array.
+pub fn render_prompts(prompts: &[Prompt], format: PromptExportFormat) -> String {
+    match format {
+        PromptExportFormat::Json => serde_json::to_string_pretty(prompts).unwrap_or_default(),
+        PromptExportFormat::Markdown => {
+            let mut out = String::from("# Prompts\n");
+            for prompt in prompts {
+                out.push_str(&format!("\n## {}\n\n", prompt.title));
+                if !prompt.variables.is_empty() {
+                    let variables: Vec<String> = prompt
+                        .variables
+                        .iter()
+                        .map(|variable| format!("`{}`", variable))
+                        .collect();
+                    out.push_str(&format!("Variables: {}\n\n", variables.join(", ")));
+                }
+                // Fence longer than any backtick run in the body
+                let longest_run = prompt
+                    .body
+                    .split(|c| c != '`')
+                    .map(str::len)
+                    .max()
+                    .unwrap_or(0);
+                let fence = "`".repeat(longest_run.max(2) + 1);
+                out.push_str(&format!("{}text\n{}\n{}\n", fence, prompt.body, fence));
+            }
+            out
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::sqlite::init_db;
+
+    fn setup() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        init_db(&conn).unwrap();
+        conn
+    }
+
+    fn input(title: &str, body: &str) -> PromptInput {
+        PromptInput {
+            id: None,
+            title: title.to_string(),
+            body: body.to_string(),
+            source_conversation_id: None,
+            source_message_id: None,
+        }
+    }
+
+    #[test]
+    fn test_detect_variables() {
+        assert_eq!(
+            detect_variables(
+                "Review [file] for {{ focus }} issues.\n- [x] done\nSee [the docs](https://x.test) and [file]."
+            ),
+            vec!["file", "focus"]
+        );
+        assert!(detect_variables("let a = items[0]; [ ] todo").is_empty());
+    }
+
+    #[test]
+    fn test_fill_variables_leaves_unknown_placeholders() {
+        let values = HashMap::from([("file".to_string(), "src/main.rs".to_string())]);
+        assert_eq!(
+            fill_variables("Review [file] for {{focus}}; [docs](u)", &values),
+            "Review src/main.rs for {{focus}}; [docs](u)"
+        );
+    }
+
+    #[test]
+    fn test_save_search_and_delete_prompts() {
+        let conn = setup();
+        let review = save_prompt(&conn, &input("Code review", "Review [file] carefully"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(review.variables, vec!["file"]);
+        save_prompt(&conn, &input("Release notes", "Summarize the changes"))
+            .unwrap()
+            .unwrap();
+
+        let found = list_prompts(&conn, Some("REVIEW")).unwrap();
+        assert_eq!(found, vec![review.clone()]);
+        assert_eq!(list_prompts(&conn, None).unwrap().len(), 2);
+
+        let mut update = input("Code review", "Review [file] for [focus]");
+        update.id = Some(review.id);
+        let updated = save_prompt(&conn, &update).unwrap().unwrap();
+        assert_eq!(updated.variables, vec!["file", "focus"]);
+        update.id = Some(999);
+        assert_eq!(save_prompt(&conn, &update).unwrap(), None);
+
+        assert!(delete_prompt(&conn, review.id).unwrap());
+        assert!(!delete_prompt(&conn, review.id).unwrap());
+    }
+
+    #[test]
+    fn test_render_prompts_markdown() {
+        let conn = setup();
+        let prompt = save_prompt(&conn, &input("Fix", "Fix [bug]:\n```\nlog\n```"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            render_prompts(&[prompt], PromptExportFormat::Markdown),
+            "# Prompts\n\n## Fix\n\nVariables: `bug`\n\n````text\nFix [bug]:\n```\nlog\n```\n````\n"
+        );
+    }
+}
@@ -0,0 +1,212 @@
+//! Follow-up reminders on conversations.
+//!
+//! A reminder says when to revisit a conversation, as in "revisit this
+//! debugging thread Friday", with an optional note. Each conversation has
+//! at most one: setting another replaces it, and it's gone once cleared or
+//! once its conversation is deleted. A background thread checks for
+//! reminders coming due and emits `reminder-due` once for each, so the
+//! frontend can notify; due reminders stay listed until they're cleared.
+
+use crate::db::sqlite::{Database, DbResult};
+use crate::events::emit_reminders_due;
+use crate::models::Reminder;
+use chrono::DateTime;
+use rusqlite::{params, Connection, OptionalExtension};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use tauri::AppHandle;
+use tracing::error;
+
+/// How often the scheduler checks for reminders coming due.
+const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+const REMINDER_COLUMNS: &str = "r.conversation_id, c.project_name, c.preview, r.due_at, r.note,
+     r.created_at, r.notified_at
+     FROM reminders r JOIN conversations c ON c.id = r.conversation_id";
+
+fn reminder_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<Reminder> {
+    let due_at: i64 = row.get(3)?;
+    Ok(Reminder {
+        conversation_id: row.get(0)?,
+        project_name: row.get(1)?,
+        preview: row.get(2)?,
+        due_at: DateTime::from_timestamp_millis(due_at)
+            .unwrap_or_default()
+            .to_rfc3339(),
+        note: row.get(4)?,
+        created_at: row.get(5)?,
+        notified_at: row.get(6)?,
+    })
+}
+
+/// Reads a conversation's reminder.
+pub fn get_reminder(conn: &Connection, conversation_id: &str) -> DbResult<Option<Reminder>> {
+    Ok(conn
+        .query_row(
+            &format!("SELECT {} WHERE r.conversation_id = ?1", REMINDER_COLUMNS),
+            [conversation_id],
+            reminder_from_row,
+        )
+        .optional()?)
+}
+
+/// Sets a conversation's reminder for `due_at` (epoch milliseconds),
+/// replacing any it had; the new one is notified again when it comes due.
+/// Returns `None` if the conversation doesn't exist.
+pub fn set_reminder(
+    conn: &Connection,
+    conversation_id: &str,
+    due_at: i64,
+    note: Option<&str>,
+) -> DbResult<Option<Reminder>> {
+    let inserted = conn.execute(
+        "INSERT INTO reminders (conversation_id, due_at, note, created_at)
+         SELECT id, ?2, ?3, ?4 FROM conversations WHERE id = ?1
+         ON CONFLICT(conversation_id) DO UPDATE SET
+             due_at = excluded.due_at, note = excluded.note,
+             created_at = excluded.created_at, notified_at = NULL",
+        params![
+            conversation_id,
+            due_at,
+            note,
+            chrono::Utc::now().to_rfc3339()
+        ],
+    )?;
+    if inserted == 0 {
+        return Ok(None);
+    }
+    get_reminder(conn, conversation_id)
+}
+
+/// Clears a conversation's reminder. Returns whether it had one.
+pub fn clear_reminder(conn: &Connection, conversation_id: &str) -> DbResult<bool> {
+    Ok(conn.execute(
+        "DELETE FROM reminders WHERE conversation_id = ?1",
+        [conversation_id],
+    )? > 0)
+}
+
+/// Lists the reminders due at `now` (epoch milliseconds), earliest first.
+pub fn get_due_reminders(conn: &Connection, now: i64) -> DbResult<Vec<Reminder>> {
+    let mut stmt = conn.prepare_cached(&format!(
+        "SELECT {} WHERE r.due_at <= ?1 ORDER BY r.due_at, r.conversation_id",
+        REMINDER_COLUMNS
+    ))?;
+    let reminders = stmt.query_map([now], reminder_from_row)?;
+    Ok(reminders.collect::<Result<_, _>>()?)
+}
+
+/// Takes the reminders that came due by `now` (epoch milliseconds) and
+/// weren't notified yet, marking them notified.
+pub fn take_new_due_reminders(conn: &Connection, now: i64) -> DbResult<Vec<Reminder>> {
+    let notified_at = chrono::Utc::now().to_rfc3339();
+    let mut due = get_due_reminders(conn, now)?;
+    due.retain(|reminder| reminder.notified_at.is_none());
+    for reminder in &mut due {
+        conn.execute(
+            "UPDATE reminders SET notified_at = ?2 WHERE conversation_id = ?1",
+            params![reminder.conversation_id, notified_at],
+        )?;
+        reminder.notified_at = Some(notified_at.clone());
+    }
+    Ok(due)
+}
+
+/// Starts the background thread that emits `reminder-due` as reminders
+/// come due, including those that came due while the app was closed.
+pub fn start_scheduler(app_handle: AppHandle, db: Arc<Database>) {
+    let spawned = thread::Builder::new()
+        .name("reminder-scheduler".to_string())
+        .spawn(move || loop {
+            let now = chrono::Utc::now().timestamp_millis();
+            match db.with_connection(|conn| take_new_due_reminders(conn, now)) {
+                Ok(due) if due.is_empty() => {}
+                Ok(due) => emit_reminders_due(&app_handle, &due),
+                Err(e) => error!("Reminders: failed to check for due reminders: {}", e),
+            }
+            thread::sleep(CHECK_INTERVAL);
+        });
+    if let Err(e) = spawned {
+        error!("Failed to start reminder scheduler: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::sqlite::init_db;
+
+    const HOUR: i64 = 60 * 60 * 1000;
+
+    fn setup() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("PRAGMA foreign_keys = ON;").unwrap();
+        init_db(&conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO conversations (id, project_path, project_name, start_time, last_time, file_path, file_modified_at, preview)
+             VALUES ('c1', '/p', 'api', '2025-01-15T10:00:00Z', '2025-01-15T10:30:00Z', '/a.jsonl', '', 'Why does the login test flake?'),
+                    ('c2', '/p', 'api', '2025-01-16T10:00:00Z', '2025-01-16T10:30:00Z', '/b.jsonl', '', 'Add a retry');",
+        )
+        .unwrap();
+        conn
+    }
+
+    #[test]
+    fn test_set_replace_and_clear_reminders() {
+        let conn = setup();
+        let now = 1_700_000_000_000;
+        let reminder = set_reminder(&conn, "c1", now + HOUR, Some("Check the CI run"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(reminder.project_name, "api");
+        assert_eq!(reminder.preview, "Why does the login test flake?");
+        assert_eq!(reminder.due_at, "2023-11-14T23:13:20+00:00");
+        assert_eq!(reminder.note.as_deref(), Some("Check the CI run"));
+        assert_eq!(set_reminder(&conn, "missing", now, None).unwrap(), None);
+
+        let replaced = set_reminder(&conn, "c1", now + 2 * HOUR, None)
+            .unwrap()
+            .unwrap();
+        assert_eq!(replaced.note, None);
+        assert_eq!(get_reminder(&conn, "c1").unwrap(), Some(replaced));
+
+        assert!(clear_reminder(&conn, "c1").unwrap());
+        assert!(!clear_reminder(&conn, "c1").unwrap());
+        assert_eq!(get_reminder(&conn, "c1").unwrap(), None);
+
+        // Reminders go with their conversation
+        set_reminder(&conn, "c2", now, None).unwrap();
+        conn.execute("DELETE FROM conversations WHERE id = 'c2'", [])
+            .unwrap();
+        assert!(get_due_reminders(&conn, now).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_due_reminders_are_notified_once() {
+        let conn = setup();
+        let now = 1_700_000_000_000;
+        set_reminder(&conn, "c1", now - HOUR, None).unwrap();
+        set_reminder(&conn, "c2", now + HOUR, None).unwrap();
+
+        let due = take_new_due_reminders(&conn, now).unwrap();
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].conversation_id, "c1");
+        assert!(due[0].notified_at.is_some());
+        assert!(take_new_due_reminders(&conn, now).unwrap().is_empty());
+
+        // Due reminders stay listed until cleared
+        assert_eq!(get_due_reminders(&conn, now).unwrap(), due);
+        let later: Vec<String> = take_new_due_reminders(&conn, now + 2 * HOUR)
+            .unwrap()
+            .into_iter()
+            .map(|reminder| reminder.conversation_id)
+            .collect();
+        assert_eq!(later, vec!["c2"]);
+        assert_eq!(get_due_reminders(&conn, now + 2 * HOUR).unwrap().len(), 2);
+
+        // Setting a reminder again notifies it again
+        set_reminder(&conn, "c1", now, None).unwrap();
+        assert_eq!(take_new_due_reminders(&conn, now).unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,234 @@
+//! Replay of a conversation as it unfolded.
+//!
+//! A replay sends a conversation's messages to the frontend one by one,
+//! over a channel of its own, with the pauses between them as they were
+//! in the session divided by the replay speed. Long pauses (lunch, a
+//! session resumed the next day) are shortened to `MAX_REPLAY_PAUSE`, and
+//! messages without a usable timestamp follow the previous one at once.
+//!
+//! Each replay runs on its own thread and can be stopped by ID; a replay
+//! whose channel is gone (the window was closed or reloaded) stops by
+//! itself.
+
+use crate::models::{Message, ReplayEvent};
+use crate::parser::epoch_millis;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Replay speed when none is given: as fast as the session went.
+pub const DEFAULT_REPLAY_SPEED: f64 = 1.0;
+
+/// Fastest replay speed accepted.
+pub const MAX_REPLAY_SPEED: f64 = 1000.0;
+
+/// Longest pause between two messages in a replay, after scaling.
+pub const MAX_REPLAY_PAUSE: Duration = Duration::from_secs(5);
+
+/// How often a pausing replay checks whether it was stopped.
+const STOP_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Computes the pause before each message of a replay at `speed`: the time
+/// since the previous message divided by `speed`, at most
+/// `MAX_REPLAY_PAUSE`. The first message comes at once.
+pub fn replay_pauses(messages: &[Message], speed: f64) -> Vec<Duration> {
+    let mut previous: Option<i64> = None;
+    messages
+        .iter()
+        .map(|message| {
+            let sent = epoch_millis(&message.timestamp);
+            let pause = match (previous, sent) {
+                (Some(previous), Some(sent)) if sent > previous => {
+                    Duration::from_secs_f64((sent - previous) as f64 / 1000.0 / speed)
+                        .min(MAX_REPLAY_PAUSE)
+                }
+                _ => Duration::ZERO,
+            };
+            if sent.is_some() {
+                previous = sent.max(previous);
+            }
+            pause
+        })
+        .collect()
+}
+
+/// Replays in progress, by ID.
+#[derive(Default)]
+pub struct Replays {
+    stopped: Mutex<HashMap<String, Arc<AtomicBool>>>,
+    next_id: AtomicU64,
+}
+
+impl Replays {
+    /// Registers a replay and returns its ID with its stop flag.
+    pub fn begin(&self) -> (String, Arc<AtomicBool>) {
+        let id = format!("replay-{}", self.next_id.fetch_add(1, Ordering::Relaxed) + 1);
+        let stopped = Arc::new(AtomicBool::new(false));
+        self.lock().insert(id.clone(), Arc::clone(&stopped));
+        (id, stopped)
+    }
+
+    /// Stops a replay. Returns false if it isn't running (anymore).
+    pub fn stop(&self, id: &str) -> bool {
+        match self.lock().get(id) {
+            Some(stopped) => {
+                stopped.store(true, Ordering::Relaxed);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Stops all replays in progress.
+    pub fn stop_all(&self) {
+        for stopped in self.lock().values() {
+            stopped.store(true, Ordering::Relaxed);
+        }
+    }
+
+    /// Number of replays in progress.
+    pub fn running(&self) -> usize {
+        self.lock().len()
+    }
+
+    fn finish(&self, id: &str) {
+        self.lock().remove(id);
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, Arc<AtomicBool>>> {
+        self.stopped.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Waits `pause` unless the replay is stopped first. Returns whether it
+/// was stopped.
+fn wait(pause: Duration, stopped: &AtomicBool) -> bool {
+    let until = Instant::now() + pause;
+    loop {
+        if stopped.load(Ordering::Relaxed) {
+            return true;
+        }
+        let left = until.saturating_duration_since(Instant::now());
+        if left.is_zero() {
+            return false;
+        }
+        thread::sleep(left.min(STOP_POLL_INTERVAL));
+    }
+}
+
+/// Plays a replay registered with `Replays::begin`: `Started`, each
+/// message after its pause, then `Finished`. `send` returns false when the
+/// events can't be delivered anymore, which stops the replay.
+pub fn play(
+    replays: &Replays,
+    id: &str,
+    stopped: &AtomicBool,
+    messages: Vec<Message>,
+    pauses: Vec<Duration>,
+    send: impl Fn(ReplayEvent) -> bool,
+) {
+    let mut delivered = send(ReplayEvent::Started {
+        replay_id: id.to_string(),
+        message_count: messages.len(),
+        duration_millis: pauses.iter().sum::<Duration>().as_millis() as u64,
+    });
+    let mut was_stopped = false;
+    for (message, pause) in messages.into_iter().zip(pauses) {
+        if !delivered {
+            break;
+        }
+        if wait(pause, stopped) {
+            was_stopped = true;
+            break;
+        }
+        delivered = send(ReplayEvent::Message { message });
+    }
+    // Unregistered first, so a replay is no longer running once it's finished
+    replays.finish(id);
+    if delivered {
+        send(ReplayEvent::Finished { stopped: was_stopped });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::MessageRole;
+
+    fn message(id: &str, timestamp: &str) -> Message {
+        Message {
+            id: id.to_string(),
+            ordinal: 0,
+            role: MessageRole::User,
+            content: Vec::new(),
+            timestamp: timestamp.to_string(),
+            token_count: None,
+            collapsed_reminders: 0,
+            is_meta: false,
+        }
+    }
+
+    #[test]
+    fn test_replay_pauses_scale_and_cap_gaps() {
+        let messages = vec![
+            message("a", "2025-01-15T10:00:00Z"),
+            message("b", "2025-01-15T10:00:04Z"),
+            message("c", ""),
+            message("d", "2025-01-15T10:00:02Z"),
+            message("e", "2025-01-15T10:00:10Z"),
+            message("f", "2025-01-15T12:00:00Z"),
+        ];
+        let pauses = replay_pauses(&messages, 2.0);
+        let millis: Vec<u128> = pauses.iter().map(Duration::as_millis).collect();
+        // Gaps are halved; missing and out-of-order timestamps don't pause,
+        // and a two-hour gap is capped
+        assert_eq!(millis, vec![0, 2000, 0, 0, 3000, 5000]);
+    }
+
+    #[test]
+    fn test_play_sends_messages_and_stops() {
+        let replays = Replays::default();
+        let (id, stopped) = replays.begin();
+        let messages = vec![
+            message("a", "2025-01-15T10:00:00Z"),
+            message("b", "2025-01-15T10:00:01Z"),
+        ];
+        let pauses = replay_pauses(&messages, 100.0);
+        let events = Mutex::new(Vec::new());
+        play(&replays, &id, &stopped, messages.clone(), pauses, |event| {
+            events.lock().unwrap().push(event);
+            true
+        });
+        let events = events.into_inner().unwrap();
+        assert_eq!(events.len(), 4);
+        assert!(matches!(
+            &events[0],
+            ReplayEvent::Started { message_count: 2, duration_millis: 10, .. }
+        ));
+        assert!(matches!(&events[2], ReplayEvent::Message { message } if message.id == "b"));
+        assert!(matches!(events[3], ReplayEvent::Finished { stopped: false }));
+        assert_eq!(replays.running(), 0);
+        assert!(!replays.stop(&id));
+
+        // A stopped replay ends without sending the rest
+        let (id, stopped) = replays.begin();
+        assert!(replays.stop(&id));
+        let events = Mutex::new(Vec::new());
+        play(
+            &replays,
+            &id,
+            &stopped,
+            messages,
+            vec![Duration::ZERO, Duration::from_secs(60)],
+            |event| {
+                events.lock().unwrap().push(event);
+                true
+            },
+        );
+        let events = events.into_inner().unwrap();
+        assert_eq!(events.len(), 2);
+        assert!(matches!(events[1], ReplayEvent::Finished { stopped: true }));
+    }
+}
@@ -0,0 +1,136 @@
+//! Facet counts for search results.
+//!
+//! Counts cover the conversations a search returned, so the numbers on the
+//! filter chips match the list next to them.
+
+use crate::db::DbResult;
+use crate::models::{FacetCount, SearchFacets};
+use crate::parser::FilterTimezone;
+use rusqlite::{Connection, OptionalExtension};
+use std::collections::HashMap;
+
+/// Sorts counts by count, most first, then by value.
+pub fn by_count(counts: HashMap<String, i64>) -> Vec<FacetCount> {
+    let mut facets: Vec<FacetCount> = counts
+        .into_iter()
+        .map(|(value, count)| FacetCount { value, count })
+        .collect();
+    facets.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.value.cmp(&b.value)));
+    facets
+}
+
+/// Counts the conversations per project, tag, and month of last activity.
+///
+/// Months are in `tz`, like date filters. Unknown IDs are ignored.
+pub fn search_facets(
+    conn: &Connection,
+    conversation_ids: &[String],
+    tz: FilterTimezone,
+) -> DbResult<SearchFacets> {
+    let mut conversation_stmt =
+        conn.prepare_cached("SELECT project_name, last_epoch FROM conversations WHERE id = ?1")?;
+    let mut tags_stmt =
+        conn.prepare_cached("SELECT tag FROM conversation_tags WHERE conversation_id = ?1")?;
+
+    let mut projects: HashMap<String, i64> = HashMap::new();
+    let mut tags: HashMap<String, i64> = HashMap::new();
+    let mut months: HashMap<String, i64> = HashMap::new();
+
+    for id in conversation_ids {
+        let row: Option<(String, Option<i64>)> = conversation_stmt
+            .query_row([id], |row| Ok((row.get(0)?, row.get(1)?)))
+            .optional()?;
+        let Some((project_name, last_epoch)) = row else {
+            continue;
+        };
+
+        *projects.entry(project_name).or_default() += 1;
+        if let Some(month) = last_epoch.and_then(|millis| tz.month_of_millis(millis)) {
+            *months.entry(month).or_default() += 1;
+        }
+        let conversation_tags = tags_stmt.query_map([id], |row| row.get::<_, String>(0))?;
+        for tag in conversation_tags {
+            *tags.entry(tag?).or_default() += 1;
+        }
+    }
+
+    let mut months: Vec<FacetCount> = months
+        .into_iter()
+        .map(|(value, count)| FacetCount { value, count })
+        .collect();
+    months.sort_by(|a, b| b.value.cmp(&a.value));
+
+    Ok(SearchFacets {
+        projects: by_count(projects),
+        tags: by_count(tags),
+        months,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::sqlite::init_db;
+
+    fn setup() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        init_db(&conn).unwrap();
+        let conversations = [
+            ("c1", "alpha", "2025-01-31T23:30:00Z"),
+            ("c2", "alpha", "2025-02-10T12:00:00Z"),
+            ("c3", "beta", "2025-02-11T12:00:00Z"),
+        ];
+        for (id, project, last_time) in conversations {
+            conn.execute(
+                "INSERT INTO conversations (id, project_path, project_name, start_time, last_time, file_path, file_modified_at)
+                 VALUES (?1, '/p', ?2, ?3, ?3, '/p/s.jsonl', '')",
+                rusqlite::params![id, project, last_time],
+            )
+            .unwrap();
+        }
+        for (id, tag) in [("c1", "bug"), ("c2", "bug"), ("c2", "ui"), ("c3", "ui")] {
+            conn.execute(
+                "INSERT INTO conversation_tags (conversation_id, tag, created_at) VALUES (?1, ?2, '')",
+                [id, tag],
+            )
+            .unwrap();
+        }
+        conn
+    }
+
+    fn counts(facets: &[FacetCount]) -> Vec<(&str, i64)> {
+        facets.iter().map(|f| (f.value.as_str(), f.count)).collect()
+    }
+
+    #[test]
+    fn test_counts_projects_tags_and_months() {
+        let conn = setup();
+        let ids = ["c1", "c2", "c3", "missing"].map(String::from);
+        let facets = search_facets(&conn, &ids, FilterTimezone::Utc).unwrap();
+
+        assert_eq!(counts(&facets.projects), vec![("alpha", 2), ("beta", 1)]);
+        assert_eq!(counts(&facets.tags), vec![("bug", 2), ("ui", 2)]);
+        assert_eq!(counts(&facets.months), vec![("2025-02", 2), ("2025-01", 1)]);
+    }
+
+    #[test]
+    fn test_months_use_timezone() {
+        let conn = setup();
+        let tz = FilterTimezone::parse("Asia/Tokyo").unwrap();
+        let facets = search_facets(&conn, &["c1".to_string()], tz).unwrap();
+        assert_eq!(counts(&facets.months), vec![("2025-02", 1)]);
+    }
+
+    #[test]
+    fn test_only_given_conversations_are_counted() {
+        let conn = setup();
+        let facets = search_facets(&conn, &["c3".to_string()], FilterTimezone::Utc).unwrap();
+        assert_eq!(counts(&facets.projects), vec![("beta", 1)]);
+        assert_eq!(counts(&facets.tags), vec![("ui", 1)]);
+
+        assert_eq!(
+            search_facets(&conn, &[], FilterTimezone::Utc).unwrap(),
+            SearchFacets::default()
+        );
+    }
+}
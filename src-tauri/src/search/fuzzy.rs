@@ -0,0 +1,304 @@
+//! Fuzzy matching for the quick-switcher.
+//!
+//! Independent of FTS: candidates (conversation previews, project names,
+//! tags) are short, so they're scored in memory. Scoring is skim-style: the
+//! query must appear as a case-insensitive subsequence, and matches on word
+//! starts and consecutive runs score higher. Queries that aren't a
+//! subsequence, such as "conevrsation", fall back to per-word edit distance
+//! so small typos still match, below any subsequence match.
+
+/// Score of each matched character.
+const MATCH_SCORE: i64 = 16;
+
+/// Bonus for matching right after the previous matched character.
+const CONSECUTIVE_BONUS: i64 = 8;
+
+/// Bonus for matching at the start of a word (or of the candidate).
+const WORD_START_BONUS: i64 = 10;
+
+/// Bonus for matching an uppercase letter following a lowercase one.
+const CAMEL_CASE_BONUS: i64 = 8;
+
+/// Penalty for the first skipped character of a gap.
+const GAP_START_PENALTY: i64 = 3;
+
+/// Penalty for each further skipped character.
+const GAP_EXTEND_PENALTY: i64 = 1;
+
+/// Penalty for each edit in a typo match.
+const TYPO_PENALTY: i64 = 8;
+
+const NONE: i64 = i64::MIN / 2;
+
+/// A successful fuzzy match.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FuzzyMatch {
+    /// Higher is better.
+    pub score: i64,
+    /// Matched character positions in the candidate, in UTF-16 code units.
+    /// Empty for typo matches.
+    pub positions: Vec<usize>,
+}
+
+/// Scores `candidate` against `query`, or returns `None` if it doesn't match.
+pub fn fuzzy_match(query: &str, candidate: &str) -> Option<FuzzyMatch> {
+    let folded: Vec<char> = query
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .collect();
+    if folded.is_empty() {
+        return Some(FuzzyMatch {
+            score: 0,
+            positions: Vec::new(),
+        });
+    }
+
+    let chars: Vec<char> = candidate.chars().collect();
+    subsequence_match(&folded, &chars).or_else(|| typo_match(query, candidate))
+}
+
+fn fold(c: char) -> char {
+    c.to_lowercase().next().unwrap_or(c)
+}
+
+/// Bonus for matching `chars[j]`, based on the character before it.
+fn position_bonus(chars: &[char], j: usize) -> i64 {
+    if j == 0 || !chars[j - 1].is_alphanumeric() {
+        WORD_START_BONUS
+    } else if chars[j - 1].is_lowercase() && chars[j].is_uppercase() {
+        CAMEL_CASE_BONUS
+    } else {
+        0
+    }
+}
+
+/// Finds the best-scoring alignment of `query` as a subsequence of `chars`.
+fn subsequence_match(query: &[char], chars: &[char]) -> Option<FuzzyMatch> {
+    // Cheap rejection before the full alignment
+    let mut remaining = query.iter().peekable();
+    for &c in chars {
+        if remaining.peek().is_some_and(|&&q| q == fold(c)) {
+            remaining.next();
+        }
+    }
+    if remaining.peek().is_some() {
+        return None;
+    }
+
+    let (n, m) = (query.len(), chars.len());
+    // score[i][j]: best score with query[i] matched at chars[j]; from[i][j]:
+    // where query[i - 1] was matched
+    let mut score = vec![vec![NONE; m]; n];
+    let mut from = vec![vec![0usize; m]; n];
+
+    for i in 0..n {
+        // Best predecessor before j, with its gap penalty applied
+        let mut best = NONE;
+        let mut best_at = 0;
+        for j in 0..m {
+            if i > 0 && j > 0 {
+                let decayed = best - GAP_EXTEND_PENALTY;
+                let opened = score[i - 1][j - 1] - GAP_START_PENALTY;
+                if opened >= decayed {
+                    best = opened;
+                    best_at = j - 1;
+                } else {
+                    best = decayed;
+                }
+            }
+            if fold(chars[j]) != query[i] {
+                continue;
+            }
+
+            let gained = MATCH_SCORE + position_bonus(chars, j);
+            if i == 0 {
+                // Later starts cost a little, capped so long candidates still rank
+                score[0][j] = gained - (j as i64).min(MATCH_SCORE);
+                continue;
+            }
+            let consecutive = if j > 0 { score[i - 1][j - 1] } else { NONE };
+            if consecutive > NONE && consecutive + CONSECUTIVE_BONUS >= best {
+                score[i][j] = consecutive + CONSECUTIVE_BONUS + gained;
+                from[i][j] = j - 1;
+            } else if best > NONE {
+                score[i][j] = best + gained;
+                from[i][j] = best_at;
+            }
+        }
+    }
+
+    let (mut j, &best) = score[n - 1]
+        .iter()
+        .enumerate()
+        .max_by_key(|&(j, &s)| (s, std::cmp::Reverse(j)))?;
+    if best <= NONE {
+        return None;
+    }
+
+    let mut matched = vec![0; n];
+    for i in (0..n).rev() {
+        matched[i] = j;
+        j = from[i][j];
+    }
+
+    // Character indices to UTF-16 offsets
+    let mut offsets = Vec::with_capacity(m);
+    let mut offset = 0;
+    for c in chars {
+        offsets.push(offset);
+        offset += c.len_utf16();
+    }
+
+    Some(FuzzyMatch {
+        score: best,
+        positions: matched.into_iter().map(|j| offsets[j]).collect(),
+    })
+}
+
+/// Edits allowed in a word of `len` characters.
+fn allowed_typos(len: usize) -> usize {
+    match len {
+        0..=3 => 0,
+        4..=7 => 1,
+        _ => 2,
+    }
+}
+
+/// Matches each query word to a candidate word within a few edits.
+fn typo_match(query: &str, candidate: &str) -> Option<FuzzyMatch> {
+    let words: Vec<Vec<char>> = candidate
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(|w| w.chars().map(fold).collect())
+        .collect();
+
+    let mut edits = 0;
+    let mut query_len = 0;
+    for term in query
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|t| !t.is_empty())
+    {
+        let term: Vec<char> = term.chars().map(fold).collect();
+        let allowed = allowed_typos(term.len());
+        let distance = words
+            .iter()
+            .map(|word| {
+                // Compare against the whole word and its prefix, so partially
+                // typed words match too
+                let prefix = &word[..word.len().min(term.len())];
+                edit_distance(&term, word).min(edit_distance(&term, prefix))
+            })
+            .min()?;
+        if distance > allowed {
+            return None;
+        }
+        edits += distance;
+        query_len += term.len();
+    }
+    if query_len == 0 {
+        return None;
+    }
+
+    Some(FuzzyMatch {
+        score: MATCH_SCORE * query_len as i64 / 2 - TYPO_PENALTY * edits as i64,
+        positions: Vec::new(),
+    })
+}
+
+/// Optimal string alignment distance: edits where swapping two adjacent
+/// characters counts as one.
+fn edit_distance(a: &[char], b: &[char]) -> usize {
+    let mut rows = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for (i, row) in rows.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in rows[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            let mut d = (rows[i - 1][j] + 1)
+                .min(rows[i][j - 1] + 1)
+                .min(rows[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                d = d.min(rows[i - 2][j - 2] + 1);
+            }
+            rows[i][j] = d;
+        }
+    }
+    rows[a.len()][b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn score(query: &str, candidate: &str) -> Option<i64> {
+        fuzzy_match(query, candidate).map(|m| m.score)
+    }
+
+    #[test]
+    fn test_subsequence_positions() {
+        let m = fuzzy_match("cfg", "Refactor config loader").unwrap();
+        assert_eq!(m.positions, vec![9, 12, 14]);
+
+        // Offsets are UTF-16 code units
+        let m = fuzzy_match("db", "🚀 Fix db").unwrap();
+        assert_eq!(m.positions, vec![7, 8]);
+    }
+
+    #[test]
+    fn test_prefers_word_starts_and_runs() {
+        let word_start = score("lo", "Fix login bug").unwrap();
+        let mid_word = score("lo", "Fix a hello bug").unwrap();
+        assert!(word_start > mid_word);
+
+        let run = score("config", "config loader").unwrap();
+        let scattered = score("config", "counted on new figs").unwrap();
+        assert!(run > scattered);
+    }
+
+    #[test]
+    fn test_matching_is_case_insensitive() {
+        assert!(fuzzy_match("DARK", "Add a dark mode toggle").is_some());
+        assert!(fuzzy_match("dm", "DarkMode").is_some());
+    }
+
+    #[test]
+    fn test_typos_match_below_subsequences() {
+        let typo = fuzzy_match("conevrsation", "Conversation list is slow").unwrap();
+        assert!(typo.positions.is_empty());
+        let exact = score("conversation", "Conversation list is slow").unwrap();
+        assert!(exact > typo.score);
+
+        // Partially typed words with a typo
+        assert!(fuzzy_match("kuberentes dep", "Kubernetes deployment fails").is_some());
+    }
+
+    #[test]
+    fn test_rejects_unrelated_candidates() {
+        assert_eq!(score("zebra", "Add a dark mode toggle"), None);
+        // Too many edits for a short word
+        assert_eq!(score("xyz", "abc"), None);
+    }
+
+    #[test]
+    fn test_empty_query_matches_everything() {
+        assert_eq!(score("", "anything"), Some(0));
+        assert_eq!(score("  ", "anything"), Some(0));
+    }
+
+    #[test]
+    fn test_edit_distance_counts_transpositions_once() {
+        let chars = |s: &str| s.chars().collect::<Vec<_>>();
+        assert_eq!(
+            edit_distance(&chars("conevrsation"), &chars("conversation")),
+            1
+        );
+        assert_eq!(edit_distance(&chars("kitten"), &chars("sitting")), 3);
+        assert_eq!(edit_distance(&chars(""), &chars("abc")), 3);
+    }
+}
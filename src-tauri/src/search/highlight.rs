@@ -0,0 +1,182 @@
+//! Match spans for highlighting search terms in a conversation.
+//!
+//! Matching mirrors how `search_conversations` queries FTS5: text is split
+//! into alphanumeric tokens and compared case-insensitively, and each term
+//! of the query (see [`parse_terms`]) is highlighted where it occurs. The
+//! last term also matches tokens starting with it (FTS5 prefix query); a
+//! quoted phrase matches its words consecutively.
+//!
+//! Span offsets are UTF-16 code units, so the frontend can pass them
+//! straight to `String.prototype.slice`.
+
+use crate::models::{Conversation, ConversationMatch, MatchSpan};
+use crate::search::parse_terms;
+
+/// A token of the text: UTF-16 offsets and its lowercase form.
+struct Token {
+    start: usize,
+    end: usize,
+    folded: String,
+}
+
+/// Splits text into alphanumeric runs, like the FTS5 `unicode61` tokenizer.
+fn tokenize(text: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut current: Option<Token> = None;
+    let mut offset = 0;
+
+    for c in text.chars() {
+        let width = c.len_utf16();
+        if c.is_alphanumeric() {
+            let token = current.get_or_insert_with(|| Token {
+                start: offset,
+                end: offset,
+                folded: String::new(),
+            });
+            token.end = offset + width;
+            token.folded.extend(c.to_lowercase());
+        } else if let Some(token) = current.take() {
+            tokens.push(token);
+        }
+        offset += width;
+    }
+    tokens.extend(current);
+    tokens
+}
+
+/// Finds the spans of `text` matching `query`, in order.
+pub fn find_match_spans(text: &str, query: &str) -> Vec<MatchSpan> {
+    let tokens = tokenize(text);
+    let mut spans: Vec<MatchSpan> = Vec::new();
+    for term in parse_terms(query) {
+        let words: Vec<String> = tokenize(&term.text).into_iter().map(|t| t.folded).collect();
+        if !words.is_empty() {
+            spans.extend(phrase_spans(&tokens, &words, term.prefix));
+        }
+    }
+
+    // Terms can overlap ("pool" and "pool size"); keep the earliest
+    spans.sort_by_key(|span| (span.start, std::cmp::Reverse(span.end)));
+    let mut merged: Vec<MatchSpan> = Vec::with_capacity(spans.len());
+    for span in spans {
+        if merged.last().is_none_or(|last| span.start >= last.end) {
+            merged.push(span);
+        }
+    }
+    merged
+}
+
+/// Finds the runs of tokens equal to `words`; with `prefix`, the last word
+/// only has to start the token.
+fn phrase_spans(tokens: &[Token], words: &[String], prefix: bool) -> Vec<MatchSpan> {
+    let last = words.len() - 1;
+    let mut spans = Vec::new();
+    let mut i = 0;
+    while i + words.len() <= tokens.len() {
+        let window = &tokens[i..i + words.len()];
+        let matched = window
+            .iter()
+            .zip(words)
+            .enumerate()
+            .all(|(j, (token, word))| {
+                if prefix && j == last {
+                    token.folded.starts_with(word.as_str())
+                } else {
+                    token.folded == *word
+                }
+            });
+        if matched {
+            spans.push(MatchSpan {
+                start: window[0].start,
+                end: window[last].end,
+            });
+            i += words.len();
+        } else {
+            i += 1;
+        }
+    }
+    spans
+}
+
+/// Finds all matches of `query` in a conversation's messages, in reading
+/// order, for next/previous navigation.
+pub fn find_conversation_matches(
+    conversation: &Conversation,
+    query: &str,
+) -> Vec<ConversationMatch> {
+    let mut matches = Vec::new();
+    for message in &conversation.messages {
+        for (block_index, block) in message.content.iter().enumerate() {
+            for span in find_match_spans(&block.content, query) {
+                matches.push(ConversationMatch {
+                    message_ordinal: message.ordinal,
+                    block_index,
+                    start: span.start,
+                    end: span.end,
+                });
+            }
+        }
+    }
+    matches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spans(text: &str, query: &str) -> Vec<(usize, usize)> {
+        find_match_spans(text, query)
+            .into_iter()
+            .map(|span| (span.start, span.end))
+            .collect()
+    }
+
+    #[test]
+    fn test_single_word_matches_prefixes() {
+        assert_eq!(
+            spans("Refactor the config loader; configs are cached", "config"),
+            vec![(13, 19), (28, 35)]
+        );
+        assert_eq!(spans("Reconfigure it", "config"), vec![]);
+    }
+
+    #[test]
+    fn test_matching_is_case_insensitive() {
+        assert_eq!(spans("SQLite and sqlite", "SqLite"), vec![(0, 6), (11, 17)]);
+    }
+
+    #[test]
+    fn test_phrase_matches_consecutive_words() {
+        let text = "The connection pool leaks. Connection, pool: still leaking.";
+        assert_eq!(spans(text, "\"connection pool\""), vec![(4, 19), (27, 43)]);
+        assert_eq!(spans("pool connection", "\"connection pool\""), vec![]);
+    }
+
+    #[test]
+    fn test_terms_match_anywhere_and_last_by_prefix() {
+        assert_eq!(
+            spans("pool the connections", "connection pool"),
+            vec![(0, 4)]
+        );
+        assert_eq!(
+            spans("pool the connections", "pool connection"),
+            vec![(0, 4), (9, 20)]
+        );
+        // Overlapping terms highlight once
+        assert_eq!(spans("pool size", "size \"pool size\""), vec![(0, 9)]);
+    }
+
+    #[test]
+    fn test_offsets_are_utf16_code_units() {
+        // 🚀 is two UTF-16 code units, each CJK character one
+        let text = "🚀 日本語 test";
+        assert_eq!(spans(text, "test"), vec![(7, 11)]);
+        assert_eq!(spans(text, "日本"), vec![(3, 6)]);
+    }
+
+    #[test]
+    fn test_empty_query_matches_nothing() {
+        assert!(find_match_spans("anything", "").is_empty());
+        assert!(find_match_spans("anything", "  ... ").is_empty());
+    }
+}
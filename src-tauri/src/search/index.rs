@@ -4,7 +4,9 @@
 //! index for conversation content and metadata.
 
 use crate::db::{Database, DbError, DbResult};
+use crate::models::SearchTokenizer;
 use crate::parser::{ParsedConversation, RawContent, RawMessageType};
+use crate::search::partition::project_key;
 use rusqlite::Connection;
 use tracing::{debug, info, warn};
 
@@ -48,16 +50,7 @@ pub fn build_search_index(db: &Database, conversations: &[ParsedConversation]) -
 
             match rowid {
                 Some(rid) => {
-                    // Update existing FTS entry (delete then insert)
-                    tx.execute(
-                        "DELETE FROM conversations_fts WHERE rowid = ?1",
-                        [rid],
-                    )?;
-
-                    tx.execute(
-                        "INSERT INTO conversations_fts(rowid, content, project_name) VALUES (?1, ?2, ?3)",
-                        rusqlite::params![rid, content, conversation.project_name],
-                    )?;
+                    write_index_entry(&tx, rid, &content, &conversation.project_name)?;
 
                     indexed_count += 1;
                     debug!("Updated FTS index for conversation {}", conversation.id);
@@ -90,13 +83,14 @@ pub fn rebuild_search_index(db: &Database) -> DbResult<usize> {
 
         // Clear existing FTS index
         tx.execute("DELETE FROM conversations_fts", [])?;
+        tx.execute("DELETE FROM conversations_trigram", [])?;
 
         // Get all conversations with their content
         // Note: We need to re-parse files to get full content, or store content summary
         // For now, we'll index what we have in the database (project_name + preview)
         // Collect all data first, then drop the statement before inserting
         let conversations_data: Vec<(i64, String, String)> = {
-            let mut stmt = tx.prepare(
+            let mut stmt = tx.prepare_cached(
                 "SELECT rowid, project_name, preview FROM conversations"
             )?;
 
@@ -116,10 +110,7 @@ pub fn rebuild_search_index(db: &Database) -> DbResult<usize> {
         let mut indexed_count = 0;
 
         for (rowid, project_name, preview) in conversations_data {
-            tx.execute(
-                "INSERT INTO conversations_fts(rowid, content, project_name) VALUES (?1, ?2, ?3)",
-                rusqlite::params![rowid, preview, project_name],
-            )?;
+            insert_index_entry(&tx, rowid, &preview, &project_name)?;
 
             indexed_count += 1;
         }
@@ -131,6 +122,47 @@ pub fn rebuild_search_index(db: &Database) -> DbResult<usize> {
     })
 }
 
+/// Recreates the FTS table with `tokenizer` and refills it from the
+/// conversations table, like `rebuild_search_index`, partitioned by project.
+///
+/// The tokenizer is part of the table definition, so changing it needs a new
+/// table. Run inside a transaction so searches never see a missing table.
+pub fn rebuild_fts_table(conn: &Connection, tokenizer: SearchTokenizer) -> DbResult<usize> {
+    conn.execute_batch(&format!(
+        r#"
+        DROP TABLE IF EXISTS conversations_fts;
+        CREATE VIRTUAL TABLE conversations_fts USING fts5(
+            content,
+            project_name,
+            project_key,
+            tokenize='{}',
+            content='',
+            contentless_delete=1
+        );
+        "#,
+        tokenizer.fts5_option()
+    ))?;
+    let rows: Vec<(i64, String, String)> = {
+        let mut stmt = conn.prepare("SELECT rowid, preview, project_name FROM conversations")?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?;
+        rows.collect::<Result<_, _>>()?
+    };
+    let mut insert = conn.prepare(
+        "INSERT INTO conversations_fts(rowid, content, project_name, project_key) VALUES (?1, ?2, ?3, ?4)",
+    )?;
+    for (rowid, preview, project_name) in &rows {
+        insert.execute(rusqlite::params![rowid, preview, project_name, project_key(project_name)])?;
+    }
+    let indexed = rows.len();
+
+    info!(
+        "Rebuilt search index with the {} tokenizer ({} entries)",
+        tokenizer.as_str(),
+        indexed
+    );
+    Ok(indexed)
+}
+
 /// Indexes a single conversation in the FTS index.
 ///
 /// This is useful for incremental updates when a single conversation changes.
@@ -150,23 +182,15 @@ pub fn index_conversation_content(
     project_name: &str,
 ) -> DbResult<()> {
     // Get the rowid for this conversation
-    let rowid: i64 = conn.query_row(
-        "SELECT rowid FROM conversations WHERE id = ?1",
-        [conversation_id],
-        |row| row.get(0),
-    ).map_err(|e| {
-        warn!("Conversation {} not found: {}", conversation_id, e);
-        DbError::Sqlite(e)
-    })?;
-
-    // Delete existing entry if any
-    conn.execute("DELETE FROM conversations_fts WHERE rowid = ?1", [rowid])?;
-
-    // Insert new entry
-    conn.execute(
-        "INSERT INTO conversations_fts(rowid, content, project_name) VALUES (?1, ?2, ?3)",
-        rusqlite::params![rowid, content, project_name],
-    )?;
+    let rowid: i64 = conn
+        .prepare_cached("SELECT rowid FROM conversations WHERE id = ?1")?
+        .query_row([conversation_id], |row| row.get(0))
+        .map_err(|e| {
+            warn!("Conversation {} not found: {}", conversation_id, e);
+            DbError::Sqlite(e)
+        })?;
+
+    write_index_entry(conn, rowid, content, project_name)?;
 
     debug!("Indexed conversation {} in FTS", conversation_id);
     Ok(())
@@ -184,13 +208,39 @@ pub fn remove_from_index(conn: &Connection, conversation_id: &str) -> DbResult<(
         .ok();
 
     if let Some(rid) = rowid {
-        conn.execute("DELETE FROM conversations_fts WHERE rowid = ?1", [rid])?;
+        delete_index_entry(conn, rid)?;
         debug!("Removed conversation {} from FTS index", conversation_id);
     }
 
     Ok(())
 }
 
+/// Replaces a conversation's entries in the FTS and trigram indexes.
+fn write_index_entry(conn: &Connection, rowid: i64, content: &str, project_name: &str) -> DbResult<()> {
+    delete_index_entry(conn, rowid)?;
+    insert_index_entry(conn, rowid, content, project_name)
+}
+
+/// Adds a conversation to the FTS and trigram indexes.
+fn insert_index_entry(conn: &Connection, rowid: i64, content: &str, project_name: &str) -> DbResult<()> {
+    conn.prepare_cached(
+        "INSERT INTO conversations_fts(rowid, content, project_name, project_key) VALUES (?1, ?2, ?3, ?4)",
+    )?
+    .execute(rusqlite::params![rowid, content, project_name, project_key(project_name)])?;
+    conn.prepare_cached("INSERT INTO conversations_trigram(rowid, content, project_name) VALUES (?1, ?2, ?3)")?
+        .execute(rusqlite::params![rowid, content, project_name])?;
+    Ok(())
+}
+
+/// Removes a conversation's entries from the FTS and trigram indexes.
+pub(crate) fn delete_index_entry(conn: &Connection, rowid: i64) -> DbResult<()> {
+    conn.prepare_cached("DELETE FROM conversations_fts WHERE rowid = ?1")?
+        .execute([rowid])?;
+    conn.prepare_cached("DELETE FROM conversations_trigram WHERE rowid = ?1")?
+        .execute([rowid])?;
+    Ok(())
+}
+
 /// Extracts all searchable text content from a conversation.
 ///
 /// Combines all message text content into a single searchable string.
@@ -199,8 +249,8 @@ fn extract_searchable_content(conversation: &ParsedConversation) -> String {
     let mut content_parts: Vec<String> = Vec::new();
 
     for message in &conversation.messages {
-        // Include user and assistant messages (skip system for now)
-        if message.message_type == RawMessageType::System {
+        // Include user and assistant messages (skip system and meta for now)
+        if message.message_type == RawMessageType::System || message.is_meta {
             continue;
         }
 
@@ -239,6 +289,7 @@ fn extract_searchable_content(conversation: &ParsedConversation) -> String {
 pub fn clear_search_index(db: &Database) -> DbResult<()> {
     db.with_connection(|conn| {
         conn.execute("DELETE FROM conversations_fts", [])?;
+        conn.execute("DELETE FROM conversations_trigram", [])?;
         info!("Cleared search index");
         Ok(())
     })
@@ -278,11 +329,22 @@ mod tests {
                 token_count: Some(RawTokenCount::default()),
                 uuid: Some("test-uuid".to_string()),
                 session_id: Some("test-session".to_string()),
+                model: None,
+                usage: None,
+                api_message_id: None,
+                command_output: None,
+                collapsed_reminders: 0,
+                is_meta: false,
+                line_index: 0,
             }],
             total_input_tokens: 100,
             total_output_tokens: 200,
             session_id: "test-session".to_string(),
             file_path: PathBuf::from("/test/session.jsonl"),
+            skipped_lines: 0,
+            continues_from: Vec::new(),
+            is_subagent: false,
+            cwd: None,
         }
     }
 
@@ -311,6 +373,7 @@ mod tests {
                 input: None,
                 tool_use_id: None,
                 content: None,
+                is_error: None,
             },
             crate::parser::RawContentBlock {
                 block_type: "tool_use".to_string(),
@@ -319,6 +382,7 @@ mod tests {
                 input: None,
                 tool_use_id: None,
                 content: None,
+                is_error: None,
             },
         ]);
 
@@ -434,6 +498,65 @@ mod tests {
         }).unwrap();
     }
 
+    #[test]
+    fn test_rebuild_fts_table_switches_tokenizer() {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::db::sqlite::init_db(&conn).unwrap();
+        conn.execute(
+            r#"INSERT INTO conversations
+               (id, project_path, project_name, start_time, last_time, preview, file_path, file_modified_at)
+               VALUES ('conv1', '/test/project', 'project', '2025-01-01T00:00:00Z', '2025-01-01T01:00:00Z',
+                       '日本語のテキストを検索する', '/test/session.jsonl', '2025-01-01T00:00:00Z')"#,
+            [],
+        )
+        .unwrap();
+        let matches = |query: &str| -> i64 {
+            conn.query_row(
+                "SELECT COUNT(*) FROM conversations_fts WHERE conversations_fts MATCH ?1",
+                [query],
+                |row| row.get(0),
+            )
+            .unwrap()
+        };
+
+        // unicode61 keeps the unspaced sentence as one term
+        assert_eq!(rebuild_fts_table(&conn, SearchTokenizer::Unicode61).unwrap(), 1);
+        assert_eq!(matches("\"テキスト\""), 0);
+
+        assert_eq!(rebuild_fts_table(&conn, SearchTokenizer::Trigram).unwrap(), 1);
+        assert_eq!(matches("\"テキスト\""), 1);
+        assert_eq!(matches("\"検索する\""), 1);
+    }
+
+    #[test]
+    fn test_rebuild_fts_table_porter_stems_words() {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::db::sqlite::init_db(&conn).unwrap();
+        conn.execute(
+            r#"INSERT INTO conversations
+               (id, project_path, project_name, start_time, last_time, preview, file_path, file_modified_at)
+               VALUES ('conv1', '/test/project', 'project', '2025-01-01T00:00:00Z', '2025-01-01T01:00:00Z',
+                       'Debugging the flaky tests', '/test/session.jsonl', '2025-01-01T00:00:00Z')"#,
+            [],
+        )
+        .unwrap();
+        let matches = |query: &str| -> i64 {
+            conn.query_row(
+                "SELECT COUNT(*) FROM conversations_fts WHERE conversations_fts MATCH ?1",
+                [query],
+                |row| row.get(0),
+            )
+            .unwrap()
+        };
+
+        rebuild_fts_table(&conn, SearchTokenizer::Unicode61).unwrap();
+        assert_eq!(matches("debugged"), 0);
+
+        rebuild_fts_table(&conn, SearchTokenizer::Porter).unwrap();
+        assert_eq!(matches("debugged"), 1);
+        assert_eq!(matches("test"), 1);
+    }
+
     #[test]
     fn test_clear_search_index() {
         let temp_dir = tempdir().unwrap();
@@ -1158,7 +1281,7 @@ mod tests {
         // Verify the data was inserted
         let count: i64 = db.with_connection(|conn| {
             conn.query_row("SELECT COUNT(*) FROM conversations_fts", [], |row| row.get(0))
-                .map_err(|e| crate::db::DbError::from(e))
+                .map_err(crate::db::DbError::from)
         }).unwrap();
         assert_eq!(count, num_conversations, "Should have inserted all conversations");
 
@@ -1168,7 +1291,7 @@ mod tests {
                 "SELECT COUNT(*) FROM conversations_fts WHERE conversations_fts MATCH 'Conversation'",
                 [],
                 |row| row.get(0)
-            ).map_err(|e| crate::db::DbError::from(e))?;
+            ).map_err(crate::db::DbError::from)?;
             Ok(())
         }).unwrap();
 
@@ -1273,7 +1396,7 @@ mod tests {
                 "SELECT COUNT(*) FROM conversations_fts WHERE conversations_fts MATCH 'Conversation'",
                 [],
                 |row| row.get(0)
-            ).map_err(|e| crate::db::DbError::from(e))?;
+            ).map_err(crate::db::DbError::from)?;
             Ok(())
         }).unwrap();
 
@@ -1321,7 +1444,7 @@ mod tests {
                     "SELECT project_name FROM conversations WHERE id = ?1",
                     [id],
                     |row| row.get(0),
-                ).map_err(|e| crate::db::DbError::from(e))
+                ).map_err(crate::db::DbError::from)
             }).unwrap();
             assert_eq!(project, "project-0");
         }
@@ -1358,6 +1481,10 @@ mod tests {
             total_output_tokens: 0,
             session_id: "test-session".to_string(),
             file_path: PathBuf::from("/test/session.jsonl"),
+            skipped_lines: 0,
+            continues_from: Vec::new(),
+            is_subagent: false,
+            cwd: None,
         };
 
         // Should handle empty content gracefully
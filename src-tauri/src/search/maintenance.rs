@@ -0,0 +1,208 @@
+//! Maintenance of the FTS5 indexes.
+//!
+//! Every ingest replaces index entries, and FTS5 only marks replaced and
+//! deleted entries in new segments until segments are merged; contentless
+//! tables (`conversations_fts`, `conversations_trigram`) keep the stale
+//! entries until then. After each ingest a bounded `merge` folds small
+//! segments together; `maintain_search_indexes` verifies every index with
+//! `integrity-check`, rebuilds a damaged one from the stored text, and runs
+//! a full `optimize`.
+
+use crate::db::settings::load_settings;
+use crate::db::sqlite::{DbError, DbResult};
+use crate::metrics::millis;
+use crate::models::{FtsIndexReport, SearchMaintenanceReport};
+use crate::search::index::rebuild_fts_table;
+use rusqlite::{Connection, ErrorCode};
+use std::time::Instant;
+use tracing::{info, warn};
+
+/// Pages of segments merged after an ingest. Small enough to stay well
+/// under a second on large indexes; repeated ingests catch up.
+pub const INGEST_MERGE_PAGES: i64 = 500;
+
+/// Rebuilds an index from the text it was built from.
+type Rebuild = fn(&Connection) -> DbResult<()>;
+
+/// The FTS5 tables: (name, rebuild).
+const FTS_TABLES: &[(&str, Rebuild)] = &[
+    ("conversations_fts", rebuild_conversations_fts),
+    ("conversations_trigram", rebuild_conversations_trigram),
+    ("questions_fts", rebuild_questions_fts),
+];
+
+fn rebuild_conversations_fts(conn: &Connection) -> DbResult<()> {
+    rebuild_fts_table(conn, load_settings(conn)?.search_tokenizer)?;
+    Ok(())
+}
+
+fn rebuild_conversations_trigram(conn: &Connection) -> DbResult<()> {
+    conn.execute_batch(
+        "INSERT INTO conversations_trigram(conversations_trigram) VALUES ('delete-all');
+         INSERT INTO conversations_trigram(rowid, content, project_name)
+         SELECT rowid, preview, project_name FROM conversations;",
+    )?;
+    Ok(())
+}
+
+fn rebuild_questions_fts(conn: &Connection) -> DbResult<()> {
+    // External content: FTS5 rereads user_questions
+    conn.execute(
+        "INSERT INTO questions_fts(questions_fts) VALUES ('rebuild')",
+        [],
+    )?;
+    Ok(())
+}
+
+/// Runs an FTS5 special command (`merge`, `optimize`, ...) on a table.
+fn fts_command(conn: &Connection, table: &str, command: &str, rank: Option<i64>) -> DbResult<()> {
+    match rank {
+        Some(rank) => conn.execute(
+            &format!("INSERT INTO {0}({0}, rank) VALUES (?1, ?2)", table),
+            rusqlite::params![command, rank],
+        )?,
+        None => conn.execute(
+            &format!("INSERT INTO {0}({0}) VALUES (?1)", table),
+            [command],
+        )?,
+    };
+    Ok(())
+}
+
+/// Checks an index, including against its content table if it has one.
+/// Returns the problem found, or `None` if the index is intact.
+fn check_integrity(conn: &Connection, table: &str) -> DbResult<Option<String>> {
+    match fts_command(conn, table, "integrity-check", Some(1)) {
+        Ok(()) => Ok(None),
+        Err(DbError::Sqlite(rusqlite::Error::SqliteFailure(error, message)))
+            if error.code == ErrorCode::DatabaseCorrupt =>
+        {
+            Ok(Some(message.unwrap_or_else(|| error.to_string())))
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Merges some of the segments of every index, at most `pages` pages each.
+pub fn merge_search_indexes(conn: &Connection, pages: i64) -> DbResult<()> {
+    for (table, _) in FTS_TABLES {
+        fts_command(conn, table, "merge", Some(pages))?;
+    }
+    Ok(())
+}
+
+/// Verifies, repairs, and fully optimizes every index.
+pub fn maintain_search_indexes(conn: &Connection) -> DbResult<SearchMaintenanceReport> {
+    let started = Instant::now();
+    let mut report = SearchMaintenanceReport::default();
+    for (table, rebuild) in FTS_TABLES {
+        let table_started = Instant::now();
+        let problem = check_integrity(conn, table)?;
+        if let Some(problem) = &problem {
+            warn!(
+                "Search index {} failed its integrity check, rebuilding: {}",
+                table, problem
+            );
+            rebuild(conn)?;
+        }
+        fts_command(conn, table, "optimize", None)?;
+        report.indexes.push(FtsIndexReport {
+            table: table.to_string(),
+            rebuilt: problem.is_some(),
+            problem,
+            duration_ms: millis(table_started.elapsed()),
+        });
+    }
+    report.duration_ms = millis(started.elapsed());
+    info!(
+        "Search index maintenance: {} indexes optimized, {} rebuilt in {:.1}ms",
+        report.indexes.len(),
+        report.indexes.iter().filter(|index| index.rebuilt).count(),
+        report.duration_ms
+    );
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::sqlite::init_db;
+    use crate::search::index::index_conversation_content;
+    use crate::search::search_questions;
+
+    fn setup() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        init_db(&conn).unwrap();
+        for (id, preview) in [("c1", "cache invalidation"), ("c2", "release notes")] {
+            conn.execute(
+                "INSERT INTO conversations (id, project_path, project_name, start_time, last_time, file_path, file_modified_at, preview)
+                 VALUES (?1, '/p', 'p', '', '', '/f.jsonl', '', ?2)",
+                [id, preview],
+            )
+            .unwrap();
+            index_conversation_content(&conn, id, preview, "p").unwrap();
+        }
+        conn.execute(
+            "INSERT INTO user_questions (conversation_id, message_id, position, text)
+             VALUES ('c1', 'u-1', 0, 'why is the cache stale')",
+            [],
+        )
+        .unwrap();
+        conn
+    }
+
+    fn count(conn: &Connection, table: &str, query: &str) -> i64 {
+        conn.query_row(
+            &format!("SELECT COUNT(*) FROM {0} WHERE {0} MATCH ?1", table),
+            [query],
+            |row| row.get(0),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_maintenance_keeps_healthy_indexes() {
+        let conn = setup();
+        // Replaced entries leave deletions to merge away
+        index_conversation_content(&conn, "c1", "cache warmup", "p").unwrap();
+        merge_search_indexes(&conn, INGEST_MERGE_PAGES).unwrap();
+
+        let report = maintain_search_indexes(&conn).unwrap();
+        let tables: Vec<&str> = report.indexes.iter().map(|i| i.table.as_str()).collect();
+        assert_eq!(
+            tables,
+            vec![
+                "conversations_fts",
+                "conversations_trigram",
+                "questions_fts"
+            ]
+        );
+        assert!(report
+            .indexes
+            .iter()
+            .all(|i| !i.rebuilt && i.problem.is_none()));
+        assert_eq!(count(&conn, "conversations_fts", "warmup"), 1);
+        assert_eq!(count(&conn, "conversations_fts", "invalidation"), 0);
+        assert_eq!(count(&conn, "conversations_trigram", "arm"), 1);
+    }
+
+    #[test]
+    fn test_maintenance_rebuilds_damaged_index() {
+        let conn = setup();
+        // Deleting with the wrong text leaves the index out of step with
+        // user_questions
+        conn.execute(
+            "INSERT INTO questions_fts(questions_fts, rowid, text) VALUES ('delete', 1, 'unrelated words')",
+            [],
+        )
+        .unwrap();
+
+        let report = maintain_search_indexes(&conn).unwrap();
+        let questions = &report.indexes[2];
+        assert!(questions.rebuilt);
+        assert!(questions.problem.is_some());
+        assert!(!report.indexes[0].rebuilt);
+        assert_eq!(search_questions(&conn, "stale", 10).unwrap().len(), 1);
+        assert_eq!(check_integrity(&conn, "questions_fts").unwrap(), None);
+    }
+}
@@ -1,11 +1,46 @@
 //! SQLite FTS5 full-text search indexing.
 //!
 //! This module handles building and querying the FTS5 search index
-//! for conversation content and metadata.
+//! for conversation content and metadata, recency-aware ranking,
+//! user-managed synonyms, a trigram index for typo-tolerant fallback search, facet counts for
+//! results, locating matches for highlighting, fuzzy matching for the
+//! quick-switcher, search-as-you-type sessions that cancel superseded
+//! queries, a separate index of the questions asked, per-project
+//! partitions of the full-text index, index maintenance, result
+//! snippets built from stored text, parsing of queries into terms and
+//! metadata filters, and the terms characteristic of a set of conversations.
 
+pub mod facets;
+pub mod fuzzy;
+pub mod highlight;
 pub mod index;
+pub mod maintenance;
+pub mod partition;
+pub mod questions;
+pub mod ranking;
+pub mod sessions;
+pub mod snippets;
+pub mod synonyms;
+pub mod terms;
+pub mod top_terms;
+pub mod trigram;
 
 pub use index::{
     build_search_index, clear_search_index, get_index_count, index_conversation,
     rebuild_search_index, remove_from_index,
 };
+pub use facets::{by_count, search_facets};
+pub use fuzzy::{fuzzy_match, FuzzyMatch};
+pub use maintenance::{maintain_search_indexes, merge_search_indexes, INGEST_MERGE_PAGES};
+pub use partition::{project_key, route_query};
+pub use questions::{
+    extract_questions, index_questions, question_hits, search_questions, Question, QuestionHits,
+};
+pub use highlight::{find_conversation_matches, find_match_spans};
+pub use ranking::{rank_components, MAX_RECENCY_HALF_LIFE_DAYS};
+pub use sessions::{run_latest, SearchSessions};
+pub use snippets::{build_snippet, conversation_snippet};
+pub use synonyms::{expand_query, list_synonyms, set_synonyms};
+pub use terms::{parse_terms, split_metadata_terms, QueryTerm};
+pub use top_terms::top_terms;
+pub use trigram::{trigram_search, TrigramMatch};
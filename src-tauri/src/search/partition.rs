@@ -0,0 +1,95 @@
+//! Project partitions of the full-text index.
+//!
+//! An FTS5 query matches across the whole index, and a project filter only
+//! drops rows after every match has been joined and ranked, which dominates
+//! project-scoped searches once there are 100k+ conversations. Each row of
+//! `conversations_fts` therefore also holds its project's key, a single
+//! token in the `project_key` column, and `route_query` adds the key to
+//! project-scoped queries so FTS5 only returns that project's rows.
+
+use sha2::{Digest, Sha256};
+
+/// Returns the partition key of a project: `p` and 20 digits derived from
+/// the name. Digits aren't stemmed, so it stays one exact token under every
+/// tokenizer.
+pub fn project_key(project_name: &str) -> String {
+    let digest = Sha256::digest(project_name.as_bytes());
+    let mut prefix = [0u8; 8];
+    prefix.copy_from_slice(&digest[..8]);
+    format!("p{:020}", u64::from_be_bytes(prefix))
+}
+
+/// Restricts an FTS5 query to the text columns, so the key column never
+/// matches, and to a project's partition when one is given.
+pub fn route_query(fts_query: &str, project: Option<&str>) -> String {
+    let text_query = format!("{{content project_name}} : ({})", fts_query);
+    match project {
+        Some(project) => format!(
+            "project_key : \"{}\" AND {}",
+            project_key(project),
+            text_query
+        ),
+        None => text_query,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::SearchTokenizer;
+    use crate::search::index::rebuild_fts_table;
+    use rusqlite::Connection;
+
+    fn matches(conn: &Connection, query: &str) -> Vec<i64> {
+        let mut stmt = conn
+            .prepare("SELECT rowid FROM conversations_fts WHERE conversations_fts MATCH ?1 ORDER BY rowid")
+            .unwrap();
+        stmt.query_map([query], |row| row.get(0))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_project_key_is_stable_and_distinct() {
+        assert_eq!(project_key("rust-cli"), project_key("rust-cli"));
+        assert_ne!(project_key("rust-cli"), project_key("rust-cli-2"));
+        assert_eq!(project_key("").len(), 21);
+    }
+
+    #[test]
+    fn test_route_query_limits_matches_to_project() {
+        for tokenizer in [
+            SearchTokenizer::Unicode61,
+            SearchTokenizer::Trigram,
+            SearchTokenizer::Porter,
+        ] {
+            let conn = Connection::open_in_memory().unwrap();
+            conn.execute_batch(
+                "CREATE TABLE conversations (project_name TEXT, preview TEXT);
+                 INSERT INTO conversations (rowid, project_name, preview) VALUES
+                     (1, 'web', 'cache invalidation bug'),
+                     (2, 'web-api', 'cache warmup'),
+                     (3, 'web', 'release notes');",
+            )
+            .unwrap();
+            rebuild_fts_table(&conn, tokenizer).unwrap();
+
+            assert_eq!(matches(&conn, &route_query("\"cache\"", None)), vec![1, 2]);
+            assert_eq!(
+                matches(&conn, &route_query("\"cache\"", Some("web"))),
+                vec![1]
+            );
+            assert_eq!(
+                matches(&conn, &route_query("\"cache\"", Some("docs"))),
+                Vec::<i64>::new()
+            );
+            // The key itself is never matched as text
+            let key = project_key("web");
+            assert_eq!(
+                matches(&conn, &route_query(&format!("\"{}\"", key), None)),
+                Vec::<i64>::new()
+            );
+        }
+    }
+}
@@ -0,0 +1,366 @@
+//! Index of the questions asked in conversations.
+//!
+//! `conversations_fts` indexes whole conversations, where a question is a
+//! line or two next to pages of answers and tool output. `user_questions`
+//! holds the text of each user message on its own, indexed by
+//! `questions_fts`, so a search can be restricted to what was asked. Rows
+//! are replaced whenever a conversation is ingested and deleted with it.
+
+use crate::db::DbResult;
+use crate::export::html::escape_html;
+use crate::models::{MessageHit, QuestionMatch};
+use crate::parser::{RawContent, RawMessage, RawMessageType};
+use crate::search::build_snippet;
+use rusqlite::{params, Connection};
+use std::collections::HashMap;
+
+/// Prefixes of user messages the CLI injects rather than the user typing
+/// them: slash command markup, local command output, and reminders.
+const INJECTED_PREFIXES: &[&str] = &["<command-", "<local-command-", "<system-reminder>"];
+
+/// A user message's text, with its position in the conversation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Question {
+    /// ID of the message, as in `Message::id`.
+    pub message_id: String,
+    /// Position of the message in its conversation.
+    pub position: usize,
+    /// Text of the message; tool results aren't included.
+    pub text: String,
+}
+
+/// Collects the text of the user messages in a conversation, skipping tool
+/// results, meta messages, and injected reminders.
+pub fn extract_questions(messages: &[RawMessage]) -> Vec<Question> {
+    messages
+        .iter()
+        .enumerate()
+        .filter(|(_, message)| message.message_type == RawMessageType::User && !message.is_meta)
+        .filter_map(|(position, message)| {
+            let text = match &message.message.content {
+                RawContent::Text(text) => text.trim().to_string(),
+                RawContent::Blocks(blocks) => blocks
+                    .iter()
+                    .filter(|block| block.block_type == "text")
+                    .filter_map(|block| block.text.as_deref().map(str::trim))
+                    .filter(|text| !text.is_empty())
+                    .collect::<Vec<_>>()
+                    .join("\n\n"),
+            };
+            if text.is_empty()
+                || INJECTED_PREFIXES
+                    .iter()
+                    .any(|prefix| text.starts_with(prefix))
+            {
+                return None;
+            }
+            Some(Question {
+                message_id: message
+                    .uuid
+                    .clone()
+                    .unwrap_or_else(|| format!("msg_{}", position)),
+                position,
+                text,
+            })
+        })
+        .collect()
+}
+
+/// Replaces a conversation's questions in the index.
+pub fn index_questions(
+    conn: &Connection,
+    conversation_id: &str,
+    messages: &[RawMessage],
+) -> DbResult<()> {
+    conn.prepare_cached("DELETE FROM user_questions WHERE conversation_id = ?1")?
+        .execute([conversation_id])?;
+    let mut stmt = conn.prepare_cached(
+        "INSERT INTO user_questions (conversation_id, message_id, position, text)
+         VALUES (?1, ?2, ?3, ?4)",
+    )?;
+    for question in extract_questions(messages) {
+        stmt.execute(params![
+            conversation_id,
+            question.message_id,
+            question.position as i64,
+            question.text
+        ])?;
+    }
+    Ok(())
+}
+
+/// Finds the user messages matching an FTS5 query, best match first; the
+/// more recent conversation breaks ties.
+pub fn search_questions(
+    conn: &Connection,
+    fts_query: &str,
+    limit: usize,
+) -> DbResult<Vec<QuestionMatch>> {
+    let mut stmt = conn.prepare_cached(
+        r#"
+        SELECT
+            q.conversation_id,
+            q.message_id,
+            q.position,
+            c.project_name,
+            c.last_time,
+            snippet(questions_fts, 0, '<mark>', '</mark>', '...', 32),
+            bm25(questions_fts) AS rank
+        FROM questions_fts
+        INNER JOIN user_questions q ON q.id = questions_fts.rowid
+        INNER JOIN conversations c ON c.id = q.conversation_id
+        WHERE questions_fts MATCH ?1
+        ORDER BY rank, c.last_epoch DESC, q.position
+        LIMIT ?2
+        "#,
+    )?;
+    let matches = stmt.query_map(params![fts_query, limit as i64], |row| {
+        Ok(QuestionMatch {
+            conversation_id: row.get(0)?,
+            message_id: row.get(1)?,
+            ordinal: row.get(2)?,
+            project_name: row.get(3)?,
+            last_time: row.get(4)?,
+            snippet: row.get(5)?,
+            rank: row.get::<_, f64>(6)?.abs(),
+        })
+    })?;
+    Ok(matches.collect::<Result<_, _>>()?)
+}
+
+/// The user messages of a conversation matching a query.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct QuestionHits {
+    /// Number of matching messages.
+    pub count: usize,
+    /// The best matching messages, best first.
+    pub hits: Vec<MessageHit>,
+}
+
+/// Finds the user messages matching an FTS5 query in each of
+/// `conversation_ids`, keeping the best `per_conversation` of each.
+///
+/// Snippets are built from the message text with `queries`, the query and
+/// its synonym expansions, like search result snippets.
+pub fn question_hits(
+    conn: &Connection,
+    fts_query: &str,
+    queries: &[String],
+    conversation_ids: &[String],
+    per_conversation: usize,
+) -> DbResult<HashMap<String, QuestionHits>> {
+    let mut stmt = conn.prepare_cached(
+        r#"
+        SELECT q.conversation_id, q.message_id, q.position, q.text
+        FROM questions_fts
+        INNER JOIN user_questions q ON q.id = questions_fts.rowid
+        WHERE questions_fts MATCH ?1
+          AND q.conversation_id IN (SELECT value FROM json_each(?2))
+        ORDER BY bm25(questions_fts), q.position
+        "#,
+    )?;
+    let ids = serde_json::to_string(conversation_ids).unwrap_or_default();
+    let rows = stmt.query_map(params![fts_query, ids], |row| {
+        Ok((
+            row.get::<_, String>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, u32>(2)?,
+            row.get::<_, String>(3)?,
+        ))
+    })?;
+
+    let mut found: HashMap<String, QuestionHits> = HashMap::new();
+    for row in rows {
+        let (conversation_id, message_id, ordinal, text) = row?;
+        let entry = found.entry(conversation_id).or_default();
+        entry.count += 1;
+        if entry.hits.len() < per_conversation {
+            entry.hits.push(MessageHit {
+                message_id,
+                ordinal,
+                snippet: build_snippet(&text, queries).unwrap_or_else(|| escape_html(&text)),
+            });
+        }
+    }
+    Ok(found)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::sqlite::init_db;
+    use crate::parser::RawContentBlock;
+
+    fn message(
+        message_type: RawMessageType,
+        content: RawContent,
+        uuid: Option<&str>,
+    ) -> RawMessage {
+        RawMessage {
+            uuid: uuid.map(str::to_string),
+            ..RawMessage::new(message_type, content)
+        }
+    }
+
+    fn text(message_type: RawMessageType, text: &str, uuid: &str) -> RawMessage {
+        message(message_type, RawContent::Text(text.to_string()), Some(uuid))
+    }
+
+    fn tool_result(output: &str) -> RawMessage {
+        message(
+            RawMessageType::User,
+            RawContent::Blocks(vec![RawContentBlock {
+                block_type: "tool_result".to_string(),
+                text: None,
+                name: None,
+                input: None,
+                tool_use_id: Some("t1".to_string()),
+                content: Some(serde_json::json!(output)),
+                is_error: None,
+            }]),
+            None,
+        )
+    }
+
+    fn insert_conversation(conn: &Connection, id: &str, last_time: &str) {
+        conn.execute(
+            "INSERT INTO conversations (id, project_path, project_name, start_time, last_time, file_path, file_modified_at)
+             VALUES (?1, '/p', 'p', ?2, ?2, '/f.jsonl', '')",
+            [id, last_time],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_extract_questions_skips_tool_results_and_injected_messages() {
+        let messages = vec![
+            text(RawMessageType::User, "  How do I parse JSON?  ", "u-1"),
+            text(
+                RawMessageType::Assistant,
+                "Use serde_json to parse JSON.",
+                "a-1",
+            ),
+            tool_result("parse JSON output"),
+            text(
+                RawMessageType::User,
+                "<command-name>/clear</command-name>",
+                "u-2",
+            ),
+            message(
+                RawMessageType::User,
+                RawContent::Text("And YAML?".to_string()),
+                None,
+            ),
+        ];
+
+        assert_eq!(
+            extract_questions(&messages),
+            vec![
+                Question {
+                    message_id: "u-1".to_string(),
+                    position: 0,
+                    text: "How do I parse JSON?".to_string(),
+                },
+                Question {
+                    message_id: "msg_4".to_string(),
+                    position: 4,
+                    text: "And YAML?".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_search_questions_ignores_answers() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("PRAGMA foreign_keys = ON;").unwrap();
+        init_db(&conn).unwrap();
+        insert_conversation(&conn, "c1", "2025-01-15T10:00:00Z");
+        insert_conversation(&conn, "c2", "2025-01-16T10:00:00Z");
+        index_questions(
+            &conn,
+            "c1",
+            &[
+                text(
+                    RawMessageType::User,
+                    "Why is the borrow checker angry?",
+                    "u-1",
+                ),
+                text(
+                    RawMessageType::Assistant,
+                    "Lifetimes and migrations explained.",
+                    "a-1",
+                ),
+            ],
+        )
+        .unwrap();
+        index_questions(
+            &conn,
+            "c2",
+            &[text(
+                RawMessageType::User,
+                "Write the database migrations",
+                "u-2",
+            )],
+        )
+        .unwrap();
+
+        let found = search_questions(&conn, "migrations", 10).unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].conversation_id, "c2");
+        assert_eq!(found[0].message_id, "u-2");
+        assert_eq!(
+            found[0].snippet,
+            "Write the database <mark>migrations</mark>"
+        );
+
+        // Re-indexing replaces the questions, and deleting the conversation
+        // removes them
+        index_questions(
+            &conn,
+            "c1",
+            &[text(RawMessageType::User, "Plan the migrations", "u-3")],
+        )
+        .unwrap();
+        assert_eq!(search_questions(&conn, "borrow", 10).unwrap(), vec![]);
+        assert_eq!(search_questions(&conn, "migrations", 10).unwrap().len(), 2);
+        conn.execute("DELETE FROM conversations WHERE id = 'c2'", [])
+            .unwrap();
+        let found = search_questions(&conn, "migrations", 10).unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].message_id, "u-3");
+    }
+
+    #[test]
+    fn test_question_hits_keeps_best_per_conversation() {
+        let conn = Connection::open_in_memory().unwrap();
+        init_db(&conn).unwrap();
+        insert_conversation(&conn, "c1", "2025-01-15T10:00:00Z");
+        insert_conversation(&conn, "c2", "2025-01-16T10:00:00Z");
+        let questions = [
+            "Why does the cache miss?",
+            "Unrelated",
+            "Cache the <cache> cache keys",
+            "Clear the cache",
+        ];
+        let messages: Vec<RawMessage> = questions
+            .iter()
+            .enumerate()
+            .map(|(i, question)| text(RawMessageType::User, question, &format!("u-{}", i)))
+            .collect();
+        index_questions(&conn, "c1", &messages).unwrap();
+        index_questions(&conn, "c2", &messages[..1]).unwrap();
+
+        let queries = vec!["cache".to_string()];
+        let found = question_hits(&conn, "cache*", &queries, &["c1".to_string()], 2).unwrap();
+        assert_eq!(found.len(), 1);
+        let hits = &found["c1"];
+        assert_eq!(hits.count, 3);
+        let ids: Vec<&str> = hits.hits.iter().map(|hit| hit.message_id.as_str()).collect();
+        assert_eq!(ids, vec!["u-2", "u-3"]);
+        assert_eq!(
+            hits.hits[0].snippet,
+            "<mark>Cache</mark> the &lt;<mark>cache</mark>&gt; <mark>cache</mark> keys"
+        );
+    }
+}
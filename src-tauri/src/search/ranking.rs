@@ -0,0 +1,94 @@
+//! Recency-aware ranking of full-text matches.
+//!
+//! BM25 alone often puts years-old conversations first. Each match's BM25
+//! score is boosted by a recency factor that halves every
+//! `recency_half_life_days`, so a conversation from today scores up to
+//! `1 + RECENCY_BOOST` times as well as an ancient one with equal text
+//! relevance. Ties go to the more recent conversation.
+
+use crate::models::RankComponents;
+
+/// Longest recency half-life the settings accept, in days.
+pub const MAX_RECENCY_HALF_LIFE_DAYS: u32 = 3650;
+
+/// Largest relative boost, for a conversation active right now.
+pub const RECENCY_BOOST: f64 = 1.0;
+
+const MILLIS_PER_DAY: f64 = 86_400_000.0;
+
+/// Computes the rank components of a match.
+///
+/// `bm25` is FTS5's score (negative, lower is better). A `half_life_days` of
+/// 0 disables the boost; a missing or future `last_epoch` counts as now.
+pub fn rank_components(
+    bm25: f64,
+    last_epoch: Option<i64>,
+    now_ms: i64,
+    half_life_days: u32,
+) -> RankComponents {
+    let age_days = last_epoch
+        .map(|epoch| (now_ms - epoch).max(0) as f64 / MILLIS_PER_DAY)
+        .unwrap_or(0.0);
+    let recency = if half_life_days == 0 {
+        0.0
+    } else {
+        0.5_f64.powf(age_days / half_life_days as f64)
+    };
+
+    RankComponents {
+        bm25,
+        age_days,
+        recency,
+        score: bm25 * (1.0 + RECENCY_BOOST * recency),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: i64 = 1_760_000_000_000;
+
+    fn days_ago(days: f64) -> Option<i64> {
+        Some(NOW - (days * MILLIS_PER_DAY) as i64)
+    }
+
+    #[test]
+    fn test_recency_halves_every_half_life() {
+        let today = rank_components(-4.0, days_ago(0.0), NOW, 30);
+        assert_eq!(today.recency, 1.0);
+        assert_eq!(today.score, -8.0);
+
+        let month = rank_components(-4.0, days_ago(30.0), NOW, 30);
+        assert!((month.recency - 0.5).abs() < 1e-9);
+        assert!((month.age_days - 30.0).abs() < 1e-9);
+        assert!((month.score - -6.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_recent_match_outranks_equal_old_one() {
+        let recent = rank_components(-3.0, days_ago(1.0), NOW, 90);
+        let old = rank_components(-3.0, days_ago(900.0), NOW, 90);
+        assert!(recent.score < old.score);
+
+        // A much better text match still wins
+        let relevant_old = rank_components(-9.0, days_ago(900.0), NOW, 90);
+        assert!(relevant_old.score < recent.score);
+    }
+
+    #[test]
+    fn test_zero_half_life_disables_boost() {
+        let components = rank_components(-2.5, days_ago(0.0), NOW, 0);
+        assert_eq!(components.recency, 0.0);
+        assert_eq!(components.score, -2.5);
+    }
+
+    #[test]
+    fn test_missing_or_future_times_count_as_now() {
+        assert_eq!(rank_components(-1.0, None, NOW, 30).age_days, 0.0);
+        assert_eq!(
+            rank_components(-1.0, Some(NOW + 1_000), NOW, 30).age_days,
+            0.0
+        );
+    }
+}
@@ -0,0 +1,223 @@
+//! Search-as-you-type sessions.
+//!
+//! A search box begins a session and sends each keystroke's query to it.
+//! Every query supersedes the previous one: starting a query interrupts
+//! the session's FTS query still running, and results of a superseded
+//! query are dropped, so stale results never reach the UI.
+//!
+//! A running query is registered only while it holds the database
+//! connection, so an interrupt never hits another command's statements.
+
+use crate::db::sqlite::{DbError, DbResult};
+use rusqlite::{Connection, ErrorCode, InterruptHandle};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+/// Most sessions kept; the oldest is dropped when another begins.
+pub const MAX_SEARCH_SESSIONS: usize = 32;
+
+struct Session {
+    /// Order in which sessions began, for dropping the oldest.
+    order: u64,
+    /// Number of the latest query.
+    generation: u64,
+    /// Interrupts the latest query while it runs.
+    running: Option<InterruptHandle>,
+}
+
+/// Open search sessions, by ID.
+#[derive(Default)]
+pub struct SearchSessions {
+    sessions: Mutex<HashMap<String, Session>>,
+    next_order: AtomicU64,
+}
+
+impl SearchSessions {
+    /// Begins a session and returns its ID.
+    pub fn begin(&self) -> String {
+        let order = self.next_order.fetch_add(1, Ordering::Relaxed) + 1;
+        let id = format!("search-{}", order);
+
+        let mut sessions = self.lock();
+        if sessions.len() >= MAX_SEARCH_SESSIONS {
+            let oldest = sessions
+                .iter()
+                .min_by_key(|(_, session)| session.order)
+                .map(|(id, _)| id.clone());
+            if let Some(oldest) = oldest {
+                sessions.remove(&oldest);
+            }
+        }
+        sessions.insert(
+            id.clone(),
+            Session {
+                order,
+                generation: 0,
+                running: None,
+            },
+        );
+        id
+    }
+
+    /// Starts a new query in a session, interrupting the running one.
+    ///
+    /// Returns the new query's generation, or `None` for an unknown session.
+    pub fn start_query(&self, id: &str) -> Option<u64> {
+        let mut sessions = self.lock();
+        let session = sessions.get_mut(id)?;
+        if let Some(running) = session.running.take() {
+            running.interrupt();
+        }
+        session.generation += 1;
+        Some(session.generation)
+    }
+
+    /// Checks whether `generation` is still the latest query of a session.
+    pub fn is_current(&self, id: &str, generation: u64) -> bool {
+        self.lock()
+            .get(id)
+            .is_some_and(|session| session.generation == generation)
+    }
+
+    /// Registers (or with `None`, clears) the interrupt handle of a running
+    /// query, if it's still the latest one. Returns whether it is.
+    fn set_running(&self, id: &str, generation: u64, running: Option<InterruptHandle>) -> bool {
+        let mut sessions = self.lock();
+        match sessions.get_mut(id) {
+            Some(session) if session.generation == generation => {
+                session.running = running;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, Session>> {
+        self.sessions.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+fn is_interrupted(error: &DbError) -> bool {
+    matches!(
+        error,
+        DbError::Sqlite(rusqlite::Error::SqliteFailure(e, _))
+            if e.code == ErrorCode::OperationInterrupted
+    )
+}
+
+/// Runs `search` as query `generation` of a session, unless a newer query
+/// supersedes it before, during, or after it runs.
+///
+/// Returns `None` if it was superseded.
+pub fn run_latest<T>(
+    sessions: &SearchSessions,
+    id: &str,
+    generation: u64,
+    conn: &Connection,
+    search: impl FnOnce(&Connection) -> DbResult<T>,
+) -> DbResult<Option<T>> {
+    if !sessions.set_running(id, generation, Some(conn.get_interrupt_handle())) {
+        return Ok(None);
+    }
+    let result = search(conn);
+    let current = sessions.set_running(id, generation, None);
+
+    match result {
+        Ok(value) if current => Ok(Some(value)),
+        Ok(_) => Ok(None),
+        Err(e) if is_interrupted(&e) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    fn select_one(conn: &Connection) -> DbResult<i64> {
+        Ok(conn.query_row("SELECT 1", [], |row| row.get(0))?)
+    }
+
+    #[test]
+    fn test_newer_query_supersedes_older() {
+        let conn = Connection::open_in_memory().unwrap();
+        let sessions = SearchSessions::default();
+        let id = sessions.begin();
+
+        let first = sessions.start_query(&id).unwrap();
+        let second = sessions.start_query(&id).unwrap();
+        assert!(!sessions.is_current(&id, first));
+        assert!(sessions.is_current(&id, second));
+
+        assert_eq!(
+            run_latest(&sessions, &id, first, &conn, select_one).unwrap(),
+            None
+        );
+        assert_eq!(
+            run_latest(&sessions, &id, second, &conn, select_one).unwrap(),
+            Some(1)
+        );
+    }
+
+    #[test]
+    fn test_results_finished_after_newer_query_are_dropped() {
+        let conn = Connection::open_in_memory().unwrap();
+        let sessions = SearchSessions::default();
+        let id = sessions.begin();
+
+        let first = sessions.start_query(&id).unwrap();
+        let result = run_latest(&sessions, &id, first, &conn, |conn| {
+            let value = select_one(conn)?;
+            sessions.start_query(&id);
+            Ok(value)
+        });
+        assert_eq!(result.unwrap(), None);
+    }
+
+    #[test]
+    fn test_newer_query_interrupts_running_one() {
+        let conn = Connection::open_in_memory().unwrap();
+        let sessions = Arc::new(SearchSessions::default());
+        let id = sessions.begin();
+        let first = sessions.start_query(&id).unwrap();
+
+        let keystroke = {
+            let sessions = sessions.clone();
+            let id = id.clone();
+            thread::spawn(move || {
+                thread::sleep(Duration::from_millis(50));
+                sessions.start_query(&id)
+            })
+        };
+
+        let started = Instant::now();
+        let result = run_latest(&sessions, &id, first, &conn, |conn| {
+            Ok(conn.query_row(
+                "WITH RECURSIVE c(x) AS (SELECT 1 UNION ALL SELECT x + 1 FROM c WHERE x < 1000000000)
+                 SELECT COUNT(*) FROM c",
+                [],
+                |row| row.get::<_, i64>(0),
+            )?)
+        });
+        assert_eq!(result.unwrap(), None);
+        assert!(started.elapsed() < Duration::from_secs(10));
+        assert_eq!(keystroke.join().unwrap(), Some(first + 1));
+    }
+
+    #[test]
+    fn test_unknown_and_evicted_sessions() {
+        let sessions = SearchSessions::default();
+        assert_eq!(sessions.start_query("search-0"), None);
+
+        let oldest = sessions.begin();
+        for _ in 0..MAX_SEARCH_SESSIONS {
+            sessions.begin();
+        }
+        assert_eq!(sessions.start_query(&oldest), None);
+        assert_eq!(sessions.lock().len(), MAX_SEARCH_SESSIONS);
+    }
+}
@@ -0,0 +1,169 @@
+//! Snippets of search results, built from stored text.
+//!
+//! `conversations_fts` is contentless, so FTS5's `snippet()` has no text to
+//! excerpt. Snippets are built from the text the database keeps instead: a
+//! conversation's user messages (`user_questions`), then its preview. The
+//! excerpt is cut around the first match, HTML-escaped, and has its
+//! matches wrapped in `<mark>`, like `snippet()` output.
+
+use crate::db::DbResult;
+use crate::export::html::escape_html;
+use crate::search::find_match_spans;
+use rusqlite::Connection;
+
+/// Characters of context kept before the first match.
+const CONTEXT_BEFORE: usize = 40;
+
+/// Longest excerpt, in characters.
+const MAX_SNIPPET_CHARS: usize = 200;
+
+/// Builds an excerpt of `text` around its first match of any of `queries`,
+/// tried in order. Returns `None` if none matches.
+pub fn build_snippet(text: &str, queries: &[String]) -> Option<String> {
+    let spans = queries
+        .iter()
+        .map(|query| find_match_spans(text, query))
+        .find(|spans| !spans.is_empty())?;
+
+    // Byte offset and UTF-16 offset of each character, and of the end
+    let mut boundaries: Vec<(usize, usize)> = Vec::with_capacity(text.len() + 1);
+    let mut utf16 = 0;
+    for (byte, c) in text.char_indices() {
+        boundaries.push((byte, utf16));
+        utf16 += c.len_utf16();
+    }
+    boundaries.push((text.len(), utf16));
+    let char_at = |offset: usize| boundaries.partition_point(|&(_, u)| u < offset);
+    let chars: Vec<char> = text.chars().collect();
+
+    // Window of characters, starting and ending at word boundaries
+    let first = char_at(spans[0].start);
+    let mut start = first.saturating_sub(CONTEXT_BEFORE);
+    while start > 0 && start < first && !chars[start - 1].is_whitespace() {
+        start += 1;
+    }
+    let first_end = char_at(spans[0].end);
+    let mut end = (start + MAX_SNIPPET_CHARS).max(first_end).min(chars.len());
+    while end < chars.len() && end > first_end && !chars[end].is_whitespace() {
+        end -= 1;
+    }
+
+    let mut snippet = String::new();
+    if start > 0 {
+        snippet.push_str("...");
+    }
+    let mut cursor = start;
+    for span in spans {
+        let (span_start, span_end) = (char_at(span.start), char_at(span.end));
+        if span_start < cursor || span_end > end {
+            continue;
+        }
+        snippet.push_str(&escape_html(
+            &text[boundaries[cursor].0..boundaries[span_start].0],
+        ));
+        snippet.push_str("<mark>");
+        snippet.push_str(&escape_html(
+            &text[boundaries[span_start].0..boundaries[span_end].0],
+        ));
+        snippet.push_str("</mark>");
+        cursor = span_end;
+    }
+    snippet.push_str(&escape_html(&text[boundaries[cursor].0..boundaries[end].0]));
+    if end < chars.len() {
+        snippet.push_str("...");
+    }
+    Some(snippet.trim().to_string())
+}
+
+/// Builds the snippet of a conversation from the first of its user
+/// messages, or else its preview, matching any of `queries`. Without a
+/// match, the preview is returned escaped.
+pub fn conversation_snippet(
+    conn: &Connection,
+    conversation_id: &str,
+    preview: &str,
+    queries: &[String],
+) -> DbResult<String> {
+    let mut stmt = conn.prepare_cached(
+        "SELECT text FROM user_questions WHERE conversation_id = ?1 ORDER BY position",
+    )?;
+    let texts = stmt.query_map([conversation_id], |row| row.get::<_, String>(0))?;
+    for text in texts {
+        if let Some(snippet) = build_snippet(&text?, queries) {
+            return Ok(snippet);
+        }
+    }
+    Ok(build_snippet(preview, queries).unwrap_or_else(|| escape_html(preview)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::sqlite::init_db;
+
+    fn queries(queries: &[&str]) -> Vec<String> {
+        queries.iter().map(|query| query.to_string()).collect()
+    }
+
+    #[test]
+    fn test_build_snippet_marks_and_escapes_matches() {
+        assert_eq!(
+            build_snippet("Why does <Cache> miss? The cache key", &queries(&["cache"])),
+            Some(
+                "Why does &lt;<mark>Cache</mark>&gt; miss? The <mark>cache</mark> key".to_string()
+            )
+        );
+        assert_eq!(build_snippet("nothing here", &queries(&["cache"])), None);
+        // Later queries (synonym expansions) are tried when earlier ones miss
+        assert_eq!(
+            build_snippet("Set up the k8s cluster", &queries(&["kubernetes", "k8s"])),
+            Some("Set up the <mark>k8s</mark> cluster".to_string())
+        );
+    }
+
+    #[test]
+    fn test_build_snippet_cuts_long_text_at_words() {
+        let text = format!(
+            "{} needle {}",
+            "lorem ipsum ".repeat(20),
+            "dolor sit ".repeat(30)
+        );
+        let snippet = build_snippet(&text, &queries(&["needle"])).unwrap();
+        assert!(snippet.starts_with("...lorem") || snippet.starts_with("...ipsum"));
+        assert!(snippet.contains(" <mark>needle</mark> dolor"));
+        assert!(snippet.ends_with("sit...") || snippet.ends_with("dolor..."));
+        assert!(snippet.chars().count() <= MAX_SNIPPET_CHARS + 6 + "<mark></mark>".len());
+
+        // Multibyte text around the match is cut at character boundaries
+        let text = format!("{}テスト {}", "日本語 ".repeat(30), "🚀 ".repeat(200));
+        let snippet = build_snippet(&text, &queries(&["テスト"])).unwrap();
+        assert!(snippet.contains("<mark>テスト</mark>"));
+    }
+
+    #[test]
+    fn test_conversation_snippet_prefers_user_messages() {
+        let conn = Connection::open_in_memory().unwrap();
+        init_db(&conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO conversations (id, project_path, project_name, start_time, last_time, file_path, file_modified_at)
+             VALUES ('c1', '/p', 'p', '', '', '/f.jsonl', '');
+             INSERT INTO user_questions (conversation_id, message_id, position, text) VALUES
+                 ('c1', 'u-1', 0, 'Set up CI'),
+                 ('c1', 'u-2', 2, 'Now make the flaky test retry');",
+        )
+        .unwrap();
+
+        let snippet = |query: &str, preview: &str| {
+            conversation_snippet(&conn, "c1", preview, &queries(&[query])).unwrap()
+        };
+        assert_eq!(
+            snippet("flaky", "Set up CI"),
+            "Now make the <mark>flaky</mark> test retry"
+        );
+        assert_eq!(
+            snippet("pipeline", "CI pipeline"),
+            "CI <mark>pipeline</mark>"
+        );
+        assert_eq!(snippet("deploy", "a < b"), "a &lt; b");
+    }
+}
@@ -0,0 +1,222 @@
+//! User-managed search synonyms.
+//!
+//! Synonyms live in the `search_synonyms` table and are expanded when a
+//! query is run, so changes apply without reindexing. Expansion is one-way:
+//! "k8s" → "kubernetes" makes "k8s" find "kubernetes", not the reverse.
+
+use crate::db::DbResult;
+use crate::models::SynonymEntry;
+use rusqlite::Connection;
+
+/// Most variants a query expands to, so a query with many expandable words
+/// stays small.
+pub const MAX_QUERY_VARIANTS: usize = 16;
+
+/// Lowercases a term and collapses its whitespace.
+pub fn normalize_term(term: &str) -> String {
+    term.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// Lists all terms with their synonyms, sorted by term.
+pub fn list_synonyms(conn: &Connection) -> DbResult<Vec<SynonymEntry>> {
+    let mut stmt =
+        conn.prepare_cached("SELECT term, synonym FROM search_synonyms ORDER BY term ASC, synonym ASC")?;
+    let rows = stmt.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get(1)?)))?;
+
+    let mut entries: Vec<SynonymEntry> = Vec::new();
+    for row in rows {
+        let (term, synonym) = row?;
+        match entries.last_mut() {
+            Some(entry) if entry.term == term => entry.synonyms.push(synonym),
+            _ => entries.push(SynonymEntry {
+                term,
+                synonyms: vec![synonym],
+            }),
+        }
+    }
+    Ok(entries)
+}
+
+/// Replaces the synonyms of `term`; an empty list removes it.
+///
+/// Returns the stored synonyms, normalized, deduplicated, and sorted.
+pub fn set_synonyms(conn: &Connection, term: &str, synonyms: &[String]) -> DbResult<Vec<String>> {
+    let term = normalize_term(term);
+    let term = term.as_str();
+    conn.execute("DELETE FROM search_synonyms WHERE term = ?1", [term])?;
+
+    let now = chrono::Utc::now().to_rfc3339();
+    let mut stored = Vec::new();
+    for synonym in synonyms {
+        let normalized = normalize_term(synonym);
+        if normalized.is_empty() || normalized == term || stored.contains(&normalized) {
+            continue;
+        }
+        conn.execute(
+            "INSERT INTO search_synonyms (term, synonym, created_at) VALUES (?1, ?2, ?3)",
+            rusqlite::params![term, normalized, now],
+        )?;
+        stored.push(normalized);
+    }
+
+    stored.sort();
+    Ok(stored)
+}
+
+/// Returns the synonyms of a term, or none.
+fn synonyms_of(conn: &Connection, term: &str) -> DbResult<Vec<String>> {
+    let mut stmt =
+        conn.prepare_cached("SELECT synonym FROM search_synonyms WHERE term = ?1 ORDER BY synonym ASC")?;
+    let rows = stmt.query_map([normalize_term(term)], |row| row.get(0))?;
+    Ok(rows.collect::<Result<_, _>>()?)
+}
+
+/// Expands a query into the variants to search for, the query itself first.
+///
+/// Each word is replaced by each of its synonyms in turn, and a multi-word
+/// query can also have synonyms as a whole. Without any synonyms this is
+/// just the query.
+pub fn expand_query(conn: &Connection, query: &str) -> DbResult<Vec<String>> {
+    let words: Vec<&str> = query.split_whitespace().collect();
+
+    let mut variants: Vec<Vec<String>> = vec![Vec::new()];
+    let mut expanded = false;
+    for word in &words {
+        let synonyms = synonyms_of(conn, word)?;
+        expanded |= !synonyms.is_empty();
+
+        let alternatives: Vec<String> = std::iter::once(word.to_string()).chain(synonyms).collect();
+        variants = variants
+            .iter()
+            .flat_map(|prefix| {
+                alternatives.iter().map(move |alternative| {
+                    let mut variant = prefix.clone();
+                    variant.push(alternative.clone());
+                    variant
+                })
+            })
+            .take(MAX_QUERY_VARIANTS)
+            .collect();
+    }
+
+    let mut variants: Vec<String> = variants.into_iter().map(|words| words.join(" ")).collect();
+    if words.len() > 1 {
+        let synonyms = synonyms_of(conn, query)?;
+        expanded |= !synonyms.is_empty();
+        variants.extend(synonyms);
+    }
+
+    if !expanded {
+        return Ok(vec![query.to_string()]);
+    }
+    let mut unique = Vec::new();
+    for variant in variants {
+        if !unique.contains(&variant) {
+            unique.push(variant);
+        }
+    }
+    unique.truncate(MAX_QUERY_VARIANTS);
+    Ok(unique)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::sqlite::init_db;
+
+    fn setup() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        init_db(&conn).unwrap();
+        conn
+    }
+
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn test_set_and_list_synonyms() {
+        let conn = setup();
+        let stored = set_synonyms(
+            &conn,
+            "k8s",
+            &strings(&["Kubernetes", " kubernetes ", "", "k8s", "kube"]),
+        )
+        .unwrap();
+        assert_eq!(stored, strings(&["kube", "kubernetes"]));
+        set_synonyms(&conn, "ts", &strings(&["typescript"])).unwrap();
+
+        let entries = list_synonyms(&conn).unwrap();
+        assert_eq!(
+            entries,
+            vec![
+                SynonymEntry {
+                    term: "k8s".to_string(),
+                    synonyms: strings(&["kube", "kubernetes"]),
+                },
+                SynonymEntry {
+                    term: "ts".to_string(),
+                    synonyms: strings(&["typescript"]),
+                },
+            ]
+        );
+
+        // An empty list removes the term
+        set_synonyms(&conn, "k8s", &[]).unwrap();
+        assert_eq!(list_synonyms(&conn).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_expand_query_without_synonyms() {
+        let conn = setup();
+        assert_eq!(
+            expand_query(&conn, "rust  async").unwrap(),
+            strings(&["rust  async"])
+        );
+    }
+
+    #[test]
+    fn test_expand_query_replaces_words() {
+        let conn = setup();
+        set_synonyms(&conn, "k8s", &strings(&["kubernetes"])).unwrap();
+        set_synonyms(&conn, "db", &strings(&["database", "sqlite"])).unwrap();
+
+        assert_eq!(
+            expand_query(&conn, "K8s").unwrap(),
+            strings(&["K8s", "kubernetes"])
+        );
+        assert_eq!(
+            expand_query(&conn, "k8s db").unwrap(),
+            strings(&[
+                "k8s db",
+                "k8s database",
+                "k8s sqlite",
+                "kubernetes db",
+                "kubernetes database",
+                "kubernetes sqlite",
+            ])
+        );
+    }
+
+    #[test]
+    fn test_expand_query_whole_phrase() {
+        let conn = setup();
+        set_synonyms(&conn, "Continuous  Integration", &strings(&["CI"])).unwrap();
+        assert_eq!(
+            expand_query(&conn, "Continuous Integration").unwrap(),
+            strings(&["Continuous Integration", "ci"])
+        );
+    }
+
+    #[test]
+    fn test_expand_query_is_capped() {
+        let conn = setup();
+        set_synonyms(&conn, "a", &strings(&["b", "c", "d"])).unwrap();
+        let variants = expand_query(&conn, "a a a a").unwrap();
+        assert_eq!(variants.len(), MAX_QUERY_VARIANTS);
+        assert_eq!(variants[0], "a a a a");
+    }
+}
@@ -0,0 +1,157 @@
+//! Terms of a search query.
+//!
+//! Queries read like search engine queries: every term must match, the
+//! last term also matches as a prefix (so results follow the user's
+//! typing), and text in double quotes is an exact phrase. The FTS5 query
+//! and match highlighting are both built from these terms, so they agree on
+//! what matched. `metadata:key=value` terms aren't searched for: they're
+//! split off first and filter by custom metadata fields instead.
+
+use std::collections::BTreeMap;
+
+/// Prefix of the query terms filtering by a metadata field.
+const METADATA_PREFIX: &str = "metadata:";
+
+/// A word or quoted phrase of a query.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueryTerm {
+    /// The term's text, without quotes.
+    pub text: String,
+    /// Whether the term was quoted, so it only matches as an exact phrase.
+    pub quoted: bool,
+    /// Whether the term also matches words it's a prefix of: the last term,
+    /// unless quoted.
+    pub prefix: bool,
+}
+
+/// Splits a query into whitespace-separated words and `"quoted phrases"`.
+/// An unclosed quote runs to the end of the query.
+pub fn parse_terms(query: &str) -> Vec<QueryTerm> {
+    let mut terms = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+
+    let mut finish = |text: &mut String, quoted: bool| {
+        let trimmed = text.trim();
+        if !trimmed.is_empty() {
+            terms.push(QueryTerm {
+                text: trimmed.to_string(),
+                quoted,
+                prefix: false,
+            });
+        }
+        text.clear();
+    };
+
+    for c in query.chars() {
+        if c == '"' {
+            finish(&mut current, in_quotes);
+            in_quotes = !in_quotes;
+        } else if c.is_whitespace() && !in_quotes {
+            finish(&mut current, false);
+        } else {
+            current.push(c);
+        }
+    }
+    finish(&mut current, in_quotes);
+
+    if let Some(last) = terms.last_mut() {
+        last.prefix = !last.quoted;
+    }
+    terms
+}
+
+/// Splits the `metadata:key=value` terms off a query, returning the rest of
+/// the query and the fields by lowercase key. Values may be quoted, as in
+/// `metadata:customer="acme corp"`; terms without a key or value are left
+/// in the query.
+pub fn split_metadata_terms(query: &str) -> (String, BTreeMap<String, String>) {
+    let mut words: Vec<String> = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    for c in query.chars() {
+        if c == '"' {
+            in_quotes = !in_quotes;
+        }
+        if c.is_whitespace() && !in_quotes {
+            words.push(std::mem::take(&mut current));
+        } else {
+            current.push(c);
+        }
+    }
+    words.push(current);
+
+    let mut rest: Vec<String> = Vec::new();
+    let mut fields = BTreeMap::new();
+    for word in words.into_iter().filter(|word| !word.is_empty()) {
+        let field = word
+            .get(..METADATA_PREFIX.len())
+            .filter(|prefix| prefix.eq_ignore_ascii_case(METADATA_PREFIX))
+            .and_then(|_| word[METADATA_PREFIX.len()..].split_once('='))
+            .map(|(key, value)| (key.trim().to_lowercase(), value.trim_matches('"').trim()))
+            .filter(|(key, value)| !key.is_empty() && !value.is_empty());
+        match field {
+            Some((key, value)) => {
+                fields.insert(key, value.to_string());
+            }
+            None => rest.push(word),
+        }
+    }
+    (rest.join(" "), fields)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn term(text: &str, quoted: bool, prefix: bool) -> QueryTerm {
+        QueryTerm {
+            text: text.to_string(),
+            quoted,
+            prefix,
+        }
+    }
+
+    #[test]
+    fn test_parse_terms() {
+        assert_eq!(
+            parse_terms("  rust  async fn "),
+            vec![
+                term("rust", false, false),
+                term("async", false, false),
+                term("fn", false, true)
+            ]
+        );
+        assert_eq!(
+            parse_terms("fix \"connection pool\" leak"),
+            vec![
+                term("fix", false, false),
+                term("connection pool", true, false),
+                term("leak", false, true)
+            ]
+        );
+        assert_eq!(
+            parse_terms("say \"hello wor"),
+            vec![term("say", false, false), term("hello wor", true, false)]
+        );
+        assert!(parse_terms(" \"\" ").is_empty());
+    }
+
+    #[test]
+    fn test_split_metadata_terms() {
+        let (rest, fields) =
+            split_metadata_terms("timeout Metadata:Customer=\"Acme Corp\" \"pool leak\" metadata:sprint=42");
+        assert_eq!(rest, "timeout \"pool leak\"");
+        assert_eq!(
+            fields.into_iter().collect::<Vec<_>>(),
+            vec![
+                ("customer".to_string(), "Acme Corp".to_string()),
+                ("sprint".to_string(), "42".to_string())
+            ]
+        );
+
+        let (rest, fields) = split_metadata_terms("metadata:sprint= metadata: \"metadata:a=b\"");
+        assert_eq!(rest, "metadata:sprint= metadata: \"metadata:a=b\"");
+        assert!(fields.is_empty());
+    }
+}
@@ -0,0 +1,166 @@
+//! Terms that characterize a set of conversations, for a word cloud.
+//!
+//! Terms come from the full-text index's vocabulary, read through `fts5vocab`
+//! tables: how often each term occurs in the set's conversations, and in how
+//! many conversations of the whole index it occurs. Weights are tf-idf
+//! style, so words used everywhere ("file", "the") rank below the ones that
+//! set the conversations apart. Terms are as indexed, so they're stems under
+//! the Porter tokenizer.
+
+use crate::db::DbResult;
+use crate::models::TopTerm;
+use rusqlite::Connection;
+use std::collections::HashMap;
+
+/// Shortest term worth showing, in characters.
+const MIN_TERM_CHARS: usize = 3;
+
+/// Creates the per-connection vocabulary tables of `conversations_fts`.
+///
+/// They read the index lazily, so they keep working after the FTS table is
+/// rebuilt with another tokenizer.
+fn create_vocabulary_tables(conn: &Connection) -> DbResult<()> {
+    conn.execute_batch(
+        r#"
+        CREATE VIRTUAL TABLE IF NOT EXISTS temp.conversations_fts_instance
+            USING fts5vocab(main, conversations_fts, instance);
+        CREATE VIRTUAL TABLE IF NOT EXISTS temp.conversations_fts_col
+            USING fts5vocab(main, conversations_fts, col);
+        "#,
+    )?;
+    Ok(())
+}
+
+/// Returns true for terms that say something: long enough, and not just
+/// digits (line numbers, ports, IDs).
+fn is_meaningful(term: &str) -> bool {
+    term.chars().count() >= MIN_TERM_CHARS && !term.chars().all(|c| c.is_ascii_digit())
+}
+
+/// Weighs a term by its occurrences in the set (sublinearly) and how rare
+/// it is in the index: `(1 + ln tf) * ln((N + 1) / df)`.
+fn weight(occurrences: i64, indexed_conversations: i64, total_conversations: i64) -> f64 {
+    let tf = 1.0 + (occurrences as f64).ln();
+    let idf = ((total_conversations + 1) as f64 / indexed_conversations.max(1) as f64).ln();
+    tf * idf
+}
+
+/// Finds the `limit` terms of the conversations' content with the highest
+/// weight, highest first. Unknown IDs are ignored.
+///
+/// Reading per-conversation occurrences scans the whole index, so this is
+/// meant for occasional use, not every keystroke.
+pub fn top_terms(
+    conn: &Connection,
+    conversation_ids: &[String],
+    limit: usize,
+) -> DbResult<Vec<TopTerm>> {
+    if conversation_ids.is_empty() || limit == 0 {
+        return Ok(Vec::new());
+    }
+    create_vocabulary_tables(conn)?;
+
+    let ids = serde_json::to_string(conversation_ids).unwrap_or_default();
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT v.term, COUNT(*), COUNT(DISTINCT v.doc)
+        FROM temp.conversations_fts_instance v
+        WHERE v.col = 'content'
+          AND v.doc IN (
+              SELECT rowid FROM conversations
+              WHERE id IN (SELECT value FROM json_each(?1))
+          )
+        GROUP BY v.term
+        "#,
+    )?;
+    let rows = stmt.query_map([ids], |row| {
+        Ok((
+            row.get::<_, String>(0)?,
+            row.get::<_, i64>(1)?,
+            row.get::<_, i64>(2)?,
+        ))
+    })?;
+    let mut in_set = Vec::new();
+    for row in rows {
+        let (term, occurrences, conversations) = row?;
+        if is_meaningful(&term) {
+            in_set.push((term, occurrences, conversations));
+        }
+    }
+
+    let mut document_counts: HashMap<String, i64> = HashMap::new();
+    let mut stmt = conn.prepare(
+        "SELECT term, doc FROM temp.conversations_fts_col WHERE col = 'content'",
+    )?;
+    let rows = stmt.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?)))?;
+    for row in rows {
+        let (term, count) = row?;
+        document_counts.insert(term, count);
+    }
+    let total: i64 = conn.query_row("SELECT COUNT(*) FROM conversations", [], |row| row.get(0))?;
+
+    let mut terms: Vec<TopTerm> = in_set
+        .into_iter()
+        .map(|(term, occurrences, conversations)| {
+            let indexed = document_counts.get(&term).copied().unwrap_or(conversations);
+            TopTerm {
+                weight: weight(occurrences, indexed, total),
+                term,
+                occurrences,
+                conversations,
+            }
+        })
+        .collect();
+    terms.sort_by(|a, b| {
+        b.weight
+            .total_cmp(&a.weight)
+            .then_with(|| a.term.cmp(&b.term))
+    });
+    terms.truncate(limit);
+    Ok(terms)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::sqlite::init_db;
+
+    fn insert_conversation(conn: &Connection, id: &str, content: &str) {
+        conn.execute(
+            "INSERT INTO conversations (id, project_path, project_name, start_time, last_time, file_path, file_modified_at)
+             VALUES (?1, '/p', 'p', '', '', '/f.jsonl', '')",
+            [id],
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO conversations_fts (rowid, content, project_name)
+             SELECT rowid, ?2, project_name FROM conversations WHERE id = ?1",
+            [id, content],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_top_terms_prefers_distinctive_terms() {
+        let conn = Connection::open_in_memory().unwrap();
+        init_db(&conn).unwrap();
+        insert_conversation(&conn, "c1", "the parser fails on the parser input 404");
+        insert_conversation(&conn, "c2", "the webhook retries the delivery");
+        insert_conversation(&conn, "c3", "the parser tests");
+
+        let ids = vec!["c1".to_string(), "unknown".to_string()];
+        let terms = top_terms(&conn, &ids, 10).unwrap();
+        let names: Vec<&str> = terms.iter().map(|term| term.term.as_str()).collect();
+        // "parser" occurs twice but also in c3; "the" is everywhere, and
+        // short or numeric terms are left out
+        assert_eq!(names, vec!["fails", "input", "parser", "the"]);
+        assert_eq!((terms[2].occurrences, terms[2].conversations), (2, 1));
+        assert!(terms[0].weight > terms[2].weight);
+        assert!(terms[2].weight > terms[3].weight);
+
+        let both = top_terms(&conn, &["c1".to_string(), "c3".to_string()], 1).unwrap();
+        assert_eq!(both[0].term, "parser");
+        assert_eq!(both[0].conversations, 2);
+        assert!(top_terms(&conn, &[], 10).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,158 @@
+//! Typo-tolerant fallback search over the trigram index.
+//!
+//! `conversations_trigram` indexes the same text as `conversations_fts`
+//! with FTS5's trigram tokenizer. Each query word is split into trigrams,
+//! and a conversation's similarity to the word is the fraction of those
+//! trigrams its text contains, so "conevrsation" still shares most of its
+//! trigrams with "conversation".
+
+use crate::db::DbResult;
+use rusqlite::Connection;
+use std::collections::HashMap;
+
+/// Fraction of a word's trigrams a conversation must contain to match it.
+pub const MIN_TRIGRAM_SIMILARITY: f64 = 0.5;
+
+/// A conversation found by [`trigram_search`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrigramMatch {
+    /// Row ID of the conversation.
+    pub rowid: i64,
+    /// Mean similarity over the query words, from 0 to 1.
+    pub similarity: f64,
+}
+
+/// Distinct lowercase trigrams of a word.
+fn word_trigrams(word: &str) -> Vec<String> {
+    let chars: Vec<char> = word.chars().flat_map(char::to_lowercase).collect();
+    let mut trigrams: Vec<String> = chars.windows(3).map(|w| w.iter().collect()).collect();
+    trigrams.sort();
+    trigrams.dedup();
+    trigrams
+}
+
+/// Finds conversations similar to every word of `query` (words shorter than
+/// three characters are ignored), most similar first.
+pub fn trigram_search(conn: &Connection, query: &str) -> DbResult<Vec<TrigramMatch>> {
+    let words: Vec<Vec<String>> = query
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| word.chars().count() >= 3)
+        .map(word_trigrams)
+        .collect();
+    if words.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut stmt = conn
+        .prepare_cached("SELECT rowid FROM conversations_trigram WHERE conversations_trigram MATCH ?1")?;
+
+    // Summed similarity of conversations matching every word so far
+    let mut totals: Option<HashMap<i64, f64>> = None;
+    for trigrams in &words {
+        let mut hits: HashMap<i64, usize> = HashMap::new();
+        for trigram in trigrams {
+            // Quoted, so the trigram is matched literally
+            let rows = stmt.query_map([format!("\"{}\"", trigram)], |row| row.get::<_, i64>(0))?;
+            for rowid in rows {
+                *hits.entry(rowid?).or_default() += 1;
+            }
+        }
+
+        let similar = hits
+            .into_iter()
+            .map(|(rowid, count)| (rowid, count as f64 / trigrams.len() as f64))
+            .filter(|&(_, similarity)| similarity >= MIN_TRIGRAM_SIMILARITY);
+        totals = Some(match totals {
+            None => similar.collect(),
+            Some(previous) => similar
+                .filter_map(|(rowid, similarity)| {
+                    previous
+                        .get(&rowid)
+                        .map(|total| (rowid, total + similarity))
+                })
+                .collect(),
+        });
+    }
+
+    let mut matches: Vec<TrigramMatch> = totals
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(rowid, total)| TrigramMatch {
+            rowid,
+            similarity: total / words.len() as f64,
+        })
+        .collect();
+    matches.sort_by(|a, b| {
+        b.similarity
+            .total_cmp(&a.similarity)
+            .then(a.rowid.cmp(&b.rowid))
+    });
+    Ok(matches)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::sqlite::init_db;
+
+    fn setup(texts: &[&str]) -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        init_db(&conn).unwrap();
+        for (i, text) in texts.iter().enumerate() {
+            conn.execute(
+                "INSERT INTO conversations_trigram(rowid, content, project_name) VALUES (?1, ?2, 'project')",
+                rusqlite::params![i as i64 + 1, text],
+            )
+            .unwrap();
+        }
+        conn
+    }
+
+    fn rowids(conn: &Connection, query: &str) -> Vec<i64> {
+        trigram_search(conn, query)
+            .unwrap()
+            .into_iter()
+            .map(|m| m.rowid)
+            .collect()
+    }
+
+    #[test]
+    fn test_word_trigrams() {
+        assert_eq!(word_trigrams("Abab"), vec!["aba", "bab"]);
+        assert!(word_trigrams("ab").is_empty());
+    }
+
+    #[test]
+    fn test_misspellings_match() {
+        let conn = setup(&[
+            "Conversation list is slow to load",
+            "Add a dark mode toggle to the settings page",
+            "Kubernetes deployment fails",
+        ]);
+        assert_eq!(rowids(&conn, "conevrsation"), vec![1]);
+        assert_eq!(rowids(&conn, "togle settngs"), vec![2]);
+        assert_eq!(rowids(&conn, "kubernets"), vec![3]);
+    }
+
+    #[test]
+    fn test_every_word_must_match() {
+        let conn = setup(&["Add a dark mode toggle", "Dark theme colors"]);
+        assert_eq!(rowids(&conn, "drak"), Vec::<i64>::new());
+        assert_eq!(rowids(&conn, "darkk"), vec![1, 2]);
+        assert_eq!(rowids(&conn, "darkk togle"), vec![1]);
+    }
+
+    #[test]
+    fn test_closer_matches_rank_first() {
+        let conn = setup(&["Refactor the config parser", "Refactoring configuration"]);
+        let matches = trigram_search(&conn, "configuraton").unwrap();
+        assert_eq!(matches[0].rowid, 2);
+        assert!(matches[0].similarity > matches.get(1).map_or(0.0, |m| m.similarity));
+    }
+
+    #[test]
+    fn test_short_words_are_ignored() {
+        let conn = setup(&["Fix a db bug"]);
+        assert!(rowids(&conn, "db").is_empty());
+    }
+}
@@ -0,0 +1,212 @@
+//! Sealing documents into self-contained encrypted HTML pages.
+//!
+//! The document is encrypted with AES-256-GCM under a key derived from the
+//! passphrase with PBKDF2-HMAC-SHA256, the same primitives the browser's
+//! Web Crypto API offers, so the page decrypts it with no code beyond its
+//! own script. The page asks for the passphrase, refuses to open after the
+//! expiry, and replaces itself with the decrypted document.
+
+use crate::export::html::escape_html;
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes256Gcm, Nonce};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use serde_json::json;
+use sha2::Sha256;
+use std::io;
+
+/// PBKDF2 iterations deriving the key; each guess at a passphrase costs
+/// as many. Pages read the count from their bundle, so tests, which run
+/// unoptimized, get by with fewer.
+pub const KEY_ITERATIONS: u32 = if cfg!(test) { 1_000 } else { 310_000 };
+
+/// Characters of passphrases, without look-alikes such as `l`, `1`, `o`,
+/// and `0`. There are 32, so each random byte picks one without bias.
+const PASSPHRASE_ALPHABET: &[u8; 32] = b"abcdefghijkmnpqrstuvwxyz23456789";
+
+/// Groups of passphrase characters, and characters per group: 20 random
+/// characters hold 100 bits.
+const PASSPHRASE_GROUPS: usize = 4;
+const PASSPHRASE_GROUP_LEN: usize = 5;
+
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+
+const STYLE: &str = "body{font-family:system-ui,sans-serif;max-width:32rem;margin:4rem auto;padding:0 1rem;line-height:1.5;color:#1a1a1a}\
+form{display:flex;gap:.5rem}input{flex:1;padding:.5rem;font:inherit}button{padding:.5rem 1rem;font:inherit}\
+.meta{color:#666;font-size:.875rem}";
+
+/// Opens the bundle in `BUNDLE`: derives the key from the passphrase,
+/// decrypts, and replaces the page with the document.
+const SCRIPT: &str = r#"const bytes = (text) => Uint8Array.from(atob(text), (c) => c.charCodeAt(0));
+const form = document.getElementById("unlock");
+const status = document.getElementById("status");
+function expired() {
+  if (Date.now() < BUNDLE.expiresAt) return false;
+  status.textContent = "This link has expired.";
+  form.remove();
+  return true;
+}
+if (!expired()) {
+  form.addEventListener("submit", async (event) => {
+    event.preventDefault();
+    if (expired()) return;
+    const passphrase = document.getElementById("passphrase").value.trim().toLowerCase();
+    try {
+      const material = await crypto.subtle.importKey(
+        "raw", new TextEncoder().encode(passphrase), "PBKDF2", false, ["deriveKey"]);
+      const { salt, iterations } = BUNDLE;
+      const key = await crypto.subtle.deriveKey(
+        { name: "PBKDF2", salt: bytes(salt), iterations, hash: "SHA-256" },
+        material, { name: "AES-GCM", length: 256 }, false, ["decrypt"]);
+      const plain = await crypto.subtle.decrypt(
+        { name: "AES-GCM", iv: bytes(BUNDLE.iv) }, key, bytes(BUNDLE.data));
+      document.open();
+      document.write(new TextDecoder().decode(plain));
+      document.close();
+    } catch {
+      status.textContent = "That passphrase doesn't open this conversation.";
+    }
+  });
+}"#;
+
+/// A sealed document and the passphrase opening it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SealedBundle {
+    /// The encrypted page.
+    pub html: String,
+    pub passphrase: String,
+}
+
+/// Fills a buffer of `N` bytes from the operating system's random source.
+pub fn random_bytes<const N: usize>() -> io::Result<[u8; N]> {
+    let mut bytes = [0; N];
+    getrandom::getrandom(&mut bytes).map_err(|e| io::Error::other(e.to_string()))?;
+    Ok(bytes)
+}
+
+/// Generates a passphrase such as `k3f9x-mq2ta-7hwze-c4npr`.
+pub fn generate_passphrase() -> io::Result<String> {
+    let bytes = random_bytes::<{ PASSPHRASE_GROUPS * PASSPHRASE_GROUP_LEN }>()?;
+    let groups: Vec<String> = bytes
+        .chunks(PASSPHRASE_GROUP_LEN)
+        .map(|group| {
+            group
+                .iter()
+                .map(|byte| PASSPHRASE_ALPHABET[(byte % 32) as usize] as char)
+                .collect()
+        })
+        .collect();
+    Ok(groups.join("-"))
+}
+
+/// Derives the AES-256 key of a passphrase. Passphrases are compared
+/// trimmed and in lower case, as the page reads them.
+fn derive_key(passphrase: &str, salt: &[u8]) -> [u8; 32] {
+    let mut key = [0; 32];
+    let passphrase = passphrase.trim().to_lowercase();
+    pbkdf2::pbkdf2_hmac::<Sha256>(passphrase.as_bytes(), salt, KEY_ITERATIONS, &mut key);
+    key
+}
+
+/// Encrypts `plaintext` under `passphrase`, returning the ciphertext
+/// followed by its authentication tag, as Web Crypto expects.
+fn encrypt(plaintext: &[u8], passphrase: &str, salt: &[u8], nonce: &[u8]) -> io::Result<Vec<u8>> {
+    let cipher = Aes256Gcm::new_from_slice(&derive_key(passphrase, salt))
+        .map_err(|e| io::Error::other(e.to_string()))?;
+    cipher
+        .encrypt(Nonce::from_slice(nonce), plaintext)
+        .map_err(|e| io::Error::other(e.to_string()))
+}
+
+/// Seals an HTML document into a page opened by a new passphrase, which
+/// stops opening at `expires_at` (epoch milliseconds). `expires_label` is
+/// the expiry as shown on the page.
+pub fn seal(
+    document: &str,
+    title: &str,
+    expires_at: i64,
+    expires_label: &str,
+) -> io::Result<SealedBundle> {
+    let passphrase = generate_passphrase()?;
+    let salt = random_bytes::<SALT_LEN>()?;
+    let nonce = random_bytes::<NONCE_LEN>()?;
+    let ciphertext = encrypt(document.as_bytes(), &passphrase, &salt, &nonce)?;
+
+    let bundle = json!({
+        "salt": BASE64.encode(salt),
+        "iv": BASE64.encode(nonce),
+        "iterations": KEY_ITERATIONS,
+        "expiresAt": expires_at,
+        "data": BASE64.encode(ciphertext),
+    });
+    let title = escape_html(title);
+    let mut html = String::new();
+    html.push_str("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n");
+    html.push_str("<meta name=\"robots\" content=\"noindex\">\n");
+    html.push_str(&format!("<title>{}</title>\n", title));
+    html.push_str(&format!("<style>{}</style>\n</head>\n<body>\n", STYLE));
+    html.push_str(&format!("<h1>{}</h1>\n", title));
+    html.push_str(&format!(
+        "<p id=\"status\">Enter the passphrase you were given to read this conversation.</p>\n\
+         <form id=\"unlock\"><input id=\"passphrase\" type=\"password\" autocomplete=\"off\" \
+         aria-label=\"Passphrase\" autofocus required><button>Open</button></form>\n\
+         <p class=\"meta\">This link expires {}.</p>\n",
+        escape_html(expires_label)
+    ));
+    html.push_str(&format!(
+        "<script>\nconst BUNDLE = {};\n{}\n</script>\n",
+        bundle, SCRIPT
+    ));
+    html.push_str("</body>\n</html>\n");
+    Ok(SealedBundle { html, passphrase })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use regex::Regex;
+
+    /// Decrypts what [`encrypt`] wrote, as the page does.
+    fn decrypt(ciphertext: &[u8], passphrase: &str, salt: &[u8], nonce: &[u8]) -> Option<Vec<u8>> {
+        Aes256Gcm::new_from_slice(&derive_key(passphrase, salt))
+            .unwrap()
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .ok()
+    }
+
+    #[test]
+    fn test_generate_passphrase() {
+        let passphrase = generate_passphrase().unwrap();
+        let pattern = Regex::new("^[a-km-np-z2-9]{5}(-[a-km-np-z2-9]{5}){3}$").unwrap();
+        assert!(pattern.is_match(&passphrase), "{}", passphrase);
+        assert_ne!(passphrase, generate_passphrase().unwrap());
+    }
+
+    #[test]
+    fn test_sealed_page_opens_with_its_passphrase_only() {
+        let document = "<html><body>Why does the login test fail?</body></html>";
+        let sealed = seal(document, "my-project <2025>", 1_700_000_000_000, "Jan 1").unwrap();
+        assert!(!sealed.html.contains("login test"));
+        assert!(sealed
+            .html
+            .contains("<title>my-project &lt;2025&gt;</title>"));
+        assert!(sealed.html.contains("\"expiresAt\":1700000000000"));
+
+        let field = |name: &str| {
+            let pattern = Regex::new(&format!("\"{}\":\"([^\"]+)\"", name)).unwrap();
+            BASE64
+                .decode(&pattern.captures(&sealed.html).unwrap()[1])
+                .unwrap()
+        };
+        let (salt, nonce, data) = (field("salt"), field("iv"), field("data"));
+        let opened = decrypt(
+            &data,
+            &format!(" {} ", sealed.passphrase.to_uppercase()),
+            &salt,
+            &nonce,
+        );
+        assert_eq!(opened.as_deref(), Some(document.as_bytes()));
+        assert_eq!(decrypt(&data, "wrong-passphrase", &salt, &nonce), None);
+    }
+}
@@ -0,0 +1,286 @@
+//! Expiring, encrypted share bundles of conversations.
+//!
+//! A share bundle is one HTML file holding a conversation's HTML export,
+//! encrypted (see [`bundle`]) under a passphrase that's returned once and
+//! never stored. It's safer to send around than a raw export: the file is
+//! unreadable without the passphrase, which goes over another channel.
+//! Bundles are written to the Shares folder in app data and tracked in
+//! `share_bundles` until they're revoked or expire, either of which deletes
+//! the file. Copies made before then can't be recalled, but their page
+//! stops opening at the expiry.
+
+pub mod bundle;
+
+pub use bundle::{generate_passphrase, seal, SealedBundle};
+
+use crate::db::sqlite::{get_app_data_dir, Database, DbResult};
+use crate::export::{export_file_stem, render_conversation};
+use crate::locale::DateFormatter;
+use crate::models::{Conversation, ExportFormat, ShareBundle};
+use bundle::random_bytes;
+use chrono::DateTime;
+use rusqlite::{params, Connection, OptionalExtension};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use tracing::{error, info};
+
+/// Name of the folder in app data bundles are written to.
+pub const SHARES_DIR_NAME: &str = "Shares";
+
+/// How long bundles open by default.
+pub const DEFAULT_SHARE_TTL_HOURS: u32 = 24;
+
+/// Longest a bundle may open for.
+pub const MAX_SHARE_TTL_HOURS: u32 = 30 * 24;
+
+/// How often expired bundles are deleted.
+const CLEANUP_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// Returns the folder bundles are written to.
+pub fn shares_dir() -> DbResult<PathBuf> {
+    Ok(get_app_data_dir()?.join(SHARES_DIR_NAME))
+}
+
+/// Writes the share bundle of a conversation into `dir` and tracks it. The
+/// bundle opens for `ttl` from `now` (epoch milliseconds); dates are
+/// formatted by `dates`. The returned bundle carries its passphrase.
+pub fn create_share_bundle(
+    conn: &Connection,
+    conversation: &Conversation,
+    dates: &DateFormatter,
+    dir: &Path,
+    ttl: Duration,
+    now: i64,
+) -> DbResult<ShareBundle> {
+    let expires_at = now + ttl.as_millis() as i64;
+    let document = render_conversation(conversation, ExportFormat::Html, dates);
+    let expires = rfc3339(expires_at);
+    let sealed = seal(
+        &document,
+        &conversation.project_name,
+        expires_at,
+        &dates.datetime(&expires),
+    )?;
+
+    let id: String = random_bytes::<8>()?
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+    fs::create_dir_all(dir)?;
+    let path = dir.join(format!("{}_{}.html", export_file_stem(conversation), id));
+    fs::write(&path, sealed.html)?;
+    let path = path.to_string_lossy().into_owned();
+
+    let created_at = rfc3339(now);
+    conn.execute(
+        "INSERT INTO share_bundles (id, conversation_id, path, created_at, expires_at)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![id, conversation.id, path, created_at, expires_at],
+    )?;
+    info!(
+        "Created share bundle {} of {}, expiring {}",
+        id, conversation.id, expires
+    );
+    Ok(ShareBundle {
+        id,
+        conversation_id: conversation.id.clone(),
+        url: file_url(&path),
+        path,
+        created_at,
+        expires_at: expires,
+        passphrase: Some(sealed.passphrase),
+    })
+}
+
+/// Lists the bundles not yet revoked or deleted as expired, newest first.
+pub fn list_share_bundles(conn: &Connection) -> DbResult<Vec<ShareBundle>> {
+    let mut stmt = conn.prepare_cached(
+        "SELECT id, conversation_id, path, created_at, expires_at
+         FROM share_bundles
+         ORDER BY created_at DESC, id",
+    )?;
+    let rows = stmt.query_map([], |row| {
+        let path: String = row.get(2)?;
+        Ok(ShareBundle {
+            id: row.get(0)?,
+            conversation_id: row.get(1)?,
+            url: file_url(&path),
+            path,
+            created_at: row.get(3)?,
+            expires_at: rfc3339(row.get(4)?),
+            passphrase: None,
+        })
+    })?;
+    Ok(rows.collect::<Result<_, _>>()?)
+}
+
+/// Revokes a bundle: deletes its file and stops tracking it. Returns
+/// whether the bundle was tracked.
+pub fn revoke_share_bundle(conn: &Connection, id: &str) -> DbResult<bool> {
+    let path: Option<String> = conn
+        .query_row(
+            "SELECT path FROM share_bundles WHERE id = ?1",
+            [id],
+            |row| row.get(0),
+        )
+        .optional()?;
+    let Some(path) = path else {
+        return Ok(false);
+    };
+    remove_bundle_file(&path)?;
+    conn.execute("DELETE FROM share_bundles WHERE id = ?1", [id])?;
+    info!("Revoked share bundle {}", id);
+    Ok(true)
+}
+
+/// Deletes the bundles expired at `now` (epoch milliseconds). Returns how
+/// many were deleted.
+pub fn remove_expired_share_bundles(conn: &Connection, now: i64) -> DbResult<usize> {
+    let expired = {
+        let mut stmt =
+            conn.prepare_cached("SELECT id, path FROM share_bundles WHERE expires_at <= ?1")?;
+        let rows = stmt.query_map([now], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })?;
+        rows.collect::<Result<Vec<_>, _>>()?
+    };
+    for (id, path) in &expired {
+        remove_bundle_file(path)?;
+        conn.execute("DELETE FROM share_bundles WHERE id = ?1", [id])?;
+    }
+    Ok(expired.len())
+}
+
+/// Starts the background thread that deletes expired bundles.
+pub fn start_cleaner(db: Arc<Database>) {
+    let spawned = thread::Builder::new()
+        .name("share-cleaner".to_string())
+        .spawn(move || loop {
+            let now = chrono::Utc::now().timestamp_millis();
+            match db.with_connection(|conn| remove_expired_share_bundles(conn, now)) {
+                Ok(0) => {}
+                Ok(removed) => info!("Shares: deleted {} expired bundles", removed),
+                Err(e) => error!("Shares: failed to delete expired bundles: {}", e),
+            }
+            thread::sleep(CLEANUP_INTERVAL);
+        });
+    if let Err(e) = spawned {
+        error!("Failed to start share cleaner: {}", e);
+    }
+}
+
+/// Deletes a bundle's file, if it's still there.
+fn remove_bundle_file(path: &str) -> io::Result<()> {
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+fn rfc3339(millis: i64) -> String {
+    DateTime::from_timestamp_millis(millis)
+        .unwrap_or_default()
+        .to_rfc3339()
+}
+
+/// `file://` link to a path, with the characters URLs reserve escaped.
+fn file_url(path: &str) -> String {
+    let path = path.replace('\\', "/");
+    let mut url = String::from(if path.starts_with('/') {
+        "file://"
+    } else {
+        "file:///"
+    });
+    for c in path.chars() {
+        match c {
+            ' ' => url.push_str("%20"),
+            '#' => url.push_str("%23"),
+            '%' => url.push_str("%25"),
+            '?' => url.push_str("%3F"),
+            c => url.push(c),
+        }
+    }
+    url
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::sqlite::init_db;
+    use crate::models::TokenCount;
+    use tempfile::tempdir;
+
+    fn conversation() -> Conversation {
+        Conversation {
+            id: "c1".to_string(),
+            project_path: "/work/api".to_string(),
+            project_name: "api".to_string(),
+            start_time: "2025-01-15T10:00:00Z".to_string(),
+            last_time: "2025-01-15T10:30:00Z".to_string(),
+            messages: Vec::new(),
+            total_tokens: TokenCount::default(),
+            bookmarked: None,
+            tags: None,
+            completeness: Default::default(),
+            content_hash: None,
+            read_position: None,
+        }
+    }
+
+    #[test]
+    fn test_file_url() {
+        assert_eq!(
+            file_url("/tmp/my shares/a#1.html"),
+            "file:///tmp/my%20shares/a%231.html"
+        );
+        assert_eq!(
+            file_url(r"C:\Users\me\a.html"),
+            "file:///C:/Users/me/a.html"
+        );
+    }
+
+    #[test]
+    fn test_share_bundles_are_revoked_or_expire() {
+        let conn = Connection::open_in_memory().unwrap();
+        init_db(&conn).unwrap();
+        let dir = tempdir().unwrap();
+        let dates = DateFormatter::default();
+        let now = 1_700_000_000_000;
+        let hour = Duration::from_secs(60 * 60);
+
+        let short =
+            create_share_bundle(&conn, &conversation(), &dates, dir.path(), hour, now).unwrap();
+        let long =
+            create_share_bundle(&conn, &conversation(), &dates, dir.path(), 2 * hour, now).unwrap();
+        assert!(short.passphrase.is_some());
+        assert!(short
+            .path
+            .ends_with(&format!("api_2025-01-15_{}.html", short.id)));
+        assert!(Path::new(&short.path).is_file());
+
+        let listed = list_share_bundles(&conn).unwrap();
+        assert_eq!(listed.len(), 2);
+        assert!(listed.iter().all(|bundle| bundle.passphrase.is_none()));
+        let listed_short = listed.iter().find(|bundle| bundle.id == short.id).unwrap();
+        assert_eq!(listed_short.expires_at, short.expires_at);
+        assert_eq!(listed_short.url, file_url(&short.path));
+
+        // Only bundles past their expiry are deleted
+        let in_90_minutes = now + 90 * 60 * 1000;
+        assert_eq!(
+            remove_expired_share_bundles(&conn, in_90_minutes).unwrap(),
+            1
+        );
+        assert!(!Path::new(&short.path).exists());
+        assert!(Path::new(&long.path).is_file());
+
+        assert!(revoke_share_bundle(&conn, &long.id).unwrap());
+        assert!(!revoke_share_bundle(&conn, &long.id).unwrap());
+        assert!(!Path::new(&long.path).exists());
+        assert!(list_share_bundles(&conn).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,282 @@
+//! Sharing conversations to Slack.
+//!
+//! A share posts a conversation's summary to a channel as a message, then
+//! uploads the full transcript as a file in that message's thread, using
+//! the Web API with the bot token from settings. Files go through the
+//! external upload flow: `files.getUploadURLExternal`, a POST of the
+//! contents, then `files.completeUploadExternal` to share them.
+
+use crate::models::SlackShare;
+use serde_json::{json, Value};
+use std::time::Duration;
+use thiserror::Error;
+
+/// Base URL of Slack Web API methods.
+const API_BASE_URL: &str = "https://slack.com/api/";
+
+/// How long Slack has to respond to each request.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Errors sharing to Slack.
+#[derive(Error, Debug)]
+pub enum SlackError {
+    /// Slack refused the request, e.g. `channel_not_found` or `invalid_auth`.
+    #[error("Slack API error: {0}")]
+    Api(String),
+
+    /// Slack couldn't be reached or sent an unreadable response.
+    #[error("Slack request failed: {0}")]
+    Request(String),
+}
+
+/// Calls the Slack Web API.
+pub trait SlackTransport {
+    /// Calls a Web API method with form arguments, returning the response
+    /// body.
+    fn call(&self, method: &str, args: &[(&str, String)]) -> Result<Value, SlackError>;
+
+    /// POSTs file contents to an upload URL from `files.getUploadURLExternal`.
+    fn upload(&self, url: &str, contents: &[u8]) -> Result<(), SlackError>;
+}
+
+/// Calls the Web API over HTTPS with a bot token.
+pub struct SlackClient {
+    agent: ureq::Agent,
+    token: String,
+}
+
+impl SlackClient {
+    pub fn new(token: &str) -> Self {
+        Self {
+            agent: ureq::AgentBuilder::new().timeout(REQUEST_TIMEOUT).build(),
+            token: token.to_string(),
+        }
+    }
+}
+
+fn request_error(error: ureq::Error) -> SlackError {
+    match error {
+        ureq::Error::Status(status, _) => SlackError::Request(format!("HTTP {}", status)),
+        e => SlackError::Request(e.to_string()),
+    }
+}
+
+impl SlackTransport for SlackClient {
+    fn call(&self, method: &str, args: &[(&str, String)]) -> Result<Value, SlackError> {
+        let form: Vec<(&str, &str)> = args.iter().map(|(k, v)| (*k, v.as_str())).collect();
+        let body = self
+            .agent
+            .post(&format!("{}{}", API_BASE_URL, method))
+            .set("Authorization", &format!("Bearer {}", self.token))
+            .send_form(&form)
+            .map_err(request_error)?
+            .into_string()
+            .map_err(|e| SlackError::Request(e.to_string()))?;
+        serde_json::from_str(&body).map_err(|e| SlackError::Request(e.to_string()))
+    }
+
+    fn upload(&self, url: &str, contents: &[u8]) -> Result<(), SlackError> {
+        self.agent
+            .post(url)
+            .send_bytes(contents)
+            .map_err(request_error)?;
+        Ok(())
+    }
+}
+
+/// Calls a method, turning an `ok: false` response into an error.
+fn call_ok(
+    transport: &dyn SlackTransport,
+    method: &str,
+    args: &[(&str, String)],
+) -> Result<Value, SlackError> {
+    let response = transport.call(method, args)?;
+    if response["ok"].as_bool() == Some(true) {
+        return Ok(response);
+    }
+    let error = response["error"].as_str().unwrap_or("unknown_error");
+    Err(SlackError::Api(error.to_string()))
+}
+
+/// Reads a string field of a response.
+fn field(response: &Value, name: &str) -> Result<String, SlackError> {
+    response[name]
+        .as_str()
+        .map(str::to_string)
+        .ok_or_else(|| SlackError::Request(format!("response has no {}", name)))
+}
+
+/// Posts a summary to a channel and uploads a transcript in its thread.
+///
+/// # Arguments
+/// * `transport` - Calls the Web API
+/// * `channel` - Channel name (with or without `#`) or ID
+/// * `summary` - Markdown shown in the message
+/// * `file_name` - Name of the transcript file
+/// * `transcript` - Contents of the transcript file
+pub fn share(
+    transport: &dyn SlackTransport,
+    channel: &str,
+    summary: &str,
+    file_name: &str,
+    transcript: &str,
+) -> Result<SlackShare, SlackError> {
+    let channel = channel.trim().trim_start_matches('#');
+    let blocks = json!([{ "type": "markdown", "text": summary }]);
+    let message = call_ok(
+        transport,
+        "chat.postMessage",
+        &[
+            ("channel", channel.to_string()),
+            // Shown in notifications, which don't render blocks
+            ("text", summary.to_string()),
+            ("blocks", blocks.to_string()),
+            ("unfurl_links", "false".to_string()),
+        ],
+    )?;
+    let channel_id = field(&message, "channel")?;
+    let message_ts = field(&message, "ts")?;
+
+    let upload = call_ok(
+        transport,
+        "files.getUploadURLExternal",
+        &[
+            ("filename", file_name.to_string()),
+            ("length", transcript.len().to_string()),
+        ],
+    )?;
+    let file_id = field(&upload, "file_id")?;
+    transport.upload(&field(&upload, "upload_url")?, transcript.as_bytes())?;
+
+    let files = json!([{ "id": file_id, "title": file_name }]);
+    call_ok(
+        transport,
+        "files.completeUploadExternal",
+        &[
+            ("files", files.to_string()),
+            ("channel_id", channel_id.clone()),
+            ("thread_ts", message_ts.clone()),
+        ],
+    )?;
+
+    Ok(SlackShare {
+        channel_id,
+        message_ts,
+        file_id,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+
+    /// A method called and its arguments.
+    type Call = (String, Vec<(String, String)>);
+
+    /// Answers each method with a canned response, recording the calls.
+    struct MockTransport {
+        responses: Vec<(&'static str, Value)>,
+        calls: RefCell<Vec<Call>>,
+        uploads: RefCell<Vec<(String, Vec<u8>)>>,
+    }
+
+    impl MockTransport {
+        fn new(responses: Vec<(&'static str, Value)>) -> Self {
+            Self {
+                responses,
+                calls: RefCell::new(Vec::new()),
+                uploads: RefCell::new(Vec::new()),
+            }
+        }
+    }
+
+    impl SlackTransport for MockTransport {
+        fn call(&self, method: &str, args: &[(&str, String)]) -> Result<Value, SlackError> {
+            self.calls.borrow_mut().push((
+                method.to_string(),
+                args.iter()
+                    .map(|(k, v)| (k.to_string(), v.clone()))
+                    .collect(),
+            ));
+            Ok(self
+                .responses
+                .iter()
+                .find(|(name, _)| *name == method)
+                .map(|(_, response)| response.clone())
+                .unwrap_or_else(|| json!({ "ok": true })))
+        }
+
+        fn upload(&self, url: &str, contents: &[u8]) -> Result<(), SlackError> {
+            self.uploads
+                .borrow_mut()
+                .push((url.to_string(), contents.to_vec()));
+            Ok(())
+        }
+    }
+
+    fn arg<'a>(args: &'a [(String, String)], name: &str) -> &'a str {
+        &args.iter().find(|(k, _)| k == name).unwrap().1
+    }
+
+    #[test]
+    fn test_share_posts_summary_and_uploads_transcript_in_thread() {
+        let transport = MockTransport::new(vec![
+            (
+                "chat.postMessage",
+                json!({ "ok": true, "channel": "C123", "ts": "1736935200.000100" }),
+            ),
+            (
+                "files.getUploadURLExternal",
+                json!({ "ok": true, "upload_url": "https://files.slack.com/upload/v1/abc", "file_id": "F456" }),
+            ),
+        ]);
+
+        let shared = share(&transport, " #incidents ", "**api**", "api.md", "# api\n").unwrap();
+        assert_eq!(
+            shared,
+            SlackShare {
+                channel_id: "C123".to_string(),
+                message_ts: "1736935200.000100".to_string(),
+                file_id: "F456".to_string(),
+            }
+        );
+
+        let calls = transport.calls.borrow();
+        let methods: Vec<&str> = calls.iter().map(|(m, _)| m.as_str()).collect();
+        assert_eq!(
+            methods,
+            vec![
+                "chat.postMessage",
+                "files.getUploadURLExternal",
+                "files.completeUploadExternal"
+            ]
+        );
+        assert_eq!(arg(&calls[0].1, "channel"), "incidents");
+        assert_eq!(
+            arg(&calls[0].1, "blocks"),
+            r#"[{"text":"**api**","type":"markdown"}]"#
+        );
+        assert_eq!(arg(&calls[1].1, "length"), "6");
+        assert_eq!(arg(&calls[2].1, "channel_id"), "C123");
+        assert_eq!(arg(&calls[2].1, "thread_ts"), "1736935200.000100");
+        assert_eq!(
+            *transport.uploads.borrow(),
+            vec![(
+                "https://files.slack.com/upload/v1/abc".to_string(),
+                b"# api\n".to_vec()
+            )]
+        );
+    }
+
+    #[test]
+    fn test_share_stops_at_api_error() {
+        let transport = MockTransport::new(vec![(
+            "chat.postMessage",
+            json!({ "ok": false, "error": "channel_not_found" }),
+        )]);
+        let result = share(&transport, "nope", "summary", "a.md", "");
+        assert!(matches!(result, Err(SlackError::Api(ref e)) if e == "channel_not_found"));
+        assert_eq!(transport.calls.borrow().len(), 1);
+    }
+}
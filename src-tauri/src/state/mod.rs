@@ -1,23 +1,55 @@
 //! Application state management.
 //!
 //! This module provides shared application state with thread-safe access
-//! to the database connection and cached conversation data.
+//! to the database connection and cached conversation data, and the state
+//! kept per window.
+
+mod window;
+
+pub use window::{WindowState, Windows};
 
 use crate::db::sqlite::{Database, DbResult};
-use crate::models::ConversationSummary;
-use std::sync::{Arc, RwLock};
-use tracing::{debug, info};
+use crate::demo::write_fixture_files;
+use crate::ingest::{ingest_files, NoProgress};
+use crate::metrics;
+use crate::models::{ConversationSummary, Resolution, UsageAnomaly, WatcherState};
+use std::collections::HashSet;
+use std::fs::File;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, UNIX_EPOCH};
+use tracing::{debug, info, warn};
+
+/// Modification time given to fixture files (2025-01-10T18:00:00Z), so
+/// ingest is deterministic.
+const FIXTURE_MODIFIED_SECS: u64 = 1_736_532_000;
+
+/// Distinguishes fixture directories of states created by the same process.
+static FIXTURE_DIR_COUNTER: AtomicUsize = AtomicUsize::new(0);
 
 /// Application state shared across all Tauri commands.
 ///
 /// Provides thread-safe access to:
 /// - Database connection (via `Database` which has internal `Mutex<Connection>`)
 /// - Conversations cache (via `RwLock<Vec<ConversationSummary>>`)
+/// - The state of each window: search-as-you-type sessions, keyset
+///   checkpoints of conversation lists, replays, and the selection
 pub struct AppState {
     /// Database connection manager.
     db: Arc<Database>,
     /// Cached conversation summaries for faster list retrieval.
     conversations_cache: RwLock<Vec<ConversationSummary>>,
+    /// Usage anomalies already notified in this session.
+    notified_anomalies: Mutex<HashSet<String>>,
+    /// Directory holding fixture session files, removed on drop.
+    fixtures_dir: Option<PathBuf>,
+    /// State of each window, by label.
+    windows: Windows,
+    /// Last state of the file watcher; `None` until it's started.
+    watcher_state: RwLock<Option<WatcherState>>,
+    /// When the last full scan finished (RFC 3339).
+    last_scan_at: RwLock<Option<String>>,
 }
 
 impl AppState {
@@ -33,9 +65,62 @@ impl AppState {
         Ok(Self {
             db: Arc::new(db),
             conversations_cache: RwLock::new(Vec::new()),
+            notified_anomalies: Mutex::new(HashSet::new()),
+            fixtures_dir: None,
+            windows: Windows::default(),
+            watcher_state: RwLock::new(None),
+            last_scan_at: RwLock::new(None),
         })
     }
 
+    /// Creates a new AppState backed by an in-memory database seeded with
+    /// deterministic fixture conversations.
+    ///
+    /// Used by the `--demo` launch flag and UI tests, so they don't depend on
+    /// the user's real history. Fixture session files are written to a
+    /// temporary directory (conversations are loaded from their files) that
+    /// is removed when the state is dropped.
+    pub fn new_in_memory_with_fixtures() -> DbResult<Self> {
+        let db = Database::open_in_memory()?;
+        db.init_schema()?;
+        let db = Arc::new(db);
+
+        let fixtures_dir = std::env::temp_dir().join(format!(
+            "claude-history-demo-{}-{}",
+            std::process::id(),
+            FIXTURE_DIR_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        let state = Self {
+            db: Arc::clone(&db),
+            conversations_cache: RwLock::new(Vec::new()),
+            notified_anomalies: Mutex::new(HashSet::new()),
+            fixtures_dir: Some(fixtures_dir.clone()),
+            windows: Windows::default(),
+            watcher_state: RwLock::new(None),
+            last_scan_at: RwLock::new(None),
+        };
+
+        let paths = write_fixture_files(&fixtures_dir)?;
+        let modified = UNIX_EPOCH + Duration::from_secs(FIXTURE_MODIFIED_SECS);
+        for path in &paths {
+            if let Err(e) = File::options().write(true).open(path).and_then(|f| f.set_modified(modified)) {
+                warn!("Failed to set modification time of fixture {:?}: {}", path, e);
+            }
+        }
+        let stats = ingest_files(&db, &paths, &NoProgress)?;
+        if stats.failed_files > 0 {
+            warn!("Failed to load {} fixture files", stats.failed_files);
+        }
+        state.refresh_conversations_cache()?;
+
+        info!(
+            "AppState initialized with {} fixture conversations",
+            state.cache_size()
+        );
+
+        Ok(state)
+    }
+
     /// Creates a new AppState with a specific database.
     ///
     /// Useful for testing with in-memory or custom database paths.
@@ -43,6 +128,11 @@ impl AppState {
         Self {
             db: Arc::new(db),
             conversations_cache: RwLock::new(Vec::new()),
+            notified_anomalies: Mutex::new(HashSet::new()),
+            fixtures_dir: None,
+            windows: Windows::default(),
+            watcher_state: RwLock::new(None),
+            last_scan_at: RwLock::new(None),
         }
     }
 
@@ -51,18 +141,36 @@ impl AppState {
         Arc::clone(&self.db)
     }
 
+    /// Returns the state of a window, creating it on first use.
+    pub fn window(&self, label: &str) -> Arc<WindowState> {
+        self.windows.get(label)
+    }
+
+    /// Drops the state of a closed window and stops its replays.
+    pub fn close_window(&self, label: &str) {
+        if self.windows.close(label) {
+            debug!("Dropped the state of window {}", label);
+        }
+    }
+
     /// Returns the cached conversation summaries.
     ///
     /// Returns an empty vector if the cache hasn't been populated or is poisoned.
     pub fn get_cached_conversations(&self) -> Vec<ConversationSummary> {
-        match self.conversations_cache.read() {
+        let conversations = match self.conversations_cache.read() {
             Ok(cache) => cache.clone(),
             Err(poisoned) => {
                 // If poisoned, still try to return data
                 debug!("Cache lock was poisoned, recovering");
                 poisoned.into_inner().clone()
             }
-        }
+        };
+        metrics::increment(if conversations.is_empty() {
+            "cache.conversations.misses"
+        } else {
+            "cache.conversations.hits"
+        });
+        conversations
     }
 
     /// Updates the conversations cache with new data.
@@ -85,13 +193,15 @@ impl AppState {
     ///
     /// Loads all conversation summaries sorted by last_time descending.
     pub fn refresh_conversations_cache(&self) -> DbResult<()> {
+        let _timer = metrics::start_timer("cache.conversations.refresh");
         let conversations = self.db.with_connection(|conn| {
             let mut stmt = conn.prepare(
                 r#"
-                SELECT c.id, c.project_name, c.start_time, c.last_time, c.preview, c.message_count,
-                       (SELECT 1 FROM bookmarks b WHERE b.conversation_id = c.id) IS NOT NULL as bookmarked
+                SELECT c.id, c.project_name, c.start_time, c.last_time, c.preview, c.preview_medium, c.message_count,
+                       (SELECT 1 FROM bookmarks b WHERE b.conversation_id = c.id) IS NOT NULL as bookmarked,
+                       c.resolution
                 FROM conversations c
-                ORDER BY c.last_time DESC
+                ORDER BY c.last_epoch DESC
                 "#,
             )?;
 
@@ -102,8 +212,10 @@ impl AppState {
                     start_time: row.get(2)?,
                     last_time: row.get(3)?,
                     preview: row.get(4)?,
-                    message_count: row.get(5)?,
-                    bookmarked: row.get::<_, i32>(6)? != 0,
+                    preview_medium: row.get(5)?,
+                    message_count: row.get(6)?,
+                    bookmarked: row.get::<_, i32>(7)? != 0,
+                    resolution: Resolution::from_db(&row.get::<_, String>(8)?).unwrap_or_default(),
                 })
             })?;
 
@@ -122,6 +234,26 @@ impl AppState {
         Ok(())
     }
 
+    /// Returns the anomalies not yet notified in this session and marks them
+    /// as notified, so a session is only reported once per day.
+    pub fn take_unnotified_anomalies(&self, anomalies: Vec<UsageAnomaly>) -> Vec<UsageAnomaly> {
+        let mut notified = match self.notified_anomalies.lock() {
+            Ok(notified) => notified,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        anomalies
+            .into_iter()
+            .filter(|a| {
+                notified.insert(format!(
+                    "{:?}|{}|{}",
+                    a.kind,
+                    a.day,
+                    a.conversation_id.as_deref().unwrap_or_default()
+                ))
+            })
+            .collect()
+    }
+
     /// Clears the conversations cache.
     pub fn clear_cache(&self) {
         self.set_cached_conversations(Vec::new());
@@ -140,6 +272,49 @@ impl AppState {
     pub fn is_cache_empty(&self) -> bool {
         self.cache_size() == 0
     }
+
+    /// Records the file watcher's state for the health report.
+    pub fn set_watcher_state(&self, state: WatcherState) {
+        match self.watcher_state.write() {
+            Ok(mut current) => *current = Some(state),
+            Err(poisoned) => *poisoned.into_inner() = Some(state),
+        }
+    }
+
+    /// Returns the file watcher's last state, if it was started.
+    pub fn watcher_state(&self) -> Option<WatcherState> {
+        match self.watcher_state.read() {
+            Ok(state) => *state,
+            Err(poisoned) => *poisoned.into_inner(),
+        }
+    }
+
+    /// Records that a full scan of the session files just finished.
+    pub fn record_scan(&self) {
+        let now = chrono::Utc::now().to_rfc3339();
+        match self.last_scan_at.write() {
+            Ok(mut last) => *last = Some(now),
+            Err(poisoned) => *poisoned.into_inner() = Some(now),
+        }
+    }
+
+    /// Returns when the last full scan finished (RFC 3339).
+    pub fn last_scan_at(&self) -> Option<String> {
+        match self.last_scan_at.read() {
+            Ok(last) => last.clone(),
+            Err(poisoned) => poisoned.into_inner().clone(),
+        }
+    }
+}
+
+impl Drop for AppState {
+    fn drop(&mut self) {
+        if let Some(dir) = &self.fixtures_dir {
+            if let Err(e) = std::fs::remove_dir_all(dir) {
+                debug!("Failed to remove fixtures directory {:?}: {}", dir, e);
+            }
+        }
+    }
 }
 
 #[cfg(test)]
@@ -173,8 +348,10 @@ mod tests {
                 start_time: "2025-01-01T00:00:00Z".to_string(),
                 last_time: "2025-01-01T01:00:00Z".to_string(),
                 preview: "Hello world".to_string(),
+                preview_medium: String::new(),
                 message_count: 5,
                 bookmarked: false,
+                resolution: Resolution::Unknown,
             },
             ConversationSummary {
                 id: "conv2".to_string(),
@@ -182,8 +359,10 @@ mod tests {
                 start_time: "2025-01-02T00:00:00Z".to_string(),
                 last_time: "2025-01-02T01:00:00Z".to_string(),
                 preview: "Another conversation".to_string(),
+                preview_medium: String::new(),
                 message_count: 10,
                 bookmarked: true,
+                resolution: Resolution::Unknown,
             },
         ];
 
@@ -208,8 +387,10 @@ mod tests {
             start_time: "2025-01-01T00:00:00Z".to_string(),
             last_time: "2025-01-01T01:00:00Z".to_string(),
             preview: "Test".to_string(),
+            preview_medium: String::new(),
             message_count: 1,
             bookmarked: false,
+            resolution: Resolution::Unknown,
         }];
 
         state.set_cached_conversations(conversations);
@@ -283,4 +464,51 @@ mod tests {
         assert!(result.is_ok());
         assert_eq!(result.unwrap(), 0);
     }
+
+    #[test]
+    fn test_take_unnotified_anomalies_reports_once() {
+        use crate::models::AnomalyKind;
+
+        let state = setup_test_state();
+        let anomaly = UsageAnomaly {
+            kind: AnomalyKind::TokenSpike,
+            day: "2025-01-09".to_string(),
+            conversation_id: Some("runaway".to_string()),
+            project_name: None,
+            value: 50_000,
+            baseline: 1_000.0,
+            score: 9.0,
+        };
+
+        assert_eq!(state.take_unnotified_anomalies(vec![anomaly.clone()]).len(), 1);
+        assert!(state.take_unnotified_anomalies(vec![anomaly.clone()]).is_empty());
+
+        let next_day = UsageAnomaly {
+            day: "2025-01-10".to_string(),
+            ..anomaly
+        };
+        assert_eq!(state.take_unnotified_anomalies(vec![next_day]).len(), 1);
+    }
+
+    #[test]
+    fn test_new_in_memory_with_fixtures() {
+        let state = AppState::new_in_memory_with_fixtures().unwrap();
+        assert_eq!(state.cache_size(), 6);
+        assert_eq!(state.db().path(), &PathBuf::from(":memory:"));
+
+        let fixtures_dir = state.fixtures_dir.clone().unwrap();
+        assert!(fixtures_dir.is_dir());
+
+        // Fixture conversation IDs are stable across states
+        let other = AppState::new_in_memory_with_fixtures().unwrap();
+        let ids = |s: &AppState| {
+            let mut ids: Vec<_> = s.get_cached_conversations().into_iter().map(|c| c.id).collect();
+            ids.sort();
+            ids
+        };
+        assert_eq!(ids(&state), ids(&other));
+
+        drop(state);
+        assert!(!fixtures_dir.exists());
+    }
 }
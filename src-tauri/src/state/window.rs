@@ -0,0 +1,122 @@
+//! State kept per window.
+//!
+//! Each window searches, scrolls its conversation list, replays, and selects
+//! on its own, so the state behind those commands is kept apart per window
+//! label. A window's state is created on first use and dropped when the
+//! window is closed, which also stops its replays.
+
+use crate::db::window::ListCheckpoints;
+use crate::replay::Replays;
+use crate::search::SearchSessions;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, RwLock};
+
+/// The state of one window.
+#[derive(Default)]
+pub struct WindowState {
+    /// Open search sessions of `begin_search` / `update_query`.
+    search_sessions: SearchSessions,
+    /// Checkpoints of `get_conversation_summaries_window` lists.
+    list_checkpoints: ListCheckpoints,
+    /// Replays of `replay_conversation` in progress.
+    replays: Replays,
+    /// ID of the conversation open in the window.
+    selection: RwLock<Option<String>>,
+}
+
+impl WindowState {
+    /// Returns the open search sessions.
+    pub fn search_sessions(&self) -> &SearchSessions {
+        &self.search_sessions
+    }
+
+    /// Returns the checkpoints of conversation list windows.
+    pub fn list_checkpoints(&self) -> &ListCheckpoints {
+        &self.list_checkpoints
+    }
+
+    /// Returns the replays in progress.
+    pub fn replays(&self) -> &Replays {
+        &self.replays
+    }
+
+    /// Returns the ID of the conversation open in the window.
+    pub fn selection(&self) -> Option<String> {
+        self.selection
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Sets the conversation open in the window.
+    pub fn set_selection(&self, id: Option<String>) {
+        *self.selection.write().unwrap_or_else(|e| e.into_inner()) = id;
+    }
+}
+
+/// The state of each window, by label.
+#[derive(Default)]
+pub struct Windows {
+    windows: Mutex<HashMap<String, Arc<WindowState>>>,
+}
+
+impl Windows {
+    /// Returns the state of a window, creating it on first use.
+    pub fn get(&self, label: &str) -> Arc<WindowState> {
+        let mut windows = self.lock();
+        Arc::clone(windows.entry(label.to_string()).or_default())
+    }
+
+    /// Drops the state of a closed window and stops its replays. Returns
+    /// false if the window had no state.
+    pub fn close(&self, label: &str) -> bool {
+        match self.lock().remove(label) {
+            Some(window) => {
+                window.replays().stop_all();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Number of windows with state.
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Whether no window has state.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, Arc<WindowState>>> {
+        self.windows.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_windows_are_isolated_and_closed() {
+        let windows = Windows::default();
+        let main = windows.get("main");
+        main.set_selection(Some("c1".to_string()));
+        let session = main.search_sessions().begin();
+
+        let other = windows.get("other");
+        assert_eq!(other.selection(), None);
+        assert!(other.search_sessions().start_query(&session).is_none());
+        assert_eq!(windows.get("main").selection().as_deref(), Some("c1"));
+        assert_eq!(windows.len(), 2);
+
+        // Closing stops the window's replays and forgets its state
+        let (replay, stopped) = main.replays().begin();
+        assert!(windows.close("main"));
+        assert!(stopped.load(std::sync::atomic::Ordering::Relaxed));
+        assert!(!windows.close("main"));
+        assert_eq!(windows.get("main").selection(), None);
+        assert!(!windows.get("main").replays().stop(&replay));
+    }
+}
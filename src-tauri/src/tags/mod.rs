@@ -0,0 +1,471 @@
+//! Tag suggestions from a conversation's content.
+//!
+//! `suggest_tags` proposes tags to accept with one click, each with a
+//! confidence from 0 to 1, from four kinds of evidence:
+//!
+//! - the languages of the code blocks Claude wrote (`language_usage`);
+//! - frameworks and tools named in the dialogue, or implied by the files
+//!   it touched (`Cargo.toml`, `svelte.config.js`, ...);
+//! - the extensions of the files its tool calls read or changed;
+//! - the tags of similar conversations: those of the same project, or
+//!   sharing its languages.
+//!
+//! Evidence for a tag from several sources combines, so sources that agree
+//! make a suggestion surer. Tags the conversation already has are left out.
+
+use crate::db::operations::current_tags;
+use crate::db::sqlite::DbResult;
+use crate::models::{TagSuggestion, TagSuggestionSource};
+use crate::parser::jsonl::{RawContent, RawMessage};
+use rusqlite::Connection;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::Path;
+
+/// Most suggestions returned for a conversation.
+pub const MAX_SUGGESTIONS: usize = 10;
+
+/// Suggestions less sure than this are left out.
+const MIN_CONFIDENCE: f64 = 0.2;
+
+/// Most similar conversations whose tags are considered.
+const SIMILAR_LIMIT: usize = 20;
+
+/// Frameworks and tools, by tag, with the words that name them.
+const FRAMEWORKS: &[(&str, &[&str])] = &[
+    ("react", &["react", "react.js", "reactjs"]),
+    ("nextjs", &["next.js", "nextjs"]),
+    ("vue", &["vue", "vue.js", "vuejs", "nuxt"]),
+    ("angular", &["angular"]),
+    ("svelte", &["svelte", "sveltekit"]),
+    ("tauri", &["tauri"]),
+    ("electron", &["electron"]),
+    ("tailwind", &["tailwind", "tailwindcss"]),
+    ("node", &["node.js", "nodejs"]),
+    ("express", &["express.js", "expressjs"]),
+    ("django", &["django"]),
+    ("flask", &["flask"]),
+    ("fastapi", &["fastapi"]),
+    ("rails", &["rails"]),
+    ("spring", &["spring", "springboot"]),
+    ("tokio", &["tokio"]),
+    ("axum", &["axum"]),
+    ("actix", &["actix", "actix-web"]),
+    ("docker", &["docker", "dockerfile", "docker-compose"]),
+    ("kubernetes", &["kubernetes", "k8s", "kubectl", "helm"]),
+    ("terraform", &["terraform"]),
+    ("aws", &["aws", "lambda", "s3", "cloudformation"]),
+    ("graphql", &["graphql"]),
+    ("postgres", &["postgres", "postgresql", "psql"]),
+    ("sqlite", &["sqlite", "rusqlite"]),
+    ("redis", &["redis"]),
+    ("prisma", &["prisma"]),
+    ("jest", &["jest"]),
+    ("vitest", &["vitest"]),
+    ("pytest", &["pytest"]),
+    ("playwright", &["playwright"]),
+    ("vite", &["vite"]),
+    ("webpack", &["webpack"]),
+];
+
+/// Files whose presence implies a framework or tool, by file name.
+const MARKER_FILES: &[(&str, &str)] = &[
+    ("cargo.toml", "rust"),
+    ("tauri.conf.json", "tauri"),
+    ("svelte.config.js", "svelte"),
+    ("next.config.js", "nextjs"),
+    ("next.config.mjs", "nextjs"),
+    ("nuxt.config.ts", "vue"),
+    ("angular.json", "angular"),
+    ("tailwind.config.js", "tailwind"),
+    ("tailwind.config.ts", "tailwind"),
+    ("vite.config.js", "vite"),
+    ("vite.config.ts", "vite"),
+    ("webpack.config.js", "webpack"),
+    ("dockerfile", "docker"),
+    ("docker-compose.yml", "docker"),
+    ("docker-compose.yaml", "docker"),
+    ("pyproject.toml", "python"),
+    ("requirements.txt", "python"),
+    ("manage.py", "django"),
+    ("go.mod", "go"),
+    ("gemfile", "ruby"),
+    ("pom.xml", "java"),
+    ("schema.prisma", "prisma"),
+    ("jest.config.js", "jest"),
+    ("vitest.config.ts", "vitest"),
+    ("playwright.config.ts", "playwright"),
+];
+
+/// Confidence of a framework named in one message; each further message
+/// naming it makes it surer.
+const MENTION_CONFIDENCE: f64 = 0.3;
+
+/// Confidence of a framework implied by a file the conversation touched.
+const MARKER_CONFIDENCE: f64 = 0.8;
+
+/// The language tag of a file extension, named as code blocks' languages
+/// are (see `normalize_language`), or `None` for files that aren't code.
+fn extension_tag(extension: &str) -> Option<&'static str> {
+    let tag = match extension.to_lowercase().as_str() {
+        "rs" => "rust",
+        "ts" | "tsx" | "mts" | "cts" => "typescript",
+        "js" | "jsx" | "mjs" | "cjs" => "javascript",
+        "py" => "python",
+        "go" => "go",
+        "rb" => "ruby",
+        "java" => "java",
+        "kt" | "kts" => "kotlin",
+        "swift" => "swift",
+        "c" | "h" => "c",
+        "cpp" | "cc" | "cxx" | "hpp" => "cpp",
+        "cs" => "csharp",
+        "php" => "php",
+        "scala" => "scala",
+        "sh" | "bash" | "zsh" => "bash",
+        "sql" => "sql",
+        "svelte" => "svelte",
+        "vue" => "vue",
+        "html" | "htm" => "html",
+        "css" | "scss" | "sass" | "less" => "css",
+        "lua" => "lua",
+        "dart" => "dart",
+        "ex" | "exs" => "elixir",
+        "hs" => "haskell",
+        "zig" => "zig",
+        "tf" => "terraform",
+        _ => return None,
+    };
+    Some(tag)
+}
+
+/// Evidence gathered for the tags of one conversation.
+#[derive(Default)]
+struct Evidence {
+    tags: BTreeMap<String, Vec<(TagSuggestionSource, f64)>>,
+}
+
+impl Evidence {
+    fn add(&mut self, tag: &str, source: TagSuggestionSource, confidence: f64) {
+        let sources = self.tags.entry(tag.to_string()).or_default();
+        match sources.iter_mut().find(|(known, _)| *known == source) {
+            Some((_, known)) => *known = known.max(confidence),
+            None => sources.push((source, confidence)),
+        }
+    }
+
+    /// Combines each tag's evidence, surest first, leaving out `existing`.
+    fn into_suggestions(self, existing: &[String]) -> Vec<TagSuggestion> {
+        let mut suggestions: Vec<TagSuggestion> = self
+            .tags
+            .into_iter()
+            .filter(|(tag, _)| !existing.contains(tag))
+            .map(|(tag, mut sources)| {
+                let doubt: f64 = sources.iter().map(|(_, c)| 1.0 - c).product();
+                sources.sort_by(|a, b| b.1.total_cmp(&a.1));
+                TagSuggestion {
+                    tag,
+                    confidence: ((1.0 - doubt) * 100.0).round() / 100.0,
+                    sources: sources.into_iter().map(|(source, _)| source).collect(),
+                }
+            })
+            .filter(|suggestion| suggestion.confidence >= MIN_CONFIDENCE)
+            .collect();
+        suggestions.sort_by(|a, b| b.confidence.total_cmp(&a.confidence));
+        suggestions.truncate(MAX_SUGGESTIONS);
+        suggestions
+    }
+}
+
+/// Code blocks per language Claude wrote in a conversation.
+fn conversation_languages(
+    conn: &Connection,
+    conversation_id: &str,
+) -> DbResult<Vec<(String, i64)>> {
+    let mut stmt = conn.prepare_cached(
+        "SELECT language, SUM(blocks) FROM language_usage
+         WHERE conversation_id = ?1
+         GROUP BY language",
+    )?;
+    let rows = stmt.query_map([conversation_id], |row| Ok((row.get(0)?, row.get(1)?)))?;
+    Ok(rows.collect::<Result<_, _>>()?)
+}
+
+/// Languages make surer tags the more of the code blocks they're in.
+fn add_languages(evidence: &mut Evidence, languages: &[(String, i64)]) {
+    let total: i64 = languages.iter().map(|(_, blocks)| blocks).sum();
+    for (language, blocks) in languages {
+        if total > 0 && *blocks > 0 {
+            let share = *blocks as f64 / total as f64;
+            evidence.add(language, TagSuggestionSource::Language, 0.5 + 0.4 * share);
+        }
+    }
+}
+
+/// Words of a text, lowercase, keeping the dots and dashes of names such
+/// as `next.js` and `docker-compose`.
+fn words(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric() && !matches!(c, '.' | '-' | '_'))
+        .map(|word| {
+            word.trim_matches(|c| matches!(c, '.' | '-' | '_'))
+                .to_lowercase()
+        })
+        .filter(|word| !word.is_empty())
+}
+
+/// The text of a message's dialogue, without tool calls and results.
+fn dialogue_text(message: &RawMessage) -> Vec<&str> {
+    match &message.message.content {
+        RawContent::Text(text) => vec![text.as_str()],
+        RawContent::Blocks(blocks) => blocks
+            .iter()
+            .filter(|block| block.block_type == "text")
+            .filter_map(|block| block.text.as_deref())
+            .collect(),
+    }
+}
+
+/// Paths of the files a message's tool calls read or changed.
+fn touched_files(message: &RawMessage) -> Vec<&str> {
+    let RawContent::Blocks(blocks) = &message.message.content else {
+        return Vec::new();
+    };
+    blocks
+        .iter()
+        .filter(|block| block.block_type == "tool_use")
+        .filter_map(|block| block.input.as_ref())
+        .filter_map(|input| {
+            ["file_path", "notebook_path", "path"]
+                .iter()
+                .find_map(|key| input.get(key).and_then(Value::as_str))
+        })
+        .collect()
+}
+
+/// Frameworks make surer tags the more messages name them; files that
+/// imply one make a sure tag at once.
+fn add_frameworks(evidence: &mut Evidence, messages: &[RawMessage], files: &HashSet<&str>) {
+    let by_word: HashMap<&str, &str> = FRAMEWORKS
+        .iter()
+        .flat_map(|(tag, names)| names.iter().map(move |name| (*name, *tag)))
+        .collect();
+    let mut mentions: HashMap<&str, i32> = HashMap::new();
+    for message in messages.iter().filter(|message| !message.is_meta) {
+        let named: HashSet<&str> = dialogue_text(message)
+            .into_iter()
+            .flat_map(words)
+            .filter_map(|word| by_word.get(word.as_str()).copied())
+            .collect();
+        for tag in named {
+            *mentions.entry(tag).or_default() += 1;
+        }
+    }
+    for (tag, count) in mentions {
+        let confidence = 1.0 - (1.0 - MENTION_CONFIDENCE).powi(count);
+        evidence.add(tag, TagSuggestionSource::Framework, confidence.min(0.9));
+    }
+
+    for file in files {
+        let Some(name) = Path::new(file).file_name().and_then(|name| name.to_str()) else {
+            continue;
+        };
+        let name = name.to_lowercase();
+        if let Some((_, tag)) = MARKER_FILES.iter().find(|(marker, _)| *marker == name) {
+            evidence.add(tag, TagSuggestionSource::Framework, MARKER_CONFIDENCE);
+        }
+    }
+}
+
+/// Extensions make surer tags the more of the touched files have them.
+fn add_extensions(evidence: &mut Evidence, files: &HashSet<&str>) {
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for file in files {
+        let extension = Path::new(file).extension().and_then(|ext| ext.to_str());
+        if let Some(tag) = extension.and_then(extension_tag) {
+            *counts.entry(tag).or_default() += 1;
+        }
+    }
+    let total: usize = counts.values().sum();
+    for (tag, count) in counts {
+        let share = count as f64 / total as f64;
+        evidence.add(tag, TagSuggestionSource::FileExtension, 0.4 + 0.5 * share);
+    }
+}
+
+/// Tags make surer suggestions the more similar conversations have them,
+/// and the more similar conversations there are. A conversation is more
+/// similar in the same project, and the more of the languages it shares.
+fn add_similar_tags(
+    evidence: &mut Evidence,
+    conn: &Connection,
+    conversation_id: &str,
+    languages: &[(String, i64)],
+) -> DbResult<()> {
+    let mut stmt = conn.prepare_cached(
+        "SELECT c.project_path = me.project_path,
+                (SELECT COUNT(DISTINCT l.language) FROM language_usage l
+                 WHERE l.conversation_id = c.id
+                   AND l.language IN (SELECT language FROM language_usage
+                                      WHERE conversation_id = me.id)),
+                group_concat(t.tag, char(31))
+         FROM conversations me
+         JOIN conversations c ON c.id != me.id
+         JOIN conversation_tags t ON t.conversation_id = c.id
+         WHERE me.id = ?1
+         GROUP BY c.id
+         ORDER BY c.last_epoch DESC",
+    )?;
+    let rows = stmt.query_map([conversation_id], |row| {
+        Ok((
+            row.get::<_, bool>(0)?,
+            row.get::<_, i64>(1)?,
+            row.get::<_, String>(2)?,
+        ))
+    })?;
+
+    let mut similar: Vec<(f64, String)> = Vec::new();
+    for row in rows {
+        let (same_project, shared_languages, tags) = row?;
+        let shared = shared_languages as f64 / languages.len().max(1) as f64;
+        let weight = if same_project { 0.6 } else { 0.0 } + 0.4 * shared;
+        if weight > 0.0 {
+            similar.push((weight, tags));
+        }
+    }
+    similar.sort_by(|a, b| b.0.total_cmp(&a.0));
+    similar.truncate(SIMILAR_LIMIT);
+
+    let total: f64 = similar.iter().map(|(weight, _)| weight).sum();
+    let damping = similar.len() as f64 / (similar.len() as f64 + 2.0);
+    let mut weights: HashMap<&str, f64> = HashMap::new();
+    for (weight, tags) in &similar {
+        for tag in tags.split('\u{1f}') {
+            *weights.entry(tag).or_default() += weight;
+        }
+    }
+    for (tag, weight) in weights {
+        let confidence = 0.9 * weight / total * damping;
+        evidence.add(tag, TagSuggestionSource::SimilarConversations, confidence);
+    }
+    Ok(())
+}
+
+/// Proposes tags for a conversation from its messages, its code blocks, and
+/// the tags of similar conversations, surest first.
+///
+/// # Arguments
+/// * `conn` - Database connection
+/// * `conversation_id` - ID of the conversation
+/// * `messages` - The conversation's messages; may be empty if its file is gone
+pub fn suggest_tags(
+    conn: &Connection,
+    conversation_id: &str,
+    messages: &[RawMessage],
+) -> DbResult<Vec<TagSuggestion>> {
+    let mut evidence = Evidence::default();
+    let languages = conversation_languages(conn, conversation_id)?;
+    let files: HashSet<&str> = messages.iter().flat_map(touched_files).collect();
+
+    add_languages(&mut evidence, &languages);
+    add_frameworks(&mut evidence, messages, &files);
+    add_extensions(&mut evidence, &files);
+    add_similar_tags(&mut evidence, conn, conversation_id, &languages)?;
+
+    Ok(evidence.into_suggestions(&current_tags(conn, conversation_id)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::sqlite::init_db;
+    use crate::parser::jsonl::parse_jsonl_line;
+
+    fn insert_conversation(conn: &Connection, id: &str, project: &str, tags: &[&str]) {
+        conn.execute(
+            "INSERT INTO conversations (id, project_path, project_name, start_time, last_time, file_path, file_modified_at)
+             VALUES (?1, ?2, 'p', '2025-01-15T10:00:00Z', '2025-01-15T10:30:00Z', '/a.jsonl', '')",
+            [id, project],
+        )
+        .unwrap();
+        for tag in tags {
+            conn.execute(
+                "INSERT INTO conversation_tags (conversation_id, tag, created_at) VALUES (?1, ?2, 'now')",
+                [id, tag],
+            )
+            .unwrap();
+        }
+    }
+
+    fn add_language(conn: &Connection, id: &str, language: &str, blocks: i64) {
+        conn.execute(
+            "INSERT INTO language_usage (conversation_id, day, language, blocks, lines)
+             VALUES (?1, '2025-01-15', ?2, ?3, 10)",
+            rusqlite::params![id, language, blocks],
+        )
+        .unwrap();
+    }
+
+    fn sources_of<'a>(suggestions: &'a [TagSuggestion], tag: &str) -> &'a [TagSuggestionSource] {
+        &suggestions.iter().find(|s| s.tag == tag).unwrap().sources
+    }
+
+    #[test]
+    fn test_suggest_tags_combines_evidence() {
+        let conn = Connection::open_in_memory().unwrap();
+        init_db(&conn).unwrap();
+        insert_conversation(&conn, "c1", "/work/app", &["rust"]);
+        add_language(&conn, "c1", "rust", 3);
+        add_language(&conn, "c1", "typescript", 1);
+        for id in ["c2", "c3"] {
+            insert_conversation(&conn, id, "/work/app", &["desktop-app"]);
+        }
+        insert_conversation(&conn, "c4", "/work/other", &["unrelated"]);
+
+        let messages: Vec<RawMessage> = [
+            r#"{"type":"user","message":{"role":"user","content":"The Tauri window flickers, and Svelte re-renders it"},"timestamp":"2025-01-15T10:00:00Z"}"#,
+            r#"{"type":"assistant","message":{"role":"assistant","content":[{"type":"text","text":"Let me look at the Tauri config."},{"type":"tool_use","id":"t1","name":"Read","input":{"file_path":"/work/app/src-tauri/tauri.conf.json"}},{"type":"tool_use","id":"t2","name":"Edit","input":{"file_path":"/work/app/src/App.svelte"}}]},"timestamp":"2025-01-15T10:00:05Z"}"#,
+        ]
+        .iter()
+        .map(|line| parse_jsonl_line(line).unwrap())
+        .collect();
+
+        let suggestions = suggest_tags(&conn, "c1", &messages).unwrap();
+        let tags: Vec<&str> = suggestions.iter().map(|s| s.tag.as_str()).collect();
+
+        assert_eq!(tags, vec!["svelte", "tauri", "typescript", "desktop-app"]);
+        // The extension of an edited file, and named once
+        assert_eq!(
+            sources_of(&suggestions, "svelte"),
+            [
+                TagSuggestionSource::FileExtension,
+                TagSuggestionSource::Framework
+            ]
+        );
+        assert_eq!(suggestions[0].confidence, 0.93);
+        // Named twice, and implied by its config file
+        assert_eq!(
+            sources_of(&suggestions, "tauri"),
+            [TagSuggestionSource::Framework]
+        );
+        assert_eq!(suggestions[1].confidence, 0.8);
+        assert_eq!(
+            sources_of(&suggestions, "typescript"),
+            [TagSuggestionSource::Language]
+        );
+        // Two tagged conversations of the same project
+        assert_eq!(
+            sources_of(&suggestions, "desktop-app"),
+            [TagSuggestionSource::SimilarConversations]
+        );
+        assert_eq!(suggestions[3].confidence, 0.45);
+        // Already tagged, or only on a conversation with nothing in common
+        assert!(!tags.contains(&"rust"));
+        assert!(!tags.contains(&"unrelated"));
+    }
+
+    #[test]
+    fn test_words_keep_dotted_names() {
+        let found: Vec<String> = words("Use Next.js (with docker-compose).").collect();
+        assert_eq!(found, vec!["use", "next.js", "with", "docker-compose"]);
+    }
+}
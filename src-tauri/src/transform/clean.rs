@@ -0,0 +1,157 @@
+//! Transcript-only "clean mode".
+//!
+//! Strips tool activity from a conversation and stitches consecutive
+//! assistant turns into one, leaving a readable dialogue for people who
+//! don't care how the answer was produced.
+
+use crate::models::{ContentBlock, ContentBlockType, Message, MessageRole, TokenCount};
+
+/// Converts messages into a clean transcript.
+///
+/// * Tool use and tool result blocks are removed (thinking blocks are never
+///   carried into `Message` content in the first place).
+/// * Messages left without content, such as user turns that only returned
+///   tool output, are dropped.
+/// * Consecutive assistant messages are merged into the first one, and
+///   adjacent text blocks are joined into paragraphs.
+///
+/// The merged message keeps the ID, ordinal, and timestamp of the first
+/// message in the run, so links into the full conversation still work.
+pub fn clean_transcript(messages: Vec<Message>) -> Vec<Message> {
+    let mut result: Vec<Message> = Vec::new();
+
+    for mut message in messages {
+        message.content.retain(|block| {
+            matches!(block.block_type, ContentBlockType::Text | ContentBlockType::Code)
+        });
+        if message.content.is_empty() {
+            continue;
+        }
+
+        match result.last_mut() {
+            Some(previous)
+                if previous.role == MessageRole::Assistant
+                    && message.role == MessageRole::Assistant =>
+            {
+                previous.content.append(&mut message.content);
+                previous.token_count = add_tokens(previous.token_count.take(), message.token_count);
+            }
+            _ => result.push(message),
+        }
+    }
+
+    for message in &mut result {
+        message.content = join_text_blocks(std::mem::take(&mut message.content));
+    }
+
+    result
+}
+
+/// Joins runs of adjacent text blocks with blank lines.
+fn join_text_blocks(blocks: Vec<ContentBlock>) -> Vec<ContentBlock> {
+    let mut joined: Vec<ContentBlock> = Vec::with_capacity(blocks.len());
+
+    for block in blocks {
+        match joined.last_mut() {
+            Some(previous)
+                if previous.block_type == ContentBlockType::Text
+                    && block.block_type == ContentBlockType::Text =>
+            {
+                previous.content.push_str("\n\n");
+                previous.content.push_str(&block.content);
+            }
+            _ => joined.push(block),
+        }
+    }
+
+    joined
+}
+
+fn add_tokens(a: Option<TokenCount>, b: Option<TokenCount>) -> Option<TokenCount> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(TokenCount {
+            input: a.input + b.input,
+            output: a.output + b.output,
+        }),
+        (a, b) => a.or(b),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block(block_type: ContentBlockType, content: &str) -> ContentBlock {
+        ContentBlock {
+            block_type,
+            content: content.to_string(),
+            language: None,
+            tool_name: None,
+            spans: None,
+            terminal: None,
+            file_view: None,
+        }
+    }
+
+    fn message(ordinal: u32, role: MessageRole, content: Vec<ContentBlock>) -> Message {
+        Message {
+            id: format!("msg_{}", ordinal),
+            ordinal,
+            role,
+            content,
+            timestamp: String::new(),
+            token_count: Some(TokenCount { input: 1, output: 2 }),
+            collapsed_reminders: 0,
+            is_meta: false,
+        }
+    }
+
+    #[test]
+    fn test_clean_transcript_stitches_assistant_turns() {
+        let messages = vec![
+            message(0, MessageRole::User, vec![block(ContentBlockType::Text, "Fix the bug")]),
+            message(
+                1,
+                MessageRole::Assistant,
+                vec![
+                    block(ContentBlockType::Text, "Let me look at the file."),
+                    block(ContentBlockType::ToolUse, "{}"),
+                ],
+            ),
+            message(2, MessageRole::User, vec![block(ContentBlockType::ToolResult, "output")]),
+            message(
+                3,
+                MessageRole::Assistant,
+                vec![
+                    block(ContentBlockType::Text, "Found it, here is the fix:"),
+                    block(ContentBlockType::Code, "fn main() {}"),
+                ],
+            ),
+            message(4, MessageRole::User, vec![block(ContentBlockType::Text, "Thanks")]),
+        ];
+
+        let cleaned = clean_transcript(messages);
+
+        assert_eq!(cleaned.len(), 3);
+        let assistant = &cleaned[1];
+        assert_eq!(assistant.ordinal, 1);
+        assert_eq!(assistant.content.len(), 2);
+        assert_eq!(
+            assistant.content[0].content,
+            "Let me look at the file.\n\nFound it, here is the fix:"
+        );
+        assert_eq!(assistant.content[1].block_type, ContentBlockType::Code);
+        let tokens = assistant.token_count.as_ref().unwrap();
+        assert_eq!((tokens.input, tokens.output), (2, 4));
+        assert_eq!(cleaned[2].ordinal, 4);
+    }
+
+    #[test]
+    fn test_clean_transcript_keeps_separate_user_turns() {
+        let messages = vec![
+            message(0, MessageRole::User, vec![block(ContentBlockType::Text, "One")]),
+            message(1, MessageRole::User, vec![block(ContentBlockType::Text, "Two")]),
+        ];
+        assert_eq!(clean_transcript(messages).len(), 2);
+    }
+}
@@ -0,0 +1,156 @@
+//! Role and block-type filtering of conversation messages.
+
+use crate::models::{ContentBlockType, Message, MessageFilterOptions};
+
+/// Applies message filter options to a list of messages.
+///
+/// Messages keep their original ordinals so filtered views can still link
+/// into the full conversation. Messages left without any content blocks
+/// (e.g. user turns that only carried tool results) are dropped, as are meta
+/// messages unless `show_meta` is set.
+///
+/// # Arguments
+/// * `messages` - Messages in chronological order
+/// * `options` - Which roles and block types to keep
+///
+/// # Returns
+/// * `Vec<Message>` - The filtered messages, in the same order
+pub fn filter_messages(messages: Vec<Message>, options: &MessageFilterOptions) -> Vec<Message> {
+    messages
+        .into_iter()
+        .filter(|message| options.show_meta || !message.is_meta)
+        .filter(|message| match &options.roles {
+            Some(roles) if !roles.is_empty() => roles.contains(&message.role),
+            _ => true,
+        })
+        .filter_map(|mut message| {
+            let original_len = message.content.len();
+            message.content.retain(|block| keep_block(&block.block_type, options));
+
+            // Keep genuinely empty messages; drop ones emptied by the filter
+            if message.content.is_empty() && original_len > 0 {
+                None
+            } else {
+                Some(message)
+            }
+        })
+        .collect()
+}
+
+fn keep_block(block_type: &ContentBlockType, options: &MessageFilterOptions) -> bool {
+    match block_type {
+        ContentBlockType::Text | ContentBlockType::Code => true,
+        ContentBlockType::ToolUse | ContentBlockType::Terminal => !options.text_only,
+        ContentBlockType::ToolResult | ContentBlockType::Image | ContentBlockType::FileView => {
+            !options.text_only && !options.hide_tool_results
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ContentBlock, MessageRole};
+
+    fn block(block_type: ContentBlockType, content: &str) -> ContentBlock {
+        ContentBlock {
+            block_type,
+            content: content.to_string(),
+            language: None,
+            tool_name: None,
+            spans: None,
+            terminal: None,
+            file_view: None,
+        }
+    }
+
+    fn message(ordinal: u32, role: MessageRole, content: Vec<ContentBlock>) -> Message {
+        Message {
+            id: format!("msg_{}", ordinal),
+            ordinal,
+            role,
+            content,
+            timestamp: String::new(),
+            token_count: None,
+            collapsed_reminders: 0,
+            is_meta: false,
+        }
+    }
+
+    /// A typical agentic exchange: question, tool call, tool result, answer.
+    fn agentic_session() -> Vec<Message> {
+        vec![
+            message(0, MessageRole::User, vec![block(ContentBlockType::Text, "Fix the bug")]),
+            message(
+                1,
+                MessageRole::Assistant,
+                vec![
+                    block(ContentBlockType::Text, "Let me look"),
+                    block(ContentBlockType::ToolUse, "{\"path\":\"src/lib.rs\"}"),
+                ],
+            ),
+            message(2, MessageRole::User, vec![block(ContentBlockType::ToolResult, "fn main() {}")]),
+            message(3, MessageRole::Assistant, vec![block(ContentBlockType::Text, "Fixed")]),
+        ]
+    }
+
+    #[test]
+    fn test_default_options_keep_everything() {
+        let filtered = filter_messages(agentic_session(), &MessageFilterOptions::default());
+        assert_eq!(filtered.len(), 4);
+    }
+
+    #[test]
+    fn test_only_user_messages() {
+        let options = MessageFilterOptions {
+            roles: Some(vec![MessageRole::User]),
+            hide_tool_results: true,
+            ..Default::default()
+        };
+        let filtered = filter_messages(agentic_session(), &options);
+        let ordinals: Vec<u32> = filtered.iter().map(|m| m.ordinal).collect();
+        assert_eq!(ordinals, vec![0]);
+    }
+
+    #[test]
+    fn test_only_assistant_text() {
+        let options = MessageFilterOptions {
+            roles: Some(vec![MessageRole::Assistant]),
+            text_only: true,
+            ..Default::default()
+        };
+        let filtered = filter_messages(agentic_session(), &options);
+        assert_eq!(filtered.len(), 2);
+        assert_eq!(filtered[0].content.len(), 1);
+        assert_eq!(filtered[0].content[0].content, "Let me look");
+        assert_eq!(filtered[1].ordinal, 3);
+    }
+
+    #[test]
+    fn test_hide_tool_results_keeps_tool_use() {
+        let options = MessageFilterOptions {
+            hide_tool_results: true,
+            ..Default::default()
+        };
+        let filtered = filter_messages(agentic_session(), &options);
+        let ordinals: Vec<u32> = filtered.iter().map(|m| m.ordinal).collect();
+        assert_eq!(ordinals, vec![0, 1, 3]);
+        assert_eq!(filtered[1].content.len(), 2);
+    }
+
+    #[test]
+    fn test_meta_messages_hidden_unless_shown() {
+        let mut messages = agentic_session();
+        let mut hook = message(4, MessageRole::System, vec![block(ContentBlockType::Text, "PostToolUse hook")]);
+        hook.is_meta = true;
+        messages.push(hook);
+
+        let filtered = filter_messages(messages.clone(), &MessageFilterOptions::default());
+        assert_eq!(filtered.len(), 4);
+        let options = MessageFilterOptions {
+            show_meta: true,
+            ..Default::default()
+        };
+        assert_eq!(filter_messages(messages, &options).len(), 5);
+    }
+}
@@ -0,0 +1,13 @@
+//! Message transforms for display and export.
+//!
+//! This module reshapes parsed conversation messages, e.g. filtering by role
+//! or block type so long agentic sessions read as a plain dialogue, and
+//! pairing shell commands with their output.
+
+pub mod clean;
+pub mod filter;
+pub mod terminal;
+
+pub use clean::clean_transcript;
+pub use filter::filter_messages;
+pub use terminal::{combine_terminal_blocks, recorded_output};
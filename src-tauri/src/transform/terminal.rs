@@ -0,0 +1,217 @@
+//! Terminal transcripts of Bash tool calls.
+//!
+//! A Bash call and its result arrive in separate messages: the assistant's
+//! tool call, then a user turn returning the output. This pairs them up so
+//! the call's terminal block holds the command, output, and exit code, and
+//! the result no longer shows as a separate block.
+
+use crate::models::{ContentBlock, ContentBlockType, Message, TerminalTranscript};
+use crate::parser::ansi;
+use std::collections::{HashMap, HashSet};
+
+/// Prefix Bash results start with when the command exited non-zero.
+const EXIT_CODE_PREFIX: &str = "Exit code ";
+
+/// Moves the result of each Bash call into the call's terminal block.
+///
+/// Results of calls without a terminal block stay as they are. Messages
+/// left without content blocks (user turns that only returned the output)
+/// are dropped; the rest keep their ordinals.
+///
+/// # Arguments
+/// * `messages` - Messages in chronological order
+///
+/// # Returns
+/// * `Vec<Message>` - The messages, with terminal blocks completed
+pub fn combine_terminal_blocks(messages: Vec<Message>) -> Vec<Message> {
+    let call_ids: HashSet<String> = messages
+        .iter()
+        .flat_map(|message| &message.content)
+        .filter(|block| block.block_type == ContentBlockType::Terminal)
+        .filter_map(|block| block.terminal.as_ref()?.tool_use_id.clone())
+        .collect();
+    if call_ids.is_empty() {
+        return messages;
+    }
+
+    let mut results: HashMap<String, ContentBlock> = HashMap::new();
+    let mut messages: Vec<Message> = messages
+        .into_iter()
+        .filter_map(|mut message| {
+            let original_len = message.content.len();
+            let mut kept = Vec::with_capacity(original_len);
+            for block in message.content {
+                match block.tool_name.as_deref() {
+                    Some(id)
+                        if block.block_type == ContentBlockType::ToolResult
+                            && call_ids.contains(id) =>
+                    {
+                        results.insert(id.to_string(), block);
+                    }
+                    _ => kept.push(block),
+                }
+            }
+            message.content = kept;
+
+            // Keep genuinely empty messages; drop ones emptied by combining
+            if message.content.is_empty() && original_len > 0 {
+                None
+            } else {
+                Some(message)
+            }
+        })
+        .collect();
+
+    for block in messages.iter_mut().flat_map(|message| &mut message.content) {
+        if block.block_type != ContentBlockType::Terminal {
+            continue;
+        }
+        let Some(terminal) = block.terminal.as_mut() else {
+            continue;
+        };
+        let Some(result) = terminal
+            .tool_use_id
+            .as_ref()
+            .and_then(|id| results.remove(id))
+        else {
+            continue;
+        };
+
+        let (exit_code, output) = split_exit_code(&result.content);
+        match result.terminal {
+            // Output streams recorded with the result
+            Some(recorded) => {
+                terminal.stdout = recorded.stdout;
+                terminal.stderr = recorded.stderr;
+                terminal.interrupted = recorded.interrupted;
+            }
+            None => terminal.stdout = output.to_string(),
+        }
+        terminal.exit_code = if terminal.interrupted {
+            exit_code
+        } else {
+            Some(exit_code.unwrap_or(0))
+        };
+
+        if !output.is_empty() {
+            block.content = format!("{}\n{}", block.content, output);
+        }
+        block.spans = result.spans;
+    }
+
+    messages
+}
+
+/// Splits the `Exit code N` line off a Bash result, if it has one.
+fn split_exit_code(content: &str) -> (Option<i32>, &str) {
+    content
+        .strip_prefix(EXIT_CODE_PREFIX)
+        .and_then(|rest| {
+            let (code, output) = rest.split_once('\n').unwrap_or((rest, ""));
+            Some((Some(code.trim().parse().ok()?), output))
+        })
+        .unwrap_or((None, content))
+}
+
+/// Output streams recorded with a Bash result, as a transcript without a
+/// command, for the result block. Escape sequences are removed.
+pub fn recorded_output(stdout: &str, stderr: &str, interrupted: bool) -> TerminalTranscript {
+    TerminalTranscript {
+        stdout: ansi::strip(stdout),
+        stderr: ansi::strip(stderr),
+        interrupted,
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::MessageRole;
+
+    fn message(ordinal: u32, role: MessageRole, content: Vec<ContentBlock>) -> Message {
+        Message {
+            id: format!("m{}", ordinal),
+            ordinal,
+            role,
+            content,
+            timestamp: String::new(),
+            token_count: None,
+            collapsed_reminders: 0,
+            is_meta: false,
+        }
+    }
+
+    fn call(id: &str, command: &str) -> ContentBlock {
+        ContentBlock {
+            block_type: ContentBlockType::Terminal,
+            content: format!("$ {}", command),
+            language: None,
+            tool_name: Some("Bash".to_string()),
+            spans: None,
+            terminal: Some(TerminalTranscript {
+                tool_use_id: Some(id.to_string()),
+                command: command.to_string(),
+                ..Default::default()
+            }),
+            file_view: None,
+        }
+    }
+
+    fn result(id: &str, content: &str) -> ContentBlock {
+        ContentBlock {
+            block_type: ContentBlockType::ToolResult,
+            content: content.to_string(),
+            language: None,
+            tool_name: Some(id.to_string()),
+            spans: None,
+            terminal: None,
+            file_view: None,
+        }
+    }
+
+    #[test]
+    fn test_results_move_into_terminal_blocks() {
+        let mut failing = result("t2", "Exit code 101\nerror: test failed");
+        failing.terminal = Some(recorded_output("", "error: test failed", false));
+        let messages = vec![
+            message(0, MessageRole::Assistant, vec![call("t1", "ls")]),
+            message(1, MessageRole::User, vec![result("t1", "Cargo.toml\nsrc")]),
+            message(2, MessageRole::Assistant, vec![call("t2", "cargo test")]),
+            message(3, MessageRole::User, vec![failing]),
+            message(4, MessageRole::User, vec![result("other", "not a command")]),
+        ];
+
+        let combined = combine_terminal_blocks(messages);
+        let ordinals: Vec<u32> = combined.iter().map(|m| m.ordinal).collect();
+        assert_eq!(ordinals, vec![0, 2, 4]);
+
+        let ls = &combined[0].content[0];
+        assert_eq!(ls.content, "$ ls\nCargo.toml\nsrc");
+        let ls = ls.terminal.as_ref().unwrap();
+        assert_eq!(ls.stdout, "Cargo.toml\nsrc");
+        assert_eq!(ls.exit_code, Some(0));
+
+        let test = combined[1].content[0].terminal.as_ref().unwrap();
+        assert_eq!(test.stdout, "");
+        assert_eq!(test.stderr, "error: test failed");
+        assert_eq!(test.exit_code, Some(101));
+
+        assert_eq!(
+            combined[2].content[0].block_type,
+            ContentBlockType::ToolResult
+        );
+    }
+
+    #[test]
+    fn test_call_without_result_keeps_unknown_exit_code() {
+        let combined = combine_terminal_blocks(vec![message(
+            0,
+            MessageRole::Assistant,
+            vec![call("t1", "sleep 100")],
+        )]);
+        let terminal = combined[0].content[0].terminal.as_ref().unwrap();
+        assert_eq!(terminal.exit_code, None);
+        assert_eq!(combined[0].content[0].content, "$ sleep 100");
+    }
+}
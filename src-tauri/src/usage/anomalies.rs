@@ -0,0 +1,292 @@
+//! Lightweight anomaly detection on recorded usage.
+//!
+//! Tokens and tool errors are compared per day and per conversation using
+//! the modified z-score, which is based on the median and the median
+//! absolute deviation so a single runaway session can't inflate its own
+//! baseline.
+
+use crate::db::sqlite::DbResult;
+use crate::models::{AnomalyKind, AnomalyOptions, UsageAnomaly};
+use chrono::{Days, NaiveDate};
+use rusqlite::{params, Connection};
+
+/// Default number of trailing days analyzed.
+pub const DEFAULT_LOOKBACK_DAYS: u32 = 30;
+
+/// Default robust z-score above which a value is flagged.
+pub const DEFAULT_THRESHOLD: f64 = 3.5;
+
+/// Fewest samples (days or conversations) a baseline is computed from.
+const MIN_SAMPLES: usize = 5;
+
+/// Fewest tool errors that can count as a burst.
+const MIN_BURST_ERRORS: i64 = 5;
+
+/// One observation in a series: a day, or a conversation's total.
+struct Sample {
+    day: String,
+    conversation_id: Option<String>,
+    project_name: Option<String>,
+    value: i64,
+}
+
+/// Flags days and conversations with unusual token usage or tool errors
+/// within the lookback window ending on `today` (UTC).
+///
+/// Returns the anomalies sorted by score, most unusual first.
+pub fn detect_anomalies(
+    conn: &Connection,
+    options: &AnomalyOptions,
+    today: NaiveDate,
+) -> DbResult<Vec<UsageAnomaly>> {
+    let lookback = options.lookback_days.unwrap_or(DEFAULT_LOOKBACK_DAYS);
+    let threshold = options.threshold.unwrap_or(DEFAULT_THRESHOLD);
+    let since = today
+        .checked_sub_days(Days::new(u64::from(lookback.saturating_sub(1))))
+        .unwrap_or(NaiveDate::MIN)
+        .format("%Y-%m-%d")
+        .to_string();
+
+    let daily_tokens = query_samples(
+        conn,
+        r#"
+        SELECT day, NULL, NULL,
+               SUM(input_tokens + output_tokens + cache_creation_tokens + cache_read_tokens)
+        FROM usage_daily
+        WHERE day >= ?1
+        GROUP BY day
+        "#,
+        &since,
+    )?;
+    let daily_errors = query_samples(
+        conn,
+        r#"
+        SELECT day, NULL, NULL, SUM(errors)
+        FROM tool_usage
+        WHERE day >= ?1
+        GROUP BY day
+        "#,
+        &since,
+    )?;
+    let conversation_tokens = query_samples(
+        conn,
+        r#"
+        SELECT MAX(u.day), u.conversation_id, c.project_name,
+               SUM(u.input_tokens + u.output_tokens + u.cache_creation_tokens + u.cache_read_tokens)
+        FROM usage_daily u
+        JOIN conversations c ON c.id = u.conversation_id
+        WHERE u.day >= ?1
+        GROUP BY u.conversation_id
+        "#,
+        &since,
+    )?;
+    let conversation_errors = query_samples(
+        conn,
+        r#"
+        SELECT MAX(t.day), t.conversation_id, c.project_name, SUM(t.errors)
+        FROM tool_usage t
+        JOIN conversations c ON c.id = t.conversation_id
+        WHERE t.day >= ?1
+        GROUP BY t.conversation_id
+        "#,
+        &since,
+    )?;
+
+    let mut anomalies = Vec::new();
+    anomalies.extend(score_samples(AnomalyKind::TokenSpike, &daily_tokens, threshold, 0));
+    anomalies.extend(score_samples(
+        AnomalyKind::ErrorBurst,
+        &daily_errors,
+        threshold,
+        MIN_BURST_ERRORS,
+    ));
+    anomalies.extend(score_samples(
+        AnomalyKind::TokenSpike,
+        &conversation_tokens,
+        threshold,
+        0,
+    ));
+    anomalies.extend(score_samples(
+        AnomalyKind::ErrorBurst,
+        &conversation_errors,
+        threshold,
+        MIN_BURST_ERRORS,
+    ));
+
+    anomalies.sort_by(|a, b| b.score.total_cmp(&a.score));
+    Ok(anomalies)
+}
+
+fn query_samples(conn: &Connection, sql: &str, since: &str) -> DbResult<Vec<Sample>> {
+    let mut stmt = conn.prepare(sql)?;
+    let rows = stmt.query_map(params![since], |row| {
+        Ok(Sample {
+            day: row.get(0)?,
+            conversation_id: row.get(1)?,
+            project_name: row.get(2)?,
+            value: row.get(3)?,
+        })
+    })?;
+    Ok(rows.collect::<Result<_, _>>()?)
+}
+
+/// Scores a series and returns the samples above `threshold` that are at
+/// least `min_value`.
+fn score_samples(
+    kind: AnomalyKind,
+    samples: &[Sample],
+    threshold: f64,
+    min_value: i64,
+) -> Vec<UsageAnomaly> {
+    if samples.len() < MIN_SAMPLES {
+        return Vec::new();
+    }
+
+    let values: Vec<f64> = samples.iter().map(|s| s.value as f64).collect();
+    let baseline = median(values.clone());
+    let deviations: Vec<f64> = values.iter().map(|v| (v - baseline).abs()).collect();
+    let mad = median(deviations.clone());
+
+    // Both spreads are scaled to be comparable to a standard deviation. The
+    // mean absolute deviation covers series where most values are identical
+    // (e.g. no errors on most days), which leaves the MAD at zero.
+    let spread = if mad > 0.0 {
+        mad / 0.6745
+    } else {
+        deviations.iter().sum::<f64>() / deviations.len() as f64 * 1.253314
+    };
+    if spread == 0.0 {
+        return Vec::new();
+    }
+
+    samples
+        .iter()
+        .filter(|s| s.value >= min_value)
+        .filter_map(|s| {
+            let score = (s.value as f64 - baseline) / spread;
+            (score > threshold).then(|| UsageAnomaly {
+                kind,
+                day: s.day.clone(),
+                conversation_id: s.conversation_id.clone(),
+                project_name: s.project_name.clone(),
+                value: s.value,
+                baseline,
+                score,
+            })
+        })
+        .collect()
+}
+
+fn median(mut values: Vec<f64>) -> f64 {
+    values.sort_by(|a, b| a.total_cmp(b));
+    let mid = values.len() / 2;
+    if values.len().is_multiple_of(2) {
+        (values[mid - 1] + values[mid]) / 2.0
+    } else {
+        values[mid]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::sqlite::init_db;
+
+    fn setup() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        init_db(&conn).unwrap();
+        conn
+    }
+
+    /// Inserts a conversation active on one day with the given tokens and tool errors.
+    fn insert(conn: &Connection, id: &str, day: &str, tokens: i64, errors: i64) {
+        conn.execute(
+            "INSERT INTO conversations (id, project_path, project_name, start_time, last_time, file_path, file_modified_at)
+             VALUES (?1, '/p', 'proj', '', '', '/f.jsonl', '')",
+            params![id],
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO usage_daily (conversation_id, day, model, messages, input_tokens) VALUES (?1, ?2, 'm', 1, ?3)",
+            params![id, day, tokens],
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO tool_usage (conversation_id, day, tool_name, calls, errors) VALUES (?1, ?2, 'Bash', 10, ?3)",
+            params![id, day, errors],
+        )
+        .unwrap();
+    }
+
+    fn today() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 1, 10).unwrap()
+    }
+
+    fn seed_steady_week(conn: &Connection) {
+        for (i, tokens) in [1000, 1100, 950, 1050, 1000, 1020].iter().enumerate() {
+            insert(conn, &format!("c{}", i), &format!("2025-01-0{}", i + 1), *tokens, 0);
+        }
+    }
+
+    #[test]
+    fn test_steady_usage_has_no_anomalies() {
+        let conn = setup();
+        seed_steady_week(&conn);
+
+        let anomalies = detect_anomalies(&conn, &AnomalyOptions::default(), today()).unwrap();
+        assert!(anomalies.is_empty(), "{:?}", anomalies);
+    }
+
+    #[test]
+    fn test_token_spike_flags_day_and_conversation() {
+        let conn = setup();
+        seed_steady_week(&conn);
+        insert(&conn, "runaway", "2025-01-09", 50_000, 0);
+
+        let anomalies = detect_anomalies(&conn, &AnomalyOptions::default(), today()).unwrap();
+        assert_eq!(anomalies.len(), 2);
+        assert!(anomalies.iter().all(|a| a.kind == AnomalyKind::TokenSpike));
+        assert!(anomalies.iter().all(|a| a.day == "2025-01-09" && a.value == 50_000));
+        assert!(anomalies
+            .iter()
+            .any(|a| a.conversation_id.as_deref() == Some("runaway")));
+        assert!(anomalies.iter().any(|a| a.conversation_id.is_none()));
+    }
+
+    #[test]
+    fn test_error_burst_requires_minimum_errors() {
+        let conn = setup();
+        seed_steady_week(&conn);
+        insert(&conn, "flaky", "2025-01-08", 1000, 2);
+        insert(&conn, "broken", "2025-01-09", 1000, 12);
+
+        let anomalies = detect_anomalies(&conn, &AnomalyOptions::default(), today()).unwrap();
+        let bursts: Vec<_> = anomalies
+            .iter()
+            .filter(|a| a.kind == AnomalyKind::ErrorBurst)
+            .collect();
+        assert_eq!(bursts.len(), 2);
+        assert!(bursts.iter().all(|a| a.day == "2025-01-09" && a.value == 12));
+    }
+
+    #[test]
+    fn test_lookback_excludes_older_days() {
+        let conn = setup();
+        seed_steady_week(&conn);
+        insert(&conn, "runaway", "2025-01-09", 50_000, 0);
+
+        let options = AnomalyOptions {
+            lookback_days: Some(3),
+            ..Default::default()
+        };
+        // Only 2025-01-08..10 are analyzed, which is too few samples
+        let anomalies = detect_anomalies(&conn, &options, today()).unwrap();
+        assert!(anomalies.is_empty());
+    }
+
+    #[test]
+    fn test_median() {
+        assert_eq!(median(vec![3.0, 1.0, 2.0]), 2.0);
+        assert_eq!(median(vec![4.0, 1.0, 2.0, 3.0]), 2.5);
+    }
+}
@@ -0,0 +1,160 @@
+//! Per-project usage metrics for side-by-side comparisons.
+
+use crate::db::sqlite::DbResult;
+use crate::models::{ProjectMetrics, ToolStats};
+use crate::usage::pricing::estimate_cost;
+use crate::usage::record::DailyUsage;
+use rusqlite::{params, Connection};
+
+/// Aggregates conversations, tokens, estimated cost, and tool usage for a
+/// project (matched by project name, like the conversation list filter).
+pub fn project_metrics(conn: &Connection, project_name: &str) -> DbResult<ProjectMetrics> {
+    let mut metrics = ProjectMetrics {
+        project_name: project_name.to_string(),
+        ..Default::default()
+    };
+
+    (metrics.conversations, metrics.messages) = conn.query_row(
+        "SELECT COUNT(*), COALESCE(SUM(message_count), 0) FROM conversations WHERE project_name = ?1",
+        params![project_name],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )?;
+
+    let by_model: Vec<DailyUsage> = {
+        let mut stmt = conn.prepare(
+            r#"
+            SELECT u.model, SUM(u.messages), SUM(u.input_tokens), SUM(u.output_tokens),
+                   SUM(u.cache_creation_tokens), SUM(u.cache_read_tokens)
+            FROM usage_daily u
+            JOIN conversations c ON c.id = u.conversation_id
+            WHERE c.project_name = ?1
+            GROUP BY u.model
+            "#,
+        )?;
+        let mapped = stmt.query_map(params![project_name], |row| {
+            Ok(DailyUsage {
+                day: String::new(),
+                model: row.get(0)?,
+                messages: row.get(1)?,
+                input_tokens: row.get(2)?,
+                output_tokens: row.get(3)?,
+                cache_creation_tokens: row.get(4)?,
+                cache_read_tokens: row.get(5)?,
+                ..Default::default()
+            })
+        })?;
+        mapped.collect::<Result<_, _>>()?
+    };
+
+    for usage in &by_model {
+        metrics.input_tokens += usage.input_tokens;
+        metrics.output_tokens += usage.output_tokens;
+        metrics.cache_creation_tokens += usage.cache_creation_tokens;
+        metrics.cache_read_tokens += usage.cache_read_tokens;
+        match estimate_cost(usage) {
+            Some(cost) => metrics.estimated_cost_usd += cost,
+            None => {
+                metrics.unpriced_tokens += usage.input_tokens
+                    + usage.output_tokens
+                    + usage.cache_creation_tokens
+                    + usage.cache_read_tokens
+            }
+        }
+    }
+
+    metrics.tool_mix = {
+        let mut stmt = conn.prepare(
+            r#"
+            SELECT t.tool_name, SUM(t.calls) AS calls, SUM(t.errors)
+            FROM tool_usage t
+            JOIN conversations c ON c.id = t.conversation_id
+            WHERE c.project_name = ?1
+            GROUP BY t.tool_name
+            ORDER BY calls DESC, t.tool_name ASC
+            "#,
+        )?;
+        let mapped = stmt.query_map(params![project_name], |row| {
+            Ok(ToolStats {
+                tool_name: row.get(0)?,
+                calls: row.get(1)?,
+                errors: row.get(2)?,
+            })
+        })?;
+        mapped.collect::<Result<_, _>>()?
+    };
+
+    metrics.tool_calls = metrics.tool_mix.iter().map(|t| t.calls).sum();
+    metrics.tool_errors = metrics.tool_mix.iter().map(|t| t.errors).sum();
+    if metrics.tool_calls > 0 {
+        metrics.error_rate = metrics.tool_errors as f64 / metrics.tool_calls as f64;
+    }
+
+    Ok(metrics)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::sqlite::init_db;
+    use crate::usage::record::{record_usage, ConversationUsage, ToolUsage};
+
+    fn insert(conn: &Connection, id: &str, project: &str, usage: ConversationUsage) {
+        conn.execute(
+            "INSERT INTO conversations (id, project_path, project_name, start_time, last_time, message_count, file_path, file_modified_at)
+             VALUES (?1, '/p', ?2, '', '', 4, '/f.jsonl', '')",
+            params![id, project],
+        )
+        .unwrap();
+        record_usage(conn, id, &usage).unwrap();
+    }
+
+    fn usage(model: &str, input: i64, tool: &str, calls: i64, errors: i64) -> ConversationUsage {
+        ConversationUsage {
+            daily: vec![DailyUsage {
+                day: "2025-01-15".to_string(),
+                model: model.to_string(),
+                messages: 2,
+                input_tokens: input,
+                ..Default::default()
+            }],
+            tools: vec![ToolUsage {
+                day: "2025-01-15".to_string(),
+                tool_name: tool.to_string(),
+                calls,
+                errors,
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_project_metrics_aggregates_conversations() {
+        let conn = Connection::open_in_memory().unwrap();
+        init_db(&conn).unwrap();
+        insert(&conn, "c1", "alpha", usage("claude-sonnet-4-5", 1_000_000, "Bash", 3, 1));
+        insert(&conn, "c2", "alpha", usage("custom-model", 500, "Read", 5, 0));
+        insert(&conn, "c3", "beta", usage("claude-sonnet-4-5", 10, "Bash", 1, 1));
+
+        let metrics = project_metrics(&conn, "alpha").unwrap();
+        assert_eq!(metrics.conversations, 2);
+        assert_eq!(metrics.messages, 8);
+        assert_eq!(metrics.input_tokens, 1_000_500);
+        assert!((metrics.estimated_cost_usd - 3.0).abs() < 1e-9);
+        assert_eq!(metrics.unpriced_tokens, 500);
+        assert_eq!(metrics.tool_calls, 8);
+        assert_eq!(metrics.tool_errors, 1);
+        assert!((metrics.error_rate - 0.125).abs() < 1e-9);
+        assert_eq!(metrics.tool_mix[0].tool_name, "Read");
+    }
+
+    #[test]
+    fn test_project_metrics_unknown_project_is_empty() {
+        let conn = Connection::open_in_memory().unwrap();
+        init_db(&conn).unwrap();
+
+        let metrics = project_metrics(&conn, "missing").unwrap();
+        assert_eq!(metrics.conversations, 0);
+        assert_eq!(metrics.error_rate, 0.0);
+        assert!(metrics.tool_mix.is_empty());
+    }
+}
@@ -0,0 +1,170 @@
+//! CSV export of daily usage for billing reconciliation.
+
+use crate::db::sqlite::DbResult;
+use crate::usage::pricing::estimate_cost;
+use crate::usage::record::DailyUsage;
+use chrono::NaiveDate;
+use rusqlite::{params, Connection};
+
+/// CSV header row. Token columns use the API's usage field names.
+const HEADER: &str = "date,model,input_tokens,output_tokens,cache_creation_input_tokens,\
+cache_read_input_tokens,total_tokens,estimated_cost_usd";
+
+/// Model label for usage recorded without a model ID.
+const UNKNOWN_MODEL: &str = "unknown";
+
+/// Parses a `YYYY-MM` month into its first day and the first day of the
+/// following month.
+pub fn month_bounds(month: &str) -> Option<(NaiveDate, NaiveDate)> {
+    let start = NaiveDate::parse_from_str(&format!("{}-01", month.trim()), "%Y-%m-%d").ok()?;
+    let end = start.checked_add_months(chrono::Months::new(1))?;
+    Some((start, end))
+}
+
+/// Renders per-day, per-model token counts for UTC days in `[start, end)`
+/// as CSV.
+///
+/// Rows without tokens are omitted, and the estimated cost is left blank
+/// for models without known pricing.
+pub fn usage_csv(conn: &Connection, start: NaiveDate, end: NaiveDate) -> DbResult<String> {
+    let rows: Vec<DailyUsage> = {
+        let mut stmt = conn.prepare(
+            r#"
+            SELECT day, model, SUM(messages), SUM(input_tokens), SUM(output_tokens),
+                   SUM(cache_creation_tokens), SUM(cache_read_tokens)
+            FROM usage_daily
+            WHERE day >= ?1 AND day < ?2
+            GROUP BY day, model
+            ORDER BY day ASC, model ASC
+            "#,
+        )?;
+        let mapped = stmt.query_map(
+            params![start.format("%Y-%m-%d").to_string(), end.format("%Y-%m-%d").to_string()],
+            |row| {
+                Ok(DailyUsage {
+                    day: row.get(0)?,
+                    model: row.get(1)?,
+                    messages: row.get(2)?,
+                    input_tokens: row.get(3)?,
+                    output_tokens: row.get(4)?,
+                    cache_creation_tokens: row.get(5)?,
+                    cache_read_tokens: row.get(6)?,
+                    ..Default::default()
+                })
+            },
+        )?;
+        mapped.collect::<Result<_, _>>()?
+    };
+
+    let mut csv = String::from(HEADER);
+    csv.push('\n');
+    for row in &rows {
+        let total = row.input_tokens
+            + row.output_tokens
+            + row.cache_creation_tokens
+            + row.cache_read_tokens;
+        if total == 0 {
+            continue;
+        }
+
+        let model = if row.model.is_empty() {
+            UNKNOWN_MODEL
+        } else {
+            &row.model
+        };
+        let cost = estimate_cost(row)
+            .map(|cost| format!("{:.4}", cost))
+            .unwrap_or_default();
+
+        csv.push_str(&format!(
+            "{},{},{},{},{},{},{},{}\n",
+            row.day,
+            csv_field(model),
+            row.input_tokens,
+            row.output_tokens,
+            row.cache_creation_tokens,
+            row.cache_read_tokens,
+            total,
+            cost
+        ));
+    }
+
+    Ok(csv)
+}
+
+/// Quotes a CSV field if it contains a separator, quote, or line break.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::sqlite::init_db;
+
+    fn setup() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        init_db(&conn).unwrap();
+        for id in ["c1", "c2"] {
+            conn.execute(
+                "INSERT INTO conversations (id, project_path, project_name, start_time, last_time, file_path, file_modified_at)
+                 VALUES (?1, '/p', 'p', '', '', '/f.jsonl', '')",
+                params![id],
+            )
+            .unwrap();
+        }
+        conn
+    }
+
+    fn insert_usage(conn: &Connection, id: &str, day: &str, model: &str, input: i64, output: i64) {
+        conn.execute(
+            "INSERT INTO usage_daily (conversation_id, day, model, messages, input_tokens, output_tokens)
+             VALUES (?1, ?2, ?3, 1, ?4, ?5)",
+            params![id, day, model, input, output],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_month_bounds() {
+        let (start, end) = month_bounds("2025-12").unwrap();
+        assert_eq!(start, NaiveDate::from_ymd_opt(2025, 12, 1).unwrap());
+        assert_eq!(end, NaiveDate::from_ymd_opt(2026, 1, 1).unwrap());
+        assert!(month_bounds("2025-13").is_none());
+        assert!(month_bounds("January").is_none());
+        assert!(month_bounds("2025-01-15").is_none());
+    }
+
+    #[test]
+    fn test_usage_csv_sums_conversations_per_day_and_model() {
+        let conn = setup();
+        insert_usage(&conn, "c1", "2025-01-15", "claude-sonnet-4-5", 1_000_000, 0);
+        insert_usage(&conn, "c2", "2025-01-15", "claude-sonnet-4-5", 0, 100_000);
+        insert_usage(&conn, "c2", "2025-01-15", "", 10, 5);
+        insert_usage(&conn, "c1", "2025-01-02", "claude-opus-4-1", 0, 0);
+        insert_usage(&conn, "c1", "2025-02-01", "claude-sonnet-4-5", 7, 7);
+
+        let (start, end) = month_bounds("2025-01").unwrap();
+        let csv = usage_csv(&conn, start, end).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], HEADER);
+        assert_eq!(
+            &lines[1..],
+            &[
+                "2025-01-15,unknown,10,5,0,0,15,",
+                "2025-01-15,claude-sonnet-4-5,1000000,100000,0,0,1100000,4.5000",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_csv_field_quoting() {
+        assert_eq!(csv_field("plain"), "plain");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+    }
+}
@@ -0,0 +1,165 @@
+//! Programming languages of the code Claude wrote.
+//!
+//! The fenced code blocks of assistant messages are counted per UTC day
+//! and language when a file is ingested (see [`crate::usage::record`]), so
+//! the languages of a project or a period are a sum over `language_usage`.
+
+use crate::db::query::QueryBuilder;
+use crate::db::sqlite::DbResult;
+use crate::models::{LanguageStats, UsageStatsOptions};
+use crate::usage::period::{period_expression, push_usage_filters};
+use rusqlite::Connection;
+
+/// Normalizes a code fence's language tag, so `ts` and `typescript` count
+/// as one language. Returns `None` for untagged and plain text blocks.
+pub fn normalize_language(tag: &str) -> Option<String> {
+    let tag = tag.trim().to_lowercase();
+    let language = match tag.as_str() {
+        "" | "text" | "txt" | "plaintext" | "plain" => return None,
+        "rs" => "rust",
+        "ts" | "mts" | "cts" => "typescript",
+        "js" | "mjs" | "cjs" | "node" => "javascript",
+        "py" | "python3" => "python",
+        "golang" => "go",
+        "rb" => "ruby",
+        "kt" | "kts" => "kotlin",
+        "cs" | "c#" => "csharp",
+        "c++" | "cc" | "cxx" | "hpp" => "cpp",
+        "sh" | "shell" | "zsh" | "console" | "shellsession" => "bash",
+        "yml" => "yaml",
+        "md" => "markdown",
+        "htm" => "html",
+        "postgresql" | "postgres" | "sqlite" | "mysql" => "sql",
+        "dockerfile" | "docker" => "dockerfile",
+        other => other,
+    };
+    Some(language.to_string())
+}
+
+/// Sums code blocks and lines per language for the options' project and
+/// days (inclusive), per period if one is given. Languages with the most
+/// blocks come first, within periods in order.
+pub fn language_stats(
+    conn: &Connection,
+    options: &UsageStatsOptions,
+) -> DbResult<Vec<LanguageStats>> {
+    let mut query = QueryBuilder::new(&format!(
+        r#"
+        SELECT {} AS period, l.language, SUM(l.blocks) AS blocks, SUM(l.lines),
+               COUNT(DISTINCT l.conversation_id)
+        FROM language_usage l
+        JOIN conversations c ON c.id = l.conversation_id
+        WHERE 1=1"#,
+        period_expression(options.period, "l.day"),
+    ));
+    push_usage_filters(&mut query, options, "l.day");
+    query.push(" GROUP BY period, l.language ORDER BY period, blocks DESC, l.language");
+
+    let mut stmt = query.prepare(conn)?;
+    let rows = stmt.query_map(query.params().as_slice(), |row| {
+        Ok(LanguageStats {
+            period: row.get(0)?,
+            language: row.get(1)?,
+            blocks: row.get(2)?,
+            lines: row.get(3)?,
+            conversations: row.get(4)?,
+        })
+    })?;
+    Ok(rows.collect::<Result<_, _>>()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::sqlite::init_db;
+    use crate::models::StatsPeriod;
+
+    fn setup() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        init_db(&conn).unwrap();
+        for (id, project) in [("c1", "alpha"), ("c2", "beta")] {
+            conn.execute(
+                "INSERT INTO conversations (id, project_path, project_name, start_time, last_time, file_path, file_modified_at)
+                 VALUES (?1, '/p', ?2, '', '', '/f.jsonl', '')",
+                [id, project],
+            )
+            .unwrap();
+        }
+        for (id, day, language, blocks, lines) in [
+            ("c1", "2025-01-06", "rust", 3, 40),
+            ("c1", "2025-01-08", "bash", 1, 2),
+            ("c1", "2025-02-03", "rust", 1, 10),
+            ("c2", "2025-01-07", "rust", 2, 5),
+            ("c2", "2025-01-07", "typescript", 4, 30),
+        ] {
+            conn.execute(
+                "INSERT INTO language_usage (conversation_id, day, language, blocks, lines)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                rusqlite::params![id, day, language, blocks, lines],
+            )
+            .unwrap();
+        }
+        conn
+    }
+
+    fn summary(stats: &[LanguageStats]) -> Vec<(Option<&str>, &str, i64, i64)> {
+        stats
+            .iter()
+            .map(|s| (s.period.as_deref(), s.language.as_str(), s.blocks, s.conversations))
+            .collect()
+    }
+
+    #[test]
+    fn test_normalize_language() {
+        assert_eq!(normalize_language("TS").as_deref(), Some("typescript"));
+        assert_eq!(normalize_language("zsh").as_deref(), Some("bash"));
+        assert_eq!(normalize_language("haskell").as_deref(), Some("haskell"));
+        assert_eq!(normalize_language("text"), None);
+        assert_eq!(normalize_language(""), None);
+    }
+
+    #[test]
+    fn test_language_stats_by_project_and_period() {
+        let conn = setup();
+
+        let all = language_stats(&conn, &UsageStatsOptions::default()).unwrap();
+        assert_eq!(
+            summary(&all),
+            vec![
+                (None, "rust", 6, 2),
+                (None, "typescript", 4, 1),
+                (None, "bash", 1, 1)
+            ]
+        );
+        assert_eq!(all[0].lines, 55);
+
+        let options = UsageStatsOptions {
+            project: Some("alpha".to_string()),
+            period: Some(StatsPeriod::Month),
+            ..Default::default()
+        };
+        assert_eq!(
+            summary(&language_stats(&conn, &options).unwrap()),
+            vec![
+                (Some("2025-01"), "rust", 3, 1),
+                (Some("2025-01"), "bash", 1, 1),
+                (Some("2025-02"), "rust", 1, 1)
+            ]
+        );
+
+        // Weeks start on Monday; the day range is inclusive
+        let options = UsageStatsOptions {
+            start_date: Some("2025-01-06".to_string()),
+            end_date: Some("2025-01-07".to_string()),
+            period: Some(StatsPeriod::Week),
+            ..Default::default()
+        };
+        assert_eq!(
+            summary(&language_stats(&conn, &options).unwrap()),
+            vec![
+                (Some("2025-01-06"), "rust", 5, 2),
+                (Some("2025-01-06"), "typescript", 4, 1)
+            ]
+        );
+    }
+}
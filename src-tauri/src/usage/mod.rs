@@ -0,0 +1,23 @@
+//! Token usage, cost, tool, model, and programming language statistics.
+//!
+//! Usage is summarized from each session file when it is ingested and stored
+//! per UTC day, so analytics queries never have to re-read JSONL files.
+
+pub mod anomalies;
+pub mod compare;
+pub mod csv;
+pub mod languages;
+pub mod models;
+pub mod period;
+pub mod pricing;
+pub mod record;
+
+pub use anomalies::{detect_anomalies, DEFAULT_LOOKBACK_DAYS, DEFAULT_THRESHOLD};
+pub use compare::project_metrics;
+pub use csv::{month_bounds, usage_csv};
+pub use languages::{language_stats, normalize_language};
+pub use models::model_stats;
+pub use pricing::{estimate_cost, model_pricing, ModelPricing};
+pub use record::{
+    record_usage, summarize_usage, ConversationUsage, DailyUsage, LanguageUsage, ToolUsage,
+};
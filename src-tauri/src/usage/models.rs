@@ -0,0 +1,153 @@
+//! Side-by-side statistics of the models answering, over time.
+//!
+//! Everything comes from `usage_daily`, which holds the answers, tokens,
+//! and latency of each model per UTC day, so switching models shows up as
+//! a change in share, answer length, and wait between periods.
+
+use crate::db::query::QueryBuilder;
+use crate::db::sqlite::DbResult;
+use crate::models::{ModelStats, UsageStatsOptions};
+use crate::usage::period::{period_expression, push_usage_filters};
+use rusqlite::Connection;
+use std::collections::HashMap;
+
+/// Sums the answers, tokens, and latency of each model for the options'
+/// project and days (inclusive), per period if one is given. Within each
+/// period (oldest first), the model with the most answers comes first.
+pub fn model_stats(conn: &Connection, options: &UsageStatsOptions) -> DbResult<Vec<ModelStats>> {
+    let mut query = QueryBuilder::new(&format!(
+        r#"
+        SELECT {} AS period, u.model, SUM(u.messages) AS answers, SUM(u.input_tokens),
+               SUM(u.output_tokens), SUM(u.response_millis), SUM(u.timed_responses),
+               COUNT(DISTINCT u.conversation_id)
+        FROM usage_daily u
+        JOIN conversations c ON c.id = u.conversation_id
+        WHERE 1=1"#,
+        period_expression(options.period, "u.day"),
+    ));
+    push_usage_filters(&mut query, options, "u.day");
+    query.push(" GROUP BY period, u.model ORDER BY period, answers DESC, u.model");
+
+    let mut stmt = query.prepare(conn)?;
+    let rows = stmt.query_map(query.params().as_slice(), |row| {
+        let answers: i64 = row.get(2)?;
+        let output_tokens: i64 = row.get(4)?;
+        let response_millis: i64 = row.get(5)?;
+        let timed_responses: i64 = row.get(6)?;
+        Ok(ModelStats {
+            period: row.get(0)?,
+            model: row.get(1)?,
+            answers,
+            share: 0.0,
+            input_tokens: row.get(3)?,
+            output_tokens,
+            average_output_tokens: if answers > 0 {
+                output_tokens as f64 / answers as f64
+            } else {
+                0.0
+            },
+            average_latency_millis: (timed_responses > 0)
+                .then(|| response_millis as f64 / timed_responses as f64),
+            conversations: row.get(7)?,
+        })
+    })?;
+    let mut stats: Vec<ModelStats> = rows.collect::<Result<_, _>>()?;
+
+    let mut period_answers: HashMap<Option<String>, i64> = HashMap::new();
+    for row in &stats {
+        *period_answers.entry(row.period.clone()).or_default() += row.answers;
+    }
+    for row in &mut stats {
+        let total = period_answers[&row.period];
+        if total > 0 {
+            row.share = row.answers as f64 / total as f64;
+        }
+    }
+    Ok(stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::sqlite::init_db;
+    use crate::models::StatsPeriod;
+    use rusqlite::params;
+
+    fn setup() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        init_db(&conn).unwrap();
+        for (id, project) in [("c1", "alpha"), ("c2", "beta")] {
+            conn.execute(
+                "INSERT INTO conversations (id, project_path, project_name, start_time, last_time, file_path, file_modified_at)
+                 VALUES (?1, '/p', ?2, '', '', '/f.jsonl', '')",
+                [id, project],
+            )
+            .unwrap();
+        }
+        for (id, day, model, answers, output, millis, timed) in [
+            ("c1", "2025-01-10", "claude-sonnet-4-5", 3, 300, 6_000, 3),
+            ("c2", "2025-01-20", "claude-sonnet-4-5", 1, 100, 0, 0),
+            ("c1", "2025-02-03", "claude-opus-4-1", 2, 800, 20_000, 2),
+            ("c1", "2025-02-04", "claude-sonnet-4-5", 2, 200, 3_000, 1),
+        ] {
+            conn.execute(
+                "INSERT INTO usage_daily (conversation_id, day, model, messages, output_tokens, response_millis, timed_responses)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                params![id, day, model, answers, output, millis, timed],
+            )
+            .unwrap();
+        }
+        conn
+    }
+
+    #[test]
+    fn test_model_stats_by_month() {
+        let conn = setup();
+        let options = UsageStatsOptions {
+            period: Some(StatsPeriod::Month),
+            ..Default::default()
+        };
+        let stats = model_stats(&conn, &options).unwrap();
+        let summary: Vec<(Option<&str>, &str, i64, f64)> = stats
+            .iter()
+            .map(|s| (s.period.as_deref(), s.model.as_str(), s.answers, s.share))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (Some("2025-01"), "claude-sonnet-4-5", 4, 1.0),
+                (Some("2025-02"), "claude-opus-4-1", 2, 0.5),
+                (Some("2025-02"), "claude-sonnet-4-5", 2, 0.5),
+            ]
+        );
+        // Only timed answers count towards the latency
+        assert_eq!(stats[0].average_output_tokens, 100.0);
+        assert_eq!(stats[0].average_latency_millis, Some(2_000.0));
+        assert_eq!(stats[0].conversations, 2);
+        assert_eq!(stats[1].average_output_tokens, 400.0);
+        assert_eq!(stats[1].average_latency_millis, Some(10_000.0));
+    }
+
+    #[test]
+    fn test_model_stats_for_project_and_range() {
+        let conn = setup();
+        let options = UsageStatsOptions {
+            project: Some("beta".to_string()),
+            ..Default::default()
+        };
+        let stats = model_stats(&conn, &options).unwrap();
+        assert_eq!(stats.len(), 1);
+        assert_eq!((stats[0].period.as_deref(), stats[0].answers), (None, 1));
+        assert_eq!(stats[0].average_latency_millis, None);
+
+        let options = UsageStatsOptions {
+            start_date: Some("2025-02-01".to_string()),
+            end_date: Some("2025-02-03".to_string()),
+            ..Default::default()
+        };
+        let stats = model_stats(&conn, &options).unwrap();
+        assert_eq!(stats.len(), 1);
+        assert_eq!(stats[0].model, "claude-opus-4-1");
+        assert_eq!(stats[0].share, 1.0);
+    }
+}
@@ -0,0 +1,30 @@
+//! Project and period selection shared by the usage statistics queries.
+
+use crate::db::query::QueryBuilder;
+use crate::models::{StatsPeriod, UsageStatsOptions};
+
+/// The SQL expression grouping a `YYYY-MM-DD` day column into a period:
+/// the day, the Monday starting its week, or its `YYYY-MM` month. `NULL`
+/// when the statistics aren't grouped by period.
+pub fn period_expression(period: Option<StatsPeriod>, day_column: &str) -> String {
+    match period {
+        None => "NULL".to_string(),
+        Some(StatsPeriod::Day) => day_column.to_string(),
+        Some(StatsPeriod::Week) => format!("date({}, '-6 days', 'weekday 1')", day_column),
+        Some(StatsPeriod::Month) => format!("substr({}, 1, 7)", day_column),
+    }
+}
+
+/// Appends the options' project and day range (inclusive) to a query
+/// joining the statistics table to `conversations c`.
+pub fn push_usage_filters(query: &mut QueryBuilder, options: &UsageStatsOptions, day_column: &str) {
+    if let Some(project) = &options.project {
+        query.push_bind(" AND c.project_name = ?", project.clone());
+    }
+    if let Some(start) = &options.start_date {
+        query.push_bind(&format!(" AND {} >= ?", day_column), start.clone());
+    }
+    if let Some(end) = &options.end_date {
+        query.push_bind(&format!(" AND {} <= ?", day_column), end.clone());
+    }
+}
@@ -0,0 +1,100 @@
+//! Model pricing for cost estimates.
+//!
+//! Prices are list prices in USD per million tokens; cache writes use the
+//! 5-minute cache write price. Estimates ignore batch and volume discounts,
+//! so they are an upper bound rather than an invoice.
+
+use crate::usage::record::DailyUsage;
+
+/// Per-million-token prices for a model family.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ModelPricing {
+    pub input: f64,
+    pub output: f64,
+    pub cache_write: f64,
+    pub cache_read: f64,
+}
+
+/// Model ID fragments and their pricing. The first match wins, so newer
+/// model versions are listed before their family's catch-all entry.
+const PRICING: &[(&str, ModelPricing)] = &[
+    (
+        "opus-4-5",
+        ModelPricing { input: 5.0, output: 25.0, cache_write: 6.25, cache_read: 0.50 },
+    ),
+    (
+        "opus",
+        ModelPricing { input: 15.0, output: 75.0, cache_write: 18.75, cache_read: 1.50 },
+    ),
+    (
+        "sonnet",
+        ModelPricing { input: 3.0, output: 15.0, cache_write: 3.75, cache_read: 0.30 },
+    ),
+    (
+        "haiku-4-5",
+        ModelPricing { input: 1.0, output: 5.0, cache_write: 1.25, cache_read: 0.10 },
+    ),
+    (
+        "3-5-haiku",
+        ModelPricing { input: 0.80, output: 4.0, cache_write: 1.0, cache_read: 0.08 },
+    ),
+    (
+        "haiku",
+        ModelPricing { input: 0.25, output: 1.25, cache_write: 0.30, cache_read: 0.03 },
+    ),
+];
+
+/// Looks up pricing for a model ID such as `claude-sonnet-4-5-20250929`.
+///
+/// Returns `None` for unknown or unrecorded models.
+pub fn model_pricing(model: &str) -> Option<ModelPricing> {
+    let model = model.to_lowercase();
+    PRICING
+        .iter()
+        .find(|(fragment, _)| model.contains(fragment))
+        .map(|(_, pricing)| *pricing)
+}
+
+/// Estimates the cost in USD of `usage`, or `None` if its model is unpriced.
+pub fn estimate_cost(usage: &DailyUsage) -> Option<f64> {
+    let pricing = model_pricing(&usage.model)?;
+    let cost = usage.input_tokens as f64 * pricing.input
+        + usage.output_tokens as f64 * pricing.output
+        + usage.cache_creation_tokens as f64 * pricing.cache_write
+        + usage.cache_read_tokens as f64 * pricing.cache_read;
+    Some(cost / 1_000_000.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_model_pricing_prefers_specific_versions() {
+        assert_eq!(model_pricing("claude-opus-4-5-20251101").unwrap().input, 5.0);
+        assert_eq!(model_pricing("claude-opus-4-1-20250805").unwrap().input, 15.0);
+        assert_eq!(model_pricing("claude-3-5-haiku-20241022").unwrap().input, 0.80);
+        assert_eq!(model_pricing("claude-haiku-4-5-20251001").unwrap().input, 1.0);
+        assert!(model_pricing("").is_none());
+        assert!(model_pricing("<synthetic>").is_none());
+    }
+
+    #[test]
+    fn test_estimate_cost() {
+        let usage = DailyUsage {
+            day: "2025-01-15".to_string(),
+            model: "claude-sonnet-4-5-20250929".to_string(),
+            messages: 1,
+            input_tokens: 1_000_000,
+            output_tokens: 100_000,
+            cache_creation_tokens: 0,
+            cache_read_tokens: 1_000_000,
+            ..Default::default()
+        };
+        let cost = estimate_cost(&usage).unwrap();
+        assert!((cost - 4.8).abs() < 1e-9);
+
+        let unknown = DailyUsage { model: String::new(), ..usage };
+        assert!(estimate_cost(&unknown).is_none());
+    }
+}
@@ -0,0 +1,416 @@
+//! Summarizing and storing a conversation's usage.
+
+use crate::db::sqlite::DbResult;
+use crate::models::ContentBlockType;
+use crate::parser::{
+    epoch_millis, parse_content_blocks, ParsedConversation, RawContent, RawMessageType,
+};
+use crate::usage::languages::normalize_language;
+use chrono::DateTime;
+use rusqlite::{params, Connection};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// Tool name recorded when a tool call or result can't be attributed.
+const UNKNOWN_TOOL: &str = "unknown";
+
+/// Longest wait for an answer counted as its latency. Longer gaps are
+/// sessions resumed later, not the model thinking.
+const MAX_RESPONSE_MILLIS: i64 = 30 * 60 * 1000;
+
+/// Token usage for one model on one UTC day.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DailyUsage {
+    /// UTC day (`YYYY-MM-DD`).
+    pub day: String,
+    /// Model ID, or empty when the file doesn't record one.
+    pub model: String,
+    /// Number of messages carrying usage.
+    pub messages: i64,
+    pub input_tokens: i64,
+    pub output_tokens: i64,
+    pub cache_creation_tokens: i64,
+    pub cache_read_tokens: i64,
+    /// Total time from the user message or tool result to the answer, over
+    /// `timed_responses` answers.
+    pub response_millis: i64,
+    /// Answers whose latency is known.
+    pub timed_responses: i64,
+}
+
+/// Calls to one tool on one UTC day.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ToolUsage {
+    /// UTC day (`YYYY-MM-DD`).
+    pub day: String,
+    pub tool_name: String,
+    pub calls: i64,
+    /// Tool results flagged with `is_error`.
+    pub errors: i64,
+}
+
+/// Code blocks in one language written on one UTC day.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LanguageUsage {
+    /// UTC day (`YYYY-MM-DD`).
+    pub day: String,
+    /// Normalized language name (see `normalize_language`).
+    pub language: String,
+    /// Fenced code blocks in assistant messages.
+    pub blocks: i64,
+    pub lines: i64,
+}
+
+/// Usage summary of a single conversation.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConversationUsage {
+    pub daily: Vec<DailyUsage>,
+    pub tools: Vec<ToolUsage>,
+    pub languages: Vec<LanguageUsage>,
+}
+
+/// Summarizes token usage per day and model, tool calls per day and tool,
+/// and the code blocks of assistant messages per day and language.
+///
+/// Usage repeated across the lines of one streamed response is counted once.
+/// An answer's latency is the time since the user message or tool result
+/// it answers. Tool errors are attributed to the tool whose `tool_use` block they answer.
+/// Untagged and plain text code blocks have no language and aren't counted.
+pub fn summarize_usage(conv: &ParsedConversation) -> ConversationUsage {
+    let fallback_day = utc_day(&conv.start_time).unwrap_or_default();
+    let mut daily: BTreeMap<(String, String), DailyUsage> = BTreeMap::new();
+    let mut tools: BTreeMap<(String, String), ToolUsage> = BTreeMap::new();
+    let mut languages: BTreeMap<(String, String), LanguageUsage> = BTreeMap::new();
+    let mut tool_names: HashMap<&str, &str> = HashMap::new();
+    let mut seen_responses = HashSet::new();
+    let mut asked_at = None;
+
+    for msg in &conv.messages {
+        let day = msg
+            .timestamp
+            .as_deref()
+            .and_then(utc_day)
+            .unwrap_or_else(|| fallback_day.clone());
+        let sent_at = msg.timestamp.as_deref().and_then(epoch_millis);
+        // Only the first assistant line after a user turn answers it
+        let answered = match msg.message_type {
+            RawMessageType::Assistant => asked_at.take(),
+            RawMessageType::User if !msg.is_meta => {
+                asked_at = sent_at;
+                None
+            }
+            _ => None,
+        };
+
+        if let RawContent::Blocks(blocks) = &msg.message.content {
+            for block in blocks {
+                match block.block_type.as_str() {
+                    "tool_use" => {
+                        let name = block.name.as_deref().unwrap_or(UNKNOWN_TOOL);
+                        if let Some(id) = block.tool_use_id.as_deref() {
+                            tool_names.insert(id, name);
+                        }
+                        tool_entry(&mut tools, &day, name).calls += 1;
+                    }
+                    "tool_result" if block.is_error == Some(true) => {
+                        let name = block
+                            .tool_use_id
+                            .as_deref()
+                            .and_then(|id| tool_names.get(id).copied())
+                            .unwrap_or(UNKNOWN_TOOL);
+                        tool_entry(&mut tools, &day, name).errors += 1;
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        if msg.message_type == RawMessageType::Assistant {
+            let code = parse_content_blocks(&msg.message.content)
+                .into_iter()
+                .filter(|block| block.block_type == ContentBlockType::Code);
+            for block in code {
+                let Some(language) = block.language.as_deref().and_then(normalize_language) else {
+                    continue;
+                };
+                let entry = languages
+                    .entry((day.clone(), language.clone()))
+                    .or_insert_with(|| LanguageUsage {
+                        day: day.clone(),
+                        language,
+                        ..Default::default()
+                    });
+                entry.blocks += 1;
+                entry.lines += block.content.lines().count() as i64;
+            }
+        }
+
+        if msg.usage.is_none() && msg.token_count.is_none() {
+            continue;
+        }
+        if msg.usage.is_some() {
+            if let Some(id) = msg.api_message_id.as_deref() {
+                if !seen_responses.insert(id) {
+                    continue;
+                }
+            }
+        }
+
+        let model = msg.model.clone().unwrap_or_default();
+        let entry = daily
+            .entry((day.clone(), model.clone()))
+            .or_insert_with(|| DailyUsage {
+                day,
+                model,
+                ..Default::default()
+            });
+        entry.messages += 1;
+        if let (Some(asked), Some(sent)) = (answered, sent_at) {
+            let latency = sent - asked;
+            if (0..=MAX_RESPONSE_MILLIS).contains(&latency) {
+                entry.response_millis += latency;
+                entry.timed_responses += 1;
+            }
+        }
+        match (&msg.usage, &msg.token_count) {
+            (Some(usage), _) => {
+                entry.input_tokens += usage.input_tokens;
+                entry.output_tokens += usage.output_tokens;
+                entry.cache_creation_tokens += usage.cache_creation_input_tokens;
+                entry.cache_read_tokens += usage.cache_read_input_tokens;
+            }
+            (None, Some(tokens)) => {
+                entry.input_tokens += tokens.input;
+                entry.output_tokens += tokens.output;
+            }
+            (None, None) => {}
+        }
+    }
+
+    ConversationUsage {
+        daily: daily.into_values().collect(),
+        tools: tools.into_values().collect(),
+        languages: languages.into_values().collect(),
+    }
+}
+
+/// Replaces the stored usage of a conversation.
+pub fn record_usage(
+    conn: &Connection,
+    conversation_id: &str,
+    usage: &ConversationUsage,
+) -> DbResult<()> {
+    conn.prepare_cached("DELETE FROM usage_daily WHERE conversation_id = ?1")?
+        .execute([conversation_id])?;
+    conn.prepare_cached("DELETE FROM tool_usage WHERE conversation_id = ?1")?
+        .execute([conversation_id])?;
+    conn.prepare_cached("DELETE FROM language_usage WHERE conversation_id = ?1")?
+        .execute([conversation_id])?;
+
+    let mut insert_daily = conn.prepare_cached(
+        r#"
+        INSERT INTO usage_daily (
+            conversation_id, day, model, messages, input_tokens, output_tokens,
+            cache_creation_tokens, cache_read_tokens, response_millis, timed_responses
+        )
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
+        "#,
+    )?;
+    for row in &usage.daily {
+        insert_daily.execute(params![
+            conversation_id,
+            row.day,
+            row.model,
+            row.messages,
+            row.input_tokens,
+            row.output_tokens,
+            row.cache_creation_tokens,
+            row.cache_read_tokens,
+            row.response_millis,
+            row.timed_responses,
+        ])?;
+    }
+
+    let mut insert_tool = conn.prepare_cached(
+        r#"
+        INSERT INTO tool_usage (conversation_id, day, tool_name, calls, errors)
+        VALUES (?1, ?2, ?3, ?4, ?5)
+        "#,
+    )?;
+    for row in &usage.tools {
+        insert_tool.execute(params![conversation_id, row.day, row.tool_name, row.calls, row.errors])?;
+    }
+
+    let mut insert_language = conn.prepare_cached(
+        r#"
+        INSERT INTO language_usage (conversation_id, day, language, blocks, lines)
+        VALUES (?1, ?2, ?3, ?4, ?5)
+        "#,
+    )?;
+    for row in &usage.languages {
+        insert_language.execute(params![conversation_id, row.day, row.language, row.blocks, row.lines])?;
+    }
+
+    Ok(())
+}
+
+/// Returns the UTC day (`YYYY-MM-DD`) of a timestamp.
+fn utc_day(timestamp: &str) -> Option<String> {
+    let millis = epoch_millis(timestamp)?;
+    let utc = DateTime::from_timestamp_millis(millis)?;
+    Some(utc.format("%Y-%m-%d").to_string())
+}
+
+fn tool_entry<'a>(
+    tools: &'a mut BTreeMap<(String, String), ToolUsage>,
+    day: &str,
+    name: &str,
+) -> &'a mut ToolUsage {
+    tools
+        .entry((day.to_string(), name.to_string()))
+        .or_insert_with(|| ToolUsage {
+            day: day.to_string(),
+            tool_name: name.to_string(),
+            ..Default::default()
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::sqlite::init_db;
+    use crate::parser::parse_conversation_file;
+    use std::io::Write;
+
+    fn parse_lines(lines: &[&str]) -> ParsedConversation {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("session-usage.jsonl");
+        let mut file = std::fs::File::create(&path).unwrap();
+        for line in lines {
+            writeln!(file, "{}", line).unwrap();
+        }
+        parse_conversation_file(&path).unwrap().remove(0)
+    }
+
+    fn sample_conversation() -> ParsedConversation {
+        parse_lines(&[
+            r#"{"type":"user","message":{"role":"user","content":"Fix the build"},"timestamp":"2025-01-15T23:50:00Z","sessionId":"s1"}"#,
+            r#"{"type":"assistant","message":{"id":"msg_1","model":"claude-sonnet-4-5","role":"assistant","content":[{"type":"text","text":"Running it"}],"usage":{"input_tokens":100,"output_tokens":10,"cache_read_input_tokens":1000}},"timestamp":"2025-01-15T23:55:00Z","sessionId":"s1"}"#,
+            r#"{"type":"assistant","message":{"id":"msg_1","model":"claude-sonnet-4-5","role":"assistant","content":[{"type":"tool_use","id":"t1","name":"Bash","input":{}}],"usage":{"input_tokens":100,"output_tokens":10,"cache_read_input_tokens":1000}},"timestamp":"2025-01-15T23:55:01Z","sessionId":"s1"}"#,
+            r#"{"type":"user","message":{"role":"user","content":[{"type":"tool_result","tool_use_id":"t1","content":"exit 1","is_error":true}]},"timestamp":"2025-01-16T00:01:00Z","sessionId":"s1"}"#,
+            r#"{"type":"assistant","message":{"id":"msg_2","model":"claude-sonnet-4-5","role":"assistant","content":[{"type":"tool_use","id":"t2","name":"Bash","input":{}}],"usage":{"input_tokens":5,"output_tokens":7}},"timestamp":"2025-01-16T00:02:00Z","sessionId":"s1"}"#,
+        ])
+    }
+
+    #[test]
+    fn test_summarize_usage_counts_code_languages() {
+        let usage = summarize_usage(&parse_lines(&[
+            r#"{"type":"user","message":{"role":"user","content":"```rust\nfn main() {}\n```"},"timestamp":"2025-01-15T10:00:00Z","sessionId":"s1"}"#,
+            r#"{"type":"assistant","message":{"role":"assistant","content":[{"type":"text","text":"```ts\nlet a = 1;\nlet b = 2;\n```\nand\n```typescript\nexport {};\n```\n```\nplain\n```"}]},"timestamp":"2025-01-15T10:01:00Z","sessionId":"s1"}"#,
+            r#"{"type":"assistant","message":{"role":"assistant","content":"```sh\nls\n```"},"timestamp":"2025-01-16T09:00:00Z","sessionId":"s1"}"#,
+        ]));
+
+        // The user's code and the untagged block don't count
+        assert_eq!(
+            usage.languages,
+            vec![
+                LanguageUsage {
+                    day: "2025-01-15".to_string(),
+                    language: "typescript".to_string(),
+                    blocks: 2,
+                    lines: 3,
+                },
+                LanguageUsage {
+                    day: "2025-01-16".to_string(),
+                    language: "bash".to_string(),
+                    blocks: 1,
+                    lines: 1,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_summarize_usage_by_day_and_model() {
+        let usage = summarize_usage(&sample_conversation());
+
+        assert_eq!(usage.daily.len(), 2);
+        let first = &usage.daily[0];
+        assert_eq!(first.day, "2025-01-15");
+        assert_eq!(first.model, "claude-sonnet-4-5");
+        // The streamed response's two lines count once
+        assert_eq!(first.messages, 1);
+        assert_eq!(first.input_tokens, 100);
+        assert_eq!(first.cache_read_tokens, 1000);
+        assert_eq!(usage.daily[1].day, "2025-01-16");
+        assert_eq!(usage.daily[1].output_tokens, 7);
+        // Answers are timed from the prompt or tool result they answer
+        assert_eq!((first.response_millis, first.timed_responses), (300_000, 1));
+        assert_eq!(
+            (usage.daily[1].response_millis, usage.daily[1].timed_responses),
+            (60_000, 1)
+        );
+    }
+
+    #[test]
+    fn test_summarize_usage_attributes_tool_errors() {
+        let usage = summarize_usage(&sample_conversation());
+
+        assert_eq!(
+            usage.tools,
+            vec![
+                ToolUsage {
+                    day: "2025-01-15".to_string(),
+                    tool_name: "Bash".to_string(),
+                    calls: 1,
+                    errors: 0,
+                },
+                ToolUsage {
+                    day: "2025-01-16".to_string(),
+                    tool_name: "Bash".to_string(),
+                    calls: 1,
+                    errors: 1,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_record_usage_replaces_previous_rows() {
+        let conn = Connection::open_in_memory().unwrap();
+        init_db(&conn).unwrap();
+        conn.execute(
+            "INSERT INTO conversations (id, project_path, project_name, start_time, last_time, file_path, file_modified_at)
+             VALUES ('c1', '/p', 'p', '', '', '/f.jsonl', '')",
+            [],
+        )
+        .unwrap();
+
+        let mut usage = summarize_usage(&sample_conversation());
+        usage.languages.push(LanguageUsage {
+            day: "2025-01-15".to_string(),
+            language: "rust".to_string(),
+            blocks: 2,
+            lines: 9,
+        });
+        record_usage(&conn, "c1", &usage).unwrap();
+        record_usage(&conn, "c1", &usage).unwrap();
+
+        let (rows, input): (i64, i64) = conn
+            .query_row(
+                "SELECT COUNT(*), SUM(input_tokens) FROM usage_daily WHERE conversation_id = 'c1'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(rows, 2);
+        assert_eq!(input, 105);
+        let lines: i64 = conn
+            .query_row(
+                "SELECT SUM(lines) FROM language_usage WHERE conversation_id = 'c1'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(lines, 9);
+    }
+}
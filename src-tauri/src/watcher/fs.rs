@@ -3,16 +3,25 @@
 //! Watches `~/.claude/projects/` for new/modified JSONL files and triggers
 //! incremental parsing and indexing when changes are detected.
 
-use crate::db::metadata::{get_modified_files, update_file_metadata};
-use crate::db::sqlite::Database;
-use crate::parser::jsonl::{discover_jsonl_files, get_claude_projects_dir, parse_conversation_file};
-use crate::search::index::index_conversation_content;
+use crate::db::access::next_access_retry;
+use crate::db::settings::load_settings;
+use crate::events::{
+    emit_conversations_changed, emit_watcher_status, INDEX_PROGRESS_EVENT,
+};
+use crate::ingest::{ingest_files, scan_all, NoProgress, ProgressSink};
+use crate::metrics;
+use crate::parser::jsonl::get_session_roots;
+use crate::parser::{is_session_file, normalize_path, primary_session_file, IgnoreRules};
 use crate::state::AppState;
+use crate::models::{AnomalyOptions, IngestProgress, IngestStats, WatcherState};
+use crate::usage::detect_anomalies;
+use chrono::Utc;
 use notify::{
     event::{CreateKind, ModifyKind},
     Config, Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher,
 };
 use std::collections::HashSet;
+use std::fs;
 use std::path::PathBuf;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{mpsc, Arc};
@@ -44,72 +53,34 @@ pub enum WatcherError {
     Parser(String),
 }
 
-/// Event name for conversations updated events sent to frontend.
-pub const CONVERSATIONS_UPDATED_EVENT: &str = "conversations-updated";
+/// Event name for usage anomaly notifications sent to frontend.
+pub const USAGE_ANOMALY_EVENT: &str = "usage-anomaly";
 
-/// Processes a batch of files and emits an update event.
-/// This is a public helper for both the file watcher and initial scan.
-pub fn process_files_and_emit(
-    files: &[crate::db::metadata::ModifiedFile],
-    app_handle: &AppHandle,
-    app_state: &Arc<AppState>,
-) {
-    let db = app_state.db();
+/// Forwards ingest progress to the frontend as `index-progress` events.
+pub struct EventProgress<'a>(pub &'a AppHandle);
 
-    let mut new_count = 0;
-    let mut updated_count = 0;
-
-    // Process each file
-    for modified_file in files {
-        match process_single_file(&db, &modified_file.file_path, &modified_file.current_modified_at)
-        {
-            Ok(count) => {
-                if modified_file.is_new {
-                    new_count += count;
-                } else {
-                    updated_count += count;
-                }
-            }
-            Err(e) => {
-                error!(
-                    "Error processing file {:?}: {}",
-                    modified_file.file_path, e
-                );
-            }
+impl ProgressSink for EventProgress<'_> {
+    fn report(&self, progress: &IngestProgress) {
+        if let Err(e) = self.0.emit(INDEX_PROGRESS_EVENT, progress) {
+            error!("Error emitting index-progress event: {}", e);
         }
     }
+}
 
+/// Refreshes the conversations cache and emits `conversation-added` and
+/// `conversation-updated` events for an ingest run.
+pub fn emit_conversations_updated(
+    app_handle: &AppHandle,
+    app_state: &Arc<AppState>,
+    stats: &IngestStats,
+    from_watcher: bool,
+) {
     // Refresh the conversations cache
     if let Err(e) = app_state.refresh_conversations_cache() {
         error!("Error refreshing conversations cache: {}", e);
     }
 
-    // Emit event to frontend
-    let payload = ConversationsUpdatedPayload {
-        new_count,
-        updated_count,
-        from_watcher: false, // Set to false for initial scan
-    };
-
-    if let Err(e) = app_handle.emit(CONVERSATIONS_UPDATED_EVENT, payload) {
-        error!("Error emitting conversations-updated event: {}", e);
-    } else {
-        info!(
-            "Emitted conversations-updated event: {} new, {} updated",
-            new_count, updated_count
-        );
-    }
-}
-
-/// Payload for the conversations-updated event.
-#[derive(Clone, serde::Serialize)]
-pub struct ConversationsUpdatedPayload {
-    /// Number of new conversations added.
-    pub new_count: usize,
-    /// Number of existing conversations updated.
-    pub updated_count: usize,
-    /// Whether this was triggered by file watcher (vs initial load).
-    pub from_watcher: bool,
+    emit_conversations_changed(app_handle, stats, from_watcher);
 }
 
 /// Handle to control the file watcher.
@@ -143,13 +114,17 @@ pub fn stop_watcher(handle: WatcherHandle) {
 
 /// Starts the file system watcher in a background thread.
 ///
-/// Watches `~/.claude/projects/` for new/modified `.jsonl` files.
+/// Watches `~/.claude/projects/` for new/modified `.jsonl` files, including
+/// gzipped (`.jsonl.gz`) and rotated (`.jsonl.1`) segments.
 /// When changes are detected:
 /// 1. Debounces rapid changes (100ms)
 /// 2. Parses the modified file
 /// 3. Updates the database and search index
 /// 4. Refreshes the AppState cache
-/// 5. Emits a Tauri event to the frontend
+/// 5. Emits `conversation-added`/`conversation-updated` events to the frontend
+///
+/// Emits `watcher-status` once watching starts and when the watcher thread
+/// exits.
 ///
 /// # Arguments
 /// * `app_handle` - Tauri app handle for emitting events to frontend
@@ -162,9 +137,10 @@ pub fn start_watcher(
     app_handle: AppHandle,
     app_state: Arc<AppState>,
 ) -> Result<WatcherHandle, WatcherError> {
-    // Get the Claude projects directory
-    let projects_dir = get_claude_projects_dir()
-        .map_err(|e| WatcherError::ProjectsDirNotFound(e.to_string()))?;
+    // Get the Claude projects directory and the imported sessions directory
+    let roots =
+        get_session_roots().map_err(|e| WatcherError::ProjectsDirNotFound(e.to_string()))?;
+    let (projects_dir, other_roots) = roots.split_first().expect("projects directory");
 
     // Check if directory exists
     if !projects_dir.exists() {
@@ -174,7 +150,7 @@ pub fn start_watcher(
         );
     }
 
-    info!("Starting file watcher for: {:?}", projects_dir);
+    info!("Starting file watcher for: {:?}", roots);
 
     // Create channel for receiving events
     let (tx, rx) = mpsc::channel::<Event>();
@@ -188,12 +164,8 @@ pub fn start_watcher(
                     EventKind::Create(CreateKind::File)
                     | EventKind::Modify(ModifyKind::Data(_))
                     | EventKind::Modify(ModifyKind::Any) => {
-                        // Filter to only JSONL files
-                        let has_jsonl = event.paths.iter().any(|p| {
-                            p.extension()
-                                .map(|ext| ext == "jsonl")
-                                .unwrap_or(false)
-                        });
+                        // Filter to only session files (plain, gzipped, or rotated)
+                        let has_jsonl = event.paths.iter().any(|p| is_session_file(p));
                         if has_jsonl {
                             let _ = tx.send(event);
                         }
@@ -206,10 +178,21 @@ pub fn start_watcher(
     )
     .map_err(|e| WatcherError::WatcherCreation(e.to_string()))?;
 
-    // Start watching the directory
+    // Start watching the directories
     watcher
-        .watch(&projects_dir, RecursiveMode::Recursive)
+        .watch(projects_dir, RecursiveMode::Recursive)
         .map_err(|e| WatcherError::WatchStart(e.to_string()))?;
+    // The imported directory is the app's own, created before the first
+    // drop so files copied into it later are seen
+    for root in other_roots {
+        if let Err(e) = fs::create_dir_all(root) {
+            warn!("Not watching {:?}: {}", root, e);
+        } else if let Err(e) = watcher.watch(root, RecursiveMode::Recursive) {
+            warn!("Not watching {:?}: {}", root, e);
+        }
+    }
+    app_state.set_watcher_state(WatcherState::Running);
+    emit_watcher_status(&app_handle, WatcherState::Running, None);
 
     // Create stop flag
     let stop_flag = Arc::new(AtomicBool::new(false));
@@ -234,11 +217,13 @@ pub fn start_watcher(
             // Try to receive with timeout
             match rx.recv_timeout(Duration::from_millis(50)) {
                 Ok(event) => {
-                    // Add paths to pending set
+                    metrics::increment("watcher.events");
+                    // Add paths to pending set, by the session they belong to
                     for path in event.paths {
-                        if path.extension().map(|ext| ext == "jsonl").unwrap_or(false) {
+                        if is_session_file(&path) {
                             debug!("File change detected: {:?}", path);
-                            pending_files.insert(path);
+                            // Events may carry the verbatim spelling on Windows
+                            pending_files.insert(primary_session_file(&normalize_path(&path)));
                         }
                     }
                     last_event_time = Some(Instant::now());
@@ -251,6 +236,7 @@ pub fn start_watcher(
                                 // Process pending files
                                 let files: Vec<PathBuf> = pending_files.drain().collect();
                                 info!("Processing {} changed files after debounce", files.len());
+                                let _timer = metrics::start_timer("watcher.process_changes");
 
                                 if let Err(e) = process_changed_files(
                                     &files,
@@ -267,10 +253,18 @@ pub fn start_watcher(
                 }
                 Err(mpsc::RecvTimeoutError::Disconnected) => {
                     warn!("Watcher channel disconnected");
-                    break;
+                    app_state.set_watcher_state(WatcherState::Failed);
+                    emit_watcher_status(
+                        &app_handle,
+                        WatcherState::Failed,
+                        Some("Watcher channel disconnected".to_string()),
+                    );
+                    return;
                 }
             }
         }
+        app_state.set_watcher_state(WatcherState::Stopped);
+        emit_watcher_status(&app_handle, WatcherState::Stopped, None);
     });
 
     Ok(WatcherHandle {
@@ -279,6 +273,44 @@ pub fn start_watcher(
     })
 }
 
+/// Scans again whenever a path the last scan couldn't read is due for a
+/// retry (see [`crate::db::access`]), until a scan reads them all. Runs on
+/// the calling thread.
+pub fn retry_unreadable_paths(app_handle: &AppHandle, app_state: &Arc<AppState>) {
+    let db = app_state.db();
+    let mut previous_retry = None;
+    loop {
+        let next_retry = match db.with_connection(next_access_retry) {
+            Ok(Some(next_retry)) => next_retry,
+            Ok(None) => return,
+            Err(e) => {
+                error!("Error reading access errors: {}", e);
+                return;
+            }
+        };
+        // The scan couldn't record its outcome, so retrying would spin
+        if previous_retry == Some(next_retry) {
+            warn!("Access errors weren't updated; no longer retrying unreadable paths");
+            return;
+        }
+        previous_retry = Some(next_retry);
+
+        let wait = (next_retry - Utc::now().timestamp_millis()).max(0) as u64;
+        info!("Retrying unreadable paths in {} s", wait / 1000);
+        thread::sleep(Duration::from_millis(wait));
+        match scan_all(&db, &NoProgress) {
+            Ok(stats) if stats.processed_files + stats.failed_files > 0 => {
+                emit_conversations_updated(app_handle, app_state, &stats, false);
+            }
+            Ok(_) => {}
+            Err(e) => {
+                error!("Retry scan failed: {}", e);
+                return;
+            }
+        }
+    }
+}
+
 /// Processes changed files: parses, updates database, and emits events.
 fn process_changed_files(
     changed_paths: &[PathBuf],
@@ -286,206 +318,66 @@ fn process_changed_files(
     app_state: &Arc<AppState>,
 ) -> Result<(), WatcherError> {
     let db = app_state.db();
-
-    // Discover all JSONL files to get current state
-    let all_files = discover_jsonl_files()
-        .map_err(|e| WatcherError::Parser(e.to_string()))?;
-
-    // Get modified files from database comparison
-    let modified_files = db
-        .with_connection(|conn| get_modified_files(conn, &all_files))
+    let settings = db
+        .with_connection(load_settings)
         .map_err(|e| WatcherError::Database(e.to_string()))?;
-
-    // Filter to only files that were actually changed (from watcher)
-    let files_to_process: Vec<_> = modified_files
-        .into_iter()
-        .filter(|f| changed_paths.contains(&f.file_path))
+    let rules = IgnoreRules::from_settings(&settings);
+    let paths: Vec<PathBuf> = changed_paths
+        .iter()
+        .filter(|path| !rules.is_ignored(path))
+        .cloned()
         .collect();
-
-    if files_to_process.is_empty() {
-        debug!("No files need processing after filtering");
-        return Ok(());
+    if paths.len() < changed_paths.len() {
+        debug!("Ignoring {} changed files", changed_paths.len() - paths.len());
     }
 
-    info!("Processing {} modified files", files_to_process.len());
-
-    let mut new_count = 0;
-    let mut updated_count = 0;
-
-    // Process each file
-    for modified_file in &files_to_process {
-        match process_single_file(&db, &modified_file.file_path, &modified_file.current_modified_at)
-        {
-            Ok(count) => {
-                if modified_file.is_new {
-                    new_count += count;
-                } else {
-                    updated_count += count;
-                }
-            }
-            Err(e) => {
-                error!(
-                    "Error processing file {:?}: {}",
-                    modified_file.file_path, e
-                );
-            }
-        }
-    }
+    let stats = ingest_files(&db, &paths, &NoProgress)
+        .map_err(|e| WatcherError::Database(e.to_string()))?;
 
-    // Refresh the conversations cache
-    if let Err(e) = app_state.refresh_conversations_cache() {
-        error!("Error refreshing conversations cache: {}", e);
+    if stats.processed_files == 0 && stats.failed_files == 0 {
+        debug!("No files need processing after filtering");
+        return Ok(());
     }
 
-    // Emit event to frontend
-    let payload = ConversationsUpdatedPayload {
-        new_count,
-        updated_count,
-        from_watcher: true,
-    };
-
-    if let Err(e) = app_handle.emit(CONVERSATIONS_UPDATED_EVENT, payload) {
-        error!("Error emitting conversations-updated event: {}", e);
-    } else {
-        info!(
-            "Emitted conversations-updated event: {} new, {} updated",
-            new_count, updated_count
-        );
-    }
+    emit_conversations_updated(app_handle, app_state, &stats, true);
+    notify_usage_anomalies(app_handle, app_state);
 
     Ok(())
 }
 
-/// Processes a single file: parses it and updates the database.
-/// Returns the number of conversations processed.
-fn process_single_file(
-    db: &Arc<Database>,
-    file_path: &PathBuf,
-    modified_at: &str,
-) -> Result<usize, WatcherError> {
-    debug!("Processing file: {:?}", file_path);
-
-    // Parse the file
-    let conversations = parse_conversation_file(file_path)
-        .map_err(|e| WatcherError::Parser(e.to_string()))?;
-
-    if conversations.is_empty() {
-        debug!("No conversations found in {:?}", file_path);
-        return Ok(0);
-    }
-
-    let count = conversations.len();
-
-    // Update database
-    db.with_connection_mut(|conn| {
-        let tx = conn.transaction().map_err(crate::db::sqlite::DbError::Sqlite)?;
-
-        for conv in &conversations {
-            // Generate preview from first message content
-            let preview = generate_preview(&conv.messages);
-
-            // Insert or update conversation
-            tx.execute(
-                r#"
-                INSERT INTO conversations (
-                    id, project_path, project_name, start_time, last_time,
-                    preview, message_count, total_input_tokens, total_output_tokens,
-                    file_path, file_modified_at
-                )
-                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)
-                ON CONFLICT(id) DO UPDATE SET
-                    project_path = excluded.project_path,
-                    project_name = excluded.project_name,
-                    start_time = excluded.start_time,
-                    last_time = excluded.last_time,
-                    preview = excluded.preview,
-                    message_count = excluded.message_count,
-                    total_input_tokens = excluded.total_input_tokens,
-                    total_output_tokens = excluded.total_output_tokens,
-                    file_path = excluded.file_path,
-                    file_modified_at = excluded.file_modified_at
-                "#,
-                rusqlite::params![
-                    conv.id,
-                    conv.project_path,
-                    conv.project_name,
-                    conv.start_time,
-                    conv.last_time,
-                    preview,
-                    conv.messages.len(),
-                    conv.total_input_tokens,
-                    conv.total_output_tokens,
-                    conv.file_path.to_string_lossy(),
-                    modified_at,
-                ],
-            )
-            .map_err(crate::db::sqlite::DbError::Sqlite)?;
-
-            // Update search index (best-effort: log warning if fails but continue)
-            if let Err(e) = index_conversation_content(&tx, &conv.id, &preview, &conv.project_name) {
-                warn!("Error indexing conversation {}: {}", conv.id, e);
-            }
+/// Emits today's not-yet-reported usage anomalies, if enabled in settings.
+///
+/// Only runs for live changes so runaway sessions are caught while they
+/// are still running; a one-off scan of old history never notifies.
+fn notify_usage_anomalies(app_handle: &AppHandle, app_state: &Arc<AppState>) {
+    let today = Utc::now().date_naive();
+    let detected = app_state.db().with_connection(|conn| {
+        if !load_settings(conn)?.anomaly_notifications {
+            return Ok(Vec::new());
         }
+        detect_anomalies(conn, &AnomalyOptions::default(), today)
+    });
 
-        // Update file metadata
-        update_file_metadata(&tx, file_path, modified_at)?;
-
-        tx.commit().map_err(crate::db::sqlite::DbError::Sqlite)?;
-        Ok(())
-    })
-    .map_err(|e| WatcherError::Database(e.to_string()))?;
-
-    debug!("Processed {} conversations from {:?}", count, file_path);
-    Ok(count)
-}
-
-/// Generates a preview string from conversation messages.
-fn generate_preview(messages: &[crate::parser::jsonl::RawMessage]) -> String {
-    use crate::parser::jsonl::RawContent;
-
-    // Find first user message for preview
-    for msg in messages {
-        if let crate::parser::jsonl::RawMessageType::User = msg.message_type {
-            match &msg.message.content {
-                RawContent::Text(text) => {
-                    // Truncate to reasonable preview length
-                    let preview = text.chars().take(200).collect::<String>();
-                    return preview.replace('\n', " ").trim().to_string();
-                }
-                RawContent::Blocks(blocks) => {
-                    // Get text from first text block
-                    for block in blocks {
-                        if block.block_type == "text" {
-                            if let Some(text) = &block.text {
-                                let preview = text.chars().take(200).collect::<String>();
-                                return preview.replace('\n', " ").trim().to_string();
-                            }
-                        }
-                    }
-                }
-            }
+    let anomalies = match detected {
+        Ok(anomalies) => anomalies,
+        Err(e) => {
+            error!("Error detecting usage anomalies: {}", e);
+            return;
         }
-    }
+    };
 
-    // Fallback to first message of any type
-    if let Some(first) = messages.first() {
-        match &first.message.content {
-            RawContent::Text(text) => {
-                let preview = text.chars().take(200).collect::<String>();
-                return preview.replace('\n', " ").trim().to_string();
-            }
-            RawContent::Blocks(blocks) => {
-                for block in blocks {
-                    if let Some(text) = &block.text {
-                        let preview = text.chars().take(200).collect::<String>();
-                        return preview.replace('\n', " ").trim().to_string();
-                    }
-                }
-            }
-        }
+    let today = today.format("%Y-%m-%d").to_string();
+    let todays = anomalies.into_iter().filter(|a| a.day == today).collect();
+    let new_anomalies = app_state.take_unnotified_anomalies(todays);
+    if new_anomalies.is_empty() {
+        return;
     }
 
-    String::new()
+    if let Err(e) = app_handle.emit(USAGE_ANOMALY_EVENT, &new_anomalies) {
+        error!("Error emitting usage-anomaly event: {}", e);
+    } else {
+        info!("Emitted usage-anomaly event: {} anomalies", new_anomalies.len());
+    }
 }
 
 #[cfg(test)]
@@ -496,73 +388,4 @@ mod tests {
     fn test_debounce_duration() {
         assert_eq!(DEBOUNCE_DURATION, Duration::from_millis(100));
     }
-
-    #[test]
-    fn test_generate_preview_text_content() {
-        use crate::parser::jsonl::{RawContent, RawInnerMessage, RawMessage, RawMessageType};
-
-        let messages = vec![RawMessage {
-            message_type: RawMessageType::User,
-            message: RawInnerMessage {
-                content: RawContent::Text("Hello, how can I help you today?".to_string()),
-                role: Some("user".to_string()),
-            },
-            timestamp: None,
-            token_count: None,
-            uuid: None,
-            session_id: None,
-        }];
-
-        let preview = generate_preview(&messages);
-        assert_eq!(preview, "Hello, how can I help you today?");
-    }
-
-    #[test]
-    fn test_generate_preview_truncates_long_text() {
-        use crate::parser::jsonl::{RawContent, RawInnerMessage, RawMessage, RawMessageType};
-
-        let long_text = "a".repeat(300);
-        let messages = vec![RawMessage {
-            message_type: RawMessageType::User,
-            message: RawInnerMessage {
-                content: RawContent::Text(long_text),
-                role: Some("user".to_string()),
-            },
-            timestamp: None,
-            token_count: None,
-            uuid: None,
-            session_id: None,
-        }];
-
-        let preview = generate_preview(&messages);
-        assert_eq!(preview.len(), 200);
-    }
-
-    #[test]
-    fn test_generate_preview_removes_newlines() {
-        use crate::parser::jsonl::{RawContent, RawInnerMessage, RawMessage, RawMessageType};
-
-        let messages = vec![RawMessage {
-            message_type: RawMessageType::User,
-            message: RawInnerMessage {
-                content: RawContent::Text("Line 1\nLine 2\nLine 3".to_string()),
-                role: Some("user".to_string()),
-            },
-            timestamp: None,
-            token_count: None,
-            uuid: None,
-            session_id: None,
-        }];
-
-        let preview = generate_preview(&messages);
-        assert!(!preview.contains('\n'));
-        assert_eq!(preview, "Line 1 Line 2 Line 3");
-    }
-
-    #[test]
-    fn test_generate_preview_empty_messages() {
-        let messages: Vec<crate::parser::jsonl::RawMessage> = vec![];
-        let preview = generate_preview(&messages);
-        assert!(preview.is_empty());
-    }
 }
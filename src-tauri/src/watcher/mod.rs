@@ -5,4 +5,7 @@
 
 pub mod fs;
 
-pub use fs::{process_files_and_emit, start_watcher, stop_watcher, WatcherError, WatcherHandle};
+pub use fs::{
+    emit_conversations_updated, retry_unreadable_paths, start_watcher, stop_watcher,
+    EventProgress, WatcherError, WatcherHandle,
+};
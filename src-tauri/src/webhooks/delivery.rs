@@ -0,0 +1,341 @@
+//! Sending queued webhook deliveries.
+//!
+//! The dispatcher thread wakes every `DISPATCH_INTERVAL`, sends the
+//! deliveries that are due, and records each outcome. A 2xx response
+//! delivers a payload; anything else is retried with exponential backoff
+//! until `MAX_DELIVERY_ATTEMPTS`, after which the delivery is failed.
+//! Deliveries of disabled webhooks wait until they're enabled again.
+
+use crate::db::sqlite::{Database, DbResult};
+use crate::metrics;
+use crate::models::DeliveryStatus;
+use rusqlite::{params, Connection};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use tracing::{debug, error, info, warn};
+
+/// Attempts made before a delivery is failed.
+pub const MAX_DELIVERY_ATTEMPTS: u32 = 5;
+
+/// Wait before the first retry; doubled for each later one.
+const RETRY_BASE_DELAY: Duration = Duration::from_secs(30);
+
+/// Longest wait between retries.
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(60 * 60);
+
+/// How often the dispatcher looks for due deliveries.
+const DISPATCH_INTERVAL: Duration = Duration::from_secs(10);
+
+/// How long a webhook has to respond.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Most deliveries sent per dispatcher pass.
+const DELIVERY_BATCH_SIZE: u32 = 50;
+
+/// Sends webhook payloads.
+pub trait Transport {
+    /// POSTs a JSON body to `url`, returning the response status, or why
+    /// no response arrived.
+    fn post_json(&self, url: &str, body: &str) -> Result<u16, String>;
+}
+
+/// Sends payloads over HTTP(S).
+pub struct HttpTransport {
+    agent: ureq::Agent,
+}
+
+impl HttpTransport {
+    pub fn new() -> Self {
+        Self {
+            agent: ureq::AgentBuilder::new().timeout(REQUEST_TIMEOUT).build(),
+        }
+    }
+}
+
+impl Default for HttpTransport {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Transport for HttpTransport {
+    fn post_json(&self, url: &str, body: &str) -> Result<u16, String> {
+        let response = self
+            .agent
+            .post(url)
+            .set("Content-Type", "application/json")
+            .set(
+                "User-Agent",
+                concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION")),
+            )
+            .send_string(body);
+        match response {
+            Ok(response) => Ok(response.status()),
+            Err(ureq::Error::Status(status, _)) => Ok(status),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+}
+
+/// Wait before retrying a delivery that has failed `attempts` times.
+pub fn retry_delay(attempts: u32) -> Duration {
+    let doublings = attempts.saturating_sub(1).min(16);
+    (RETRY_BASE_DELAY * 2u32.pow(doublings)).min(MAX_RETRY_DELAY)
+}
+
+/// A delivery due to be sent.
+struct DueDelivery {
+    id: i64,
+    url: String,
+    payload: String,
+    attempts: u32,
+}
+
+/// Sends the deliveries that are due and records the outcomes. Returns
+/// the number sent.
+///
+/// The database isn't locked while a request is in flight.
+pub fn deliver_due(db: &Database, transport: &dyn Transport) -> DbResult<usize> {
+    let now = chrono::Utc::now().timestamp_millis();
+    let due = db.with_connection(|conn| due_deliveries(conn, now))?;
+    for delivery in &due {
+        let outcome = transport.post_json(&delivery.url, &delivery.payload);
+        db.with_connection(|conn| record_outcome(conn, delivery, &outcome))?;
+    }
+    Ok(due.len())
+}
+
+fn due_deliveries(conn: &Connection, now: i64) -> DbResult<Vec<DueDelivery>> {
+    let mut stmt = conn.prepare_cached(
+        "SELECT d.id, w.url, d.payload, d.attempts
+         FROM webhook_deliveries d
+         JOIN webhooks w ON w.id = d.webhook_id
+         WHERE d.status = ?1 AND d.next_attempt_at <= ?2 AND w.enabled = 1
+         ORDER BY d.next_attempt_at, d.id
+         LIMIT ?3",
+    )?;
+    let due = stmt.query_map(
+        params![DeliveryStatus::Pending.as_str(), now, DELIVERY_BATCH_SIZE],
+        |row| {
+            Ok(DueDelivery {
+                id: row.get(0)?,
+                url: row.get(1)?,
+                payload: row.get(2)?,
+                attempts: row.get(3)?,
+            })
+        },
+    )?;
+    Ok(due.collect::<Result<_, _>>()?)
+}
+
+fn record_outcome(
+    conn: &Connection,
+    delivery: &DueDelivery,
+    outcome: &Result<u16, String>,
+) -> DbResult<()> {
+    let now = chrono::Utc::now();
+    let attempts = delivery.attempts + 1;
+    let (response_status, error) = match outcome {
+        Ok(status) if (200..300).contains(status) => {
+            metrics::increment("webhooks.delivered");
+            conn.execute(
+                "UPDATE webhook_deliveries
+                 SET status = ?2, attempts = ?3, response_status = ?4, last_error = NULL,
+                     next_attempt_at = NULL, delivered_at = ?5
+                 WHERE id = ?1",
+                params![
+                    delivery.id,
+                    DeliveryStatus::Delivered.as_str(),
+                    attempts,
+                    status,
+                    now.to_rfc3339()
+                ],
+            )?;
+            debug!("Webhook delivery {} delivered ({})", delivery.id, status);
+            return Ok(());
+        }
+        Ok(status) => (Some(*status), format!("HTTP {}", status)),
+        Err(e) => (None, e.clone()),
+    };
+
+    let (status, next_attempt_at) = if attempts >= MAX_DELIVERY_ATTEMPTS {
+        metrics::increment("webhooks.failed");
+        warn!(
+            "Webhook delivery {} failed after {} attempts: {}",
+            delivery.id, attempts, error
+        );
+        (DeliveryStatus::Failed, None)
+    } else {
+        let delay = retry_delay(attempts).as_millis() as i64;
+        debug!(
+            "Webhook delivery {} attempt {} failed, retrying in {} ms: {}",
+            delivery.id, attempts, delay, error
+        );
+        (
+            DeliveryStatus::Pending,
+            Some(now.timestamp_millis() + delay),
+        )
+    };
+    conn.execute(
+        "UPDATE webhook_deliveries
+         SET status = ?2, attempts = ?3, response_status = ?4, last_error = ?5,
+             next_attempt_at = ?6
+         WHERE id = ?1",
+        params![
+            delivery.id,
+            status.as_str(),
+            attempts,
+            response_status,
+            error,
+            next_attempt_at
+        ],
+    )?;
+    Ok(())
+}
+
+/// Starts the background thread that sends due deliveries over HTTP.
+pub fn start_dispatcher(db: Arc<Database>) {
+    let spawned = thread::Builder::new()
+        .name("webhook-dispatcher".to_string())
+        .spawn(move || {
+            let transport = HttpTransport::new();
+            loop {
+                match deliver_due(&db, &transport) {
+                    Ok(0) => {}
+                    Ok(sent) => info!("Webhooks: sent {} deliveries", sent),
+                    Err(e) => error!("Webhooks: failed to send deliveries: {}", e),
+                }
+                thread::sleep(DISPATCH_INTERVAL);
+            }
+        });
+    if let Err(e) = spawned {
+        error!("Failed to start webhook dispatcher: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{WebhookDelivery, WebhookEvent, WebhookInput};
+    use crate::webhooks::{list_deliveries, save_webhook};
+    use std::sync::Mutex;
+
+    /// Answers requests with queued outcomes, recording the URLs posted to.
+    struct MockTransport {
+        outcomes: Mutex<Vec<Result<u16, String>>>,
+        urls: Mutex<Vec<String>>,
+    }
+
+    impl MockTransport {
+        fn new(mut outcomes: Vec<Result<u16, String>>) -> Self {
+            outcomes.reverse();
+            Self {
+                outcomes: Mutex::new(outcomes),
+                urls: Mutex::new(Vec::new()),
+            }
+        }
+    }
+
+    impl Transport for MockTransport {
+        fn post_json(&self, url: &str, _body: &str) -> Result<u16, String> {
+            self.urls.lock().unwrap().push(url.to_string());
+            self.outcomes.lock().unwrap().pop().unwrap()
+        }
+    }
+
+    fn setup() -> Database {
+        let db = Database::open_in_memory().unwrap();
+        db.init_schema().unwrap();
+        db.with_connection(|conn| {
+            let webhook = save_webhook(
+                conn,
+                &WebhookInput {
+                    id: None,
+                    url: "https://hooks.example.com/in".to_string(),
+                    filters: Default::default(),
+                    events: vec![WebhookEvent::Added],
+                    enabled: true,
+                },
+            )?
+            .unwrap();
+            conn.execute(
+                "INSERT INTO webhook_deliveries
+                     (webhook_id, conversation_id, event, payload, next_attempt_at, created_at)
+                 VALUES (?1, 'c1', 'conversation.added', '{}', 0, '2025-01-15T10:00:00Z')",
+                [webhook.id],
+            )?;
+            Ok(())
+        })
+        .unwrap();
+        db
+    }
+
+    fn delivery(db: &Database) -> WebhookDelivery {
+        db.with_connection(|conn| list_deliveries(conn, None, 1))
+            .unwrap()
+            .remove(0)
+    }
+
+    fn make_due(db: &Database) {
+        db.with_connection(|conn| {
+            conn.execute("UPDATE webhook_deliveries SET next_attempt_at = 0", [])?;
+            Ok(())
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn test_retry_delay_backs_off() {
+        assert_eq!(retry_delay(1), Duration::from_secs(30));
+        assert_eq!(retry_delay(3), Duration::from_secs(120));
+        assert_eq!(retry_delay(20), MAX_RETRY_DELAY);
+    }
+
+    #[test]
+    fn test_failed_delivery_is_retried_then_delivered() {
+        let db = setup();
+        let transport = MockTransport::new(vec![Err("connection refused".to_string()), Ok(204)]);
+
+        assert_eq!(deliver_due(&db, &transport).unwrap(), 1);
+        let retrying = delivery(&db);
+        assert_eq!(retrying.status, DeliveryStatus::Pending);
+        assert_eq!(retrying.attempts, 1);
+        assert_eq!(retrying.last_error.as_deref(), Some("connection refused"));
+
+        // Not due again until the backoff has passed
+        assert_eq!(deliver_due(&db, &transport).unwrap(), 0);
+        make_due(&db);
+        assert_eq!(deliver_due(&db, &transport).unwrap(), 1);
+
+        let delivered = delivery(&db);
+        assert_eq!(delivered.status, DeliveryStatus::Delivered);
+        assert_eq!(delivered.attempts, 2);
+        assert_eq!(delivered.response_status, Some(204));
+        assert_eq!(delivered.last_error, None);
+        assert!(delivered.delivered_at.is_some());
+        assert_eq!(
+            *transport.urls.lock().unwrap(),
+            vec!["https://hooks.example.com/in"; 2]
+        );
+    }
+
+    #[test]
+    fn test_delivery_fails_after_max_attempts() {
+        let db = setup();
+        let transport = MockTransport::new(vec![Ok(500); MAX_DELIVERY_ATTEMPTS as usize]);
+        for _ in 0..MAX_DELIVERY_ATTEMPTS {
+            make_due(&db);
+            assert_eq!(deliver_due(&db, &transport).unwrap(), 1);
+        }
+
+        let failed = delivery(&db);
+        assert_eq!(failed.status, DeliveryStatus::Failed);
+        assert_eq!(failed.attempts, MAX_DELIVERY_ATTEMPTS);
+        assert_eq!(failed.response_status, Some(500));
+        assert_eq!(failed.last_error.as_deref(), Some("HTTP 500"));
+
+        make_due(&db);
+        assert_eq!(deliver_due(&db, &transport).unwrap(), 0);
+    }
+}
@@ -0,0 +1,436 @@
+//! Webhooks notified when conversations are ingested.
+//!
+//! A webhook is a URL with conversation filters and the ingest events it
+//! wants. After each ingest run, [`enqueue_deliveries`] queues a delivery
+//! for every new or updated conversation matching an enabled webhook, with
+//! the payload built at that point. The dispatcher in [`delivery`] POSTs
+//! due deliveries and retries failed ones with exponential backoff;
+//! `webhook_deliveries` doubles as the delivery log.
+
+pub mod delivery;
+
+pub use delivery::{deliver_due, start_dispatcher, HttpTransport, Transport};
+
+use crate::db::filters::push_conversation_filters;
+use crate::db::operations::{current_tags, is_bookmarked};
+use crate::db::query::QueryBuilder;
+use crate::db::sqlite::DbResult;
+use crate::models::{
+    ConversationFilters, DeliveryStatus, IngestStats, Webhook, WebhookDelivery, WebhookEvent,
+    WebhookInput,
+};
+use rusqlite::types::Type;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+/// Most finished deliveries kept in the log; older ones are pruned as new
+/// ones are queued.
+pub const MAX_DELIVERY_LOG_ENTRIES: i64 = 1000;
+
+/// JSON body POSTed to a webhook.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WebhookPayload {
+    /// `conversation.added` or `conversation.updated`.
+    pub event: String,
+    pub webhook_id: i64,
+    /// One-line summary, so chat webhooks that post a `text` field (such as
+    /// Slack's incoming webhooks) can take the payload as is.
+    pub text: String,
+    pub conversation: PayloadConversation,
+}
+
+/// The ingested conversation, as of when the delivery was queued.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PayloadConversation {
+    pub id: String,
+    pub project_name: String,
+    pub project_path: String,
+    pub start_time: String,
+    pub last_time: String,
+    pub preview: String,
+    pub message_count: i64,
+    pub tags: Vec<String>,
+    pub bookmarked: bool,
+    /// Hash of the messages, for `has_changed_since`.
+    #[serde(default)]
+    pub content_hash: String,
+}
+
+const WEBHOOK_COLUMNS: &str = "id, url, filters, events, enabled, created_at";
+
+fn webhook_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<Webhook> {
+    let filters: String = row.get(2)?;
+    let events: String = row.get(3)?;
+    Ok(Webhook {
+        id: row.get(0)?,
+        url: row.get(1)?,
+        filters: serde_json::from_str(&filters)
+            .map_err(|e| rusqlite::Error::FromSqlConversionFailure(2, Type::Text, Box::new(e)))?,
+        events: events
+            .split(',')
+            .filter_map(WebhookEvent::from_db)
+            .collect(),
+        enabled: row.get::<_, i32>(4)? != 0,
+        created_at: row.get(5)?,
+    })
+}
+
+fn events_to_db(events: &[WebhookEvent]) -> String {
+    events
+        .iter()
+        .map(|event| event.as_str())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Lists webhooks in the order they were created.
+pub fn list_webhooks(conn: &Connection) -> DbResult<Vec<Webhook>> {
+    let mut stmt = conn.prepare_cached(&format!(
+        "SELECT {} FROM webhooks ORDER BY id",
+        WEBHOOK_COLUMNS
+    ))?;
+    let webhooks = stmt.query_map([], webhook_from_row)?;
+    Ok(webhooks.collect::<Result<_, _>>()?)
+}
+
+/// Reads a webhook.
+pub fn get_webhook(conn: &Connection, id: i64) -> DbResult<Option<Webhook>> {
+    Ok(conn
+        .query_row(
+            &format!("SELECT {} FROM webhooks WHERE id = ?1", WEBHOOK_COLUMNS),
+            [id],
+            webhook_from_row,
+        )
+        .optional()?)
+}
+
+/// Creates a webhook, or updates the one `input.id` names. Returns `None`
+/// if that webhook doesn't exist.
+///
+/// Updating a webhook doesn't change deliveries already queued for it.
+pub fn save_webhook(conn: &Connection, input: &WebhookInput) -> DbResult<Option<Webhook>> {
+    let filters = serde_json::to_string(&input.filters)
+        .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+    let events = events_to_db(&input.events);
+    let id = match input.id {
+        Some(id) => {
+            let updated = conn.execute(
+                "UPDATE webhooks SET url = ?2, filters = ?3, events = ?4, enabled = ?5
+                 WHERE id = ?1",
+                params![id, input.url, filters, events, input.enabled],
+            )?;
+            if updated == 0 {
+                return Ok(None);
+            }
+            id
+        }
+        None => {
+            conn.execute(
+                "INSERT INTO webhooks (url, filters, events, enabled, created_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![
+                    input.url,
+                    filters,
+                    events,
+                    input.enabled,
+                    chrono::Utc::now().to_rfc3339()
+                ],
+            )?;
+            conn.last_insert_rowid()
+        }
+    };
+    get_webhook(conn, id)
+}
+
+/// Deletes a webhook and its deliveries. Returns whether it existed.
+pub fn delete_webhook(conn: &Connection, id: i64) -> DbResult<bool> {
+    Ok(conn.execute("DELETE FROM webhooks WHERE id = ?1", [id])? > 0)
+}
+
+/// Lists deliveries, newest first, optionally only those of one webhook.
+pub fn list_deliveries(
+    conn: &Connection,
+    webhook_id: Option<i64>,
+    limit: u32,
+) -> DbResult<Vec<WebhookDelivery>> {
+    let mut stmt = conn.prepare_cached(
+        "SELECT id, webhook_id, conversation_id, event, status, attempts, response_status,
+                last_error, created_at, delivered_at
+         FROM webhook_deliveries
+         WHERE ?1 IS NULL OR webhook_id = ?1
+         ORDER BY id DESC
+         LIMIT ?2",
+    )?;
+    let deliveries = stmt.query_map(params![webhook_id, limit], |row| {
+        let event: String = row.get(3)?;
+        let status: String = row.get(4)?;
+        Ok(WebhookDelivery {
+            id: row.get(0)?,
+            webhook_id: row.get(1)?,
+            conversation_id: row.get(2)?,
+            event: WebhookEvent::from_db(&event).ok_or_else(|| {
+                rusqlite::Error::FromSqlConversionFailure(
+                    3,
+                    Type::Text,
+                    format!("unknown webhook event '{}'", event).into(),
+                )
+            })?,
+            status: DeliveryStatus::from_db(&status).ok_or_else(|| {
+                rusqlite::Error::FromSqlConversionFailure(
+                    4,
+                    Type::Text,
+                    format!("unknown delivery status '{}'", status).into(),
+                )
+            })?,
+            attempts: row.get(5)?,
+            response_status: row.get(6)?,
+            last_error: row.get(7)?,
+            created_at: row.get(8)?,
+            delivered_at: row.get(9)?,
+        })
+    })?;
+    Ok(deliveries.collect::<Result<_, _>>()?)
+}
+
+/// Queues deliveries of the conversations an ingest run added or updated
+/// to the enabled webhooks whose filters and events match them. Returns
+/// the number queued.
+///
+/// Deliveries are due immediately; the dispatcher sends them.
+pub fn enqueue_deliveries(conn: &Connection, stats: &IngestStats) -> DbResult<usize> {
+    if stats.added_ids.is_empty() && stats.updated_ids.is_empty() {
+        return Ok(0);
+    }
+
+    let now = chrono::Utc::now();
+    let mut queued = 0;
+    for webhook in list_webhooks(conn)?.into_iter().filter(|w| w.enabled) {
+        let batches = [
+            (WebhookEvent::Added, &stats.added_ids),
+            (WebhookEvent::Updated, &stats.updated_ids),
+        ];
+        for (event, ids) in batches {
+            if !webhook.events.contains(&event) {
+                continue;
+            }
+            for id in ids {
+                let Some(conversation) = matching_conversation(conn, &webhook.filters, id)? else {
+                    continue;
+                };
+                let payload = payload(webhook.id, event, conversation);
+                let payload = serde_json::to_string(&payload)
+                    .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+                conn.execute(
+                    "INSERT INTO webhook_deliveries
+                         (webhook_id, conversation_id, event, payload, status, next_attempt_at, created_at)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                    params![
+                        webhook.id,
+                        id,
+                        event.as_str(),
+                        payload,
+                        DeliveryStatus::Pending.as_str(),
+                        now.timestamp_millis(),
+                        now.to_rfc3339()
+                    ],
+                )?;
+                queued += 1;
+            }
+        }
+    }
+
+    if queued > 0 {
+        conn.execute(
+            "DELETE FROM webhook_deliveries
+             WHERE status != ?1 AND id <= last_insert_rowid() - ?2",
+            params![DeliveryStatus::Pending.as_str(), MAX_DELIVERY_LOG_ENTRIES],
+        )?;
+    }
+    Ok(queued)
+}
+
+/// Reads a conversation if it matches `filters`.
+fn matching_conversation(
+    conn: &Connection,
+    filters: &ConversationFilters,
+    id: &str,
+) -> DbResult<Option<PayloadConversation>> {
+    let mut query = QueryBuilder::new(
+        "SELECT c.id, c.project_name, c.project_path, c.start_time, c.last_time, c.preview,
+                c.message_count, c.content_hash
+         FROM conversations c WHERE 1=1",
+    );
+    query.push_bind(" AND c.id = ?", id.to_string());
+    push_conversation_filters(conn, filters, &mut query)?;
+    let conversation = query
+        .prepare(conn)?
+        .query_row(query.params().as_slice(), |row| {
+            Ok(PayloadConversation {
+                id: row.get(0)?,
+                project_name: row.get(1)?,
+                project_path: row.get(2)?,
+                start_time: row.get(3)?,
+                last_time: row.get(4)?,
+                preview: row.get(5)?,
+                message_count: row.get(6)?,
+                tags: Vec::new(),
+                bookmarked: false,
+                content_hash: row.get(7)?,
+            })
+        })
+        .optional()?;
+    let Some(mut conversation) = conversation else {
+        return Ok(None);
+    };
+    conversation.tags = current_tags(conn, id)?;
+    conversation.bookmarked = is_bookmarked(conn, id)?;
+    Ok(Some(conversation))
+}
+
+fn payload(
+    webhook_id: i64,
+    event: WebhookEvent,
+    conversation: PayloadConversation,
+) -> WebhookPayload {
+    let verb = match event {
+        WebhookEvent::Added => "New",
+        WebhookEvent::Updated => "Updated",
+    };
+    let mut text = format!("{} conversation in {}", verb, conversation.project_name);
+    if !conversation.preview.is_empty() {
+        text.push_str(": ");
+        text.push_str(&conversation.preview);
+    }
+    WebhookPayload {
+        event: event.as_str().to_string(),
+        webhook_id,
+        text,
+        conversation,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::sqlite::init_db;
+
+    fn setup() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("PRAGMA foreign_keys = ON;").unwrap();
+        init_db(&conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO conversations
+                 (id, project_path, project_name, start_time, last_time, preview, message_count,
+                  file_path, file_modified_at)
+             VALUES
+                 ('c1', '/work/api', 'api', '2025-01-15T10:00:00Z', '2025-01-15T10:05:00Z',
+                  'Fix the outage', 4, '/f1.jsonl', '2025-01-15T10:05:00Z'),
+                 ('c2', '/work/web', 'web', '2025-01-15T11:00:00Z', '2025-01-15T11:05:00Z',
+                  'Add a button', 2, '/f2.jsonl', '2025-01-15T11:05:00Z');
+             INSERT INTO conversation_tags (conversation_id, tag, created_at)
+             VALUES ('c1', 'incident', '2025-01-15T10:06:00Z');",
+        )
+        .unwrap();
+        conn
+    }
+
+    fn input(filters: ConversationFilters, events: Vec<WebhookEvent>) -> WebhookInput {
+        WebhookInput {
+            id: None,
+            url: "https://hooks.example.com/in".to_string(),
+            filters,
+            events,
+            enabled: true,
+        }
+    }
+
+    #[test]
+    fn test_save_and_delete_webhook() {
+        let conn = setup();
+        let created = save_webhook(&conn, &input(Default::default(), vec![WebhookEvent::Added]))
+            .unwrap()
+            .unwrap();
+        assert_eq!(created.events, vec![WebhookEvent::Added]);
+
+        let mut update = input(Default::default(), vec![WebhookEvent::Updated]);
+        update.id = Some(created.id);
+        update.enabled = false;
+        let updated = save_webhook(&conn, &update).unwrap().unwrap();
+        assert_eq!(updated.events, vec![WebhookEvent::Updated]);
+        assert!(!updated.enabled);
+        assert_eq!(updated.created_at, created.created_at);
+
+        update.id = Some(created.id + 1);
+        assert!(save_webhook(&conn, &update).unwrap().is_none());
+
+        assert!(delete_webhook(&conn, created.id).unwrap());
+        assert!(!delete_webhook(&conn, created.id).unwrap());
+        assert!(list_webhooks(&conn).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_enqueue_matches_filters_and_events() {
+        let conn = setup();
+        let incidents = ConversationFilters {
+            tags: Some(vec!["incident".to_string()]),
+            ..Default::default()
+        };
+        let tagged = save_webhook(&conn, &input(incidents, vec![WebhookEvent::Added]))
+            .unwrap()
+            .unwrap();
+        let all = save_webhook(
+            &conn,
+            &input(Default::default(), vec![WebhookEvent::Updated]),
+        )
+        .unwrap()
+        .unwrap();
+        let mut disabled = input(Default::default(), vec![WebhookEvent::Added]);
+        disabled.enabled = false;
+        save_webhook(&conn, &disabled).unwrap();
+
+        let stats = IngestStats {
+            added_ids: vec!["c1".to_string(), "c2".to_string()],
+            updated_ids: vec!["c2".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(enqueue_deliveries(&conn, &stats).unwrap(), 2);
+
+        let deliveries = list_deliveries(&conn, None, 10).unwrap();
+        let queued: Vec<(i64, &str, WebhookEvent)> = deliveries
+            .iter()
+            .map(|d| (d.webhook_id, d.conversation_id.as_str(), d.event))
+            .collect();
+        assert_eq!(
+            queued,
+            vec![
+                (all.id, "c2", WebhookEvent::Updated),
+                (tagged.id, "c1", WebhookEvent::Added),
+            ]
+        );
+        assert!(deliveries
+            .iter()
+            .all(|d| d.status == DeliveryStatus::Pending && d.attempts == 0));
+
+        let payload: String = conn
+            .query_row(
+                "SELECT payload FROM webhook_deliveries WHERE conversation_id = 'c1'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        let payload: WebhookPayload = serde_json::from_str(&payload).unwrap();
+        assert_eq!(payload.event, "conversation.added");
+        assert_eq!(payload.text, "New conversation in api: Fix the outage");
+        assert_eq!(payload.conversation.tags, vec!["incident"]);
+        assert_eq!(payload.conversation.message_count, 4);
+
+        assert_eq!(
+            list_deliveries(&conn, Some(tagged.id), 10).unwrap().len(),
+            1
+        );
+        delete_webhook(&conn, tagged.id).unwrap();
+        assert_eq!(list_deliveries(&conn, None, 10).unwrap().len(), 1);
+    }
+}
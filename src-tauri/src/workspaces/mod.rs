@@ -0,0 +1,242 @@
+//! Named workspaces.
+//!
+//! A workspace keeps what makes up a context, such as "incident review" or
+//! "rust learning": the conversation list's filters and order, pinned
+//! conversations, and the tabs that were open, so switching contexts is
+//! loading one. Workspaces reference conversations by ID without a foreign
+//! key; conversations deleted since a workspace was saved are dropped from
+//! it when it's loaded.
+
+use crate::db::sqlite::DbResult;
+use crate::models::{ConversationSort, Workspace, WorkspaceInput};
+use rusqlite::types::Type;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::HashSet;
+
+const WORKSPACE_COLUMNS: &str = "name, filters, sort, pinned, open_tabs, active_tab, created_at,
+     updated_at, last_opened_at";
+
+fn to_json<T: Serialize>(value: &T) -> rusqlite::Result<String> {
+    serde_json::to_string(value).map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))
+}
+
+fn from_json<T: DeserializeOwned>(row: &rusqlite::Row<'_>, column: usize) -> rusqlite::Result<T> {
+    let json: String = row.get(column)?;
+    serde_json::from_str(&json)
+        .map_err(|e| rusqlite::Error::FromSqlConversionFailure(column, Type::Text, Box::new(e)))
+}
+
+fn sort_to_db(sort: ConversationSort) -> &'static str {
+    match sort {
+        ConversationSort::Newest => "newest",
+        ConversationSort::Oldest => "oldest",
+    }
+}
+
+fn sort_from_db(sort: &str) -> ConversationSort {
+    match sort {
+        "oldest" => ConversationSort::Oldest,
+        _ => ConversationSort::Newest,
+    }
+}
+
+fn workspace_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<Workspace> {
+    let sort: String = row.get(2)?;
+    Ok(Workspace {
+        name: row.get(0)?,
+        filters: from_json(row, 1)?,
+        sort: sort_from_db(&sort),
+        pinned: from_json(row, 3)?,
+        open_tabs: from_json(row, 4)?,
+        active_tab: row.get(5)?,
+        created_at: row.get(6)?,
+        updated_at: row.get(7)?,
+        last_opened_at: row.get(8)?,
+    })
+}
+
+/// Lists workspaces, most recently loaded first; those never loaded come
+/// last, by name.
+pub fn list_workspaces(conn: &Connection) -> DbResult<Vec<Workspace>> {
+    let mut stmt = conn.prepare_cached(&format!(
+        "SELECT {} FROM workspaces
+         ORDER BY last_opened_at IS NULL, last_opened_at DESC, name",
+        WORKSPACE_COLUMNS
+    ))?;
+    let workspaces = stmt.query_map([], workspace_from_row)?;
+    Ok(workspaces.collect::<Result<_, _>>()?)
+}
+
+/// Reads a workspace by name (case-insensitively).
+pub fn get_workspace(conn: &Connection, name: &str) -> DbResult<Option<Workspace>> {
+    Ok(conn
+        .query_row(
+            &format!(
+                "SELECT {} FROM workspaces WHERE name = ?1",
+                WORKSPACE_COLUMNS
+            ),
+            [name],
+            workspace_from_row,
+        )
+        .optional()?)
+}
+
+/// Saves a workspace, replacing the one of the same name. A replaced
+/// workspace keeps when it was created and last loaded, and takes the
+/// new spelling of the name.
+pub fn save_workspace(conn: &Connection, input: &WorkspaceInput) -> DbResult<Workspace> {
+    let now = chrono::Utc::now().to_rfc3339();
+    conn.execute(
+        "INSERT INTO workspaces
+             (name, filters, sort, pinned, open_tabs, active_tab, created_at, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?7)
+         ON CONFLICT(name) DO UPDATE SET
+             name = excluded.name, filters = excluded.filters, sort = excluded.sort,
+             pinned = excluded.pinned, open_tabs = excluded.open_tabs,
+             active_tab = excluded.active_tab, updated_at = excluded.updated_at",
+        params![
+            input.name,
+            to_json(&input.filters)?,
+            sort_to_db(input.sort),
+            to_json(&input.pinned)?,
+            to_json(&input.open_tabs)?,
+            input.active_tab,
+            now
+        ],
+    )?;
+    Ok(conn.query_row(
+        &format!(
+            "SELECT {} FROM workspaces WHERE name = ?1",
+            WORKSPACE_COLUMNS
+        ),
+        [&input.name],
+        workspace_from_row,
+    )?)
+}
+
+/// Loads a workspace to switch to it: records when it was loaded and drops
+/// the conversations deleted since it was saved from its pins and tabs.
+/// Returns `None` if there's no workspace of that name.
+pub fn load_workspace(conn: &Connection, name: &str) -> DbResult<Option<Workspace>> {
+    let Some(mut workspace) = get_workspace(conn, name)? else {
+        return Ok(None);
+    };
+    let existing: HashSet<String> = {
+        let mut stmt = conn.prepare_cached("SELECT 1 FROM conversations WHERE id = ?1")?;
+        let mut existing = HashSet::new();
+        for id in workspace.pinned.iter().chain(&workspace.open_tabs) {
+            if stmt.exists([id])? {
+                existing.insert(id.clone());
+            }
+        }
+        existing
+    };
+    workspace.pinned.retain(|id| existing.contains(id));
+    workspace.open_tabs.retain(|id| existing.contains(id));
+    if let Some(ref active) = workspace.active_tab {
+        if !existing.contains(active) {
+            workspace.active_tab = workspace.open_tabs.first().cloned();
+        }
+    }
+    workspace.last_opened_at = Some(chrono::Utc::now().to_rfc3339());
+
+    conn.execute(
+        "UPDATE workspaces SET pinned = ?2, open_tabs = ?3, active_tab = ?4, last_opened_at = ?5
+         WHERE name = ?1",
+        params![
+            workspace.name,
+            to_json(&workspace.pinned)?,
+            to_json(&workspace.open_tabs)?,
+            workspace.active_tab,
+            workspace.last_opened_at
+        ],
+    )?;
+    Ok(Some(workspace))
+}
+
+/// Deletes a workspace. Returns whether it existed.
+pub fn delete_workspace(conn: &Connection, name: &str) -> DbResult<bool> {
+    Ok(conn.execute("DELETE FROM workspaces WHERE name = ?1", [name])? > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::sqlite::init_db;
+    use crate::models::ConversationFilters;
+
+    fn setup() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        init_db(&conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO conversations (id, project_path, project_name, start_time, last_time, file_path, file_modified_at)
+             VALUES ('c1', '/p', 'p', '2025-01-15T10:00:00Z', '2025-01-15T10:30:00Z', '/a.jsonl', ''),
+                    ('c2', '/p', 'p', '2025-01-16T10:00:00Z', '2025-01-16T10:30:00Z', '/b.jsonl', ''),
+                    ('c3', '/p', 'p', '2025-01-17T10:00:00Z', '2025-01-17T10:30:00Z', '/c.jsonl', '');",
+        )
+        .unwrap();
+        conn
+    }
+
+    fn input(name: &str) -> WorkspaceInput {
+        WorkspaceInput {
+            name: name.to_string(),
+            filters: ConversationFilters {
+                tags: Some(vec!["incident".to_string()]),
+                ..Default::default()
+            },
+            sort: ConversationSort::Oldest,
+            pinned: vec!["c1".to_string()],
+            open_tabs: vec!["c2".to_string(), "c3".to_string()],
+            active_tab: Some("c3".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_save_replaces_workspaces_by_name() {
+        let conn = setup();
+        let saved = save_workspace(&conn, &input("Incident review")).unwrap();
+        assert_eq!(saved.sort, ConversationSort::Oldest);
+        assert_eq!(saved.filters.tags, Some(vec!["incident".to_string()]));
+        assert_eq!(saved.open_tabs, vec!["c2", "c3"]);
+        assert_eq!(saved.last_opened_at, None);
+
+        let mut update = input("incident REVIEW");
+        update.sort = ConversationSort::Newest;
+        update.pinned.clear();
+        let updated = save_workspace(&conn, &update).unwrap();
+        assert_eq!(updated.name, "incident REVIEW");
+        assert_eq!(updated.sort, ConversationSort::Newest);
+        assert!(updated.pinned.is_empty());
+        assert_eq!(updated.created_at, saved.created_at);
+        assert_eq!(list_workspaces(&conn).unwrap().len(), 1);
+
+        assert!(delete_workspace(&conn, "Incident Review").unwrap());
+        assert!(!delete_workspace(&conn, "Incident Review").unwrap());
+    }
+
+    #[test]
+    fn test_load_drops_deleted_conversations() {
+        let conn = setup();
+        save_workspace(&conn, &input("Incident review")).unwrap();
+        save_workspace(&conn, &input("Rust learning")).unwrap();
+        assert_eq!(load_workspace(&conn, "nope").unwrap().map(|w| w.name), None);
+
+        conn.execute_batch("DELETE FROM conversations WHERE id IN ('c1', 'c3')")
+            .unwrap();
+        let loaded = load_workspace(&conn, "rust learning").unwrap().unwrap();
+        assert!(loaded.pinned.is_empty());
+        assert_eq!(loaded.open_tabs, vec!["c2"]);
+        assert_eq!(loaded.active_tab.as_deref(), Some("c2"));
+        assert!(loaded.last_opened_at.is_some());
+
+        // Loaded workspaces come first; others keep their conversations until loaded
+        let listed = list_workspaces(&conn).unwrap();
+        assert_eq!(listed[0].name, "Rust learning");
+        assert_eq!(listed[0].open_tabs, vec!["c2"]);
+        assert_eq!(listed[1].name, "Incident review");
+        assert_eq!(listed[1].open_tabs, vec!["c2", "c3"]);
+    }
+}
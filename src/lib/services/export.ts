@@ -55,6 +55,16 @@ function sanitizeFilename(name: string): string {
   return sanitized || "conversation"; // Fallback if empty
 }
 
+/**
+ * Title of a file view block: `path:start-end`.
+ */
+export function fileViewTitle(block: ContentBlock): string {
+  if (!block.fileView) return "File";
+  const { path, startLine } = block.fileView;
+  const endLine = startLine + Math.max(block.content.split("\n").length - 1, 0);
+  return `${path}:${startLine}-${endLine}`;
+}
+
 /**
  * Convert a content block to Markdown.
  */
@@ -74,6 +84,20 @@ function contentBlockToMarkdown(block: ContentBlock): string {
     case "tool_result":
       return `> **Tool Result${block.toolName ? `: ${block.toolName}` : ""}**\n>\n> \`\`\`\n> ${block.content.split("\n").join("\n> ")}\n> \`\`\``;
 
+    case "terminal": {
+      const exitCode = block.terminal?.exitCode;
+      const status = exitCode !== undefined && exitCode !== 0 ? `\n*Exit code ${exitCode}*` : "";
+      return `\`\`\`console\n${block.content}\n\`\`\`${status}`;
+    }
+
+    case "file_view": {
+      const lang = block.language || "";
+      return `\`${fileViewTitle(block)}\`\n\n\`\`\`${lang}\n${block.content}\n\`\`\``;
+    }
+
+    case "image":
+      return `> *[Image attachment ${block.content}]*`;
+
     default:
       return block.content;
   }
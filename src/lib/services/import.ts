@@ -0,0 +1,49 @@
+/**
+ * Import service for session files outside ~/.claude/projects/.
+ *
+ * Uses Tauri's dialog plugin to pick a session file or a folder of them,
+ * then ingests it through the import_path command.
+ */
+
+import { open } from "@tauri-apps/plugin-dialog";
+import type { IngestStats } from "$lib/types";
+import { importPath } from "./tauri";
+
+/**
+ * What the picker selects: a single session file or a folder to search.
+ */
+export type ImportKind = "file" | "folder";
+
+/**
+ * Let the user pick a session file or folder and import it.
+ *
+ * @param kind - Whether to pick a file or a folder
+ * @returns The import's stats, or null if the picker was cancelled
+ * @throws TauriError if the import fails
+ */
+export async function importFromDialog(kind: ImportKind): Promise<IngestStats | null> {
+  const selected = await open(
+    kind === "folder"
+      ? { directory: true, title: "Import conversations from folder" }
+      : {
+          title: "Import conversation file",
+          filters: [{ name: "Claude Code sessions", extensions: ["jsonl", "gz"] }],
+        }
+  );
+  if (typeof selected !== "string") {
+    return null;
+  }
+  return importPath(selected);
+}
+
+/**
+ * Describe an import's outcome for a toast.
+ */
+export function describeImport(stats: IngestStats): string {
+  const imported = stats.newConversations + stats.updatedConversations;
+  if (stats.processedFiles === 0 && stats.failedFiles === 0) {
+    return "No new conversations to import";
+  }
+  const summary = `Imported ${imported} conversation${imported === 1 ? "" : "s"}`;
+  return stats.failedFiles > 0 ? `${summary} (${stats.failedFiles} files failed)` : summary;
+}
@@ -5,9 +5,14 @@ export {
   isTauriAvailable,
   getConversations,
   getConversation,
+  getAttachment,
+  getBlockCopyText,
   getProjects,
   searchConversations,
+  beginSearch,
+  updateQuery,
   TauriError,
   NotFoundError,
+  ValidationError,
   NetworkError,
 } from "./tauri";
@@ -6,13 +6,74 @@
  */
 
 import type {
+  ActionInfo,
+  AnomalyOptions,
+  AppliedOperation,
+  AppMetrics,
+  AppSettings,
+  ArtifactExport,
+  Attachment,
+  CopyMode,
+  AuditEntry,
+  BenchmarkOptions,
+  BenchmarkReport,
+  BulkOperationResult,
+  CommandErrorPayload,
   Conversation,
+  ConversationSort,
   ConversationSummary,
+  ConversationWindow,
+  ConversationWithHighlights,
+  Message,
+  MessageFilterOptions,
+  MessageRange,
+  MetadataField,
+  ModelStats,
   ConversationFilters,
+  ExportFormat,
+  ExportTemplate,
+  FilterCounts,
+  HealthReport,
+  IngestProgress,
+  DropImportResult,
+  IngestStats,
+  IssueLink,
+  LanguageStats,
+  ListDirection,
+  LogEntry,
+  LogLevel,
+  OpenConversationEvent,
+  ProjectComparison,
   ProjectInfo,
+  Prompt,
+  PromptExportFormat,
+  PromptInput,
+  QuestionMatch,
+  QuickOpenResult,
+  ReadPosition,
+  Reminder,
+  ReplayEvent,
+  SchemaObservation,
+  SearchMaintenanceReport,
+  SearchResponse,
   SearchResult,
-  ConversationsUpdatedEvent,
+  ShareBundle,
+  SlackShare,
+  StaticSiteExport,
+  SynonymEntry,
+  ConversationsChangedEvent,
   TagInfo,
+  TagSuggestion,
+  TagsChangedEvent,
+  TopTerm,
+  UsageAnomaly,
+  UsageStatsOptions,
+  WatcherStatusEvent,
+  Webhook,
+  WebhookDelivery,
+  WebhookInput,
+  Workspace,
+  WorkspaceInput,
 } from "$lib/types";
 
 /**
@@ -21,7 +82,8 @@ import type {
 export class TauriError extends Error {
   constructor(
     message: string,
-    public readonly code: string
+    public readonly code: string,
+    public readonly details: Record<string, unknown> | null = null
   ) {
     super(message);
     this.name = "TauriError";
@@ -35,6 +97,17 @@ export class NotFoundError extends TauriError {
   }
 }
 
+export class ValidationError extends TauriError {
+  constructor(
+    message: string,
+    public readonly field: string,
+    public readonly reason: string
+  ) {
+    super(message, "VALIDATION", { field, reason });
+    this.name = "ValidationError";
+  }
+}
+
 export class NetworkError extends TauriError {
   constructor(message: string) {
     super(message, "NETWORK_ERROR");
@@ -64,6 +137,26 @@ async function getInvoke(): Promise<typeof import("@tauri-apps/api/core").invoke
   return invoke;
 }
 
+/**
+ * Label of the current window, which keys its undo history.
+ */
+async function currentWindowLabel(): Promise<string> {
+  const { getCurrentWindow } = await import("@tauri-apps/api/window");
+  return getCurrentWindow().label;
+}
+
+/**
+ * Check if a rejected invoke carries a structured backend error.
+ */
+function isCommandErrorPayload(error: unknown): error is CommandErrorPayload {
+  return (
+    typeof error === "object" &&
+    error !== null &&
+    typeof (error as CommandErrorPayload).code === "string" &&
+    typeof (error as CommandErrorPayload).message === "string"
+  );
+}
+
 /**
  * Wrap Tauri errors in typed error classes.
  */
@@ -72,6 +165,24 @@ function wrapError(error: unknown, operation: string): TauriError {
     return error;
   }
 
+  if (isCommandErrorPayload(error)) {
+    const { code, message, details } = error;
+    if (code === "not_found") {
+      return new NotFoundError(`${operation}: ${message}`);
+    }
+    if (code === "network") {
+      return new NetworkError(`${operation}: ${message}`);
+    }
+    if (code === "validation" && details) {
+      return new ValidationError(
+        `${operation}: ${message}`,
+        String(details.field),
+        String(details.reason)
+      );
+    }
+    return new TauriError(`${operation} failed: ${message}`, code.toUpperCase(), details);
+  }
+
   const message = error instanceof Error ? error.message : String(error);
 
   if (message.includes("not found") || message.includes("NotFound")) {
@@ -108,11 +219,83 @@ export async function getConversations(
   }
 }
 
+/**
+ * Get a range of the conversation list, for virtual scrolling.
+ *
+ * @param start - Index of the first conversation
+ * @param end - Index after the last conversation (at most 1000 after start)
+ * @param filters - Optional filters for project, date range, bookmarks, tags
+ * @param sort - Optional order (default: newest first)
+ * @returns The conversations in the range and the length of the list
+ * @throws ValidationError if the range or filters are invalid
+ * @throws TauriError if operation fails
+ */
+export async function getConversationSummariesWindow(
+  start: number,
+  end: number,
+  filters?: ConversationFilters,
+  sort?: ConversationSort
+): Promise<ConversationWindow> {
+  const invoke = await getInvoke();
+
+  if (!invoke) {
+    throw new TauriError("Not in Tauri environment", "NOT_AVAILABLE");
+  }
+
+  try {
+    return await invoke<ConversationWindow>("get_conversation_summaries_window", {
+      filters: filters && Object.keys(filters).length > 0 ? filters : null,
+      sort: sort ?? null,
+      start,
+      end,
+      windowLabel: await currentWindowLabel(),
+    });
+  } catch (error) {
+    throw wrapError(error, "getConversationSummariesWindow");
+  }
+}
+
+/**
+ * Get the conversation before or after one in the list the filters and
+ * sort describe, for keyboard navigation.
+ *
+ * @param id - Conversation to move from
+ * @param direction - Whether to move to the next or previous conversation
+ * @param filters - Optional filters for project, date range, bookmarks, tags
+ * @param sort - Optional order (default: newest first)
+ * @returns The adjacent conversation, or null at the end of the list
+ * @throws NotFoundError if the conversation is not found
+ * @throws TauriError if operation fails
+ */
+export async function getAdjacentConversation(
+  id: string,
+  direction: ListDirection,
+  filters?: ConversationFilters,
+  sort?: ConversationSort
+): Promise<ConversationSummary | null> {
+  const invoke = await getInvoke();
+
+  if (!invoke) {
+    throw new TauriError("Not in Tauri environment", "NOT_AVAILABLE");
+  }
+
+  try {
+    return await invoke<ConversationSummary | null>("get_adjacent_conversation", {
+      id,
+      filters: filters && Object.keys(filters).length > 0 ? filters : null,
+      sort: sort ?? null,
+      direction,
+    });
+  } catch (error) {
+    throw wrapError(error, "getAdjacentConversation");
+  }
+}
+
 /**
  * Get full conversation details by ID.
  *
  * @param id - Conversation ID
- * @returns Full conversation with all messages
+ * @returns Full conversation with all messages, and where reading left off
  * @throws NotFoundError if conversation not found
  * @throws TauriError if operation fails
  */
@@ -132,112 +315,204 @@ export async function getConversation(id: string): Promise<Conversation> {
 }
 
 /**
- * Get list of all projects with conversation counts.
+ * Get a conversation stored partially because its session file is large,
+ * with its tool results in full. It's loaded in full from then on.
  *
- * @returns Array of project info sorted alphabetically by name
- * @throws TauriError if operation fails
+ * @param id - Conversation ID
+ * @returns The full conversation
+ * @throws NotFoundError if conversation not found
+ * @throws TauriError if the operation fails
  */
-export async function getProjects(): Promise<ProjectInfo[]> {
+export async function loadFull(id: string): Promise<Conversation> {
   const invoke = await getInvoke();
 
   if (!invoke) {
-    console.log("[tauri service] Not in Tauri environment, returning empty array");
-    return [];
+    throw new TauriError("Not in Tauri environment", "NOT_AVAILABLE");
   }
 
   try {
-    const result = await invoke<ProjectInfo[]>("get_projects");
+    const result = await invoke<Conversation>("load_full", { id });
     return result;
   } catch (error) {
-    throw wrapError(error, "getProjects");
+    throw wrapError(error, "loadFull");
   }
 }
 
 /**
- * Search conversations by query with optional filters.
+ * Check whether a conversation changed since its content hash was taken,
+ * without loading it.
  *
- * @param query - Search query string (min 2 characters)
- * @param filters - Optional filters for project, date range
- * @returns Array of search results with snippets and match counts
- * @throws TauriError if operation fails
+ * @param id - Conversation ID
+ * @param hash - The conversation's contentHash as last seen
+ * @returns Whether the conversation's messages changed
+ * @throws NotFoundError if conversation not found
+ * @throws TauriError if the operation fails
  */
-export async function searchConversations(
-  query: string,
-  filters?: ConversationFilters
-): Promise<SearchResult[]> {
+export async function hasChangedSince(id: string, hash: string): Promise<boolean> {
   const invoke = await getInvoke();
 
   if (!invoke) {
-    console.log("[tauri service] Not in Tauri environment, returning empty array");
-    return [];
+    throw new TauriError("Not in Tauri environment", "NOT_AVAILABLE");
   }
 
-  // Enforce minimum query length
-  if (query.length < 2) {
-    return [];
+  try {
+    const result = await invoke<boolean>("has_changed_since", { id, hash });
+    return result;
+  } catch (error) {
+    throw wrapError(error, "hasChangedSince");
+  }
+}
+
+/**
+ * Get a conversation opened from search results, with the positions of the
+ * query's matches for highlighting and next/previous navigation.
+ *
+ * @param id - Conversation ID
+ * @param query - The search query
+ * @returns The conversation and its matches in reading order
+ * @throws NotFoundError if conversation not found
+ * @throws TauriError if the query is empty or the operation fails
+ */
+export async function getConversationWithHighlights(
+  id: string,
+  query: string
+): Promise<ConversationWithHighlights> {
+  const invoke = await getInvoke();
+
+  if (!invoke) {
+    throw new TauriError("Not in Tauri environment", "NOT_AVAILABLE");
   }
 
   try {
-    const result = await invoke<SearchResult[]>("search_conversations", {
+    return await invoke<ConversationWithHighlights>("get_conversation_with_highlights", {
+      id,
       query,
-      filters: filters && Object.keys(filters).length > 0 ? filters : null,
     });
-    return result;
   } catch (error) {
-    throw wrapError(error, "searchConversations");
+    throw wrapError(error, "getConversationWithHighlights");
   }
 }
 
 /**
- * Unlisten function type from Tauri events API.
+ * Replay a conversation as it unfolded: messages arrive on `onEvent` one
+ * by one, with the session's pauses divided by `speed` (long pauses are
+ * shortened). Meta messages are left out.
+ *
+ * @param id - Conversation ID
+ * @param onEvent - Called with the replay's events, ending with `finished`
+ * @param speed - How many times faster than the session (default: 1, at most 1000)
+ * @returns ID of the replay, for stopReplay
+ * @throws NotFoundError if conversation not found
+ * @throws TauriError if the speed is invalid or the operation fails
  */
-export type UnlistenFn = () => void;
+export async function replayConversation(
+  id: string,
+  onEvent: (event: ReplayEvent) => void,
+  speed?: number
+): Promise<string> {
+  const invoke = await getInvoke();
+
+  if (!invoke) {
+    throw new TauriError("Not in Tauri environment", "NOT_AVAILABLE");
+  }
+
+  const { Channel } = await import("@tauri-apps/api/core");
+  const channel = new Channel<ReplayEvent>();
+  channel.onmessage = onEvent;
+
+  try {
+    return await invoke<string>("replay_conversation", {
+      id,
+      speed: speed ?? null,
+      onEvent: channel,
+      windowLabel: await currentWindowLabel(),
+    });
+  } catch (error) {
+    throw wrapError(error, "replayConversation");
+  }
+}
 
 /**
- * Event name for conversations updated events from backend.
+ * Stop a replay; it sends a final `finished` event with `stopped: true`.
+ *
+ * @param replayId - ID returned by replayConversation
+ * @returns Whether the replay was still running
  */
-export const CONVERSATIONS_UPDATED_EVENT = "conversations-updated";
+export async function stopReplay(replayId: string): Promise<boolean> {
+  const invoke = await getInvoke();
+
+  if (!invoke) {
+    throw new TauriError("Not in Tauri environment", "NOT_AVAILABLE");
+  }
+
+  try {
+    return await invoke<boolean>("stop_replay", {
+      replayId,
+      windowLabel: await currentWindowLabel(),
+    });
+  } catch (error) {
+    throw wrapError(error, "stopReplay");
+  }
+}
 
 /**
- * Listen for conversations-updated events from the backend file watcher.
- * Returns an unlisten function to clean up the listener.
+ * Get the conversation open in the current window, as recorded by
+ * setSelection.
  *
- * @param callback - Function to call when conversations are updated
- * @returns Promise resolving to unlisten function, or null if not in Tauri
+ * @returns ID of the conversation, or null if none is open
  */
-export async function listenToConversationsUpdated(
-  callback: (event: ConversationsUpdatedEvent) => void
-): Promise<UnlistenFn | null> {
-  if (!isTauriAvailable()) {
-    console.log("[tauri service] Not in Tauri environment, skipping event listener");
-    return null;
+export async function getSelection(): Promise<string | null> {
+  const invoke = await getInvoke();
+
+  if (!invoke) {
+    throw new TauriError("Not in Tauri environment", "NOT_AVAILABLE");
   }
 
   try {
-    const { listen } = await import("@tauri-apps/api/event");
-    const unlisten = await listen<ConversationsUpdatedEvent>(
-      CONVERSATIONS_UPDATED_EVENT,
-      (event) => {
-        console.log("[tauri service] Received conversations-updated event:", event.payload);
-        callback(event.payload);
-      }
-    );
-    console.log("[tauri service] Listening for conversations-updated events");
-    return unlisten;
+    return await invoke<string | null>("get_selection", {
+      windowLabel: await currentWindowLabel(),
+    });
   } catch (error) {
-    console.error("[tauri service] Failed to listen for conversations-updated:", error);
-    return null;
+    throw wrapError(error, "getSelection");
   }
 }
 
 /**
- * Toggle the bookmark status of a conversation.
+ * Record the conversation open in the current window, so each window
+ * restores its own selection.
  *
- * @param conversationId - ID of the conversation to toggle
- * @returns The new bookmark status (true if now bookmarked, false if unbookmarked)
+ * @param conversationId - ID of the conversation, or null when none is open
+ */
+export async function setSelection(conversationId: string | null): Promise<void> {
+  const invoke = await getInvoke();
+
+  if (!invoke) {
+    throw new TauriError("Not in Tauri environment", "NOT_AVAILABLE");
+  }
+
+  try {
+    await invoke<void>("set_selection", {
+      conversationId,
+      windowLabel: await currentWindowLabel(),
+    });
+  } catch (error) {
+    throw wrapError(error, "setSelection");
+  }
+}
+
+/**
+ * Get a conversation's messages, optionally filtered by role or block type.
+ *
+ * @param conversationId - Conversation to load
+ * @param options - Optional role and block-type filters
+ * @returns Matching messages with their original ordinals
+ * @throws NotFoundError if conversation not found
  * @throws TauriError if operation fails
  */
-export async function toggleBookmark(conversationId: string): Promise<boolean> {
+export async function getConversationMessages(
+  conversationId: string,
+  options?: MessageFilterOptions
+): Promise<Message[]> {
   const invoke = await getInvoke();
 
   if (!invoke) {
@@ -245,23 +520,31 @@ export async function toggleBookmark(conversationId: string): Promise<boolean> {
   }
 
   try {
-    const result = await invoke<boolean>("toggle_bookmark", { conversationId });
-    return result;
+    return await invoke<Message[]>("get_conversation_messages", {
+      conversationId,
+      options: options ?? null,
+    });
   } catch (error) {
-    throw wrapError(error, "toggleBookmark");
+    throw wrapError(error, "getConversationMessages");
   }
 }
 
 /**
- * Set tags for a conversation.
- * Replaces all existing tags with the provided list.
+ * Render a conversation for export on the backend.
  *
- * @param conversationId - ID of the conversation
- * @param tags - Array of tag strings (will be normalized: lowercase, trimmed, deduplicated)
- * @returns The normalized, sorted array of tags that were set
+ * @param id - Conversation to export
+ * @param format - Export format (default: full Markdown)
+ * @param range - Messages to export (default: all of them)
+ * @returns The rendered document
+ * @throws NotFoundError if conversation not found
+ * @throws ValidationError if a message ID is not in the conversation
  * @throws TauriError if operation fails
  */
-export async function setTags(conversationId: string, tags: string[]): Promise<string[]> {
+export async function exportConversationAs(
+  id: string,
+  format: ExportFormat = "markdown",
+  range?: MessageRange
+): Promise<string> {
   const invoke = await getInvoke();
 
   if (!invoke) {
@@ -269,32 +552,2267 @@ export async function setTags(conversationId: string, tags: string[]): Promise<s
   }
 
   try {
-    const result = await invoke<string[]>("set_tags", { conversationId, tags });
-    return result;
+    return await invoke<string>("export_conversation", {
+      id,
+      format,
+      fromMessageId: range?.fromMessageId ?? null,
+      toMessageId: range?.toMessageId ?? null,
+    });
   } catch (error) {
-    throw wrapError(error, "setTags");
+    throw wrapError(error, "exportConversationAs");
   }
 }
 
 /**
- * Get all unique tags across all conversations with usage counts.
+ * Export a conversation as numbered, cross-linked files in a directory.
  *
- * @returns Array of tag info sorted alphabetically by tag name
+ * @param id - Conversation to export
+ * @param destDir - Directory to write the files into
+ * @param format - Export format (default: full Markdown)
+ * @param maxPageBytes - Target page size (backend default: 1 MB)
+ * @param range - Messages to export (default: all of them)
+ * @param previewImage - Also write the conversation's preview card as a PNG
+ * @returns Paths of the written files, in page order, then the preview card
+ * @throws NotFoundError if conversation not found
+ * @throws ValidationError if a message ID is not in the conversation
  * @throws TauriError if operation fails
  */
-export async function getAllTags(): Promise<TagInfo[]> {
+export async function exportConversationPages(
+  id: string,
+  destDir: string,
+  format: ExportFormat = "markdown",
+  maxPageBytes?: number,
+  range?: MessageRange,
+  previewImage = false
+): Promise<string[]> {
   const invoke = await getInvoke();
 
   if (!invoke) {
-    console.log("[tauri service] Not in Tauri environment, returning empty array");
-    return [];
+    throw new TauriError("Not in Tauri environment", "NOT_AVAILABLE");
   }
 
   try {
-    const result = await invoke<TagInfo[]>("get_all_tags");
-    return result;
+    return await invoke<string[]>("export_conversation_pages", {
+      id,
+      format,
+      destDir,
+      maxPageBytes: maxPageBytes ?? null,
+      fromMessageId: range?.fromMessageId ?? null,
+      toMessageId: range?.toMessageId ?? null,
+      previewImage,
+    });
   } catch (error) {
-    throw wrapError(error, "getAllTags");
+    throw wrapError(error, "exportConversationPages");
+  }
+}
+
+/**
+ * Render the preview card of a conversation, a PNG of its first question
+ * and answer for link previews.
+ *
+ * @param id - Conversation to render
+ * @returns The PNG file, 1200x630 pixels
+ * @throws NotFoundError if conversation not found
+ * @throws TauriError if operation fails
+ */
+export async function renderPreviewImage(id: string): Promise<Uint8Array> {
+  const invoke = await getInvoke();
+
+  if (!invoke) {
+    throw new TauriError("Not in Tauri environment", "NOT_AVAILABLE");
+  }
+
+  try {
+    return new Uint8Array(await invoke<number[]>("render_preview_image", { id }));
+  } catch (error) {
+    throw wrapError(error, "renderPreviewImage");
+  }
+}
+
+/**
+ * List the user-editable export templates.
+ *
+ * @returns Templates by name, with the files to edit
+ * @throws TauriError if operation fails
+ */
+export async function listExportTemplates(): Promise<ExportTemplate[]> {
+  const invoke = await getInvoke();
+
+  if (!invoke) {
+    throw new TauriError("Not in Tauri environment", "NOT_AVAILABLE");
+  }
+
+  try {
+    return await invoke<ExportTemplate[]>("list_export_templates");
+  } catch (error) {
+    throw wrapError(error, "listExportTemplates");
+  }
+}
+
+/**
+ * Render a conversation with an export template.
+ *
+ * @param id - Conversation to export
+ * @param template - Name of the template
+ * @param range - Messages to export (default: all of them)
+ * @returns The rendered document
+ * @throws NotFoundError if the conversation or template is not found
+ * @throws ValidationError if the template fails to render
+ * @throws TauriError if operation fails
+ */
+export async function exportWithTemplate(
+  id: string,
+  template: string,
+  range?: MessageRange
+): Promise<string> {
+  const invoke = await getInvoke();
+
+  if (!invoke) {
+    throw new TauriError("Not in Tauri environment", "NOT_AVAILABLE");
+  }
+
+  try {
+    return await invoke<string>("export_with_template", {
+      id,
+      template,
+      fromMessageId: range?.fromMessageId ?? null,
+      toMessageId: range?.toMessageId ?? null,
+    });
+  } catch (error) {
+    throw wrapError(error, "exportWithTemplate");
+  }
+}
+
+/**
+ * Export several conversations into a directory, one file each, plus an
+ * index file linking to them.
+ *
+ * @param ids - Conversations to export (at most 1000), in index order
+ * @param destDir - Directory to write the files into
+ * @param format - Export format (default: full Markdown)
+ * @returns Paths of the written files: the conversations, then the index
+ * @throws NotFoundError if a conversation is not found (nothing is written)
+ * @throws ValidationError if ids is empty or too long, or destDir is not a directory
+ * @throws TauriError if operation fails
+ */
+export async function exportConversations(
+  ids: string[],
+  destDir: string,
+  format: ExportFormat = "markdown"
+): Promise<string[]> {
+  const invoke = await getInvoke();
+
+  if (!invoke) {
+    throw new TauriError("Not in Tauri environment", "NOT_AVAILABLE");
+  }
+
+  try {
+    return await invoke<string[]>("export_conversations", { ids, format, destDir });
+  } catch (error) {
+    throw wrapError(error, "exportConversations");
+  }
+}
+
+/**
+ * Export the conversations matching filters as a static HTML site with
+ * client-side search, e.g. for publishing an internal knowledge archive.
+ *
+ * @param destDir - Directory to write the site into
+ * @param filters - Optional filters for project, date range, bookmarks, tags
+ * @returns Path of the index page and number of conversations
+ * @throws ValidationError if the filters are invalid or destDir is not a directory
+ * @throws TauriError if operation fails
+ */
+export async function exportStaticSite(
+  destDir: string,
+  filters?: ConversationFilters
+): Promise<StaticSiteExport> {
+  const invoke = await getInvoke();
+
+  if (!invoke) {
+    throw new TauriError("Not in Tauri environment", "NOT_AVAILABLE");
+  }
+
+  try {
+    return await invoke<StaticSiteExport>("export_static_site", {
+      filters: filters && Object.keys(filters).length > 0 ? filters : null,
+      destDir,
+    });
+  } catch (error) {
+    throw wrapError(error, "exportStaticSite");
+  }
+}
+
+/**
+ * Extract the files a conversation wrote with Write and Edit tool calls, as
+ * it left them, into a directory or a ZIP archive. Contents are rebuilt
+ * from the tool calls, so they're recovered even if the files changed since.
+ *
+ * @param id - Conversation to extract from
+ * @param destDir - Directory to write the files, or the archive, into
+ * @param zip - Write a ZIP archive instead of files
+ * @returns Where the files went, which were written, and which were skipped
+ * @throws ValidationError if destDir is not a directory
+ * @throws NotFoundError if the conversation does not exist
+ * @throws TauriError if the conversation wrote no files or a file cannot be written
+ */
+export async function extractArtifacts(
+  id: string,
+  destDir: string,
+  zip = false
+): Promise<ArtifactExport> {
+  const invoke = await getInvoke();
+
+  if (!invoke) {
+    throw new TauriError("Not in Tauri environment", "NOT_AVAILABLE");
+  }
+
+  try {
+    return await invoke<ArtifactExport>("extract_artifacts", { id, destDir, zip });
+  } catch (error) {
+    throw wrapError(error, "extractArtifacts");
+  }
+}
+
+/**
+ * Share a conversation to a Slack channel: a trimmed Markdown summary as a
+ * message, with the full transcript attached in its thread. Uses the Slack
+ * token from settings.
+ *
+ * @param id - Conversation to share
+ * @param channel - Channel name (with or without #) or ID
+ * @returns The channel, message, and file posted
+ * @throws ValidationError if the channel is empty
+ * @throws NotFoundError if the conversation or channel does not exist
+ * @throws NetworkError if Slack can't be reached or refuses the request
+ */
+export async function shareToSlack(id: string, channel: string): Promise<SlackShare> {
+  const invoke = await getInvoke();
+
+  if (!invoke) {
+    throw new TauriError("Not in Tauri environment", "NOT_AVAILABLE");
+  }
+
+  try {
+    return await invoke<SlackShare>("share_to_slack", { id, channel });
+  } catch (error) {
+    throw wrapError(error, "shareToSlack");
+  }
+}
+
+/**
+ * Create an encrypted share bundle of a conversation: one HTML file that
+ * opens in a browser with the returned passphrase until it expires.
+ *
+ * @param id - Conversation to share
+ * @param ttlHours - Hours the bundle opens for (backend default: 24, at most 30 days)
+ * @returns The bundle's file and link, with its passphrase, which is only returned here
+ * @throws ValidationError if ttlHours is zero or over 30 days
+ * @throws NotFoundError if the conversation does not exist
+ * @throws TauriError if operation fails
+ */
+export async function createShareBundle(id: string, ttlHours?: number): Promise<ShareBundle> {
+  const invoke = await getInvoke();
+
+  if (!invoke) {
+    throw new TauriError("Not in Tauri environment", "NOT_AVAILABLE");
+  }
+
+  try {
+    return await invoke<ShareBundle>("create_share_bundle", { id, ttlHours: ttlHours ?? null });
+  } catch (error) {
+    throw wrapError(error, "createShareBundle");
+  }
+}
+
+/**
+ * List the share bundles that weren't revoked or deleted as expired.
+ *
+ * @returns Bundles, newest first, without passphrases
+ * @throws TauriError if operation fails
+ */
+export async function listShareBundles(): Promise<ShareBundle[]> {
+  const invoke = await getInvoke();
+
+  if (!invoke) {
+    throw new TauriError("Not in Tauri environment", "NOT_AVAILABLE");
+  }
+
+  try {
+    return await invoke<ShareBundle[]>("list_share_bundles");
+  } catch (error) {
+    throw wrapError(error, "listShareBundles");
+  }
+}
+
+/**
+ * Revoke a share bundle before it expires, deleting its file.
+ *
+ * @param shareId - Bundle to revoke
+ * @throws NotFoundError if no bundle has the ID, e.g. it expired
+ * @throws TauriError if operation fails
+ */
+export async function revokeShareBundle(shareId: string): Promise<void> {
+  const invoke = await getInvoke();
+
+  if (!invoke) {
+    throw new TauriError("Not in Tauri environment", "NOT_AVAILABLE");
+  }
+
+  try {
+    await invoke("revoke_share_bundle", { shareId });
+  } catch (error) {
+    throw wrapError(error, "revokeShareBundle");
+  }
+}
+
+/**
+ * Link a conversation to a Jira or Linear issue by the issue's URL. Issues
+ * mentioned by key in a conversation are linked at ingest without this.
+ *
+ * @param conversationId - Conversation to link
+ * @param url - Issue URL containing its key, e.g. https://acme.atlassian.net/browse/ABC-123
+ * @returns The issue linked
+ * @throws ValidationError if the URL has no issue key
+ * @throws NotFoundError if the conversation does not exist
+ */
+export async function linkIssue(conversationId: string, url: string): Promise<IssueLink> {
+  const invoke = await getInvoke();
+
+  if (!invoke) {
+    throw new TauriError("Not in Tauri environment", "NOT_AVAILABLE");
+  }
+
+  try {
+    return await invoke<IssueLink>("link_issue", { conversationId, url });
+  } catch (error) {
+    throw wrapError(error, "linkIssue");
+  }
+}
+
+/**
+ * Get the issues a conversation is linked to, by URL or by mentioning their keys.
+ *
+ * @param conversationId - Conversation to look up
+ * @returns Linked issues, by key
+ */
+export async function getIssueLinks(conversationId: string): Promise<IssueLink[]> {
+  const invoke = await getInvoke();
+
+  if (!invoke) {
+    throw new TauriError("Not in Tauri environment", "NOT_AVAILABLE");
+  }
+
+  try {
+    return await invoke<IssueLink[]>("get_issue_links", { conversationId });
+  } catch (error) {
+    throw wrapError(error, "getIssueLinks");
+  }
+}
+
+/**
+ * Get the conversations linked to an issue, newest first.
+ *
+ * @param key - Issue key, e.g. ABC-123 (case-insensitive)
+ * @returns Linked conversations, including archived ones
+ * @throws ValidationError if the key isn't an issue key
+ */
+export async function getConversationsForIssue(key: string): Promise<ConversationSummary[]> {
+  const invoke = await getInvoke();
+
+  if (!invoke) {
+    throw new TauriError("Not in Tauri environment", "NOT_AVAILABLE");
+  }
+
+  try {
+    return await invoke<ConversationSummary[]>("get_conversations_for_issue", { key });
+  } catch (error) {
+    throw wrapError(error, "getConversationsForIssue");
+  }
+}
+
+/**
+ * Get a single message by ID, e.g. to resolve a deep link.
+ *
+ * @param conversationId - Conversation containing the message
+ * @param messageId - Message ID as returned in `Message.id`
+ * @returns The message, including its ordinal for scrolling
+ * @throws NotFoundError if the conversation or message is not found
+ * @throws TauriError if operation fails
+ */
+export async function getMessage(conversationId: string, messageId: string): Promise<Message> {
+  const invoke = await getInvoke();
+
+  if (!invoke) {
+    throw new TauriError("Not in Tauri environment", "NOT_AVAILABLE");
+  }
+
+  try {
+    return await invoke<Message>("get_message", { conversationId, messageId });
+  } catch (error) {
+    throw wrapError(error, "getMessage");
+  }
+}
+
+/**
+ * Get an image extracted from a tool result.
+ *
+ * @param id - Attachment ID, the content of an `image` block
+ * @returns The image's media type and base64 data
+ * @throws NotFoundError if the attachment is not found
+ * @throws TauriError if operation fails
+ */
+export async function getAttachment(id: string): Promise<Attachment> {
+  const invoke = await getInvoke();
+
+  if (!invoke) {
+    throw new TauriError("Not in Tauri environment", "NOT_AVAILABLE");
+  }
+
+  try {
+    return await invoke<Attachment>("get_attachment", { id });
+  } catch (error) {
+    throw wrapError(error, "getAttachment");
+  }
+}
+
+/**
+ * Get the text to copy for one content block of a message.
+ *
+ * @param conversationId - Conversation containing the message
+ * @param messageId - Message ID as returned in `Message.id`
+ * @param blockIndex - Index of the block in the message's content
+ * @param mode - What to copy (defaults to the block's text)
+ * @returns Text ready for the clipboard
+ * @throws NotFoundError if the conversation, message, or block is not found
+ * @throws ValidationError if the block can't be copied in the mode
+ */
+export async function getBlockCopyText(
+  conversationId: string,
+  messageId: string,
+  blockIndex: number,
+  mode?: CopyMode
+): Promise<string> {
+  const invoke = await getInvoke();
+
+  if (!invoke) {
+    throw new TauriError("Not in Tauri environment", "NOT_AVAILABLE");
+  }
+
+  try {
+    return await invoke<string>("get_block_copy_text", {
+      conversationId,
+      messageId,
+      blockIndex,
+      mode,
+    });
+  } catch (error) {
+    throw wrapError(error, "getBlockCopyText");
+  }
+}
+
+/**
+ * Get list of all projects with conversation counts.
+ *
+ * @returns Array of project info sorted alphabetically by name
+ * @throws TauriError if operation fails
+ */
+export async function getProjects(): Promise<ProjectInfo[]> {
+  const invoke = await getInvoke();
+
+  if (!invoke) {
+    console.log("[tauri service] Not in Tauri environment, returning empty array");
+    return [];
+  }
+
+  try {
+    const result = await invoke<ProjectInfo[]>("get_projects");
+    return result;
+  } catch (error) {
+    throw wrapError(error, "getProjects");
+  }
+}
+
+/**
+ * Compare usage metrics of two projects side by side.
+ *
+ * @param projectA - Name of the first project
+ * @param projectB - Name of the second project
+ * @returns Conversations, tokens, estimated cost, tool mix, and error rate of each
+ * @throws NotFoundError if either project has no conversations
+ * @throws TauriError if operation fails
+ */
+export async function compareProjects(
+  projectA: string,
+  projectB: string
+): Promise<ProjectComparison> {
+  const invoke = await getInvoke();
+
+  if (!invoke) {
+    throw new TauriError("Not in Tauri environment", "NOT_AVAILABLE");
+  }
+
+  try {
+    return await invoke<ProjectComparison>("compare_projects", { projectA, projectB });
+  } catch (error) {
+    throw wrapError(error, "compareProjects");
+  }
+}
+
+/**
+ * Get days and conversations with unusual token spikes or tool error bursts.
+ *
+ * @param options - Optional lookback window and sensitivity
+ * @returns Anomalies within the window, most unusual first
+ * @throws TauriError if options are invalid or the operation fails
+ */
+export async function getUsageAnomalies(options?: AnomalyOptions): Promise<UsageAnomaly[]> {
+  const invoke = await getInvoke();
+
+  if (!invoke) {
+    throw new TauriError("Not in Tauri environment", "NOT_AVAILABLE");
+  }
+
+  try {
+    return await invoke<UsageAnomaly[]>("get_usage_anomalies", { options: options ?? null });
+  } catch (error) {
+    throw wrapError(error, "getUsageAnomalies");
+  }
+}
+
+/**
+ * Export a month of token usage as CSV for comparison against invoices.
+ *
+ * @param month - Month to export (YYYY-MM, UTC days)
+ * @returns CSV with one row per day and model, including estimated cost
+ * @throws TauriError if the month is invalid or the operation fails
+ */
+export async function exportUsageCsv(month: string): Promise<string> {
+  const invoke = await getInvoke();
+
+  if (!invoke) {
+    throw new TauriError("Not in Tauri environment", "NOT_AVAILABLE");
+  }
+
+  try {
+    return await invoke<string>("export_usage_csv", { month });
+  } catch (error) {
+    throw wrapError(error, "exportUsageCsv");
+  }
+}
+
+/**
+ * Count the code blocks Claude wrote per programming language.
+ *
+ * @param options - Optional project, UTC day range, and period to group by
+ * @returns Blocks, lines, and conversations per language, most blocks first
+ * @throws ValidationError if a date is invalid or the range is reversed
+ * @throws TauriError if operation fails
+ */
+export async function getLanguageStats(options?: UsageStatsOptions): Promise<LanguageStats[]> {
+  const invoke = await getInvoke();
+
+  if (!invoke) {
+    throw new TauriError("Not in Tauri environment", "NOT_AVAILABLE");
+  }
+
+  try {
+    return await invoke<LanguageStats[]>("get_language_stats", { options: options ?? null });
+  } catch (error) {
+    throw wrapError(error, "getLanguageStats");
+  }
+}
+
+/**
+ * Compare the models that answered, e.g. before and after switching models.
+ *
+ * @param options - Optional project, UTC day range, and period to group by
+ * @returns Answers, share, tokens, answer length, and latency per model
+ * @throws ValidationError if a date is invalid or the range is reversed
+ * @throws TauriError if operation fails
+ */
+export async function getModelStats(options?: UsageStatsOptions): Promise<ModelStats[]> {
+  const invoke = await getInvoke();
+
+  if (!invoke) {
+    throw new TauriError("Not in Tauri environment", "NOT_AVAILABLE");
+  }
+
+  try {
+    return await invoke<ModelStats[]>("get_model_stats", { options: options ?? null });
+  } catch (error) {
+    throw wrapError(error, "getModelStats");
+  }
+}
+
+/**
+ * Fill the database with synthetic conversations for performance testing.
+ * Development builds only; generated conversations can't be opened.
+ *
+ * @param count - Number of conversations to generate (at most 100,000)
+ * @returns Number of conversations written
+ * @throws TauriError if the count is invalid or in release builds
+ */
+export async function generateDemoData(count: number): Promise<number> {
+  const invoke = await getInvoke();
+
+  if (!invoke) {
+    throw new TauriError("Not in Tauri environment", "NOT_AVAILABLE");
+  }
+
+  try {
+    return await invoke<number>("generate_demo_data", { count });
+  } catch (error) {
+    throw wrapError(error, "generateDemoData");
+  }
+}
+
+/**
+ * Measure parse throughput, ingest rate, and search latency on the current
+ * dataset. The stored history is not modified.
+ *
+ * @param options - Optional limits (files to parse, search repetitions)
+ * @returns Throughput, rates, and search latency percentiles
+ * @throws TauriError if the options are invalid or the operation fails
+ */
+export async function runBenchmark(options?: BenchmarkOptions): Promise<BenchmarkReport> {
+  const invoke = await getInvoke();
+
+  if (!invoke) {
+    throw new TauriError("Not in Tauri environment", "NOT_AVAILABLE");
+  }
+
+  try {
+    return await invoke<BenchmarkReport>("run_benchmark", { options: options ?? null });
+  } catch (error) {
+    throw wrapError(error, "runBenchmark");
+  }
+}
+
+/**
+ * Get local performance metrics (command latencies, watcher events, cache
+ * hits) for this session.
+ *
+ * @returns Snapshot of all counters and timers
+ * @throws TauriError if operation fails
+ */
+export async function getAppMetrics(): Promise<AppMetrics> {
+  const invoke = await getInvoke();
+
+  if (!invoke) {
+    throw new TauriError("Not in Tauri environment", "NOT_AVAILABLE");
+  }
+
+  try {
+    return await invoke<AppMetrics>("get_app_metrics");
+  } catch (error) {
+    throw wrapError(error, "getAppMetrics");
+  }
+}
+
+/**
+ * Get a summary of the app's health for the diagnostics panel: watcher
+ * state, last scan, pending files, parse errors, and search index state.
+ *
+ * @returns The health report
+ * @throws TauriError if operation fails
+ */
+export async function getHealth(): Promise<HealthReport> {
+  const invoke = await getInvoke();
+
+  if (!invoke) {
+    throw new TauriError("Not in Tauri environment", "NOT_AVAILABLE");
+  }
+
+  try {
+    return await invoke<HealthReport>("get_health");
+  } catch (error) {
+    throw wrapError(error, "getHealth");
+  }
+}
+
+/**
+ * Get the fields, block types, and line types seen in session files that
+ * the parser doesn't know, to notice new structures it drops.
+ *
+ * @returns Unknown structures, most frequent first
+ * @throws TauriError if operation fails
+ */
+export async function getSchemaObservations(): Promise<SchemaObservation[]> {
+  const invoke = await getInvoke();
+
+  if (!invoke) {
+    throw new TauriError("Not in Tauri environment", "NOT_AVAILABLE");
+  }
+
+  try {
+    return await invoke<SchemaObservation[]>("get_schema_observations");
+  } catch (error) {
+    throw wrapError(error, "getSchemaObservations");
+  }
+}
+
+/**
+ * Get recent entries from the application log file, e.g. to attach to a
+ * bug report.
+ *
+ * @param level - Minimum severity (default: info)
+ * @param limit - Maximum number of entries (default: 200, at most 5,000)
+ * @returns The most recent matching entries, oldest first
+ * @throws TauriError if the level or limit is invalid or the operation fails
+ */
+export async function getRecentLogs(level?: LogLevel, limit?: number): Promise<LogEntry[]> {
+  const invoke = await getInvoke();
+
+  if (!invoke) {
+    throw new TauriError("Not in Tauri environment", "NOT_AVAILABLE");
+  }
+
+  try {
+    return await invoke<LogEntry[]>("get_recent_logs", {
+      level: level ?? null,
+      limit: limit ?? null,
+    });
+  } catch (error) {
+    throw wrapError(error, "getRecentLogs");
+  }
+}
+
+/**
+ * Scan for new and changed session files and ingest them, like the startup
+ * scan.
+ *
+ * @returns Files processed and conversations added or updated
+ * @throws TauriError if the operation fails
+ */
+export async function rescanConversations(): Promise<IngestStats> {
+  const invoke = await getInvoke();
+
+  if (!invoke) {
+    throw new TauriError("Not in Tauri environment", "NOT_AVAILABLE");
+  }
+
+  try {
+    return await invoke<IngestStats>("rescan_conversations");
+  } catch (error) {
+    throw wrapError(error, "rescanConversations");
+  }
+}
+
+/**
+ * Re-ingest session files even if they haven't changed.
+ *
+ * @param paths - Session files to re-ingest (default: all)
+ * @returns Files processed and conversations added or updated
+ * @throws TauriError if a path is not a file or the operation fails
+ */
+export async function reimportConversations(paths?: string[]): Promise<IngestStats> {
+  const invoke = await getInvoke();
+
+  if (!invoke) {
+    throw new TauriError("Not in Tauri environment", "NOT_AVAILABLE");
+  }
+
+  try {
+    return await invoke<IngestStats>("reimport_conversations", { paths: paths ?? null });
+  } catch (error) {
+    throw wrapError(error, "reimportConversations");
+  }
+}
+
+/**
+ * Import session files from outside ~/.claude/projects/, e.g. a restored backup.
+ *
+ * @param path - A session file (.jsonl, .jsonl.gz, or rotated segment) or a folder to search
+ * @returns Files processed and conversations added or updated
+ * @throws TauriError if the path doesn't exist, isn't a session file, or the import fails
+ */
+export async function importPath(path: string): Promise<IngestStats> {
+  const invoke = await getInvoke();
+
+  if (!invoke) {
+    throw new TauriError("Not in Tauri environment", "NOT_AVAILABLE");
+  }
+
+  try {
+    return await invoke<IngestStats>("import_path", { path });
+  } catch (error) {
+    throw wrapError(error, "importPath");
+  }
+}
+
+/**
+ * Rebuild all conversation previews from their session files, e.g. after
+ * changing the preview setting.
+ *
+ * @returns Number of previews that changed
+ * @throws TauriError if the operation fails
+ */
+export async function regeneratePreviews(): Promise<number> {
+  const invoke = await getInvoke();
+
+  if (!invoke) {
+    throw new TauriError("Not in Tauri environment", "NOT_AVAILABLE");
+  }
+
+  try {
+    return await invoke<number>("regenerate_previews");
+  } catch (error) {
+    throw wrapError(error, "regeneratePreviews");
+  }
+}
+
+/**
+ * Search conversations by query with optional filters.
+ *
+ * @param query - Search query string (min 2 characters); `metadata:key=value` terms filter
+ *   by metadata fields
+ * @param filters - Optional filters for project, date range
+ * @returns Array of search results with snippets and match counts
+ * @throws TauriError if operation fails
+ */
+export async function searchConversations(
+  query: string,
+  filters?: ConversationFilters
+): Promise<SearchResult[]> {
+  const invoke = await getInvoke();
+
+  if (!invoke) {
+    console.log("[tauri service] Not in Tauri environment, returning empty array");
+    return [];
+  }
+
+  // Enforce minimum query length
+  if (query.length < 2) {
+    return [];
+  }
+
+  try {
+    const result = await invoke<SearchResult[]>("search_conversations", {
+      query,
+      filters: filters && Object.keys(filters).length > 0 ? filters : null,
+    });
+    return result;
+  } catch (error) {
+    throw wrapError(error, "searchConversations");
+  }
+}
+
+/**
+ * Begin a search-as-you-type session for `updateQuery`.
+ *
+ * @returns Session ID
+ * @throws TauriError if operation fails
+ */
+export async function beginSearch(): Promise<string> {
+  const invoke = await getInvoke();
+
+  if (!invoke) {
+    throw new TauriError("Not in Tauri environment", "NOT_AVAILABLE");
+  }
+
+  try {
+    return await invoke<string>("begin_search", {
+      windowLabel: await currentWindowLabel(),
+    });
+  } catch (error) {
+    throw wrapError(error, "beginSearch");
+  }
+}
+
+/**
+ * Search conversations as the latest query of a search session, cancelling
+ * the session's previous query if it's still running.
+ *
+ * @param sessionId - Session ID from `beginSearch`
+ * @param query - Search query string (min 2 characters)
+ * @param filters - Optional filters for project, date range
+ * @returns Search results, or null if a newer query superseded this one
+ * @throws NotFoundError if the session doesn't exist
+ * @throws TauriError if operation fails
+ */
+export async function updateQuery(
+  sessionId: string,
+  query: string,
+  filters?: ConversationFilters
+): Promise<SearchResult[] | null> {
+  const invoke = await getInvoke();
+
+  if (!invoke) {
+    throw new TauriError("Not in Tauri environment", "NOT_AVAILABLE");
+  }
+
+  try {
+    return await invoke<SearchResult[] | null>("update_query", {
+      sessionId,
+      query,
+      filters: filters && Object.keys(filters).length > 0 ? filters : null,
+      windowLabel: await currentWindowLabel(),
+    });
+  } catch (error) {
+    throw wrapError(error, "updateQuery");
+  }
+}
+
+/**
+ * Search conversations and count the results per project, tag, and month.
+ *
+ * @param query - Search query string (min 2 characters)
+ * @param filters - Optional filters for project, date range
+ * @returns Search results and facet counts for filter chips
+ * @throws TauriError if operation fails
+ */
+export async function searchWithFacets(
+  query: string,
+  filters?: ConversationFilters
+): Promise<SearchResponse> {
+  const invoke = await getInvoke();
+  const empty: SearchResponse = { results: [], facets: { projects: [], tags: [], months: [] } };
+
+  if (!invoke) {
+    console.log("[tauri service] Not in Tauri environment, returning empty response");
+    return empty;
+  }
+
+  if (query.length < 2) {
+    return empty;
+  }
+
+  try {
+    return await invoke<SearchResponse>("search_with_facets", {
+      query,
+      filters: filters && Object.keys(filters).length > 0 ? filters : null,
+    });
+  } catch (error) {
+    throw wrapError(error, "searchWithFacets");
+  }
+}
+
+/**
+ * Search only the user's own messages, leaving out the answers.
+ *
+ * @param query - Search query string (min 2 characters)
+ * @returns Matching user messages, best match first
+ * @throws TauriError if operation fails
+ */
+export async function searchQuestions(query: string): Promise<QuestionMatch[]> {
+  const invoke = await getInvoke();
+
+  if (!invoke) {
+    console.log("[tauri service] Not in Tauri environment, returning empty results");
+    return [];
+  }
+
+  if (query.length < 2) {
+    return [];
+  }
+
+  try {
+    return await invoke<QuestionMatch[]>("search_questions", { query });
+  } catch (error) {
+    throw wrapError(error, "searchQuestions");
+  }
+}
+
+/**
+ * Verify, repair, and optimize the full-text search indexes.
+ *
+ * @returns What was checked and rebuilt
+ * @throws TauriError if the operation fails
+ */
+export async function maintainSearchIndex(): Promise<SearchMaintenanceReport> {
+  const invoke = await getInvoke();
+
+  if (!invoke) {
+    throw new TauriError("Not in Tauri environment", "NOT_AVAILABLE");
+  }
+
+  try {
+    return await invoke<SearchMaintenanceReport>("maintain_search_index");
+  } catch (error) {
+    throw wrapError(error, "maintainSearchIndex");
+  }
+}
+
+/**
+ * Fuzzy-match conversations, projects, and tags for the quick-switcher.
+ * Tolerates small typos; an empty query lists recent conversations.
+ *
+ * @param query - What the user typed
+ * @param limit - Maximum number of results (default 20, at most 200)
+ * @returns Best matches first
+ * @throws TauriError if the limit is invalid or the operation fails
+ */
+export async function quickOpen(query: string, limit?: number): Promise<QuickOpenResult[]> {
+  const invoke = await getInvoke();
+
+  if (!invoke) {
+    throw new TauriError("Not in Tauri environment", "NOT_AVAILABLE");
+  }
+
+  try {
+    return await invoke<QuickOpenResult[]>("quick_open", { query, limit: limit ?? null });
+  } catch (error) {
+    throw wrapError(error, "quickOpen");
+  }
+}
+
+/**
+ * Unlisten function type from Tauri events API.
+ */
+export type UnlistenFn = () => void;
+
+/**
+ * Event names for conversation changes from backend.
+ */
+export const CONVERSATION_ADDED_EVENT = "conversation-added";
+export const CONVERSATION_UPDATED_EVENT = "conversation-updated";
+
+/**
+ * Listen for one of the conversation change events from the backend scan
+ * and file watcher.
+ */
+async function listenToConversationsChanged(
+  eventName: string,
+  callback: (event: ConversationsChangedEvent) => void
+): Promise<UnlistenFn | null> {
+  if (!isTauriAvailable()) {
+    console.log("[tauri service] Not in Tauri environment, skipping event listener");
+    return null;
+  }
+
+  try {
+    const { listen } = await import("@tauri-apps/api/event");
+    const unlisten = await listen<ConversationsChangedEvent>(eventName, (event) => {
+      console.log(`[tauri service] Received ${eventName} event:`, event.payload);
+      callback(event.payload);
+    });
+    console.log(`[tauri service] Listening for ${eventName} events`);
+    return unlisten;
+  } catch (error) {
+    console.error(`[tauri service] Failed to listen for ${eventName}:`, error);
+    return null;
+  }
+}
+
+/**
+ * Listen for conversation-added events, emitted when a scan or the file
+ * watcher finds new conversations.
+ * Returns an unlisten function to clean up the listener.
+ *
+ * @param callback - Function to call with the new conversations' IDs
+ * @returns Promise resolving to unlisten function, or null if not in Tauri
+ */
+export async function listenToConversationAdded(
+  callback: (event: ConversationsChangedEvent) => void
+): Promise<UnlistenFn | null> {
+  return listenToConversationsChanged(CONVERSATION_ADDED_EVENT, callback);
+}
+
+/**
+ * Listen for conversation-updated events, emitted when the session files
+ * of existing conversations change.
+ * Returns an unlisten function to clean up the listener.
+ *
+ * @param callback - Function to call with the updated conversations' IDs
+ * @returns Promise resolving to unlisten function, or null if not in Tauri
+ */
+export async function listenToConversationUpdated(
+  callback: (event: ConversationsChangedEvent) => void
+): Promise<UnlistenFn | null> {
+  return listenToConversationsChanged(CONVERSATION_UPDATED_EVENT, callback);
+}
+
+/**
+ * Event name for tag changes from backend.
+ */
+export const TAGS_CHANGED_EVENT = "tags-changed";
+
+/**
+ * Listen for tags-changed events, emitted when tags are set, undone, or
+ * redone in any window.
+ *
+ * @param callback - Function to call with the conversation and its new tags
+ * @returns Promise resolving to unlisten function, or null if not in Tauri
+ */
+export async function listenToTagsChanged(
+  callback: (event: TagsChangedEvent) => void
+): Promise<UnlistenFn | null> {
+  if (!isTauriAvailable()) {
+    return null;
+  }
+
+  try {
+    const { listen } = await import("@tauri-apps/api/event");
+    return await listen<TagsChangedEvent>(TAGS_CHANGED_EVENT, (event) => {
+      callback(event.payload);
+    });
+  } catch (error) {
+    console.error("[tauri service] Failed to listen for tags-changed:", error);
+    return null;
+  }
+}
+
+/**
+ * Event name for file watcher status from backend.
+ */
+export const WATCHER_STATUS_EVENT = "watcher-status";
+
+/**
+ * Listen for watcher-status events, emitted when the file watcher starts,
+ * stops, or fails.
+ *
+ * @param callback - Function to call with the watcher's new status
+ * @returns Promise resolving to unlisten function, or null if not in Tauri
+ */
+export async function listenToWatcherStatus(
+  callback: (status: WatcherStatusEvent) => void
+): Promise<UnlistenFn | null> {
+  if (!isTauriAvailable()) {
+    return null;
+  }
+
+  try {
+    const { listen } = await import("@tauri-apps/api/event");
+    return await listen<WatcherStatusEvent>(WATCHER_STATUS_EVENT, (event) => {
+      callback(event.payload);
+    });
+  } catch (error) {
+    console.error("[tauri service] Failed to listen for watcher-status:", error);
+    return null;
+  }
+}
+
+/**
+ * Event name for usage anomaly notifications from backend.
+ */
+export const USAGE_ANOMALY_EVENT = "usage-anomaly";
+
+/**
+ * Listen for usage-anomaly events, emitted by the file watcher when today's
+ * usage looks unusual and anomaly notifications are enabled in settings.
+ *
+ * @param callback - Function to call with the newly detected anomalies
+ * @returns Promise resolving to unlisten function, or null if not in Tauri
+ */
+export async function listenToUsageAnomalies(
+  callback: (anomalies: UsageAnomaly[]) => void
+): Promise<UnlistenFn | null> {
+  if (!isTauriAvailable()) {
+    return null;
+  }
+
+  try {
+    const { listen } = await import("@tauri-apps/api/event");
+    return await listen<UsageAnomaly[]>(USAGE_ANOMALY_EVENT, (event) => {
+      callback(event.payload);
+    });
+  } catch (error) {
+    console.error("[tauri service] Failed to listen for usage-anomaly:", error);
+    return null;
+  }
+}
+
+/**
+ * Event name for the outcome of importing files dropped on the window.
+ */
+export const IMPORT_RESULT_EVENT = "import-result";
+
+/**
+ * Listen for import-result events, emitted once session files dropped on
+ * the window have been copied and ingested.
+ *
+ * @param callback - Function to call with the import's outcome
+ * @returns Promise resolving to unlisten function, or null if not in Tauri
+ */
+export async function listenToImportResult(
+  callback: (result: DropImportResult) => void
+): Promise<UnlistenFn | null> {
+  if (!isTauriAvailable()) {
+    return null;
+  }
+
+  try {
+    const { listen } = await import("@tauri-apps/api/event");
+    return await listen<DropImportResult>(IMPORT_RESULT_EVENT, (event) => {
+      callback(event.payload);
+    });
+  } catch (error) {
+    console.error("[tauri service] Failed to listen for import-result:", error);
+    return null;
+  }
+}
+
+/**
+ * Event name for a link asking to open a conversation.
+ */
+export const OPEN_CONVERSATION_EVENT = "open-conversation";
+
+/**
+ * Listen for open-conversation events, emitted when a claude-history://
+ * link, such as one in a Spotlight stub, opens a conversation.
+ *
+ * @param callback - Function to call with the conversation to open
+ * @returns Promise resolving to unlisten function, or null if not in Tauri
+ */
+export async function listenToOpenConversation(
+  callback: (event: OpenConversationEvent) => void
+): Promise<UnlistenFn | null> {
+  if (!isTauriAvailable()) {
+    return null;
+  }
+
+  try {
+    const { listen } = await import("@tauri-apps/api/event");
+    return await listen<OpenConversationEvent>(OPEN_CONVERSATION_EVENT, (event) => {
+      callback(event.payload);
+    });
+  } catch (error) {
+    console.error("[tauri service] Failed to listen for open-conversation:", error);
+    return null;
+  }
+}
+
+/**
+ * Event name for follow-up reminders coming due.
+ */
+export const REMINDER_DUE_EVENT = "reminder-due";
+
+/**
+ * Listen for reminder-due events, emitted once for each reminder as it comes due,
+ * including those that came due while the app was closed.
+ *
+ * @param callback - Function to call with the reminders that just came due
+ * @returns Promise resolving to unlisten function, or null if not in Tauri
+ */
+export async function listenToReminderDue(
+  callback: (reminders: Reminder[]) => void
+): Promise<UnlistenFn | null> {
+  if (!isTauriAvailable()) {
+    return null;
+  }
+
+  try {
+    const { listen } = await import("@tauri-apps/api/event");
+    return await listen<Reminder[]>(REMINDER_DUE_EVENT, (event) => {
+      callback(event.payload);
+    });
+  } catch (error) {
+    console.error("[tauri service] Failed to listen for reminder-due:", error);
+    return null;
+  }
+}
+
+/**
+ * Event name for indexing progress from backend.
+ */
+export const INDEX_PROGRESS_EVENT = "index-progress";
+
+/**
+ * Listen for index-progress events, emitted after each batch of the
+ * startup scan.
+ *
+ * @param callback - Function to call with the progress so far
+ * @returns Promise resolving to unlisten function, or null if not in Tauri
+ */
+export async function listenToIndexProgress(
+  callback: (progress: IngestProgress) => void
+): Promise<UnlistenFn | null> {
+  if (!isTauriAvailable()) {
+    return null;
+  }
+
+  try {
+    const { listen } = await import("@tauri-apps/api/event");
+    return await listen<IngestProgress>(INDEX_PROGRESS_EVENT, (event) => {
+      callback(event.payload);
+    });
+  } catch (error) {
+    console.error("[tauri service] Failed to listen for index-progress:", error);
+    return null;
+  }
+}
+
+/**
+ * Toggle the bookmark status of a conversation.
+ *
+ * @param conversationId - ID of the conversation to toggle
+ * @returns The new bookmark status (true if now bookmarked, false if unbookmarked)
+ * @throws TauriError if operation fails
+ */
+export async function toggleBookmark(conversationId: string): Promise<boolean> {
+  const invoke = await getInvoke();
+
+  if (!invoke) {
+    throw new TauriError("Not in Tauri environment", "NOT_AVAILABLE");
+  }
+
+  try {
+    const result = await invoke<boolean>("toggle_bookmark", {
+      conversationId,
+      windowLabel: await currentWindowLabel(),
+    });
+    return result;
+  } catch (error) {
+    throw wrapError(error, "toggleBookmark");
+  }
+}
+
+/**
+ * Set tags for a conversation.
+ * Replaces all existing tags with the provided list.
+ *
+ * @param conversationId - ID of the conversation
+ * @param tags - Array of tag strings (will be normalized: lowercase, trimmed, deduplicated)
+ * @returns The normalized, sorted array of tags that were set
+ * @throws TauriError if operation fails
+ */
+export async function setTags(conversationId: string, tags: string[]): Promise<string[]> {
+  const invoke = await getInvoke();
+
+  if (!invoke) {
+    throw new TauriError("Not in Tauri environment", "NOT_AVAILABLE");
+  }
+
+  try {
+    const result = await invoke<string[]>("set_tags", {
+      conversationId,
+      tags,
+      windowLabel: await currentWindowLabel(),
+    });
+    return result;
+  } catch (error) {
+    throw wrapError(error, "setTags");
+  }
+}
+
+/**
+ * Undo the latest tag or bookmark change made in this window.
+ *
+ * @returns The conversation and its restored state, or null if there's nothing to undo
+ * @throws TauriError if operation fails
+ */
+export async function undoLastOperation(): Promise<AppliedOperation | null> {
+  const invoke = await getInvoke();
+
+  if (!invoke) {
+    throw new TauriError("Not in Tauri environment", "NOT_AVAILABLE");
+  }
+
+  try {
+    return await invoke<AppliedOperation | null>("undo_last_operation", {
+      windowLabel: await currentWindowLabel(),
+    });
+  } catch (error) {
+    throw wrapError(error, "undoLastOperation");
+  }
+}
+
+/**
+ * Redo the latest tag or bookmark change undone in this window.
+ *
+ * @returns The conversation and its new state, or null if there's nothing to redo
+ * @throws TauriError if operation fails
+ */
+export async function redo(): Promise<AppliedOperation | null> {
+  const invoke = await getInvoke();
+
+  if (!invoke) {
+    throw new TauriError("Not in Tauri environment", "NOT_AVAILABLE");
+  }
+
+  try {
+    return await invoke<AppliedOperation | null>("redo", {
+      windowLabel: await currentWindowLabel(),
+    });
+  } catch (error) {
+    throw wrapError(error, "redo");
+  }
+}
+
+/**
+ * Get the audit log of user-data changes, newest first.
+ *
+ * @param conversationId - Only entries about this conversation
+ * @param limit - Maximum number of entries (default 100, at most 1000)
+ * @returns Who changed what, and when
+ * @throws TauriError if the limit is out of range or the operation fails
+ */
+export async function getAuditLog(conversationId?: string, limit?: number): Promise<AuditEntry[]> {
+  const invoke = await getInvoke();
+
+  if (!invoke) {
+    throw new TauriError("Not in Tauri environment", "NOT_AVAILABLE");
+  }
+
+  try {
+    return await invoke<AuditEntry[]>("get_audit_log", {
+      conversationId: conversationId ?? null,
+      limit: limit ?? null,
+    });
+  } catch (error) {
+    throw wrapError(error, "getAuditLog");
+  }
+}
+
+/**
+ * List the webhooks notified when conversations are ingested.
+ *
+ * @returns Webhooks in the order they were created
+ * @throws TauriError if the operation fails
+ */
+export async function listWebhooks(): Promise<Webhook[]> {
+  const invoke = await getInvoke();
+
+  if (!invoke) {
+    throw new TauriError("Not in Tauri environment", "NOT_AVAILABLE");
+  }
+
+  try {
+    return await invoke<Webhook[]>("list_webhooks");
+  } catch (error) {
+    throw wrapError(error, "listWebhooks");
+  }
+}
+
+/**
+ * Create a webhook, or update the one `webhook.id` names.
+ *
+ * @param webhook - URL, filters, and events to notify it of
+ * @returns The stored webhook
+ * @throws TauriError if the URL, events, or filters are invalid, or the webhook doesn't exist
+ */
+export async function saveWebhook(webhook: WebhookInput): Promise<Webhook> {
+  const invoke = await getInvoke();
+
+  if (!invoke) {
+    throw new TauriError("Not in Tauri environment", "NOT_AVAILABLE");
+  }
+
+  try {
+    return await invoke<Webhook>("save_webhook", { webhook });
+  } catch (error) {
+    throw wrapError(error, "saveWebhook");
+  }
+}
+
+/**
+ * Delete a webhook and its delivery log.
+ *
+ * @param id - Webhook to delete
+ * @throws TauriError if the webhook doesn't exist or the operation fails
+ */
+export async function deleteWebhook(id: number): Promise<void> {
+  const invoke = await getInvoke();
+
+  if (!invoke) {
+    throw new TauriError("Not in Tauri environment", "NOT_AVAILABLE");
+  }
+
+  try {
+    await invoke<void>("delete_webhook", { id });
+  } catch (error) {
+    throw wrapError(error, "deleteWebhook");
+  }
+}
+
+/**
+ * Get the webhook delivery log, newest first.
+ *
+ * @param webhookId - Only deliveries to this webhook
+ * @param limit - Maximum number of deliveries (default 100, at most 1000)
+ * @returns Deliveries with their status and attempts
+ * @throws TauriError if the limit is out of range or the operation fails
+ */
+export async function getWebhookDeliveries(
+  webhookId?: number,
+  limit?: number
+): Promise<WebhookDelivery[]> {
+  const invoke = await getInvoke();
+
+  if (!invoke) {
+    throw new TauriError("Not in Tauri environment", "NOT_AVAILABLE");
+  }
+
+  try {
+    return await invoke<WebhookDelivery[]>("get_webhook_deliveries", {
+      webhookId: webhookId ?? null,
+      limit: limit ?? null,
+    });
+  } catch (error) {
+    throw wrapError(error, "getWebhookDeliveries");
+  }
+}
+
+/**
+ * List saved prompts, most recently changed first.
+ *
+ * @param query - Only prompts whose title or body contains this text
+ * @returns Prompts with their detected variables
+ * @throws ValidationError if the query is too long
+ */
+export async function listPrompts(query?: string): Promise<Prompt[]> {
+  const invoke = await getInvoke();
+
+  if (!invoke) {
+    throw new TauriError("Not in Tauri environment", "NOT_AVAILABLE");
+  }
+
+  try {
+    return await invoke<Prompt[]>("list_prompts", { query: query ?? null });
+  } catch (error) {
+    throw wrapError(error, "listPrompts");
+  }
+}
+
+/**
+ * Create a prompt, or update the one `prompt.id` names.
+ *
+ * @param prompt - Title and body
+ * @returns The stored prompt, with its detected variables
+ * @throws ValidationError if the title or body is empty
+ * @throws NotFoundError if the prompt to update does not exist
+ */
+export async function savePrompt(prompt: PromptInput): Promise<Prompt> {
+  const invoke = await getInvoke();
+
+  if (!invoke) {
+    throw new TauriError("Not in Tauri environment", "NOT_AVAILABLE");
+  }
+
+  try {
+    return await invoke<Prompt>("save_prompt", { prompt });
+  } catch (error) {
+    throw wrapError(error, "savePrompt");
+  }
+}
+
+/**
+ * Save a user message from history as a prompt. Bracketed placeholders in
+ * it, like [file], become variables.
+ *
+ * @param conversationId - Conversation containing the message
+ * @param messageId - Message ID as returned in `Message.id`
+ * @param title - Prompt title (default: the message's first line)
+ * @returns The new prompt
+ * @throws NotFoundError if the conversation or message is not found
+ * @throws ValidationError if the message isn't a user message with text
+ */
+export async function savePromptFromMessage(
+  conversationId: string,
+  messageId: string,
+  title?: string
+): Promise<Prompt> {
+  const invoke = await getInvoke();
+
+  if (!invoke) {
+    throw new TauriError("Not in Tauri environment", "NOT_AVAILABLE");
+  }
+
+  try {
+    return await invoke<Prompt>("save_prompt_from_message", {
+      conversationId,
+      messageId,
+      title: title ?? null,
+    });
+  } catch (error) {
+    throw wrapError(error, "savePromptFromMessage");
+  }
+}
+
+/**
+ * Delete a prompt.
+ *
+ * @param id - Prompt to delete
+ * @throws NotFoundError if the prompt does not exist
+ */
+export async function deletePrompt(id: number): Promise<void> {
+  const invoke = await getInvoke();
+
+  if (!invoke) {
+    throw new TauriError("Not in Tauri environment", "NOT_AVAILABLE");
+  }
+
+  try {
+    await invoke<void>("delete_prompt", { id });
+  } catch (error) {
+    throw wrapError(error, "deletePrompt");
+  }
+}
+
+/**
+ * Get the text of a prompt to copy, with its variables filled in.
+ *
+ * @param id - Prompt to copy
+ * @param values - Values by variable name; placeholders without one are left as written
+ * @returns The prompt body, filled in
+ * @throws NotFoundError if the prompt does not exist
+ */
+export async function copyPrompt(id: number, values?: Record<string, string>): Promise<string> {
+  const invoke = await getInvoke();
+
+  if (!invoke) {
+    throw new TauriError("Not in Tauri environment", "NOT_AVAILABLE");
+  }
+
+  try {
+    return await invoke<string>("copy_prompt", { id, values: values ?? null });
+  } catch (error) {
+    throw wrapError(error, "copyPrompt");
+  }
+}
+
+/**
+ * Export prompts as a Markdown document or a JSON array.
+ *
+ * @param ids - Prompts to export, in order (default: all)
+ * @param format - Export format (default: markdown)
+ * @returns The rendered prompts, ready to save
+ * @throws NotFoundError if a prompt does not exist
+ */
+export async function exportPrompts(
+  ids?: number[],
+  format: PromptExportFormat = "markdown"
+): Promise<string> {
+  const invoke = await getInvoke();
+
+  if (!invoke) {
+    throw new TauriError("Not in Tauri environment", "NOT_AVAILABLE");
+  }
+
+  try {
+    return await invoke<string>("export_prompts", { ids: ids ?? null, format });
+  } catch (error) {
+    throw wrapError(error, "exportPrompts");
+  }
+}
+
+/**
+ * List saved workspaces, most recently loaded first.
+ *
+ * @returns Workspaces with their filters, sort, pins, and tabs
+ * @throws TauriError if operation fails
+ */
+export async function listWorkspaces(): Promise<Workspace[]> {
+  const invoke = await getInvoke();
+
+  if (!invoke) {
+    throw new TauriError("Not in Tauri environment", "NOT_AVAILABLE");
+  }
+
+  try {
+    return await invoke<Workspace[]>("list_workspaces");
+  } catch (error) {
+    throw wrapError(error, "listWorkspaces");
+  }
+}
+
+/**
+ * Save the current context as a named workspace, replacing any workspace of the same name
+ * (compared case-insensitively).
+ *
+ * @param workspace - Name, filters, sort, pinned conversations, and open tabs
+ * @returns The stored workspace
+ * @throws ValidationError if the name is empty or too long, the filters are invalid, or the
+ *   active tab isn't open
+ */
+export async function saveWorkspace(workspace: WorkspaceInput): Promise<Workspace> {
+  const invoke = await getInvoke();
+
+  if (!invoke) {
+    throw new TauriError("Not in Tauri environment", "NOT_AVAILABLE");
+  }
+
+  try {
+    return await invoke<Workspace>("save_workspace", { workspace });
+  } catch (error) {
+    throw wrapError(error, "saveWorkspace");
+  }
+}
+
+/**
+ * Load a workspace to switch to it. Conversations deleted since it was saved are dropped
+ * from its pins and tabs.
+ *
+ * @param name - Workspace to load (case-insensitive)
+ * @returns The workspace, marked as just opened
+ * @throws NotFoundError if there's no workspace of that name
+ */
+export async function loadWorkspace(name: string): Promise<Workspace> {
+  const invoke = await getInvoke();
+
+  if (!invoke) {
+    throw new TauriError("Not in Tauri environment", "NOT_AVAILABLE");
+  }
+
+  try {
+    return await invoke<Workspace>("load_workspace", { name });
+  } catch (error) {
+    throw wrapError(error, "loadWorkspace");
+  }
+}
+
+/**
+ * Delete a workspace. Its conversations are left alone.
+ *
+ * @param name - Workspace to delete (case-insensitive)
+ * @throws NotFoundError if there's no workspace of that name
+ */
+export async function deleteWorkspace(name: string): Promise<void> {
+  const invoke = await getInvoke();
+
+  if (!invoke) {
+    throw new TauriError("Not in Tauri environment", "NOT_AVAILABLE");
+  }
+
+  try {
+    await invoke<void>("delete_workspace", { name });
+  } catch (error) {
+    throw wrapError(error, "deleteWorkspace");
+  }
+}
+
+/**
+ * Get all unique tags across all conversations with usage counts.
+ *
+ * @returns Array of tag info sorted alphabetically by tag name
+ * @throws TauriError if operation fails
+ */
+export async function getAllTags(): Promise<TagInfo[]> {
+  const invoke = await getInvoke();
+
+  if (!invoke) {
+    console.log("[tauri service] Not in Tauri environment, returning empty array");
+    return [];
+  }
+
+  try {
+    const result = await invoke<TagInfo[]>("get_all_tags");
+    return result;
+  } catch (error) {
+    throw wrapError(error, "getAllTags");
+  }
+}
+
+/**
+ * Suggest tags for a conversation from the languages and frameworks it's about, the
+ * files it touched, and the tags of similar conversations. Accept one with setTags.
+ *
+ * @param id - ID of the conversation
+ * @returns Up to 10 tags the conversation doesn't have, surest first
+ * @throws NotFoundError if the conversation does not exist
+ */
+export async function suggestTags(id: string): Promise<TagSuggestion[]> {
+  const invoke = await getInvoke();
+
+  if (!invoke) {
+    throw new TauriError("Not in Tauri environment", "NOT_AVAILABLE");
+  }
+
+  try {
+    return await invoke<TagSuggestion[]>("suggest_tags", { id });
+  } catch (error) {
+    throw wrapError(error, "suggestTags");
+  }
+}
+
+/**
+ * List the actions of the command palette: every capability of the backend beyond
+ * reading for a view, with the arguments each takes.
+ *
+ * @returns The actions by category
+ */
+export async function listActions(): Promise<ActionInfo[]> {
+  const invoke = await getInvoke();
+
+  if (!invoke) {
+    throw new TauriError("Not in Tauri environment", "NOT_AVAILABLE");
+  }
+
+  try {
+    return await invoke<ActionInfo[]>("list_actions");
+  } catch (error) {
+    throw wrapError(error, "listActions");
+  }
+}
+
+/**
+ * Run an action of the command palette by calling its command.
+ *
+ * @param actionId - ID of the action, as listed by listActions
+ * @param args - The action's arguments by name
+ * @returns What the action's command returns
+ * @throws NotFoundError if there is no such action
+ * @throws ValidationError if an argument is unknown, missing, or of the wrong type
+ */
+export async function runAction<T = unknown>(
+  actionId: string,
+  args?: Record<string, unknown>
+): Promise<T> {
+  const invoke = await getInvoke();
+
+  if (!invoke) {
+    throw new TauriError("Not in Tauri environment", "NOT_AVAILABLE");
+  }
+
+  try {
+    return await invoke<T>("run_action", { actionId, args: args ?? null });
+  } catch (error) {
+    throw wrapError(error, "runAction");
+  }
+}
+
+/**
+ * Get the custom metadata fields of a conversation.
+ *
+ * @param id - ID of the conversation
+ * @returns The fields, sorted by key
+ * @throws NotFoundError if the conversation does not exist
+ */
+export async function getMetadata(id: string): Promise<MetadataField[]> {
+  const invoke = await getInvoke();
+
+  if (!invoke) {
+    throw new TauriError("Not in Tauri environment", "NOT_AVAILABLE");
+  }
+
+  try {
+    return await invoke<MetadataField[]>("get_metadata", { id });
+  } catch (error) {
+    throw wrapError(error, "getMetadata");
+  }
+}
+
+/**
+ * Set a custom metadata field of a conversation, such as `customer` to `acme`, or remove
+ * it. Searches can then filter by the field with `metadata:customer=acme`.
+ *
+ * @param id - ID of the conversation
+ * @param key - Field name; letters, digits, `-`, `_`, and `.` (stored lowercase)
+ * @param value - New value; null or blank removes the field
+ * @returns The conversation's fields after the change
+ * @throws ValidationError if the key or value is too long or has unsupported characters
+ * @throws NotFoundError if the conversation does not exist
+ */
+export async function setMetadata(
+  id: string,
+  key: string,
+  value: string | null
+): Promise<MetadataField[]> {
+  const invoke = await getInvoke();
+
+  if (!invoke) {
+    throw new TauriError("Not in Tauri environment", "NOT_AVAILABLE");
+  }
+
+  try {
+    return await invoke<MetadataField[]>("set_metadata", { id, key, value });
+  } catch (error) {
+    throw wrapError(error, "setMetadata");
+  }
+}
+
+/**
+ * Set a follow-up reminder on a conversation, replacing any it had. When it comes due,
+ * reminder-due is emitted with it.
+ *
+ * @param id - ID of the conversation
+ * @param datetime - When it's due: an RFC 3339 timestamp, or a date (YYYY-MM-DD) for the
+ *   start of that day in the configured timezone
+ * @param note - What to follow up on
+ * @returns The reminder set
+ * @throws ValidationError if the datetime is invalid or in the past, or the note is too long
+ * @throws NotFoundError if the conversation does not exist
+ */
+export async function setReminder(
+  id: string,
+  datetime: string,
+  note?: string
+): Promise<Reminder> {
+  const invoke = await getInvoke();
+
+  if (!invoke) {
+    throw new TauriError("Not in Tauri environment", "NOT_AVAILABLE");
+  }
+
+  try {
+    return await invoke<Reminder>("set_reminder", { id, datetime, note: note ?? null });
+  } catch (error) {
+    throw wrapError(error, "setReminder");
+  }
+}
+
+/**
+ * Clear a conversation's reminder, e.g. once it's been followed up.
+ *
+ * @param id - ID of the conversation
+ * @throws NotFoundError if the conversation has no reminder
+ */
+export async function clearReminder(id: string): Promise<void> {
+  const invoke = await getInvoke();
+
+  if (!invoke) {
+    throw new TauriError("Not in Tauri environment", "NOT_AVAILABLE");
+  }
+
+  try {
+    await invoke<void>("clear_reminder", { id });
+  } catch (error) {
+    throw wrapError(error, "clearReminder");
+  }
+}
+
+/**
+ * Get the reminders that are due and not yet cleared, earliest first.
+ *
+ * @returns Due reminders with their conversation's project and preview
+ * @throws TauriError if operation fails
+ */
+export async function getDueReminders(): Promise<Reminder[]> {
+  const invoke = await getInvoke();
+
+  if (!invoke) {
+    throw new TauriError("Not in Tauri environment", "NOT_AVAILABLE");
+  }
+
+  try {
+    return await invoke<Reminder[]>("get_due_reminders");
+  } catch (error) {
+    throw wrapError(error, "getDueReminders");
+  }
+}
+
+/**
+ * Record where reading left off in a conversation, e.g. the last message scrolled past.
+ * getConversation returns it as readPosition, to resume there.
+ *
+ * @param id - ID of the conversation
+ * @param messageId - ID of the last-read message
+ * @returns The position recorded
+ * @throws ValidationError if the message ID is empty
+ * @throws NotFoundError if the conversation does not exist
+ */
+export async function setReadPosition(id: string, messageId: string): Promise<ReadPosition> {
+  const invoke = await getInvoke();
+
+  if (!invoke) {
+    throw new TauriError("Not in Tauri environment", "NOT_AVAILABLE");
+  }
+
+  try {
+    return await invoke<ReadPosition>("set_read_position", { id, messageId });
+  } catch (error) {
+    throw wrapError(error, "setReadPosition");
+  }
+}
+
+/**
+ * Get the chain of sessions a conversation belongs to when it was resumed
+ * (`claude --resume`) or resumed from, to read the whole story end to end.
+ *
+ * @param id - ID of a conversation in the chain
+ * @returns The sessions from the original through each resume, oldest first; just the
+ *   conversation itself if it wasn't resumed
+ * @throws NotFoundError if the conversation does not exist
+ */
+export async function getConversationChain(id: string): Promise<ConversationSummary[]> {
+  const invoke = await getInvoke();
+
+  if (!invoke) {
+    throw new TauriError("Not in Tauri environment", "NOT_AVAILABLE");
+  }
+
+  try {
+    return await invoke<ConversationSummary[]>("get_conversation_chain", { id });
+  } catch (error) {
+    throw wrapError(error, "getConversationChain");
+  }
+}
+
+/**
+ * Nest a conversation under another by hand, such as a sub-agent transcript
+ * under the session that ran it when it wasn't found at import. Replaces the
+ * parent the child had.
+ *
+ * @param parent - ID of the invoking session
+ * @param child - ID of the conversation to nest under it
+ * @throws ValidationError if the child is the parent or a conversation it's nested under
+ * @throws NotFoundError if either conversation does not exist
+ */
+export async function attachChildConversation(parent: string, child: string): Promise<void> {
+  const invoke = await getInvoke();
+
+  if (!invoke) {
+    throw new TauriError("Not in Tauri environment", "NOT_AVAILABLE");
+  }
+
+  try {
+    await invoke<void>("attach_child_conversation", { parent, child });
+  } catch (error) {
+    throw wrapError(error, "attachChildConversation");
+  }
+}
+
+/**
+ * Get the conversations nested under a conversation, such as the transcripts
+ * of the sub-agents it ran.
+ *
+ * @param id - ID of the parent conversation
+ * @returns The nested conversations, oldest first
+ * @throws NotFoundError if the conversation does not exist
+ */
+export async function getChildConversations(id: string): Promise<ConversationSummary[]> {
+  const invoke = await getInvoke();
+
+  if (!invoke) {
+    throw new TauriError("Not in Tauri environment", "NOT_AVAILABLE");
+  }
+
+  try {
+    return await invoke<ConversationSummary[]>("get_child_conversations", { id });
+  } catch (error) {
+    throw wrapError(error, "getChildConversations");
+  }
+}
+
+/**
+ * Count conversations per project, tag, and bookmark state under the active
+ * filters.
+ *
+ * @param filters - Optional filters for project, date range, bookmarks, tags
+ * @returns Counts for the badges next to filter controls
+ * @throws ValidationError if the filters are invalid
+ * @throws TauriError if operation fails
+ */
+export async function getFilterCounts(filters?: ConversationFilters): Promise<FilterCounts> {
+  const invoke = await getInvoke();
+
+  if (!invoke) {
+    throw new TauriError("Not in Tauri environment", "NOT_AVAILABLE");
+  }
+
+  try {
+    return await invoke<FilterCounts>("get_filter_counts", {
+      filters: filters && Object.keys(filters).length > 0 ? filters : null,
+    });
+  } catch (error) {
+    throw wrapError(error, "getFilterCounts");
+  }
+}
+
+/**
+ * Find the terms most characteristic of the conversations matching the
+ * filters, for a word cloud.
+ *
+ * @param filters - Optional filters for project, date range, bookmarks, tags
+ * @param n - Maximum number of terms (default: 50, at most 500)
+ * @returns Terms with their weights, highest first
+ * @throws ValidationError if the filters or n are invalid
+ * @throws TauriError if the index uses the trigram tokenizer, or operation fails
+ */
+export async function getTopTerms(filters?: ConversationFilters, n?: number): Promise<TopTerm[]> {
+  const invoke = await getInvoke();
+
+  if (!invoke) {
+    throw new TauriError("Not in Tauri environment", "NOT_AVAILABLE");
+  }
+
+  try {
+    return await invoke<TopTerm[]>("get_top_terms", {
+      filters: filters && Object.keys(filters).length > 0 ? filters : null,
+      n: n ?? null,
+    });
+  } catch (error) {
+    throw wrapError(error, "getTopTerms");
+  }
+}
+
+/**
+ * Get all search synonyms.
+ *
+ * @returns Terms with their synonyms, sorted by term
+ * @throws TauriError if operation fails
+ */
+export async function getSynonyms(): Promise<SynonymEntry[]> {
+  const invoke = await getInvoke();
+
+  if (!invoke) {
+    throw new TauriError("Not in Tauri environment", "NOT_AVAILABLE");
+  }
+
+  try {
+    return await invoke<SynonymEntry[]>("get_synonyms");
+  } catch (error) {
+    throw wrapError(error, "getSynonyms");
+  }
+}
+
+/**
+ * Set the synonyms of a search term, e.g. "k8s" → ["kubernetes"].
+ * Replaces existing synonyms; an empty array removes the term.
+ *
+ * @param term - Term to expand (matched case-insensitively)
+ * @param synonyms - Terms to also search for
+ * @returns The normalized, sorted synonyms that were set
+ * @throws TauriError if the term is empty or the operation fails
+ */
+export async function setSynonyms(term: string, synonyms: string[]): Promise<string[]> {
+  const invoke = await getInvoke();
+
+  if (!invoke) {
+    throw new TauriError("Not in Tauri environment", "NOT_AVAILABLE");
+  }
+
+  try {
+    return await invoke<string[]>("set_synonyms", { term, synonyms });
+  } catch (error) {
+    throw wrapError(error, "setSynonyms");
+  }
+}
+
+/**
+ * Archive every conversation matching the filters, hiding them from
+ * conversation lists. Archived conversations stay searchable.
+ *
+ * @param filters - Which conversations to archive
+ * @param dryRun - Only count the conversations that would be archived
+ * @returns How many conversations were (or would be) archived
+ * @throws TauriError if the operation fails
+ */
+export async function bulkArchive(
+  filters: ConversationFilters,
+  dryRun = false
+): Promise<BulkOperationResult> {
+  const invoke = await getInvoke();
+
+  if (!invoke) {
+    throw new TauriError("Not in Tauri environment", "NOT_AVAILABLE");
+  }
+
+  try {
+    return await invoke<BulkOperationResult>("bulk_archive", { filters, dryRun });
+  } catch (error) {
+    throw wrapError(error, "bulkArchive");
+  }
+}
+
+/**
+ * Permanently delete every conversation matching the filters from the
+ * database. JSONL files are left alone, and rescans skip them.
+ *
+ * Call without a token first: that dry run returns the count and a
+ * `confirmToken`, and passing it back deletes exactly those conversations.
+ *
+ * @param filters - Which conversations to delete
+ * @param confirmToken - Token from a dry run with the same filters
+ * @returns How many conversations were (or would be) deleted
+ * @throws TauriError if the token no longer matches or the operation fails
+ */
+export async function bulkDelete(
+  filters: ConversationFilters,
+  confirmToken?: string
+): Promise<BulkOperationResult> {
+  const invoke = await getInvoke();
+
+  if (!invoke) {
+    throw new TauriError("Not in Tauri environment", "NOT_AVAILABLE");
+  }
+
+  try {
+    return await invoke<BulkOperationResult>("bulk_delete", {
+      filters,
+      confirmToken: confirmToken ?? null,
+    });
+  } catch (error) {
+    throw wrapError(error, "bulkDelete");
+  }
+}
+
+/**
+ * Get the application settings.
+ *
+ * @returns The settings, with defaults for those never set
+ * @throws TauriError if the operation fails
+ */
+export async function getSettings(): Promise<AppSettings> {
+  const invoke = await getInvoke();
+
+  if (!invoke) {
+    throw new TauriError("Not in Tauri environment", "NOT_AVAILABLE");
+  }
+
+  try {
+    return await invoke<AppSettings>("get_settings");
+  } catch (error) {
+    throw wrapError(error, "getSettings");
+  }
+}
+
+/**
+ * Update the application settings. Changing the search tokenizer rebuilds the
+ * search index.
+ *
+ * @param settings - The new settings, all of them
+ * @returns The settings as saved
+ * @throws ValidationError if a setting is invalid, e.g. an unknown timezone or locale
+ * @throws TauriError if the operation fails
+ */
+export async function updateSettings(settings: AppSettings): Promise<AppSettings> {
+  const invoke = await getInvoke();
+
+  if (!invoke) {
+    throw new TauriError("Not in Tauri environment", "NOT_AVAILABLE");
+  }
+
+  try {
+    return await invoke<AppSettings>("update_settings", { settings });
+  } catch (error) {
+    throw wrapError(error, "updateSettings");
+  }
+}
+
+/**
+ * Whether the app is in read-only mode, by the `--read-only` launch flag or
+ * the setting. Deleting, archiving, and exports into the session directory
+ * are refused then.
+ *
+ * @returns Whether the app is read-only
+ * @throws TauriError if the operation fails
+ */
+export async function getReadOnlyMode(): Promise<boolean> {
+  const invoke = await getInvoke();
+
+  if (!invoke) {
+    throw new TauriError("Not in Tauri environment", "NOT_AVAILABLE");
+  }
+
+  try {
+    return await invoke<boolean>("get_read_only_mode");
+  } catch (error) {
+    throw wrapError(error, "getReadOnlyMode");
+  }
+}
+
+/**
+ * Protect a conversation from bulk archiving and deletion, or remove the
+ * protection.
+ *
+ * @param conversationId - ID of the conversation
+ * @param isProtected - Whether the conversation should be protected
+ * @returns The new protection status
+ * @throws TauriError if the conversation doesn't exist or the operation fails
+ */
+export async function setProtected(conversationId: string, isProtected: boolean): Promise<boolean> {
+  const invoke = await getInvoke();
+
+  if (!invoke) {
+    throw new TauriError("Not in Tauri environment", "NOT_AVAILABLE");
+  }
+
+  try {
+    return await invoke<boolean>("set_protected", { conversationId, protected: isProtected });
+  } catch (error) {
+    throw wrapError(error, "setProtected");
   }
 }
 
@@ -304,11 +2822,107 @@ export async function getAllTags(): Promise<TagInfo[]> {
 export const tauriService = {
   isTauriAvailable,
   getConversations,
+  getConversationSummariesWindow,
+  getAdjacentConversation,
   getConversation,
+  loadFull,
+  hasChangedSince,
+  getConversationWithHighlights,
+  replayConversation,
+  stopReplay,
+  getSelection,
+  setSelection,
+  getConversationMessages,
+  getMessage,
+  getAttachment,
+  getBlockCopyText,
+  exportConversationAs,
+  exportConversationPages,
+  renderPreviewImage,
+  exportConversations,
+  listExportTemplates,
+  exportWithTemplate,
+  exportStaticSite,
+  extractArtifacts,
+  shareToSlack,
+  createShareBundle,
+  listShareBundles,
+  revokeShareBundle,
+  linkIssue,
+  getIssueLinks,
+  getConversationsForIssue,
   getProjects,
+  compareProjects,
+  getUsageAnomalies,
+  exportUsageCsv,
+  getLanguageStats,
+  getModelStats,
+  generateDemoData,
+  runBenchmark,
+  getAppMetrics,
+  getHealth,
+  getSchemaObservations,
+  getRecentLogs,
+  rescanConversations,
+  reimportConversations,
+  importPath,
+  regeneratePreviews,
   searchConversations,
+  searchWithFacets,
+  searchQuestions,
+  maintainSearchIndex,
+  beginSearch,
+  updateQuery,
+  quickOpen,
   toggleBookmark,
   setTags,
+  undoLastOperation,
+  redo,
+  getAuditLog,
+  listWebhooks,
+  saveWebhook,
+  deleteWebhook,
+  getWebhookDeliveries,
+  listPrompts,
+  savePrompt,
+  savePromptFromMessage,
+  deletePrompt,
+  copyPrompt,
+  exportPrompts,
+  listWorkspaces,
+  saveWorkspace,
+  loadWorkspace,
+  deleteWorkspace,
   getAllTags,
-  listenToConversationsUpdated,
+  getMetadata,
+  setMetadata,
+  setReminder,
+  clearReminder,
+  getDueReminders,
+  setReadPosition,
+  getConversationChain,
+  attachChildConversation,
+  getChildConversations,
+  suggestTags,
+  listActions,
+  runAction,
+  getFilterCounts,
+  getTopTerms,
+  getSynonyms,
+  setSynonyms,
+  bulkArchive,
+  bulkDelete,
+  getSettings,
+  updateSettings,
+  getReadOnlyMode,
+  setProtected,
+  listenToConversationAdded,
+  listenToConversationUpdated,
+  listenToTagsChanged,
+  listenToWatcherStatus,
+  listenToUsageAnomalies,
+  listenToIndexProgress,
+  listenToImportResult,
+  listenToOpenConversation,
+  listenToReminderDue,
 };
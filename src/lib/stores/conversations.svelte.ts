@@ -316,7 +316,11 @@ export async function toggleBookmark(conversationId: string): Promise<boolean> {
   // Try to use Tauri IPC if available (Tauri v2 uses __TAURI_INTERNALS__)
   if (typeof window !== "undefined" && ("__TAURI_INTERNALS__" in window || "__TAURI__" in window)) {
     const { invoke } = await import("@tauri-apps/api/core");
-    const newStatus = await invoke<boolean>("toggle_bookmark", { conversationId });
+    const { getCurrentWindow } = await import("@tauri-apps/api/window");
+    const newStatus = await invoke<boolean>("toggle_bookmark", {
+      conversationId,
+      windowLabel: getCurrentWindow().label,
+    });
 
     // Update the conversation in the list
     conversations = conversations.map((c) =>
@@ -352,6 +356,21 @@ export async function toggleBookmark(conversationId: string): Promise<boolean> {
   }
 }
 
+/**
+ * Apply a conversation's new tags from a tags-changed event.
+ * Updates the cache and selected conversation without refetching.
+ */
+export function applyTags(conversationId: string, tags: string[]): void {
+  const cached = conversationCache.get(conversationId);
+  if (cached) {
+    conversationCache.set(conversationId, { ...cached, tags });
+  }
+
+  if (selectedConversation && selectedConversation.id === conversationId) {
+    selectedConversation = { ...selectedConversation, tags };
+  }
+}
+
 // Export reactive getters
 export const conversationsStore = {
   get conversations() {
@@ -392,4 +411,5 @@ export const conversationsStore = {
   setLoading,
   clearCache,
   toggleBookmark,
+  applyTags,
 };
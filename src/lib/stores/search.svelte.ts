@@ -2,11 +2,13 @@
  * Search store using Svelte 5 Runes.
  *
  * Manages search state with debounced search trigger
- * and integration with Tauri IPC for full-text search.
+ * and integration with Tauri IPC for full-text search. Searches run in a
+ * backend search session, so a newer query cancels the one still running
+ * and superseded results are dropped.
  */
 
 import type { SearchResult, ConversationFilters } from "$lib/types";
-import { searchConversations, isTauriAvailable } from "$lib/services";
+import { beginSearch, updateQuery, isTauriAvailable, NotFoundError } from "$lib/services";
 
 // Reactive state using Svelte 5 runes
 let query = $state("");
@@ -23,6 +25,34 @@ let debounceTimeout: ReturnType<typeof setTimeout> | null = null;
 // Default debounce delay in milliseconds
 const DEBOUNCE_MS = 300;
 
+// Backend search session, begun on the first search
+let sessionId: Promise<string> | null = null;
+
+/**
+ * Run a query in the search session, beginning a new session if there's
+ * none or the backend dropped it.
+ *
+ * @returns Search results, or null if a newer query superseded this one
+ */
+async function runQuery(
+  searchQuery: string,
+  filters?: ConversationFilters
+): Promise<SearchResult[] | null> {
+  sessionId ??= beginSearch();
+  const current = sessionId;
+  try {
+    return await updateQuery(await current, searchQuery, filters);
+  } catch (err) {
+    if (!(err instanceof NotFoundError)) {
+      throw err;
+    }
+    if (sessionId === current) {
+      sessionId = beginSearch();
+    }
+    return updateQuery(await sessionId, searchQuery, filters);
+  }
+}
+
 /**
  * Execute search with current query and filters.
  */
@@ -41,13 +71,19 @@ async function executeSearch(searchQuery: string): Promise<void> {
 
   isSearching = true;
   error = null;
+  let superseded = false;
 
   try {
     if (isTauriAvailable()) {
-      const searchResults = await searchConversations(
+      const searchResults = await runQuery(
         searchQuery,
         Object.keys(activeFilters).length > 0 ? activeFilters : undefined
       );
+      // A newer query superseded this one and will set the results
+      if (searchResults === null) {
+        superseded = true;
+        return;
+      }
       results = searchResults;
     } else {
       // Development mode: use mock search
@@ -59,7 +95,9 @@ async function executeSearch(searchQuery: string): Promise<void> {
     error = err instanceof Error ? err.message : "Search failed";
     results = [];
   } finally {
-    isSearching = false;
+    if (!superseded) {
+      isSearching = false;
+    }
   }
 }
 
@@ -2,17 +2,52 @@
  * Tags store using Svelte 5 Runes.
  *
  * Manages the list of all available tags across conversations
- * for autocomplete and filtering functionality.
+ * for autocomplete and filtering functionality, and the conversation
+ * counts under the active filters shown next to filter controls.
  */
 
-import type { TagInfo } from "$lib/types";
-import { getAllTags } from "$lib/services/tauri";
+import type { ConversationFilters, FilterCounts, TagInfo } from "$lib/types";
+import { getAllTags, getFilterCounts, isTauriAvailable } from "$lib/services/tauri";
 
 // Reactive state for all tags
 let allTags = $state<TagInfo[]>([]);
 let isLoading = $state(false);
 let lastLoadError = $state<string | null>(null);
 
+// Counts under the active filters, and the filters they were counted for
+let filterCounts = $state<FilterCounts | null>(null);
+let countedFilters: ConversationFilters = {};
+
+/**
+ * All tags, with counts of the conversations matching the active filters
+ * once those are loaded.
+ */
+function tagsWithFilterCounts(): TagInfo[] {
+  if (!filterCounts) return allTags;
+  const counts = new Map(filterCounts.tags.map((t) => [t.value, t.count]));
+  return allTags.map((t) => ({ tag: t.tag, count: counts.get(t.tag) ?? 0 }));
+}
+
+/**
+ * Load the counts for the given filters.
+ *
+ * @param filters - The active filters
+ */
+async function loadCounts(filters: ConversationFilters = countedFilters): Promise<void> {
+  countedFilters = filters;
+  if (!isTauriAvailable()) return;
+
+  try {
+    const counts = await getFilterCounts(filters);
+    // Drop counts for filters that changed while loading
+    if (countedFilters === filters) {
+      filterCounts = counts;
+    }
+  } catch (error) {
+    console.error("[tags store] Failed to load filter counts:", error);
+  }
+}
+
 /**
  * Load all tags from the backend.
  */
@@ -37,7 +72,7 @@ async function load(): Promise<void> {
  * Refresh tags (alias for load).
  */
 async function refresh(): Promise<void> {
-  return load();
+  await Promise.all([load(), loadCounts()]);
 }
 
 /**
@@ -45,6 +80,7 @@ async function refresh(): Promise<void> {
  */
 function clear(): void {
   allTags = [];
+  filterCounts = null;
   lastLoadError = null;
 }
 
@@ -59,8 +95,15 @@ export const tagsStore = {
   get lastLoadError() {
     return lastLoadError;
   },
+  get filterCounts() {
+    return filterCounts;
+  },
+  get tagsWithFilterCounts() {
+    return tagsWithFilterCounts();
+  },
   // Actions
   load,
+  loadCounts,
   refresh,
   clear,
 };
@@ -13,23 +13,88 @@ export interface TokenCount {
  * Messages can contain multiple blocks of different types.
  */
 export interface ContentBlock {
-  type: "text" | "code" | "tool_use" | "tool_result";
+  type: "text" | "code" | "tool_use" | "tool_result" | "image" | "terminal" | "file_view";
+  /** Text of the block; for image blocks, the attachment ID */
   content: string;
   /** Programming language for code blocks */
   language?: string;
   /** Tool name for tool_use/tool_result blocks */
   toolName?: string;
+  /** Styled runs of tool output that had ANSI colors (when the ANSI mode is "styled") */
+  spans?: StyledSpan[];
+  /** Command and output, for terminal blocks */
+  terminal?: TerminalTranscript;
+  /** File and first line number, for file view blocks */
+  fileView?: FileView;
+}
+
+/**
+ * Where the lines of a file view block (a file read by the Read tool) come from.
+ */
+export interface FileView {
+  path: string;
+  /** Number of the first line, from 1 */
+  startLine: number;
+}
+
+/**
+ * A shell command run through the Bash tool, with its output.
+ */
+export interface TerminalTranscript {
+  toolUseId?: string;
+  command: string;
+  /** What the command does, as given with the tool call */
+  description?: string;
+  stdout: string;
+  stderr: string;
+  /** Missing until the result is known */
+  exitCode?: number;
+  interrupted: boolean;
+}
+
+/**
+ * A run of terminal output sharing one style. Colors are standard color
+ * names (`red`, `bright_blue`, ...) or `#rrggbb`.
+ */
+export interface StyledSpan {
+  text: string;
+  fg?: string;
+  bg?: string;
+  bold?: boolean;
+  dim?: boolean;
+  italic?: boolean;
+  underline?: boolean;
+}
+
+/**
+ * An image extracted from a tool result, fetched by the ID in its image block.
+ */
+export interface Attachment {
+  id: string;
+  /** MIME type, such as `image/png` */
+  mediaType: string;
+  /** Base64-encoded bytes */
+  data: string;
+  /** Decoded size in bytes */
+  size: number;
 }
 
 /**
  * A single message in a conversation.
  */
 export interface Message {
+  /** Message UUID, or `msg_{ordinal}` when the file has none */
   id: string;
+  /** Zero-based position in the conversation (for deep links and scrolling) */
+  ordinal?: number;
   role: "user" | "assistant" | "system";
   content: ContentBlock[];
   timestamp: string; // ISO 8601 format
   tokenCount?: TokenCount;
+  /** Repeated system reminders removed from the content */
+  collapsedReminders?: number;
+  /** Not part of the dialogue: command output, hook output, or progress */
+  isMeta?: boolean;
 }
 
 /**
@@ -37,7 +102,7 @@ export interface Message {
  * Used when viewing conversation details.
  */
 export interface Conversation {
-  /** Unique ID derived from file path + session */
+  /** Stable ID derived from the session ID */
   id: string;
   /** Original project directory path */
   projectPath: string;
@@ -55,8 +120,74 @@ export interface Conversation {
   bookmarked?: boolean;
   /** User-defined tags (MVP extension point) */
   tags?: string[];
+  /** How much of the session file made it into messages */
+  completeness: Completeness;
+  /** Hash of the messages, for hasChangedSince */
+  contentHash?: string;
+  /** Where reading left off, to resume there (see setReadPosition) */
+  readPosition?: ReadPosition;
+}
+
+/**
+ * The message a conversation was last read up to.
+ */
+export interface ReadPosition {
+  /** ID of the last-read message */
+  messageId: string;
+  /** When it was recorded (RFC 3339) */
+  updatedAt: string;
+}
+
+/**
+ * Lines of a conversation's session file that were parsed or skipped; a
+ * transcript with skipped lines is partial.
+ */
+export interface Completeness {
+  /** Lines parsed into messages */
+  parsed: number;
+  /** Lines that couldn't be read or parsed */
+  skipped: number;
+  /** The session file is large, so tool results are shortened until loaded in full */
+  partialStorage: boolean;
+}
+
+/**
+ * A search match within a conversation's content blocks. Offsets are
+ * UTF-16 code units, usable with `String.prototype.slice`.
+ */
+export interface ConversationMatch {
+  /** Ordinal of the message containing the match */
+  messageOrdinal: number;
+  /** Index of the content block within the message */
+  blockIndex: number;
+  start: number;
+  /** Exclusive */
+  end: number;
 }
 
+/**
+ * A conversation opened from search results, with the query's matches.
+ */
+export interface ConversationWithHighlights extends Conversation {
+  /** All matches in reading order, for next/previous navigation */
+  matches: ConversationMatch[];
+}
+
+/**
+ * An event of a conversation replay, sent on the replay's channel.
+ */
+export type ReplayEvent =
+  | {
+      event: "started";
+      replayId: string;
+      /** Messages the replay will send */
+      messageCount: number;
+      /** Sum of the (scaled and shortened) pauses */
+      durationMillis: number;
+    }
+  | { event: "message"; message: Message }
+  | { event: "finished"; stopped: boolean };
+
 /**
  * Lightweight conversation summary for list view.
  * Does not include full message content for performance.
@@ -66,14 +197,23 @@ export interface ConversationSummary {
   projectName: string;
   startTime: string; // ISO 8601 format
   lastTime: string; // ISO 8601 format
-  /** First user message, truncated to 100 characters */
+  /** Single-line preview for the list */
   preview: string;
+  /** Longer, multi-line preview for tooltips */
+  previewMedium: string;
   /** Total number of messages */
   messageCount: number;
   /** Whether this conversation is bookmarked */
   bookmarked: boolean;
+  /** Whether the user's last messages say the problem was solved */
+  resolution: Resolution;
 }
 
+/**
+ * How a conversation ended, judged from the user's last messages.
+ */
+export type Resolution = "resolved" | "unresolved" | "unknown";
+
 /**
  * Filter options for querying conversations.
  */
@@ -88,8 +228,366 @@ export interface ConversationFilters {
   bookmarked?: boolean;
   /** Filter by tags (must have ALL specified tags) */
   tags?: string[];
+  /** Filter by archive status; lists hide archived conversations unless set */
+  archived?: boolean;
+  /** Filter by protection against archiving and deletion */
+  protected?: boolean;
+  /** Filter by how the conversation ended */
+  resolution?: Resolution;
+  /** Filter by custom metadata fields (must have ALL, values compared case-insensitively) */
+  metadata?: Record<string, string>;
+}
+
+/**
+ * Order of conversation lists.
+ */
+export type ConversationSort = "newest" | "oldest";
+
+/**
+ * Direction to move through a conversation list.
+ */
+export type ListDirection = "next" | "previous";
+
+/**
+ * A range of a conversation list, for virtual scrolling.
+ */
+export interface ConversationWindow {
+  /** Index of the first conversation in the list */
+  start: number;
+  /** The conversations from start, fewer than asked for at the end of the list */
+  conversations: ConversationSummary[];
+  /** Number of conversations in the whole list */
+  total: number;
+}
+
+/**
+ * An undoable annotation of a conversation.
+ */
+export type AnnotationState =
+  | { kind: "tags"; value: string[] }
+  | { kind: "bookmarked"; value: boolean };
+
+/**
+ * A user-data change reapplied by undo or redo.
+ */
+export interface AppliedOperation {
+  conversationId: string;
+  /** The annotation as it is now */
+  state: AnnotationState;
+}
+
+/**
+ * Kind of user-data change in the audit log.
+ */
+export type AuditAction =
+  | "tagAdded"
+  | "tagRemoved"
+  | "bookmarked"
+  | "unbookmarked"
+  | "archived"
+  | "deleted"
+  | "protected"
+  | "unprotected"
+  | "exported"
+  | "metadataSet"
+  | "metadataRemoved";
+
+/**
+ * A recorded user-data change.
+ */
+export interface AuditEntry {
+  id: number;
+  /** When the change was made (RFC 3339) */
+  createdAt: string;
+  /** OS user who made it */
+  actor: string;
+  action: AuditAction;
+  /** Affected conversation, if the change was about one */
+  conversationId: string | null;
+  /** What changed, e.g. the tag or the export format */
+  details: string;
+}
+
+/**
+ * Ingest event a webhook can be notified of.
+ */
+export type WebhookEvent = "added" | "updated";
+
+/**
+ * A URL that is sent a JSON payload when conversations matching its
+ * filters are ingested.
+ */
+export interface Webhook {
+  id: number;
+  url: string;
+  /** Which conversations to send; empty filters match all */
+  filters: ConversationFilters;
+  events: WebhookEvent[];
+  /** Disabled webhooks keep their settings and log but aren't sent */
+  enabled: boolean;
+  /** When the webhook was created (RFC 3339) */
+  createdAt: string;
+}
+
+/**
+ * A webhook to create, or the new settings of an existing one.
+ */
+export interface WebhookInput {
+  /** Webhook to update; a new one is created without it */
+  id?: number;
+  url: string;
+  filters?: ConversationFilters;
+  events: WebhookEvent[];
+  /** Defaults to true */
+  enabled?: boolean;
+}
+
+/**
+ * State of a webhook delivery.
+ */
+export type DeliveryStatus = "pending" | "delivered" | "failed";
+
+/**
+ * A payload sent, or to be sent, to a webhook.
+ */
+export interface WebhookDelivery {
+  id: number;
+  webhookId: number;
+  conversationId: string;
+  event: WebhookEvent;
+  status: DeliveryStatus;
+  /** Attempts made so far */
+  attempts: number;
+  /** HTTP status of the last response, if there was one */
+  responseStatus: number | null;
+  /** Why the last attempt failed */
+  lastError: string | null;
+  /** When the delivery was queued (RFC 3339) */
+  createdAt: string;
+  /** When it was accepted, if it was */
+  deliveredAt: string | null;
+}
+
+/**
+ * A reusable prompt, typically saved from a message in history.
+ */
+export interface Prompt {
+  id: number;
+  title: string;
+  body: string;
+  /** Names of the placeholders in the body, such as `file` for `[file]` or `{{file}}` */
+  variables: string[];
+  /** Conversation the prompt was saved from, if any */
+  sourceConversationId: string | null;
+  /** Message the prompt was saved from, if any */
+  sourceMessageId: string | null;
+  /** When the prompt was saved (RFC 3339) */
+  createdAt: string;
+  /** When it was last changed (RFC 3339) */
+  updatedAt: string;
+}
+
+/**
+ * A prompt to create, or to update when `id` is set.
+ */
+export interface PromptInput {
+  id?: number;
+  title: string;
+  body: string;
+  sourceConversationId?: string;
+  sourceMessageId?: string;
+}
+
+/** Format of an exported prompt library. */
+export type PromptExportFormat = "markdown" | "json";
+
+/**
+ * A named context to switch to: the conversation list's filters and order, pinned
+ * conversations, and the tabs that were open.
+ */
+export interface Workspace {
+  /** Unique name, compared case-insensitively */
+  name: string;
+  filters: ConversationFilters;
+  sort: ConversationSort;
+  /** IDs of the pinned conversations, in the order they're shown */
+  pinned: string[];
+  /** IDs of the conversations open in tabs, in tab order */
+  openTabs: string[];
+  /** Conversation of the selected tab, if any */
+  activeTab: string | null;
+  /** When the workspace was first saved (RFC 3339) */
+  createdAt: string;
+  /** When it was last saved (RFC 3339) */
+  updatedAt: string;
+  /** When it was last loaded (RFC 3339), if ever */
+  lastOpenedAt: string | null;
+}
+
+/**
+ * A workspace to save, replacing any with the same name.
+ */
+export interface WorkspaceInput {
+  name: string;
+  filters?: ConversationFilters;
+  sort?: ConversationSort;
+  pinned?: string[];
+  openTabs?: string[];
+  activeTab?: string | null;
+}
+
+/**
+ * A follow-up reminder on a conversation; each conversation has at most one. The
+ * reminder-due event carries the reminders that just came due.
+ */
+export interface Reminder {
+  conversationId: string;
+  /** Project of the conversation, to say what's due */
+  projectName: string;
+  /** Preview of the conversation's first question */
+  preview: string;
+  /** When the reminder is due (RFC 3339) */
+  dueAt: string;
+  /** What to follow up on, if the user said */
+  note: string | null;
+  /** When the reminder was set (RFC 3339) */
+  createdAt: string;
+  /** When reminder-due was emitted for it (RFC 3339), if it has been */
+  notifiedAt: string | null;
+}
+
+/**
+ * Outcome of a bulk archive or delete.
+ */
+export interface BulkOperationResult {
+  /** Conversations affected, or that would be in a dry run */
+  affected: number;
+  /** Matching conversations left alone because they're protected */
+  protected: number;
+  /** Whether nothing was changed */
+  dryRun: boolean;
+  /** For a bulk delete dry run, the token the real run must pass */
+  confirmToken?: string;
+}
+
+/**
+ * Server-side filtering options for a conversation's messages.
+ */
+export interface MessageFilterOptions {
+  /** Only include messages with these roles (all roles when omitted) */
+  roles?: Array<"user" | "assistant" | "system">;
+  /** Keep only text and code blocks */
+  textOnly?: boolean;
+  /** Drop tool result blocks */
+  hideToolResults?: boolean;
+  /** Clean mode: drop tool activity and merge consecutive assistant turns */
+  clean?: boolean;
+  /** Include meta messages (command output, hook output, progress) */
+  showMeta?: boolean;
+}
+
+/**
+ * Backend export format.
+ * "transcript" is clean-mode Markdown without tool activity; "json" follows
+ * the versioned export schema (see `schemaVersion` in the document).
+ */
+export type ExportFormat = "markdown" | "transcript" | "html" | "json";
+
+/**
+ * Slice of a conversation to export, by message ID; both ends are included
+ * and a missing end extends to the start or end of the conversation.
+ */
+export interface MessageRange {
+  /** First message to export */
+  fromMessageId?: string;
+  /** Last message to export */
+  toMessageId?: string;
+}
+
+/**
+ * Result of a static site export.
+ */
+export interface StaticSiteExport {
+  /** Path of the site's index page */
+  indexPath: string;
+  /** Number of conversation pages written */
+  conversationCount: number;
+}
+
+/** Result of extracting the files a conversation wrote. */
+export interface ArtifactExport {
+  /** Directory the files were written into, or path of the ZIP archive */
+  path: string;
+  /** Relative paths of the files extracted */
+  files: string[];
+  /** Relative paths of files that were edited but couldn't be rebuilt */
+  skipped: string[];
+}
+
+/**
+ * A user-editable export template, stored in the app data directory as
+ * `<name>.<extension>.hbs`.
+ */
+export interface ExportTemplate {
+  /** Name the template is chosen by */
+  name: string;
+  /** Extension of the files it renders, e.g. "md" */
+  extension: string;
+  /** Path of the template file, for editing */
+  path: string;
+}
+
+/**
+ * A conversation shared to Slack.
+ */
+export interface SlackShare {
+  /** ID of the channel posted to */
+  channelId: string;
+  /** Timestamp identifying the summary message in the channel */
+  messageTs: string;
+  /** ID of the uploaded transcript file */
+  fileId: string;
 }
 
+/**
+ * An encrypted share bundle of a conversation: one HTML file that opens
+ * with its passphrase until it expires.
+ */
+export interface ShareBundle {
+  id: string;
+  conversationId: string;
+  /** The bundle's HTML file, in the Shares folder of app data */
+  path: string;
+  /** file:// link to the bundle */
+  url: string;
+  createdAt: string;
+  /** When the page stops opening and the file is deleted */
+  expiresAt: string;
+  /** Passphrase opening the bundle; only returned when it's created */
+  passphrase?: string;
+}
+
+/** How a conversation came to be linked to an issue. */
+export type IssueSource = "linked" | "detected";
+
+/** A Jira or Linear issue a conversation is about. */
+export interface IssueLink {
+  /** Issue key, e.g. ABC-123 */
+  key: string;
+  /** Link to the issue, if it was linked by URL */
+  url: string | null;
+  source: IssueSource;
+  /** When the link was recorded (RFC 3339) */
+  createdAt: string;
+}
+
+/**
+ * What to copy from a content block: its text (code unfenced), a tool
+ * call's input as JSON, a file edit as a unified diff, or the block as
+ * exported Markdown.
+ */
+export type CopyMode = "text" | "json" | "patch" | "markdown";
+
 /**
  * Tag information with usage count.
  */
@@ -100,6 +598,97 @@ export interface TagInfo {
   count: number;
 }
 
+/**
+ * What a tag suggestion is based on: the languages of the code blocks, frameworks named
+ * in the dialogue or implied by its files, extensions of the files it touched, or tags
+ * of conversations of the same project or languages.
+ */
+export type TagSuggestionSource = "language" | "framework" | "fileExtension" | "similarConversations";
+
+/**
+ * A tag proposed for a conversation, accepted with setTags.
+ */
+export interface TagSuggestion {
+  tag: string;
+  /** How sure the suggestion is, from 0 to 1 */
+  confidence: number;
+  /** What it's based on, strongest first */
+  sources: TagSuggestionSource[];
+}
+
+/**
+ * Group of a command palette action.
+ */
+export type ActionCategory = "conversation" | "export" | "share" | "library" | "maintenance";
+
+/**
+ * What an action's argument holds, so the palette knows how to ask for it: `conversation`
+ * defaults to the open conversation, `conversations` to the selected ones, `filters` to
+ * the list's current filters, and `window` to the window's label.
+ */
+export type ActionArgKind =
+  | "conversation"
+  | "conversations"
+  | "message"
+  | "window"
+  | "text"
+  | "tags"
+  | "path"
+  | "boolean"
+  | "integer"
+  | "dateTime"
+  | "filters"
+  | "choice"
+  | "json";
+
+/**
+ * An argument of a command palette action.
+ */
+export interface ActionArg {
+  /** Name as passed to runAction */
+  name: string;
+  kind: ActionArgKind;
+  required: boolean;
+  description: string;
+  /** Values of a `choice` argument */
+  options?: string[];
+}
+
+/**
+ * A backend capability the command palette can run with runAction.
+ */
+export interface ActionInfo {
+  /** Action ID: the name of the command it runs */
+  id: string;
+  title: string;
+  category: ActionCategory;
+  description: string;
+  args: ActionArg[];
+  /** Whether it archives or deletes conversations, so confirm first */
+  destructive: boolean;
+}
+
+/**
+ * A custom metadata field of a conversation, such as `customer=acme`.
+ */
+export interface MetadataField {
+  /** Lowercase key, such as `customer` */
+  key: string;
+  value: string;
+  /** When the value was last set (RFC 3339) */
+  updatedAt: string;
+}
+
+/**
+ * A search term and the terms queries for it are expanded to.
+ */
+export interface SynonymEntry {
+  /** Term (normalized: lowercase, single spaces) */
+  term: string;
+  /** Synonyms searched for as well, sorted */
+  synonyms: string[];
+}
+
 /**
  * A search result with matching conversation info.
  */
@@ -112,6 +701,138 @@ export interface SearchResult {
   matchCount: number;
   /** Search relevance rank (lower is better) */
   rank: number;
+  /** Found by the typo-tolerant fallback rather than an exact term match */
+  approximate: boolean;
+  /** What the rank was computed from, for debugging; null for approximate results */
+  components?: RankComponents | null;
+  /** The best matching user messages, best first (at most 3) */
+  hits?: MessageHit[];
+}
+
+/**
+ * A user message matching a search, nested under its conversation's result.
+ */
+export interface MessageHit {
+  /** ID of the message */
+  messageId: string;
+  /** Position of the message in its conversation */
+  ordinal: number;
+  /** The message text around the match, with <mark> highlights */
+  snippet: string;
+}
+
+/**
+ * Components of a full-text search rank.
+ */
+export interface RankComponents {
+  /** FTS5 BM25 score (negative, lower is better) */
+  bm25: number;
+  /** Days since the conversation's last activity */
+  ageDays: number;
+  /** Recency factor: 1 for now, halving every half-life, 0 if disabled */
+  recency: number;
+  /** BM25 boosted by recency, which results are ordered by (lower is better) */
+  score: number;
+}
+
+/**
+ * A user message matching a question search.
+ */
+export interface QuestionMatch {
+  /** ID of the conversation the message is in */
+  conversationId: string;
+  /** ID of the message */
+  messageId: string;
+  /** Position of the message in its conversation */
+  ordinal: number;
+  /** Project the conversation belongs to */
+  projectName: string;
+  /** Last activity in the conversation (ISO 8601) */
+  lastTime: string;
+  /** The message text around the match, with <mark> highlights */
+  snippet: string;
+  /** FTS5 BM25 score (lower is better) */
+  rank: number;
+}
+
+/**
+ * Number of search results with a facet value.
+ */
+export interface FacetCount {
+  value: string;
+  count: number;
+}
+
+/**
+ * Search results broken down for filter chips.
+ */
+export interface SearchFacets {
+  /** Results per project, most first */
+  projects: FacetCount[];
+  /** Results per tag, most first */
+  tags: FacetCount[];
+  /** Results per YYYY-MM month of last activity (filter timezone), newest first */
+  months: FacetCount[];
+}
+
+/**
+ * Conversations per filter value under the active filters.
+ */
+export interface FilterCounts {
+  /** Conversations matching the filters */
+  total: number;
+  /** Matching conversations per project, most first */
+  projects: FacetCount[];
+  /** Matching conversations per tag, most first */
+  tags: FacetCount[];
+  /** Matching conversations that are bookmarked */
+  bookmarked: number;
+  /** Matching conversations that aren't bookmarked */
+  notBookmarked: number;
+}
+
+/**
+ * A term characteristic of a set of conversations, weighted for a word cloud.
+ */
+export interface TopTerm {
+  /** The term as indexed; a stem under the Porter tokenizer */
+  term: string;
+  /** tf-idf style weight; higher is more characteristic of the set */
+  weight: number;
+  /** Occurrences in the set's conversations */
+  occurrences: number;
+  /** Conversations of the set containing the term */
+  conversations: number;
+}
+
+/**
+ * Search results with their facet counts.
+ */
+export interface SearchResponse {
+  results: SearchResult[];
+  facets: SearchFacets;
+}
+
+/**
+ * What a quick-open result refers to.
+ */
+export type QuickOpenKind = "conversation" | "project" | "tag";
+
+/**
+ * A fuzzy-matched item for the quick-switcher.
+ */
+export interface QuickOpenResult {
+  kind: QuickOpenKind;
+  /** Conversation ID, project name, or tag */
+  value: string;
+  /** Matched text: the conversation preview, project name, or tag */
+  label: string;
+  /** Project of a conversation, or the conversation count of a project or tag */
+  detail: string;
+  /** Match score (higher is better) */
+  score: number;
+  /** Matched positions in `label` (UTF-16 code units), empty for typo matches */
+  positions: number[];
 }
 
 /**
@@ -129,14 +850,449 @@ export interface ProjectInfo {
 }
 
 /**
- * Payload for the conversations-updated Tauri event.
- * Emitted by the file watcher when conversations change.
+ * Call and error counts for one tool.
+ */
+export interface ToolStats {
+  toolName: string;
+  calls: number;
+  errors: number;
+}
+
+/**
+ * Aggregate usage metrics for one project.
+ */
+export interface ProjectMetrics {
+  projectName: string;
+  conversations: number;
+  messages: number;
+  inputTokens: number;
+  outputTokens: number;
+  cacheCreationTokens: number;
+  cacheReadTokens: number;
+  /** Estimated cost in USD at list prices */
+  estimatedCostUsd: number;
+  /** Tokens from models without known pricing (not included in the cost) */
+  unpricedTokens: number;
+  toolCalls: number;
+  toolErrors: number;
+  /** Fraction of tool calls that returned an error */
+  errorRate: number;
+  /** Calls per tool, most used first */
+  toolMix: ToolStats[];
+}
+
+/**
+ * Side-by-side usage metrics for two projects.
+ */
+export interface ProjectComparison {
+  projectA: ProjectMetrics;
+  projectB: ProjectMetrics;
+}
+
+/**
+ * What made a usage anomaly stand out.
+ */
+export type AnomalyKind = "tokenSpike" | "errorBurst";
+
+/**
+ * A day or conversation whose usage is unusual compared to its peers.
+ * Also the payload (as an array) of the usage-anomaly Tauri event.
+ */
+export interface UsageAnomaly {
+  kind: AnomalyKind;
+  /** UTC day (YYYY-MM-DD); a conversation's last active day */
+  day: string;
+  /** Set for conversation anomalies; absent when the whole day is unusual */
+  conversationId?: string;
+  projectName?: string;
+  /** Observed tokens or tool errors */
+  value: number;
+  /** Median over the lookback window */
+  baseline: number;
+  /** Robust z-score; higher is more unusual */
+  score: number;
+}
+
+/**
+ * Options for usage anomaly detection.
+ */
+export interface AnomalyOptions {
+  /** Number of trailing days to analyze (default: 30) */
+  lookbackDays?: number;
+  /** Robust z-score above which a value is flagged (default: 3.5) */
+  threshold?: number;
+}
+
+/**
+ * Length of the periods statistics are grouped by.
+ */
+export type StatsPeriod = "day" | "week" | "month";
+
+/**
+ * Options for get_language_stats and get_model_stats.
+ */
+export interface UsageStatsOptions {
+  /** Only this project's conversations */
+  project?: string;
+  /** First UTC day counted (YYYY-MM-DD) */
+  startDate?: string;
+  /** Last UTC day counted (YYYY-MM-DD) */
+  endDate?: string;
+  /** Group the statistics by period (default: one total for the range) */
+  period?: StatsPeriod;
+}
+
+/**
+ * Code blocks Claude wrote in one language.
+ */
+export interface LanguageStats {
+  /** The period counted, when grouped by period */
+  period: string | null;
+  /** Normalized language name, e.g. typescript for ts fences */
+  language: string;
+  /** Fenced code blocks in assistant messages */
+  blocks: number;
+  /** Lines of those blocks */
+  lines: number;
+  /** Conversations they're in */
+  conversations: number;
+}
+
+/**
+ * How one model answered over a period.
+ */
+export interface ModelStats {
+  /** The period counted, when grouped by period */
+  period: string | null;
+  /** Model ID, or empty when the session files don't record one */
+  model: string;
+  /** Answers (API responses) from the model */
+  answers: number;
+  /** Fraction of the period's answers from the model, 0 to 1 */
+  share: number;
+  inputTokens: number;
+  outputTokens: number;
+  /** Output tokens per answer */
+  averageOutputTokens: number;
+  /** Mean time from the prompt or tool result to the answer, if any answer could be timed */
+  averageLatencyMillis: number | null;
+  /** Conversations the model answered in */
+  conversations: number;
+}
+
+/**
+ * Options for run_benchmark.
+ */
+export interface BenchmarkOptions {
+  /** Most session files parsed and ingested (default: 200) */
+  maxFiles?: number;
+  /** Times each search query is repeated (default: 5) */
+  searchIterations?: number;
+}
+
+/**
+ * Latency percentiles in milliseconds.
+ */
+export interface LatencyStats {
+  samples: number;
+  p50Ms: number;
+  p95Ms: number;
+  p99Ms: number;
+  maxMs: number;
+}
+
+/**
+ * Results of a benchmark run on the current dataset.
  */
-export interface ConversationsUpdatedEvent {
-  /** Number of new conversations added */
-  newCount: number;
-  /** Number of existing conversations updated */
-  updatedCount: number;
+export interface BenchmarkReport {
+  /** Session files parsed */
+  files: number;
+  /** Total size of the parsed files */
+  bytes: number;
+  /** Parse throughput in MB/s */
+  parseMbPerSec: number;
+  /** Conversations parsed and written to a scratch database */
+  conversations: number;
+  /** Ingest rate (parse, write, and index) in conversations/s */
+  ingestConversationsPerSec: number;
+  /** Distinct search queries run */
+  searchQueries: number;
+  searchLatency: LatencyStats;
+  /** Wall-clock duration of the whole run */
+  durationMs: number;
+}
+
+/**
+ * Results of checking and optimizing the full-text indexes.
+ */
+export interface SearchMaintenanceReport {
+  indexes: FtsIndexReport[];
+  /** Wall-clock duration of the whole run */
+  durationMs: number;
+}
+
+/**
+ * Maintenance of one FTS5 table.
+ */
+export interface FtsIndexReport {
+  table: string;
+  /** What the integrity check found, if the index was damaged */
+  problem: string | null;
+  /** Whether the index was rebuilt from the stored text */
+  rebuilt: boolean;
+  durationMs: number;
+}
+
+/**
+ * Aggregated durations of one timer.
+ */
+export interface TimerMetrics {
+  /** Dotted metric name (e.g. command.get_conversations) */
+  name: string;
+  count: number;
+  totalMs: number;
+  meanMs: number;
+  /** Slowest recorded duration this session */
+  maxMs: number;
+  /** Median over the most recent samples */
+  p50Ms: number;
+  /** 95th percentile over the most recent samples */
+  p95Ms: number;
+}
+
+/**
+ * Snapshot of the local metrics registry. Never leaves the machine.
+ */
+export interface AppMetrics {
+  /** Seconds since launch */
+  uptimeSecs: number;
+  counters: Record<string, number>;
+  timers: TimerMetrics[];
+}
+
+/**
+ * Minimum severity for get_recent_logs.
+ */
+export type LogLevel = "error" | "warn" | "info" | "debug" | "trace";
+
+/**
+ * An entry of the application log file.
+ */
+export interface LogEntry {
+  /** When the entry was logged (RFC 3339) */
+  timestamp: string;
+  /** ERROR, WARN, INFO, DEBUG, or TRACE */
+  level: string;
+  /** Module that logged the entry */
+  target: string;
+  message: string;
+}
+
+/**
+ * Outcome of an ingest run (rescan or reimport).
+ */
+export interface IngestStats {
+  /** Session files parsed and written */
+  processedFiles: number;
+  /** Session files that failed and will be retried by the next scan */
+  failedFiles: number;
+  newConversations: number;
+  updatedConversations: number;
+  /** Sessions also found in another file, resolved by the session conflict policy */
+  conflicts: number;
+}
+
+/**
+ * Payload for the index-progress Tauri event.
+ */
+export interface IngestProgress extends IngestStats {
+  /** Session files needing ingest in this run */
+  totalFiles: number;
+}
+
+/**
+ * Payload for the import-result Tauri event, emitted after session files
+ * dropped on the window were copied into the imported directory and ingested.
+ */
+export interface DropImportResult extends IngestStats {
+  /** Session files copied into the imported directory */
+  copiedFiles: number;
+  /** Dropped paths that weren't imported: not session files, or failed to copy */
+  rejectedPaths: string[];
+}
+
+/**
+ * Payload for the conversation-added and conversation-updated Tauri events.
+ * Emitted after a scan or file watcher run finds new or changed sessions.
+ */
+export interface ConversationsChangedEvent {
+  /** IDs of the added or updated conversations */
+  conversationIds: string[];
   /** Whether this was triggered by file watcher (vs initial load) */
   fromWatcher: boolean;
 }
+
+/**
+ * Payload for the tags-changed Tauri event.
+ */
+export interface TagsChangedEvent {
+  conversationId: string;
+  /** The conversation's tags after the change, sorted */
+  tags: string[];
+}
+
+/**
+ * Payload of the open-conversation event.
+ */
+export interface OpenConversationEvent {
+  conversationId: string;
+}
+
+/**
+ * State of the backend file watcher.
+ */
+export type WatcherState = "running" | "stopped" | "failed";
+
+/**
+ * Payload for the watcher-status Tauri event.
+ */
+export interface WatcherStatusEvent {
+  state: WatcherState;
+  /** Why the watcher failed, if it did */
+  message: string | null;
+}
+
+/**
+ * Database figures of the health report.
+ */
+export interface DatabaseHealth {
+  /** Size of the database file, excluding the write-ahead log */
+  sizeBytes: number;
+  /** Session files scheduled or being ingested, including failed ones to retry */
+  pendingFiles: number;
+  conversations: number;
+  /** Rows of the full-text index */
+  indexedConversations: number;
+}
+
+/**
+ * Summary of the app's state for the diagnostics panel.
+ */
+export interface HealthReport {
+  /** State of the file watcher; null if it was never started (demo mode) */
+  watcher: WatcherState | null;
+  /** When the last full scan finished (RFC 3339) */
+  lastScanAt: string | null;
+  /** Session files that failed to parse since launch */
+  parseErrors: number;
+  database: DatabaseHealth;
+  /** Whether the full-text index and the conversations disagree */
+  indexMismatch: boolean;
+  /** Directories and session files the last scan couldn't read */
+  accessErrors: AccessError[];
+}
+
+/**
+ * A directory or session file the last scan couldn't read; its
+ * conversations are missing until a retry reads it.
+ */
+export interface AccessError {
+  path: string;
+  error: string;
+  /** Scans that failed to read it in a row */
+  attempts: number;
+  /** When it was first found unreadable (RFC 3339) */
+  firstFailedAt: string;
+  /** When it was last found unreadable (RFC 3339) */
+  lastFailedAt: string;
+  /** When it will be retried (RFC 3339) */
+  nextRetryAt: string;
+}
+
+/**
+ * Kind of unknown structure a schema observation is.
+ */
+export type SchemaObservationKind = "field" | "blockType" | "lineType";
+
+/**
+ * A structure seen in session files that the parsing profile doesn't know.
+ */
+export interface SchemaObservation {
+  kind: SchemaObservationKind;
+  /** Name of the field, block type, or line type */
+  name: string;
+  /** Occurrences across ingests; a file ingested again counts again */
+  count: number;
+  /** When it was first seen (RFC 3339) */
+  firstSeenAt: string;
+  /** When it was last seen (RFC 3339) */
+  lastSeenAt: string;
+  /** A session file it was last seen in */
+  exampleFile: string;
+}
+
+/** What conversation previews are built from. */
+export type PreviewSource = "firstUserMessage" | "substantivePair";
+
+/**
+ * Tokenizer of the full-text search index.
+ * "trigram" matches any substring of three or more characters, in any script;
+ * "porter" also matches English word stems.
+ */
+export type SearchTokenizer = "unicode61" | "trigram" | "porter";
+
+/** What happens to ANSI escape sequences (terminal colors) in tool results. */
+export type AnsiMode = "strip" | "styled";
+
+/** What happens when the same session is found in two files. */
+export type SessionConflictPolicy = "preferNewest" | "keepBoth";
+
+/**
+ * User-configurable application settings.
+ */
+export interface AppSettings {
+  /** Timezone for day-based date filters: "local", "UTC", or an IANA name */
+  timezone: string;
+  /** Emit a `usage-anomaly` event when today's usage looks unusual */
+  anomalyNotifications: boolean;
+  /** Custom database file or directory (absolute); applies after a restart */
+  databasePath: string | null;
+  /** What previews of newly ingested conversations are built from */
+  previewSource: PreviewSource;
+  /** Length of list previews in characters */
+  previewLength: number;
+  /** When a search finds few results, also look for near matches of misspelled words */
+  typoTolerantSearch: boolean;
+  /** How the full-text index splits text into terms; changing it rebuilds the index */
+  searchTokenizer: SearchTokenizer;
+  /** Days after which a conversation's recency boost in search halves; 0 for none */
+  recencyHalfLifeDays: number;
+  ansiMode: AnsiMode;
+  /** Slack bot token (`xoxb-...`) used by shareToSlack */
+  slackToken: string | null;
+  sessionConflictPolicy: SessionConflictPolicy;
+  /** Locale dates are written in by exports and summaries, e.g. "de-DE" */
+  locale: string;
+  /** Globs of session files left out of scans, matched against the full path */
+  ignorePatterns: string[];
+  /** Session files larger than this many megabytes are left out; null for no limit */
+  maxSessionFileMb: number | null;
+  /** Session files larger than this many megabytes are stored partially; null for none */
+  partialStorageMb: number | null;
+  /** Only read: deleting, archiving, and rebuilding history are refused */
+  readOnly: boolean;
+  /** Write a Markdown stub per conversation for Spotlight to index (macOS only) */
+  spotlightStubs: boolean;
+}
+
+/**
+ * Error returned by a failed Tauri command.
+ */
+export interface CommandErrorPayload {
+  /** Kind of error, e.g. "not_found", "validation", "io", "parse", "database", "network" */
+  code: string;
+  /** Human-readable description */
+  message: string;
+  /** Specifics such as the invalid field ({ field, reason }), or null */
+  details: Record<string, unknown> | null;
+}
@@ -200,6 +200,7 @@ async function login(email: string, password: string) {
       ),
     ],
     totalTokens: { input: 450, output: 620 },
+    completeness: { parsed: 4, skipped: 0 },
     bookmarked: true,
     tags: ["authentication", "jwt"],
   },
@@ -252,6 +253,7 @@ export function ThemeProvider({ children }) {
       ),
     ],
     totalTokens: { input: 120, output: 280 },
+    completeness: { parsed: 2, skipped: 0 },
     bookmarked: false,
     tags: ["ui", "theming"],
   },
@@ -290,6 +292,7 @@ export function ThemeProvider({ children }) {
       ),
     ],
     totalTokens: { input: 80, output: 150 },
+    completeness: { parsed: 2, skipped: 0 },
     bookmarked: false,
     tags: ["database", "debugging"],
   },
@@ -318,6 +321,7 @@ export function ThemeProvider({ children }) {
       ),
     ],
     totalTokens: { input: 60, output: 100 },
+    completeness: { parsed: 2, skipped: 0 },
     bookmarked: true,
     tags: ["mobile", "notifications"],
   },
@@ -342,6 +346,7 @@ export function ThemeProvider({ children }) {
       ),
     ],
     totalTokens: { input: 50, output: 80 },
+    completeness: { parsed: 2, skipped: 0 },
     bookmarked: false,
     tags: ["webpack", "optimization"],
   },